@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS command_usage (
+  record_id          TEXT PRIMARY KEY,
+  command_name       TEXT NOT NULL,
+  user_id            TEXT NOT NULL,
+  guild_id           TEXT,
+  latency_ms         INTEGER NOT NULL,
+  success            BOOLEAN NOT NULL,
+  occurred_at        TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON command_usage (guild_id, occurred_at);
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS term_suggestion (
+  record_id          TEXT PRIMARY KEY,
+  term_name          TEXT NOT NULL,
+  meaning            TEXT NOT NULL,
+  usage              TEXT,
+  links              TEXT[] DEFAULT ARRAY[]::TEXT[],
+  user_id            TEXT NOT NULL,
+  guild_id           TEXT NOT NULL,
+  status             TEXT DEFAULT 'pending' NOT NULL,
+  reviewed_by        TEXT,
+  created_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON term_suggestion (guild_id, status);
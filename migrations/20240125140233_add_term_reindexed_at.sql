@@ -0,0 +1 @@
+ALTER TABLE term ADD COLUMN IF NOT EXISTS reindexed_at TIMESTAMP WITH TIME ZONE;
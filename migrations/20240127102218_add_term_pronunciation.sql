@@ -0,0 +1,3 @@
+ALTER TABLE term ADD COLUMN IF NOT EXISTS pronunciation TEXT;
+ALTER TABLE term ADD COLUMN IF NOT EXISTS pronunciation_audio BYTEA;
+ALTER TABLE term ADD COLUMN IF NOT EXISTS pronunciation_audio_name TEXT;
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS quote_settings (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT UNIQUE NOT NULL,
+  card_background    TEXT NOT NULL
+);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS command_permissions (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  command_name       TEXT NOT NULL,
+  role_id            TEXT,
+  channel_id         TEXT,
+  created_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  CHECK ((role_id IS NULL) <> (channel_id IS NULL))
+);
+
+CREATE INDEX ON command_permissions (guild_id, command_name);
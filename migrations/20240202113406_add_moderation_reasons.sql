@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS moderation_reasons (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  label              TEXT NOT NULL,
+  reason             TEXT NOT NULL,
+  created_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  UNIQUE (guild_id, label)
+);
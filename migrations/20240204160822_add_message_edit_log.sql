@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS edit_log_channels (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  channel_id         TEXT UNIQUE NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS message_edits (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  channel_id         TEXT NOT NULL,
+  message_id         TEXT NOT NULL,
+  user_id            TEXT NOT NULL,
+  old_content        TEXT,
+  new_content        TEXT NOT NULL,
+  edited_at          TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON message_edits (edited_at);
+CREATE INDEX ON message_edits (message_id);
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS delete_log_settings (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT UNIQUE NOT NULL,
+  ignore_bots        BOOLEAN NOT NULL DEFAULT TRUE,
+  min_length         INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS delete_log_ignored_channels (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  channel_id         TEXT UNIQUE NOT NULL
+);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS notification_threads (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  user_id            TEXT NOT NULL,
+  thread_id          TEXT UNIQUE NOT NULL,
+  created_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON notification_threads (guild_id, user_id);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS session_limits (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT UNIQUE NOT NULL,
+  min_minutes        INTEGER,
+  max_minutes        INTEGER,
+  confirm_minutes    INTEGER NOT NULL
+);
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS anomaly_flags (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  user_id            TEXT NOT NULL,
+  kind               TEXT NOT NULL,
+  details            TEXT NOT NULL,
+  entry_ids          TEXT[] NOT NULL,
+  status             TEXT DEFAULT 'pending' NOT NULL,
+  reviewed_by        TEXT,
+  flagged_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON anomaly_flags (guild_id, status);
+CREATE INDEX ON anomaly_flags (guild_id, user_id, kind);
@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS prize_awards (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  user_id            TEXT NOT NULL,
+  steam_key          TEXT NOT NULL,
+  challenge          TEXT NOT NULL,
+  status             TEXT DEFAULT 'sent' NOT NULL,
+  dm_channel_id      TEXT,
+  dm_message_id      TEXT,
+  awarded_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  reminded_at        TIMESTAMP WITH TIME ZONE,
+  resolved_at        TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX ON prize_awards (guild_id);
+CREATE INDEX ON prize_awards (status);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS guild_emojis (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  name               TEXT NOT NULL,
+  emoji              TEXT NOT NULL,
+  UNIQUE (guild_id, name)
+);
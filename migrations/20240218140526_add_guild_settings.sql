@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS guild_settings (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL UNIQUE,
+  tracking_channel_id TEXT,
+  log_channel_id     TEXT,
+  joined_at          TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS guild_links (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  partner_guild_id   TEXT NOT NULL,
+  created_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  UNIQUE (guild_id, partner_guild_id)
+);
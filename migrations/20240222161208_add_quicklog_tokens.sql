@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS quicklog_tokens (
+  record_id          TEXT PRIMARY KEY,
+  token              TEXT NOT NULL UNIQUE,
+  user_id            TEXT NOT NULL,
+  guild_id           TEXT NOT NULL,
+  minutes            INTEGER NOT NULL,
+  created_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  last_used_at       TIMESTAMP WITH TIME ZONE,
+  UNIQUE (user_id, guild_id)
+);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS guild_webhooks (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  url                TEXT NOT NULL,
+  created_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  UNIQUE (guild_id, url)
+);
+
+ALTER TABLE guild_settings ADD COLUMN IF NOT EXISTS record_streak INTEGER DEFAULT 0 NOT NULL;
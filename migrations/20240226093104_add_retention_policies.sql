@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS retention_policies (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  category           TEXT NOT NULL,
+  retention_days     INTEGER NOT NULL,
+  enabled            BOOLEAN DEFAULT FALSE NOT NULL,
+  UNIQUE (guild_id, category)
+);
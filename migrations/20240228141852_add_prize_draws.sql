@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS prize_draws (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  user_id            TEXT NOT NULL,
+  challenge          TEXT NOT NULL,
+  challenge_start    TIMESTAMP WITH TIME ZONE NOT NULL,
+  minutes            BIGINT NOT NULL,
+  steam_key          TEXT NOT NULL,
+  state              TEXT DEFAULT 'key_reserved' NOT NULL,
+  award_id           TEXT,
+  created_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  updated_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON prize_draws (guild_id, state);
@@ -0,0 +1 @@
+ALTER TABLE session_limits ADD COLUMN IF NOT EXISTS daily_cap_minutes INTEGER;
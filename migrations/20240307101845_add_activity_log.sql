@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS activity_log (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  user_id            TEXT NOT NULL,
+  activity           TEXT NOT NULL,
+  note               TEXT,
+  occurred_at        TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON activity_log (guild_id, user_id, activity);
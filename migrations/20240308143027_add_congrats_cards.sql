@@ -0,0 +1 @@
+ALTER TABLE tracking_profile ADD COLUMN IF NOT EXISTS congrats_cards BOOLEAN DEFAULT FALSE NOT NULL;
@@ -0,0 +1 @@
+ALTER TABLE tracking_profile ADD COLUMN IF NOT EXISTS add_reaction TEXT;
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS quiet_hours (
+  user_id            TEXT PRIMARY KEY,
+  start_minute       SMALLINT NOT NULL,
+  end_minute         SMALLINT NOT NULL,
+  utc_offset         SMALLINT DEFAULT 0 NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS queued_notifications (
+  record_id          TEXT PRIMARY KEY,
+  user_id            TEXT NOT NULL,
+  title              TEXT NOT NULL,
+  description        TEXT NOT NULL,
+  footer             TEXT,
+  deliver_after      TIMESTAMP WITH TIME ZONE NOT NULL,
+  created_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON queued_notifications (deliver_after);
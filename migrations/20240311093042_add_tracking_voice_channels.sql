@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS tracking_voice_channels (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  channel_id         TEXT NOT NULL UNIQUE
+);
+
+CREATE INDEX ON tracking_voice_channels (guild_id);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS voice_sessions (
+  guild_id           TEXT NOT NULL,
+  user_id            TEXT NOT NULL,
+  channel_id         TEXT NOT NULL,
+  joined_at          TIMESTAMP WITH TIME ZONE NOT NULL,
+  last_seen_at       TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  PRIMARY KEY (guild_id, user_id)
+);
@@ -0,0 +1,4 @@
+ALTER TABLE tracking_voice_channels ADD COLUMN IF NOT EXISTS min_participants SMALLINT DEFAULT 1 NOT NULL;
+
+ALTER TABLE voice_sessions ADD COLUMN IF NOT EXISTS counted_seconds INTEGER DEFAULT 0 NOT NULL;
+ALTER TABLE voice_sessions ADD COLUMN IF NOT EXISTS counting_since TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS sit_bells (
+  event_id           TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  interval_minutes   INTEGER,
+  duration_minutes   INTEGER,
+  sound              TEXT NOT NULL
+);
+
+CREATE INDEX ON sit_bells (guild_id);
@@ -0,0 +1 @@
+ALTER TABLE activity_log ADD COLUMN IF NOT EXISTS minutes INTEGER;
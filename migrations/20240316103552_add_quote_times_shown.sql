@@ -0,0 +1 @@
+ALTER TABLE quote ADD COLUMN IF NOT EXISTS times_shown INTEGER DEFAULT 0 NOT NULL;
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS course_reminders (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  course_name        TEXT NOT NULL UNIQUE,
+  channel_id         TEXT NOT NULL,
+  weekday            SMALLINT NOT NULL,
+  send_minute        SMALLINT NOT NULL,
+  message            TEXT NOT NULL,
+  ends_on            DATE NOT NULL,
+  last_sent_at       TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX ON course_reminders (guild_id);
@@ -0,0 +1,15 @@
+ALTER TABLE star ADD COLUMN IF NOT EXISTS board_channel_id TEXT;
+
+CREATE TABLE IF NOT EXISTS starboard_forums (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT UNIQUE NOT NULL,
+  channel_id         TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS starboard_forum_tags (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  source_channel_id  TEXT NOT NULL,
+  tag_id             TEXT NOT NULL,
+  UNIQUE (source_channel_id, tag_id)
+);
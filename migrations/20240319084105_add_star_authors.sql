@@ -0,0 +1,12 @@
+ALTER TABLE star ADD COLUMN IF NOT EXISTS guild_id TEXT;
+ALTER TABLE star ADD COLUMN IF NOT EXISTS author_id TEXT;
+ALTER TABLE star ADD COLUMN IF NOT EXISTS star_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS star_count_history (
+  record_id          TEXT PRIMARY KEY,
+  star_id            TEXT NOT NULL,
+  star_count         INTEGER NOT NULL,
+  recorded_at        TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON star (guild_id, author_id);
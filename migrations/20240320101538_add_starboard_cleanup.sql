@@ -0,0 +1,8 @@
+ALTER TABLE star ADD COLUMN IF NOT EXISTS source_deleted BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE star ADD COLUMN IF NOT EXISTS checked_at TIMESTAMP WITH TIME ZONE;
+
+CREATE TABLE IF NOT EXISTS starboard_settings (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT UNIQUE NOT NULL,
+  deleted_action     TEXT NOT NULL
+);
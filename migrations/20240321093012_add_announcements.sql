@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS announcements (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  channel_id         TEXT NOT NULL,
+  created_by         TEXT NOT NULL,
+  title              TEXT,
+  content            TEXT NOT NULL,
+  as_embed           BOOLEAN NOT NULL DEFAULT FALSE,
+  repeat_schedule    TEXT,
+  next_run_at        TIMESTAMP WITH TIME ZONE NOT NULL,
+  created_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE INDEX ON announcements (next_run_at);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS message_templates (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  name               TEXT NOT NULL,
+  title              TEXT,
+  content            TEXT NOT NULL,
+  as_embed           BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  UNIQUE (guild_id, name)
+);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS auto_thread_channels (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  channel_id         TEXT UNIQUE NOT NULL,
+  name_pattern       TEXT NOT NULL
+);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS checkin_prompts (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL UNIQUE,
+  channel_id         TEXT NOT NULL,
+  weekday            SMALLINT NOT NULL,
+  send_minute        SMALLINT NOT NULL,
+  message            TEXT NOT NULL,
+  last_sent_at       TIMESTAMP WITH TIME ZONE
+);
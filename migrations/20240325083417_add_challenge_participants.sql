@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS challenge_participants (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  user_id            TEXT NOT NULL,
+  challenge          TEXT NOT NULL,
+  reminders          BOOLEAN NOT NULL DEFAULT FALSE,
+  joined_at          TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+  last_reminded_at   TIMESTAMP WITH TIME ZONE,
+  UNIQUE (guild_id, user_id, challenge)
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS mood_checkins (
+  guild_id           TEXT NOT NULL,
+  user_hash          TEXT NOT NULL,
+  week_start         DATE NOT NULL,
+  score              SMALLINT NOT NULL,
+  PRIMARY KEY (guild_id, week_start, user_hash)
+);
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS collective_goals (
+  record_id            TEXT PRIMARY KEY,
+  guild_id             TEXT NOT NULL UNIQUE,
+  name                 TEXT NOT NULL,
+  target_minutes       BIGINT NOT NULL,
+  starts_on            DATE NOT NULL,
+  ends_on              DATE NOT NULL,
+  channel_id           TEXT NOT NULL,
+  progress_message_id  TEXT,
+  last_milestone       SMALLINT NOT NULL DEFAULT 0,
+  last_rendered_on     DATE
+);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS hours_announcements (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL UNIQUE,
+  session_interval   INTEGER NOT NULL,
+  message            TEXT
+);
@@ -0,0 +1,3 @@
+ALTER TABLE tracking_profile ADD COLUMN IF NOT EXISTS add_quotes BOOLEAN;
+ALTER TABLE quote_settings ALTER COLUMN card_background DROP NOT NULL;
+ALTER TABLE quote_settings ADD COLUMN IF NOT EXISTS add_quotes BOOLEAN DEFAULT TRUE NOT NULL;
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS streak_credits (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  user_id            TEXT NOT NULL,
+  kind               TEXT NOT NULL,
+  amount             INTEGER NOT NULL,
+  streak_day         DATE NOT NULL,
+  occurred_at        TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE (guild_id, user_id, kind, streak_day)
+);
+
+CREATE TABLE IF NOT EXISTS streak_credit_settings (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL UNIQUE
+);
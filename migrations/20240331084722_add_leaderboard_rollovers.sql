@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS leaderboard_rollovers (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  period             TEXT NOT NULL,
+  channel_id         TEXT NOT NULL,
+  last_period_start  TIMESTAMPTZ NOT NULL,
+  pinned_message_id  TEXT,
+  UNIQUE (guild_id, period)
+);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS leaderboard_archive (
+  guild_id      TEXT NOT NULL,
+  period        TEXT NOT NULL,
+  period_start  TIMESTAMPTZ NOT NULL,
+  rank          INTEGER NOT NULL,
+  user_id       TEXT NOT NULL,
+  minutes       BIGINT NOT NULL,
+  sessions      BIGINT NOT NULL,
+  PRIMARY KEY (guild_id, period, period_start, user_id)
+);
@@ -0,0 +1,25 @@
+CREATE TABLE IF NOT EXISTS import_jobs (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  user_id            TEXT NOT NULL,
+  file_name          TEXT NOT NULL,
+  file_url           TEXT NOT NULL,
+  file_size          INTEGER NOT NULL,
+  rows_processed     INTEGER NOT NULL DEFAULT 0,
+  chunks_completed   INTEGER NOT NULL DEFAULT 0,
+  entries_added      INTEGER NOT NULL DEFAULT 0,
+  rows_failed        INTEGER NOT NULL DEFAULT 0,
+  started_at         TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  heartbeat_at       TIMESTAMPTZ,
+  finished_at        TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS import_jobs_member_idx ON import_jobs (guild_id, user_id);
+
+CREATE TABLE IF NOT EXISTS import_job_errors (
+  job_id             TEXT NOT NULL REFERENCES import_jobs (record_id) ON DELETE CASCADE,
+  chunk              INTEGER NOT NULL,
+  line               INTEGER NOT NULL,
+  message            TEXT NOT NULL,
+  PRIMARY KEY (job_id, line)
+);
@@ -0,0 +1,21 @@
+ALTER TABLE import_jobs
+  ADD COLUMN IF NOT EXISTS on_conflict         TEXT NOT NULL DEFAULT 'skip',
+  ADD COLUMN IF NOT EXISTS duplicates_skipped  INTEGER NOT NULL DEFAULT 0,
+  ADD COLUMN IF NOT EXISTS entries_replaced    INTEGER NOT NULL DEFAULT 0,
+  ADD COLUMN IF NOT EXISTS entries_merged      INTEGER NOT NULL DEFAULT 0;
+
+-- Rows of the chunk being imported, compared against existing entries before they're added. Emptied before each chunk commits.
+CREATE UNLOGGED TABLE IF NOT EXISTS import_staging (
+  job_id             TEXT NOT NULL REFERENCES import_jobs (record_id) ON DELETE CASCADE,
+  record_id          TEXT NOT NULL,
+  occurred_at        TIMESTAMPTZ NOT NULL,
+  meditation_minutes INTEGER NOT NULL,
+  PRIMARY KEY (job_id, record_id)
+);
+
+-- Days already replaced by an import, so entries it added earlier on the same day aren't replaced by a later chunk.
+CREATE TABLE IF NOT EXISTS import_job_days (
+  job_id             TEXT NOT NULL REFERENCES import_jobs (record_id) ON DELETE CASCADE,
+  day                DATE NOT NULL,
+  PRIMARY KEY (job_id, day)
+);
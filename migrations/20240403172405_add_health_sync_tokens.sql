@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS health_sync_tokens (
+  record_id          TEXT PRIMARY KEY,
+  token              TEXT NOT NULL UNIQUE,
+  user_id            TEXT NOT NULL,
+  guild_id           TEXT NOT NULL,
+  created_at         TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP NOT NULL,
+  last_synced_at     TIMESTAMP WITH TIME ZONE,
+  UNIQUE (user_id, guild_id)
+);
@@ -0,0 +1,2 @@
+-- Left empty for entries logged before sources were recorded
+ALTER TABLE meditation ADD COLUMN IF NOT EXISTS source TEXT;
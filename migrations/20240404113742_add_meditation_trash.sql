@@ -0,0 +1,16 @@
+-- Deleted entries are moved here instead of being flagged in place, so queries on meditation never count them by mistake.
+CREATE TABLE IF NOT EXISTS meditation_trash (
+  record_id          TEXT PRIMARY KEY,
+  user_id            TEXT NOT NULL,
+  guild_id           TEXT NOT NULL,
+  occurred_at        TIMESTAMPTZ NOT NULL,
+  meditation_minutes INTEGER NOT NULL,
+  source             TEXT,
+  batch_id           TEXT NOT NULL,
+  reason             TEXT NOT NULL,
+  deleted_by         TEXT NOT NULL,
+  deleted_at         TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS meditation_trash_batch_idx ON meditation_trash (guild_id, batch_id);
+CREATE INDEX IF NOT EXISTS meditation_trash_deleted_at_idx ON meditation_trash (deleted_at);
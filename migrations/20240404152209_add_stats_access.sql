@@ -0,0 +1,26 @@
+-- Staff requests to view a member's private stats, which the member approves or denies by DM.
+CREATE TABLE IF NOT EXISTS stats_access_requests (
+  record_id    TEXT PRIMARY KEY,
+  guild_id     TEXT NOT NULL,
+  user_id      TEXT NOT NULL,
+  staff_id     TEXT NOT NULL,
+  reason       TEXT NOT NULL,
+  status       TEXT NOT NULL DEFAULT 'pending',
+  requested_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  responded_at TIMESTAMPTZ,
+  expires_at   TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS stats_access_requests_grant_idx ON stats_access_requests (guild_id, user_id, staff_id);
+
+-- Every request, response, and view of private stats through a grant.
+CREATE TABLE IF NOT EXISTS stats_access_log (
+  record_id   TEXT PRIMARY KEY,
+  guild_id    TEXT NOT NULL,
+  user_id     TEXT NOT NULL,
+  staff_id    TEXT NOT NULL,
+  action      TEXT NOT NULL,
+  occurred_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS stats_access_log_user_idx ON stats_access_log (guild_id, user_id);
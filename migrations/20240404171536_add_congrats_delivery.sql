@@ -0,0 +1,2 @@
+-- Where congratulations on a new time or streak role are sent: public, private, or off.
+ALTER TABLE tracking_profile ADD COLUMN IF NOT EXISTS congrats TEXT DEFAULT 'public' NOT NULL;
@@ -0,0 +1,2 @@
+ALTER TABLE queued_notifications ADD COLUMN IF NOT EXISTS field_names TEXT[] DEFAULT '{}' NOT NULL;
+ALTER TABLE queued_notifications ADD COLUMN IF NOT EXISTS field_values TEXT[] DEFAULT '{}' NOT NULL;
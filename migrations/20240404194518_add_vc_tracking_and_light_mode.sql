@@ -0,0 +1,2 @@
+ALTER TABLE tracking_profile ADD COLUMN IF NOT EXISTS vc_tracking BOOLEAN DEFAULT TRUE NOT NULL;
+ALTER TABLE tracking_profile ADD COLUMN IF NOT EXISTS light_mode BOOLEAN DEFAULT FALSE NOT NULL;
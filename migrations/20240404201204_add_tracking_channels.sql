@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS tracking_channels (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  channel_id         TEXT UNIQUE NOT NULL
+);
+
+CREATE INDEX ON tracking_channels (guild_id);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS command_exclusions (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  command_name       TEXT NOT NULL,
+  UNIQUE (guild_id, command_name)
+);
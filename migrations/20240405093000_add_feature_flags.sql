@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS feature_flags (
+  record_id          TEXT PRIMARY KEY,
+  guild_id           TEXT NOT NULL,
+  flag_name          TEXT NOT NULL,
+  enabled            BOOLEAN NOT NULL,
+  UNIQUE (guild_id, flag_name)
+);
@@ -0,0 +1,3 @@
+-- Only a hash of each quick-log token is kept, so the links can't be recovered from the database
+ALTER TABLE quicklog_tokens RENAME COLUMN token TO token_hash;
+UPDATE quicklog_tokens SET token_hash = ENCODE(SHA256(CONVERT_TO(token_hash, 'UTF8')), 'hex');
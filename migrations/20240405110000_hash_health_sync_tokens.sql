@@ -0,0 +1,3 @@
+-- Only a hash of each health sync token is kept, so tokens can't be recovered from the database
+ALTER TABLE health_sync_tokens RENAME COLUMN token TO token_hash;
+UPDATE health_sync_tokens SET token_hash = ENCODE(SHA256(CONVERT_TO(token_hash, 'UTF8')), 'hex');
@@ -0,0 +1,141 @@
+use log::debug;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// A step of adding a meditation session, timed separately to find where slow adds spend their time.
+#[derive(Debug, Clone, Copy)]
+pub enum AddStage {
+  Profile,
+  Insert,
+  Sum,
+  Streak,
+  Quote,
+  Roles,
+  GuildHours,
+}
+
+impl AddStage {
+  pub const ALL: [Self; 7] = [
+    Self::Profile,
+    Self::Insert,
+    Self::Sum,
+    Self::Streak,
+    Self::Quote,
+    Self::Roles,
+    Self::GuildHours,
+  ];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Self::Profile => "profile",
+      Self::Insert => "insert",
+      Self::Sum => "sum",
+      Self::Streak => "streak",
+      Self::Quote => "quote",
+      Self::Roles => "roles",
+      Self::GuildHours => "guild hours",
+    }
+  }
+}
+
+/// Running totals for a stage since startup.
+struct StageTotals {
+  count: AtomicU64,
+  total_micros: AtomicU64,
+  max_micros: AtomicU64,
+}
+
+impl StageTotals {
+  const fn new() -> Self {
+    Self {
+      count: AtomicU64::new(0),
+      total_micros: AtomicU64::new(0),
+      max_micros: AtomicU64::new(0),
+    }
+  }
+}
+
+/// Indexed like [`AddStage::ALL`].
+static STAGE_TOTALS: [StageTotals; AddStage::ALL.len()] = [
+  StageTotals::new(),
+  StageTotals::new(),
+  StageTotals::new(),
+  StageTotals::new(),
+  StageTotals::new(),
+  StageTotals::new(),
+  StageTotals::new(),
+];
+
+/// Stage timings for a single add, in the order the stages ran.
+#[derive(Default)]
+pub struct AddTimings {
+  stages: Vec<(AddStage, Duration)>,
+}
+
+impl AddTimings {
+  /// Awaits `future`, recording how long it took as `stage`.
+  pub async fn time<T>(&mut self, stage: AddStage, future: impl Future<Output = T>) -> T {
+    let started_at = Instant::now();
+    let output = future.await;
+    self.stages.push((stage, started_at.elapsed()));
+
+    output
+  }
+
+  /// Adds the stages timed in `other`, such as the attempt of a retried transaction that succeeded.
+  pub fn extend(&mut self, other: Self) {
+    self.stages.extend(other.stages);
+  }
+
+  /// One line per stage, with the total at the end.
+  pub fn summary(&self) -> String {
+    let total: Duration = self.stages.iter().map(|(_, elapsed)| *elapsed).sum();
+
+    self
+      .stages
+      .iter()
+      .map(|(stage, elapsed)| format!("{}: {}ms", stage.name(), elapsed.as_millis()))
+      .chain(std::iter::once(format!("total: {}ms", total.as_millis())))
+      .collect::<Vec<String>>()
+      .join("\n")
+  }
+
+  /// Logs the timings under `trace_id` and counts them toward the totals shown in `/status`.
+  pub fn finish(&self, trace_id: &str) {
+    for (stage, elapsed) in &self.stages {
+      let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+      let totals = &STAGE_TOTALS[*stage as usize];
+
+      totals.count.fetch_add(1, Ordering::Relaxed);
+      totals.total_micros.fetch_add(micros, Ordering::Relaxed);
+      totals.max_micros.fetch_max(micros, Ordering::Relaxed);
+
+      debug!(
+        "[{trace_id}] Add stage {} took {}ms",
+        stage.name(),
+        elapsed.as_millis()
+      );
+    }
+  }
+}
+
+/// Number of times each stage has run since startup, with its average and longest time.
+pub fn stage_stats() -> Vec<(AddStage, u64, Duration, Duration)> {
+  AddStage::ALL
+    .into_iter()
+    .map(|stage| {
+      let totals = &STAGE_TOTALS[stage as usize];
+      let count = totals.count.load(Ordering::Relaxed);
+      let total = Duration::from_micros(totals.total_micros.load(Ordering::Relaxed));
+      let average = if count == 0 {
+        Duration::ZERO
+      } else {
+        total / u32::try_from(count).unwrap_or(u32::MAX)
+      };
+      let max = Duration::from_micros(totals.max_micros.load(Ordering::Relaxed));
+
+      (stage, count, average, max)
+    })
+    .collect()
+}
@@ -0,0 +1,109 @@
+use crate::commands::helpers::cron::CronSchedule;
+use crate::config::BloomBotEmbed;
+use crate::database::DatabaseHandler;
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, Utc};
+use log::{error, info, warn};
+use poise::serenity_prelude::{self as serenity, CreateMessage};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often scheduled announcements are checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Hours after its scheduled time that an announcement can still be posted, such as after a restart. Later than this, it is skipped.
+const MAX_DELAY_HOURS: i64 = 6;
+
+/// Builds a staff message, as an embed with an optional title or as plain text. Used for announcements and message templates.
+pub fn announcement_message(title: Option<&str>, content: &str, as_embed: bool) -> CreateMessage {
+  if as_embed {
+    let mut embed = BloomBotEmbed::new().description(content);
+    if let Some(title) = title {
+      embed = embed.title(title);
+    }
+    CreateMessage::new().embed(embed)
+  } else {
+    CreateMessage::new().content(content)
+  }
+}
+
+/// Posts scheduled announcements when they are due. Repeating announcements are rescheduled, and one-off announcements are removed once posted.
+///
+/// Runs until the bot shuts down.
+pub async fn post_announcements(http: Arc<serenity::Http>, db: DatabaseHandler) {
+  let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(e) = post_due_announcements(&http, &db).await {
+      error!("Error while posting scheduled announcements: {e:?}");
+    }
+  }
+}
+
+async fn post_due_announcements(http: &serenity::Http, db: &DatabaseHandler) -> Result<()> {
+  let now = Utc::now();
+
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let announcements = DatabaseHandler::get_announcements(&mut transaction, None, Some(now)).await?;
+  drop(transaction);
+
+  for announcement in announcements {
+    let next_run_at = announcement
+      .repeat_schedule
+      .as_deref()
+      .and_then(CronSchedule::parse)
+      .and_then(|schedule| schedule.next_after(now));
+
+    // Rescheduled or removed first, so a failing channel doesn't get retried every minute
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    match next_run_at {
+      Some(next_run_at) => {
+        DatabaseHandler::reschedule_announcement(&mut transaction, &announcement.id, next_run_at)
+          .await?;
+      }
+      None => {
+        DatabaseHandler::remove_announcement(
+          &mut transaction,
+          &announcement.guild_id,
+          &announcement.id,
+        )
+        .await?;
+      }
+    }
+    DatabaseHandler::commit_transaction(transaction).await?;
+
+    if now - announcement.next_run_at > ChronoDuration::hours(MAX_DELAY_HOURS) {
+      warn!(
+        "Skipped announcement {} in {}, which was due at {}",
+        announcement.id, announcement.guild_id, announcement.next_run_at
+      );
+      continue;
+    }
+
+    let result = announcement
+      .channel_id
+      .send_message(
+        http,
+        announcement_message(
+          announcement.title.as_deref(),
+          &announcement.content,
+          announcement.as_embed,
+        ),
+      )
+      .await;
+
+    match result {
+      Ok(_) => info!(
+        "Posted announcement {} in channel {}",
+        announcement.id, announcement.channel_id
+      ),
+      Err(e) => error!(
+        "Could not post announcement {} in channel {}: {e:?}",
+        announcement.id, announcement.channel_id
+      ),
+    }
+  }
+
+  Ok(())
+}
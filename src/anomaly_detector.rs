@@ -0,0 +1,118 @@
+use crate::config::{BloomBotEmbed, CHANNELS};
+use crate::database::DatabaseHandler;
+use anyhow::Result;
+use log::{error, info};
+use poise::serenity_prelude::{self as serenity, CreateMessage};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often tracking activity is analyzed.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60 * 6);
+/// How many times their usual daily average a user must add in a day to be flagged.
+const VOLUME_SPIKE_RATIO: f64 = 10.0;
+/// Minimum minutes in a day before a volume spike is flagged, so small averages don't cause noise.
+const VOLUME_SPIKE_MIN_MINUTES: i64 = 120;
+/// Number of identical durations in a week before they are flagged.
+const REPEATED_DURATION_MIN_COUNT: i64 = 5;
+/// Maximum standard deviation between repeated entries, in seconds, for the interval to look automated.
+const REPEATED_DURATION_MAX_GAP_DEVIATION: f64 = 60.0;
+
+/// Periodically flags statistically anomalous tracking activity for staff review.
+///
+/// Flags are added to the review queue for `/manage anomalies`, and staff are notified in the logs channel. Runs until the bot shuts down.
+pub async fn detect_anomalies(http: Arc<serenity::Http>, db: DatabaseHandler) {
+  let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(e) = flag_anomalies(&http, &db).await {
+      error!("Error while detecting tracking anomalies: {e:?}");
+    }
+  }
+}
+
+async fn flag_anomalies(http: &serenity::Http, db: &DatabaseHandler) -> Result<()> {
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+
+  let spikes = DatabaseHandler::get_volume_spikes(
+    &mut transaction,
+    VOLUME_SPIKE_RATIO,
+    VOLUME_SPIKE_MIN_MINUTES,
+  )
+  .await?;
+  let repeats = DatabaseHandler::get_repeated_durations(
+    &mut transaction,
+    REPEATED_DURATION_MIN_COUNT,
+    REPEATED_DURATION_MAX_GAP_DEVIATION,
+  )
+  .await?;
+
+  let mut flagged = Vec::new();
+
+  for spike in spikes {
+    let details = format!(
+      "Added {} minutes in the past day, against a daily average of {:.1} minutes over the previous 30 days.",
+      spike.recent_minutes, spike.daily_average
+    );
+
+    if DatabaseHandler::add_anomaly_flag(
+      &mut transaction,
+      &spike.guild_id,
+      &spike.user_id,
+      "volume_spike",
+      &details,
+      &spike.entry_ids,
+    )
+    .await?
+    {
+      flagged.push((spike.user_id, "Sudden Volume Increase", details));
+    }
+  }
+
+  for repeat in repeats {
+    let details = format!(
+      "Added {} minutes {} times in the past week, about every {:.1} hours.",
+      repeat.meditation_minutes,
+      repeat.entry_count,
+      repeat.average_gap / 3600.0
+    );
+
+    if DatabaseHandler::add_anomaly_flag(
+      &mut transaction,
+      &repeat.guild_id,
+      &repeat.user_id,
+      "repeated_duration",
+      &details,
+      &repeat.entry_ids,
+    )
+    .await?
+    {
+      flagged.push((repeat.user_id, "Repeated Identical Entries", details));
+    }
+  }
+
+  transaction.commit().await?;
+
+  if flagged.is_empty() {
+    return Ok(());
+  }
+
+  info!("Flagged {} tracking anomalies for review", flagged.len());
+
+  let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+
+  for (user_id, kind, details) in flagged {
+    let embed = BloomBotEmbed::new()
+      .title(format!("Suspicious Tracking Activity: {kind}"))
+      .description(format!(
+        "**User**: <@{user_id}> ({user_id})\n**Details**: {details}\n\nUse `/manage anomalies` to review."
+      ));
+
+    log_channel
+      .send_message(http, CreateMessage::new().embed(embed))
+      .await?;
+  }
+
+  Ok(())
+}
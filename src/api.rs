@@ -0,0 +1,543 @@
+use crate::config::{BloomBotEmbed, EntrySource, CHANNELS, QUICKLOG_COOLDOWN_MINUTES};
+use crate::database::DatabaseHandler;
+use anyhow::Result;
+use log::{error, info};
+use poise::serenity_prelude::{self as serenity, CreateMessage};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Largest request head accepted. Most requests only carry a token in the path.
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+/// Largest request body accepted, which is only used by health sync pushes.
+const MAX_BODY_SIZE: usize = 64 * 1024;
+/// Most sessions accepted in one health sync push.
+const MAX_SYNCED_SESSIONS: usize = 200;
+/// Oldest sessions accepted from health sync. Older history can be added with `/import`.
+const SYNC_LOOKBACK_DAYS: i64 = 30;
+/// Shortest time between health sync pushes for the same token.
+const SYNC_COOLDOWN_SECONDS: i64 = 60;
+/// Minutes either side of a synced session in which an existing entry makes it a duplicate.
+const SYNC_TOLERANCE_MINUTES: i32 = 10;
+
+struct Request {
+  /// Request line and headers.
+  head: String,
+  body: Vec<u8>,
+}
+
+impl Request {
+  /// Value of the named header, if it was sent.
+  fn header(&self, name: &str) -> Option<&str> {
+    self.head.lines().skip(1).find_map(|line| {
+      let (key, value) = line.split_once(':')?;
+      key
+        .trim()
+        .eq_ignore_ascii_case(name)
+        .then_some(value.trim())
+    })
+  }
+}
+/// How long a client has to send its request before the connection is closed.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Response {
+  status: &'static str,
+  content_type: &'static str,
+  body: String,
+}
+
+impl Response {
+  fn new(status: &'static str, body: impl Into<String>) -> Self {
+    Self {
+      status,
+      content_type: "text/plain; charset=utf-8",
+      body: body.into(),
+    }
+  }
+
+  fn calendar(body: String) -> Self {
+    Self {
+      status: "200 OK",
+      content_type: "text/calendar; charset=utf-8",
+      body,
+    }
+  }
+
+  fn to_http(&self) -> String {
+    format!(
+      "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+      self.status,
+      self.content_type,
+      self.body.len(),
+      self.body
+    )
+  }
+}
+
+/// Serves the HTTP API used by integrations outside Discord, such as quick-log links, calendar feeds, and health sync.
+///
+/// Only a handful of plain GET and POST routes are needed, so requests are parsed by hand rather than pulling in a web framework. Runs until the bot shuts down.
+/// Routes that log sessions are turned away while `maintenance` is set, like write commands.
+pub async fn serve(
+  address: String,
+  http: Arc<serenity::Http>,
+  db: DatabaseHandler,
+  maintenance: Arc<AtomicBool>,
+) {
+  let listener = match TcpListener::bind(&address).await {
+    Ok(listener) => listener,
+    Err(e) => {
+      error!("Could not start HTTP API on {address}: {e}");
+      return;
+    }
+  };
+
+  info!("HTTP API listening on {address}");
+
+  loop {
+    let stream = match listener.accept().await {
+      Ok((stream, _)) => stream,
+      Err(e) => {
+        error!("Error accepting HTTP API connection: {e}");
+        continue;
+      }
+    };
+
+    let http = http.clone();
+    let db = db.clone();
+    let maintenance = maintenance.clone();
+    tokio::spawn(async move {
+      if let Err(e) = handle_connection(stream, &http, &db, &maintenance).await {
+        error!("Error handling HTTP API request: {e}");
+      }
+    });
+  }
+}
+
+async fn read_request(stream: &mut TcpStream) -> Result<Option<Request>> {
+  let mut buffer = Vec::new();
+  let mut chunk = [0; 1024];
+
+  let head_end = loop {
+    if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+      break position + 4;
+    }
+
+    let read = stream.read(&mut chunk).await?;
+    if read == 0 || buffer.len() + read > MAX_REQUEST_SIZE + MAX_BODY_SIZE {
+      return Ok(None);
+    }
+    buffer.extend_from_slice(&chunk[..read]);
+  };
+
+  if head_end > MAX_REQUEST_SIZE {
+    return Ok(None);
+  }
+
+  let mut request = Request {
+    head: String::from_utf8_lossy(&buffer[..head_end]).into_owned(),
+    body: buffer.split_off(head_end),
+  };
+
+  let content_length = request
+    .header("Content-Length")
+    .map_or(Some(0), |length| length.parse::<usize>().ok());
+  let Some(content_length) = content_length.filter(|length| *length <= MAX_BODY_SIZE) else {
+    return Ok(None);
+  };
+
+  while request.body.len() < content_length {
+    let read = stream.read(&mut chunk).await?;
+    if read == 0 {
+      return Ok(None);
+    }
+    request.body.extend_from_slice(&chunk[..read]);
+  }
+  request.body.truncate(content_length);
+
+  Ok(Some(request))
+}
+
+async fn handle_connection(
+  mut stream: TcpStream,
+  http: &serenity::Http,
+  db: &DatabaseHandler,
+  maintenance: &AtomicBool,
+) -> Result<()> {
+  let Ok(request) = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await else {
+    return Ok(());
+  };
+
+  let response = match request? {
+    Some(request) => route(&request, http, db, maintenance).await,
+    None => Response::new("400 Bad Request", "Malformed request."),
+  };
+
+  stream.write_all(response.to_http().as_bytes()).await?;
+  stream.shutdown().await?;
+
+  Ok(())
+}
+
+async fn route(
+  request: &Request,
+  http: &serenity::Http,
+  db: &DatabaseHandler,
+  maintenance: &AtomicBool,
+) -> Response {
+  let mut request_line = request.head.lines().next().unwrap_or_default().split(' ');
+  let (Some(method), Some(path)) = (request_line.next(), request_line.next()) else {
+    return Response::new("400 Bad Request", "Malformed request.");
+  };
+
+  // Query strings are ignored, so shortcut apps can append their own parameters
+  let path = path.split('?').next().unwrap_or_default();
+  let segments: Vec<&str> = path
+    .split('/')
+    .filter(|segment| !segment.is_empty())
+    .collect();
+
+  match (method, segments.as_slice()) {
+    ("POST", ["quicklog" | "healthsync", ..]) if maintenance.load(Ordering::Relaxed) => {
+      Response::new(
+        "503 Service Unavailable",
+        "Bloom is in maintenance mode, so sessions can't be logged right now. Please try again later.",
+      )
+    }
+    // Link previews and prefetching open links with GET, which must not log a session
+    ("POST", ["quicklog", token]) => match quicklog(db, token).await {
+      Ok(response) => response,
+      Err(e) => {
+        error!("Error handling quick-log request: {e}");
+        Response::new(
+          "500 Internal Server Error",
+          "Something went wrong. Please try again later.",
+        )
+      }
+    },
+    ("GET", ["quicklog", _]) => Response::new(
+      "405 Method Not Allowed",
+      "Quick-log links only log a session when sent a POST request, such as from a phone shortcut. Opening them in a browser doesn't log anything.",
+    ),
+    (_, ["quicklog", _]) => Response::new("405 Method Not Allowed", "Method not allowed."),
+    ("GET", ["calendar", feed]) => calendar(http, feed).await,
+    ("POST", ["healthsync"]) => match health_sync(http, db, request).await {
+      Ok(response) => response,
+      Err(e) => {
+        error!("Error handling health sync request: {e}");
+        Response::new(
+          "500 Internal Server Error",
+          "Something went wrong. Please try again later.",
+        )
+      }
+    },
+    (_, ["healthsync"]) => Response::new("405 Method Not Allowed", "Method not allowed."),
+    _ => Response::new("404 Not Found", "Not found."),
+  }
+}
+
+/// Logs the preset session for a quick-log token.
+async fn quicklog(db: &DatabaseHandler, token: &str) -> Result<Response> {
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+
+  let Some(quicklog) = DatabaseHandler::get_quicklog_token(&mut transaction, token).await? else {
+    return Ok(Response::new(
+      "404 Not Found",
+      "This link is invalid or has been revoked.",
+    ));
+  };
+
+  let now = chrono::Utc::now();
+  if quicklog
+    .last_used_at
+    .is_some_and(|used_at| now - used_at < chrono::Duration::minutes(QUICKLOG_COOLDOWN_MINUTES))
+  {
+    return Ok(Response::new(
+      "429 Too Many Requests",
+      format!("This link was used recently. Please wait {QUICKLOG_COOLDOWN_MINUTES} minutes between sessions."),
+    ));
+  }
+
+  // The limits may have changed since the link was created
+  let limits = DatabaseHandler::get_session_limits(&mut transaction, &quicklog.guild_id).await?;
+  if limits.min_minutes.is_some_and(|min| quicklog.minutes < min)
+    || limits.max_minutes.is_some_and(|max| quicklog.minutes > max)
+  {
+    return Ok(Response::new(
+      "422 Unprocessable Entity",
+      "This link's session length is outside the limits for this server. Please create a new link with /customize quicklog create.",
+    ));
+  }
+
+  let offset =
+    DatabaseHandler::get_tracking_profile(&mut transaction, &quicklog.guild_id, &quicklog.user_id)
+      .await?
+      .map_or(0, |profile| i64::from(profile.utc_offset));
+
+  // Entry times are stored in the user's local time, as with /add
+  DatabaseHandler::create_meditation_entry(
+    &mut transaction,
+    &quicklog.guild_id,
+    &quicklog.user_id,
+    quicklog.minutes,
+    now + chrono::Duration::minutes(offset),
+    EntrySource::Api,
+  )
+  .await?;
+  DatabaseHandler::mark_quicklog_token_used(&mut transaction, token).await?;
+
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  info!(
+    "Quick-logged {} minutes for user {} in guild {}",
+    quicklog.minutes, quicklog.user_id, quicklog.guild_id
+  );
+
+  Ok(Response::new(
+    "200 OK",
+    format!("Logged {} minutes. Well done!", quicklog.minutes),
+  ))
+}
+
+/// Serves a guild's calendar feed, so members can subscribe to it in their calendar apps.
+async fn calendar(http: &serenity::Http, feed: &str) -> Response {
+  let Some(guild_id) = feed
+    .strip_suffix(".ics")
+    .and_then(|id| id.parse::<u64>().ok())
+    .filter(|id| *id != 0)
+    .map(serenity::GuildId::new)
+  else {
+    return Response::new("404 Not Found", "Not found.");
+  };
+
+  // Fails for guilds the bot is not in, which are treated as not found
+  match crate::calendar::guild_calendar(http, guild_id).await {
+    Ok(feed) => Response::calendar(feed),
+    Err(e) => {
+      info!("Could not build calendar for guild {guild_id}: {e}");
+      Response::new("404 Not Found", "Not found.")
+    }
+  }
+}
+
+/// Reads the sessions from a health sync push, as local times in each session's own time zone.
+///
+/// The body is a JSON object with a `sessions` array. Each session has a `start` time in RFC 3339 format, and either a number of `minutes` or an `end` time, matching what Apple Health and Google Fit record for mindful minutes.
+fn parse_synced_sessions(
+  body: &[u8],
+) -> Result<Vec<(chrono::DateTime<chrono::FixedOffset>, i32)>, String> {
+  let body: serde_json::Value =
+    serde_json::from_slice(body).map_err(|_| "The request body must be JSON.".to_string())?;
+  let Some(sessions) = body.get("sessions").and_then(serde_json::Value::as_array) else {
+    return Err("The request body must have a `sessions` array.".to_string());
+  };
+
+  if sessions.len() > MAX_SYNCED_SESSIONS {
+    return Err(format!(
+      "At most {MAX_SYNCED_SESSIONS} sessions can be synced at once."
+    ));
+  }
+
+  sessions
+    .iter()
+    .enumerate()
+    .map(|(index, session)| {
+      let time = |field: &str| {
+        session
+          .get(field)
+          .and_then(serde_json::Value::as_str)
+          .and_then(|time| chrono::DateTime::parse_from_rfc3339(time).ok())
+      };
+
+      let Some(start) = time("start") else {
+        return Err(format!(
+          "Session {} needs a `start` time in RFC 3339 format.",
+          index + 1
+        ));
+      };
+
+      let minutes = match (
+        session.get("minutes").and_then(serde_json::Value::as_f64),
+        time("end"),
+      ) {
+        (Some(minutes), _) => minutes.round(),
+        #[allow(clippy::cast_precision_loss)]
+        (None, Some(end)) => ((end - start).num_seconds() as f64 / 60.0).round(),
+        (None, None) => {
+          return Err(format!(
+            "Session {} needs a number of `minutes` or an `end` time.",
+            index + 1
+          ))
+        }
+      };
+
+      if !(1.0..=f64::from(24 * 60)).contains(&minutes) {
+        return Err(format!(
+          "Session {} must be between 1 minute and 24 hours long.",
+          index + 1
+        ));
+      }
+
+      #[allow(clippy::cast_possible_truncation)]
+      Ok((start, minutes as i32))
+    })
+    .collect()
+}
+
+/// Adds mindful minutes pushed from a phone automation, skipping sessions the member already logged, such as with `/add`.
+///
+/// Automations usually send every session from the last few days on each run, so pushes can safely repeat sessions.
+/// Sessions outside the server's session limits are skipped and flagged to staff in the Bloom logs channel.
+async fn health_sync(
+  http: &serenity::Http,
+  db: &DatabaseHandler,
+  request: &Request,
+) -> Result<Response> {
+  let Some(token) = request
+    .header("Authorization")
+    .and_then(|value| value.strip_prefix("Bearer "))
+    .map(str::trim)
+  else {
+    return Ok(Response::new(
+      "401 Unauthorized",
+      "Send your health sync token as a bearer token.",
+    ));
+  };
+
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+
+  let Some(sync) = DatabaseHandler::get_health_sync_token(&mut transaction, token).await? else {
+    return Ok(Response::new(
+      "401 Unauthorized",
+      "This token is invalid or has been revoked.",
+    ));
+  };
+
+  let now = chrono::Utc::now();
+  if sync
+    .last_synced_at
+    .is_some_and(|synced_at| now - synced_at < chrono::Duration::seconds(SYNC_COOLDOWN_SECONDS))
+  {
+    return Ok(Response::new(
+      "429 Too Many Requests",
+      format!("Please wait {SYNC_COOLDOWN_SECONDS} seconds between syncs."),
+    ));
+  }
+
+  let sessions = match parse_synced_sessions(&request.body) {
+    Ok(sessions) => sessions,
+    Err(message) => return Ok(Response::new("400 Bad Request", message)),
+  };
+
+  let limits = DatabaseHandler::get_session_limits(&mut transaction, &sync.guild_id).await?;
+  let oldest = now - chrono::Duration::days(SYNC_LOOKBACK_DAYS);
+
+  // Entry times are stored in the member's local time, as with /add, and phones send times with their offset
+  let (accepted, rejected): (Vec<_>, Vec<_>) = sessions
+    .into_iter()
+    .map(|(start, minutes)| {
+      (
+        start + chrono::Duration::minutes(i64::from(minutes)),
+        minutes,
+      )
+    })
+    .partition(|(end, minutes)| {
+      *end <= now
+        && *end >= oldest
+        && !limits.min_minutes.is_some_and(|min| *minutes < min)
+        && !limits.max_minutes.is_some_and(|max| *minutes > max)
+    });
+  // Sessions are sent again on later pushes, so only those that ended since the last push are flagged
+  let out_of_limits = rejected
+    .iter()
+    .filter(|(end, _)| *end <= now && *end >= oldest)
+    .filter(|(end, _)| {
+      !sync
+        .last_synced_at
+        .is_some_and(|synced_at| *end <= synced_at)
+    })
+    .map(|(_, minutes)| *minutes)
+    .collect::<Vec<_>>();
+  let accepted = accepted
+    .into_iter()
+    .map(|(end, minutes)| (end.naive_local().and_utc(), minutes))
+    .collect::<Vec<_>>();
+
+  let added = if accepted.is_empty() {
+    0
+  } else {
+    DatabaseHandler::add_synced_entries(
+      &mut transaction,
+      &sync.guild_id,
+      &sync.user_id,
+      &accepted,
+      SYNC_TOLERANCE_MINUTES,
+    )
+    .await?
+  };
+  DatabaseHandler::mark_health_sync_token_used(&mut transaction, token).await?;
+
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  let duplicates = accepted.len() as u64 - added;
+  if added > 0 {
+    info!(
+      "Synced {added} health sessions for user {} in guild {}",
+      sync.user_id, sync.guild_id
+    );
+  }
+
+  if !out_of_limits.is_empty() {
+    info!(
+      "Skipped {} health sessions outside the session limits for user {} in guild {}",
+      out_of_limits.len(),
+      sync.user_id,
+      sync.guild_id
+    );
+
+    // Flag skipped sessions in Bloom logs channel
+    let log_embed = BloomBotEmbed::new()
+      .title("Health Sync Sessions Outside Limits Skipped")
+      .description(format!(
+        "**User**: <@{0}> ({0})\n**Sessions**: {1}\n**Limits**: {2} to {3}",
+        sync.user_id,
+        out_of_limits
+          .iter()
+          .map(|minutes| format!("{minutes} minutes"))
+          .collect::<Vec<_>>()
+          .join(", "),
+        limits
+          .min_minutes
+          .map_or_else(|| "no minimum".to_string(), |min| format!("{min} minutes")),
+        limits
+          .max_minutes
+          .map_or_else(|| "no maximum".to_string(), |max| format!("{max} minutes")),
+      ));
+
+    let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+
+    // The sessions that were accepted are already saved, so the sync still succeeds
+    if let Err(e) = log_channel
+      .send_message(http, CreateMessage::new().embed(log_embed))
+      .await
+    {
+      error!("Error flagging skipped health sessions: {e}");
+    }
+  }
+
+  let mut message = format!("Synced {added} new sessions.");
+  if duplicates > 0 {
+    message.push_str(&format!(" {duplicates} were already logged."));
+  }
+  if !rejected.is_empty() {
+    message.push_str(&format!(
+      " {} were skipped for being older than {SYNC_LOOKBACK_DAYS} days, in the future, or outside this server's session limits.",
+      rejected.len()
+    ));
+  }
+
+  Ok(Response::new("200 OK", message))
+}
@@ -0,0 +1,71 @@
+use crate::database::{AutoThreadChannel, DatabaseHandler};
+use anyhow::Result;
+use poise::serenity_prelude as serenity;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// How long auto-thread channels are kept before they are reloaded from the database.
+const AUTO_THREAD_TTL: Duration = Duration::from_secs(60 * 60);
+
+struct CachedChannels {
+  loaded_at: Instant,
+  channels: Vec<AutoThreadChannel>,
+}
+
+/// Per-guild cache of channels where discussion threads are created automatically, since they are looked up for every message.
+///
+/// Entries expire after [`AUTO_THREAD_TTL`], and should be invalidated whenever the channels are changed.
+#[derive(Default)]
+pub struct AutoThreadCache {
+  entries: RwLock<HashMap<serenity::GuildId, CachedChannels>>,
+}
+
+impl AutoThreadCache {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Gets the auto-thread settings for a channel, if it has them.
+  pub async fn get(
+    &self,
+    db: &DatabaseHandler,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+  ) -> Result<Option<AutoThreadChannel>> {
+    if let Some(cached) = self.entries.read().await.get(guild_id) {
+      if cached.loaded_at.elapsed() < AUTO_THREAD_TTL {
+        return Ok(
+          cached
+            .channels
+            .iter()
+            .find(|channel| channel.channel_id == *channel_id)
+            .cloned(),
+        );
+      }
+    }
+
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    let channels = DatabaseHandler::get_auto_thread_channels(&mut transaction, guild_id).await?;
+    drop(transaction);
+
+    let channel = channels
+      .iter()
+      .find(|channel| channel.channel_id == *channel_id)
+      .cloned();
+
+    self.entries.write().await.insert(
+      *guild_id,
+      CachedChannels {
+        loaded_at: Instant::now(),
+        channels,
+      },
+    );
+
+    Ok(channel)
+  }
+
+  pub async fn invalidate(&self, guild_id: &serenity::GuildId) {
+    self.entries.write().await.remove(guild_id);
+  }
+}
@@ -0,0 +1,93 @@
+use anyhow::Result;
+use log::info;
+use poise::serenity_prelude as serenity;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// How long downloaded avatars are kept. Avatar URLs change when the avatar does, so this only limits memory use.
+const AVATAR_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+/// Most avatars kept at once. The oldest is dropped first.
+const MAX_AVATARS: usize = 500;
+/// Size in pixels of the avatars requested from Discord.
+const AVATAR_SIZE: u16 = 64;
+/// How long an avatar download can take before it is given up on.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct CachedAvatar {
+  loaded_at: Instant,
+  bytes: Arc<Vec<u8>>,
+}
+
+/// Cache of small avatar images downloaded for charts, keyed by avatar URL.
+///
+/// Failed downloads aren't cached, so they are tried again the next time the avatar is needed.
+#[derive(Default)]
+pub struct AvatarCache {
+  client: reqwest::Client,
+  entries: RwLock<HashMap<String, CachedAvatar>>,
+}
+
+impl AvatarCache {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Returns the user's avatar image, downloading it if it isn't cached.
+  ///
+  /// Never fails, since avatars are only decoration; download errors are logged and `None` is returned.
+  pub async fn get(&self, user: &serenity::User) -> Option<Arc<Vec<u8>>> {
+    // Discord serves smaller sizes on request, which is all a chart needs
+    let url = user
+      .static_face()
+      .replace("?size=1024", &format!("?size={AVATAR_SIZE}"));
+
+    if let Some(cached) = self.entries.read().await.get(&url) {
+      if cached.loaded_at.elapsed() < AVATAR_TTL {
+        return Some(cached.bytes.clone());
+      }
+    }
+
+    let bytes = match self.download(&url).await {
+      Ok(bytes) => bytes,
+      Err(e) => {
+        info!("Could not download avatar for {}: {e}", user.id);
+        return None;
+      }
+    };
+
+    let mut entries = self.entries.write().await;
+    entries.retain(|_, cached| cached.loaded_at.elapsed() < AVATAR_TTL);
+    if entries.len() >= MAX_AVATARS {
+      let oldest = entries
+        .iter()
+        .min_by_key(|(_, cached)| cached.loaded_at)
+        .map(|(url, _)| url.clone());
+      if let Some(oldest) = oldest {
+        entries.remove(&oldest);
+      }
+    }
+    entries.insert(
+      url,
+      CachedAvatar {
+        loaded_at: Instant::now(),
+        bytes: bytes.clone(),
+      },
+    );
+
+    Some(bytes)
+  }
+
+  async fn download(&self, url: &str) -> Result<Arc<Vec<u8>>> {
+    let response = self
+      .client
+      .get(url)
+      .timeout(DOWNLOAD_TIMEOUT)
+      .send()
+      .await?
+      .error_for_status()?;
+
+    Ok(Arc::new(response.bytes().await?.to_vec()))
+  }
+}
@@ -0,0 +1,177 @@
+use crate::config::BellSound;
+use crate::database::SitBells;
+use anyhow::Result;
+use log::{error, info};
+use poise::serenity_prelude as serenity;
+use songbird::input::Input;
+use songbird::{Call, Songbird};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+const BOWL: &[u8] = include_bytes!("../assets/bells/bowl.wav");
+const CHIME: &[u8] = include_bytes!("../assets/bells/chime.wav");
+
+/// Number of bells rung to close a sit.
+const ENDING_BELLS: u32 = 3;
+
+/// Time between the ending bells, long enough for each to mostly fade out.
+const RING_OUT: Duration = Duration::from_secs(8);
+
+fn audio(sound: BellSound) -> &'static [u8] {
+  match sound {
+    BellSound::Bowl => BOWL,
+    BellSound::Chime => CHIME,
+  }
+}
+
+async fn strike(call: &Mutex<Call>, sound: BellSound) {
+  call.lock().await.play_input(Input::from(audio(sound)));
+}
+
+/// Joins a voice channel for a community sit and rings the opening, interval, and ending bells.
+///
+/// Without a set duration, the bells keep going until the sit is stopped.
+async fn ring_sit(
+  manager: Arc<Songbird>,
+  guild_id: serenity::GuildId,
+  channel_id: serenity::ChannelId,
+  bells: SitBells,
+) -> Result<()> {
+  let sound = BellSound::from_key(&bells.sound).unwrap_or(BellSound::Bowl);
+  let interval = bells
+    .interval_minutes
+    .map(|minutes| Duration::from_secs(u64::try_from(minutes).unwrap_or(1) * 60));
+  let duration = bells
+    .duration_minutes
+    .map(|minutes| Duration::from_secs(u64::try_from(minutes).unwrap_or(1) * 60));
+
+  let call = manager.join(guild_id, channel_id).await?;
+  let started_at = Instant::now();
+  strike(&call, sound).await;
+
+  if let Some(interval) = interval {
+    let mut next = interval;
+    while duration.map_or(true, |duration| next < duration) {
+      tokio::time::sleep_until(started_at + next).await;
+      strike(&call, sound).await;
+      next += interval;
+    }
+  }
+
+  let Some(duration) = duration else {
+    // Rung out when the event ends
+    return std::future::pending().await;
+  };
+
+  tokio::time::sleep_until(started_at + duration).await;
+  ring_out(&manager, guild_id, &call, sound).await
+}
+
+/// Rings the ending bells, then leaves the voice channel.
+async fn ring_out(
+  manager: &Songbird,
+  guild_id: serenity::GuildId,
+  call: &Mutex<Call>,
+  sound: BellSound,
+) -> Result<()> {
+  for _ in 0..ENDING_BELLS {
+    strike(call, sound).await;
+    tokio::time::sleep(RING_OUT).await;
+  }
+
+  manager.remove(guild_id).await?;
+
+  Ok(())
+}
+
+/// Community sits the bot is currently ringing bells for, keyed by scheduled event.
+pub struct BellRinger {
+  sits: Mutex<HashMap<serenity::ScheduledEventId, (JoinHandle<()>, BellSound)>>,
+}
+
+impl BellRinger {
+  pub fn new() -> Self {
+    Self {
+      sits: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Starts ringing bells for a sit, unless they are already being rung.
+  pub async fn start(
+    &self,
+    ctx: &serenity::Context,
+    guild_id: serenity::GuildId,
+    channel_id: serenity::ChannelId,
+    bells: SitBells,
+  ) {
+    let Some(manager) = songbird::get(ctx).await else {
+      error!("Voice client is not registered, so bells can't be rung");
+      return;
+    };
+
+    let mut sits = self.sits.lock().await;
+    if sits
+      .get(&bells.event_id)
+      .is_some_and(|(task, _)| !task.is_finished())
+    {
+      return;
+    }
+
+    info!(
+      "Ringing bells for event {} in channel {channel_id}",
+      bells.event_id
+    );
+    let event_id = bells.event_id;
+    let sound = BellSound::from_key(&bells.sound).unwrap_or(BellSound::Bowl);
+    let task = tokio::spawn(async move {
+      if let Err(e) = ring_sit(manager, guild_id, channel_id, bells).await {
+        error!("Error ringing bells for event {event_id}: {e:?}");
+      }
+    });
+
+    sits.insert(event_id, (task, sound));
+  }
+
+  /// Stops ringing bells for a sit.
+  ///
+  /// When the sit ended normally and its ending bells haven't been rung yet, they are rung before leaving the voice channel. Otherwise the bot leaves right away.
+  pub async fn stop(
+    &self,
+    ctx: &serenity::Context,
+    guild_id: serenity::GuildId,
+    event_id: serenity::ScheduledEventId,
+    ring_out_first: bool,
+  ) -> Result<()> {
+    let Some((task, sound)) = self.sits.lock().await.remove(&event_id) else {
+      return Ok(());
+    };
+
+    if task.is_finished() {
+      return Ok(());
+    }
+    task.abort();
+
+    let Some(manager) = songbird::get(ctx).await else {
+      return Ok(());
+    };
+
+    if ring_out_first {
+      if let Some(call) = manager.get(guild_id) {
+        tokio::spawn(async move {
+          if let Err(e) = ring_out(&manager, guild_id, &call, sound).await {
+            error!("Error ringing out event {event_id}: {e:?}");
+          }
+        });
+        return Ok(());
+      }
+    }
+
+    manager.remove(guild_id).await?;
+
+    Ok(())
+  }
+}
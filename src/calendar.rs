@@ -0,0 +1,159 @@
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate, Utc};
+use poise::serenity_prelude as serenity;
+
+/// Longest line allowed by the iCalendar format, in bytes. Longer lines are folded.
+const MAX_LINE_LENGTH: usize = 75;
+
+enum EventTime {
+  AllDay {
+    start: NaiveDate,
+    end: NaiveDate,
+  },
+  Timed {
+    start: chrono::DateTime<Utc>,
+    end: chrono::DateTime<Utc>,
+  },
+}
+
+struct CalendarEvent {
+  uid: String,
+  title: String,
+  description: Option<String>,
+  location: Option<String>,
+  time: EventTime,
+}
+
+/// Escapes text values as required by the iCalendar format.
+fn escape(text: &str) -> String {
+  text
+    .replace('\\', "\\\\")
+    .replace(';', "\\;")
+    .replace(',', "\\,")
+    .replace('\n', "\\n")
+}
+
+/// Folds a content line into lines of at most [`MAX_LINE_LENGTH`] bytes, without splitting characters.
+fn fold(line: &str) -> String {
+  let mut folded = String::new();
+  let mut length = 0;
+
+  for c in line.chars() {
+    if length + c.len_utf8() > MAX_LINE_LENGTH {
+      folded.push_str("\r\n ");
+      // The leading space of a continuation line counts towards its length
+      length = 1;
+    }
+    folded.push(c);
+    length += c.len_utf8();
+  }
+
+  folded.push_str("\r\n");
+  folded
+}
+
+fn render(name: &str, events: &[CalendarEvent]) -> String {
+  let now = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+  let mut lines = vec![
+    "BEGIN:VCALENDAR".to_string(),
+    "VERSION:2.0".to_string(),
+    "PRODID:-//Meditation Mind//Bloom//EN".to_string(),
+    "CALSCALE:GREGORIAN".to_string(),
+    format!("X-WR-CALNAME:{}", escape(name)),
+  ];
+
+  for event in events {
+    lines.push("BEGIN:VEVENT".to_string());
+    lines.push(format!("UID:{}", event.uid));
+    lines.push(format!("DTSTAMP:{now}"));
+    match event.time {
+      EventTime::AllDay { start, end } => {
+        lines.push(format!("DTSTART;VALUE=DATE:{}", start.format("%Y%m%d")));
+        lines.push(format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")));
+      }
+      EventTime::Timed { start, end } => {
+        lines.push(format!("DTSTART:{}", start.format("%Y%m%dT%H%M%SZ")));
+        lines.push(format!("DTEND:{}", end.format("%Y%m%dT%H%M%SZ")));
+      }
+    }
+    lines.push(format!("SUMMARY:{}", escape(&event.title)));
+    if let Some(description) = &event.description {
+      lines.push(format!("DESCRIPTION:{}", escape(description)));
+    }
+    if let Some(location) = &event.location {
+      lines.push(format!("LOCATION:{}", escape(location)));
+    }
+    lines.push("END:VEVENT".to_string());
+  }
+
+  lines.push("END:VCALENDAR".to_string());
+
+  lines.iter().map(|line| fold(line)).collect()
+}
+
+/// Monthly challenges for the current and next month, as all-day events spanning the month.
+fn challenge_events(guild_id: serenity::GuildId) -> Vec<CalendarEvent> {
+  let today = Utc::now().date_naive();
+  let mut events = Vec::new();
+  let mut month_start = today.with_day(1).unwrap_or(today);
+
+  for _ in 0..2 {
+    let Some(next_month_start) = month_start.checked_add_months(chrono::Months::new(1)) else {
+      break;
+    };
+
+    events.push(CalendarEvent {
+      uid: format!("challenge-{}@{guild_id}.bloom", month_start.format("%Y-%m")),
+      title: format!("Monthly Meditation Challenge: {}", month_start.format("%B %Y")),
+      description: Some("Meditate at least 30 minutes over 8 or more sessions this month for a chance to be picked as the challenge winner. Join with /challenge.".to_string()),
+      location: None,
+      time: EventTime::AllDay {
+        start: month_start,
+        end: next_month_start,
+      },
+    });
+
+    month_start = next_month_start;
+  }
+
+  events
+}
+
+/// Builds an iCalendar feed of the guild's upcoming scheduled events and monthly challenges.
+///
+/// Scheduled events without an end time are assumed to last an hour.
+pub async fn guild_calendar(http: &serenity::Http, guild_id: serenity::GuildId) -> Result<String> {
+  let guild = guild_id.to_partial_guild(http).await?;
+  let scheduled_events = guild_id.scheduled_events(http, false).await?;
+
+  let mut events: Vec<CalendarEvent> = scheduled_events
+    .into_iter()
+    .filter_map(|event| {
+      let start = chrono::DateTime::from_timestamp(event.start_time.unix_timestamp(), 0)?;
+      let end = event
+        .end_time
+        .and_then(|end_time| chrono::DateTime::from_timestamp(end_time.unix_timestamp(), 0))
+        .unwrap_or(start + chrono::Duration::hours(1));
+
+      // Events in a voice or stage channel link to the channel, since there is no physical location
+      let location = event.metadata.and_then(|metadata| metadata.location).or(
+        event
+          .channel_id
+          .map(|channel_id| format!("https://discord.com/channels/{guild_id}/{channel_id}")),
+      );
+
+      Some(CalendarEvent {
+        uid: format!("{}@{guild_id}.bloom", event.id),
+        title: event.name,
+        description: event.description,
+        location,
+        time: EventTime::Timed { start, end },
+      })
+    })
+    .collect();
+
+  events.extend(challenge_events(guild_id));
+
+  Ok(render(&guild.name, &events))
+}
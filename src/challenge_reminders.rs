@@ -0,0 +1,115 @@
+use crate::commands::challenge::{goal_percent, ChallengeChoices};
+use crate::config::{CHALLENGE_GOAL_MINUTES, CHALLENGE_GOAL_SESSIONS, CHALLENGE_REMINDER_DAY};
+use crate::database::DatabaseHandler;
+use crate::notifications::{self, Delivered, Delivery, Notification};
+use anyhow::Result;
+use chrono::{Datelike, Utc};
+use log::{error, info};
+use poise::serenity_prelude as serenity;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often challenge reminders are checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Days after the reminder day that reminders can still be sent, such as after a restart. Later than this, the month is skipped.
+const MAX_DELAY_DAYS: u32 = 2;
+
+/// Sends monthly challenge participants who opted in a progress reminder in the middle of the month.
+///
+/// Runs until the bot shuts down.
+pub async fn send_challenge_reminders(http: Arc<serenity::Http>, db: DatabaseHandler) {
+  let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(e) = send_due_reminders(&http, &db).await {
+      error!("Error while sending challenge reminders: {e:?}");
+    }
+  }
+}
+
+async fn send_due_reminders(http: &serenity::Http, db: &DatabaseHandler) -> Result<()> {
+  let now = Utc::now();
+  if !(CHALLENGE_REMINDER_DAY..=CHALLENGE_REMINDER_DAY + MAX_DELAY_DAYS).contains(&now.day()) {
+    return Ok(());
+  }
+
+  let Some(month_start) = now
+    .date_naive()
+    .with_day(1)
+    .and_then(|date| date.and_hms_opt(0, 0, 0))
+    .map(|start| start.and_utc())
+  else {
+    return Ok(());
+  };
+
+  let challenge = ChallengeChoices::Monthly.key();
+
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let participants =
+    DatabaseHandler::get_challenge_reminders_due(&mut transaction, challenge, month_start).await?;
+  drop(transaction);
+
+  for participant in participants {
+    // Marked as reminded first, so a failing reminder isn't retried every hour
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    DatabaseHandler::mark_challenge_reminded(
+      &mut transaction,
+      &participant.guild_id,
+      &participant.user_id,
+      challenge,
+      now,
+    )
+    .await?;
+    let limits =
+      DatabaseHandler::get_session_limits(&mut transaction, &participant.guild_id).await?;
+    let progress = DatabaseHandler::get_challenge_progress(
+      &mut transaction,
+      &participant.guild_id,
+      Some(&participant.user_id),
+      challenge,
+      month_start,
+      now,
+      limits.daily_cap_minutes,
+    )
+    .await?;
+    DatabaseHandler::commit_transaction(transaction).await?;
+
+    let Some(progress) = progress.first() else {
+      continue;
+    };
+
+    let percent = goal_percent(progress);
+    let description = if percent >= 100 {
+      format!(
+        "You've already reached this month's challenge goal, with {} minutes over {} sessions. Keep it up!",
+        progress.minutes, progress.sessions
+      )
+    } else {
+      format!(
+        "You're {percent}% of the way to this month's challenge goal of {CHALLENGE_GOAL_MINUTES} minutes over {CHALLENGE_GOAL_SESSIONS} sessions. So far, you have {} minutes over {} sessions. There's still plenty of time!",
+        progress.minutes, progress.sessions
+      )
+    };
+
+    let notification = Notification {
+      title: "Monthly Challenge Check-In",
+      description: &description,
+      footer: Some("To stop these reminders, use /challenge join with reminders set to False."),
+      ..Default::default()
+    };
+
+    // Reminders are best effort, since the participant may have closed their DMs
+    if notifications::deliver(http, db, participant.user_id, Delivery::Dm, &notification).await?
+      == Delivered::Nowhere
+    {
+      info!(
+        "Could not send challenge reminder to {}",
+        participant.user_id
+      );
+    }
+  }
+
+  Ok(())
+}
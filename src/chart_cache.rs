@@ -0,0 +1,79 @@
+use anyhow::Result;
+use log::{error, info};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How long a rendered chart is reused for identical requests.
+const CHART_CACHE_TTL: Duration = Duration::from_secs(60 * 5);
+/// How often expired charts are removed from disk.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 15);
+
+fn cache_dir() -> PathBuf {
+  std::env::temp_dir().join("bloombot-chart-cache")
+}
+
+fn cache_path(key: u64) -> PathBuf {
+  cache_dir().join(format!("{key:016x}"))
+}
+
+fn is_fresh(path: &Path) -> bool {
+  std::fs::metadata(path)
+    .and_then(|metadata| metadata.modified())
+    .ok()
+    .and_then(|modified| modified.elapsed().ok())
+    .is_some_and(|age| age < CHART_CACHE_TTL)
+}
+
+/// Path of the cached chart for the key, if one was rendered recently.
+pub fn lookup(key: u64) -> Option<PathBuf> {
+  let path = cache_path(key);
+  is_fresh(&path).then_some(path)
+}
+
+/// Keeps a copy of a rendered chart for the key.
+///
+/// Never fails, since the cache only saves work; errors are logged and the chart is rendered again next time.
+pub fn store(key: u64, chart: &Path) {
+  let result = std::fs::create_dir_all(cache_dir())
+    .and_then(|()| std::fs::copy(chart, cache_path(key)).map(|_| ()));
+
+  if let Err(e) = result {
+    error!("Could not cache chart: {e}");
+  }
+}
+
+/// Removes expired charts from the cache every 15 minutes.
+///
+/// Runs until the bot shuts down.
+pub async fn clean_chart_cache() {
+  let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    match remove_expired() {
+      Ok(0) => {}
+      Ok(removed) => info!("Removed {removed} expired charts from the cache"),
+      Err(e) => error!("Error while cleaning the chart cache: {e:?}"),
+    }
+  }
+}
+
+fn remove_expired() -> Result<usize> {
+  let entries = match std::fs::read_dir(cache_dir()) {
+    Ok(entries) => entries,
+    // Nothing has been cached yet
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+    Err(e) => return Err(e.into()),
+  };
+
+  let mut removed = 0;
+  for entry in entries {
+    let path = entry?.path();
+    if !is_fresh(&path) && std::fs::remove_file(&path).is_ok() {
+      removed += 1;
+    }
+  }
+
+  Ok(removed)
+}
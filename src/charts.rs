@@ -1,11 +1,19 @@
 #![allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
 
+use crate::chart_cache;
 use crate::commands::stats::StatsType;
-use crate::database::{Timeframe, TimeframeStats};
+use crate::config::ChartPalette;
+use crate::database::{GrowthMonth, MoodWeek, Timeframe, TimeframeStats};
 use anyhow::{Context, Result};
 use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use tempfile::NamedTempFile;
+use tokio::sync::Semaphore;
 
 pub struct Chart {
   file: NamedTempFile,
@@ -13,6 +21,249 @@ pub struct Chart {
 
 pub struct ChartDrawer {
   file: NamedTempFile,
+  locale: String,
+  format: ChartFormat,
+  quality: u8,
+}
+
+/// Image format charts are sent in, set with the `CHART_FORMAT` environment variable.
+#[derive(Debug, Clone, Copy, Hash)]
+enum ChartFormat {
+  Png,
+  Jpeg,
+  /// Lossless, since only lossless WebP can be encoded without native libraries.
+  WebP,
+}
+
+impl ChartFormat {
+  /// Reads the format from the `CHART_FORMAT` environment variable (`png`, `jpeg`, or `webp`), defaulting to PNG.
+  fn from_env() -> Self {
+    match std::env::var("CHART_FORMAT").as_deref() {
+      Ok("jpeg" | "jpg") => Self::Jpeg,
+      Ok("webp") => Self::WebP,
+      _ => Self::Png,
+    }
+  }
+
+  fn extension(self) -> &'static str {
+    match self {
+      Self::Png => ".png",
+      Self::Jpeg => ".jpg",
+      Self::WebP => ".webp",
+    }
+  }
+}
+
+/// Largest chart file sent, well under Discord's attachment limit so charts upload quickly.
+const CHART_SIZE_BUDGET: usize = 1024 * 1024;
+/// Tallest chart sent, so long leaderboards are scaled down to stay readable in Discord's preview.
+const MAX_CHART_HEIGHT: u32 = 1600;
+/// Narrowest a chart is scaled down to while trying to fit the size budget.
+const MIN_CHART_WIDTH: u32 = 320;
+/// JPEG quality used when `CHART_QUALITY` (1 to 100) isn't set.
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+/// Lowest JPEG quality used while trying to fit the size budget, before the chart is scaled down instead.
+const MIN_JPEG_QUALITY: u8 = 50;
+
+/// A ranked member on a leaderboard chart, with their avatar image if it could be downloaded.
+#[derive(Clone)]
+pub struct LeaderboardBar {
+  pub name: String,
+  pub value: f64,
+  pub avatar: Option<Arc<Vec<u8>>>,
+}
+
+/// Height of the title above the rows of a leaderboard chart.
+const LEADERBOARD_HEADER_HEIGHT: u32 = 70;
+/// Height of each row of a leaderboard chart.
+const LEADERBOARD_ROW_HEIGHT: u32 = 44;
+/// Size of the avatars on a leaderboard chart.
+const LEADERBOARD_AVATAR_SIZE: u32 = 32;
+/// Width of the names on a leaderboard chart, left of the bars.
+const LEADERBOARD_NAME_WIDTH: u32 = 170;
+
+/// Most charts rendered at once. Rendering is CPU-bound, so running more at a time would only slow every chart down.
+const MAX_CONCURRENT_RENDERS: usize = 2;
+
+static RENDER_PERMITS: Semaphore = Semaphore::const_new(MAX_CONCURRENT_RENDERS);
+static QUEUED_RENDERS: AtomicUsize = AtomicUsize::new(0);
+static ACTIVE_RENDERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts a render as queued or active until it is dropped, so renders whose command gave up aren't counted.
+struct RenderCount(&'static AtomicUsize);
+
+impl RenderCount {
+  fn new(counter: &'static AtomicUsize) -> Self {
+    counter.fetch_add(1, Ordering::Relaxed);
+    Self(counter)
+  }
+}
+
+impl Drop for RenderCount {
+  fn drop(&mut self) {
+    self.0.fetch_sub(1, Ordering::Relaxed);
+  }
+}
+
+/// Number of charts waiting to be rendered, and number being rendered.
+pub fn render_queue_stats() -> (usize, usize) {
+  (
+    QUEUED_RENDERS.load(Ordering::Relaxed),
+    ACTIVE_RENDERS.load(Ordering::Relaxed),
+  )
+}
+
+/// Runs a chart render on the blocking thread pool, waiting for a free slot first, so bursts of chart commands can't starve the gateway and command tasks.
+async fn render(job: impl FnOnce() -> Result<Chart> + Send + 'static) -> Result<Chart> {
+  let queued = RenderCount::new(&QUEUED_RENDERS);
+  let _permit = RENDER_PERMITS.acquire().await?;
+  drop(queued);
+
+  let _active = RenderCount::new(&ACTIVE_RENDERS);
+  tokio::task::spawn_blocking(job).await?
+}
+
+/// Locale used for numbers when the user's locale isn't known, such as in background jobs and text commands.
+const DEFAULT_LOCALE: &str = "en-US";
+
+/// Characters shown for a bar label before it is shortened, so labels don't overlap.
+const MAX_LABEL_CHARS: usize = 14;
+
+/// Thousands and decimal separators for a Discord locale, such as `de` or `en-US`.
+fn separators(locale: &str) -> (char, char) {
+  match locale {
+    "da" | "de" | "el" | "es-ES" | "hr" | "id" | "it" | "nl" | "pt-BR" | "ro" | "tr" | "vi" => {
+      ('.', ',')
+    }
+    "bg" | "cs" | "fi" | "fr" | "hu" | "lt" | "no" | "pl" | "ru" | "sv-SE" | "uk" => {
+      ('\u{a0}', ',')
+    }
+    _ => (',', '.'),
+  }
+}
+
+/// Formats a whole number with the locale's thousands separator, such as 12,345 or 12.345. Unknown locales use the default.
+pub fn format_number(value: impl std::fmt::Display, locale: Option<&str>) -> String {
+  let (group, _) = separators(locale.unwrap_or(DEFAULT_LOCALE));
+  let formatted = value.to_string();
+  let (sign, digits) = formatted
+    .strip_prefix('-')
+    .map_or(("", formatted.as_str()), |digits| ("-", digits));
+
+  let mut number = sign.to_string();
+  for (i, digit) in digits.chars().enumerate() {
+    if i > 0 && (digits.len() - i) % 3 == 0 {
+      number.push(group);
+    }
+    number.push(digit);
+  }
+
+  number
+}
+
+/// Encodes a chart in the format. Charts have no transparency, so they are encoded as RGB.
+fn encode_chart(image: &image::DynamicImage, format: ChartFormat, quality: u8) -> Result<Vec<u8>> {
+  use image::ImageEncoder;
+
+  let rgb = image.to_rgb8();
+  let mut encoded = Vec::new();
+
+  match format {
+    ChartFormat::Png => image::codecs::png::PngEncoder::new_with_quality(
+      &mut encoded,
+      image::codecs::png::CompressionType::Best,
+      image::codecs::png::FilterType::Adaptive,
+    )
+    .write_image(
+      rgb.as_raw(),
+      rgb.width(),
+      rgb.height(),
+      image::ColorType::Rgb8,
+    )?,
+    ChartFormat::Jpeg => image::codecs::jpeg::JpegEncoder::new_with_quality(&mut encoded, quality)
+      .write_image(
+        rgb.as_raw(),
+        rgb.width(),
+        rgb.height(),
+        image::ColorType::Rgb8,
+      )?,
+    ChartFormat::WebP => image::codecs::webp::WebPEncoder::new_lossless(&mut encoded).write_image(
+      rgb.as_raw(),
+      rgb.width(),
+      rgb.height(),
+      image::ColorType::Rgb8,
+    )?,
+  }
+
+  Ok(encoded)
+}
+
+/// Decodes an avatar and crops it to a circle, painting the corners with the background. Returns `None` if the image can't be decoded.
+fn avatar_bitmap(bytes: &[u8], size: u32, background: RGBColor) -> Option<image::DynamicImage> {
+  let mut avatar = image::load_from_memory(bytes)
+    .ok()?
+    .resize_exact(size, size, image::imageops::FilterType::Triangle)
+    .to_rgb8();
+
+  // Bitmaps can't be clipped, so pixels outside the circle are painted with the background instead
+  let radius = size as i32 / 2;
+  for (x, y, pixel) in avatar.enumerate_pixels_mut() {
+    let dx = x as i32 - radius;
+    let dy = y as i32 - radius;
+    if dx * dx + dy * dy > radius * radius {
+      *pixel = image::Rgb([background.0, background.1, background.2]);
+    }
+  }
+
+  Some(image::DynamicImage::ImageRgb8(avatar))
+}
+
+/// Shortens an axis value with K, M, or B, using the locale's decimal separator.
+fn axis_label(value: f64, locale: &str) -> String {
+  let mut index: usize = 0;
+  let base: f64 = 1000.0;
+  let mut value = value;
+
+  loop {
+    if value < base {
+      break;
+    }
+
+    value /= base;
+    index += 1;
+  }
+
+  let unit = match index {
+    1 => "K",
+    2 => "M",
+    3 => "B",
+    _ => "",
+  };
+
+  let (_, decimal) = separators(locale);
+
+  format!("{value}{unit}").replace('.', &decimal.to_string())
+}
+
+/// Label for a bar, shortened with an ellipsis if it is too long. Names are otherwise kept as they are, since the chart font covers most scripts.
+fn bar_label(label: &str) -> String {
+  if label.chars().count() > MAX_LABEL_CHARS {
+    let shortened = label.chars().take(MAX_LABEL_CHARS - 1).collect::<String>();
+    format!("{}…", shortened.trim_end())
+  } else {
+    label.to_string()
+  }
+}
+
+/// Bar color for the palette on a light or dark background. The colorblind-safe colors are from the Okabe-Ito palette.
+pub fn bar_color(palette: ChartPalette, light_mode: bool) -> (u8, u8, u8, f64) {
+  match (palette, light_mode) {
+    (ChartPalette::Default, _) => (253, 172, 46, 1.0),
+    (ChartPalette::HighContrast, false) => (255, 221, 0, 1.0),
+    (ChartPalette::HighContrast, true) => (0, 56, 168, 1.0),
+    (ChartPalette::ColorblindSafe, false) => (86, 180, 233, 1.0),
+    (ChartPalette::ColorblindSafe, true) => (0, 114, 178, 1.0),
+  }
 }
 
 fn next_largest_factor(x: u32) -> u32 {
@@ -26,6 +277,90 @@ fn next_largest_factor(x: u32) -> u32 {
   (quotient + 1) * factor
 }
 
+/// Date label for a position on the x axis. Bars for the 12 buckets are drawn at positions 1 to 12.
+fn bucket_label(timeframe: &Timeframe, now: chrono::DateTime<chrono::Utc>, x: i64) -> String {
+  match timeframe {
+    Timeframe::Daily => {
+      let date = now - chrono::Duration::days(12 - x);
+      date.format("%m/%d").to_string()
+    }
+    Timeframe::Weekly => {
+      let date = now - chrono::Duration::weeks(12 - x);
+      date.format("%m/%d").to_string()
+    }
+    Timeframe::Monthly => {
+      let date = now - chrono::Duration::days((12 * 30) - (x * 30));
+      date.format("%y/%m").to_string()
+    }
+    Timeframe::Yearly => {
+      let date = now - chrono::Duration::days((12 * 365) - (x * 365));
+      date.format("%Y").to_string()
+    }
+  }
+}
+
+/// Builds the rows shown in a chart as text, with the same date labels as the chart and an optional trend column.
+pub fn chart_rows(
+  stats: &[TimeframeStats],
+  timeframe: &Timeframe,
+  stats_type: &StatsType,
+  trend: Option<&[f64]>,
+) -> Vec<Vec<String>> {
+  let now = chrono::Utc::now();
+
+  stats
+    .iter()
+    .enumerate()
+    .zip(1..)
+    .map(|((i, stat), x)| {
+      let value = match stats_type {
+        StatsType::MeditationMinutes => stat.sum.unwrap_or(0),
+        StatsType::MeditationCount => stat.count.unwrap_or(0),
+      };
+      let mut row = vec![bucket_label(timeframe, now, x), value.to_string()];
+      if let Some(trend) = trend {
+        row.push(format!("{:.1}", trend.get(i).copied().unwrap_or_default()));
+      }
+      row
+    })
+    .collect()
+}
+
+/// Formats rows as a plain text table in a code block, so chart data can be read without the image.
+pub fn render_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+  let widths: Vec<usize> = headers
+    .iter()
+    .enumerate()
+    .map(|(column, header)| {
+      rows
+        .iter()
+        .filter_map(|row| row.get(column))
+        .map(|cell| cell.chars().count())
+        .chain(std::iter::once(header.chars().count()))
+        .max()
+        .unwrap_or_default()
+    })
+    .collect();
+
+  let format_row = |cells: Vec<&str>| {
+    cells
+      .iter()
+      .zip(&widths)
+      .map(|(cell, width)| format!("{cell:>width$}"))
+      .collect::<Vec<String>>()
+      .join("  ")
+  };
+
+  let mut lines = vec![format_row(headers.to_vec())];
+  lines.extend(
+    rows
+      .iter()
+      .map(|row| format_row(row.iter().map(String::as_str).collect())),
+  );
+
+  format!("```\n{}\n```", lines.join("\n"))
+}
+
 impl ChartDrawer {
   pub fn new() -> Result<Self> {
     // let file = NamedTempFile::new_in("/tmp").with_context(|| "Could not create new temporary file")?;
@@ -34,10 +369,81 @@ impl ChartDrawer {
       .suffix(".png")
       .tempfile()?;
 
-    Ok(Self { file })
+    let quality = std::env::var("CHART_QUALITY")
+      .ok()
+      .and_then(|quality| quality.parse::<u8>().ok())
+      .map_or(DEFAULT_JPEG_QUALITY, |quality| quality.clamp(1, 100));
+
+    Ok(Self {
+      file,
+      locale: DEFAULT_LOCALE.to_string(),
+      format: ChartFormat::from_env(),
+      quality,
+    })
   }
 
-  #[allow(clippy::unused_async)]
+  /// Encodes the drawn chart in the chosen format, lowering quality and then scaling it down until it fits the size budget.
+  ///
+  /// Charts are always drawn as PNG, so a PNG chart that already fits is sent as it is.
+  fn finish(self) -> Result<Chart> {
+    let path = self.file.path();
+    let (_, height) = image::image_dimensions(path)?;
+    let size = std::fs::metadata(path)?.len();
+    if matches!(self.format, ChartFormat::Png)
+      && height <= MAX_CHART_HEIGHT
+      && usize::try_from(size).is_ok_and(|size| size <= CHART_SIZE_BUDGET)
+    {
+      return Ok(Chart { file: self.file });
+    }
+
+    let mut image = image::open(path)?;
+    if image.height() > MAX_CHART_HEIGHT {
+      image = image.resize(
+        image.width() * MAX_CHART_HEIGHT / image.height(),
+        MAX_CHART_HEIGHT,
+        image::imageops::FilterType::Triangle,
+      );
+    }
+
+    let mut quality = self.quality;
+    let encoded = loop {
+      let encoded = encode_chart(&image, self.format, quality)?;
+      if encoded.len() <= CHART_SIZE_BUDGET || image.width() <= MIN_CHART_WIDTH {
+        break encoded;
+      }
+
+      if matches!(self.format, ChartFormat::Jpeg) && quality > MIN_JPEG_QUALITY {
+        quality = quality.saturating_sub(15).max(MIN_JPEG_QUALITY);
+      } else {
+        image = image.resize(
+          image.width() * 3 / 4,
+          image.height() * 3 / 4,
+          image::imageops::FilterType::Triangle,
+        );
+      }
+    };
+
+    let file = tempfile::Builder::new()
+      .prefix("attachment")
+      .suffix(self.format.extension())
+      .tempfile()?;
+    std::fs::write(file.path(), encoded).with_context(|| "Could not write chart")?;
+
+    Ok(Chart { file })
+  }
+
+  /// Formats numbers in the chart for the locale, such as `de` or `en-US`. Unknown locales use the default.
+  #[must_use]
+  pub fn locale(mut self, locale: Option<&str>) -> Self {
+    if let Some(locale) = locale {
+      self.locale = locale.to_string();
+    }
+    self
+  }
+
+  /// Draws a bar chart of the stats, with an optional trend line overlay given as a label and one value per bar.
+  ///
+  /// A chart drawn from the same stats and options in the past few minutes is reused instead of being drawn again.
   pub async fn draw(
     self,
     stats: &[TimeframeStats],
@@ -45,6 +451,69 @@ impl ChartDrawer {
     stats_type: &StatsType,
     bar_color: (u8, u8, u8, f64),
     light_mode: bool,
+    trend: Option<(&str, &[f64])>,
+  ) -> Result<Chart> {
+    // The key covers everything drawn, including today's date for the bar labels, so it changes whenever the chart would
+    let mut hasher = DefaultHasher::new();
+    stats.hash(&mut hasher);
+    timeframe.hash(&mut hasher);
+    stats_type.hash(&mut hasher);
+    (bar_color.0, bar_color.1, bar_color.2, bar_color.3.to_bits()).hash(&mut hasher);
+    light_mode.hash(&mut hasher);
+    if let Some((label, values)) = trend {
+      label.hash(&mut hasher);
+      for value in values {
+        value.to_bits().hash(&mut hasher);
+      }
+    }
+    self.locale.hash(&mut hasher);
+    self.format.hash(&mut hasher);
+    self.quality.hash(&mut hasher);
+    chrono::Utc::now().date_naive().hash(&mut hasher);
+    let key = hasher.finish();
+
+    if let Some(cached) = chart_cache::lookup(key) {
+      let file = tempfile::Builder::new()
+        .prefix("attachment")
+        .suffix(self.format.extension())
+        .tempfile()?;
+      if std::fs::copy(cached, file.path()).is_ok() {
+        return Ok(Chart { file });
+      }
+    }
+
+    let stats = stats.to_vec();
+    let timeframe = *timeframe;
+    let stats_type = stats_type.clone();
+    let trend = trend.map(|(label, values)| (label.to_string(), values.to_vec()));
+
+    let chart = render(move || {
+      self.render_stats(
+        &stats,
+        &timeframe,
+        &stats_type,
+        bar_color,
+        light_mode,
+        trend
+          .as_ref()
+          .map(|(label, values)| (label.as_str(), values.as_slice())),
+      )
+    })
+    .await?;
+
+    chart_cache::store(key, &chart.get_file_path());
+
+    Ok(chart)
+  }
+
+  fn render_stats(
+    self,
+    stats: &[TimeframeStats],
+    timeframe: &Timeframe,
+    stats_type: &StatsType,
+    bar_color: (u8, u8, u8, f64),
+    light_mode: bool,
+    trend: Option<(&str, &[f64])>,
   ) -> Result<Chart> {
     let path = self.file.path().to_path_buf();
 
@@ -61,24 +530,25 @@ impl ChartDrawer {
       StatsType::MeditationCount => String::from("# of Sessions"),
     };
 
-    let upper_bound = match stats_type {
-      StatsType::MeditationMinutes => {
-        let largest = stats.iter().map(|x| x.sum.unwrap()).max().unwrap();
-        next_largest_factor(largest as u32)
-      }
-      StatsType::MeditationCount => {
-        let largest = stats.iter().map(|x| x.count).max().unwrap();
-        next_largest_factor(largest.unwrap() as u32)
-      }
+    let largest = match stats_type {
+      StatsType::MeditationMinutes => stats.iter().map(|x| x.sum.unwrap()).max().unwrap(),
+      StatsType::MeditationCount => stats.iter().map(|x| x.count).max().unwrap().unwrap(),
     };
 
+    // The trend line can exceed the bars when earlier days in the window were higher
+    let largest_trend = trend
+      .map(|(_, values)| values.iter().copied().fold(0.0, f64::max))
+      .unwrap_or_default();
+
+    let upper_bound = next_largest_factor((largest as u32).max(largest_trend.ceil() as u32));
+
     let mut chart = ChartBuilder::on(&root)
       .caption(header, ("sans-serif", 35).into_font().color(text_color))
       .margin(15)
       .margin_right(45)
       .x_label_area_size(45)
       .y_label_area_size(50)
-      .build_cartesian_2d(0u32..13u32, 0u32..upper_bound)
+      .build_cartesian_2d(0u32..13u32, 0f64..f64::from(upper_bound))
       .with_context(|| "Could not build chart")?;
 
     let now = chrono::Utc::now();
@@ -90,53 +560,8 @@ impl ChartDrawer {
       .bold_line_style(text_color.mix(0.2))
       .x_label_style(("sans-serif", 25).into_font().color(text_color))
       .y_label_style(("sans-serif", 25).into_font().color(text_color))
-      .x_label_formatter(&|x| {
-        // Dates
-        let x: i64 = <i64>::from(*x);
-        match timeframe {
-          Timeframe::Daily => {
-            let date = now - chrono::Duration::days(12 - x);
-            date.format("%m/%d").to_string()
-          }
-          Timeframe::Weekly => {
-            let date = now - chrono::Duration::weeks(12 - x);
-            date.format("%m/%d").to_string()
-          }
-          Timeframe::Monthly => {
-            let date = now - chrono::Duration::days((12 * 30) - (x * 30));
-            date.format("%y/%m").to_string()
-          }
-          Timeframe::Yearly => {
-            let date = now - chrono::Duration::days((12 * 365) - (x * 365));
-            date.format("%Y").to_string()
-          }
-        }
-      })
-      .y_label_formatter(&|y| {
-        let mut index: usize = 0;
-        let base: f64 = 1000.0;
-        let mut value: f64 = <f64>::from(*y);
-
-        loop {
-          if value < base {
-            break;
-          }
-
-          value /= base;
-          index += 1;
-        }
-
-        let unit = match index {
-          1 => "K",
-          2 => "M",
-          3 => "B",
-          _ => "",
-        };
-
-        let y_label = format!("{value}{unit}");
-
-        y_label
-      })
+      .x_label_formatter(&|x| bucket_label(timeframe, now, <i64>::from(*x)))
+      .y_label_formatter(&|y| axis_label(*y, &self.locale))
       .draw()?;
 
     let shape_color = ShapeStyle {
@@ -164,16 +589,584 @@ impl ChartDrawer {
 
     chart.draw_series((0..12).map(|x: u32| {
       let height = stats.get(x as usize).unwrap();
-      let mut rect = Rectangle::new([(x + 1, 0), (x + 1, *height)], shape_color.filled());
+      let mut rect = Rectangle::new(
+        [(x + 1, 0.0), (x + 1, f64::from(*height))],
+        shape_color.filled(),
+      );
 
       rect.set_margin(0, 0, 15, 15);
 
       rect
     }))?;
 
+    if let Some((label, values)) = trend {
+      let line_style = text_color.stroke_width(3);
+
+      chart
+        .draw_series(LineSeries::new(
+          (0..12u32)
+            .zip(values.iter().copied())
+            .map(|(x, y)| (x + 1, y)),
+          line_style,
+        ))?
+        .label(label)
+        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], line_style));
+
+      chart
+        .configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .background_style(background_color.mix(0.8))
+        .border_style(text_color)
+        .label_font(("sans-serif", 20).into_font().color(text_color))
+        .draw()?;
+    }
+
+    root.present().with_context(|| "Could not present chart")?;
+
+    self.finish()
+  }
+
+  /// Draws a bar chart comparing one value per labeled group, such as the average minutes of members with each role.
+  pub async fn draw_comparison(
+    self,
+    header: &str,
+    bars: &[(String, f64)],
+    bar_color: (u8, u8, u8, f64),
+    light_mode: bool,
+  ) -> Result<Chart> {
+    let header = header.to_string();
+    let bars = bars.to_vec();
+
+    render(move || self.render_comparison(&header, &bars, bar_color, light_mode)).await
+  }
+
+  fn render_comparison(
+    self,
+    header: &str,
+    bars: &[(String, f64)],
+    bar_color: (u8, u8, u8, f64),
+    light_mode: bool,
+  ) -> Result<Chart> {
+    let path = self.file.path().to_path_buf();
+
+    let text_color = if light_mode { &BLACK } else { &WHITE };
+
+    let background_color = if light_mode { &WHITE } else { &BLACK };
+
+    let root = BitMapBackend::new(&path, (640, 480)).into_drawing_area();
+    root.fill(background_color).unwrap();
+
+    let largest = bars.iter().map(|(_, value)| *value).fold(0.0, f64::max);
+    let upper_bound = next_largest_factor(largest.ceil() as u32);
+    let count = bars.len() as u32;
+
+    let mut chart = ChartBuilder::on(&root)
+      .caption(header, ("sans-serif", 35).into_font().color(text_color))
+      .margin(15)
+      .margin_right(45)
+      .x_label_area_size(45)
+      .y_label_area_size(50)
+      .build_cartesian_2d(0u32..count + 1, 0f64..f64::from(upper_bound))
+      .with_context(|| "Could not build chart")?;
+
+    chart
+      .configure_mesh()
+      .axis_style(text_color)
+      .light_line_style(text_color.mix(0.1))
+      .bold_line_style(text_color.mix(0.2))
+      .x_labels(bars.len() + 2)
+      .x_label_style(("sans-serif", 20).into_font().color(text_color))
+      .y_label_style(("sans-serif", 25).into_font().color(text_color))
+      .x_label_formatter(&|x| {
+        x.checked_sub(1)
+          .and_then(|index| bars.get(index as usize))
+          .map(|(label, _)| bar_label(label))
+          .unwrap_or_default()
+      })
+      .y_label_formatter(&|y| axis_label(*y, &self.locale))
+      .draw()?;
+
+    let shape_color = ShapeStyle {
+      color: RGBAColor(bar_color.0, bar_color.1, bar_color.2, bar_color.3),
+      filled: true,
+      stroke_width: 1,
+    };
+
+    chart.draw_series(bars.iter().zip(1u32..).map(|((_, value), x)| {
+      let mut rect = Rectangle::new([(x, 0.0), (x, *value)], shape_color.filled());
+
+      rect.set_margin(0, 0, 30, 30);
+
+      rect
+    }))?;
+
+    root.present().with_context(|| "Could not present chart")?;
+
+    self.finish()
+  }
+
+  /// Draws a ranked leaderboard as horizontal bars, with each member's avatar and name next to their bar and the value after it.
+  ///
+  /// Members whose avatar is missing or can't be decoded get a circle with their initial instead.
+  pub async fn draw_leaderboard(
+    self,
+    header: &str,
+    bars: &[LeaderboardBar],
+    bar_color: (u8, u8, u8, f64),
+    light_mode: bool,
+  ) -> Result<Chart> {
+    let header = header.to_string();
+    let bars = bars.to_vec();
+
+    render(move || self.render_leaderboard(&header, &bars, bar_color, light_mode)).await
+  }
+
+  #[allow(clippy::cast_possible_wrap, clippy::cast_precision_loss)]
+  fn render_leaderboard(
+    self,
+    header: &str,
+    bars: &[LeaderboardBar],
+    bar_color: (u8, u8, u8, f64),
+    light_mode: bool,
+  ) -> Result<Chart> {
+    let path = self.file.path().to_path_buf();
+
+    let text_color = if light_mode { &BLACK } else { &WHITE };
+
+    let background_color = if light_mode { &WHITE } else { &BLACK };
+
+    let width = 640;
+    let height = LEADERBOARD_HEADER_HEIGHT + bars.len() as u32 * LEADERBOARD_ROW_HEIGHT + 15;
+
+    let root = BitMapBackend::new(&path, (width, height)).into_drawing_area();
+    root.fill(background_color).unwrap();
+
+    root.draw(&Text::new(
+      header.to_string(),
+      (width as i32 / 2, LEADERBOARD_HEADER_HEIGHT as i32 / 2),
+      ("sans-serif", 35)
+        .into_font()
+        .color(text_color)
+        .pos(Pos::new(HPos::Center, VPos::Center)),
+    ))?;
+
+    let bar_fill = RGBAColor(bar_color.0, bar_color.1, bar_color.2, bar_color.3).filled();
+    let largest = bars.iter().map(|bar| bar.value).fold(0.0, f64::max);
+
+    let avatar_left = 15;
+    let name_left = avatar_left + LEADERBOARD_AVATAR_SIZE as i32 + 10;
+    let bar_left = name_left + LEADERBOARD_NAME_WIDTH as i32;
+    // Room is left after the longest bar for its value
+    let bar_max_width = f64::from(width as i32 - bar_left - 90);
+
+    for (bar, row) in bars.iter().zip(0u32..) {
+      let top = (LEADERBOARD_HEADER_HEIGHT + row * LEADERBOARD_ROW_HEIGHT) as i32;
+      let center_y = top + LEADERBOARD_ROW_HEIGHT as i32 / 2;
+      let avatar_top = center_y - LEADERBOARD_AVATAR_SIZE as i32 / 2;
+
+      match bar
+        .avatar
+        .as_deref()
+        .and_then(|bytes| avatar_bitmap(bytes, LEADERBOARD_AVATAR_SIZE, *background_color))
+      {
+        Some(avatar) => {
+          root.draw(&BitMapElement::from(((avatar_left, avatar_top), avatar)))?;
+        }
+        None => {
+          let radius = LEADERBOARD_AVATAR_SIZE as i32 / 2;
+          let avatar_center = (avatar_left + radius, center_y);
+          let initial = bar
+            .name
+            .chars()
+            .next()
+            .map(|initial| initial.to_uppercase().to_string())
+            .unwrap_or_default();
+
+          root.draw(&Circle::new(avatar_center, radius, bar_fill))?;
+          root.draw(&Text::new(
+            initial,
+            avatar_center,
+            ("sans-serif", 20)
+              .into_font()
+              .color(background_color)
+              .pos(Pos::new(HPos::Center, VPos::Center)),
+          ))?;
+        }
+      }
+
+      root.draw(&Text::new(
+        bar_label(&bar.name),
+        (name_left, center_y),
+        ("sans-serif", 20)
+          .into_font()
+          .color(text_color)
+          .pos(Pos::new(HPos::Left, VPos::Center)),
+      ))?;
+
+      let bar_width = if largest > 0.0 {
+        (bar.value / largest * bar_max_width).round() as i32
+      } else {
+        0
+      };
+      root.draw(&Rectangle::new(
+        [
+          (bar_left, top + 10),
+          (
+            bar_left + bar_width,
+            top + LEADERBOARD_ROW_HEIGHT as i32 - 10,
+          ),
+        ],
+        bar_fill,
+      ))?;
+
+      root.draw(&Text::new(
+        format_number(bar.value.round() as i64, Some(&self.locale)),
+        (bar_left + bar_width + 8, center_y),
+        ("sans-serif", 20)
+          .into_font()
+          .color(text_color)
+          .pos(Pos::new(HPos::Left, VPos::Center)),
+      ))?;
+    }
+
+    root.present().with_context(|| "Could not present chart")?;
+
+    self.finish()
+  }
+
+  /// Draws weekly meditation minutes as bars, with the average mood score as a line on a second axis. Weeks with fewer than `min_responses` mood check-ins have no mood shown.
+  pub async fn draw_mood_trend(
+    self,
+    weeks: &[MoodWeek],
+    min_responses: i64,
+    bar_color: (u8, u8, u8, f64),
+    light_mode: bool,
+  ) -> Result<Chart> {
+    let weeks = weeks.to_vec();
+
+    render(move || self.render_mood_trend(&weeks, min_responses, bar_color, light_mode)).await
+  }
+
+  #[allow(clippy::cast_precision_loss)]
+  fn render_mood_trend(
+    self,
+    weeks: &[MoodWeek],
+    min_responses: i64,
+    bar_color: (u8, u8, u8, f64),
+    light_mode: bool,
+  ) -> Result<Chart> {
+    let path = self.file.path().to_path_buf();
+
+    let text_color = if light_mode { &BLACK } else { &WHITE };
+
+    let background_color = if light_mode { &WHITE } else { &BLACK };
+
+    let root = BitMapBackend::new(&path, (640, 480)).into_drawing_area();
+    root.fill(background_color).unwrap();
+
+    let largest = weeks.iter().map(|week| week.minutes).max().unwrap_or(0);
+    let upper_bound = next_largest_factor(largest as u32);
+    let count = weeks.len() as u32;
+
+    let mut chart = ChartBuilder::on(&root)
+      .caption(
+        "Minutes and Mood by Week",
+        ("sans-serif", 35).into_font().color(text_color),
+      )
+      .margin(15)
+      .x_label_area_size(45)
+      .y_label_area_size(50)
+      .right_y_label_area_size(40)
+      .build_cartesian_2d(0u32..count + 1, 0f64..f64::from(upper_bound))
+      .with_context(|| "Could not build chart")?
+      .set_secondary_coord(0u32..count + 1, 0f64..5f64);
+
+    chart
+      .configure_mesh()
+      .axis_style(text_color)
+      .light_line_style(text_color.mix(0.1))
+      .bold_line_style(text_color.mix(0.2))
+      .x_label_style(("sans-serif", 20).into_font().color(text_color))
+      .y_label_style(("sans-serif", 25).into_font().color(text_color))
+      .x_label_formatter(&|x| {
+        x.checked_sub(1)
+          .and_then(|index| weeks.get(index as usize))
+          .map(|week| week.week_start.format("%m/%d").to_string())
+          .unwrap_or_default()
+      })
+      .y_label_formatter(&|y| axis_label(*y, &self.locale))
+      .draw()?;
+
+    chart
+      .configure_secondary_axes()
+      .axis_style(text_color)
+      .y_labels(6)
+      .y_label_formatter(&|score| axis_label(*score, &self.locale))
+      .label_style(("sans-serif", 25).into_font().color(text_color))
+      .draw()?;
+
+    let shape_color = ShapeStyle {
+      color: RGBAColor(bar_color.0, bar_color.1, bar_color.2, bar_color.3),
+      filled: true,
+      stroke_width: 1,
+    };
+
+    chart
+      .draw_series(weeks.iter().zip(1u32..).map(|(week, x)| {
+        let mut rect = Rectangle::new([(x, 0.0), (x, week.minutes as f64)], shape_color.filled());
+
+        rect.set_margin(0, 0, 10, 10);
+
+        rect
+      }))?
+      .label("Minutes")
+      .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], shape_color.filled()));
+
+    // Too few responses could reveal how individual members are feeling
+    let mood_points: Vec<(u32, f64)> = weeks
+      .iter()
+      .zip(1u32..)
+      .filter(|(week, _)| week.responses >= min_responses)
+      .filter_map(|(week, x)| week.average_score.map(|score| (x, score)))
+      .collect();
+
+    let line_style = text_color.stroke_width(3);
+
+    chart
+      .draw_secondary_series(LineSeries::new(mood_points.iter().copied(), line_style))?
+      .label("Average mood (1-5)")
+      .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], line_style));
+
+    chart.draw_secondary_series(
+      mood_points
+        .iter()
+        .map(|point| Circle::new(*point, 5, text_color.filled())),
+    )?;
+
+    chart
+      .configure_series_labels()
+      .position(SeriesLabelPosition::UpperLeft)
+      .background_style(background_color.mix(0.8))
+      .border_style(text_color)
+      .label_font(("sans-serif", 20).into_font().color(text_color))
+      .draw()?;
+
+    root.present().with_context(|| "Could not present chart")?;
+
+    self.finish()
+  }
+
+  /// Draws new members each month as bars and churned members as a line, with 30-day retention of new members as a line on a second axis.
+  pub async fn draw_growth(
+    self,
+    months: &[GrowthMonth],
+    bar_color: (u8, u8, u8, f64),
+    light_mode: bool,
+  ) -> Result<Chart> {
+    let months = months.to_vec();
+
+    render(move || self.render_growth(&months, bar_color, light_mode)).await
+  }
+
+  #[allow(clippy::cast_precision_loss)]
+  fn render_growth(
+    self,
+    months: &[GrowthMonth],
+    bar_color: (u8, u8, u8, f64),
+    light_mode: bool,
+  ) -> Result<Chart> {
+    let path = self.file.path().to_path_buf();
+
+    let text_color = if light_mode { &BLACK } else { &WHITE };
+
+    let background_color = if light_mode { &WHITE } else { &BLACK };
+
+    let root = BitMapBackend::new(&path, (640, 480)).into_drawing_area();
+    root.fill(background_color).unwrap();
+
+    let largest = months
+      .iter()
+      .map(|month| month.new_members.max(month.churned))
+      .max()
+      .unwrap_or(0);
+    let upper_bound = next_largest_factor(largest as u32);
+    let count = months.len() as u32;
+
+    let mut chart = ChartBuilder::on(&root)
+      .caption(
+        "New Members and Retention by Month",
+        ("sans-serif", 35).into_font().color(text_color),
+      )
+      .margin(15)
+      .x_label_area_size(45)
+      .y_label_area_size(50)
+      .right_y_label_area_size(50)
+      .build_cartesian_2d(0u32..count + 1, 0f64..f64::from(upper_bound))
+      .with_context(|| "Could not build chart")?
+      .set_secondary_coord(0u32..count + 1, 0f64..100f64);
+
+    chart
+      .configure_mesh()
+      .axis_style(text_color)
+      .light_line_style(text_color.mix(0.1))
+      .bold_line_style(text_color.mix(0.2))
+      .x_label_style(("sans-serif", 20).into_font().color(text_color))
+      .y_label_style(("sans-serif", 25).into_font().color(text_color))
+      .x_label_formatter(&|x| {
+        x.checked_sub(1)
+          .and_then(|index| months.get(index as usize))
+          .map(|month| month.month_start.format("%y/%m").to_string())
+          .unwrap_or_default()
+      })
+      .y_label_formatter(&|y| axis_label(*y, &self.locale))
+      .draw()?;
+
+    chart
+      .configure_secondary_axes()
+      .axis_style(text_color)
+      .y_labels(6)
+      .y_label_formatter(&|percent| format!("{percent:.0}%"))
+      .label_style(("sans-serif", 25).into_font().color(text_color))
+      .draw()?;
+
+    let shape_color = ShapeStyle {
+      color: RGBAColor(bar_color.0, bar_color.1, bar_color.2, bar_color.3),
+      filled: true,
+      stroke_width: 1,
+    };
+
+    chart
+      .draw_series(months.iter().zip(1u32..).map(|(month, x)| {
+        let mut rect = Rectangle::new(
+          [(x, 0.0), (x, month.new_members as f64)],
+          shape_color.filled(),
+        );
+
+        rect.set_margin(0, 0, 10, 10);
+
+        rect
+      }))?
+      .label("New members")
+      .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], shape_color.filled()));
+
+    let churn_style = text_color.mix(0.5).stroke_width(2);
+
+    chart
+      .draw_series(LineSeries::new(
+        months
+          .iter()
+          .zip(1u32..)
+          .map(|(month, x)| (x, month.churned as f64)),
+        churn_style,
+      ))?
+      .label("Churned members")
+      .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], churn_style));
+
+    // Months whose new members joined too recently have no retention yet
+    let retention_points: Vec<(u32, f64)> = months
+      .iter()
+      .zip(1u32..)
+      .filter_map(|(month, x)| month.retention_percent().map(|percent| (x, percent)))
+      .collect();
+
+    let line_style = text_color.stroke_width(3);
+
+    chart
+      .draw_secondary_series(LineSeries::new(
+        retention_points.iter().copied(),
+        line_style,
+      ))?
+      .label("30-day retention")
+      .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], line_style));
+
+    chart.draw_secondary_series(
+      retention_points
+        .iter()
+        .map(|point| Circle::new(*point, 5, text_color.filled())),
+    )?;
+
+    chart
+      .configure_series_labels()
+      .position(SeriesLabelPosition::UpperLeft)
+      .background_style(background_color.mix(0.8))
+      .border_style(text_color)
+      .label_font(("sans-serif", 20).into_font().color(text_color))
+      .draw()?;
+
+    root.present().with_context(|| "Could not present chart")?;
+
+    self.finish()
+  }
+
+  /// Draws a progress bar toward a target, with a title and the progress written underneath.
+  pub async fn draw_progress(
+    self,
+    title: &str,
+    label: &str,
+    progress: f64,
+    bar_color: (u8, u8, u8, f64),
+    light_mode: bool,
+  ) -> Result<Chart> {
+    let title = title.to_string();
+    let label = label.to_string();
+
+    render(move || self.render_progress(&title, &label, progress, bar_color, light_mode)).await
+  }
+
+  fn render_progress(
+    self,
+    title: &str,
+    label: &str,
+    progress: f64,
+    bar_color: (u8, u8, u8, f64),
+    light_mode: bool,
+  ) -> Result<Chart> {
+    let path = self.file.path().to_path_buf();
+
+    let text_color = if light_mode { &BLACK } else { &WHITE };
+
+    let background_color = if light_mode { &WHITE } else { &BLACK };
+
+    let root = BitMapBackend::new(&path, (640, 180)).into_drawing_area();
+    root.fill(background_color).unwrap();
+
+    let (left, right, top, bottom) = (30, 610, 65, 115);
+    let filled_right = left + (f64::from(right - left) * progress.clamp(0.0, 1.0)) as i32;
+
+    let shape_color = ShapeStyle {
+      color: RGBAColor(bar_color.0, bar_color.1, bar_color.2, bar_color.3),
+      filled: true,
+      stroke_width: 1,
+    };
+
+    root.draw(&Text::new(
+      title.to_string(),
+      (left, 15),
+      ("sans-serif", 30).into_font().color(text_color),
+    ))?;
+    root.draw(&Rectangle::new(
+      [(left, top), (right, bottom)],
+      text_color.mix(0.15).filled(),
+    ))?;
+    if filled_right > left {
+      root.draw(&Rectangle::new(
+        [(left, top), (filled_right, bottom)],
+        shape_color.filled(),
+      ))?;
+    }
+    root.draw(&Rectangle::new(
+      [(left, top), (right, bottom)],
+      text_color.stroke_width(2),
+    ))?;
+    root.draw(&Text::new(
+      label.to_string(),
+      (left, 130),
+      ("sans-serif", 25).into_font().color(text_color),
+    ))?;
+
     root.present().with_context(|| "Could not present chart")?;
 
-    Ok(Chart { file: self.file })
+    self.finish()
   }
 }
 
@@ -0,0 +1,84 @@
+use crate::commands::helpers::time::last_weekly_time;
+use crate::config::BloomBotEmbed;
+use crate::database::DatabaseHandler;
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, Utc};
+use log::{error, info};
+use poise::serenity_prelude::{self as serenity, CreateActionRow, CreateButton, CreateMessage};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Custom ID of the button on check-in prompts. It doesn't change between posts, so the button keeps working after a restart.
+pub const CHECKIN_BUTTON_ID: &str = "weekly_checkin";
+
+/// How often check-in prompts are checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 5);
+/// Hours after its scheduled time that a prompt can still be posted, such as after a restart. Later than this, the week is skipped.
+const MAX_DELAY_HOURS: i64 = 6;
+
+/// Builds the weekly check-in prompt, with a button members press to check in.
+pub fn checkin_message(message: &str) -> CreateMessage {
+  let button = CreateButton::new(CHECKIN_BUTTON_ID)
+    .label("Check In")
+    .style(serenity::ButtonStyle::Primary);
+
+  CreateMessage::new()
+    .embed(
+      BloomBotEmbed::new()
+        .title("Weekly Check-In")
+        .description(message),
+    )
+    .components(vec![CreateActionRow::Buttons(vec![button])])
+}
+
+/// Posts each guild's weekly check-in prompt when it is due.
+///
+/// Runs until the bot shuts down.
+pub async fn post_checkin_prompts(http: Arc<serenity::Http>, db: DatabaseHandler) {
+  let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(e) = post_due_prompts(&http, &db).await {
+      error!("Error while posting check-in prompts: {e:?}");
+    }
+  }
+}
+
+async fn post_due_prompts(http: &serenity::Http, db: &DatabaseHandler) -> Result<()> {
+  let now = Utc::now();
+
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let prompts = DatabaseHandler::get_checkin_prompts(&mut transaction, None).await?;
+  drop(transaction);
+
+  for prompt in prompts {
+    let due_at = last_weekly_time(prompt.weekday, prompt.send_minute, now);
+
+    if prompt.last_sent_at.is_some_and(|sent_at| sent_at >= due_at)
+      || now - due_at > ChronoDuration::hours(MAX_DELAY_HOURS)
+    {
+      continue;
+    }
+
+    // Marked as sent first, so a failing channel doesn't get retried every few minutes
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    DatabaseHandler::mark_checkin_prompt_sent(&mut transaction, &prompt.id, now).await?;
+    DatabaseHandler::commit_transaction(transaction).await?;
+
+    match prompt
+      .channel_id
+      .send_message(http, checkin_message(&prompt.message))
+      .await
+    {
+      Ok(_) => info!("Posted check-in prompt in {}", prompt.guild_id),
+      Err(e) => error!(
+        "Could not post check-in prompt in {}: {e:?}",
+        prompt.guild_id
+      ),
+    }
+  }
+
+  Ok(())
+}
@@ -0,0 +1,226 @@
+use crate::charts::{self, Chart, ChartDrawer};
+use crate::config::{BloomBotEmbed, ChartPalette};
+use crate::database::{CollectiveGoal, DatabaseHandler};
+use anyhow::Result;
+use log::{error, info};
+use poise::serenity_prelude::{
+  self as serenity, CreateAttachment, CreateEmbed, CreateMessage, EditMessage,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often collective goals are checked for milestones.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Percentages of the target that are announced when reached.
+const MILESTONES: [i16; 4] = [25, 50, 75, 100];
+
+/// Highest milestone reached with the given minutes, or 0 if none has been.
+pub fn reached_milestone(minutes: i64, target_minutes: i64) -> i16 {
+  MILESTONES
+    .into_iter()
+    .filter(|milestone| minutes * 100 >= target_minutes * i64::from(*milestone))
+    .max()
+    .unwrap_or(0)
+}
+
+/// Draws the goal's progress bar in the guild's chart palette, and builds an embed showing it.
+pub async fn progress_embed(
+  goal: &CollectiveGoal,
+  minutes: i64,
+  palette: ChartPalette,
+) -> Result<(CreateEmbed, Chart)> {
+  #[allow(clippy::cast_precision_loss)]
+  let progress = minutes as f64 / goal.target_minutes.max(1) as f64;
+  let label = format!(
+    "{} of {} hours ({:.0}%)",
+    minutes / 60,
+    goal.target_minutes / 60,
+    progress * 100.0
+  );
+
+  let chart = ChartDrawer::new()?
+    .draw_progress(
+      &goal.name,
+      &label,
+      progress,
+      charts::bar_color(palette, false),
+      false,
+    )
+    .await?;
+
+  let embed = BloomBotEmbed::new()
+    .title(format!("Collective Goal: {}", goal.name))
+    .description(format!(
+      "Every minute you `/add` from {} to {} counts toward our shared goal of **{} hours**.",
+      goal.starts_on.format("%B %-d"),
+      goal.ends_on.format("%B %-d, %Y"),
+      goal.target_minutes / 60
+    ))
+    .image(chart.get_attachment_url());
+
+  Ok((embed, chart))
+}
+
+/// Announces milestones for each guild's collective goal, updates the progress bar daily, and wraps up goals once they end.
+///
+/// Runs until the bot shuts down.
+pub async fn track_collective_goals(http: Arc<serenity::Http>, db: DatabaseHandler) {
+  let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(e) = update_goals(&http, &db).await {
+      error!("Error while updating collective goals: {e:?}");
+    }
+  }
+}
+
+async fn update_goals(http: &serenity::Http, db: &DatabaseHandler) -> Result<()> {
+  let today = chrono::Utc::now().date_naive();
+
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let goals = DatabaseHandler::get_collective_goals(&mut transaction, None).await?;
+  drop(transaction);
+
+  for goal in goals {
+    if today < goal.starts_on {
+      continue;
+    }
+
+    if let Err(e) = update_goal(http, db, &goal, today).await {
+      error!(
+        "Could not update collective goal {} in {}: {e:?}",
+        goal.id, goal.guild_id
+      );
+    }
+  }
+
+  Ok(())
+}
+
+async fn update_goal(
+  http: &serenity::Http,
+  db: &DatabaseHandler,
+  goal: &CollectiveGoal,
+  today: chrono::NaiveDate,
+) -> Result<()> {
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let minutes = DatabaseHandler::get_guild_minutes_between(
+    &mut transaction,
+    &goal.guild_id,
+    goal.starts_on,
+    goal.ends_on,
+  )
+  .await?;
+  let palette = DatabaseHandler::get_chart_palette(&mut transaction, &goal.guild_id).await?;
+  drop(transaction);
+
+  let ended = today > goal.ends_on;
+  let milestone = reached_milestone(minutes, goal.target_minutes);
+
+  if milestone > goal.last_milestone {
+    let content = if milestone >= 100 {
+      format!(
+        ":tada: We did it! Together we've reached our collective goal of **{} hours** for **{}**. Thank you all!",
+        goal.target_minutes / 60,
+        goal.name
+      )
+    } else {
+      format!(
+        ":tada: We're {milestone}% of the way to our collective goal for **{}**, with {} of {} hours so far. Keep it up!",
+        goal.name,
+        minutes / 60,
+        goal.target_minutes / 60
+      )
+    };
+
+    goal
+      .channel_id
+      .send_message(http, CreateMessage::new().content(content))
+      .await?;
+
+    // Saved right away, so the milestone isn't announced again if updating the progress bar fails
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    DatabaseHandler::update_collective_goal_progress(
+      &mut transaction,
+      &goal.id,
+      goal.progress_message_id.as_ref(),
+      milestone,
+      goal.last_rendered_on,
+    )
+    .await?;
+    DatabaseHandler::commit_transaction(transaction).await?;
+  }
+
+  let mut progress_message_id = goal.progress_message_id;
+  let mut last_rendered_on = goal.last_rendered_on;
+
+  if ended || last_rendered_on.map_or(true, |rendered_on| rendered_on < today) {
+    let (embed, chart) = progress_embed(goal, minutes, palette).await?;
+    let attachment = CreateAttachment::path(chart.get_file_path()).await?;
+
+    // Edited in place, so the channel isn't filled with a new image every day
+    let edited = match progress_message_id {
+      Some(message_id) => goal
+        .channel_id
+        .edit_message(
+          http,
+          message_id,
+          EditMessage::new()
+            .embed(embed.clone())
+            .remove_all_attachments()
+            .new_attachment(attachment.clone()),
+        )
+        .await
+        .is_ok(),
+      None => false,
+    };
+
+    if !edited {
+      let message = goal
+        .channel_id
+        .send_message(http, CreateMessage::new().embed(embed).add_file(attachment))
+        .await?;
+      progress_message_id = Some(message.id);
+    }
+
+    last_rendered_on = Some(today);
+  }
+
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  if ended {
+    info!(
+      "Collective goal {} in {} ended with {minutes} of {} minutes",
+      goal.id, goal.guild_id, goal.target_minutes
+    );
+    DatabaseHandler::remove_collective_goal(&mut transaction, &goal.guild_id).await?;
+
+    if milestone < 100 {
+      goal
+        .channel_id
+        .send_message(
+          http,
+          CreateMessage::new().content(format!(
+            "Our collective goal for **{}** has ended. Together we meditated for **{} hours**. Thank you to everyone who took part!",
+            goal.name,
+            minutes / 60
+          )),
+        )
+        .await?;
+    }
+  } else {
+    DatabaseHandler::update_collective_goal_progress(
+      &mut transaction,
+      &goal.id,
+      progress_message_id.as_ref(),
+      milestone.max(goal.last_milestone),
+      last_rendered_on,
+    )
+    .await?;
+  }
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  Ok(())
+}
@@ -0,0 +1,143 @@
+use crate::database::DatabaseHandler;
+use crate::{Data, Error};
+use anyhow::Result;
+use poise::serenity_prelude as serenity;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Commands that can't be excluded from a guild, so staff can't lose the commands needed to undo an exclusion.
+pub const UNEXCLUDABLE_COMMANDS: [&str; 1] = ["manage"];
+
+/// What registering a set of commands changed, by command name.
+#[derive(Default)]
+pub struct CommandDiff {
+  pub added: Vec<String>,
+  pub removed: Vec<String>,
+  pub changed: Vec<String>,
+}
+
+impl CommandDiff {
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+  }
+}
+
+/// Builds the application commands to register, leaving out commands named in `excluded`.
+pub fn application_commands(
+  commands: &[poise::Command<Data, Error>],
+  excluded: &[String],
+) -> Vec<serenity::CreateCommand> {
+  commands
+    .iter()
+    .filter(|command| !excluded.contains(&command.name))
+    .flat_map(|command| {
+      [
+        command.create_as_slash_command(),
+        command.create_as_context_menu_command(),
+      ]
+    })
+    .flatten()
+    .collect()
+}
+
+/// Builds the application commands to register in a guild, leaving out the guild's excluded commands.
+pub async fn guild_commands(
+  db: &DatabaseHandler,
+  commands: &[poise::Command<Data, Error>],
+  guild_id: &serenity::GuildId,
+) -> Result<Vec<serenity::CreateCommand>> {
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let excluded = DatabaseHandler::get_command_exclusions(&mut transaction, guild_id).await?;
+  drop(transaction);
+
+  Ok(application_commands(commands, &excluded))
+}
+
+/// Identifies a command by its name and type, since a slash command and a context menu command can share a name.
+fn command_key(command: &Value) -> (String, u64) {
+  (
+    command["name"].as_str().unwrap_or_default().to_string(),
+    command["type"].as_u64().unwrap_or(1),
+  )
+}
+
+/// Summarizes what members see of a command: its description and its options, with their types, descriptions, and choices.
+///
+/// Registered commands come back from Discord with defaults filled in, so only the fields set when creating a command are compared.
+fn command_signature(command: &Value) -> String {
+  let options = command["options"]
+    .as_array()
+    .map(|options| {
+      options
+        .iter()
+        .map(|option| {
+          let choices = option["choices"]
+            .as_array()
+            .map(|choices| {
+              choices
+                .iter()
+                .filter_map(|choice| choice["name"].as_str())
+                .collect::<Vec<&str>>()
+                .join(",")
+            })
+            .unwrap_or_default();
+
+          format!(
+            "{}:{}:{}:[{choices}]:({})",
+            option["name"].as_str().unwrap_or_default(),
+            option["type"].as_u64().unwrap_or_default(),
+            option["required"].as_bool().unwrap_or(false),
+            command_signature(option)
+          )
+        })
+        .collect::<Vec<String>>()
+        .join(";")
+    })
+    .unwrap_or_default();
+
+  format!(
+    "{}|{options}",
+    command["description"].as_str().unwrap_or_default()
+  )
+}
+
+/// Compares the commands registered with Discord against the commands about to be registered.
+pub fn diff(
+  registered: &[serenity::Command],
+  commands: &[serenity::CreateCommand],
+) -> Result<CommandDiff> {
+  let registered = registered
+    .iter()
+    .map(|command| {
+      let value = serde_json::to_value(command)?;
+      Ok((command_key(&value), command_signature(&value)))
+    })
+    .collect::<Result<BTreeMap<_, _>>>()?;
+  let commands = commands
+    .iter()
+    .map(|command| {
+      let value = serde_json::to_value(command)?;
+      Ok((command_key(&value), command_signature(&value)))
+    })
+    .collect::<Result<BTreeMap<_, _>>>()?;
+
+  let mut diff = CommandDiff::default();
+
+  for (key, signature) in &commands {
+    match registered.get(key) {
+      None => diff.added.push(key.0.clone()),
+      Some(registered_signature) if registered_signature != signature => {
+        diff.changed.push(key.0.clone());
+      }
+      Some(_) => {}
+    }
+  }
+
+  for key in registered.keys() {
+    if !commands.contains_key(key) {
+      diff.removed.push(key.0.clone());
+    }
+  }
+
+  Ok(diff)
+}
@@ -1,6 +1,15 @@
-use crate::commands::{commit_and_say, MessageType};
-use crate::config::{BloomBotEmbed, StreakRoles, TimeSumRoles, CHANNELS};
+use crate::add_timings::{AddStage, AddTimings};
+use crate::commands::helpers::time::parse_duration;
+use crate::commands::{
+  commit_and_say, emoji, refuse_private_text_command, send_private, MessageType,
+};
+use crate::config::{
+  BloomBotEmbed, CongratsDelivery, EmojiKind, EntrySource, CHANNELS, MAX_STREAK_CREDITS, ROLES,
+  STREAK_CREDIT_DAYS,
+};
 use crate::database::{DatabaseHandler, TrackingProfile};
+use crate::milestones::{self, Promotion, RoleMilestone};
+use crate::webhooks::{self, WebhookEvent};
 use crate::Context;
 use anyhow::Result;
 use chrono::Duration;
@@ -106,17 +115,25 @@ pub enum Privacy {
 
 /// Add a meditation entry, with optional UTC offset
 ///
-/// Adds a specified number of minutes to your meditation time. You can add minutes each time you meditate or add the combined minutes for multiple sessions.
+/// Adds a specified number of minutes to your meditation time. You can add minutes each time you meditate or add the combined minutes for multiple sessions. Instead of minutes, you can also enter a duration such as `1h 20m`, `45 mins`, or `1:30:00`.
 ///
 /// You may wish to add large amounts of time on occasion, e.g., after a silent retreat. Time tracking is based on the honor system and members are welcome to track any legitimate time spent practicing.
 ///
 /// Vanity roles are purely cosmetic, so there is nothing to be gained from cheating. Furthermore, exceedingly large false entries will skew the server stats, which is unfair to other members. Please be considerate.
-#[poise::command(slash_command, category = "Meditation Tracking", guild_only)]
+#[poise::command(
+  slash_command,
+  prefix_command,
+  category = "Meditation Tracking",
+  guild_only
+)]
 pub async fn add(
   ctx: Context<'_>,
   #[description = "Number of minutes to add"]
   #[min = 1]
-  minutes: i32,
+  minutes: Option<i32>,
+  #[description = "Duration to add instead of minutes (e.g. 1h 20m, 45 mins, or 1:30:00)"]
+  #[max_length = 50]
+  duration: Option<String>,
   #[description = "Specify a UTC offset for a Western Hemisphere time zone"]
   #[rename = "western_hemisphere_offset"]
   minus_offset: Option<MinusOffsetChoices>,
@@ -124,6 +141,59 @@ pub async fn add(
   #[rename = "eastern_hemisphere_offset"]
   plus_offset: Option<PlusOffsetChoices>,
   #[description = "Set visibility of response (Defaults to public)"] privacy: Option<Privacy>,
+  #[description = "Show how long each step of the add took (staff only)"] debug: Option<bool>,
+) -> Result<()> {
+  let minutes = match (duration, minutes) {
+    (Some(duration), _) => match parse_duration(&duration) {
+      Some(minutes) => minutes,
+      None => {
+        ctx
+          .send(
+            CreateReply::default()
+              .content(":x: Could not understand that duration. Please try something like `1h 20m`, `45 mins`, or `1:30:00`.")
+              .ephemeral(true),
+          )
+          .await?;
+        return Ok(());
+      }
+    },
+    (None, Some(minutes)) => minutes,
+    (None, None) => {
+      ctx
+        .send(
+          CreateReply::default()
+            .content(":x: Please enter the number of minutes or a duration to add.")
+            .ephemeral(true),
+        )
+        .await?;
+      return Ok(());
+    }
+  };
+
+  add_session(
+    ctx,
+    minutes,
+    minus_offset,
+    plus_offset,
+    privacy,
+    EntrySource::Slash,
+    debug.unwrap_or(false),
+  )
+  .await
+}
+
+/// Adds a session for the command author, applying the server's session limits and updating roles, streaks, and milestones.
+///
+/// Shared by `/add` and `/sit stop`, so sessions are handled the same way however they are logged. The entry is attributed to `source`.
+/// Each stage is timed, and the timings are shown to staff who asked for them with `debug`.
+pub async fn add_session(
+  ctx: Context<'_>,
+  minutes: i32,
+  minus_offset: Option<MinusOffsetChoices>,
+  plus_offset: Option<PlusOffsetChoices>,
+  privacy: Option<Privacy>,
+  source: EntrySource,
+  debug: bool,
 ) -> Result<()> {
   let data = ctx.data();
 
@@ -131,15 +201,23 @@ pub async fn add(
   let guild_id = ctx.guild_id().unwrap();
   let user_id = ctx.author().id;
 
+  let debug = debug && ctx.author().has_role(ctx, guild_id, ROLES.staff).await?;
+  let mut timings = AddTimings::default();
+
   let mut transaction = data.db.start_transaction_with_retry(5).await?;
 
-  let tracking_profile =
-    match DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user_id).await? {
-      Some(tracking_profile) => tracking_profile,
-      None => TrackingProfile {
-        ..Default::default()
-      },
-    };
+  let tracking_profile = match timings
+    .time(
+      AddStage::Profile,
+      DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user_id),
+    )
+    .await?
+  {
+    Some(tracking_profile) => tracking_profile,
+    None => TrackingProfile {
+      ..Default::default()
+    },
+  };
 
   let privacy = match privacy {
     Some(privacy) => match privacy {
@@ -149,6 +227,10 @@ pub async fn add(
     None => tracking_profile.anonymous_tracking,
   };
 
+  if refuse_private_text_command(ctx, privacy).await? {
+    return Ok(());
+  }
+
   let minus_offset = match minus_offset {
     Some(minus_offset) => match minus_offset {
       MinusOffsetChoices::UTCMinus12 => -720,
@@ -213,45 +295,139 @@ pub async fn add(
       )
       .await?;
     return Ok(());
-  } else if minus_offset != 0 {
-    let adjusted_datetime = chrono::Utc::now() + Duration::minutes(minus_offset);
-    DatabaseHandler::create_meditation_entry(
-      &mut transaction,
-      &guild_id,
-      &user_id,
-      minutes,
-      adjusted_datetime,
-    )
-    .await?;
+  }
+
+  let limits = DatabaseHandler::get_session_limits(&mut transaction, &guild_id).await?;
+  let add_quotes = match tracking_profile.add_quotes {
+    Some(add_quotes) => add_quotes,
+    None => DatabaseHandler::get_add_quotes_default(&mut transaction, &guild_id).await?,
+  };
+  let confirmation_channel =
+    DatabaseHandler::get_confirmation_channel(&mut transaction, &guild_id, ctx.channel_id())
+      .await?;
+
+  drop(transaction);
+
+  if let Some(min_minutes) = limits.min_minutes {
+    if minutes < min_minutes {
+      ctx
+        .send(
+          CreateReply::default()
+            .content(format!(
+              ":x: Entries must be at least **{min_minutes} minutes**. Please try again with a longer session."
+            ))
+            .ephemeral(true),
+        )
+        .await?;
+      return Ok(());
+    }
+  }
+
+  if let Some(max_minutes) = limits.max_minutes {
+    if minutes > max_minutes {
+      ctx
+        .send(
+          CreateReply::default()
+            .content(format!(
+              ":x: Entries cannot be longer than **{max_minutes} minutes**. If you need to add more time, please contact staff."
+            ))
+            .ephemeral(true),
+        )
+        .await?;
+
+      // Flag oversized entry in Bloom logs channel
+      let log_embed = BloomBotEmbed::new()
+        .title("Oversized Meditation Entry Rejected")
+        .description(format!(
+          "**User**: {}\n**Time**: {} minutes\n**Limit**: {} minutes",
+          ctx.author(),
+          minutes,
+          max_minutes
+        ))
+        .footer(
+          CreateEmbedFooter::new(format!("Attempted by {}", ctx.author()))
+            .icon_url(ctx.author().avatar_url().unwrap_or_default()),
+        );
+
+      let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+
+      log_channel
+        .send_message(ctx, CreateMessage::new().embed(log_embed))
+        .await?;
+
+      return Ok(());
+    }
+  }
+
+  let offset = if minus_offset != 0 {
+    minus_offset
   } else if plus_offset != 0 {
-    let adjusted_datetime = chrono::Utc::now() + Duration::minutes(plus_offset);
-    DatabaseHandler::create_meditation_entry(
-      &mut transaction,
-      &guild_id,
-      &user_id,
-      minutes,
-      adjusted_datetime,
-    )
-    .await?;
-  } else if tracking_profile.utc_offset != 0 {
-    let adjusted_datetime =
-      chrono::Utc::now() + Duration::minutes(i64::from(tracking_profile.utc_offset));
-    DatabaseHandler::create_meditation_entry(
-      &mut transaction,
-      &guild_id,
-      &user_id,
-      minutes,
-      adjusted_datetime,
-    )
-    .await?;
+    plus_offset
   } else {
-    DatabaseHandler::add_minutes(&mut transaction, &guild_id, &user_id, minutes).await?;
-  }
+    i64::from(tracking_profile.utc_offset)
+  };
+
+  // Retried as a whole, since a transient failure leaves the transaction unusable
+  let (mut transaction, user_sum, user_streak, random_quote, attempt_timings) =
+    DatabaseHandler::with_retry(3, || async move {
+      let mut timings = AddTimings::default();
+      let mut transaction = data.db.start_transaction_with_retry(5).await?;
+
+      if offset == 0 {
+        timings
+          .time(
+            AddStage::Insert,
+            DatabaseHandler::add_minutes(&mut transaction, &guild_id, &user_id, minutes, source),
+          )
+          .await?;
+      } else {
+        let adjusted_datetime = chrono::Utc::now() + Duration::minutes(offset);
+        timings
+          .time(
+            AddStage::Insert,
+            DatabaseHandler::create_meditation_entry(
+              &mut transaction,
+              &guild_id,
+              &user_id,
+              minutes,
+              adjusted_datetime,
+              source,
+            ),
+          )
+          .await?;
+      }
+
+      let user_sum = timings
+        .time(
+          AddStage::Sum,
+          DatabaseHandler::get_user_meditation_sum(&mut transaction, &guild_id, &user_id),
+        )
+        .await?;
+      let user_streak = timings
+        .time(
+          AddStage::Streak,
+          DatabaseHandler::get_streak(&mut transaction, &guild_id, &user_id),
+        )
+        .await?;
+      let random_quote = timings
+        .time(AddStage::Quote, async {
+          let random_quote = if add_quotes {
+            DatabaseHandler::get_random_quote(&mut transaction, &guild_id).await?
+          } else {
+            None
+          };
+          if let Some(quote) = &random_quote {
+            DatabaseHandler::record_quote_shown(&mut transaction, &quote.id).await?;
+          }
 
-  let user_sum =
-    DatabaseHandler::get_user_meditation_sum(&mut transaction, &guild_id, &user_id).await?;
-  let user_streak = DatabaseHandler::get_streak(&mut transaction, &guild_id, &user_id).await?;
-  let random_quote = DatabaseHandler::get_random_quote(&mut transaction, &guild_id).await?;
+          anyhow::Ok(random_quote)
+        })
+        .await?;
+
+      Ok((transaction, user_sum, user_streak, random_quote, timings))
+    })
+    .await?;
+  timings.extend(attempt_timings);
 
   let response = match random_quote {
     Some(quote) => {
@@ -291,7 +467,15 @@ pub async fn add(
     }
   };
 
-  if minutes > 300 {
+  // Public confirmations outside a tracking channel are posted in the default one, naming the member
+  let posted_elsewhere = !privacy && confirmation_channel != ctx.channel_id();
+  let channel_response = if posted_elsewhere {
+    format!("{} {response}", ctx.author().mention())
+  } else {
+    response.clone()
+  };
+
+  if minutes > limits.confirm_minutes {
     let ctx_id = ctx.id();
 
     let confirm_id = format!("{ctx_id}confirm");
@@ -337,7 +521,7 @@ pub async fn add(
         .create_response(ctx, CreateInteractionResponse::UpdateMessage(
           {
               if confirm {
-                if privacy {
+                if privacy || posted_elsewhere {
                   CreateInteractionResponseMessage::new().content(format!("Added **{minutes} minutes** to your meditation time! Your total meditation time is now {user_sum} minutes :tada:"))
                     .ephemeral(privacy)
                     .components(Vec::new())
@@ -360,8 +544,9 @@ pub async fn add(
             match DatabaseHandler::commit_transaction(transaction).await {
               Ok(()) => {}
               Err(e) => {
+                let info = emoji(ctx, EmojiKind::Info).await;
                 check.edit(ctx, CreateReply::default()
-                  .content("<:mminfo:1194141918133768234> A fatal error occurred while trying to save your changes. Please contact staff for assistance.")
+                  .content(format!("{info} A fatal error occurred while trying to save your changes. Please contact staff for assistance."))
                   .ephemeral(privacy)).await?;
                 return Err(anyhow::anyhow!("Could not send message: {e}"));
               }
@@ -369,9 +554,10 @@ pub async fn add(
           }
         }
         Err(e) => {
+          let info = emoji(ctx, EmojiKind::Info).await;
           check
             .edit(ctx, CreateReply::default()
-              .content("<:mminfo:1194141918133768234> An error may have occurred. If your command failed, please contact staff for assistance.")
+              .content(format!("{info} An error may have occurred. If your command failed, please contact staff for assistance."))
                 .ephemeral(privacy)
             )
             .await?;
@@ -379,10 +565,14 @@ pub async fn add(
         }
       }
 
-      if confirm && privacy {
-        ctx
-          .channel_id()
-          .send_message(ctx, CreateMessage::new().content(response))
+      if confirm && (privacy || posted_elsewhere) {
+        confirmation_channel
+          .send_message(
+            ctx,
+            CreateMessage::new()
+              .content(channel_response)
+              .allowed_mentions(serenity::CreateAllowedMentions::new()),
+          )
           .await?;
       }
 
@@ -408,13 +598,37 @@ pub async fn add(
           .await?;
       }
 
-      return Ok(());
+      return report_timings(ctx, &timings, debug).await;
     }
   }
 
-  let guild_count =
-    DatabaseHandler::get_guild_meditation_count(&mut transaction, &guild_id).await?;
-  let guild_sum = DatabaseHandler::get_guild_meditation_sum(&mut transaction, &guild_id).await?;
+  let (guild_count, guild_sum, hours_announcement) = timings
+    .time(AddStage::GuildHours, async {
+      anyhow::Ok((
+        DatabaseHandler::get_guild_meditation_count(&mut transaction, &guild_id).await?,
+        DatabaseHandler::get_guild_meditation_sum(&mut transaction, &guild_id).await?,
+        DatabaseHandler::get_hours_announcement(&mut transaction, &guild_id).await?,
+      ))
+    })
+    .await?;
+
+  let record_streak = DatabaseHandler::get_record_streak(&mut transaction, &guild_id).await?;
+  let is_record_streak = i32::try_from(user_streak).is_ok_and(|streak| streak > record_streak);
+  if is_record_streak {
+    DatabaseHandler::set_record_streak(&mut transaction, &guild_id, user_streak.try_into()?)
+      .await?;
+  }
+
+  let earned_streak_credit = if tracking_profile.streaks_active
+    && user_streak > 0
+    && user_streak % STREAK_CREDIT_DAYS == 0
+    && DatabaseHandler::streak_restores_enabled(&mut transaction, &guild_id).await?
+  {
+    DatabaseHandler::earn_streak_credit(&mut transaction, &guild_id, &user_id, MAX_STREAK_CREDITS)
+      .await?
+  } else {
+    false
+  };
 
   if privacy {
     let private_response = format!("Added **{minutes} minutes** to your meditation time! Your total meditation time is now {user_sum} minutes :tada:");
@@ -426,106 +640,242 @@ pub async fn add(
     )
     .await?;
 
-    ctx
-      .channel_id()
+    confirmation_channel
       .send_message(ctx, CreateMessage::new().content(response))
       .await?;
+  } else if posted_elsewhere {
+    commit_and_say(
+      ctx,
+      transaction,
+      MessageType::TextOnly(format!(
+        ":white_check_mark: Added **{minutes} minutes** to your meditation time. Your confirmation was posted in {}.",
+        confirmation_channel.mention()
+      )),
+      true,
+    )
+    .await?;
+
+    let sent_message = confirmation_channel
+      .send_message(
+        ctx,
+        CreateMessage::new()
+          .content(channel_response)
+          .allowed_mentions(serenity::CreateAllowedMentions::new()),
+      )
+      .await?;
+
+    if let Some(add_reaction) = &tracking_profile.add_reaction {
+      react_to_add(ctx, &sent_message, add_reaction).await;
+    }
   } else {
-    commit_and_say(ctx, transaction, MessageType::TextOnly(response), false).await?;
+    let sent_message =
+      commit_and_say(ctx, transaction, MessageType::TextOnly(response), false).await?;
+
+    if let Some(add_reaction) = &tracking_profile.add_reaction {
+      match sent_message.message().await {
+        Ok(message) => react_to_add(ctx, &message, add_reaction).await,
+        Err(err) => error!("Error reacting to add confirmation: {err}"),
+      }
+    }
+  }
+
+  // Records only start counting from the first streak seen, so the first one isn't announced
+  if is_record_streak && record_streak > 0 {
+    webhooks::notify(
+      &data.db,
+      guild_id,
+      WebhookEvent::RecordStreak {
+        user_id,
+        streak: user_streak,
+      },
+    );
   }
 
-  if guild_count % 10 == 0 {
-    let time_in_hours = guild_sum / 60;
+  if let Some(hours) = milestones::crossed_hours_milestone(guild_sum, minutes) {
+    webhooks::notify(&data.db, guild_id, WebhookEvent::HoursMilestone { hours });
+  }
+
+  let mut notices = AddNotices::default();
+
+  if milestones::is_hours_announcement_due(&hours_announcement, guild_count) {
+    notices.server.push(milestones::hours_announcement_message(
+      &hours_announcement,
+      guild_sum,
+      guild_count,
+    ));
+  }
 
-    ctx.say(format!("Awesome sauce! This server has collectively generated {time_in_hours} hours of realmbreaking meditation!")).await?;
+  if earned_streak_credit {
+    notices.member.push(format!(":coin: You earned a streak credit for your {user_streak}-day streak! If you miss a day, use `/streak restore` within 48 hours to keep your streak going."));
   }
 
   let guild = ctx.guild().unwrap().clone();
-  let member = guild.member(ctx, user_id).await?;
-
-  let current_time_roles = TimeSumRoles::get_users_current_roles(&guild, &member);
-  let updated_time_role = TimeSumRoles::from_sum(user_sum);
-
-  if let Some(updated_time_role) = updated_time_role {
-    if !current_time_roles.contains(&updated_time_role.to_role_id()) {
-      for role in current_time_roles {
-        match member.remove_role(ctx, role).await {
-          Ok(()) => {}
-          Err(err) => {
-            error!("Error removing role: {err}");
-            ctx.send(CreateReply::default()
-              .content(":x: An error occured while updating your time roles. Your entry has been saved, but your roles have not been updated. Please contact a moderator.")
-              .allowed_mentions(serenity::CreateAllowedMentions::new())
-              .ephemeral(privacy)).await?;
+  let user_streak = tracking_profile.streaks_active.then_some(user_streak);
+  let (member, (promotions, failed)) = timings
+    .time(AddStage::Roles, async {
+      let member = guild.member(ctx, user_id).await?;
+      let promoted = milestones::promote(ctx, &guild, &member, user_sum, user_streak).await;
+
+      anyhow::Ok((member, promoted))
+    })
+    .await?;
 
-            return Ok(());
-          }
-        }
-      }
+  for promotion in &promotions {
+    let delivery = milestones::congrats_delivery(&tracking_profile, privacy, promotion.milestone);
+    notices
+      .congratulate(
+        &guild,
+        &member,
+        promotion,
+        delivery,
+        tracking_profile.congrats_cards,
+      )
+      .await?;
+  }
 
-      match member.add_role(ctx, updated_time_role.to_role_id()).await {
-        Ok(()) => {}
-        Err(err) => {
-          error!("Error adding role: {err}");
-          ctx.send(CreateReply::default()
-            .content(":x: An error occured while updating your time roles. Your entry has been saved, but your roles have not been updated. Please contact a moderator.")
-            .allowed_mentions(serenity::CreateAllowedMentions::new())
-            .ephemeral(privacy)).await?;
+  if let Some(milestone) = failed {
+    let roles = match milestone {
+      RoleMilestone::Time(_) => "time",
+      RoleMilestone::Streak(_) => "streak",
+    };
+    notices.member.push(format!(":x: An error occured while updating your {roles} roles. Your entry has been saved, but your roles have not been updated. Please contact a moderator."));
+  }
 
-          return Ok(());
-        }
-      }
+  notices.send(ctx, privacy, confirmation_channel).await?;
 
-      ctx.send(CreateReply::default()
-        .content(format!(":tada: Congrats to {}, your hard work is paying off! Your total meditation minutes have given you the <@&{}> role!", member.mention(), updated_time_role.to_role_id()))
-        .allowed_mentions(serenity::CreateAllowedMentions::new())
-        .ephemeral(privacy)).await?;
-    }
+  report_timings(ctx, &timings, debug).await
+}
+
+/// Logs the add's stage timings, and shows them privately to staff who asked for them.
+async fn report_timings(ctx: Context<'_>, timings: &AddTimings, debug: bool) -> Result<()> {
+  timings.finish(&crate::get_trace_id(ctx).await);
+
+  if debug {
+    send_private(
+      ctx,
+      CreateReply::default().content(format!("**Add timings**\n{}", timings.summary())),
+    )
+    .await?;
   }
 
-  if tracking_profile.streaks_active {
-    let current_streak_roles = StreakRoles::get_users_current_roles(&guild.clone(), &member);
-    let updated_streak_role = StreakRoles::from_streak(user_streak);
+  Ok(())
+}
 
-    if let Some(updated_streak_role) = updated_streak_role {
-      if !current_streak_roles.contains(&updated_streak_role.to_role_id()) {
-        for role in current_streak_roles {
-          match member.remove_role(ctx, role).await {
-            Ok(()) => {}
-            Err(err) => {
-              error!("Error removing role: {err}");
+/// Reacts to a public add confirmation with the emoji chosen in `/customize reaction`.
+///
+/// The session is already saved, so a reaction that can't be added (such as a custom emoji that has since been deleted) is only logged.
+async fn react_to_add(ctx: Context<'_>, message: &serenity::Message, add_reaction: &str) {
+  let Ok(reaction) = serenity::ReactionType::try_from(add_reaction) else {
+    return;
+  };
 
-              ctx.send(CreateReply::default()
-                .content(":x: An error occured while updating your streak roles. Your entry has been saved, but your roles have not been updated. Please contact a moderator.")
-                .allowed_mentions(serenity::CreateAllowedMentions::new())
-                .ephemeral(privacy)).await?;
+  if let Err(err) = message.react(ctx, reaction).await {
+    error!("Error reacting to add confirmation: {err}");
+  }
+}
 
-              return Ok(());
-            }
-          }
+/// Follow-up notices triggered by a single add, such as new roles and server milestones.
+///
+/// Notices are collected and sent together once the add has been processed, so members get one message per destination instead of several pings.
+#[derive(Default)]
+struct AddNotices {
+  /// Notices about the server, which are always public.
+  server: Vec<String>,
+  /// Notices for the member, which follow the privacy of the add.
+  member: Vec<String>,
+  /// Congratulations on new roles to send publicly, with their cards.
+  public_congrats: Vec<String>,
+  public_attachments: Vec<CreateAttachment>,
+  /// Congratulations on new roles to send only to the member, with their cards.
+  private_congrats: Vec<String>,
+  private_attachments: Vec<CreateAttachment>,
+}
+
+impl AddNotices {
+  /// Adds congratulations on a new role where `delivery` says, with an image card if the member has turned cards on in `/customize settings`.
+  ///
+  /// The role has already been given at this point, so the text is kept alone if the card can't be drawn.
+  async fn congratulate(
+    &mut self,
+    guild: &serenity::Guild,
+    member: &serenity::Member,
+    promotion: &Promotion,
+    delivery: CongratsDelivery,
+    with_card: bool,
+  ) -> Result<()> {
+    let (congrats, attachments) = match delivery {
+      CongratsDelivery::Public => (&mut self.public_congrats, &mut self.public_attachments),
+      CongratsDelivery::Private => (&mut self.private_congrats, &mut self.private_attachments),
+      CongratsDelivery::Off => return Ok(()),
+    };
+
+    congrats.push(promotion.message(member));
+
+    if with_card {
+      match promotion.draw_card(guild, member).await {
+        Ok(card) => {
+          attachments.push(CreateAttachment::path(card.get_file_path()).await?);
         }
+        Err(err) => error!("Error drawing congratulations card: {err}"),
+      }
+    }
+
+    Ok(())
+  }
 
-        match member.add_role(ctx, updated_streak_role.to_role_id()).await {
-          Ok(()) => {}
-          Err(err) => {
-            error!("Error adding role: {err}");
+  /// Sends the notices, posting public ones in `channel_id` and replying privately with the rest.
+  async fn send(
+    self,
+    ctx: Context<'_>,
+    privacy: bool,
+    channel_id: serenity::ChannelId,
+  ) -> Result<()> {
+    // Member notices posted in another channel would lose their context, so they're sent privately
+    let elsewhere = channel_id != ctx.channel_id();
+    let (mut public, mut private) = if privacy || elsewhere {
+      (self.server, self.member)
+    } else {
+      ([self.server, self.member].concat(), Vec::new())
+    };
+    public.extend(self.public_congrats);
+    private.extend(self.private_congrats);
+
+    for (lines, attachments, ephemeral) in [
+      (public, self.public_attachments, false),
+      (private, self.private_attachments, true),
+    ] {
+      if lines.is_empty() {
+        continue;
+      }
 
-            ctx.send(CreateReply::default()
-              .content(":x: An error occured while updating your streak roles. Your entry has been saved, but your roles have not been updated. Please contact a moderator.")
+      if !ephemeral && elsewhere {
+        channel_id
+          .send_message(
+            ctx,
+            CreateMessage::new()
+              .content(lines.join("\n"))
               .allowed_mentions(serenity::CreateAllowedMentions::new())
-              .ephemeral(privacy)).await?;
+              .add_files(attachments),
+          )
+          .await?;
+        continue;
+      }
 
-            return Ok(());
-          }
-        }
+      let mut reply = CreateReply::default()
+        .content(lines.join("\n"))
+        .allowed_mentions(serenity::CreateAllowedMentions::new());
+      for attachment in attachments {
+        reply = reply.attachment(attachment);
+      }
 
-        ctx.send(CreateReply::default()
-          .content(format!(":tada: Congrats to {}, your hard work is paying off! Your current streak is {}, giving you the <@&{}> role!", member.mention(), user_streak, updated_streak_role.to_role_id()))
-          .allowed_mentions(serenity::CreateAllowedMentions::new())
-          .ephemeral(privacy)).await?;
+      // Text commands can't reply ephemerally, so private notices are sent by direct message
+      if ephemeral {
+        send_private(ctx, reply).await?;
+      } else {
+        ctx.send(reply).await?;
       }
     }
-  }
 
-  Ok(())
+    Ok(())
+  }
 }
@@ -0,0 +1,60 @@
+use crate::commands::quotes::{save_quote, AddQuoteModal};
+use crate::{Data as AppData, Error as AppError};
+use anyhow::Result;
+use poise::serenity_prelude as serenity;
+use poise::CreateReply;
+
+/// Add a message as a quote
+///
+/// Adds a message to the database as a quote. The quote text and author are filled in from the message, and can be changed before saving.
+///
+/// To use, right-click the message that you want to quote, then go to "Apps" > "Add Quote".
+///
+/// Requires `Manage Roles` permissions.
+#[poise::command(
+  context_menu_command = "Add Quote",
+  required_permissions = "MANAGE_ROLES",
+  default_member_permissions = "MANAGE_ROLES",
+  category = "Context Menu Commands",
+  guild_only
+)]
+pub async fn add_quote(
+  ctx: poise::ApplicationContext<'_, AppData, AppError>,
+  #[description = "Message to quote"] message: serenity::Message,
+) -> Result<()> {
+  use poise::Modal as _;
+
+  if message.content.trim().is_empty() {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: That message has no text to quote.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let author = match message.author_nick(&ctx).await {
+    Some(nick) => nick,
+    None => message
+      .author
+      .global_name
+      .clone()
+      .unwrap_or_else(|| message.author.name.clone()),
+  };
+
+  // The modal rejects defaults longer than the quote limit
+  let defaults = AddQuoteModal {
+    quote: message.content.trim().chars().take(300).collect(),
+    author: Some(author),
+  };
+
+  let Some(quote_data) = AddQuoteModal::execute_with_defaults(ctx, defaults).await? else {
+    return Ok(());
+  };
+
+  save_quote(ctx, quote_data.quote.as_str(), quote_data.author.as_deref()).await?;
+
+  Ok(())
+}
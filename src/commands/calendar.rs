@@ -0,0 +1,38 @@
+use crate::Context;
+use anyhow::Result;
+use poise::serenity_prelude::CreateAttachment;
+use poise::CreateReply;
+
+/// Get a calendar of upcoming events
+///
+/// Sends a calendar file of the server's upcoming events and monthly challenges, which can be imported into most calendar apps.
+///
+/// If available, also shows a link that calendar apps can subscribe to for automatic updates.
+#[poise::command(slash_command, category = "Utilities", guild_only)]
+pub async fn calendar(ctx: Context<'_>) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let feed = crate::calendar::guild_calendar(ctx.http(), guild_id).await?;
+
+  let content = match std::env::var("API_BASE_URL") {
+    Ok(base_url) => format!(
+      "Here are the upcoming events. To have them update automatically, subscribe to this link in your calendar app:\n<{}/calendar/{guild_id}.ics>",
+      base_url.trim_end_matches('/')
+    ),
+    Err(_) => "Here are the upcoming events. Open the file to add them to your calendar app.".to_string(),
+  };
+
+  ctx
+    .send(
+      CreateReply::default()
+        .content(content)
+        .attachment(CreateAttachment::bytes(feed.into_bytes(), "events.ics"))
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
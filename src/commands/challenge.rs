@@ -1,10 +1,19 @@
-use crate::config::ROLES;
+use crate::commands::emoji;
+use crate::config::{
+  BloomBotEmbed, EmojiKind, CHALLENGE_GOAL_MINUTES, CHALLENGE_GOAL_SESSIONS,
+  CHALLENGE_REMINDER_DAY, ROLES,
+};
+use crate::database::{ChallengeProgress, DatabaseHandler};
 use crate::Context;
 use anyhow::Result;
-use chrono;
-use poise::CreateReply;
+use chrono::{self, Datelike};
+use poise::serenity_prelude::{self as serenity, builder::*};
+use poise::{ChoiceParameter, CreateReply};
 
-#[derive(poise::ChoiceParameter)]
+/// Most participants shown on the challenge leaderboard.
+const LEADERBOARD_SIZE: usize = 10;
+
+#[derive(Clone, Copy, poise::ChoiceParameter)]
 pub enum ChallengeChoices {
   #[name = "Monthly Challenge"]
   Monthly,
@@ -12,13 +21,69 @@ pub enum ChallengeChoices {
   YearRound,
 }
 
+impl ChallengeChoices {
+  /// Name used to store participation in the database.
+  pub fn key(self) -> &'static str {
+    match self {
+      Self::Monthly => "monthly",
+      Self::YearRound => "365",
+    }
+  }
+
+  fn role(self) -> serenity::RoleId {
+    match self {
+      Self::Monthly => serenity::RoleId::new(ROLES.meditation_challenger),
+      Self::YearRound => serenity::RoleId::new(ROLES.meditation_challenger_365),
+    }
+  }
+
+  fn label(self) -> &'static str {
+    match self {
+      Self::Monthly => "monthly",
+      Self::YearRound => "365-day",
+    }
+  }
+
+  /// Start of the current challenge period, which is the month for the monthly challenge and the year for the 365-day challenge.
+  fn period_start(self, now: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::Utc> {
+    let date = match self {
+      Self::Monthly => now.date_naive().with_day(1),
+      Self::YearRound => now.date_naive().with_ordinal(1),
+    };
+
+    date
+      .and_then(|date| date.and_hms_opt(0, 0, 0))
+      .map_or(now, |start| start.and_utc())
+  }
+}
+
+/// How far a participant is toward the monthly challenge goal, as a percentage up to 100.
+///
+/// Both the minutes and the sessions are needed, so the one furthest from its goal is used.
+pub fn goal_percent(progress: &ChallengeProgress) -> i64 {
+  let minutes = progress.minutes * 100 / CHALLENGE_GOAL_MINUTES;
+  let sessions = progress.sessions * 100 / CHALLENGE_GOAL_SESSIONS;
+
+  minutes.min(sessions).clamp(0, 100)
+}
+
+fn reminder_note(challenge: ChallengeChoices, reminders: Option<bool>) -> String {
+  match (challenge, reminders) {
+    (ChallengeChoices::Monthly, Some(true)) => format!(
+      " You'll get a progress reminder by DM on day {CHALLENGE_REMINDER_DAY} of each month."
+    ),
+    (ChallengeChoices::Monthly, Some(false)) => " You won't get progress reminders.".to_string(),
+    _ => String::new(),
+  }
+}
+
 /// Join or leave a meditation challenge
 ///
-/// Join or leave the monthly or 365-day meditation challenge.
+/// Join or leave the monthly or 365-day meditation challenge, or see how participants are doing.
 #[poise::command(
   slash_command,
   category = "Meditation Tracking",
-  subcommands("join", "leave"),
+  subcommands("join", "leave", "stats"),
   guild_only
 )]
 #[allow(clippy::unused_async)]
@@ -28,87 +93,53 @@ pub async fn challenge(_: Context<'_>) -> Result<()> {
 
 /// Join a meditation challenge
 ///
-/// Join the monthly or 365-day meditation challenge.
+/// Join the monthly or 365-day meditation challenge. Participants in the monthly challenge can opt in to a progress reminder by DM in the middle of each month. Joining again changes whether you get reminders.
 #[poise::command(slash_command)]
 pub async fn join(
   ctx: Context<'_>,
   #[description = "Challenge you wish to join (Defaults to monthly)"] challenge: Option<
     ChallengeChoices,
   >,
+  #[description = "Get a progress reminder by DM in the middle of each month (Monthly challenge only)"]
+  reminders: Option<bool>,
 ) -> Result<()> {
+  let data = ctx.data();
+
   let guild_id = ctx.guild_id().unwrap();
   let member = guild_id.member(ctx, ctx.author().id).await?;
 
-  if let Some(challenge) = challenge {
-    match challenge {
-      ChallengeChoices::Monthly => {
-        if ctx
-          .author()
-          .has_role(ctx, guild_id, ROLES.meditation_challenger)
-          .await?
-        {
-          ctx
-            .send(
-              CreateReply::default()
-                .content("You've already joined the monthly challenge. Awesome!")
-                .ephemeral(true),
-            )
-            .await?;
-
-          return Ok(());
-        }
-
-        member.add_role(ctx, ROLES.meditation_challenger).await?;
-
-        ctx.say(format!(
-    "Challenge accepted! You're awesome, <@{}>! Now commit to practicing consistently throughout the month of {} and `/add` your times in this channel. You can use <#534702592245235733> and <#465656096929873942> for extra accountability. Let's do this!",
-    member.user.id,
-    chrono::Utc::now().format("%B"),
-    )).await?;
-
-        return Ok(());
-      }
-      ChallengeChoices::YearRound => {
-        if ctx
-          .author()
-          .has_role(ctx, guild_id, ROLES.meditation_challenger_365)
-          .await?
-        {
-          ctx
-            .send(
-              CreateReply::default()
-                .content("You've already joined the 365-day challenge. Awesome!")
-                .ephemeral(true),
-            )
-            .await?;
-
-          return Ok(());
-        }
-
-        member
-          .add_role(ctx, ROLES.meditation_challenger_365)
-          .await?;
-
-        ctx.say(format!(
-    "Awesome, <@{}>! You have successfully joined the 365-day challenge <:pepeglow:1174181400249901076>",
-    member.user.id,
-    )).await?;
-
-        return Ok(());
-      }
-    }
-  }
-
   // Defaults to monthly
+  let challenge = challenge.unwrap_or(ChallengeChoices::Monthly);
+  let reminders = match challenge {
+    ChallengeChoices::Monthly => reminders,
+    ChallengeChoices::YearRound => None,
+  };
+
+  // Recorded even for members who already have the role, so members who joined before participation was tracked are added
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::join_challenge(
+    &mut transaction,
+    &guild_id,
+    &member.user.id,
+    challenge.key(),
+    reminders,
+  )
+  .await?;
+  DatabaseHandler::commit_transaction(transaction).await?;
+
   if ctx
     .author()
-    .has_role(ctx, guild_id, ROLES.meditation_challenger)
+    .has_role(ctx, guild_id, challenge.role())
     .await?
   {
     ctx
       .send(
         CreateReply::default()
-          .content("You've already joined the monthly challenge. Awesome!")
+          .content(format!(
+            "You've already joined the {} challenge. Awesome!{}",
+            challenge.label(),
+            reminder_note(challenge, reminders)
+          ))
           .ephemeral(true),
       )
       .await?;
@@ -116,13 +147,27 @@ pub async fn join(
     return Ok(());
   }
 
-  member.add_role(ctx, ROLES.meditation_challenger).await?;
+  member.add_role(ctx, challenge.role()).await?;
 
-  ctx.say(format!(
-    "Challenge accepted! You're awesome, <@{}>! Now commit to practicing consistently throughout the month of {} and `/add` your times in this channel. You can use <#534702592245235733> and <#465656096929873942> for extra accountability. Let's do this!",
+  match challenge {
+    ChallengeChoices::Monthly => {
+      ctx.say(format!(
+    "Challenge accepted! You're awesome, <@{}>! Now commit to practicing consistently throughout the month of {} and `/add` your times in this channel. You can use <#534702592245235733> and <#465656096929873942> for extra accountability. Let's do this!{}",
     member.user.id,
     chrono::Utc::now().format("%B"),
+    reminder_note(challenge, reminders),
     )).await?;
+    }
+    ChallengeChoices::YearRound => {
+      ctx
+        .say(format!(
+          "Awesome, <@{}>! You have successfully joined the 365-day challenge {}",
+          member.user.id,
+          emoji(ctx, EmojiKind::Celebrate).await,
+        ))
+        .await?;
+    }
+  }
 
   Ok(())
 }
@@ -137,92 +182,139 @@ pub async fn leave(
     ChallengeChoices,
   >,
 ) -> Result<()> {
+  let data = ctx.data();
+
   let guild_id = ctx.guild_id().unwrap();
   let member = guild_id.member(ctx, ctx.author().id).await?;
 
-  if let Some(challenge) = challenge {
-    match challenge {
-      ChallengeChoices::Monthly => {
-        if ctx
-          .author()
-          .has_role(ctx, guild_id, ROLES.meditation_challenger)
-          .await?
-        {
-          member.remove_role(ctx, ROLES.meditation_challenger).await?;
-
-          ctx
-            .say(format!(
-              "You have successfully opted out of the monthly challenge, <@{}>.",
-              member.user.id,
-            ))
-            .await?;
-
-          return Ok(());
-        }
-
-        ctx
-          .send(CreateReply::default()
-          .content("You're not currently participating in the monthly challenge. If you want to join, use `/challenge join`.")
-          .ephemeral(true)
-          )
-          .await?;
-
-        return Ok(());
-      }
-      ChallengeChoices::YearRound => {
-        if ctx
-          .author()
-          .has_role(ctx, guild_id, ROLES.meditation_challenger_365)
-          .await?
-        {
-          member
-            .remove_role(ctx, ROLES.meditation_challenger_365)
-            .await?;
-
-          ctx
-            .say(format!(
-              "You have successfully opted out of the 365-day challenge, <@{}>.",
-              member.user.id,
-            ))
-            .await?;
-
-          return Ok(());
-        }
-
-        ctx
-          .send(CreateReply::default()
-          .content("You're not currently participating in the 365-day challenge. If you want to join, use `/challenge join`.")
-          .ephemeral(true)
-          )
-          .await?;
-
-        return Ok(());
-      }
-    }
-  }
-
   // Defaults to monthly
-  if ctx
+  let challenge = challenge.unwrap_or(ChallengeChoices::Monthly);
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed = DatabaseHandler::leave_challenge(
+    &mut transaction,
+    &guild_id,
+    &member.user.id,
+    challenge.key(),
+  )
+  .await?;
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  let has_role = ctx
     .author()
-    .has_role(ctx, guild_id, ROLES.meditation_challenger)
-    .await?
-  {
-    member.remove_role(ctx, ROLES.meditation_challenger).await?;
+    .has_role(ctx, guild_id, challenge.role())
+    .await?;
 
+  if !has_role && removed == 0 {
     ctx
-      .say(format!(
-        "You have successfully opted out of the monthly challenge, <@{}>.",
-        member.user.id,
-      ))
+      .send(CreateReply::default()
+      .content(format!("You're not currently participating in the {} challenge. If you want to join, use `/challenge join`.", challenge.label()))
+      .ephemeral(true)
+      )
       .await?;
 
     return Ok(());
   }
 
+  if has_role {
+    member.remove_role(ctx, challenge.role()).await?;
+  }
+
+  ctx
+    .say(format!(
+      "You have successfully opted out of the {} challenge, <@{}>.",
+      challenge.label(),
+      member.user.id,
+    ))
+    .await?;
+
+  Ok(())
+}
+
+/// See how challenge participants are doing
+///
+/// Shows the challenge leaderboard, ranking members who joined with `/challenge join` by minutes meditated this month, or this year for the 365-day challenge. Members with private stats are not shown.
+///
+/// The monthly challenge leaderboard also shows progress toward the goal of 30 minutes over 8 sessions.
+#[poise::command(slash_command)]
+pub async fn stats(
+  ctx: Context<'_>,
+  #[description = "Challenge to show (Defaults to monthly)"] challenge: Option<ChallengeChoices>,
+) -> Result<()> {
+  ctx.defer().await?;
+
+  let data = ctx.data();
+
+  let guild_id = ctx.guild_id().unwrap();
+
+  let challenge = challenge.unwrap_or(ChallengeChoices::Monthly);
+  let now = chrono::Utc::now();
+  let start = challenge.period_start(now);
+
+  let mut participants = DatabaseHandler::with_retry(3, || async move {
+    let mut transaction = data.db.start_transaction_with_retry(5).await?;
+    let limits = DatabaseHandler::get_session_limits(&mut transaction, &guild_id).await?;
+    let participants = DatabaseHandler::get_challenge_progress(
+      &mut transaction,
+      &guild_id,
+      None,
+      challenge.key(),
+      start,
+      now,
+      limits.daily_cap_minutes,
+    )
+    .await?;
+    Ok(participants)
+  })
+  .await?;
+
+  participants.retain(|participant| !participant.stats_private && participant.minutes > 0);
+  participants.sort_by_key(|participant| std::cmp::Reverse(participant.minutes));
+
+  let period = match challenge {
+    ChallengeChoices::Monthly => now.format("%B %Y").to_string(),
+    ChallengeChoices::YearRound => now.format("%Y").to_string(),
+  };
+
+  let description = if participants.is_empty() {
+    format!("No challenge participants have meditated in {period} yet.")
+  } else {
+    participants
+      .iter()
+      .take(LEADERBOARD_SIZE)
+      .zip(1..)
+      .map(|(participant, rank)| {
+        let goal = match challenge {
+          ChallengeChoices::Monthly => format!(" ({}% of goal)", goal_percent(participant)),
+          ChallengeChoices::YearRound => String::new(),
+        };
+        format!(
+          "**{rank}.** <@{}> — {} minutes, {} sessions{goal}",
+          participant.user_id, participant.minutes, participant.sessions
+        )
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  };
+
+  let mut embed = BloomBotEmbed::new()
+    .title(format!("{} Leaderboard", challenge.name()))
+    .description(description)
+    .footer(CreateEmbedFooter::new(period));
+
+  if matches!(challenge, ChallengeChoices::Monthly) {
+    let completed = participants
+      .iter()
+      .filter(|participant| goal_percent(participant) >= 100)
+      .count();
+    embed = embed.field("Goal Reached", format!("```{completed} members```"), true);
+  }
+
   ctx
-    .send(CreateReply::default()
-    .content("You're not currently participating in the monthly challenge. If you want to join, use `/challenge join`.")
-    .ephemeral(true)
+    .send(
+      CreateReply::default()
+        .embed(embed)
+        .allowed_mentions(serenity::CreateAllowedMentions::new()),
     )
     .await?;
 
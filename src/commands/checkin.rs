@@ -0,0 +1,157 @@
+use crate::charts;
+use crate::commands::helpers::rate_limit::throttle_images;
+use crate::commands::stats::Theme;
+use crate::config::BloomBotEmbed;
+use crate::database::DatabaseHandler;
+use crate::Context;
+use anyhow::Result;
+use poise::serenity_prelude::CreateAttachment;
+use poise::CreateReply;
+
+/// Weeks shown in the mood trend chart.
+const MOOD_TREND_WEEKS: i32 = 12;
+
+/// Fewest responses in a week before its average mood is shown, so no one's score can be worked out.
+const MIN_MOOD_RESPONSES: i64 = 3;
+
+/// Share how you're doing
+///
+/// Commands to anonymously share how you're feeling, and to see how the community is doing over time.
+#[poise::command(
+  slash_command,
+  category = "Meditation Tracking",
+  subcommands("mood", "trends"),
+  guild_only
+)]
+#[allow(clippy::unused_async)]
+pub async fn checkin(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Anonymously share how you're feeling
+///
+/// Records how you're feeling this week on a scale from 1 (struggling) to 5 (thriving). Scores are anonymous and only shown as a weekly average for the whole community. Checking in again the same week replaces your score.
+#[poise::command(slash_command)]
+pub async fn mood(
+  ctx: Context<'_>,
+  #[description = "How you're feeling, from 1 (struggling) to 5 (thriving)"]
+  #[min = 1]
+  #[max = 5]
+  score: i16,
+) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  // Without a secret, the stored hashes could be matched to members by hashing every member ID
+  let Ok(secret) = std::env::var("MOOD_HASH_SECRET") else {
+    ctx
+      .say(":x: Mood check-ins are not available right now.")
+      .await?;
+    return Ok(());
+  };
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let replaced = DatabaseHandler::set_mood_checkin(
+    &mut transaction,
+    &guild_id,
+    &ctx.author().id,
+    &secret,
+    score,
+  )
+  .await?;
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  let content = if replaced {
+    ":white_check_mark: Your score for this week has been updated. Thank you for checking in."
+  } else {
+    ":white_check_mark: Thank you for checking in. Your score is anonymous and only counts toward the community's weekly average."
+  };
+
+  ctx.say(content).await?;
+
+  Ok(())
+}
+
+/// See how the community is feeling
+///
+/// Shows the community's average mood each week alongside the minutes meditated, for the past 12 weeks. Weeks with fewer than 3 check-ins don't show a mood, to keep scores anonymous.
+#[poise::command(slash_command)]
+pub async fn trends(
+  ctx: Context<'_>,
+  #[description = "Toggle between light mode and dark mode (Defaults to dark mode)"] theme: Option<
+    Theme,
+  >,
+) -> Result<()> {
+  if !throttle_images(ctx).await? {
+    return Ok(());
+  }
+
+  ctx.defer().await?;
+
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let (weeks, palette) = DatabaseHandler::with_retry(3, || async move {
+    let mut transaction = data.db.start_transaction_with_retry(5).await?;
+    let weeks =
+      DatabaseHandler::get_mood_trend(&mut transaction, &guild_id, MOOD_TREND_WEEKS).await?;
+    let palette = DatabaseHandler::get_chart_palette(&mut transaction, &guild_id).await?;
+    Ok((weeks, palette))
+  })
+  .await?;
+
+  let light_mode = matches!(theme, Some(Theme::LightMode));
+
+  let chart = charts::ChartDrawer::new()?
+    .locale(ctx.locale())
+    .draw_mood_trend(
+      &weeks,
+      MIN_MOOD_RESPONSES,
+      charts::bar_color(palette, light_mode),
+      light_mode,
+    )
+    .await?;
+
+  let this_week = weeks.last().map_or(0, |week| week.responses);
+  let shown = weeks
+    .iter()
+    .filter(|week| week.responses >= MIN_MOOD_RESPONSES)
+    .collect::<Vec<_>>();
+  let responses = shown.iter().map(|week| week.responses).sum::<i64>();
+
+  let average = if responses > 0 {
+    #[allow(clippy::cast_precision_loss)]
+    let total = shown
+      .iter()
+      .map(|week| week.average_score.unwrap_or_default() * week.responses as f64)
+      .sum::<f64>();
+    #[allow(clippy::cast_precision_loss)]
+    let average = total / responses as f64;
+    format!("```{average:.1} / 5```")
+  } else {
+    "```Not enough check-ins yet```".to_string()
+  };
+
+  let embed = BloomBotEmbed::new()
+    .title("Community Mood")
+    .description("Check in anonymously with `/checkin mood`.")
+    .field("Check-Ins This Week", format!("```{this_week}```"), true)
+    .field("Average Mood (12 Weeks)", average, true)
+    .image(chart.get_attachment_url());
+
+  ctx
+    .send(
+      CreateReply::default()
+        .embed(embed)
+        .attachment(CreateAttachment::path(chart.get_file_path()).await?),
+    )
+    .await?;
+
+  Ok(())
+}
@@ -0,0 +1,280 @@
+use crate::commands::{commit_and_say, MessageType};
+use crate::config::BellSound;
+use crate::database::DatabaseHandler;
+use crate::Context;
+use anyhow::Result;
+use poise::serenity_prelude::{self as serenity, Mentionable};
+use poise::{ChoiceParameter, CreateReply};
+
+/// Commands for running community sits
+///
+/// Commands for setting up scheduled community sits.
+///
+/// Requires `Manage Events` permissions.
+#[poise::command(
+  slash_command,
+  required_permissions = "MANAGE_EVENTS",
+  default_member_permissions = "MANAGE_EVENTS",
+  category = "Moderator Commands",
+  subcommands("bells"),
+  subcommand_required,
+  //hide_in_help,
+  guild_only
+)]
+#[allow(clippy::unused_async)]
+pub async fn community_sit(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Manage bells for community sits
+///
+/// Set up bells that Bloom rings in the voice channel while a scheduled community sit is running.
+#[poise::command(
+  slash_command,
+  subcommands("bells_set", "bells_remove", "bells_list"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn bells(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+async fn autocomplete_event(ctx: Context<'_>, partial: &str) -> Vec<serenity::AutocompleteChoice> {
+  let Some(guild_id) = ctx.guild_id() else {
+    return Vec::new();
+  };
+
+  let Ok(events) = guild_id.scheduled_events(ctx, false).await else {
+    return Vec::new();
+  };
+
+  let partial = partial.to_lowercase();
+
+  events
+    .into_iter()
+    .filter(|event| event.name.to_lowercase().contains(&partial))
+    .take(25)
+    .map(|event| serenity::AutocompleteChoice::new(event.name, event.id.to_string()))
+    .collect()
+}
+
+/// Looks up a scheduled event chosen from autocomplete.
+async fn find_event(
+  ctx: Context<'_>,
+  guild_id: serenity::GuildId,
+  event: &str,
+) -> Option<serenity::ScheduledEvent> {
+  let event_id = event.parse::<u64>().ok().filter(|id| *id != 0)?;
+
+  guild_id
+    .scheduled_event(ctx, serenity::ScheduledEventId::new(event_id), false)
+    .await
+    .ok()
+}
+
+/// Ring bells during a community sit
+///
+/// Sets up bells for a scheduled community sit. When the event starts, Bloom joins its voice channel and rings a bell, then rings interval bells if set. Three ending bells are rung after the set duration, or when the event ends.
+///
+/// The event must take place in a voice channel where time is tracked. Using this on an event that already has bells updates them.
+#[poise::command(slash_command, rename = "set")]
+pub async fn bells_set(
+  ctx: Context<'_>,
+  #[description = "The scheduled event for the sit"]
+  #[autocomplete = "autocomplete_event"]
+  event: String,
+  #[description = "Sound of the bells (Defaults to singing bowl)"] sound: Option<BellSound>,
+  #[description = "Minutes between interval bells (Defaults to none)"]
+  #[min = 1]
+  #[max = 120]
+  interval: Option<i32>,
+  #[description = "Minutes until the ending bells (Defaults to when the event ends)"]
+  #[min = 1]
+  #[max = 360]
+  duration: Option<i32>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let Some(event) = find_event(ctx, guild_id, &event).await else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Event not found. Please choose an event from the list.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let tracked = DatabaseHandler::get_tracking_voice_channels(&mut transaction, &guild_id).await?;
+
+  let Some(channel_id) = event.channel_id.filter(|channel_id| {
+    tracked
+      .iter()
+      .any(|tracked| tracked.channel_id == *channel_id)
+  }) else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Bells can only be rung for events in a voice channel where time is tracked. Use `/manage voice track` to track the channel first.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  if let (Some(interval), Some(duration)) = (interval, duration) {
+    if interval >= duration {
+      ctx
+        .send(
+          CreateReply::default()
+            .content(":x: The interval must be shorter than the duration.")
+            .ephemeral(true),
+        )
+        .await?;
+      return Ok(());
+    }
+  }
+
+  let sound = sound.unwrap_or(BellSound::Bowl);
+
+  DatabaseHandler::set_sit_bells(
+    &mut transaction,
+    &guild_id,
+    &event.id,
+    interval,
+    duration,
+    sound.key(),
+  )
+  .await?;
+
+  let interval_text = match interval {
+    Some(interval) => format!(" every **{interval}** minutes"),
+    None => " at the start".to_string(),
+  };
+  let ending_text = match duration {
+    Some(duration) => format!("after **{duration}** minutes"),
+    None => "when the event ends".to_string(),
+  };
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: A {} will ring{interval_text} in {} during **{}**, with ending bells {ending_text}.",
+      sound.name(),
+      channel_id.mention(),
+      event.name,
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Stop ringing bells during a community sit
+///
+/// Removes the bells from a scheduled community sit. Bells already ringing stop when the event ends.
+#[poise::command(slash_command, rename = "remove")]
+pub async fn bells_remove(
+  ctx: Context<'_>,
+  #[description = "The scheduled event for the sit"]
+  #[autocomplete = "autocomplete_event"]
+  event: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let event_id = event
+    .parse::<u64>()
+    .ok()
+    .filter(|id| *id != 0)
+    .map(serenity::ScheduledEventId::new);
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed = match event_id {
+    Some(event_id) => {
+      DatabaseHandler::remove_sit_bells(&mut transaction, &guild_id, &event_id).await?
+    }
+    None => 0,
+  };
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: That event doesn't have bells.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(":white_check_mark: Bells removed from the event.".to_string()),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// List community sits with bells
+///
+/// Lists the scheduled events that have bells, and how the bells are rung.
+#[poise::command(slash_command, rename = "list")]
+pub async fn bells_list(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let sit_bells = DatabaseHandler::get_sit_bells(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let content = if sit_bells.is_empty() {
+    "No events have bells.".to_string()
+  } else {
+    let events = guild_id.scheduled_events(ctx, false).await?;
+
+    sit_bells
+      .iter()
+      .map(|bells| {
+        let name = events
+          .iter()
+          .find(|event| event.id == bells.event_id)
+          .map_or("Past event", |event| event.name.as_str());
+        let sound = BellSound::from_key(&bells.sound).map_or("bell", |sound| sound.name());
+        let interval = bells
+          .interval_minutes
+          .map_or("no interval bells".to_string(), |minutes| {
+            format!("every {minutes} minutes")
+          });
+        let duration = bells
+          .duration_minutes
+          .map_or("until the event ends".to_string(), |minutes| {
+            format!("for {minutes} minutes")
+          });
+
+        format!("- **{name}**: {sound}, {interval}, {duration}")
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  };
+
+  ctx
+    .send(CreateReply::default().content(content).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
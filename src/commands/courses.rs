@@ -1,14 +1,60 @@
+use crate::commands::helpers::time::parse_time_of_day;
 use crate::commands::{commit_and_say, course_not_found, MessageType};
+use crate::config::BloomBotEmbed;
 use crate::database::DatabaseHandler;
 use crate::pagination::{PageRowRef, Pagination};
 use crate::Context;
 use anyhow::Result;
-use poise::serenity_prelude::{self as serenity, builder::*};
-use poise::CreateReply;
+use futures::StreamExt;
+use log::error;
+use poise::serenity_prelude::{self as serenity, builder::*, Mentionable};
+use poise::{ChoiceParameter, CreateReply};
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum Weekday {
+  Monday,
+  Tuesday,
+  Wednesday,
+  Thursday,
+  Friday,
+  Saturday,
+  Sunday,
+}
+
+impl Weekday {
+  pub const ALL: [Self; 7] = [
+    Self::Monday,
+    Self::Tuesday,
+    Self::Wednesday,
+    Self::Thursday,
+    Self::Friday,
+    Self::Saturday,
+    Self::Sunday,
+  ];
+
+  /// Days from Monday, as stored in the database.
+  pub fn days_from_monday(self) -> i16 {
+    match self {
+      Self::Monday => 0,
+      Self::Tuesday => 1,
+      Self::Wednesday => 2,
+      Self::Thursday => 3,
+      Self::Friday => 4,
+      Self::Saturday => 5,
+      Self::Sunday => 6,
+    }
+  }
+
+  pub fn from_days_from_monday(days: i16) -> Option<Self> {
+    Self::ALL
+      .into_iter()
+      .find(|weekday| weekday.days_from_monday() == days)
+  }
+}
 
 /// Commands for managing courses
 ///
-/// Commands to add, edit, list, or remove courses.
+/// Commands to add, edit, list, or remove courses, see how a course is going, or schedule weekly announcements for course participants.
 ///
 /// Requires `Administrator` permissions.
 #[poise::command(
@@ -16,7 +62,7 @@ use poise::CreateReply;
   required_permissions = "ADMINISTRATOR",
   default_member_permissions = "ADMINISTRATOR",
   category = "Admin Commands",
-  subcommands("add", "remove", "edit", "list"),
+  subcommands("add", "remove", "edit", "list", "stats", "reminders"),
   subcommand_required,
   //hide_in_help,
   guild_only
@@ -26,16 +72,97 @@ pub async fn course(_: Context<'_>) -> Result<()> {
   Ok(())
 }
 
+/// Parses a hex color such as `#5865F2` or `5865F2`.
+fn parse_color(input: &str) -> Option<serenity::Colour> {
+  let hex = input.trim().trim_start_matches('#');
+  if hex.len() != 6 {
+    return None;
+  }
+
+  u32::from_str_radix(hex, 16).ok().map(serenity::Colour::new)
+}
+
+/// Roles and channel permissions set up while adding a course, so they can be undone if a later step fails.
+#[derive(Default)]
+struct Provisioned {
+  roles: Vec<serenity::RoleId>,
+  /// The private course channel and its permission overwrites from before they were changed.
+  channel: Option<(serenity::ChannelId, Vec<serenity::PermissionOverwrite>)>,
+  /// Permission overwrites changed on the private course channel.
+  overwritten: Vec<serenity::PermissionOverwriteType>,
+}
+
+impl Provisioned {
+  /// Restores the channel's permission overwrites and deletes created roles. Errors are logged, so every step is attempted.
+  async fn rollback(self, ctx: Context<'_>, guild_id: serenity::GuildId) {
+    if let Some((channel_id, original)) = self.channel {
+      for kind in self.overwritten {
+        let result = match original.iter().find(|overwrite| overwrite.kind == kind) {
+          Some(overwrite) => channel_id.create_permission(ctx, overwrite.clone()).await,
+          None => channel_id.delete_permission(ctx, kind).await,
+        };
+        if let Err(e) = result {
+          error!("Could not restore permissions for channel {channel_id}: {e:?}");
+        }
+      }
+    }
+
+    for role_id in self.roles {
+      if let Err(e) = guild_id.delete_role(ctx, role_id).await {
+        error!("Could not delete role {role_id} created for a course: {e:?}");
+      }
+    }
+  }
+}
+
+/// Checks that a role can be used for a course, telling the user if it can't.
+async fn check_course_role(
+  ctx: Context<'_>,
+  guild_id: serenity::GuildId,
+  role: &serenity::Role,
+  kind: &str,
+) -> Result<bool> {
+  let problem = if role.guild_id != guild_id {
+    "be in the same guild as the command"
+  } else if role.managed {
+    // Verify that the role is not managed by an integration
+    "not be a bot role"
+  } else if role.permissions.administrator() {
+    // Verify that the role is not privileged
+    "not be an administrator role"
+  } else {
+    return Ok(true);
+  };
+
+  ctx
+    .say(format!(":x: The {kind} role must {problem}."))
+    .await?;
+
+  Ok(false)
+}
+
 /// Add a course and its associated graduate role to the database
 ///
 /// Adds a course and its associated graduate role to the database.
+///
+/// Leave out the participant or graduate role to have it created, optionally with a color. A private course channel can also be given, which is then hidden from everyone except participants and graduates. If any step fails, created roles and channel permission changes are undone.
 #[poise::command(slash_command)]
 pub async fn add(
   ctx: Context<'_>,
   #[description = "Name of the course"] course_name: String,
-  #[description = "The role participants of the course are assumed to have"]
-  participant_role: serenity::Role,
-  #[description = "Role to be given to graduates"] graduate_role: serenity::Role,
+  #[description = "The role participants of the course are assumed to have (Defaults to creating one)"]
+  participant_role: Option<serenity::Role>,
+  #[description = "Role to be given to graduates (Defaults to creating one)"] graduate_role: Option<
+    serenity::Role,
+  >,
+  #[description = "Color for a created participant role, such as #5865F2"]
+  participant_color: Option<String>,
+  #[description = "Color for a created graduate role, such as #57F287"] graduate_color: Option<
+    String,
+  >,
+  #[description = "A channel to make private to participants and graduates"]
+  #[channel_types("Text", "Forum")]
+  private_channel: Option<serenity::GuildChannel>,
 ) -> Result<()> {
   ctx.defer_ephemeral().await?;
 
@@ -50,71 +177,144 @@ pub async fn add(
     return Ok(());
   }
 
-  // Verify that the roles are in the guild
-  if !participant_role.guild_id.eq(&guild_id) {
-    ctx
-      .say(":x: The participant role must be in the same guild as the command.")
-      .await?;
-    return Ok(());
+  if let Some(participant_role) = &participant_role {
+    if !check_course_role(ctx, guild_id, participant_role, "participant").await? {
+      return Ok(());
+    }
   }
-  if !graduate_role.guild_id.eq(&guild_id) {
-    ctx
-      .say(":x: The graduate role must be in the same guild as the command.")
-      .await?;
-    return Ok(());
+  if let Some(graduate_role) = &graduate_role {
+    if !check_course_role(ctx, guild_id, graduate_role, "graduate").await? {
+      return Ok(());
+    }
   }
 
-  // Verify that the roles are not managed by an integration
-  if participant_role.managed {
-    ctx
-      .say(":x: The participant role must not be a bot role.")
-      .await?;
-    return Ok(());
-  }
-  if graduate_role.managed {
-    ctx
-      .say(":x: The graduate role must not be a bot role.")
-      .await?;
-    return Ok(());
+  if let (Some(participant_role), Some(graduate_role)) = (&participant_role, &graduate_role) {
+    if participant_role == graduate_role {
+      ctx
+        .say(":x: The participant role and the graduate role must not be the same.")
+        .await?;
+      return Ok(());
+    }
   }
 
-  // Verify that the roles are not privileged
-  if participant_role.permissions.administrator() {
-    ctx
-      .say(":x: The participant role must not be an administrator role.")
-      .await?;
-    return Ok(());
+  let mut colors = [None, None];
+  for (color, input) in colors.iter_mut().zip([&participant_color, &graduate_color]) {
+    if let Some(input) = input {
+      let Some(parsed) = parse_color(input) else {
+        ctx
+          .say(":x: Please specify colors as hex codes, such as `#5865F2`.")
+          .await?;
+        return Ok(());
+      };
+      *color = Some(parsed);
+    }
   }
-  if graduate_role.permissions.administrator() {
-    ctx
-      .say(":x: The graduate role must not be an administrator role.")
-      .await?;
-    return Ok(());
+  let [participant_color, graduate_color] = colors;
+
+  if let Some(channel) = &private_channel {
+    if channel.guild_id != guild_id {
+      ctx
+        .say(":x: The private channel must be in the same guild as the command.")
+        .await?;
+      return Ok(());
+    }
   }
 
-  if participant_role == graduate_role {
-    ctx
-      .say(":x: The participant role and the graduate role must not be the same.")
-      .await?;
-    return Ok(());
-  }
+  let mut provisioned = Provisioned::default();
+
+  let result = async {
+    let participant_role = match participant_role {
+      Some(participant_role) => participant_role,
+      None => {
+        let mut role = EditRole::new().name(format!("{course_name} Participant"));
+        if let Some(color) = participant_color {
+          role = role.colour(color);
+        }
+        let role = guild_id.create_role(ctx, role).await?;
+        provisioned.roles.push(role.id);
+        role
+      }
+    };
+
+    let graduate_role = match graduate_role {
+      Some(graduate_role) => graduate_role,
+      None => {
+        let mut role = EditRole::new().name(format!("{course_name} Graduate"));
+        if let Some(color) = graduate_color {
+          role = role.colour(color);
+        }
+        let role = guild_id.create_role(ctx, role).await?;
+        provisioned.roles.push(role.id);
+        role
+      }
+    };
+
+    if let Some(channel) = &private_channel {
+      provisioned.channel = Some((channel.id, channel.permission_overwrites.clone()));
+
+      let overwrites = [
+        (guild_id.everyone_role(), false),
+        (participant_role.id, true),
+        (graduate_role.id, true),
+      ];
+      for (role_id, can_view) in overwrites {
+        let kind = serenity::PermissionOverwriteType::Role(role_id);
+        provisioned.overwritten.push(kind);
+
+        let (allow, deny) = if can_view {
+          (
+            serenity::Permissions::VIEW_CHANNEL,
+            serenity::Permissions::empty(),
+          )
+        } else {
+          (
+            serenity::Permissions::empty(),
+            serenity::Permissions::VIEW_CHANNEL,
+          )
+        };
+        channel
+          .id
+          .create_permission(ctx, serenity::PermissionOverwrite { allow, deny, kind })
+          .await?;
+      }
+    }
 
-  DatabaseHandler::add_course(
-    &mut transaction,
-    &guild_id,
-    course_name.as_str(),
-    &participant_role,
-    &graduate_role,
-  )
-  .await?;
+    DatabaseHandler::add_course(
+      &mut transaction,
+      &guild_id,
+      course_name.as_str(),
+      &participant_role,
+      &graduate_role,
+    )
+    .await?;
 
-  commit_and_say(
-    ctx,
-    transaction,
-    MessageType::TextOnly(":white_check_mark: Course has been added.".to_string()),
-    true,
-  )
-  .await?;
+    let mut summary = vec![
+      format!("Participants: {}", participant_role.mention()),
+      format!("Graduates: {}", graduate_role.mention()),
+    ];
+    if let Some(channel) = &private_channel {
+      summary.push(format!("Private channel: {}", channel.mention()));
+    }
+
+    commit_and_say(
+      ctx,
+      transaction,
+      MessageType::TextOnly(format!(
+        ":white_check_mark: Course has been added.\n{}",
+        summary.join("\n")
+      )),
+      true,
+    )
+    .await?;
+
+    anyhow::Ok(())
+  }
+  .await;
+
+  if let Err(e) = result {
+    provisioned.rollback(ctx, guild_id).await;
+    return Err(e);
+  }
 
   Ok(())
 }
@@ -319,6 +519,97 @@ pub async fn list(
   Ok(())
 }
 
+/// Show statistics for a course
+///
+/// Shows how many members are enrolled in a course and how many have graduated, along with the average meditation time of participants since the course was added.
+#[poise::command(slash_command)]
+pub async fn stats(
+  ctx: Context<'_>,
+  #[description = "Name of the course"] course_name: String,
+) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let course =
+    DatabaseHandler::get_course(&mut transaction, &guild_id, course_name.as_str()).await?;
+
+  let Some(course) = course else {
+    course_not_found(ctx, &mut transaction, guild_id, course_name).await?;
+    return Ok(());
+  };
+
+  let started_at =
+    DatabaseHandler::get_course_created_at(&mut transaction, &guild_id, &course.course_name)
+      .await?
+      .unwrap_or_default();
+  drop(transaction);
+
+  // Role membership isn't stored, so members are fetched when the command is used
+  let mut enrolled = Vec::new();
+  let mut graduates: usize = 0;
+  let mut members = guild_id.members_iter(ctx).boxed();
+  while let Some(member) = members.next().await {
+    let member = member?;
+    let is_graduate = member.roles.contains(&course.graduate_role);
+    if is_graduate {
+      graduates += 1;
+    }
+    if is_graduate || member.roles.contains(&course.participant_role) {
+      enrolled.push(member.user.id);
+    }
+  }
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let totals =
+    DatabaseHandler::get_users_totals(&mut transaction, &guild_id, &enrolled, started_at).await?;
+  drop(transaction);
+
+  let total_minutes: i64 = totals.iter().map(|totals| totals.minutes).sum();
+  let active = totals.iter().filter(|totals| totals.minutes > 0).count();
+
+  // Counts and minutes are far below the point where f64 loses precision
+  #[allow(clippy::cast_precision_loss)]
+  let (completion_rate, average_minutes) = if enrolled.is_empty() {
+    (0.0, 0.0)
+  } else {
+    (
+      graduates as f64 / enrolled.len() as f64 * 100.0,
+      total_minutes as f64 / enrolled.len() as f64,
+    )
+  };
+
+  let embed = BloomBotEmbed::new()
+    .title(format!("{} Statistics", course.course_name))
+    .field("Enrolled", format!("```{}```", enrolled.len()), true)
+    .field("Graduated", format!("```{graduates}```"), true)
+    .field(
+      "Completion Rate",
+      format!("```{completion_rate:.1}%```"),
+      true,
+    )
+    .field(
+      "Average Meditation",
+      format!("```{average_minutes:.0} minutes```"),
+      true,
+    )
+    .field("Meditating Participants", format!("```{active}```"), true)
+    .footer(CreateEmbedFooter::new(format!(
+      "Enrolled members have the participant or graduate role. Meditation is counted since {}.",
+      started_at.format("%Y-%m-%d")
+    )));
+
+  ctx
+    .send(CreateReply::default().embed(embed).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
 /// Remove a course from the database
 ///
 /// Removes a course from the database.
@@ -352,3 +643,180 @@ pub async fn remove(
 
   Ok(())
 }
+
+/// Manage weekly course announcements
+///
+/// Commands to schedule, remove, or list weekly announcements for course participants, such as lesson releases.
+#[poise::command(
+  slash_command,
+  subcommands("reminders_set", "reminders_remove", "reminders_list"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn reminders(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Schedule a weekly announcement for a course
+///
+/// Schedules a weekly announcement that mentions the course's participant role, such as for lesson releases. Announcements stop automatically after the end date. Note that all times are in UTC.
+///
+/// Each course has one weekly announcement. Using this on a course that already has one replaces it.
+#[poise::command(slash_command, rename = "set")]
+pub async fn reminders_set(
+  ctx: Context<'_>,
+  #[description = "Name of the course"] course_name: String,
+  #[description = "The channel to send the announcement in"]
+  #[channel_types("Text")]
+  channel: serenity::GuildChannel,
+  #[description = "The day of the week to send the announcement"] weekday: Weekday,
+  #[description = "The time to send the announcement in UTC, such as 18:00 or 6pm"] time: String,
+  #[description = "The last day of the course, as YYYY-MM-DD"] end_date: String,
+  #[description = "The announcement to send"]
+  #[max_length = 2000]
+  message: String,
+) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let Some(send_minute) = parse_time_of_day(&time) else {
+    ctx
+      .say(":x: Please specify a time such as `18:00` or `6pm`.")
+      .await?;
+    return Ok(());
+  };
+
+  let Ok(ends_on) = chrono::NaiveDate::parse_from_str(end_date.trim(), "%Y-%m-%d") else {
+    ctx
+      .say(":x: Please specify the end date as `YYYY-MM-DD`.")
+      .await?;
+    return Ok(());
+  };
+
+  if ends_on < chrono::Utc::now().date_naive() {
+    ctx.say(":x: The end date must not be in the past.").await?;
+    return Ok(());
+  }
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let course =
+    DatabaseHandler::get_course(&mut transaction, &guild_id, course_name.as_str()).await?;
+
+  let Some(course) = course else {
+    course_not_found(ctx, &mut transaction, guild_id, course_name).await?;
+    return Ok(());
+  };
+
+  DatabaseHandler::set_course_reminder(
+    &mut transaction,
+    &guild_id,
+    &course.course_name,
+    &channel.id,
+    weekday.days_from_monday(),
+    send_minute,
+    &message,
+    ends_on,
+  )
+  .await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Announcements for **{}** will be sent in {} every {} at **{:02}:{:02}** UTC until {}.",
+      course.course_name,
+      channel.mention(),
+      weekday.name(),
+      send_minute / 60,
+      send_minute % 60,
+      ends_on.format("%Y-%m-%d"),
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Remove a course's weekly announcement
+///
+/// Stops the weekly announcement for a course before its end date.
+#[poise::command(slash_command, rename = "remove")]
+pub async fn reminders_remove(
+  ctx: Context<'_>,
+  #[description = "Name of the course"] course_name: String,
+) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed =
+    DatabaseHandler::remove_course_reminder(&mut transaction, &guild_id, course_name.as_str())
+      .await?;
+
+  if removed == 0 {
+    ctx
+      .say(":x: That course has no weekly announcement.")
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(":white_check_mark: Weekly announcement has been removed.".to_string()),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// List weekly course announcements
+///
+/// Lists the courses with weekly announcements, and when they are sent.
+#[poise::command(slash_command, rename = "list")]
+pub async fn reminders_list(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let reminders = DatabaseHandler::get_course_reminders(&mut transaction, Some(&guild_id)).await?;
+  drop(transaction);
+
+  let content = if reminders.is_empty() {
+    "No courses have weekly announcements.".to_string()
+  } else {
+    reminders
+      .iter()
+      .map(|reminder| {
+        format!(
+          "- **{}**: {} every {} at {:02}:{:02} UTC, until {}",
+          reminder.course_name,
+          reminder.channel_id.mention(),
+          Weekday::from_days_from_monday(reminder.weekday).map_or("week", |weekday| weekday.name()),
+          reminder.send_minute / 60,
+          reminder.send_minute % 60,
+          reminder.ends_on.format("%Y-%m-%d"),
+        )
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  };
+
+  ctx
+    .send(CreateReply::default().content(content).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
@@ -1,11 +1,14 @@
+use crate::commands::helpers::time::parse_time_of_day;
 use crate::commands::{commit_and_say, MessageType};
-use crate::config::{BloomBotEmbed, StreakRoles};
-use crate::database::{DatabaseHandler, TrackingProfile};
+use crate::config::{BloomBotEmbed, CongratsDelivery, StreakRoles};
+use crate::database::{DatabaseHandler, QuietHours, TrackingProfile};
+use crate::notifications;
 use crate::Context;
 use anyhow::Result;
 use log::error;
 use poise::serenity_prelude::{self as serenity, builder::*};
 use poise::{ChoiceParameter, CreateReply};
+use rand::Rng;
 
 #[derive(poise::ChoiceParameter)]
 pub enum MinusOffsetChoices {
@@ -95,12 +98,64 @@ pub enum PlusOffsetChoices {
   UTCPlus14,
 }
 
-#[derive(poise::ChoiceParameter)]
-pub enum Privacy {
-  #[name = "private"]
-  Private,
-  #[name = "public"]
-  Public,
+/// Name of the time zone choice for no UTC offset.
+const UTC_NAME: &str = "UTC+0 (GMT, UTC, WET)";
+
+/// Minutes to wait for the settings hub's menus to be used before they're removed.
+const SETTINGS_TIMEOUT_MINUTES: u64 = 10;
+
+impl MinusOffsetChoices {
+  fn minutes(&self) -> i16 {
+    match self {
+      Self::UTCMinus12 => -720,
+      Self::UTCMinus11 => -660,
+      Self::UTCMinus10 => -600,
+      Self::UTCMinus9_30 => -570,
+      Self::UTCMinus9 => -540,
+      Self::UTCMinus8 => -480,
+      Self::UTCMinus7 => -420,
+      Self::UTCMinus6 => -360,
+      Self::UTCMinus5 => -300,
+      Self::UTCMinus4_30 => -270,
+      Self::UTCMinus4 => -240,
+      Self::UTCMinus3_30 => -210,
+      Self::UTCMinus3 => -180,
+      Self::UTCMinus2_30 => -150,
+      Self::UTCMinus2 => -120,
+      Self::UTCMinus1 => -60,
+    }
+  }
+}
+
+impl PlusOffsetChoices {
+  fn minutes(&self) -> i16 {
+    match self {
+      Self::UTCPlus1 => 60,
+      Self::UTCPlus2 => 120,
+      Self::UTCPlus3 => 180,
+      Self::UTCPlus3_30 => 210,
+      Self::UTCPlus4 => 240,
+      Self::UTCPlus4_30 => 270,
+      Self::UTCPlus5 => 300,
+      Self::UTCPlus5_30 => 330,
+      Self::UTCPlus5_45 => 345,
+      Self::UTCPlus6 => 360,
+      Self::UTCPlus6_30 => 390,
+      Self::UTCPlus7 => 420,
+      Self::UTCPlus8 => 480,
+      Self::UTCPlus8_45 => 525,
+      Self::UTCPlus9 => 540,
+      Self::UTCPlus9_30 => 570,
+      Self::UTCPlus10 => 600,
+      Self::UTCPlus10_30 => 630,
+      Self::UTCPlus11 => 660,
+      Self::UTCPlus12 => 720,
+      Self::UTCPlus12_45 => 765,
+      Self::UTCPlus13 => 780,
+      Self::UTCPlus13_45 => 825,
+      Self::UTCPlus14 => 840,
+    }
+  }
 }
 
 #[derive(poise::ChoiceParameter)]
@@ -115,10 +170,10 @@ pub enum OnOff {
 ///
 /// Customize your meditation tracking experience.
 ///
-/// Set a UTC offset, make your stats or streak private, turn streak reporting off, or enable anonymous tracking.
+/// Open your settings to set your time zone, make your stats or streak private, turn streak reporting or voice channel tracking off, enable anonymous tracking, choose where you're congratulated on new roles, get image cards for new roles, or draw charts in light mode. You can also pick a reaction for your added sessions, turn off quotes in your `/add` confirmations, set quiet hours for notifications, reset your private notification thread, create a quick-log link for phone automations, or sync mindful minutes from Apple Health or Google Fit.
 #[poise::command(
   slash_command,
-  subcommands("show", "offset", "tracking", "streak", "stats"),
+  subcommands("settings", "reaction", "quotes", "quiet", "thread", "quicklog", "healthsync"),
   category = "Meditation Tracking",
   //hide_in_help,
   guild_only
@@ -128,11 +183,15 @@ pub async fn customize(_: Context<'_>) -> Result<()> {
   Ok(())
 }
 
-/// Show your current customization settings
+/// Open your customization settings
 ///
-/// Show your current settings for meditation tracking experience customization.
+/// Show your current settings for meditation tracking, with menus to change your privacy, streaks, voice channel tracking, congratulations, chart theme, and time zone. Changes are saved as soon as you make them.
+///
+/// When anonymous tracking is turned on, the anonymous entry is displayed in the channel to motivate others, but personal information (total meditation time, streak and role info) is shared with you privately. When streak reporting is turned off, your streak role is removed and your streak is hidden from your stats, though it is still tracked. Private streaks and stats can't be viewed by other members. Public congratulations are sent privately instead when the add itself was private, or when they would reveal a streak you keep private.
+///
+/// Time zones are set as a UTC offset, so daylight savings time adjustments will need to be made manually, if necessary.
 #[poise::command(slash_command)]
-pub async fn show(ctx: Context<'_>) -> Result<()> {
+pub async fn settings(ctx: Context<'_>) -> Result<()> {
   let data = ctx.data();
 
   // We unwrap here, because we know that the command is guild-only.
@@ -140,93 +199,479 @@ pub async fn show(ctx: Context<'_>) -> Result<()> {
   let user_id = ctx.author().id;
 
   let mut transaction = data.db.start_transaction_with_retry(5).await?;
-  //let tracking_profile = DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user_id).await?;
-  let tracking_profile =
-    match DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user_id).await? {
-      Some(tracking_profile) => tracking_profile,
-      None => TrackingProfile {
-        ..Default::default()
-      },
+  let existing_profile =
+    DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user_id).await?;
+  let quotes_default = DatabaseHandler::get_add_quotes_default(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let mut has_profile = existing_profile.is_some();
+  let mut tracking_profile = existing_profile.unwrap_or_default();
+
+  let ctx_id = ctx.id();
+
+  let hub = ctx
+    .send(
+      CreateReply::default()
+        .embed(settings_embed(
+          ctx.author(),
+          &tracking_profile,
+          quotes_default,
+        ))
+        .components(settings_menus(ctx_id, &tracking_profile, quotes_default))
+        .ephemeral(true),
+    )
+    .await?;
+
+  // Loop through incoming interactions with the menus
+  while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+    // We defined our menu IDs to start with `ctx_id`. If they don't, some other command's
+    // menu was used
+    .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+    // Timeout when the menus haven't been used for a while
+    .timeout(std::time::Duration::from_secs(
+      60 * SETTINGS_TIMEOUT_MINUTES,
+    ))
+    .await
+  {
+    let serenity::ComponentInteractionDataKind::StringSelect { values } = &press.data.kind else {
+      continue;
     };
+    let setting = press
+      .data
+      .custom_id
+      .strip_prefix(&ctx_id.to_string())
+      .unwrap_or_default();
+
+    let streaks_were_active = tracking_profile.streaks_active;
+    let previous_offset = tracking_profile.utc_offset;
+    apply_setting(&mut tracking_profile, setting, values, quotes_default);
+
+    let mut transaction = data.db.start_transaction_with_retry(5).await?;
+    save_settings(
+      &mut transaction,
+      &guild_id,
+      &user_id,
+      &tracking_profile,
+      has_profile,
+    )
+    .await?;
+    has_profile = true;
 
-  let utc_offset = match tracking_profile.utc_offset {
-    -720 => MinusOffsetChoices::UTCMinus12.name(),
-    -660 => MinusOffsetChoices::UTCMinus11.name(),
-    -600 => MinusOffsetChoices::UTCMinus10.name(),
-    -570 => MinusOffsetChoices::UTCMinus9_30.name(),
-    -540 => MinusOffsetChoices::UTCMinus9.name(),
-    -480 => MinusOffsetChoices::UTCMinus8.name(),
-    -420 => MinusOffsetChoices::UTCMinus7.name(),
-    -360 => MinusOffsetChoices::UTCMinus6.name(),
-    -300 => MinusOffsetChoices::UTCMinus5.name(),
-    -270 => MinusOffsetChoices::UTCMinus4_30.name(),
-    -240 => MinusOffsetChoices::UTCMinus4.name(),
-    -210 => MinusOffsetChoices::UTCMinus3_30.name(),
-    -180 => MinusOffsetChoices::UTCMinus3.name(),
-    -150 => MinusOffsetChoices::UTCMinus2_30.name(),
-    -120 => MinusOffsetChoices::UTCMinus2.name(),
-    -60 => MinusOffsetChoices::UTCMinus1.name(),
-    60 => PlusOffsetChoices::UTCPlus1.name(),
-    120 => PlusOffsetChoices::UTCPlus2.name(),
-    180 => PlusOffsetChoices::UTCPlus3.name(),
-    210 => PlusOffsetChoices::UTCPlus3_30.name(),
-    240 => PlusOffsetChoices::UTCPlus4.name(),
-    270 => PlusOffsetChoices::UTCPlus4_30.name(),
-    300 => PlusOffsetChoices::UTCPlus5.name(),
-    330 => PlusOffsetChoices::UTCPlus5_30.name(),
-    345 => PlusOffsetChoices::UTCPlus5_45.name(),
-    360 => PlusOffsetChoices::UTCPlus6.name(),
-    390 => PlusOffsetChoices::UTCPlus6_30.name(),
-    420 => PlusOffsetChoices::UTCPlus7.name(),
-    480 => PlusOffsetChoices::UTCPlus8.name(),
-    525 => PlusOffsetChoices::UTCPlus8_45.name(),
-    540 => PlusOffsetChoices::UTCPlus9.name(),
-    570 => PlusOffsetChoices::UTCPlus9_30.name(),
-    600 => PlusOffsetChoices::UTCPlus10.name(),
-    630 => PlusOffsetChoices::UTCPlus10_30.name(),
-    660 => PlusOffsetChoices::UTCPlus11.name(),
-    720 => PlusOffsetChoices::UTCPlus12.name(),
-    765 => PlusOffsetChoices::UTCPlus12_45.name(),
-    780 => PlusOffsetChoices::UTCPlus13.name(),
-    825 => PlusOffsetChoices::UTCPlus13_45.name(),
-    840 => PlusOffsetChoices::UTCPlus14.name(),
-    _ => "None",
-  };
+    if tracking_profile.utc_offset != previous_offset {
+      DatabaseHandler::update_quiet_hours_offset(
+        &mut transaction,
+        &user_id,
+        tracking_profile.utc_offset,
+      )
+      .await?;
+    }
 
-  ctx
-    .send(CreateReply::default()
-    .embed(BloomBotEmbed::new()
-        .author(CreateEmbedAuthor::new("Meditation Tracking Customization Settings").icon_url(ctx.author().face()))
-        //.title("Meditation Tracking Customization Settings")
-        .description(format!(
-          //"**UTC Offset**: {}\n**Anonymous Tracking**: {}\n**Streak Reporting**: {}\n**Streak Visibility**: {}\n**Stats Visibility**: {}",
-          "```UTC Offset:           {}\nAnonymous Tracking:   {}\nStreak Reporting:     {}\nStreak Visibility:    {}\nStats Visibility:     {}```",
-          //Only show the offset (no time zone abbreviations)
-          utc_offset.split_whitespace().next().unwrap(),
-          if tracking_profile.anonymous_tracking { "On" } else { "Off" },
-          if tracking_profile.streaks_active { "On" } else { "Off" },
-          if tracking_profile.streaks_private { "Private" } else { "Public" },
-          if tracking_profile.stats_private { "Private" } else { "Public" },
+    if tracking_profile.streaks_active != streaks_were_active {
+      update_streak_roles(
+        ctx,
+        &mut transaction,
+        &guild_id,
+        user_id,
+        tracking_profile.streaks_active,
+      )
+      .await?;
+    }
+
+    DatabaseHandler::commit_transaction(transaction).await?;
+
+    press
+      .create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+          CreateInteractionResponseMessage::new()
+            .embed(settings_embed(
+              ctx.author(),
+              &tracking_profile,
+              quotes_default,
+            ))
+            .components(settings_menus(ctx_id, &tracking_profile, quotes_default)),
+        ),
+      )
+      .await?;
+  }
+
+  // The menus stop working once the collector times out, so they're removed
+  hub
+    .edit(
+      ctx,
+      CreateReply::default()
+        .embed(settings_embed(
+          ctx.author(),
+          &tracking_profile,
+          quotes_default,
         ))
+        .components(Vec::new()),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Lists the offset choices in the order they are declared.
+fn offset_choices<T: ChoiceParameter>() -> impl Iterator<Item = T> {
+  (0..T::list().len()).filter_map(T::from_index)
+}
+
+/// Returns the name of the offset choice matching a UTC offset in minutes.
+fn utc_offset_name(utc_offset: i16) -> &'static str {
+  offset_choices::<MinusOffsetChoices>()
+    .find(|choice| choice.minutes() == utc_offset)
+    .map(|choice| choice.name())
+    .or_else(|| {
+      offset_choices::<PlusOffsetChoices>()
+        .find(|choice| choice.minutes() == utc_offset)
+        .map(|choice| choice.name())
+    })
+    .unwrap_or(UTC_NAME)
+}
+
+fn settings_embed(
+  author: &serenity::User,
+  tracking_profile: &TrackingProfile,
+  quotes_default: bool,
+) -> CreateEmbed {
+  let add_quotes = match tracking_profile.add_quotes {
+    Some(true) => "On",
+    Some(false) => "Off",
+    None if quotes_default => "On (Server Default)",
+    None => "Off (Server Default)",
+  };
+
+  BloomBotEmbed::new()
+    .author(
+      CreateEmbedAuthor::new("Meditation Tracking Customization Settings").icon_url(author.face()),
+    )
+    .description(format!(
+      "```UTC Offset:           {}\nAnonymous Tracking:   {}\nStreak Reporting:     {}\nStreak Visibility:    {}\nStats Visibility:     {}\nVC Tracking:          {}\nCongratulations:      {}\nCongrats Cards:       {}\nAdd Reaction:         {}\nAdd Quotes:           {}\nChart Theme:          {}```\nUse the menus below to change your settings. Changes are saved right away.",
+      //Only show the offset (no time zone abbreviations)
+      utc_offset_name(tracking_profile.utc_offset).split_whitespace().next().unwrap(),
+      if tracking_profile.anonymous_tracking { "On" } else { "Off" },
+      if tracking_profile.streaks_active { "On" } else { "Off" },
+      if tracking_profile.streaks_private { "Private" } else { "Public" },
+      if tracking_profile.stats_private { "Private" } else { "Public" },
+      if tracking_profile.vc_tracking { "On" } else { "Off" },
+      match tracking_profile.congrats {
+        CongratsDelivery::Public => "Public",
+        CongratsDelivery::Private => "Private",
+        CongratsDelivery::Off => "Off",
+      },
+      if tracking_profile.congrats_cards { "On" } else { "Off" },
+      tracking_profile.add_reaction.as_deref().unwrap_or("Off"),
+      add_quotes,
+      if tracking_profile.light_mode { "Light" } else { "Dark" },
+    ))
+}
+
+/// Builds the hub's menus, with the current settings selected.
+fn settings_menus(
+  ctx_id: u64,
+  tracking_profile: &TrackingProfile,
+  quotes_default: bool,
+) -> Vec<CreateActionRow> {
+  let option = |label: &str, value: &str, description: &str, selected: bool| {
+    CreateSelectMenuOption::new(label, value)
+      .description(description)
+      .default_selection(selected)
+  };
+
+  let privacy = CreateSelectMenu::new(
+    format!("{ctx_id}privacy"),
+    CreateSelectMenuKind::String {
+      options: vec![
+        option(
+          "Anonymous tracking",
+          "anonymous",
+          "Share your totals, streak and roles only with you",
+          tracking_profile.anonymous_tracking,
+        ),
+        option(
+          "Private streak",
+          "streak",
+          "Hide your streak from other members",
+          tracking_profile.streaks_private,
+        ),
+        option(
+          "Private stats",
+          "stats",
+          "Hide your stats from other members",
+          tracking_profile.stats_private,
+        ),
+      ],
+    },
+  )
+  .placeholder("Privacy: nothing private")
+  .min_values(0)
+  .max_values(3);
+
+  let features = CreateSelectMenu::new(
+    format!("{ctx_id}features"),
+    CreateSelectMenuKind::String {
+      options: vec![
+        option(
+          "Streak reporting",
+          "streaks",
+          "Show your streak and give streak roles",
+          tracking_profile.streaks_active,
+        ),
+        option(
+          "Voice channel tracking",
+          "vc",
+          "Add time spent in meditation voice channels",
+          tracking_profile.vc_tracking,
+        ),
+        option(
+          "Congratulations cards",
+          "cards",
+          "Add an image card when you earn a new role",
+          tracking_profile.congrats_cards,
+        ),
+        option(
+          "Quotes in /add",
+          "quotes",
+          "End your /add confirmations with a random quote",
+          tracking_profile.add_quotes.unwrap_or(quotes_default),
+        ),
+        option(
+          "Light mode charts",
+          "light",
+          "Draw your stats charts in light mode",
+          tracking_profile.light_mode,
+        ),
+      ],
+    },
+  )
+  .placeholder("Features: all off")
+  .min_values(0)
+  .max_values(5);
+
+  let congrats = CreateSelectMenu::new(
+    format!("{ctx_id}congrats"),
+    CreateSelectMenuKind::String {
+      options: CongratsDelivery::ALL
+        .into_iter()
+        .map(|delivery| {
+          let (label, description) = match delivery {
+            CongratsDelivery::Public => ("Congratulations: Public", "Posted where you add time"),
+            CongratsDelivery::Private => ("Congratulations: Private", "Sent to you privately"),
+            CongratsDelivery::Off => ("Congratulations: Off", "No congratulations"),
+          };
+          option(
+            label,
+            delivery.key(),
+            description,
+            delivery == tracking_profile.congrats,
+          )
+        })
+        .collect(),
+    },
+  );
+
+  let mut western =
+    vec![CreateSelectMenuOption::new(UTC_NAME, "0")
+      .default_selection(tracking_profile.utc_offset == 0)];
+  western.extend(offset_choices::<MinusOffsetChoices>().map(|choice| {
+    CreateSelectMenuOption::new(choice.name(), choice.minutes().to_string())
+      .default_selection(choice.minutes() == tracking_profile.utc_offset)
+  }));
+  let western = CreateSelectMenu::new(
+    format!("{ctx_id}western"),
+    CreateSelectMenuKind::String { options: western },
+  )
+  .placeholder("Western Hemisphere time zone");
+
+  let eastern = CreateSelectMenu::new(
+    format!("{ctx_id}eastern"),
+    CreateSelectMenuKind::String {
+      options: offset_choices::<PlusOffsetChoices>()
+        .map(|choice| {
+          CreateSelectMenuOption::new(choice.name(), choice.minutes().to_string())
+            .default_selection(choice.minutes() == tracking_profile.utc_offset)
+        })
+        .collect(),
+    },
+  )
+  .placeholder("Eastern Hemisphere time zone");
+
+  vec![
+    CreateActionRow::SelectMenu(privacy),
+    CreateActionRow::SelectMenu(features),
+    CreateActionRow::SelectMenu(congrats),
+    CreateActionRow::SelectMenu(western),
+    CreateActionRow::SelectMenu(eastern),
+  ]
+}
+
+/// Applies a choice made in one of the hub's menus to the tracking profile.
+fn apply_setting(
+  tracking_profile: &mut TrackingProfile,
+  setting: &str,
+  values: &[String],
+  quotes_default: bool,
+) {
+  let selected = |value: &str| values.iter().any(|selected| selected == value);
+
+  match setting {
+    "privacy" => {
+      tracking_profile.anonymous_tracking = selected("anonymous");
+      tracking_profile.streaks_private = selected("streak");
+      tracking_profile.stats_private = selected("stats");
+    }
+    "features" => {
+      tracking_profile.streaks_active = selected("streaks");
+      tracking_profile.vc_tracking = selected("vc");
+      tracking_profile.congrats_cards = selected("cards");
+      tracking_profile.light_mode = selected("light");
+
+      // Quotes keep following the server default unless they were changed here
+      let add_quotes = selected("quotes");
+      if add_quotes != tracking_profile.add_quotes.unwrap_or(quotes_default) {
+        tracking_profile.add_quotes = Some(add_quotes);
+      }
+    }
+    "congrats" => {
+      if let Some(delivery) = values
+        .first()
+        .and_then(|value| CongratsDelivery::from_key(value))
+      {
+        tracking_profile.congrats = delivery;
+      }
+    }
+    "western" | "eastern" => {
+      if let Some(utc_offset) = values.first().and_then(|value| value.parse().ok()) {
+        tracking_profile.utc_offset = utc_offset;
+      }
+    }
+    _ => {}
+  }
+}
+
+/// Writes every setting shown in the hub, creating the tracking profile if the member doesn't have one yet.
+async fn save_settings(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  guild_id: &serenity::GuildId,
+  user_id: &serenity::UserId,
+  tracking_profile: &TrackingProfile,
+  has_profile: bool,
+) -> Result<()> {
+  if has_profile {
+    DatabaseHandler::update_tracking_profile(
+      transaction,
+      guild_id,
+      user_id,
+      tracking_profile.utc_offset,
+      tracking_profile.anonymous_tracking,
+      tracking_profile.streaks_active,
+      tracking_profile.streaks_private,
+      tracking_profile.stats_private,
+    )
+    .await?;
+  } else {
+    DatabaseHandler::create_tracking_profile(
+      transaction,
+      guild_id,
+      user_id,
+      tracking_profile.utc_offset,
+      tracking_profile.anonymous_tracking,
+      tracking_profile.streaks_active,
+      tracking_profile.streaks_private,
+      tracking_profile.stats_private,
     )
-    .ephemeral(true))
     .await?;
+  }
+
+  DatabaseHandler::update_congrats_cards(
+    transaction,
+    guild_id,
+    user_id,
+    tracking_profile.congrats_cards,
+  )
+  .await?;
+  DatabaseHandler::update_congrats_delivery(
+    transaction,
+    guild_id,
+    user_id,
+    tracking_profile.congrats,
+  )
+  .await?;
+  DatabaseHandler::update_add_quotes(transaction, guild_id, user_id, tracking_profile.add_quotes)
+    .await?;
+  DatabaseHandler::update_vc_tracking(transaction, guild_id, user_id, tracking_profile.vc_tracking)
+    .await?;
+  DatabaseHandler::update_light_mode(transaction, guild_id, user_id, tracking_profile.light_mode)
+    .await?;
+
+  Ok(())
+}
+
+/// Removes streak roles when streak reporting is turned off, and gives back the earned streak role when it is turned on again.
+async fn update_streak_roles(
+  ctx: Context<'_>,
+  transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  guild_id: &serenity::GuildId,
+  user_id: serenity::UserId,
+  streaks_active: bool,
+) -> Result<()> {
+  let guild = ctx.guild().unwrap().clone();
+  let member = guild.member(ctx, user_id).await?;
+
+  let current_streak_roles = StreakRoles::get_users_current_roles(&guild, &member);
+
+  if !streaks_active {
+    for role in current_streak_roles {
+      match member.remove_role(ctx, role).await {
+        Ok(()) => {}
+        Err(err) => {
+          error!("Error removing role: {err}");
+
+          ctx.send(CreateReply::default()
+            .content(":x: An error occured while removing your streak role. Your settings have been saved, but your roles have not been updated. Please contact a moderator.")
+            .allowed_mentions(serenity::CreateAllowedMentions::new())
+            .ephemeral(true)).await?;
+        }
+      }
+    }
+
+    return Ok(());
+  }
+
+  let user_streak = DatabaseHandler::get_streak(transaction, guild_id, &user_id).await?;
+  let earned_streak_role = StreakRoles::from_streak(user_streak);
+
+  if let Some(earned_streak_role) = earned_streak_role {
+    if !current_streak_roles.contains(&earned_streak_role.to_role_id()) {
+      match member.add_role(ctx, earned_streak_role.to_role_id()).await {
+        Ok(()) => {}
+        Err(err) => {
+          error!("Error adding role: {err}");
+
+          ctx.send(CreateReply::default()
+            .content(":x: An error occured while adding your streak role. Your settings have been saved, but your roles have not been updated. Please contact a moderator.")
+            .allowed_mentions(serenity::CreateAllowedMentions::new())
+            .ephemeral(true)).await?;
+        }
+      }
+    }
+  }
 
   Ok(())
 }
 
-/// Set a UTC offset to be used for tracking
+/// Set a reaction for your added sessions
 ///
-/// Set a UTC offset to be used for tracking. Times will be adjusted to your local time. Note that daylight savings time adjustments will need to be made manually, if necessary.
+/// Set an emoji for the bot to react with when you add a session, or leave the emoji empty to turn reactions off.
+///
+/// Any standard emoji can be used, as well as custom emojis from this server. Reactions are only added to public confirmations, so anonymous sessions stay anonymous.
 #[poise::command(slash_command)]
-pub async fn offset(
+pub async fn reaction(
   ctx: Context<'_>,
-  #[description = "Specify a UTC offset for a Western Hemisphere time zone"]
-  #[rename = "western_hemisphere_offset"]
-  minus_offset: Option<MinusOffsetChoices>,
-  #[description = "Specify a UTC offset for an Eastern Hemisphere time zone"]
-  #[rename = "eastern_hemisphere_offset"]
-  plus_offset: Option<PlusOffsetChoices>,
+  #[description = "The emoji to react with (Leave empty to turn reactions off)"] emoji: Option<
+    String,
+  >,
 ) -> Result<()> {
   let data = ctx.data();
 
@@ -234,111 +679,60 @@ pub async fn offset(
   let guild_id = ctx.guild_id().unwrap();
   let user_id = ctx.author().id;
 
-  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let add_reaction = match emoji.as_deref().map(str::trim) {
+    Some(emoji) => {
+      let reaction = serenity::ReactionType::try_from(emoji)
+        .ok()
+        .filter(|reaction| {
+          match reaction {
+            serenity::ReactionType::Custom { id, .. } => ctx
+              .guild()
+              .is_some_and(|guild| guild.emojis.contains_key(id)),
+            // Unicode emojis aren't validated by Discord until used, so rule out plain text here
+            serenity::ReactionType::Unicode(emoji) => {
+              !emoji.is_empty()
+                && emoji.chars().count() <= 8
+                && !emoji
+                  .chars()
+                  .any(|c| c.is_ascii_alphanumeric() || c.is_whitespace())
+            }
+            _ => false,
+          }
+        });
+
+      let Some(reaction) = reaction else {
+        ctx
+          .send(
+            CreateReply::default()
+              .content(":x: Please use a standard emoji or a custom emoji from this server.")
+              .ephemeral(true),
+          )
+          .await?;
 
-  let minus_offset = match minus_offset {
-    Some(minus_offset) => match minus_offset {
-      MinusOffsetChoices::UTCMinus12 => -720,
-      MinusOffsetChoices::UTCMinus11 => -660,
-      MinusOffsetChoices::UTCMinus10 => -600,
-      MinusOffsetChoices::UTCMinus9_30 => -570,
-      MinusOffsetChoices::UTCMinus9 => -540,
-      MinusOffsetChoices::UTCMinus8 => -480,
-      MinusOffsetChoices::UTCMinus7 => -420,
-      MinusOffsetChoices::UTCMinus6 => -360,
-      MinusOffsetChoices::UTCMinus5 => -300,
-      MinusOffsetChoices::UTCMinus4_30 => -270,
-      MinusOffsetChoices::UTCMinus4 => -240,
-      MinusOffsetChoices::UTCMinus3_30 => -210,
-      MinusOffsetChoices::UTCMinus3 => -180,
-      MinusOffsetChoices::UTCMinus2_30 => -150,
-      MinusOffsetChoices::UTCMinus2 => -120,
-      MinusOffsetChoices::UTCMinus1 => -60,
-    },
-    None => 0,
-  };
+        return Ok(());
+      };
 
-  let plus_offset = match plus_offset {
-    Some(plus_offset) => match plus_offset {
-      PlusOffsetChoices::UTCPlus1 => 60,
-      PlusOffsetChoices::UTCPlus2 => 120,
-      PlusOffsetChoices::UTCPlus3 => 180,
-      PlusOffsetChoices::UTCPlus3_30 => 210,
-      PlusOffsetChoices::UTCPlus4 => 240,
-      PlusOffsetChoices::UTCPlus4_30 => 270,
-      PlusOffsetChoices::UTCPlus5 => 300,
-      PlusOffsetChoices::UTCPlus5_30 => 330,
-      PlusOffsetChoices::UTCPlus5_45 => 345,
-      PlusOffsetChoices::UTCPlus6 => 360,
-      PlusOffsetChoices::UTCPlus6_30 => 390,
-      PlusOffsetChoices::UTCPlus7 => 420,
-      PlusOffsetChoices::UTCPlus8 => 480,
-      PlusOffsetChoices::UTCPlus8_45 => 525,
-      PlusOffsetChoices::UTCPlus9 => 540,
-      PlusOffsetChoices::UTCPlus9_30 => 570,
-      PlusOffsetChoices::UTCPlus10 => 600,
-      PlusOffsetChoices::UTCPlus10_30 => 630,
-      PlusOffsetChoices::UTCPlus11 => 660,
-      PlusOffsetChoices::UTCPlus12 => 720,
-      PlusOffsetChoices::UTCPlus12_45 => 765,
-      PlusOffsetChoices::UTCPlus13 => 780,
-      PlusOffsetChoices::UTCPlus13_45 => 825,
-      PlusOffsetChoices::UTCPlus14 => 840,
-    },
-    None => 0,
+      Some(reaction.to_string())
+    }
+    None => None,
   };
 
-  if minus_offset != 0 && plus_offset != 0 {
-    ctx
-      .send(
-        CreateReply::default()
-          .content(
-            "Cannot specify multiple time zones. Please try again with only one offset."
-              .to_string(),
-          )
-          .ephemeral(true),
-      )
-      .await?;
-    return Ok(());
-  }
-
-  let utc_offset = if minus_offset != 0 {
-    minus_offset
-  } else {
-    plus_offset
-  };
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
 
   if let Some(tracking_profile) =
     DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user_id).await?
   {
-    let existing_profile = tracking_profile;
-
-    if utc_offset == existing_profile.utc_offset {
+    if add_reaction == tracking_profile.add_reaction {
       ctx
         .send(
           CreateReply::default()
-            .content(
-              "Your current UTC offset already matches the specified offset. No changes made."
-                .to_string(),
-            )
+            .content("Reaction already set to that. No changes made.")
             .ephemeral(true),
         )
         .await?;
 
       return Ok(());
     }
-
-    DatabaseHandler::update_tracking_profile(
-      &mut transaction,
-      &guild_id,
-      &user_id,
-      utc_offset,
-      existing_profile.anonymous_tracking,
-      existing_profile.streaks_active,
-      existing_profile.streaks_private,
-      existing_profile.stats_private,
-    )
-    .await?;
   } else {
     let default = TrackingProfile {
       ..Default::default()
@@ -348,7 +742,7 @@ pub async fn offset(
       &mut transaction,
       &guild_id,
       &user_id,
-      utc_offset,
+      default.utc_offset,
       default.anonymous_tracking,
       default.streaks_active,
       default.streaks_private,
@@ -357,26 +751,37 @@ pub async fn offset(
     .await?;
   }
 
-  commit_and_say(
-    ctx,
-    transaction,
-    MessageType::TextOnly(":white_check_mark: UTC offset successfully updated.".to_string()),
-    true,
+  DatabaseHandler::update_add_reaction(
+    &mut transaction,
+    &guild_id,
+    &user_id,
+    add_reaction.as_deref(),
   )
   .await?;
 
+  let message = match &add_reaction {
+    Some(add_reaction) => {
+      format!(":white_check_mark: Your added sessions will now get a {add_reaction} reaction.")
+    }
+    None => ":white_check_mark: Reactions to your added sessions have been turned off.".to_string(),
+  };
+
+  commit_and_say(ctx, transaction, MessageType::TextOnly(message), true).await?;
+
   Ok(())
 }
 
-/// Turn anonymous tracking on or off
+/// Turn quotes in your add confirmations on or off
 ///
-/// Turn anonymous tracking on or off.
+/// Turn the random quote at the end of your `/add` confirmations on or off, or leave the choice empty to follow the server default.
 ///
-/// When anonymous tracking is turned on, the anonymous entry is displayed in the channel to motivate others, but personal information (total meditation time, streak and role info) is shared with you privately via ephemeral messages.
+/// Quotes are on by default, unless server moderators have turned them off for everyone.
 #[poise::command(slash_command)]
-pub async fn tracking(
+pub async fn quotes(
   ctx: Context<'_>,
-  #[description = "Turn anonymous tracking on or off (Default is off)"] anonymous: OnOff,
+  #[description = "Turn quotes on or off (Leave empty to use the server default)"] quotes: Option<
+    OnOff,
+  >,
 ) -> Result<()> {
   let data = ctx.data();
 
@@ -386,42 +791,22 @@ pub async fn tracking(
 
   let mut transaction = data.db.start_transaction_with_retry(5).await?;
 
-  let anonymous_tracking = match anonymous {
-    OnOff::On => true,
-    OnOff::Off => false,
-  };
+  let add_quotes = quotes.as_ref().map(|quotes| matches!(quotes, OnOff::On));
 
   if let Some(tracking_profile) =
     DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user_id).await?
   {
-    let existing_profile = tracking_profile;
-
-    if anonymous_tracking == existing_profile.anonymous_tracking {
+    if add_quotes == tracking_profile.add_quotes {
       ctx
         .send(
           CreateReply::default()
-            .content(format!(
-              "Anonymous tracking already turned **{}**. No changes made.",
-              anonymous.name()
-            ))
+            .content("Quotes are already set to that. No changes made.")
             .ephemeral(true),
         )
         .await?;
 
       return Ok(());
     }
-
-    DatabaseHandler::update_tracking_profile(
-      &mut transaction,
-      &guild_id,
-      &user_id,
-      existing_profile.utc_offset,
-      anonymous_tracking,
-      existing_profile.streaks_active,
-      existing_profile.streaks_private,
-      existing_profile.stats_private,
-    )
-    .await?;
   } else {
     let default = TrackingProfile {
       ..Default::default()
@@ -432,7 +817,7 @@ pub async fn tracking(
       &guild_id,
       &user_id,
       default.utc_offset,
-      anonymous_tracking,
+      default.anonymous_tracking,
       default.streaks_active,
       default.streaks_private,
       default.stats_private,
@@ -440,32 +825,32 @@ pub async fn tracking(
     .await?;
   }
 
-  commit_and_say(
-    ctx,
-    transaction,
-    MessageType::TextOnly(format!(
-      ":white_check_mark: Anonymous tracking successfully turned **{}**.",
-      anonymous.name()
-    )),
-    true,
-  )
-  .await?;
+  DatabaseHandler::update_add_quotes(&mut transaction, &guild_id, &user_id, add_quotes).await?;
+
+  let message = match quotes {
+    Some(quotes) => format!(
+      ":white_check_mark: Quotes in your add confirmations successfully turned **{}**.",
+      quotes.name()
+    ),
+    None => ":white_check_mark: Quotes in your add confirmations now follow the server default."
+      .to_string(),
+  };
+
+  commit_and_say(ctx, transaction, MessageType::TextOnly(message), true).await?;
 
   Ok(())
 }
 
-/// Enable/disable streaks or set streak privacy
+/// Set quiet hours for notifications
 ///
-/// Enable/disable streak reporting or set your streak privacy.
+/// Set a time range during which direct messages from the bot, such as reminders, are held back and delivered once the range ends. Leave both times empty to turn quiet hours off.
 ///
-/// Streak reporting is enabled by default. When disabled, any existing streak role will be removed and you will no longer receive streak-related notifications when adding time. Your streak will also be hidden from your stats. However, your streak status will still be tracked and you will still be able to check your current streak using the /streak command.
-///
-/// When streaks are set to private, other members will be unable to view your streak using the /streak command. When you view your own streak using the /streak command, the response will be shown privately in an ephemeral message by default. This can be overridden by setting privacy to "public" when using the command.
+/// Times are in your local time, based on the time zone set with `/customize settings`, and can be given as `22:00` or `10pm`. Quiet hours apply in every server you share with the bot.
 #[poise::command(slash_command)]
-pub async fn streak(
+pub async fn quiet(
   ctx: Context<'_>,
-  #[description = "Set streak privacy (Defaults to public)"] privacy: Option<Privacy>,
-  #[description = "Turn streak reporting on or off (Defaults to on)"] reporting: Option<OnOff>,
+  #[description = "When quiet hours start, such as 22:00 or 10pm"] start: Option<String>,
+  #[description = "When quiet hours end, such as 07:00 or 7am"] end: Option<String>,
 ) -> Result<()> {
   let data = ctx.data();
 
@@ -475,186 +860,272 @@ pub async fn streak(
 
   let mut transaction = data.db.start_transaction_with_retry(5).await?;
 
-  if let Some(tracking_profile) =
-    DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user_id).await?
-  {
-    let existing_profile = tracking_profile;
-
-    let streaks_active = match reporting {
-      Some(reporting) => match reporting {
-        OnOff::On => true,
-        OnOff::Off => false,
-      },
-      None => existing_profile.streaks_active,
-    };
-
-    let streaks_private = match privacy {
-      Some(privacy) => match privacy {
-        Privacy::Private => true,
-        Privacy::Public => false,
-      },
-      None => existing_profile.streaks_private,
-    };
+  let (start, end) = match (start, end) {
+    (None, None) => {
+      let message = if DatabaseHandler::remove_quiet_hours(&mut transaction, &user_id).await? {
+        ":white_check_mark: Quiet hours have been turned off."
+      } else {
+        "Quiet hours are already off. No changes made."
+      };
+
+      commit_and_say(
+        ctx,
+        transaction,
+        MessageType::TextOnly(message.to_string()),
+        true,
+      )
+      .await?;
 
-    if (streaks_active == existing_profile.streaks_active)
-      && (streaks_private == existing_profile.streaks_private)
-    {
+      return Ok(());
+    }
+    (Some(start), Some(end)) => (start, end),
+    _ => {
       ctx
         .send(
           CreateReply::default()
-            .content(
-              "Current settings already match specified settings. No changes made.".to_string(),
-            )
+            .content(":x: Please specify both a start and an end time, or neither to turn quiet hours off.")
             .ephemeral(true),
         )
         .await?;
 
       return Ok(());
     }
+  };
 
-    DatabaseHandler::update_tracking_profile(
-      &mut transaction,
-      &guild_id,
-      &user_id,
-      existing_profile.utc_offset,
-      existing_profile.anonymous_tracking,
-      streaks_active,
-      streaks_private,
-      existing_profile.stats_private,
-    )
-    .await?;
+  let (Some(start_minute), Some(end_minute)) = (parse_time_of_day(&start), parse_time_of_day(&end))
+  else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Please specify times such as `22:00` or `10pm`.")
+          .ephemeral(true),
+      )
+      .await?;
 
-    if existing_profile.streaks_active && !streaks_active {
-      let guild = ctx.guild().unwrap().clone();
-      let member = guild.member(ctx, user_id).await?;
+    return Ok(());
+  };
 
-      let current_streak_roles = StreakRoles::get_users_current_roles(&guild, &member);
+  if start_minute == end_minute {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Quiet hours must start and end at different times.")
+          .ephemeral(true),
+      )
+      .await?;
 
-      for role in current_streak_roles {
-        match member.remove_role(ctx, role).await {
-          Ok(()) => {}
-          Err(err) => {
-            error!("Error removing role: {err}");
+    return Ok(());
+  }
 
-            ctx.send(CreateReply::default()
-              .content(":x: An error occured while removing your streak role. Your settings have been saved, but your roles have not been updated. Please contact a moderator.")
-              .allowed_mentions(serenity::CreateAllowedMentions::new())
-              .ephemeral(true)).await?;
-          }
-        }
-      }
-    }
+  let utc_offset =
+    match DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user_id).await? {
+      Some(tracking_profile) => tracking_profile.utc_offset,
+      None => TrackingProfile::default().utc_offset,
+    };
 
-    if !existing_profile.streaks_active && streaks_active {
-      let user_streak = DatabaseHandler::get_streak(&mut transaction, &guild_id, &user_id).await?;
+  DatabaseHandler::set_quiet_hours(
+    &mut transaction,
+    &user_id,
+    &QuietHours {
+      start_minute,
+      end_minute,
+      utc_offset,
+    },
+  )
+  .await?;
 
-      let guild = ctx.guild().unwrap().clone();
-      let member = guild.member(ctx, user_id).await?;
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Quiet hours set from **{:02}:{:02}** to **{:02}:{:02}**. Notifications during this time will be delivered afterwards.",
+      start_minute / 60,
+      start_minute % 60,
+      end_minute / 60,
+      end_minute % 60
+    )),
+    true,
+  )
+  .await?;
 
-      let current_streak_roles = StreakRoles::get_users_current_roles(&guild, &member);
-      let earned_streak_role = StreakRoles::from_streak(user_streak);
+  Ok(())
+}
 
-      if let Some(earned_streak_role) = earned_streak_role {
-        if !current_streak_roles.contains(&earned_streak_role.to_role_id()) {
-          match member.add_role(ctx, earned_streak_role.to_role_id()).await {
-            Ok(()) => {}
-            Err(err) => {
-              error!("Error adding role: {err}");
+/// Manage your private notification thread
+///
+/// Commands to manage the private thread where you receive notifications from staff when you can't be reached by DM.
+#[poise::command(slash_command, subcommands("reset_thread"), subcommand_required)]
+#[allow(clippy::unused_async)]
+pub async fn thread(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
 
-              ctx.send(CreateReply::default()
-                .content(":x: An error occured while adding your streak role. Your settings have been saved, but your roles have not been updated. Please contact a moderator.")
-                .allowed_mentions(serenity::CreateAllowedMentions::new())
-                .ephemeral(true)).await?;
-            }
-          }
-        }
-      }
+/// Reset your private notification thread
+///
+/// Deletes your existing private notification threads and creates a new one.
+#[poise::command(slash_command, rename = "reset")]
+pub async fn reset_thread(ctx: Context<'_>) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+  let user_id = ctx.author().id;
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let threads =
+    DatabaseHandler::get_user_notification_threads(&mut transaction, &guild_id, &user_id).await?;
+
+  for thread_id in threads {
+    // The thread may already have been deleted manually
+    if let Err(err) = thread_id.delete(ctx).await {
+      error!("Error deleting notification thread {thread_id}: {err}");
     }
-  } else {
-    let default = TrackingProfile {
-      ..Default::default()
-    };
+    DatabaseHandler::remove_notification_thread(&mut transaction, &thread_id).await?;
+  }
 
-    let streaks_active = match reporting {
-      Some(reporting) => match reporting {
-        OnOff::On => true,
-        OnOff::Off => false,
-      },
-      None => default.streaks_active,
-    };
+  transaction.commit().await?;
 
-    let streaks_private = match privacy {
-      Some(privacy) => match privacy {
-        Privacy::Private => true,
-        Privacy::Public => false,
-      },
-      None => default.streaks_private,
-    };
+  let notification_thread =
+    notifications::create_thread(ctx.http(), &data.db, guild_id, ctx.channel_id(), user_id).await?;
 
-    DatabaseHandler::create_tracking_profile(
-      &mut transaction,
-      &guild_id,
-      &user_id,
-      default.utc_offset,
-      default.anonymous_tracking,
-      streaks_active,
-      streaks_private,
-      default.stats_private,
+  notification_thread
+    .send_message(
+      ctx,
+      CreateMessage::new()
+        .content(format!(
+          "Private notification thread for <@{user_id}>. Only you and staff can see this thread."
+        ))
+        .allowed_mentions(CreateAllowedMentions::new().users([user_id])),
     )
     .await?;
 
-    if default.streaks_active && !streaks_active {
-      let guild = ctx.guild().unwrap().clone();
-      let member = guild.member(ctx, user_id).await?;
+  ctx
+    .send(
+      CreateReply::default()
+        .content(format!(
+          ":white_check_mark: Your notification thread has been reset: <#{}>",
+          notification_thread.id
+        ))
+        .ephemeral(true),
+    )
+    .await?;
 
-      let current_streak_roles = StreakRoles::get_users_current_roles(&guild, &member);
+  Ok(())
+}
 
-      for role in current_streak_roles {
-        match member.remove_role(ctx, role).await {
-          Ok(()) => {}
-          Err(err) => {
-            error!("Error removing role: {err}");
+/// Manage your quick-log link
+///
+/// Commands to create or revoke a link that logs a preset session when sent a POST request, for use with phone automations such as Apple Shortcuts.
+#[poise::command(
+  slash_command,
+  subcommands("create_quicklog", "revoke_quicklog"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn quicklog(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
 
-            ctx.send(CreateReply::default()
-              .content(":x: An error occured while removing your streak role. Your settings have been saved, but your roles have not been updated. Please contact a moderator.")
-              .allowed_mentions(serenity::CreateAllowedMentions::new())
-              .ephemeral(true)).await?;
-          }
-        }
-      }
-    }
+/// Create a quick-log link
+///
+/// Creates a private link that logs a session of the chosen length when sent a POST request, such as from a phone shortcut. Opening it in a browser doesn't log anything. Creating a new link revokes your previous one.
+#[poise::command(slash_command, rename = "create")]
+pub async fn create_quicklog(
+  ctx: Context<'_>,
+  #[description = "The number of minutes to log each time the link is used"]
+  #[min = 1]
+  minutes: i32,
+) -> Result<()> {
+  let data = ctx.data();
 
-    if !default.streaks_active && streaks_active {
-      let user_streak = DatabaseHandler::get_streak(&mut transaction, &guild_id, &user_id).await?;
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+  let user_id = ctx.author().id;
 
-      let guild = ctx.guild().unwrap().clone();
-      let member = guild.member(ctx, user_id).await?;
+  let Ok(base_url) = std::env::var("API_BASE_URL") else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Quick-log links are not available right now.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let limits = DatabaseHandler::get_session_limits(&mut transaction, &guild_id).await?;
+
+  if limits.min_minutes.is_some_and(|min| minutes < min)
+    || limits.max_minutes.is_some_and(|max| minutes > max)
+  {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: That session length is outside the limits for this server.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
 
-      let current_streak_roles = StreakRoles::get_users_current_roles(&guild, &member);
-      let earned_streak_role = StreakRoles::from_streak(user_streak);
+  // Thread-local RNG is cryptographically secure, so tokens can't be guessed
+  let token: String = {
+    let mut rng = rand::thread_rng();
+    (0..32)
+      .map(|_| format!("{:02x}", rng.gen::<u8>()))
+      .collect()
+  };
 
-      if let Some(earned_streak_role) = earned_streak_role {
-        if !current_streak_roles.contains(&earned_streak_role.to_role_id()) {
-          match member.add_role(ctx, earned_streak_role.to_role_id()).await {
-            Ok(()) => {}
-            Err(err) => {
-              error!("Error adding role: {err}");
+  DatabaseHandler::set_quicklog_token(&mut transaction, &guild_id, &user_id, &token, minutes)
+    .await?;
 
-              ctx.send(CreateReply::default()
-                .content(":x: An error occured while adding your streak role. Your settings have been saved, but your roles have not been updated. Please contact a moderator.")
-                .allowed_mentions(serenity::CreateAllowedMentions::new())
-                .ephemeral(true)).await?;
-            }
-          }
-        }
-      }
-    }
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Sending a POST request to this link will log **{minutes} minutes**:\n<{}/quicklog/{token}>\n\nAdd it to a phone shortcut for one-tap logging, with the request method set to POST. The link can't be shown again, so save it somewhere safe. Keep it private, since anyone with the link can log time for you. Use `/customize quicklog revoke` if it is ever shared.",
+      base_url.trim_end_matches('/')
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Revoke your quick-log link
+///
+/// Revokes your quick-log link, so it can no longer be used to log time.
+#[poise::command(slash_command, rename = "revoke")]
+pub async fn revoke_quicklog(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+  let user_id = ctx.author().id;
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed =
+    DatabaseHandler::remove_quicklog_token(&mut transaction, &guild_id, &user_id).await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: You don't have a quick-log link.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
   }
 
   commit_and_say(
     ctx,
     transaction,
-    MessageType::TextOnly(":white_check_mark: Streak settings successfully updated.".to_string()),
+    MessageType::TextOnly(":white_check_mark: Your quick-log link has been revoked.".to_string()),
     true,
   )
   .await?;
@@ -662,85 +1133,98 @@ pub async fn streak(
   Ok(())
 }
 
-/// Set stats privacy
+/// Sync mindful minutes from your phone
 ///
-/// Set your stats privacy.
+/// Commands to create or revoke a token that lets a phone automation, such as Apple Shortcuts or Tasker, send your mindful minutes from Apple Health or Google Fit.
+#[poise::command(
+  slash_command,
+  subcommands("create_healthsync", "revoke_healthsync"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn healthsync(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Create a health sync token
 ///
-/// When stats are set to private, other members will be unable to view your stats using the /stats user command. When you view your own stats using the /stats user command, the response will be shown privately in an ephemeral message by default. This can be overridden by setting privacy to "public" when using the command.
-#[poise::command(slash_command)]
-pub async fn stats(
-  ctx: Context<'_>,
-  #[description = "Set stats privacy (Defaults to public)"] privacy: Privacy,
-) -> Result<()> {
+/// Creates a private token for sending mindful minutes from Apple Health or Google Fit. Sessions you already logged here, such as with `/add`, are recognized and not added twice. Creating a new token revokes your previous one.
+#[poise::command(slash_command, rename = "create")]
+pub async fn create_healthsync(ctx: Context<'_>) -> Result<()> {
   let data = ctx.data();
 
   // We unwrap here, because we know that the command is guild-only.
   let guild_id = ctx.guild_id().unwrap();
   let user_id = ctx.author().id;
 
-  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let Ok(base_url) = std::env::var("API_BASE_URL") else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Health sync is not available right now.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
 
-  let stats_private = match privacy {
-    Privacy::Private => true,
-    Privacy::Public => false,
+  // Thread-local RNG is cryptographically secure, so tokens can't be guessed
+  let token: String = {
+    let mut rng = rand::thread_rng();
+    (0..32)
+      .map(|_| format!("{:02x}", rng.gen::<u8>()))
+      .collect()
   };
 
-  if let Some(tracking_profile) =
-    DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user_id).await?
-  {
-    let existing_profile = tracking_profile;
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::set_health_sync_token(&mut transaction, &guild_id, &user_id, &token).await?;
 
-    if stats_private == existing_profile.stats_private {
-      ctx
-        .send(
-          CreateReply::default()
-            .content(format!(
-              "Stats already set to **{}**. No changes made.",
-              privacy.name()
-            ))
-            .ephemeral(true),
-        )
-        .await?;
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Have your automation send a `POST` request to <{}/healthsync> with the header `Authorization: Bearer {token}`, and a JSON body like:\n```json\n{{\"sessions\": [{{\"start\": \"2024-04-01T07:30:00+02:00\", \"minutes\": 15}}]}}\n```\nEach session needs a `start` time and either `minutes` or an `end` time. Sessions from the last 30 days are accepted, so it's fine to send the same sessions again. Keep the token private, since anyone with it can log time for you. The token can't be shown again, so save it somewhere safe. Use `/customize healthsync revoke` if it is ever shared.",
+      base_url.trim_end_matches('/')
+    )),
+    true,
+  )
+  .await?;
 
-      return Ok(());
-    }
+  Ok(())
+}
 
-    DatabaseHandler::update_tracking_profile(
-      &mut transaction,
-      &guild_id,
-      &user_id,
-      existing_profile.utc_offset,
-      existing_profile.anonymous_tracking,
-      existing_profile.streaks_active,
-      existing_profile.streaks_private,
-      stats_private,
-    )
-    .await?;
-  } else {
-    let default = TrackingProfile {
-      ..Default::default()
-    };
+/// Revoke your health sync token
+///
+/// Revokes your health sync token, so it can no longer be used to log time.
+#[poise::command(slash_command, rename = "revoke")]
+pub async fn revoke_healthsync(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
 
-    DatabaseHandler::create_tracking_profile(
-      &mut transaction,
-      &guild_id,
-      &user_id,
-      default.utc_offset,
-      default.anonymous_tracking,
-      default.streaks_active,
-      default.streaks_private,
-      stats_private,
-    )
-    .await?;
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+  let user_id = ctx.author().id;
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed =
+    DatabaseHandler::remove_health_sync_token(&mut transaction, &guild_id, &user_id).await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: You don't have a health sync token.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
   }
 
   commit_and_say(
     ctx,
     transaction,
-    MessageType::TextOnly(format!(
-      ":white_check_mark: Stats successfully set to **{}**.",
-      privacy.name()
-    )),
+    MessageType::TextOnly(
+      ":white_check_mark: Your health sync token has been revoked.".to_string(),
+    ),
     true,
   )
   .await?;
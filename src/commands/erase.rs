@@ -1,9 +1,11 @@
 use crate::commands::{commit_and_say, MessageType};
 use crate::config::{self, BloomBotEmbed, CHANNELS};
 use crate::database::DatabaseHandler;
+use crate::notifications::{self, Delivery, Notification};
 use crate::pagination::{PageRowRef, Pagination};
 use crate::Context;
 use anyhow::Result;
+use log::error;
 use poise::serenity_prelude::{self as serenity, builder::*, ChannelId, MessageId};
 use poise::CreateReply;
 
@@ -15,6 +17,43 @@ pub enum DateFormat {
   Dmy,
 }
 
+/// Suggests moderation reason templates, showing a preview of each reason.
+///
+/// The template label is submitted, and expanded to the full reason when the command runs.
+async fn autocomplete_reason(ctx: Context<'_>, partial: &str) -> Vec<serenity::AutocompleteChoice> {
+  let Some(guild_id) = ctx.guild_id() else {
+    return Vec::new();
+  };
+
+  let data = ctx.data();
+  let reasons = match data.moderation_reasons.get(&data.db, &guild_id).await {
+    Ok(reasons) => reasons,
+    Err(e) => {
+      error!("Error loading moderation reasons for autocomplete: {e}");
+      return Vec::new();
+    }
+  };
+
+  let partial = partial.to_lowercase();
+
+  reasons
+    .into_iter()
+    .filter(|reason| reason.label.to_lowercase().contains(&partial))
+    .take(25)
+    .map(|reason| {
+      // Choice names are limited to 100 characters
+      let preview = format!("{}: {}", reason.label, reason.reason);
+      let name = if preview.chars().count() > 100 {
+        format!("{}...", preview.chars().take(97).collect::<String>())
+      } else {
+        preview
+      };
+
+      serenity::AutocompleteChoice::new(name, reason.label)
+    })
+    .collect()
+}
+
 /// Commands for erasing and erase logs
 ///
 /// Commands to delete a message with private notification or review and update deletion logs.
@@ -42,14 +81,28 @@ pub async fn message(
   ctx: Context<'_>,
   #[description = "The message to delete"] message: serenity::Message,
   #[max_length = 512] // Max length for audit log reason
-  #[description = "The reason for deleting the message"]
+  #[description = "The reason for deleting the message, or a reason template"]
+  #[autocomplete = "autocomplete_reason"]
   reason: Option<String>,
 ) -> Result<()> {
   ctx.defer_ephemeral().await?;
 
+  let data = ctx.data();
+  let guild_id = ctx.guild_id().unwrap();
+
   let channel_id: ChannelId = message.channel_id;
   let message_id: MessageId = message.id;
-  let reason = reason.unwrap_or("No reason provided.".to_string());
+  let reason = match reason {
+    Some(reason) => {
+      // Expand reason templates, falling back to the text as entered
+      let templates = data.moderation_reasons.get(&data.db, &guild_id).await?;
+      templates
+        .into_iter()
+        .find(|template| template.label.eq_ignore_ascii_case(&reason))
+        .map_or(reason, |template| template.reason)
+    }
+    None => "No reason provided.".to_string(),
+  };
   let audit_log_reason: Option<&str> = Some(reason.as_str());
 
   ctx
@@ -59,8 +112,6 @@ pub async fn message(
 
   let occurred_at = chrono::Utc::now();
 
-  let data = ctx.data();
-  let guild_id = ctx.guild_id().unwrap();
   let user_id = message.author.id;
 
   let mut transaction = data.db.start_transaction_with_retry(5).await?;
@@ -75,19 +126,17 @@ pub async fn message(
   };
 
   let mut log_embed = BloomBotEmbed::new();
-  let mut dm_embed = BloomBotEmbed::new();
+  let mut notice_fields = Vec::new();
 
   log_embed = log_embed.title("Message Deleted").description(format!(
     "**Channel**: <#{}>\n**Author**: {} ({})\n**Reason**: {}",
     message.channel_id, message.author, erase_count_message, reason,
   ));
-  dm_embed = dm_embed
-    .title("A message you sent has been deleted.")
-    .description(format!("**Reason**: {reason}"));
+  let notice_description = format!("**Reason**: {reason}");
 
   if let Some(attachment) = message.attachments.first() {
     log_embed = log_embed.field("Attachment", attachment.url.clone(), false);
-    dm_embed = dm_embed.field("Attachment", attachment.url.clone(), false);
+    notice_fields.push(("Attachment".to_string(), attachment.url.clone()));
   }
 
   if !message.content.is_empty() {
@@ -102,7 +151,7 @@ pub async fn message(
     };
 
     log_embed = log_embed.field("Message Content", format!("```{content}```"), false);
-    dm_embed = dm_embed.field("Message Content", format!("```{content}```"), false);
+    notice_fields.push(("Message Content".to_string(), format!("```{content}```")));
   }
 
   log_embed = log_embed.footer(
@@ -113,9 +162,6 @@ pub async fn message(
     ))
     .icon_url(ctx.author().avatar_url().unwrap_or_default()),
   );
-  dm_embed = dm_embed.footer(CreateEmbedFooter::new(
-    "If you have any questions or concerns regarding this action, please contact a moderator. Replies sent to Bloom are not viewable by staff."
-  ));
 
   let log_channel = serenity::ChannelId::new(CHANNELS.logs);
 
@@ -145,54 +191,24 @@ pub async fn message(
   )
   .await?;
 
-  if message
-    .author
-    .direct_message(ctx, CreateMessage::new().embed(dm_embed.clone()))
-    .await
-    .is_ok()
-  {
-  } else {
-    let thread_channel: ChannelId = match message
-      .channel_id
-      .to_channel(&ctx)
-      .await
-      .unwrap()
-      .guild()
-      .unwrap()
-      .kind
-    {
-      serenity::ChannelType::Text => channel_id,
-      // If not a text channel, then create private thread in lounge to avoid failure
-      _ => ChannelId::from(501464482996944909),
-    };
-
-    let mut notification_thread = thread_channel
-      .create_thread(
-        ctx,
-        CreateThread::new("Private Notification: Message Deleted".to_string()),
-      )
-      .await?;
-
-    notification_thread
-      .edit_thread(ctx, EditThread::new().invitable(false).locked(true))
-      .await?;
-
-    dm_embed = dm_embed.footer(CreateEmbedFooter::new(
-      "If you have any questions or concerns regarding this action, please contact staff via ModMail."
-      ));
-
-    let thread_initial_message = format!("Private notification for <@{}>:", message.author.id);
-
-    notification_thread
-      .send_message(
-        ctx,
-        CreateMessage::new()
-          .content(thread_initial_message)
-          .embed(dm_embed.clone())
-          .allowed_mentions(CreateAllowedMentions::new().users([message.author.id])),
-      )
-      .await?;
-  }
+  let notice = Notification {
+    title: "A message you sent has been deleted.",
+    description: &notice_description,
+    footer: Some("If you have any questions or concerns regarding this action, please contact a moderator. Replies sent to Bloom are not viewable by staff."),
+    fields: notice_fields,
+    thread_footer: Some("If you have any questions or concerns regarding this action, please contact staff via ModMail."),
+  };
+  notifications::deliver(
+    ctx.http(),
+    &data.db,
+    message.author.id,
+    Delivery::DmOrThread {
+      guild_id,
+      channel_id,
+    },
+    &notice,
+  )
+  .await?;
 
   Ok(())
 }
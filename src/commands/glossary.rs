@@ -1,13 +1,28 @@
-use crate::commands::BloomBotEmbed;
+use crate::commands::terms::autocomplete_term;
+use crate::commands::{commit_and_say, BloomBotEmbed, MessageType};
 use crate::config::CHANNELS;
 use crate::database::DatabaseHandler;
 // use crate::pagination::{PageRowRef, Pagination};
-use crate::Context;
+use crate::{Context, Data as AppData, Error as AppError};
 use anyhow::Result;
 use log::info;
 use pgvector;
 use poise::serenity_prelude::{self as serenity, builder::*};
-use poise::CreateReply;
+use poise::{CreateReply, Modal};
+
+#[derive(Debug, Modal)]
+#[name = "Suggest a glossary term"]
+struct SuggestTermModal {
+  #[name = "The definition of the term"]
+  #[placeholder = "Include the acronym at the beginning of your definition"]
+  #[paragraph]
+  #[max_length = 1000]
+  meaning: String,
+  #[name = "An example sentence showing the term in use"]
+  usage: Option<String>,
+  #[name = "Links to further reading, comma separated"]
+  links: Option<String>,
+}
 
 /// Glossary commands
 ///
@@ -171,7 +186,9 @@ pub async fn browse(
 #[poise::command(slash_command)]
 pub async fn info(
   ctx: Context<'_>,
-  #[description = "The term to show information about"] term: String,
+  #[description = "The term to show information about"]
+  #[autocomplete = "autocomplete_term"]
+  term: String,
 ) -> Result<()> {
   let guild_id = ctx.guild_id().unwrap();
 
@@ -434,14 +451,77 @@ pub async fn search(
 /// Suggest a term for the glossary
 ///
 /// Suggest a term for addition to the glossary.
+///
+/// You will be asked to provide a definition for the term, along with an optional example and links to further reading. Suggestions are reviewed by server staff before being added to the glossary.
 #[poise::command(slash_command)]
 pub async fn suggest(
-  ctx: Context<'_>,
+  ctx: poise::ApplicationContext<'_, AppData, AppError>,
   #[description = "Term you wish to suggest"] suggestion: String,
 ) -> Result<()> {
+  use poise::Modal as _;
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+  if DatabaseHandler::term_exists(&mut transaction, &guild_id, suggestion.as_str()).await? {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: The glossary already has an entry for **{suggestion}**. Use `/glossary info` to see it."
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+  drop(transaction);
+
+  let Some(suggestion_data) = SuggestTermModal::execute(ctx).await? else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: No data was provided.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let links: Vec<String> = match suggestion_data.links {
+    Some(links) => links.split(',').map(|s| s.trim().to_string()).collect(),
+    None => Vec::new(),
+  };
+
+  let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::add_term_suggestion(
+    &mut transaction,
+    &guild_id,
+    &ctx.author().id,
+    suggestion.as_str(),
+    suggestion_data.meaning.as_str(),
+    suggestion_data.usage.as_deref(),
+    links.as_slice(),
+  )
+  .await?;
+
+  commit_and_say(
+    poise::Context::Application(ctx),
+    transaction,
+    MessageType::TextOnly(
+      "Your suggestion has been submitted for review by server staff. Thank you!".to_string(),
+    ),
+    true,
+  )
+  .await?;
+
   let log_embed = BloomBotEmbed::new()
     .title("Term Suggestion")
-    .description(format!("**Suggestion**: {suggestion}"))
+    .description(format!(
+      "**Suggestion**: {suggestion}\n**Definition**: {}\n\nUse `/terms review` to approve, edit, or reject this suggestion.",
+      suggestion_data.meaning
+    ))
     .footer(
       CreateEmbedFooter::new(format!(
         "Suggested by {} ({})",
@@ -458,13 +538,5 @@ pub async fn suggest(
     .send_message(ctx, CreateMessage::new().embed(log_embed))
     .await?;
 
-  ctx
-    .send(
-      CreateReply::default()
-        .content("Your suggestion has been submitted. Thank you!")
-        .ephemeral(true),
-    )
-    .await?;
-
   Ok(())
 }
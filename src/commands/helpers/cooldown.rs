@@ -0,0 +1,46 @@
+use crate::Context;
+use anyhow::Result;
+use poise::CreateReply;
+use std::time::Duration;
+
+/// Starts a per-member cooldown on `command`, like poise's `member_cooldown`, but kept in shared state so it holds across every bot process.
+///
+/// Returns `false` after telling the member how long to wait if they used the command too recently.
+pub async fn member_cooldown(ctx: Context<'_>, command: &str, cooldown: Duration) -> Result<bool> {
+  let state = &ctx.data().state;
+
+  // We unwrap here, because cooldowns are only used on guild-only commands.
+  let key = format!(
+    "cooldown:{command}:{}:{}",
+    ctx.guild_id().unwrap(),
+    ctx.author().id
+  );
+  let expires_at = chrono::Utc::now() + chrono::Duration::from_std(cooldown)?;
+
+  if state
+    .set_if_absent(&key, expires_at.timestamp().to_string(), cooldown)
+    .await?
+  {
+    return Ok(true);
+  }
+
+  let remaining = state
+    .get(&key)
+    .await?
+    .and_then(|expires_at| expires_at.parse::<i64>().ok())
+    .map_or(1, |expires_at| {
+      (expires_at - chrono::Utc::now().timestamp()).max(1)
+    });
+
+  ctx
+    .send(
+      CreateReply::default()
+        .content(format!(
+          "You're too fast. Please wait {remaining} seconds before retrying."
+        ))
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(false)
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc};
+
+/// Days searched for the next match before giving up. Covers schedules that only match on February 29.
+const MAX_SEARCH_DAYS: i64 = 366 * 8;
+
+const MONTH_NAMES: [&str; 12] = [
+  "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Values allowed by one field of a cron schedule, as a bit per value.
+#[derive(Clone, Copy)]
+struct Field {
+  values: u64,
+  /// Whether the field was `*`, which matters for how day of month and day of week combine.
+  any: bool,
+}
+
+impl Field {
+  fn contains(self, value: u32) -> bool {
+    self.values & (1 << value) != 0
+  }
+
+  /// Parses a field such as `*`, `*/15`, `1-5`, `mon-fri`, or `0,30`.
+  fn parse(input: &str, min: u32, max: u32, names: &[&str], name_offset: u32) -> Option<Self> {
+    let parse_value = |value: &str| -> Option<u32> {
+      let value = value.to_lowercase();
+      let parsed = match names.iter().position(|name| *name == value) {
+        Some(index) => u32::try_from(index).ok()? + name_offset,
+        None => value.parse::<u32>().ok()?,
+      };
+      (min..=max).contains(&parsed).then_some(parsed)
+    };
+
+    let mut values = 0;
+    for part in input.split(',') {
+      let (range, step) = match part.split_once('/') {
+        Some((range, step)) => (range, step.parse::<u32>().ok().filter(|step| *step > 0)?),
+        None => (part, 1),
+      };
+
+      let (start, end) = match range {
+        "*" => (min, max),
+        _ => match range.split_once('-') {
+          Some((start, end)) => (parse_value(start)?, parse_value(end)?),
+          // A single value with a step, such as `5/15`, runs to the end of the range
+          None if step > 1 => (parse_value(range)?, max),
+          None => {
+            let value = parse_value(range)?;
+            (value, value)
+          }
+        },
+      };
+
+      if start > end {
+        return None;
+      }
+
+      for value in (start..=end).step_by(usize::try_from(step).ok()?) {
+        values |= 1 << value;
+      }
+    }
+
+    Some(Self {
+      values,
+      any: input == "*",
+    })
+  }
+}
+
+/// A standard five-field cron schedule (minute, hour, day of month, month, day of week), evaluated in UTC.
+///
+/// Supports `*`, lists, ranges, steps, and month and weekday names. Day of week accepts both 0 and 7 for Sunday. As with most cron implementations, when both the day of month and day of week are restricted, a day matching either one is used.
+#[derive(Clone, Copy)]
+pub struct CronSchedule {
+  minute: Field,
+  hour: Field,
+  day_of_month: Field,
+  month: Field,
+  day_of_week: Field,
+}
+
+impl CronSchedule {
+  pub fn parse(input: &str) -> Option<Self> {
+    let fields: Vec<&str> = input.split_whitespace().collect();
+    let [minute, hour, day_of_month, month, day_of_week] = fields.as_slice() else {
+      return None;
+    };
+
+    let mut day_of_week = Field::parse(day_of_week, 0, 7, &WEEKDAY_NAMES, 0)?;
+    if day_of_week.contains(7) {
+      day_of_week.values |= 1;
+    }
+
+    Some(Self {
+      minute: Field::parse(minute, 0, 59, &[], 0)?,
+      hour: Field::parse(hour, 0, 23, &[], 0)?,
+      day_of_month: Field::parse(day_of_month, 1, 31, &[], 0)?,
+      month: Field::parse(month, 1, 12, &MONTH_NAMES, 1)?,
+      day_of_week,
+    })
+  }
+
+  fn matches_day(&self, date: NaiveDate) -> bool {
+    if !self.month.contains(date.month()) {
+      return false;
+    }
+
+    let day_of_month = self.day_of_month.contains(date.day());
+    let day_of_week = self
+      .day_of_week
+      .contains(date.weekday().num_days_from_sunday());
+
+    match (self.day_of_month.any, self.day_of_week.any) {
+      (false, false) => day_of_month || day_of_week,
+      _ => day_of_month && day_of_week,
+    }
+  }
+
+  /// First time the schedule matches strictly after `after`, or `None` if it never does, such as for February 30.
+  pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+
+    for offset in 0..MAX_SEARCH_DAYS {
+      let date = start.date_naive() + Duration::days(offset);
+      if !self.matches_day(date) {
+        continue;
+      }
+
+      let (first_hour, first_minute) = if offset == 0 {
+        (start.hour(), start.minute())
+      } else {
+        (0, 0)
+      };
+
+      for hour in (first_hour..24).filter(|hour| self.hour.contains(*hour)) {
+        let from_minute = if hour == first_hour { first_minute } else { 0 };
+        if let Some(minute) = (from_minute..60).find(|minute| self.minute.contains(*minute)) {
+          return Some(date.and_hms_opt(hour, minute, 0)?.and_utc());
+        }
+      }
+    }
+
+    None
+  }
+}
@@ -0,0 +1,5 @@
+pub mod cooldown;
+pub mod cron;
+pub mod rate_limit;
+pub mod template;
+pub mod time;
@@ -0,0 +1,219 @@
+use crate::shared_state::SharedState;
+use crate::Context;
+use anyhow::Result;
+use poise::serenity_prelude as serenity;
+use poise::CreateReply;
+use std::time::Duration;
+
+/// Images a guild can make at once, and seconds until it can make another.
+const GUILD_BURST: f64 = 6.0;
+const GUILD_REFILL_SECONDS: f64 = 10.0;
+/// Images a member can make at once, and seconds until they can make another.
+const USER_BURST: f64 = 3.0;
+const USER_REFILL_SECONDS: f64 = 30.0;
+/// Longest a command waits its turn before it is turned away instead.
+const MAX_WAIT: Duration = Duration::from_secs(60);
+/// How long a bucket stays locked if the process updating it goes away, and how often a locked bucket is checked again.
+const LOCK_TTL: Duration = Duration::from_secs(5);
+const LOCK_RETRY: Duration = Duration::from_millis(10);
+
+/// Token buckets for guilds or members, kept in [`SharedState`] under `ratelimit:{kind}:{id}` so the limits hold across every bot process.
+///
+/// Taking a token from an empty bucket reserves the next one, leaving the bucket in debt, so commands waiting for a token are served in the order they arrived.
+/// Buckets expire once they have filled back up, since they are then the same as new ones.
+struct Buckets {
+  kind: &'static str,
+  burst: f64,
+  refill_seconds: f64,
+}
+
+const GUILD_BUCKETS: Buckets = Buckets {
+  kind: "guild",
+  burst: GUILD_BURST,
+  refill_seconds: GUILD_REFILL_SECONDS,
+};
+const USER_BUCKETS: Buckets = Buckets {
+  kind: "user",
+  burst: USER_BURST,
+  refill_seconds: USER_REFILL_SECONDS,
+};
+
+impl Buckets {
+  /// Adds `change` tokens to the bucket for `id` after refilling it, returning the tokens left.
+  ///
+  /// The bucket is locked while it is updated, so commands in other processes can't take the same token.
+  #[allow(clippy::cast_precision_loss)]
+  async fn update(&self, state: &dyn SharedState, id: u64, change: f64) -> Result<f64> {
+    let key = format!("ratelimit:{}:{id}", self.kind);
+    let lock_key = format!("{key}:lock");
+
+    while !state
+      .set_if_absent(&lock_key, String::new(), LOCK_TTL)
+      .await?
+    {
+      tokio::time::sleep(LOCK_RETRY).await;
+    }
+
+    let tokens = async {
+      let now = chrono::Utc::now().timestamp_millis();
+      let (tokens, updated_at) = state
+        .get(&key)
+        .await?
+        .and_then(|bucket| {
+          let (tokens, updated_at) = bucket.split_once(' ')?;
+          Some((tokens.parse::<f64>().ok()?, updated_at.parse::<i64>().ok()?))
+        })
+        .unwrap_or((self.burst, now));
+
+      let refilled = (now - updated_at).max(0) as f64 / 1000.0 / self.refill_seconds;
+      let tokens = ((tokens + refilled).min(self.burst) + change).min(self.burst);
+
+      if tokens < self.burst {
+        let full_after = Duration::from_secs_f64((self.burst - tokens) * self.refill_seconds);
+        state
+          .set(&key, format!("{tokens} {now}"), Some(full_after))
+          .await?;
+      } else {
+        state.remove(&key).await?;
+      }
+
+      anyhow::Ok(tokens)
+    }
+    .await;
+
+    state.remove(&lock_key).await?;
+
+    tokens
+  }
+
+  /// Takes a token, returning how long until it can be used and the command's place in the queue, which is 0 if it can be used right away.
+  #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+  async fn take(&self, state: &dyn SharedState, id: u64) -> Result<(Duration, usize)> {
+    let tokens = self.update(state, id, -1.0).await?;
+
+    if tokens >= 0.0 {
+      Ok((Duration::ZERO, 0))
+    } else {
+      Ok((
+        Duration::from_secs_f64(-tokens * self.refill_seconds),
+        (-tokens).ceil() as usize,
+      ))
+    }
+  }
+
+  /// Returns a token taken by a command that won't run after all.
+  async fn give_back(&self, state: &dyn SharedState, id: u64) -> Result<()> {
+    self.update(state, id, 1.0).await?;
+
+    Ok(())
+  }
+}
+
+/// Rate limits commands that make images, such as charts and quote cards, per guild and per member, so they can't be spammed.
+///
+/// Call this before making the image. Commands over the limit wait their turn, after telling the member their place in the queue, or are turned away if the wait would be longer than [`MAX_WAIT`].
+/// Returns `false` if the command was turned away, in which case the member has already been told.
+pub async fn throttle_images(ctx: Context<'_>) -> Result<bool> {
+  let state = ctx.data().state.as_ref();
+  let user_id = ctx.author().id.get();
+  let guild_id = ctx.guild_id().map(serenity::GuildId::get);
+
+  let (user_wait, _) = USER_BUCKETS.take(state, user_id).await?;
+  let (guild_wait, position) = match guild_id {
+    Some(guild_id) => GUILD_BUCKETS.take(state, guild_id).await?,
+    None => (Duration::ZERO, 0),
+  };
+  let wait = user_wait.max(guild_wait);
+
+  if wait.is_zero() {
+    return Ok(true);
+  }
+
+  if wait > MAX_WAIT {
+    USER_BUCKETS.give_back(state, user_id).await?;
+    if let Some(guild_id) = guild_id {
+      GUILD_BUCKETS.give_back(state, guild_id).await?;
+    }
+
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Too many images are being made right now. Please try again in {} seconds.",
+            (wait - MAX_WAIT).as_secs().max(1)
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(false);
+  }
+
+  let content = if guild_wait >= user_wait {
+    format!(
+      ":hourglass: Lots of images are being made in this server right now. You're number {position} in the queue, and yours will start in about {} seconds.",
+      wait.as_secs().max(1)
+    )
+  } else {
+    format!(
+      ":hourglass: You've made several images in a row. Yours will start in about {} seconds.",
+      wait.as_secs().max(1)
+    )
+  };
+
+  ctx
+    .send(CreateReply::default().content(content).ephemeral(true))
+    .await?;
+
+  tokio::time::sleep(wait).await;
+
+  Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::shared_state::MemoryState;
+
+  #[tokio::test]
+  async fn queues_after_burst() {
+    let state = MemoryState::default();
+
+    for _ in 0..3 {
+      assert_eq!(USER_BUCKETS.take(&state, 1).await.unwrap().1, 0);
+    }
+
+    let (wait, position) = USER_BUCKETS.take(&state, 1).await.unwrap();
+    assert_eq!(position, 1);
+    assert!(wait > Duration::from_secs(29) && wait <= Duration::from_secs(30));
+
+    let (_, position) = USER_BUCKETS.take(&state, 1).await.unwrap();
+    assert_eq!(position, 2);
+
+    // Other members have their own buckets
+    assert_eq!(USER_BUCKETS.take(&state, 2).await.unwrap().1, 0);
+  }
+
+  #[tokio::test]
+  async fn tokens_given_back() {
+    let state = MemoryState::default();
+
+    for _ in 0..3 {
+      USER_BUCKETS.take(&state, 1).await.unwrap();
+    }
+    USER_BUCKETS.give_back(&state, 1).await.unwrap();
+
+    assert_eq!(USER_BUCKETS.take(&state, 1).await.unwrap().1, 0);
+    assert_eq!(USER_BUCKETS.take(&state, 1).await.unwrap().1, 1);
+  }
+
+  #[tokio::test]
+  async fn buckets_kept_in_shared_state() {
+    let state = MemoryState::default();
+
+    GUILD_BUCKETS.take(&state, 1).await.unwrap();
+
+    let buckets = state.scan("ratelimit:").await.unwrap();
+    assert_eq!(buckets.len(), 1);
+    assert_eq!(buckets[0].0, "ratelimit:guild:1");
+  }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+fn is_variable_name(name: &str) -> bool {
+  !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parses values for template variables, written as `name=value` pairs separated by semicolons, such as `theme=Gratitude; host=Sam`.
+///
+/// Names are case-insensitive. Returns `None` if a pair has no `=` or an invalid name.
+pub fn parse_template_values(input: &str) -> Option<HashMap<String, String>> {
+  input
+    .split(';')
+    .filter(|pair| !pair.trim().is_empty())
+    .map(|pair| {
+      let (name, value) = pair.split_once('=')?;
+      let name = name.trim().to_lowercase();
+      is_variable_name(&name).then(|| (name, value.trim().to_string()))
+    })
+    .collect()
+}
+
+/// Fills in `{variable}` placeholders with their values. Braces around anything other than a variable name are left as they are.
+///
+/// Returns the names of variables that have no value as the error, without duplicates.
+pub fn render_template(
+  text: &str,
+  values: &HashMap<String, String>,
+) -> Result<String, Vec<String>> {
+  let mut rendered = String::with_capacity(text.len());
+  let mut missing: Vec<String> = Vec::new();
+  let mut rest = text;
+
+  while let Some(start) = rest.find('{') {
+    rendered.push_str(&rest[..start]);
+    let after = &rest[start + 1..];
+
+    let name = after
+      .find('}')
+      .map(|end| &after[..end])
+      .filter(|name| is_variable_name(name));
+
+    let Some(name) = name else {
+      rendered.push('{');
+      rest = after;
+      continue;
+    };
+
+    match values.get(&name.to_lowercase()) {
+      Some(value) => rendered.push_str(value),
+      None => {
+        if !missing
+          .iter()
+          .any(|existing| existing.eq_ignore_ascii_case(name))
+        {
+          missing.push(name.to_string());
+        }
+      }
+    }
+    rest = &after[name.len() + 1..];
+  }
+  rendered.push_str(rest);
+
+  if missing.is_empty() {
+    Ok(rendered)
+  } else {
+    Err(missing)
+  }
+}
@@ -0,0 +1,221 @@
+use chrono::{DateTime, Datelike, Duration, Utc};
+
+/// Parses a session duration written the way people usually type it, returning whole minutes.
+///
+/// Accepts units such as `1h 20m`, `45 mins`, or `1 hour and 5 minutes`, clock times such as `1:30:00` (hours, minutes, seconds) or `45:00` (minutes and seconds), and plain numbers, which are read as minutes. Seconds are rounded to the nearest minute.
+///
+/// Returns `None` if the input can't be understood or rounds down to no time at all.
+pub fn parse_duration(input: &str) -> Option<i32> {
+  let input = input.trim().to_lowercase();
+
+  let seconds = if input.contains(':') {
+    parse_clock(&input)?
+  } else {
+    parse_units(&input)?
+  };
+
+  let minutes = seconds.saturating_add(30) / 60;
+  i32::try_from(minutes).ok().filter(|minutes| *minutes > 0)
+}
+
+/// Parses `mm:ss` or `hh:mm:ss`, as shown by most timer apps.
+fn parse_clock(input: &str) -> Option<u64> {
+  let parts = input
+    .split(':')
+    .map(|part| part.trim().parse::<u64>().ok())
+    .collect::<Option<Vec<u64>>>()?;
+
+  match parts.as_slice() {
+    [minutes, seconds] if *seconds < 60 => minutes.checked_mul(60)?.checked_add(*seconds),
+    [hours, minutes, seconds] if *minutes < 60 && *seconds < 60 => {
+      hours.checked_mul(3600)?.checked_add(minutes * 60 + seconds)
+    }
+    _ => None,
+  }
+}
+
+/// Parses numbers followed by optional units, such as `1h 20m`. Numbers without a unit are minutes.
+fn parse_units(input: &str) -> Option<u64> {
+  let mut rest = input;
+  let mut total: u64 = 0;
+  let mut found = false;
+
+  loop {
+    rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    if rest.is_empty() {
+      break;
+    }
+    if let Some(after_and) = rest.strip_prefix("and ") {
+      rest = after_and;
+      continue;
+    }
+
+    let number_end = rest
+      .find(|c: char| !c.is_ascii_digit())
+      .unwrap_or(rest.len());
+    if number_end == 0 {
+      return None;
+    }
+    let value = rest[..number_end].parse::<u64>().ok()?;
+    rest = rest[number_end..].trim_start();
+
+    let unit_end = rest
+      .find(|c: char| !c.is_alphabetic())
+      .unwrap_or(rest.len());
+    let multiplier = match &rest[..unit_end] {
+      "" | "m" | "min" | "mins" | "minute" | "minutes" => 60,
+      "h" | "hr" | "hrs" | "hour" | "hours" => 3600,
+      "s" | "sec" | "secs" | "second" | "seconds" => 1,
+      _ => return None,
+    };
+    rest = &rest[unit_end..];
+
+    total = total.checked_add(value.checked_mul(multiplier)?)?;
+    found = true;
+  }
+
+  found.then_some(total)
+}
+
+/// Parses a time of day such as `22:00`, `7:30`, `7am`, or `10:30 pm`, returning minutes after midnight.
+pub fn parse_time_of_day(input: &str) -> Option<i16> {
+  let input = input.trim().to_lowercase();
+
+  let (clock, pm) = if let Some(clock) = input.strip_suffix("am") {
+    (clock.trim(), Some(false))
+  } else if let Some(clock) = input.strip_suffix("pm") {
+    (clock.trim(), Some(true))
+  } else {
+    (input.as_str(), None)
+  };
+
+  let (hours, minutes) = match clock.split_once(':') {
+    Some((hours, minutes)) => (hours.parse::<i16>().ok()?, minutes.parse::<i16>().ok()?),
+    None => (clock.parse::<i16>().ok()?, 0),
+  };
+
+  let hours = match pm {
+    // 12am is midnight and 12pm is noon
+    Some(pm) if (1..=12).contains(&hours) => hours % 12 + if pm { 12 } else { 0 },
+    Some(_) => return None,
+    None => hours,
+  };
+
+  ((0..24).contains(&hours) && (0..60).contains(&minutes)).then_some(hours * 60 + minutes)
+}
+
+/// Most recent time a weekly schedule was due, at or before `now`. The weekday is counted in days from Monday, and the time in minutes after midnight UTC.
+pub fn last_weekly_time(weekday: i16, send_minute: i16, now: DateTime<Utc>) -> DateTime<Utc> {
+  let days_back =
+    (i64::from(now.weekday().num_days_from_monday()) - i64::from(weekday)).rem_euclid(7);
+  let due = (now.date_naive() - Duration::days(days_back))
+    .and_hms_opt(0, 0, 0)
+    .unwrap_or_default()
+    .and_utc()
+    + Duration::minutes(i64::from(send_minute));
+
+  if due > now {
+    due - Duration::days(7)
+  } else {
+    due
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::parse_duration;
+
+  #[test]
+  fn plain_numbers_are_minutes() {
+    assert_eq!(parse_duration("20"), Some(20));
+    assert_eq!(parse_duration("1"), Some(1));
+  }
+
+  #[test]
+  fn units() {
+    assert_eq!(parse_duration("45m"), Some(45));
+    assert_eq!(parse_duration("45 min"), Some(45));
+    assert_eq!(parse_duration("45 mins"), Some(45));
+    assert_eq!(parse_duration("45 minutes"), Some(45));
+    assert_eq!(parse_duration("1 minute"), Some(1));
+    assert_eq!(parse_duration("2h"), Some(120));
+    assert_eq!(parse_duration("2 hrs"), Some(120));
+    assert_eq!(parse_duration("1 hour"), Some(60));
+    assert_eq!(parse_duration("3 hours"), Some(180));
+    assert_eq!(parse_duration("90s"), Some(2));
+    assert_eq!(parse_duration("120 seconds"), Some(2));
+  }
+
+  #[test]
+  fn units_are_case_insensitive() {
+    assert_eq!(parse_duration("1H 20M"), Some(80));
+    assert_eq!(parse_duration("45 Mins"), Some(45));
+  }
+
+  #[test]
+  fn combined_units() {
+    assert_eq!(parse_duration("1h 20m"), Some(80));
+    assert_eq!(parse_duration("1h20m"), Some(80));
+    assert_eq!(parse_duration("1 hour and 5 minutes"), Some(65));
+    assert_eq!(parse_duration("1 hour, 5 minutes"), Some(65));
+    assert_eq!(parse_duration("1h 20m 30s"), Some(81));
+    assert_eq!(parse_duration("1h 30"), Some(90));
+  }
+
+  #[test]
+  fn clock_times() {
+    assert_eq!(parse_duration("45:00"), Some(45));
+    assert_eq!(parse_duration("1:30:00"), Some(90));
+    assert_eq!(parse_duration("0:20:29"), Some(20));
+    assert_eq!(parse_duration("0:20:30"), Some(21));
+    assert_eq!(parse_duration("90:00"), Some(90));
+  }
+
+  #[test]
+  fn seconds_round_to_the_nearest_minute() {
+    assert_eq!(parse_duration("29s"), None);
+    assert_eq!(parse_duration("30s"), Some(1));
+    assert_eq!(parse_duration("10m 29s"), Some(10));
+    assert_eq!(parse_duration("10m 30s"), Some(11));
+  }
+
+  #[test]
+  fn surrounding_and_inner_whitespace() {
+    assert_eq!(parse_duration("  20  "), Some(20));
+    assert_eq!(parse_duration("\t1h\n20m "), Some(80));
+    assert_eq!(parse_duration("1   h    20   m"), Some(80));
+    assert_eq!(parse_duration(" 1 : 30 : 00 "), Some(90));
+  }
+
+  #[test]
+  fn overflow_is_rejected() {
+    assert_eq!(parse_duration("99999999999999999999"), None);
+    assert_eq!(parse_duration("18446744073709551615 hours"), None);
+    assert_eq!(parse_duration("18446744073709551615s"), None);
+    assert_eq!(parse_duration("5000000000:00:00"), None);
+    assert_eq!(parse_duration("2147483648"), None);
+  }
+
+  #[test]
+  fn zero_is_rejected() {
+    assert_eq!(parse_duration("0"), None);
+    assert_eq!(parse_duration("0h 0m"), None);
+    assert_eq!(parse_duration("0:00"), None);
+  }
+
+  #[test]
+  fn invalid_input_is_rejected() {
+    assert_eq!(parse_duration(""), None);
+    assert_eq!(parse_duration("   "), None);
+    assert_eq!(parse_duration("and"), None);
+    assert_eq!(parse_duration("twenty"), None);
+    assert_eq!(parse_duration("20 days"), None);
+    assert_eq!(parse_duration("h20"), None);
+    assert_eq!(parse_duration("-20"), None);
+    assert_eq!(parse_duration("1.5h"), None);
+    assert_eq!(parse_duration("1:60"), None);
+    assert_eq!(parse_duration("1:60:00"), None);
+    assert_eq!(parse_duration("1:2:3:4"), None);
+    assert_eq!(parse_duration("1h:20m"), None);
+  }
+}
@@ -0,0 +1,658 @@
+use crate::config::{BloomBotEmbed, ImportConflict, CHANNELS};
+use crate::database::{DatabaseHandler, ImportError, ImportJob, ImportedChunk, SessionLimits};
+use crate::Context;
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use log::{error, info};
+use poise::serenity_prelude::{
+  self as serenity, CreateAttachment, CreateEmbedFooter, CreateMessage,
+};
+use poise::ChoiceParameter;
+use poise::CreateReply;
+use std::fmt::Write;
+
+/// Largest file that can be imported, which is Discord's upload limit.
+const MAX_IMPORT_SIZE: u32 = 25 * 1024 * 1024;
+/// Rows added in each transaction. Progress is saved after every chunk.
+const CHUNK_ROWS: usize = 500;
+/// Chunks between progress updates, so large imports don't hit rate limits.
+const PROGRESS_EVERY_CHUNKS: i32 = 10;
+/// How long an import can go without saving a chunk before it's assumed to have stopped, and can be resumed.
+const STALE_AFTER_MINUTES: i64 = 2;
+/// Longest session that can be imported when the server has no maximum set.
+const MAX_SESSION_MINUTES: i32 = 24 * 60;
+/// How close in time a row must be to an existing entry to count as a duplicate of it.
+const DUPLICATE_TOLERANCE_MINUTES: i32 = 5;
+
+/// Columns of an imported file, found from its header.
+struct Columns {
+  date: usize,
+  minutes: usize,
+}
+
+/// Import past meditation entries
+///
+/// Commands to import your meditation history from a CSV file, such as one exported from another app.
+#[poise::command(
+  slash_command,
+  category = "Meditation Tracking",
+  subcommands("start", "resume"),
+  guild_only
+)]
+#[allow(clippy::unused_async)]
+pub async fn import(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Import meditation entries from a CSV file
+///
+/// Adds your past meditation entries from a CSV file. The first line must name the columns, including a `date` column and a `minutes` column; other columns are ignored. Dates can be written as `2024-01-31`, `2024-01-31 07:30`, or with a UTC offset, such as `2024-01-31T07:30:00+01:00`. Times without an offset are treated as UTC.
+///
+/// Rows within 5 minutes of one of your existing entries are duplicates. By default they are skipped. Merging them instead lengthens the existing entry to match the row, if the row is longer. Replacing overlapping days removes your existing entries on every day the file has entries for, so the file's entries take their place.
+///
+/// Large files are imported in chunks, and progress is saved after each one. If the import stops, continue it with `/import resume`. Rows that can't be imported are skipped and listed in a report at the end.
+#[poise::command(slash_command)]
+pub async fn start(
+  ctx: Context<'_>,
+  #[description = "CSV file with a date column and a minutes column"] file: serenity::Attachment,
+  #[description = "How to handle rows that overlap your existing entries (Defaults to skip duplicates)"]
+  on_conflict: Option<ImportConflict>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  if !file.filename.to_lowercase().ends_with(".csv") {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Please upload a CSV file.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  if file.size > MAX_IMPORT_SIZE {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Imported files can be at most {} MB.",
+            MAX_IMPORT_SIZE / 1024 / 1024
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  ctx.defer_ephemeral().await?;
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  if DatabaseHandler::get_unfinished_import_job(&mut transaction, &guild_id, &ctx.author().id)
+    .await?
+    .is_some()
+  {
+    ctx
+      .say(":x: You already have an unfinished import. Use `/import resume` to continue it.")
+      .await?;
+    return Ok(());
+  }
+
+  #[allow(clippy::cast_possible_wrap)]
+  let file_size = file.size as i32;
+  let job_id = DatabaseHandler::create_import_job(
+    &mut transaction,
+    &guild_id,
+    &ctx.author().id,
+    &file.filename,
+    &file.url,
+    file_size,
+    on_conflict.unwrap_or(ImportConflict::Skip),
+  )
+  .await?;
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  let job = ImportJob {
+    id: job_id,
+    file_name: file.filename.clone(),
+    file_url: file.url.clone(),
+    file_size,
+    rows_processed: 0,
+    chunks_completed: 0,
+    entries_added: 0,
+    rows_failed: 0,
+    on_conflict: on_conflict.unwrap_or(ImportConflict::Skip),
+    duplicates_skipped: 0,
+    entries_replaced: 0,
+    entries_merged: 0,
+    heartbeat_at: Some(Utc::now()),
+  };
+
+  info!(
+    "{} started importing {} ({} bytes) in {guild_id}",
+    ctx.author().id,
+    file.filename,
+    file.size
+  );
+
+  run_import(ctx, job).await
+}
+
+/// Resume an unfinished import
+///
+/// Continues your last import from the last saved chunk, such as after a restart or a timeout. Entries that were already added are not added again.
+///
+/// If the original file can no longer be downloaded, upload the same file again.
+#[poise::command(slash_command)]
+pub async fn resume(
+  ctx: Context<'_>,
+  #[description = "The same file again, if the original can no longer be downloaded"] file: Option<
+    serenity::Attachment,
+  >,
+) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let Some(mut job) =
+    DatabaseHandler::get_unfinished_import_job(&mut transaction, &guild_id, &ctx.author().id)
+      .await?
+  else {
+    ctx.say(":x: You don't have an unfinished import.").await?;
+    return Ok(());
+  };
+
+  // Only the size can be checked before reading the file, which catches most mix-ups
+  if file
+    .as_ref()
+    .is_some_and(|file| i64::from(file.size) != i64::from(job.file_size))
+  {
+    ctx
+      .say(format!(
+        ":x: That file is different from the one being imported. Please upload **{}** again.",
+        job.file_name
+      ))
+      .await?;
+    return Ok(());
+  }
+
+  let stale_before = Utc::now() - chrono::Duration::minutes(STALE_AFTER_MINUTES);
+  let file_url = file.as_ref().map(|file| file.url.as_str());
+  if !DatabaseHandler::claim_import_job(&mut transaction, &job.id, stale_before, file_url).await? {
+    ctx
+      .say(
+        ":x: Your import is still running. If it has stopped, please try again in a few minutes.",
+      )
+      .await?;
+    return Ok(());
+  }
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  if let Some(file_url) = file_url {
+    job.file_url = file_url.to_string();
+  }
+
+  run_import(ctx, job).await
+}
+
+/// Streams the job's file from its last checkpoint, then reports the result.
+async fn run_import(ctx: Context<'_>, job: ImportJob) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let limits = DatabaseHandler::get_session_limits(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let progress = ctx
+    .say(if job.rows_processed > 0 {
+      format!(
+        ":hourglass: Resuming your import of **{}** after {} rows...",
+        job.file_name, job.rows_processed
+      )
+    } else {
+      format!(":hourglass: Importing **{}**...", job.file_name)
+    })
+    .await?;
+
+  if let Err(e) = stream_rows(ctx, &job, &limits, &progress).await {
+    error!("Import {} stopped: {e:?}", job.id);
+
+    // Best effort, since a crashed import can still be resumed once it goes stale
+    if let Ok(mut transaction) = data.db.start_transaction_with_retry(5).await {
+      if DatabaseHandler::pause_import_job(&mut transaction, &job.id)
+        .await
+        .is_ok()
+      {
+        let _ = DatabaseHandler::commit_transaction(transaction).await;
+      }
+    }
+
+    let _ = progress
+      .edit(
+        ctx,
+        CreateReply::default().content(format!(
+          ":x: Your import of **{}** stopped before it finished. Everything up to the last saved chunk was added. Use `/import resume` to continue.",
+          job.file_name
+        )),
+      )
+      .await;
+    return Ok(());
+  }
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let job = DatabaseHandler::finish_import_job(&mut transaction, &job.id).await?;
+  let errors = DatabaseHandler::get_import_errors(&mut transaction, &job.id).await?;
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  let mut embed = BloomBotEmbed::new()
+    .title("Import Finished")
+    .description(format!("Finished importing **{}**.", job.file_name))
+    .field(
+      "Entries Added",
+      format!("```{}```", job.entries_added),
+      true,
+    )
+    .field("Rows Skipped", format!("```{}```", job.rows_failed), true)
+    .field("Chunks", format!("```{}```", job.chunks_completed), true);
+  embed = match job.on_conflict {
+    ImportConflict::Skip => embed.field(
+      "Duplicates Skipped",
+      format!("```{}```", job.duplicates_skipped),
+      true,
+    ),
+    ImportConflict::Replace => embed.field(
+      "Entries Replaced",
+      format!("```{}```", job.entries_replaced),
+      true,
+    ),
+    ImportConflict::Merge => embed.field(
+      "Entries Merged",
+      format!("```{}```", job.entries_merged),
+      true,
+    ),
+  };
+  if !errors.is_empty() {
+    embed = embed.footer(CreateEmbedFooter::new(
+      "Skipped rows are listed in the attached report.",
+    ));
+  }
+
+  let report = (!errors.is_empty()).then(|| {
+    CreateAttachment::bytes(
+      error_report(&errors),
+      format!("import-errors-{}.csv", job.id),
+    )
+  });
+
+  let mut reply = CreateReply::default().embed(embed.clone()).ephemeral(true);
+  if let Some(report) = &report {
+    reply = reply.attachment(report.clone());
+  }
+
+  // Long imports can outlast the interaction, so the report is sent by DM instead
+  if ctx.send(reply).await.is_err() {
+    let mut message = CreateMessage::new().embed(embed);
+    if let Some(report) = report {
+      message = message.add_file(report);
+    }
+    if let Err(e) = ctx.author().direct_message(ctx, message).await {
+      info!("Could not send import report to {}: {e}", ctx.author().id);
+    }
+  }
+
+  let mut log_embed = BloomBotEmbed::new()
+    .title("Meditation Entries Imported")
+    .description(format!(
+      "**Member**: <@{}>\n**File**: {}\n**Conflicts**: {}\n**Entries added**: {}\n**Rows skipped**: {}\n**Duplicates skipped**: {}\n**Entries replaced**: {}\n**Entries merged**: {}",
+      ctx.author().id,
+      job.file_name,
+      job.on_conflict.name(),
+      job.entries_added,
+      job.rows_failed,
+      job.duplicates_skipped,
+      job.entries_replaced,
+      job.entries_merged
+    ))
+    .footer(
+      CreateEmbedFooter::new(format!(
+        "Imported by {} ({})",
+        ctx.author().name,
+        ctx.author().id
+      ))
+      .icon_url(ctx.author().avatar_url().unwrap_or_default()),
+    );
+
+  // Replaced entries are moved to the trash under the job's ID, so the import can be undone
+  if job.entries_replaced > 0 {
+    log_embed = log_embed.field("Trash batch", format!("`{}`", job.id), false);
+  }
+
+  let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+
+  log_channel
+    .send_message(ctx, CreateMessage::new().embed(log_embed))
+    .await?;
+
+  Ok(())
+}
+
+/// Downloads the file a piece at a time and imports its rows in chunks, skipping rows that were already processed.
+///
+/// The whole file is never held in memory, only the current chunk and any partial line.
+async fn stream_rows<'a>(
+  ctx: Context<'a>,
+  job: &ImportJob,
+  limits: &SessionLimits,
+  progress: &poise::ReplyHandle<'a>,
+) -> Result<()> {
+  let mut response = reqwest::get(&job.file_url).await?.error_for_status()?;
+
+  let mut importer = ChunkedImport {
+    ctx,
+    job,
+    limits,
+    progress,
+    columns: None,
+    line: 0,
+    row: 0,
+    chunk: job.chunks_completed,
+    rows_processed: job.rows_processed,
+    pending: Vec::with_capacity(CHUNK_ROWS),
+  };
+
+  let mut buffer = Vec::new();
+  while let Some(bytes) = response.chunk().await? {
+    buffer.extend_from_slice(&bytes);
+
+    while let Some(end) = buffer.iter().position(|byte| *byte == b'\n') {
+      let line = buffer.drain(..=end).collect::<Vec<u8>>();
+      if !importer.push_line(&line).await? {
+        return Ok(());
+      }
+    }
+  }
+
+  if !buffer.is_empty() {
+    importer.push_line(&buffer).await?;
+  }
+  importer.save_chunk().await
+}
+
+/// State of an import while its file is being read.
+struct ChunkedImport<'a, 'b> {
+  ctx: Context<'a>,
+  job: &'b ImportJob,
+  limits: &'b SessionLimits,
+  progress: &'b poise::ReplyHandle<'a>,
+  columns: Option<Columns>,
+  /// Lines read so far, including the header.
+  line: i32,
+  /// Data rows read so far.
+  row: i32,
+  chunk: i32,
+  /// Data rows saved so far, including those from before the import was resumed.
+  rows_processed: i32,
+  /// Line numbers and contents of rows read since the last chunk was saved.
+  pending: Vec<(i32, String)>,
+}
+
+impl ChunkedImport<'_, '_> {
+  /// Reads one line of the file, saving a chunk once enough rows are pending. Returns `false` if the rest of the file should be skipped.
+  async fn push_line(&mut self, line: &[u8]) -> Result<bool> {
+    self.line += 1;
+    let text = String::from_utf8_lossy(line);
+    let text = text.trim_end_matches(['\r', '\n']);
+
+    if self.columns.is_none() {
+      match parse_header(text) {
+        Ok(columns) => {
+          self.columns = Some(columns);
+          return Ok(true);
+        }
+        Err(message) => {
+          // Without the columns, none of the rows can be read
+          self.chunk += 1;
+          let mut transaction = self.ctx.data().db.start_transaction_with_retry(5).await?;
+          let errors = [ImportError {
+            chunk: self.chunk,
+            line: self.line,
+            message,
+          }];
+          DatabaseHandler::add_import_errors(&mut transaction, &self.job.id, &errors).await?;
+          DatabaseHandler::save_import_checkpoint(
+            &mut transaction,
+            &self.job.id,
+            0,
+            1,
+            &ImportedChunk::default(),
+          )
+          .await?;
+          DatabaseHandler::commit_transaction(transaction).await?;
+          return Ok(false);
+        }
+      }
+    }
+
+    self.row += 1;
+    if self.row <= self.job.rows_processed {
+      return Ok(true);
+    }
+
+    self.pending.push((self.line, text.to_string()));
+    if self.pending.len() >= CHUNK_ROWS {
+      self.save_chunk().await?;
+    }
+
+    Ok(true)
+  }
+
+  /// Adds the pending rows and saves the checkpoint in one transaction.
+  async fn save_chunk(&mut self) -> Result<()> {
+    if self.pending.is_empty() {
+      return Ok(());
+    }
+    let Some(columns) = &self.columns else {
+      return Ok(());
+    };
+
+    let chunk = self.chunk + 1;
+    let now = Utc::now();
+
+    let mut entries = Vec::with_capacity(self.pending.len());
+    let mut errors = Vec::new();
+    for (line, text) in &self.pending {
+      // Blank lines, such as at the end of the file, are skipped without an error
+      if text.trim().is_empty() {
+        continue;
+      }
+
+      match parse_row(text, columns, self.limits, now) {
+        Ok(entry) => entries.push(entry),
+        Err(message) => errors.push(ImportError {
+          chunk,
+          line: *line,
+          message,
+        }),
+      }
+    }
+
+    // We unwrap here, because we know that the command is guild-only.
+    let guild_id = self.ctx.guild_id().unwrap();
+
+    let mut transaction = self.ctx.data().db.start_transaction_with_retry(5).await?;
+    let imported = if entries.is_empty() {
+      ImportedChunk::default()
+    } else {
+      DatabaseHandler::import_entries(
+        &mut transaction,
+        &self.job.id,
+        &guild_id,
+        &self.ctx.author().id,
+        self.job.on_conflict,
+        DUPLICATE_TOLERANCE_MINUTES,
+        &entries,
+      )
+      .await?
+    };
+    DatabaseHandler::add_import_errors(&mut transaction, &self.job.id, &errors).await?;
+
+    // Chunks are small, so these always fit
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let (rows, failed) = (self.pending.len() as i32, errors.len() as i32);
+    DatabaseHandler::save_import_checkpoint(
+      &mut transaction,
+      &self.job.id,
+      rows,
+      failed,
+      &imported,
+    )
+    .await?;
+    DatabaseHandler::commit_transaction(transaction).await?;
+
+    self.chunk = chunk;
+    self.pending.clear();
+    self.rows_processed += rows;
+
+    if self.chunk % PROGRESS_EVERY_CHUNKS == 0 {
+      // Best effort, since the interaction may have timed out on a long import
+      let _ = self
+        .progress
+        .edit(
+          self.ctx,
+          CreateReply::default().content(format!(
+            ":hourglass: Importing **{}**... {} rows so far.",
+            self.job.file_name, self.rows_processed
+          )),
+        )
+        .await;
+    }
+
+    Ok(())
+  }
+}
+
+/// Splits a CSV line into fields, allowing quoted fields with commas and escaped quotes.
+fn split_csv_line(line: &str) -> Vec<String> {
+  let mut fields = Vec::new();
+  let mut field = String::new();
+  let mut quoted = false;
+  let mut chars = line.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '"' if quoted && chars.peek() == Some(&'"') => {
+        field.push('"');
+        chars.next();
+      }
+      '"' => quoted = !quoted,
+      ',' if !quoted => fields.push(std::mem::take(&mut field)),
+      _ => field.push(c),
+    }
+  }
+  fields.push(field);
+
+  fields
+}
+
+fn parse_header(line: &str) -> Result<Columns, String> {
+  let names = split_csv_line(line.trim_start_matches('\u{feff}'))
+    .into_iter()
+    .map(|name| name.trim().to_lowercase())
+    .collect::<Vec<String>>();
+
+  let find = |name: &str| {
+    names
+      .iter()
+      .position(|column| column == name)
+      .ok_or_else(|| format!("The first line must name the columns, including a `{name}` column"))
+  };
+
+  Ok(Columns {
+    date: find("date")?,
+    minutes: find("minutes")?,
+  })
+}
+
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+  if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+    return Some(date.with_timezone(&Utc));
+  }
+
+  [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+  ]
+  .iter()
+  .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+  .or_else(|| {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+      .ok()
+      .map(|date| date.and_time(chrono::NaiveTime::MIN))
+  })
+  .map(|date| date.and_utc())
+}
+
+/// Reads an entry from a row, applying the server's session limits.
+fn parse_row(
+  line: &str,
+  columns: &Columns,
+  limits: &SessionLimits,
+  now: DateTime<Utc>,
+) -> Result<(DateTime<Utc>, i32), String> {
+  let fields = split_csv_line(line);
+  let field = |index: usize| fields.get(index).map_or("", |field| field.trim());
+
+  let date = field(columns.date);
+  let Some(occurred_at) = parse_date(date) else {
+    return Err(format!("Could not read the date `{date}`"));
+  };
+  if occurred_at > now {
+    return Err(format!("The date `{date}` is in the future"));
+  }
+
+  let minutes = field(columns.minutes);
+  let Ok(minutes) = minutes.parse::<i32>() else {
+    return Err(format!("Could not read the minutes `{minutes}`"));
+  };
+
+  let min_minutes = limits.min_minutes.unwrap_or(1).max(1);
+  let max_minutes = limits.max_minutes.unwrap_or(MAX_SESSION_MINUTES);
+  if minutes < min_minutes {
+    return Err(format!("Entries must be at least {min_minutes} minutes"));
+  }
+  if minutes > max_minutes {
+    return Err(format!(
+      "Entries cannot be longer than {max_minutes} minutes"
+    ));
+  }
+
+  Ok((occurred_at, minutes))
+}
+
+/// Lists skipped rows as a CSV file, grouped by chunk.
+fn error_report(errors: &[ImportError]) -> Vec<u8> {
+  let mut report = String::from("chunk,line,error\n");
+  for error in errors {
+    let _ = writeln!(
+      report,
+      "{},{},\"{}\"",
+      error.chunk,
+      error.line,
+      error.message.replace('"', "\"\"")
+    );
+  }
+
+  report.into_bytes()
+}
@@ -0,0 +1,67 @@
+use crate::database::{DatabaseHandler, TrackingProfile};
+use crate::streaks::Activity;
+use crate::Context;
+use anyhow::Result;
+use chrono::Duration;
+use poise::CreateReply;
+
+/// Log a practice diary entry
+///
+/// Logs an entry in your practice diary, building a diary streak alongside your meditation streak.
+///
+/// Optionally add a note to reflect on your practice. Notes are private and only stored for your own record.
+#[poise::command(slash_command, category = "Meditation Tracking", guild_only)]
+pub async fn journal(
+  ctx: Context<'_>,
+  #[description = "A private note about your practice"]
+  #[max_length = 1000]
+  note: Option<String>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+  let user_id = ctx.author().id;
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+
+  let tracking_profile =
+    match DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user_id).await? {
+      Some(tracking_profile) => tracking_profile,
+      None => TrackingProfile {
+        ..Default::default()
+      },
+    };
+
+  // Stored in local time, like meditation entries, so days line up with the meditation streak
+  let occurred_at = chrono::Utc::now() + Duration::minutes(i64::from(tracking_profile.utc_offset));
+
+  DatabaseHandler::add_activity_entry(
+    &mut transaction,
+    &guild_id,
+    &user_id,
+    Activity::Journal,
+    note.as_deref(),
+    None,
+    occurred_at,
+  )
+  .await?;
+
+  let streak =
+    DatabaseHandler::get_activity_streak(&mut transaction, &guild_id, &user_id, Activity::Journal)
+      .await?;
+
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  ctx
+    .send(
+      CreateReply::default()
+        .content(format!(
+          ":white_check_mark: Your diary entry has been logged. Your current diary streak is {streak} days."
+        ))
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
@@ -8,7 +8,7 @@ use poise::CreateReply;
 
 /// Commands for managing Playne keys
 ///
-/// Commands to list, add, remove, or use Playne keys.
+/// Commands to list, add, remove, or use Playne keys, and to track challenge prize awards.
 ///
 /// Requires `Administrator` permissions.
 #[poise::command(
@@ -16,7 +16,14 @@ use poise::CreateReply;
   required_permissions = "ADMINISTRATOR",
   default_member_permissions = "ADMINISTRATOR",
   category = "Admin Commands",
-  subcommands("list_keys", "add_key", "remove_key", "use_key", "recipients"),
+  subcommands(
+    "list_keys",
+    "add_key",
+    "remove_key",
+    "use_key",
+    "recipients",
+    "list_awards"
+  ),
   //hide_in_help,
   guild_only
 )]
@@ -510,3 +517,83 @@ pub async fn update_recipient(
 
   Ok(())
 }
+
+/// List challenge prize awards
+///
+/// Lists challenge prize awards with their fulfillment status, showing whether each key was accepted, declined, expired, or is still awaiting a response.
+#[poise::command(slash_command, rename = "awards")]
+pub async fn list_awards(
+  ctx: Context<'_>,
+  #[description = "The page to show"] page: Option<usize>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+
+  // Define some unique identifiers for the navigation buttons
+  let ctx_id = ctx.id();
+  let prev_button_id = format!("{ctx_id}prev");
+  let next_button_id = format!("{ctx_id}next");
+
+  let mut current_page = page.unwrap_or(0).saturating_sub(1);
+
+  let awards = DatabaseHandler::get_prize_awards(&mut transaction, &guild_id).await?;
+  let awards: Vec<PageRowRef> = awards.iter().map(|award| award as PageRowRef).collect();
+  drop(transaction);
+  let pagination = Pagination::new("Challenge Prize Awards", awards).await?;
+
+  if pagination.get_page(current_page).is_none() {
+    current_page = pagination.get_last_page_number();
+  }
+
+  let first_page = pagination.create_page_embed(current_page);
+
+  ctx
+    .send({
+      let mut f = CreateReply::default();
+      if pagination.get_page_count() > 1 {
+        f = f.components(vec![CreateActionRow::Buttons(vec![
+          CreateButton::new(&prev_button_id).label("Previous"),
+          CreateButton::new(&next_button_id).label("Next"),
+        ])]);
+      }
+      f.embeds = vec![first_page];
+      f.ephemeral(true)
+    })
+    .await?;
+
+  // Loop through incoming interactions with the navigation buttons
+  while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+    // We defined our button IDs to start with `ctx_id`. If they don't, some other command's
+    // button was pressed
+    .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+    // Timeout when no navigation button has been pressed for 24 hours
+    .timeout(std::time::Duration::from_secs(3600 * 24))
+    .await
+  {
+    // Depending on which button was pressed, go to next or previous page
+    if press.data.custom_id == next_button_id {
+      current_page = pagination.update_page_number(current_page, 1);
+    } else if press.data.custom_id == prev_button_id {
+      current_page = pagination.update_page_number(current_page, -1);
+    } else {
+      // This is an unrelated button interaction
+      continue;
+    }
+
+    // Update the message with the new page contents
+    press
+      .create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+          CreateInteractionResponseMessage::new().embed(pagination.create_page_embed(current_page)),
+        ),
+      )
+      .await?;
+  }
+
+  Ok(())
+}
@@ -1,14 +1,37 @@
 #![allow(clippy::too_many_arguments)]
 
+use crate::announcements;
+use crate::collective_goals;
+use crate::command_sync::{self, CommandDiff, UNEXCLUDABLE_COMMANDS};
+use crate::commands::courses::Weekday;
+use crate::commands::customize::OnOff;
+use crate::commands::helpers::cron::CronSchedule;
+use crate::commands::helpers::template::{parse_template_values, render_template};
+use crate::commands::helpers::time::parse_time_of_day;
 use crate::commands::{commit_and_say, MessageType};
-use crate::config::{BloomBotEmbed, CHANNELS};
-use crate::database::DatabaseHandler;
+use crate::config::{
+  BloomBotEmbed, ChartPalette, CongratsDelivery, EmojiKind, EntrySource, LeaderboardPeriod,
+  RetentionCategory, StarboardCleanup, StatsAccessAction, AUTO_THREAD_VARIABLES, CHANNELS,
+  DEFAULT_AUTO_THREAD_NAME, DEFAULT_CHECKIN_MESSAGE, DEFAULT_HOURS_INTERVAL, DEFAULT_HOURS_MESSAGE,
+  FEATURE_FLAGS, HOURS_MESSAGE_VARIABLES, MAX_STREAK_CREDITS, STREAK_CREDIT_DAYS,
+};
+use crate::database::{
+  AnomalyFlag, DatabaseHandler, HoursAnnouncement, RestoreCounts, SessionLimits, TrackingProfile,
+};
+use crate::feature_flags;
+use crate::leaderboard_rollovers;
+use crate::milestones;
 use crate::pagination::{PageRowRef, Pagination};
-use crate::Context;
+use crate::retention::TRASH_RETENTION_DAYS;
+use crate::starboard;
+use crate::stats_access;
+use crate::{Context, Data as AppData, Error as AppError};
 use anyhow::Result;
 use chrono::{Datelike, Timelike};
 use poise::serenity_prelude::{self as serenity, builder::*, Mentionable};
-use poise::{ChoiceParameter, CreateReply};
+use poise::{ChoiceParameter, CreateReply, Modal};
+use std::collections::{BTreeMap, HashMap};
+use ulid::Ulid;
 
 #[derive(poise::ChoiceParameter)]
 pub enum DataType {
@@ -18,14 +41,85 @@ pub enum DataType {
   CustomizationSettings,
 }
 
+/// Most webhooks a server can register with `/manage webhooks`.
+const MAX_WEBHOOKS: usize = 5;
+
+/// Largest backup archive accepted by `/manage restore`.
+const MAX_BACKUP_SIZE: u32 = 8 * 1024 * 1024;
+
+/// Minutes a second moderator has to approve a reset, migration, or restore before it is cancelled.
+const APPROVAL_TIMEOUT_MINUTES: u64 = 10;
+
+#[derive(poise::ChoiceParameter)]
+pub enum MaintenanceMode {
+  #[name = "on"]
+  On,
+  #[name = "off"]
+  Off,
+}
+
+#[derive(poise::ChoiceParameter)]
+pub enum UsagePeriod {
+  #[name = "past day"]
+  Day,
+  #[name = "past week"]
+  Week,
+  #[name = "past month"]
+  Month,
+  #[name = "past year"]
+  Year,
+}
+
 /// Commands for managing meditation entries
 ///
-/// Commands to create, list, update, or delete meditation entries for a user, or completely reset a user's data.
+/// Commands to create, list, update, or delete meditation entries for a user, or completely reset a user's data. Also shows command usage statistics for the server, and lets administrators restrict commands to specific roles or channels, put the bot in maintenance mode, back up and restore server data, manage moderation reason templates, choose channels where message edits are logged, filter which deleted messages are logged, set limits on session length, customize the emoji used in bot messages, review suspicious tracking activity, create channels for a newly added server, choose the channels where members track their meditation, link partner servers for combined stats, register webhooks for server milestones, fix a user's entries in bulk, set how long logged data is kept, enable text commands with a custom prefix, choose voice channels where time is tracked automatically, post starred messages in a forum with tags, schedule announcements, post messages from reusable templates, start discussion threads on new messages automatically, post a weekly check-in prompt, set a collective goal for the whole server, choose how often the server's total hours are announced, let members restore broken streaks with streak credits, post and pin the final leaderboard at the end of each week or month, choose high-contrast or colorblind-safe chart colors, restore deleted meditation entries from the trash, ask a member for access to their private stats, simulate an add to check role and milestone settings, turn features being rolled out on or off for the server, or, for the bot owner, re-register commands and exclude commands from the server.
 ///
 /// Requires `Ban Members` permissions.
 #[poise::command(
   slash_command,
-  subcommands("create", "list", "update", "delete", "reset", "migrate"),
+  subcommands(
+    "create",
+    "list",
+    "update",
+    "delete",
+    "reset",
+    "migrate",
+    "usage",
+    "permissions",
+    "maintenance",
+    "backup",
+    "restore",
+    "reasons",
+    "editlog",
+    "logging",
+    "limits",
+    "emojis",
+    "anomalies",
+    "setup",
+    "tracking",
+    "partners",
+    "webhooks",
+    "adjust",
+    "retention",
+    "prefix",
+    "voice",
+    "starboard",
+    "announce",
+    "templates",
+    "post",
+    "threads",
+    "checkin",
+    "collective",
+    "milestones",
+    "restores",
+    "rollover",
+    "charts",
+    "trash",
+    "stats",
+    "simulate",
+    "synccommands",
+    "flags"
+  ),
   subcommand_required,
   required_permissions = "BAN_MEMBERS",
   default_member_permissions = "BAN_MEMBERS",
@@ -119,6 +213,7 @@ pub async fn create(
     &user.id,
     minutes,
     datetime,
+    EntrySource::Slash,
   )
   .await?;
 
@@ -455,7 +550,13 @@ pub async fn delete(
     return Ok(());
   };
 
-  DatabaseHandler::delete_meditation_entry(&mut transaction, &entry_id).await?;
+  let batch_id = DatabaseHandler::delete_meditation_entry(
+    &mut transaction,
+    &entry_id,
+    &ctx.author().id,
+    "deleted",
+  )
+  .await?;
 
   let success_embed = BloomBotEmbed::new()
     .title("Meditation Entry Deleted")
@@ -466,6 +567,9 @@ pub async fn delete(
       entry.occurred_at.format("%B %d, %Y"),
       entry.meditation_minutes
     ))
+    .footer(CreateEmbedFooter::new(format!(
+      "Restore within {TRASH_RETENTION_DAYS} days with /manage trash restore {batch_id}"
+    )))
     .clone();
 
   commit_and_say(
@@ -479,7 +583,7 @@ pub async fn delete(
   let log_embed = BloomBotEmbed::new()
     .title("Meditation Entry Deleted")
     .description(format!(
-      "**User**: <@{}>\n**ID**: {}\n**Date**: {}\n**Time**: {} minute(s)",
+      "**User**: <@{}>\n**ID**: {}\n**Date**: {}\n**Time**: {} minute(s)\n**Trash batch**: `{batch_id}`",
       entry.user_id,
       entry.id,
       entry.occurred_at.format("%B %d, %Y"),
@@ -504,9 +608,155 @@ pub async fn delete(
   Ok(())
 }
 
+/// Posts an approval request for a destructive action in the logs channel, and waits for a second moderator to approve or reject it.
+///
+/// The moderator who started the action can't approve it, and the approver needs the `required` permissions. Returns the approver, or `None` if the action was rejected or nobody answered in time.
+/// Waiting can take up to [`APPROVAL_TIMEOUT_MINUTES`], so callers shouldn't hold a transaction open while waiting, and should only make their changes once the action is approved.
+async fn second_approval(
+  ctx: Context<'_>,
+  title: &str,
+  details: &str,
+  required: serenity::Permissions,
+) -> Result<Option<serenity::User>> {
+  let ctx_id = ctx.id();
+  let approve_id = format!("{ctx_id}second_approve");
+  let reject_id = format!("{ctx_id}second_reject");
+
+  let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+
+  let request_embed = BloomBotEmbed::new()
+    .title(format!("Approval Needed: {title}"))
+    .description(format!(
+      "{details}\n\nAnother moderator needs to approve this within {APPROVAL_TIMEOUT_MINUTES} minutes."
+    ))
+    .footer(
+      CreateEmbedFooter::new(format!(
+        "Requested by {} ({})",
+        ctx.author().name,
+        ctx.author().id
+      ))
+      .icon_url(ctx.author().avatar_url().unwrap_or_default()),
+    );
+
+  let mut request_message = log_channel
+    .send_message(
+      ctx,
+      CreateMessage::new()
+        .embed(request_embed)
+        .components(vec![CreateActionRow::Buttons(vec![
+          CreateButton::new(approve_id.clone())
+            .label("Approve")
+            .style(serenity::ButtonStyle::Success),
+          CreateButton::new(reject_id.clone())
+            .label("Reject")
+            .style(serenity::ButtonStyle::Danger),
+        ])]),
+    )
+    .await?;
+
+  ctx
+    .send(
+      CreateReply::default()
+        .content(format!(
+          "Waiting for another moderator to approve this in {}. Nothing changes until they do.",
+          log_channel.mention()
+        ))
+        .ephemeral(true),
+    )
+    .await?;
+
+  while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+    .message_id(request_message.id)
+    .timeout(std::time::Duration::from_secs(
+      APPROVAL_TIMEOUT_MINUTES * 60,
+    ))
+    .await
+  {
+    if press.data.custom_id != approve_id && press.data.custom_id != reject_id {
+      // This is an unrelated button interaction
+      continue;
+    }
+
+    let approved = press.data.custom_id == approve_id;
+
+    let has_permissions = press
+      .member
+      .as_ref()
+      .and_then(|member| member.permissions)
+      .is_some_and(|permissions| permissions.administrator() || permissions.contains(required));
+    let refusal = if !has_permissions {
+      Some("You don't have permission to answer this request.")
+    } else if approved && press.user.id == ctx.author().id {
+      Some("You can't approve your own request. Another moderator needs to approve it.")
+    } else {
+      None
+    };
+    if let Some(refusal) = refusal {
+      press
+        .create_response(
+          ctx,
+          CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+              .content(refusal)
+              .ephemeral(true),
+          ),
+        )
+        .await?;
+      continue;
+    }
+
+    let outcome = if approved { "Approved" } else { "Rejected" };
+    press
+      .create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+          CreateInteractionResponseMessage::new()
+            .content(format!("{outcome} by {}.", press.user.mention()))
+            .components(Vec::new()),
+        ),
+      )
+      .await?;
+
+    if approved {
+      return Ok(Some(press.user.clone()));
+    }
+
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: {} rejected this. Nothing was changed.",
+            press.user.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(None);
+  }
+
+  // This happens when nobody answered in time
+  request_message
+    .edit(
+      ctx,
+      EditMessage::new()
+        .content("Expired without approval.")
+        .components(Vec::new()),
+    )
+    .await?;
+  ctx
+    .send(
+      CreateReply::default()
+        .content(":x: Nobody approved this in time. Nothing was changed.")
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(None)
+}
+
 /// Reset meditation entries or customization settings
 ///
-/// Resets all meditation entries or customization settings for a user.
+/// Resets all meditation entries or customization settings for a user. Resetting meditation entries needs another moderator's approval in the logs channel.
 #[poise::command(slash_command)]
 pub async fn reset(
   ctx: Context<'_>,
@@ -518,23 +768,12 @@ pub async fn reset(
   let data = ctx.data();
   let guild_id = ctx.guild_id().unwrap();
 
-  let mut transaction = data.db.start_transaction_with_retry(5).await?;
-
   //Default to meditation entries
   let data_type = match data_type {
     Some(data_type) => data_type,
     None => DataType::MeditationEntries,
   };
 
-  match data_type {
-    DataType::CustomizationSettings => {
-      DatabaseHandler::remove_tracking_profile(&mut transaction, &guild_id, &user.id).await?;
-    }
-    DataType::MeditationEntries => {
-      DatabaseHandler::reset_user_meditation_entries(&mut transaction, &guild_id, &user.id).await?;
-    }
-  }
-
   let ctx_id = ctx.id();
 
   let confirm_id = format!("{ctx_id}confirm");
@@ -591,8 +830,56 @@ pub async fn reset(
         .await
       {
         Ok(()) => {
+          // Resetting meditation entries needs a second moderator's approval. Nothing is changed until it arrives, so no transaction is held open while waiting
+          let approver = if matches!(data_type, DataType::MeditationEntries) {
+            let Some(approver) = second_approval(
+              ctx,
+              "Meditation Entries Reset",
+              &format!("**User**: <@{}>", user.id),
+              serenity::Permissions::BAN_MEMBERS,
+            )
+            .await?
+            else {
+              return Ok(());
+            };
+            Some(approver)
+          } else {
+            None
+          };
+
+          let mut transaction = data.db.start_transaction_with_retry(5).await?;
+          let batch_id = match data_type {
+            DataType::CustomizationSettings => {
+              DatabaseHandler::remove_tracking_profile(&mut transaction, &guild_id, &user.id)
+                .await?;
+              None
+            }
+            DataType::MeditationEntries => Some(
+              DatabaseHandler::reset_user_meditation_entries(
+                &mut transaction,
+                &guild_id,
+                &user.id,
+                &ctx.author().id,
+                "reset",
+              )
+              .await?,
+            ),
+          };
           DatabaseHandler::commit_transaction(transaction).await?;
 
+          if let (Some(batch_id), Some(approver)) = (&batch_id, &approver) {
+            ctx
+              .send(
+                CreateReply::default()
+                  .content(format!(
+                    ":white_check_mark: {} approved the reset. The entries can be restored within {TRASH_RETENTION_DAYS} days with `/manage trash restore {batch_id}`.",
+                    approver.mention()
+                  ))
+                  .ephemeral(true),
+              )
+              .await?;
+          }
+
           let log_embed = BloomBotEmbed::new()
             .title(format!(
               "{} Reset",
@@ -601,7 +888,13 @@ pub async fn reset(
                 DataType::MeditationEntries => "Meditation Entries",
               }
             ))
-            .description(format!("**User**: <@{}>", user.id))
+            .description(match (&batch_id, &approver) {
+              (Some(batch_id), Some(approver)) => format!(
+                "**User**: <@{}>\n**Trash batch**: `{batch_id}`\n**Approved by**: <@{}>",
+                user.id, approver.id
+              ),
+              _ => format!("**User**: <@{}>", user.id),
+            })
             .footer(
               CreateEmbedFooter::new(format!(
                 "Reset by {} ({})",
@@ -621,9 +914,8 @@ pub async fn reset(
           return Ok(());
         }
         Err(e) => {
-          DatabaseHandler::rollback_transaction(transaction).await?;
           return Err(anyhow::anyhow!(
-            "Failed to tell user that the {} were reset: {}",
+            "Failed to tell user that resetting the {} was confirmed: {}",
             data_type.name(),
             e
           ));
@@ -649,7 +941,7 @@ pub async fn reset(
 
 /// Migrates meditation entries or customization settings
 ///
-/// Migrates all meditation entries or customization settings from one user account to another.
+/// Migrates all meditation entries or customization settings from one user account to another. If the new account already has data, the two are merged: duplicate entries are removed and settings are combined. Shows a summary of the result before confirming. Migrating meditation entries needs another moderator's approval in the logs channel.
 #[poise::command(slash_command)]
 pub async fn migrate(
   ctx: Context<'_>,
@@ -662,34 +954,27 @@ pub async fn migrate(
   let data = ctx.data();
   let guild_id = ctx.guild_id().unwrap();
 
-  let mut transaction = data.db.start_transaction_with_retry(5).await?;
-
   //Default to meditation entries
   let data_type = match data_type {
     Some(data_type) => data_type,
     None => DataType::MeditationEntries,
   };
 
-  match data_type {
-    DataType::CustomizationSettings => {
-      DatabaseHandler::migrate_tracking_profile(
-        &mut transaction,
-        &guild_id,
-        &old_user.id,
-        &new_user.id,
-      )
-      .await?;
-    }
-    DataType::MeditationEntries => {
-      DatabaseHandler::migrate_meditation_entries(
-        &mut transaction,
-        &guild_id,
-        &old_user.id,
-        &new_user.id,
-      )
-      .await?;
-    }
-  }
+  // The migration is previewed and rolled back, so no transaction is held open while waiting for confirmation or approval.
+  // The preview uses the same trash batch as the migration, so the batch it shows is the one duplicates can be restored from.
+  let batch_id = Ulid::new().to_string();
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let summary = migrate_data(
+    &mut transaction,
+    &guild_id,
+    &old_user.id,
+    &new_user.id,
+    &data_type,
+    &batch_id,
+    &ctx.author().id,
+  )
+  .await?;
+  DatabaseHandler::rollback_transaction(transaction).await?;
 
   let ctx_id = ctx.id();
 
@@ -700,7 +985,7 @@ pub async fn migrate(
     .send(
       CreateReply::default()
         .content(format!(
-          "Are you sure you want to migrate all {} from {} to {}?",
+          "Are you sure you want to migrate all {} from {} to {}?\n\n{summary}",
           data_type.name(),
           old_user.mention(),
           new_user.mention(),
@@ -748,6 +1033,49 @@ pub async fn migrate(
         .await
       {
         Ok(()) => {
+          // Migrating meditation entries needs a second moderator's approval before it is committed
+          let approved_by = if matches!(data_type, DataType::MeditationEntries) {
+            let Some(approver) = second_approval(
+              ctx,
+              "Meditation Entries Migration",
+              &format!(
+                "**From**: <@{}>\n**To**: <@{}>\n\n{summary}",
+                old_user.id, new_user.id
+              ),
+              serenity::Permissions::BAN_MEMBERS,
+            )
+            .await?
+            else {
+              return Ok(());
+            };
+
+            ctx
+              .send(
+                CreateReply::default()
+                  .content(format!(
+                    ":white_check_mark: {} approved the migration.",
+                    approver.mention()
+                  ))
+                  .ephemeral(true),
+              )
+              .await?;
+            format!("\n**Approved by**: <@{}>", approver.id)
+          } else {
+            String::new()
+          };
+
+          // Data may have changed since the preview, so the migration is run again and its own summary is logged
+          let mut transaction = data.db.start_transaction_with_retry(5).await?;
+          let summary = migrate_data(
+            &mut transaction,
+            &guild_id,
+            &old_user.id,
+            &new_user.id,
+            &data_type,
+            &batch_id,
+            &ctx.author().id,
+          )
+          .await?;
           DatabaseHandler::commit_transaction(transaction).await?;
 
           let log_embed = BloomBotEmbed::new()
@@ -759,7 +1087,7 @@ pub async fn migrate(
               }
             ))
             .description(format!(
-              "**From**: <@{}>\n**To**: <@{}>",
+              "**From**: <@{}>\n**To**: <@{}>{approved_by}\n\n{summary}",
               old_user.id, new_user.id,
             ))
             .footer(
@@ -781,9 +1109,8 @@ pub async fn migrate(
           return Ok(());
         }
         Err(e) => {
-          DatabaseHandler::rollback_transaction(transaction).await?;
           return Err(anyhow::anyhow!(
-            "Failed to tell user that the {} were migrated: {}",
+            "Failed to tell user that migrating the {} was confirmed: {}",
             data_type.name(),
             e
           ));
@@ -806,3 +1133,6094 @@ pub async fn migrate(
   // This happens when the user didn't press any button for 60 seconds
   Ok(())
 }
+
+/// Migrates one kind of data from `old_user_id` to `new_user_id`, returning a summary of what changed.
+async fn migrate_data(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  guild_id: &serenity::GuildId,
+  old_user_id: &serenity::UserId,
+  new_user_id: &serenity::UserId,
+  data_type: &DataType,
+  batch_id: &str,
+  migrated_by: &serenity::UserId,
+) -> Result<String> {
+  match data_type {
+    DataType::CustomizationSettings => {
+      merge_tracking_profiles(transaction, guild_id, old_user_id, new_user_id).await
+    }
+    DataType::MeditationEntries => {
+      merge_meditation_entries(
+        transaction,
+        guild_id,
+        old_user_id,
+        new_user_id,
+        batch_id,
+        migrated_by,
+      )
+      .await
+    }
+  }
+}
+
+/// Moves a tracking profile to another user, merging it with any profile the user already has.
+///
+/// The new account's time zone is kept unless it was never set. Privacy settings are merged so that anything either account kept private stays private.
+async fn merge_tracking_profiles(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  guild_id: &serenity::GuildId,
+  old_user_id: &serenity::UserId,
+  new_user_id: &serenity::UserId,
+) -> Result<String> {
+  let old_profile =
+    DatabaseHandler::get_tracking_profile(transaction, guild_id, old_user_id).await?;
+  let new_profile =
+    DatabaseHandler::get_tracking_profile(transaction, guild_id, new_user_id).await?;
+
+  let (Some(old_profile), Some(new_profile)) = (old_profile, new_profile) else {
+    DatabaseHandler::migrate_tracking_profile(transaction, guild_id, old_user_id, new_user_id)
+      .await?;
+    return Ok("**Settings**: Moved without changes".to_string());
+  };
+
+  let utc_offset = if new_profile.utc_offset == 0 {
+    old_profile.utc_offset
+  } else {
+    new_profile.utc_offset
+  };
+
+  DatabaseHandler::update_tracking_profile(
+    transaction,
+    guild_id,
+    new_user_id,
+    utc_offset,
+    old_profile.anonymous_tracking || new_profile.anonymous_tracking,
+    old_profile.streaks_active && new_profile.streaks_active,
+    old_profile.streaks_private || new_profile.streaks_private,
+    old_profile.stats_private || new_profile.stats_private,
+  )
+  .await?;
+  DatabaseHandler::update_congrats_cards(
+    transaction,
+    guild_id,
+    new_user_id,
+    old_profile.congrats_cards || new_profile.congrats_cards,
+  )
+  .await?;
+  DatabaseHandler::update_add_reaction(
+    transaction,
+    guild_id,
+    new_user_id,
+    new_profile
+      .add_reaction
+      .as_deref()
+      .or(old_profile.add_reaction.as_deref()),
+  )
+  .await?;
+  DatabaseHandler::remove_tracking_profile(transaction, guild_id, old_user_id).await?;
+
+  Ok(format!(
+    "**Settings**: Merged with existing settings\n**UTC Offset**: {utc_offset} minutes"
+  ))
+}
+
+/// Moves meditation entries to another user and moves entries that now appear twice, such as sessions logged from both accounts, to the trash batch `batch_id`.
+///
+/// Returns a summary of the merged totals, including the recalculated streak.
+async fn merge_meditation_entries(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  guild_id: &serenity::GuildId,
+  old_user_id: &serenity::UserId,
+  new_user_id: &serenity::UserId,
+  batch_id: &str,
+  migrated_by: &serenity::UserId,
+) -> Result<String> {
+  let moved =
+    DatabaseHandler::migrate_meditation_entries(transaction, guild_id, old_user_id, new_user_id)
+      .await?;
+  let duplicates = DatabaseHandler::remove_duplicate_meditation_entries(
+    transaction,
+    guild_id,
+    new_user_id,
+    batch_id,
+    migrated_by,
+    "migrate",
+  )
+  .await?;
+  let trash_batch = if duplicates > 0 {
+    format!("\n**Trash batch**: `{batch_id}`")
+  } else {
+    String::new()
+  };
+
+  let minutes =
+    DatabaseHandler::get_user_meditation_sum(transaction, guild_id, new_user_id).await?;
+  let sessions =
+    DatabaseHandler::get_user_meditation_count(transaction, guild_id, new_user_id).await?;
+  let streak = DatabaseHandler::get_streak(transaction, guild_id, new_user_id).await?;
+
+  Ok(format!(
+    "**Entries Moved**: {moved}\n**Duplicates Removed**: {duplicates}{trash_batch}\n**New Total**: {minutes} minutes in {sessions} sessions\n**Current Streak**: {streak} days"
+  ))
+}
+
+/// Show command usage statistics
+///
+/// Shows the most used commands for the server over the specified period, along with their error rates and response times.
+#[poise::command(slash_command)]
+pub async fn usage(
+  ctx: Context<'_>,
+  #[description = "The period to show usage for (Defaults to past week)"] period: Option<
+    UsagePeriod,
+  >,
+  #[description = "The number of commands to show (Defaults to 10)"]
+  #[min = 1]
+  #[max = 25]
+  limit: Option<usize>,
+) -> Result<()> {
+  let data = ctx.data();
+  let guild_id = ctx.guild_id().unwrap();
+
+  let period = period.unwrap_or(UsagePeriod::Week);
+  let start_time = chrono::Utc::now()
+    - match period {
+      UsagePeriod::Day => chrono::Duration::days(1),
+      UsagePeriod::Week => chrono::Duration::weeks(1),
+      UsagePeriod::Month => chrono::Duration::days(30),
+      UsagePeriod::Year => chrono::Duration::days(365),
+    };
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let usage_stats =
+    DatabaseHandler::get_command_usage_stats(&mut transaction, &guild_id, start_time).await?;
+  drop(transaction);
+
+  if usage_stats.is_empty() {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            "No commands have been used in the {}.",
+            period.name()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let total_uses: i64 = usage_stats.iter().map(|stats| stats.uses).sum();
+  let total_errors: i64 = usage_stats.iter().map(|stats| stats.errors).sum();
+
+  let mut table = format!(
+    "{:<24}{:>6}{:>8}{:>9}{:>9}\n",
+    "Command", "Uses", "Errors", "p50", "p95"
+  );
+  for stats in usage_stats.iter().take(limit.unwrap_or(10)) {
+    #[allow(clippy::cast_precision_loss)]
+    let error_rate = (stats.errors as f64 / stats.uses as f64) * 100.0;
+    table.push_str(&format!(
+      "{:<24}{:>6}{:>7.1}%{:>7.0}ms{:>7.0}ms\n",
+      stats.command_name, stats.uses, error_rate, stats.median_latency, stats.p95_latency
+    ));
+  }
+
+  #[allow(clippy::cast_precision_loss)]
+  let total_error_rate = (total_errors as f64 / total_uses as f64) * 100.0;
+
+  ctx
+    .send(
+      CreateReply::default()
+        .embed(
+          BloomBotEmbed::new()
+            .title(format!("Command Usage ({})", period.name()))
+            .description(format!("```{table}```"))
+            .footer(CreateEmbedFooter::new(format!(
+              "{total_uses} command(s) used | {total_error_rate:.1}% error rate"
+            ))),
+        )
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Collects the qualified names of all commands and subcommands.
+fn collect_command_names(commands: &[poise::Command<AppData, AppError>], names: &mut Vec<String>) {
+  for command in commands {
+    names.push(command.qualified_name.clone());
+    collect_command_names(&command.subcommands, names);
+  }
+}
+
+#[allow(clippy::unused_async)]
+async fn autocomplete_command(ctx: Context<'_>, partial: &str) -> Vec<String> {
+  let mut names = Vec::new();
+  collect_command_names(&ctx.framework().options().commands, &mut names);
+
+  let partial = partial.to_lowercase();
+  names
+    .into_iter()
+    .filter(|name| name.contains(&partial))
+    .take(25)
+    .collect()
+}
+
+/// Checks that a command name refers to a registered command or subcommand.
+fn is_known_command(ctx: Context<'_>, command_name: &str) -> bool {
+  let mut names = Vec::new();
+  collect_command_names(&ctx.framework().options().commands, &mut names);
+
+  names.iter().any(|name| name == command_name)
+}
+
+/// Restrict commands to roles or channels
+///
+/// Commands to restrict commands to specific roles or channels, remove restrictions, or list current restrictions.
+///
+/// When a command has role restrictions, members need at least one of those roles to use it. When it has channel restrictions, it can only be used in one of those channels. Restrictions on a parent command also apply to its subcommands. Administrators are never restricted.
+///
+/// Requires `Administrator` permissions.
+#[poise::command(
+  slash_command,
+  subcommands("restrict", "unrestrict", "restrictions"),
+  subcommand_required,
+  required_permissions = "ADMINISTRATOR"
+)]
+#[allow(clippy::unused_async)]
+pub async fn permissions(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Restrict a command to a role or channel
+///
+/// Restricts a command to a role or channel. Add multiple restrictions to allow several roles or channels.
+#[poise::command(slash_command)]
+pub async fn restrict(
+  ctx: Context<'_>,
+  #[description = "The command to restrict (e.g. \"terms\" or \"terms add\")"]
+  #[autocomplete = "autocomplete_command"]
+  command: String,
+  #[description = "The role allowed to use the command"] role: Option<serenity::Role>,
+  #[description = "The channel the command can be used in"] channel: Option<serenity::Channel>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let command = command.trim().trim_start_matches('/').to_lowercase();
+  if !is_known_command(ctx, &command) {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(":x: No command named `/{command}` exists."))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let (role_id, channel_id) = match (&role, &channel) {
+    (Some(role), None) => (Some(role.id), None),
+    (None, Some(channel)) => (None, Some(channel.id())),
+    _ => {
+      ctx
+        .send(
+          CreateReply::default()
+            .content(":x: Please specify either a role or a channel.")
+            .ephemeral(true),
+        )
+        .await?;
+      return Ok(());
+    }
+  };
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  if DatabaseHandler::command_restriction_exists(
+    &mut transaction,
+    &guild_id,
+    &command,
+    role_id.as_ref(),
+    channel_id.as_ref(),
+  )
+  .await?
+  {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: That restriction already exists.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  DatabaseHandler::add_command_restriction(
+    &mut transaction,
+    &guild_id,
+    &command,
+    role_id.as_ref(),
+    channel_id.as_ref(),
+  )
+  .await?;
+
+  let target = match (role_id, channel_id) {
+    (Some(role_id), _) => format!("members with {}", role_id.mention()),
+    (_, Some(channel_id)) => channel_id.mention().to_string(),
+    _ => unreachable!(),
+  };
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: `/{command}` is now restricted to {target}."
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Remove a command restriction
+///
+/// Removes a role or channel restriction from a command.
+#[poise::command(slash_command)]
+pub async fn unrestrict(
+  ctx: Context<'_>,
+  #[description = "The restricted command"]
+  #[autocomplete = "autocomplete_command"]
+  command: String,
+  #[description = "The role to remove from the restriction"] role: Option<serenity::Role>,
+  #[description = "The channel to remove from the restriction"] channel: Option<serenity::Channel>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let command = command.trim().trim_start_matches('/').to_lowercase();
+  let (role_id, channel_id) = match (&role, &channel) {
+    (Some(role), None) => (Some(role.id), None),
+    (None, Some(channel)) => (None, Some(channel.id())),
+    _ => {
+      ctx
+        .send(
+          CreateReply::default()
+            .content(":x: Please specify either a role or a channel.")
+            .ephemeral(true),
+        )
+        .await?;
+      return Ok(());
+    }
+  };
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed = DatabaseHandler::remove_command_restriction(
+    &mut transaction,
+    &guild_id,
+    &command,
+    role_id.as_ref(),
+    channel_id.as_ref(),
+  )
+  .await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: That restriction does not exist.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(":white_check_mark: Restriction has been removed.".to_string()),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// List command restrictions
+///
+/// Lists all command restrictions for the server.
+#[poise::command(slash_command, rename = "list")]
+pub async fn restrictions(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let restrictions =
+    DatabaseHandler::get_command_restrictions(&mut transaction, &guild_id, &[]).await?;
+  drop(transaction);
+
+  if restrictions.is_empty() {
+    ctx
+      .send(
+        CreateReply::default()
+          .content("No commands are restricted.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  // Group restrictions by command, keeping commands in alphabetical order
+  let mut grouped: BTreeMap<String, (Vec<String>, Vec<String>)> = BTreeMap::new();
+  for restriction in restrictions {
+    let (roles, channels) = grouped.entry(restriction.command_name).or_default();
+    if let Some(role_id) = restriction.role_id {
+      roles.push(role_id.mention().to_string());
+    }
+    if let Some(channel_id) = restriction.channel_id {
+      channels.push(channel_id.mention().to_string());
+    }
+  }
+
+  let mut embed = BloomBotEmbed::new().title("Command Restrictions");
+  for (command_name, (roles, channels)) in grouped {
+    let mut lines = Vec::new();
+    if !roles.is_empty() {
+      lines.push(format!("Roles: {}", roles.join(", ")));
+    }
+    if !channels.is_empty() {
+      lines.push(format!("Channels: {}", channels.join(", ")));
+    }
+
+    embed = embed.field(format!("/{command_name}"), lines.join("\n"), false);
+  }
+
+  ctx
+    .send(CreateReply::default().embed(embed).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Toggle read-only maintenance mode
+///
+/// Turns maintenance mode on or off. While it is on, commands that write to the database reply with a maintenance notice, while commands that only read data keep working. Quick-log links and health sync are turned away too.
+///
+/// Useful while running database migrations.
+///
+/// Requires `Administrator` permissions.
+#[poise::command(slash_command, required_permissions = "ADMINISTRATOR")]
+pub async fn maintenance(
+  ctx: Context<'_>,
+  #[description = "Turn maintenance mode on or off"] mode: MaintenanceMode,
+) -> Result<()> {
+  let enabled = matches!(mode, MaintenanceMode::On);
+  let was_enabled = ctx
+    .data()
+    .maintenance
+    .swap(enabled, std::sync::atomic::Ordering::Relaxed);
+
+  if enabled == was_enabled {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!("Maintenance mode is already {}.", mode.name()))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  ctx
+    .send(
+      CreateReply::default()
+        .content(format!(
+          ":white_check_mark: Maintenance mode is now {}.",
+          mode.name()
+        ))
+        .ephemeral(true),
+    )
+    .await?;
+
+  let log_embed = BloomBotEmbed::new()
+    .title(format!(
+      "Maintenance Mode {}",
+      if enabled { "Enabled" } else { "Disabled" }
+    ))
+    .description(if enabled {
+      "Commands that write to the database are disabled until maintenance mode is turned off."
+    } else {
+      "Commands that write to the database are available again."
+    })
+    .footer(
+      CreateEmbedFooter::new(format!(
+        "Changed by {} ({})",
+        ctx.author().name,
+        ctx.author().id
+      ))
+      .icon_url(ctx.author().avatar_url().unwrap_or_default()),
+    );
+
+  let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+
+  log_channel
+    .send_message(ctx, CreateMessage::new().embed(log_embed))
+    .await?;
+
+  Ok(())
+}
+
+/// Back up server data
+///
+/// Creates a JSON archive of the server's glossary terms, quotes, courses, Steam key recipients (and a summary of Steam keys, without the keys themselves), member settings, and bot settings.
+///
+/// Meditation entries are not included. The archive can be restored with `/manage restore`.
+///
+/// Requires `Administrator` permissions.
+#[poise::command(slash_command, required_permissions = "ADMINISTRATOR")]
+pub async fn backup(ctx: Context<'_>) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let archive = DatabaseHandler::get_guild_backup(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let file_name = format!(
+    "bloom-backup-{guild_id}-{}.json",
+    chrono::Utc::now().format("%Y%m%d-%H%M%S")
+  );
+
+  ctx
+    .send(
+      CreateReply::default()
+        .content(":white_check_mark: Backup created. Keep this file somewhere safe.")
+        .attachment(CreateAttachment::bytes(
+          archive.into_bytes(),
+          file_name.clone(),
+        ))
+        .ephemeral(true),
+    )
+    .await?;
+
+  let log_embed = BloomBotEmbed::new()
+    .title("Server Backup Created")
+    .description(format!("**File**: {file_name}"))
+    .footer(
+      CreateEmbedFooter::new(format!(
+        "Created by {} ({})",
+        ctx.author().name,
+        ctx.author().id
+      ))
+      .icon_url(ctx.author().avatar_url().unwrap_or_default()),
+    );
+
+  let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+
+  log_channel
+    .send_message(ctx, CreateMessage::new().embed(log_embed))
+    .await?;
+
+  Ok(())
+}
+
+/// Lists how many records of each kind a backup restore adds.
+fn restore_summary(counts: &RestoreCounts) -> String {
+  format!(
+    "**Terms**: {}\n**Quotes**: {}\n**Courses**: {}\n**Steam key recipients**: {}\n**Member settings**: {}\n**Command restrictions**: {}\n**Quote settings**: {}",
+    counts.terms,
+    counts.quotes,
+    counts.courses,
+    counts.steam_key_recipients,
+    counts.tracking_profiles,
+    counts.command_permissions,
+    counts.quote_settings
+  )
+}
+
+/// Restore server data from a backup
+///
+/// Restores a JSON archive created with `/manage backup`. Only missing records are added, so existing data is never overwritten. Another administrator needs to approve the restore in the logs channel before anything is added.
+///
+/// Restored glossary terms need to be reindexed with `/terms reindex` before they show up in search results.
+///
+/// Requires `Administrator` permissions.
+#[poise::command(slash_command, required_permissions = "ADMINISTRATOR")]
+pub async fn restore(
+  ctx: Context<'_>,
+  #[description = "The backup archive to restore"] archive: serenity::Attachment,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  if archive.size > MAX_BACKUP_SIZE {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Backup archives can be at most {} MB.",
+            MAX_BACKUP_SIZE / 1024 / 1024
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  ctx.defer_ephemeral().await?;
+
+  let Ok(archive_contents) = String::from_utf8(archive.download().await?) else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: That file is not a valid backup archive.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  // Validate in a separate transaction, since invalid JSON aborts the transaction
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let format = DatabaseHandler::get_backup_format(&mut transaction, &archive_contents).await;
+  drop(transaction);
+
+  let is_valid = matches!(
+    format,
+    Ok((Some(ref format), Some(ref version))) if format == "bloombot-backup" && version == "1"
+  );
+  if !is_valid {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: That file is not a valid backup archive.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  // The restore is previewed and rolled back, so no transaction is held open while waiting for approval
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let preview =
+    DatabaseHandler::restore_guild_backup(&mut transaction, &guild_id, &archive_contents).await?;
+  DatabaseHandler::rollback_transaction(transaction).await?;
+
+  let Some(approver) = second_approval(
+    ctx,
+    "Server Backup Restore",
+    &format!(
+      "**File**: {}\n\nRecords to add:\n{}",
+      archive.filename,
+      restore_summary(&preview)
+    ),
+    serenity::Permissions::ADMINISTRATOR,
+  )
+  .await?
+  else {
+    return Ok(());
+  };
+
+  // Data may have changed while waiting, so the restore is run again and its own counts are reported
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let counts =
+    DatabaseHandler::restore_guild_backup(&mut transaction, &guild_id, &archive_contents).await?;
+  let summary = restore_summary(&counts);
+
+  let mut success_embed = BloomBotEmbed::new()
+    .title("Backup Restored")
+    .description(format!(
+      "Approved by {}.\n\nRecords added:\n{summary}",
+      approver.mention()
+    ));
+  if counts.terms > 0 {
+    success_embed = success_embed.footer(CreateEmbedFooter::new(
+      "Run /terms reindex to make restored terms searchable.",
+    ));
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::EmbedOnly(success_embed),
+    true,
+  )
+  .await?;
+
+  if counts.terms > 0 {
+    data.term_names.invalidate(&guild_id).await?;
+  }
+
+  let log_embed = BloomBotEmbed::new()
+    .title("Server Backup Restored")
+    .description(format!(
+      "**File**: {}\n**Approved by**: <@{}>\n\n{summary}",
+      archive.filename, approver.id
+    ))
+    .footer(
+      CreateEmbedFooter::new(format!(
+        "Restored by {} ({})",
+        ctx.author().name,
+        ctx.author().id
+      ))
+      .icon_url(ctx.author().avatar_url().unwrap_or_default()),
+    );
+
+  let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+
+  log_channel
+    .send_message(ctx, CreateMessage::new().embed(log_embed))
+    .await?;
+
+  Ok(())
+}
+
+async fn autocomplete_reason_label(ctx: Context<'_>, partial: &str) -> Vec<String> {
+  let Some(guild_id) = ctx.guild_id() else {
+    return Vec::new();
+  };
+
+  let data = ctx.data();
+  let Ok(reasons) = data.moderation_reasons.get(&data.db, &guild_id).await else {
+    return Vec::new();
+  };
+
+  let partial = partial.to_lowercase();
+
+  reasons
+    .into_iter()
+    .map(|reason| reason.label)
+    .filter(|label| label.to_lowercase().contains(&partial))
+    .take(25)
+    .collect()
+}
+
+/// Manage moderation reason templates
+///
+/// Commands to add, edit, remove, or list reason templates. Templates are suggested when entering a reason for `/erase message`.
+#[poise::command(
+  slash_command,
+  subcommands("add_reason", "edit_reason", "remove_reason", "list_reasons"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn reasons(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Add a reason template
+///
+/// Adds a moderation reason template.
+#[poise::command(slash_command, rename = "add")]
+pub async fn add_reason(
+  ctx: Context<'_>,
+  #[description = "A short label for the template (e.g. \"Rule 3\")"]
+  #[max_length = 100]
+  label: String,
+  #[description = "The full reason"]
+  #[max_length = 512] // Max length for audit log reason
+  reason: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  if DatabaseHandler::moderation_reason_exists(&mut transaction, &guild_id, &label).await? {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: A template with that label already exists.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  DatabaseHandler::add_moderation_reason(&mut transaction, &guild_id, &label, &reason).await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(":white_check_mark: Reason template has been added.".to_string()),
+    true,
+  )
+  .await?;
+
+  data.moderation_reasons.invalidate(&guild_id).await;
+
+  Ok(())
+}
+
+/// Edit a reason template
+///
+/// Changes the reason for an existing template.
+#[poise::command(slash_command, rename = "edit")]
+pub async fn edit_reason(
+  ctx: Context<'_>,
+  #[description = "The template to edit"]
+  #[autocomplete = "autocomplete_reason_label"]
+  label: String,
+  #[description = "The new reason"]
+  #[max_length = 512] // Max length for audit log reason
+  reason: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let updated =
+    DatabaseHandler::update_moderation_reason(&mut transaction, &guild_id, &label, &reason).await?;
+
+  if updated == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Reason template does not exist.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(":white_check_mark: Reason template has been updated.".to_string()),
+    true,
+  )
+  .await?;
+
+  data.moderation_reasons.invalidate(&guild_id).await;
+
+  Ok(())
+}
+
+/// Remove a reason template
+///
+/// Removes a moderation reason template.
+#[poise::command(slash_command, rename = "remove")]
+pub async fn remove_reason(
+  ctx: Context<'_>,
+  #[description = "The template to remove"]
+  #[autocomplete = "autocomplete_reason_label"]
+  label: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed =
+    DatabaseHandler::remove_moderation_reason(&mut transaction, &guild_id, &label).await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Reason template does not exist.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(":white_check_mark: Reason template has been removed.".to_string()),
+    true,
+  )
+  .await?;
+
+  data.moderation_reasons.invalidate(&guild_id).await;
+
+  Ok(())
+}
+
+/// List reason templates
+///
+/// Lists all moderation reason templates for the server.
+#[poise::command(slash_command, rename = "list")]
+pub async fn list_reasons(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let reasons = data.moderation_reasons.get(&data.db, &guild_id).await?;
+
+  if reasons.is_empty() {
+    ctx
+      .send(
+        CreateReply::default()
+          .content("No reason templates have been added.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let mut embed = BloomBotEmbed::new().title("Reason Templates");
+  for reason in reasons.into_iter().take(25) {
+    embed = embed.field(reason.label, reason.reason, false);
+  }
+
+  ctx
+    .send(CreateReply::default().embed(embed).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Manage message edit logging
+///
+/// Commands to choose which channels have significant message edits logged, showing the content before and after the edit.
+#[poise::command(
+  slash_command,
+  subcommands("watch", "unwatch", "watched"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn editlog(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Log edits in a channel
+///
+/// Starts logging significant message edits in a channel. Logged edits are kept for a limited time.
+#[poise::command(slash_command)]
+pub async fn watch(
+  ctx: Context<'_>,
+  #[description = "The channel to log edits in"]
+  #[channel_types("Text", "News", "Voice", "Stage")]
+  channel: serenity::GuildChannel,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let added =
+    DatabaseHandler::add_edit_log_channel(&mut transaction, &guild_id, &channel.id).await?;
+
+  if added == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Edits in {} are already logged.",
+            channel.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Edits in {} will now be logged.",
+      channel.mention()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Stop logging edits in a channel
+///
+/// Stops logging message edits in a channel. Edits that have already been logged are kept until they expire.
+#[poise::command(slash_command)]
+pub async fn unwatch(
+  ctx: Context<'_>,
+  #[description = "The channel to stop logging edits in"]
+  #[channel_types("Text", "News", "Voice", "Stage")]
+  channel: serenity::GuildChannel,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed =
+    DatabaseHandler::remove_edit_log_channel(&mut transaction, &guild_id, &channel.id).await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Edits in {} are not being logged.",
+            channel.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Edits in {} will no longer be logged.",
+      channel.mention()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// List channels with edit logging
+///
+/// Lists the channels where message edits are logged.
+#[poise::command(slash_command, rename = "list")]
+pub async fn watched(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let channels = DatabaseHandler::get_edit_log_channels(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let content = if channels.is_empty() {
+    "Message edits are not logged in any channels.".to_string()
+  } else {
+    format!(
+      "Message edits are logged in: {}",
+      channels
+        .iter()
+        .map(|channel_id| channel_id.mention().to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+    )
+  };
+
+  ctx
+    .send(CreateReply::default().content(content).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Manage deleted message logging
+///
+/// Commands to filter which deleted messages are logged, to reduce noise in the logs channel.
+#[poise::command(
+  slash_command,
+  subcommands(
+    "logging_filters",
+    "logging_ignore",
+    "logging_unignore",
+    "logging_settings"
+  ),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn logging(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Set deleted message log filters
+///
+/// Sets whether deleted messages from bots are logged, and the minimum length of deleted messages that are logged. Filters that are not specified are left unchanged.
+#[poise::command(slash_command, rename = "filters")]
+pub async fn logging_filters(
+  ctx: Context<'_>,
+  #[description = "Ignore deleted messages from bots"] ignore_bots: Option<bool>,
+  #[description = "Minimum number of characters for a deleted message to be logged"]
+  #[min = 0]
+  #[max = 4000]
+  min_length: Option<i32>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  if ignore_bots.is_none() && min_length.is_none() {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Please specify at least one filter to change.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::set_delete_log_filters(&mut transaction, &guild_id, ignore_bots, min_length)
+    .await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(
+      ":white_check_mark: Deleted message log filters have been updated.".to_string(),
+    ),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Ignore deleted messages in a channel
+///
+/// Stops logging deleted messages in a channel.
+#[poise::command(slash_command, rename = "ignore")]
+pub async fn logging_ignore(
+  ctx: Context<'_>,
+  #[description = "The channel to ignore"]
+  #[channel_types("Text", "News", "Voice", "Stage")]
+  channel: serenity::GuildChannel,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let added =
+    DatabaseHandler::add_delete_log_ignored_channel(&mut transaction, &guild_id, &channel.id)
+      .await?;
+
+  if added == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Deleted messages in {} are already ignored.",
+            channel.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Deleted messages in {} will no longer be logged.",
+      channel.mention()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Stop ignoring deleted messages in a channel
+///
+/// Resumes logging deleted messages in a channel that was previously ignored.
+#[poise::command(slash_command, rename = "unignore")]
+pub async fn logging_unignore(
+  ctx: Context<'_>,
+  #[description = "The channel to stop ignoring"]
+  #[channel_types("Text", "News", "Voice", "Stage")]
+  channel: serenity::GuildChannel,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed =
+    DatabaseHandler::remove_delete_log_ignored_channel(&mut transaction, &guild_id, &channel.id)
+      .await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Deleted messages in {} are not being ignored.",
+            channel.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Deleted messages in {} will be logged again.",
+      channel.mention()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Show deleted message log filters
+///
+/// Shows the current filters for logging deleted messages.
+#[poise::command(slash_command, rename = "show")]
+pub async fn logging_settings(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let settings = DatabaseHandler::get_delete_log_settings(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let ignored_channels = if settings.ignored_channels.is_empty() {
+    "None".to_string()
+  } else {
+    settings
+      .ignored_channels
+      .iter()
+      .map(|channel_id| channel_id.mention().to_string())
+      .collect::<Vec<String>>()
+      .join(", ")
+  };
+
+  let embed = BloomBotEmbed::new()
+    .title("Deleted Message Log Filters")
+    .field(
+      "Ignore Bots",
+      if settings.ignore_bots { "Yes" } else { "No" },
+      true,
+    )
+    .field(
+      "Minimum Length",
+      format!("{} characters", settings.min_length),
+      true,
+    )
+    .field("Ignored Channels", ignored_channels, false);
+
+  ctx
+    .send(CreateReply::default().embed(embed).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Manage session length limits
+///
+/// Commands to set the minimum and maximum length of a single meditation entry, and the length above which entries require confirmation.
+#[poise::command(
+  slash_command,
+  subcommands("set_limits", "show_limits"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn limits(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Set session length limits
+///
+/// Sets limits on the length of a single meditation entry. Limits that are not specified are left unchanged. Set the minimum or maximum to 0 to remove it.
+///
+/// Entries over the maximum are rejected and flagged in the logs channel. The daily cap limits how many minutes per day count toward leaderboards and challenges, without changing anyone's personal stats.
+#[poise::command(slash_command, rename = "set")]
+pub async fn set_limits(
+  ctx: Context<'_>,
+  #[description = "Minimum minutes per entry (0 for no minimum)"]
+  #[min = 0]
+  min_minutes: Option<i32>,
+  #[description = "Maximum minutes per entry (0 for no maximum)"]
+  #[min = 0]
+  max_minutes: Option<i32>,
+  #[description = "Entries over this many minutes require confirmation"]
+  #[min = 1]
+  confirm_minutes: Option<i32>,
+  #[description = "Most minutes per day counted toward leaderboards and challenges (0 for no cap)"]
+  #[min = 0]
+  #[max = 1440]
+  daily_cap: Option<i32>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let current = DatabaseHandler::get_session_limits(&mut transaction, &guild_id).await?;
+
+  let limits = SessionLimits {
+    min_minutes: match min_minutes {
+      Some(0) => None,
+      Some(min_minutes) => Some(min_minutes),
+      None => current.min_minutes,
+    },
+    max_minutes: match max_minutes {
+      Some(0) => None,
+      Some(max_minutes) => Some(max_minutes),
+      None => current.max_minutes,
+    },
+    confirm_minutes: confirm_minutes.unwrap_or(current.confirm_minutes),
+    daily_cap_minutes: match daily_cap {
+      Some(0) => None,
+      Some(daily_cap) => Some(daily_cap),
+      None => current.daily_cap_minutes,
+    },
+  };
+
+  if let (Some(min_minutes), Some(max_minutes)) = (limits.min_minutes, limits.max_minutes) {
+    if min_minutes > max_minutes {
+      ctx
+        .send(
+          CreateReply::default()
+            .content(":x: The minimum cannot be greater than the maximum.")
+            .ephemeral(true),
+        )
+        .await?;
+      return Ok(());
+    }
+  }
+
+  DatabaseHandler::set_session_limits(&mut transaction, &guild_id, &limits).await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(
+      ":white_check_mark: Session length limits have been updated.".to_string(),
+    ),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Show session length limits
+///
+/// Shows the current limits on the length of a single meditation entry.
+#[poise::command(slash_command, rename = "show")]
+pub async fn show_limits(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let limits = DatabaseHandler::get_session_limits(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let embed = BloomBotEmbed::new()
+    .title("Session Length Limits")
+    .field(
+      "Minimum",
+      limits
+        .min_minutes
+        .map_or("None".to_string(), |minutes| format!("{minutes} minutes")),
+      true,
+    )
+    .field(
+      "Maximum",
+      limits
+        .max_minutes
+        .map_or("None".to_string(), |minutes| format!("{minutes} minutes")),
+      true,
+    )
+    .field(
+      "Confirmation Required Over",
+      format!("{} minutes", limits.confirm_minutes),
+      true,
+    )
+    .field(
+      "Daily Cap for Leaderboards",
+      limits
+        .daily_cap_minutes
+        .map_or("None".to_string(), |minutes| format!("{minutes} minutes")),
+      true,
+    );
+
+  ctx
+    .send(CreateReply::default().embed(embed).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Manage emoji used in bot messages
+///
+/// Commands to replace the emoji used in bot messages with custom emoji, or reset them to the defaults.
+#[poise::command(
+  slash_command,
+  subcommands("set_emoji", "reset_emoji", "list_emojis"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn emojis(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Set an emoji
+///
+/// Replaces the emoji used in bot messages with a custom or unicode emoji. Custom emoji must be usable by the bot.
+#[poise::command(slash_command, rename = "set")]
+pub async fn set_emoji(
+  ctx: Context<'_>,
+  #[description = "The emoji to replace"] kind: EmojiKind,
+  #[description = "The new emoji"]
+  #[max_length = 100]
+  emoji: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let emoji = emoji.trim().to_string();
+  let is_custom = serenity::utils::parse_emoji(&emoji).is_some();
+  let is_unicode = !emoji.is_empty()
+    && emoji.chars().count() <= 10
+    && !emoji
+      .chars()
+      .any(|c| c.is_ascii_alphabetic() || c.is_whitespace());
+
+  if !is_custom && !is_unicode {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Please enter a single custom or unicode emoji.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::set_guild_emoji(&mut transaction, &guild_id, kind.key(), &emoji).await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: The {} emoji is now {emoji}.",
+      kind.name()
+    )),
+    true,
+  )
+  .await?;
+
+  data.emojis.invalidate(&guild_id).await;
+
+  Ok(())
+}
+
+/// Reset an emoji
+///
+/// Resets an emoji used in bot messages to the default.
+#[poise::command(slash_command, rename = "reset")]
+pub async fn reset_emoji(
+  ctx: Context<'_>,
+  #[description = "The emoji to reset"] kind: EmojiKind,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed =
+    DatabaseHandler::remove_guild_emoji(&mut transaction, &guild_id, kind.key()).await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: That emoji is already using the default.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: The {} emoji has been reset to {}.",
+      kind.name(),
+      kind.fallback()
+    )),
+    true,
+  )
+  .await?;
+
+  data.emojis.invalidate(&guild_id).await;
+
+  Ok(())
+}
+
+/// List emoji
+///
+/// Lists the emoji used in bot messages, and whether each is customized.
+#[poise::command(slash_command, rename = "list")]
+pub async fn list_emojis(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let custom = data.emojis.get(&data.db, &guild_id).await?;
+
+  let mut embed = BloomBotEmbed::new().title("Emoji");
+  for kind in [
+    EmojiKind::Info,
+    EmojiKind::Love,
+    EmojiKind::Welcome,
+    EmojiKind::Celebrate,
+  ] {
+    let value = match custom.iter().find(|emoji| emoji.name == kind.key()) {
+      Some(emoji) => format!("{} (default: {})", emoji.emoji, kind.fallback()),
+      None => format!("{} (default)", kind.fallback()),
+    };
+    embed = embed.field(kind.name(), value, true);
+  }
+
+  ctx
+    .send(CreateReply::default().embed(embed).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Set up channels for the server
+///
+/// Creates a channel for tracking meditation and a private channel for logs. Channels that have already been set up are left unchanged.
+#[poise::command(slash_command)]
+pub async fn setup(
+  ctx: Context<'_>,
+  #[description = "Create a channel for tracking meditation (defaults to true)"]
+  tracking_channel: Option<bool>,
+  #[description = "Create a private channel for logs (defaults to true)"] log_channel: Option<bool>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  ctx.defer_ephemeral().await?;
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let mut settings = DatabaseHandler::get_guild_settings(&mut transaction, &guild_id).await?;
+
+  if tracking_channel.unwrap_or(true) && settings.tracking_channel_id.is_none() {
+    let channel = guild_id
+      .create_channel(
+        ctx,
+        CreateChannel::new("meditation-tracking")
+          .kind(serenity::ChannelType::Text)
+          .topic("Use /add to track your meditation time."),
+      )
+      .await?;
+    settings.tracking_channel_id = Some(channel.id);
+  }
+
+  if log_channel.unwrap_or(true) && settings.log_channel_id.is_none() {
+    // Logs may contain deleted messages, so only staff should see them
+    let everyone = serenity::PermissionOverwrite {
+      allow: serenity::Permissions::empty(),
+      deny: serenity::Permissions::VIEW_CHANNEL,
+      kind: serenity::PermissionOverwriteType::Role(serenity::RoleId::new(guild_id.get())),
+    };
+    let channel = guild_id
+      .create_channel(
+        ctx,
+        CreateChannel::new("bloom-logs")
+          .kind(serenity::ChannelType::Text)
+          .permissions(vec![everyone]),
+      )
+      .await?;
+    settings.log_channel_id = Some(channel.id);
+  }
+
+  DatabaseHandler::set_guild_settings(&mut transaction, &guild_id, &settings).await?;
+
+  let describe = |channel_id: Option<serenity::ChannelId>| {
+    channel_id.map_or("Not set up".to_string(), |id| id.mention().to_string())
+  };
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::EmbedOnly(
+      BloomBotEmbed::new()
+        .title("Server Setup")
+        .field(
+          "Tracking Channel",
+          describe(settings.tracking_channel_id),
+          true,
+        )
+        .field("Log Channel", describe(settings.log_channel_id), true),
+    ),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Manage tracking channels
+///
+/// Commands to choose the channels where members track their meditation. Public `/add` confirmations and server hours announcements are posted in the channel the command was used in if it's a tracking channel, and in the default tracking channel otherwise.
+#[poise::command(
+  slash_command,
+  subcommands("tracking_add", "tracking_remove", "tracking_default", "tracking_list"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn tracking(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Add a tracking channel
+///
+/// Adds a channel where `/add` confirmations and server hours announcements stay, such as a separate channel for beginners.
+#[poise::command(slash_command, rename = "add")]
+pub async fn tracking_add(
+  ctx: Context<'_>,
+  #[description = "The channel to add"]
+  #[channel_types("Text")]
+  channel: serenity::GuildChannel,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let added =
+    DatabaseHandler::add_tracking_channel(&mut transaction, &guild_id, &channel.id).await?;
+
+  if added == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: {} is already a tracking channel.",
+            channel.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: {} is now a tracking channel.",
+      channel.mention()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Remove a tracking channel
+///
+/// Removes an additional tracking channel. Confirmations for sessions added there will be posted in the default tracking channel.
+#[poise::command(slash_command, rename = "remove")]
+pub async fn tracking_remove(
+  ctx: Context<'_>,
+  #[description = "The channel to remove"]
+  #[channel_types("Text")]
+  channel: serenity::GuildChannel,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed =
+    DatabaseHandler::remove_tracking_channel(&mut transaction, &guild_id, &channel.id).await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: {} is not an additional tracking channel.",
+            channel.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: {} is no longer a tracking channel.",
+      channel.mention()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Set the default tracking channel
+///
+/// Sets the channel where `/add` confirmations and server hours announcements are posted when the command is used outside a tracking channel.
+#[poise::command(slash_command, rename = "default")]
+pub async fn tracking_default(
+  ctx: Context<'_>,
+  #[description = "The default tracking channel"]
+  #[channel_types("Text")]
+  channel: serenity::GuildChannel,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let mut settings = DatabaseHandler::get_guild_settings(&mut transaction, &guild_id).await?;
+  settings.tracking_channel_id = Some(channel.id);
+  DatabaseHandler::set_guild_settings(&mut transaction, &guild_id, &settings).await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: {} is now the default tracking channel.",
+      channel.mention()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// List tracking channels
+///
+/// Lists the default tracking channel and any additional tracking channels.
+#[poise::command(slash_command, rename = "list")]
+pub async fn tracking_list(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let settings = DatabaseHandler::get_guild_settings(&mut transaction, &guild_id).await?;
+  let channels = DatabaseHandler::get_tracking_channels(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let default_channel = settings.tracking_channel_id.map_or(
+    "None. Confirmations stay in the channel where `/add` is used.".to_string(),
+    |channel_id| channel_id.mention().to_string(),
+  );
+  let additional_channels = if channels.is_empty() {
+    "None".to_string()
+  } else {
+    channels
+      .iter()
+      .map(|channel_id| channel_id.mention().to_string())
+      .collect::<Vec<String>>()
+      .join(", ")
+  };
+
+  ctx
+    .send(
+      CreateReply::default()
+        .content(format!(
+          "**Default tracking channel**: {default_channel}\n**Additional tracking channels**: {additional_channels}"
+        ))
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Manage partner servers
+///
+/// Commands to link partner servers for joint events. Once both servers have linked each other, `/stats leaderboard` can show a combined leaderboard and total.
+#[poise::command(
+  slash_command,
+  subcommands("link_partner", "unlink_partner", "list_partners"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn partners(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+fn parse_guild_id(input: &str) -> Option<serenity::GuildId> {
+  input
+    .trim()
+    .parse::<u64>()
+    .ok()
+    .filter(|id| *id != 0)
+    .map(serenity::GuildId::new)
+}
+
+/// Link a partner server
+///
+/// Consents to sharing combined stats with a partner server. Combined stats are only shown once the partner server has linked back.
+#[poise::command(slash_command, rename = "link")]
+pub async fn link_partner(
+  ctx: Context<'_>,
+  #[description = "The ID of the partner server"] server_id: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let Some(partner_guild_id) = parse_guild_id(&server_id) else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Please enter a valid server ID.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  if partner_guild_id == guild_id || partner_guild_id.name(ctx).is_none() {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Bloom is not in that server.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let added =
+    DatabaseHandler::add_guild_link(&mut transaction, &guild_id, &partner_guild_id).await?;
+
+  if added == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: That server is already linked.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Linked with **{}**. Combined stats will be available once they link back with `/manage partners link {guild_id}`.",
+      partner_guild_id.name(ctx).unwrap_or_default()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Unlink a partner server
+///
+/// Withdraws consent to sharing combined stats with a partner server.
+#[poise::command(slash_command, rename = "unlink")]
+pub async fn unlink_partner(
+  ctx: Context<'_>,
+  #[description = "The ID of the partner server"] server_id: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let Some(partner_guild_id) = parse_guild_id(&server_id) else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Please enter a valid server ID.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed =
+    DatabaseHandler::remove_guild_link(&mut transaction, &guild_id, &partner_guild_id).await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: That server is not linked.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(":white_check_mark: Partner server has been unlinked.".to_string()),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// List partner servers
+///
+/// Lists linked partner servers, and whether each has linked back.
+#[poise::command(slash_command, rename = "list")]
+pub async fn list_partners(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let links = DatabaseHandler::get_guild_links(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  if links.is_empty() {
+    ctx
+      .send(
+        CreateReply::default()
+          .content("No partner servers have been linked.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let mut embed = BloomBotEmbed::new().title("Partner Servers");
+  for link in links.into_iter().take(25) {
+    let name = link
+      .partner_guild_id
+      .name(ctx)
+      .unwrap_or("Unknown server".to_string());
+    let status = if link.mutual {
+      "Linked"
+    } else {
+      "Waiting for them to link back"
+    };
+    embed = embed.field(
+      name,
+      format!("{status}\nID: {}", link.partner_guild_id),
+      false,
+    );
+  }
+
+  ctx
+    .send(CreateReply::default().embed(embed).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Manage milestone webhooks
+///
+/// Commands to register webhooks that receive a JSON payload when the server reaches a milestone: every 10,000 hours meditated, a monthly challenge winner, or a new record streak.
+#[poise::command(
+  slash_command,
+  subcommands("add_webhook", "remove_webhook", "list_webhooks"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn webhooks(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Add a webhook
+///
+/// Registers a URL to receive milestone events as JSON POST requests.
+#[poise::command(slash_command, rename = "add")]
+pub async fn add_webhook(
+  ctx: Context<'_>,
+  #[description = "The URL to send events to (must use HTTPS)"]
+  #[max_length = 512]
+  url: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let url = url.trim();
+  if !url.starts_with("https://") || url.contains(char::is_whitespace) {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Please enter a valid HTTPS URL.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let existing = DatabaseHandler::get_guild_webhooks(&mut transaction, &guild_id).await?;
+
+  if existing.len() >= MAX_WEBHOOKS {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Servers can have at most {MAX_WEBHOOKS} webhooks. Please remove one first."
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let added = DatabaseHandler::add_guild_webhook(&mut transaction, &guild_id, url).await?;
+
+  if added == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: That webhook has already been added.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(":white_check_mark: Webhook has been added.".to_string()),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Remove a webhook
+///
+/// Stops sending milestone events to a webhook. Use `/manage webhooks list` to find its ID.
+#[poise::command(slash_command, rename = "remove")]
+pub async fn remove_webhook(
+  ctx: Context<'_>,
+  #[description = "The ID of the webhook to remove"] id: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed = DatabaseHandler::remove_guild_webhook(&mut transaction, &guild_id, &id).await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Webhook does not exist.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(":white_check_mark: Webhook has been removed.".to_string()),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// List webhooks
+///
+/// Lists the webhooks registered for the server.
+#[poise::command(slash_command, rename = "list")]
+pub async fn list_webhooks(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let webhooks = DatabaseHandler::get_guild_webhooks(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  if webhooks.is_empty() {
+    ctx
+      .send(
+        CreateReply::default()
+          .content("No webhooks have been added.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let mut embed = BloomBotEmbed::new().title("Milestone Webhooks");
+  for webhook in webhooks {
+    embed = embed.field(
+      format!("ID: {}", webhook.id),
+      format!(
+        "{}\nAdded {}",
+        webhook.url,
+        webhook.created_at.format("%B %d, %Y")
+      ),
+      false,
+    );
+  }
+
+  ctx
+    .send(CreateReply::default().embed(embed).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+fn anomaly_embed(flag: &AnomalyFlag, remaining: usize) -> CreateEmbed {
+  let kind = match flag.kind.as_str() {
+    "volume_spike" => "Sudden Volume Increase",
+    "repeated_duration" => "Repeated Identical Entries",
+    kind => kind,
+  };
+
+  BloomBotEmbed::new()
+    .title(format!("Suspicious Tracking Activity: {kind}"))
+    .description(flag.details.clone())
+    .field(
+      "User",
+      format!("<@{}> ({})", flag.user_id, flag.user_id),
+      true,
+    )
+    .field("Entries", flag.entry_ids.len().to_string(), true)
+    .field(
+      "Flagged",
+      flag.flagged_at.format("%B %d, %Y").to_string(),
+      true,
+    )
+    .footer(CreateEmbedFooter::new(format!(
+      "{remaining} pending flag(s)"
+    )))
+}
+
+async fn log_anomaly_review(ctx: Context<'_>, flag: &AnomalyFlag, outcome: &str) -> Result<()> {
+  let log_embed = BloomBotEmbed::new()
+    .title(format!("Tracking Anomaly {outcome}"))
+    .description(format!(
+      "**User**: <@{}> ({})\n**Details**: {}",
+      flag.user_id, flag.user_id, flag.details
+    ))
+    .footer(
+      CreateEmbedFooter::new(format!(
+        "Reviewed by {} ({})",
+        ctx.author().name,
+        ctx.author().id
+      ))
+      .icon_url(ctx.author().avatar_url().unwrap_or_default()),
+    );
+
+  let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+
+  log_channel
+    .send_message(ctx, CreateMessage::new().embed(log_embed))
+    .await?;
+
+  Ok(())
+}
+
+/// Review suspicious tracking activity
+///
+/// Shows tracking activity flagged as statistically unusual one at a time, such as a sudden large increase in time or identical entries added at machine-like intervals.
+///
+/// Mark each flag as fine, remove the flagged entries, or skip it for later.
+#[poise::command(slash_command)]
+pub async fn anomalies(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let flags = DatabaseHandler::get_pending_anomaly_flags(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  if flags.is_empty() {
+    ctx
+      .send(
+        CreateReply::default()
+          .content("There is no flagged tracking activity to review.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let ctx_id = ctx.id();
+  let dismiss_id = format!("{ctx_id}dismiss");
+  let remove_id = format!("{ctx_id}remove");
+  let skip_id = format!("{ctx_id}skip");
+
+  let review_buttons = vec![CreateActionRow::Buttons(vec![
+    CreateButton::new(dismiss_id.clone())
+      .label("Looks fine")
+      .style(serenity::ButtonStyle::Success),
+    CreateButton::new(remove_id.clone())
+      .label("Remove entries")
+      .style(serenity::ButtonStyle::Danger),
+    CreateButton::new(skip_id.clone())
+      .label("Skip")
+      .style(serenity::ButtonStyle::Secondary),
+  ])];
+
+  let mut current = 0;
+
+  let reply = ctx
+    .send(
+      CreateReply::default()
+        .embed(anomaly_embed(&flags[current], flags.len()))
+        .components(review_buttons.clone())
+        .ephemeral(true),
+    )
+    .await?;
+
+  // Loop through incoming interactions with the review buttons
+  while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+    // We defined our button IDs to start with `ctx_id`. If they don't, some other command's
+    // button was pressed
+    .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+    // Timeout when no button has been pressed for 10 minutes
+    .timeout(std::time::Duration::from_secs(600))
+    .await
+  {
+    let flag = &flags[current];
+
+    let status = if press.data.custom_id == dismiss_id {
+      press
+        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+        .await?;
+
+      let mut transaction = data.db.start_transaction_with_retry(5).await?;
+      DatabaseHandler::review_anomaly_flag(
+        &mut transaction,
+        &flag.id,
+        "dismissed",
+        &ctx.author().id,
+      )
+      .await?;
+      DatabaseHandler::commit_transaction(transaction).await?;
+
+      log_anomaly_review(ctx, flag, "Dismissed").await?;
+      format!(
+        ":white_check_mark: Activity for <@{}> has been marked as fine.",
+        flag.user_id
+      )
+    } else if press.data.custom_id == remove_id {
+      press
+        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+        .await?;
+
+      let mut transaction = data.db.start_transaction_with_retry(5).await?;
+      let removed = DatabaseHandler::delete_meditation_entries(
+        &mut transaction,
+        &guild_id,
+        &flag.user_id,
+        &flag.entry_ids,
+        &ctx.author().id,
+        "anomaly",
+      )
+      .await?;
+      DatabaseHandler::review_anomaly_flag(&mut transaction, &flag.id, "removed", &ctx.author().id)
+        .await?;
+      DatabaseHandler::commit_transaction(transaction).await?;
+
+      log_anomaly_review(ctx, flag, "Entries Removed").await?;
+      format!(
+        ":white_check_mark: Removed {removed} flagged entries for <@{}>.",
+        flag.user_id
+      )
+    } else if press.data.custom_id == skip_id {
+      press
+        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+        .await?;
+
+      format!("Skipped flag for <@{}>.", flag.user_id)
+    } else {
+      // This is an unrelated button interaction
+      continue;
+    };
+
+    current += 1;
+
+    if current >= flags.len() {
+      reply
+        .edit(
+          ctx,
+          CreateReply::default()
+            .content(format!(
+              "{status}\nThere is no more flagged tracking activity to review."
+            ))
+            .components(Vec::new()),
+        )
+        .await?;
+      return Ok(());
+    }
+
+    reply
+      .edit(
+        ctx,
+        CreateReply::default()
+          .content(status)
+          .embed(anomaly_embed(&flags[current], flags.len() - current))
+          .components(review_buttons.clone()),
+      )
+      .await?;
+  }
+
+  // This happens when the user didn't press any button for 10 minutes
+  reply
+    .edit(
+      ctx,
+      CreateReply::default()
+        .embed(anomaly_embed(&flags[current], flags.len() - current))
+        .components(Vec::new()),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Adjust a user's meditation entries in bulk
+///
+/// Commands to fix a user's meditation entries in bulk, such as entries imported with the wrong time zone or imported more than once. Shows how many entries will change and asks for confirmation first.
+#[poise::command(
+  slash_command,
+  subcommands("shift_entries", "dedupe_entries"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn adjust(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Shift all of a user's entries by a time offset
+///
+/// Moves all of a user's meditation entries forward or backward in time, such as when they were imported with the wrong time zone.
+#[poise::command(slash_command, rename = "shift")]
+pub async fn shift_entries(
+  ctx: Context<'_>,
+  #[description = "The user whose entries to shift"] user: serenity::User,
+  #[description = "Hours to shift the entries by (negative to move them earlier)"]
+  #[min = -24]
+  #[max = 24]
+  hours: i32,
+  #[description = "Additional minutes to shift the entries by (Defaults to 0)"]
+  #[min = -59]
+  #[max = 59]
+  minutes: Option<i32>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let offset = hours * 60 + minutes.unwrap_or(0);
+  if offset == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Please enter a non-zero offset.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let affected =
+    DatabaseHandler::shift_user_meditation_entries(&mut transaction, &guild_id, &user.id, offset)
+      .await?;
+
+  if affected == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(":x: {} has no meditation entries.", user.mention()))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let offset_text = format!(
+    "{}{}h {:02}m",
+    if offset < 0 { "-" } else { "+" },
+    offset.abs() / 60,
+    offset.abs() % 60
+  );
+
+  confirm_adjustment(
+    ctx,
+    transaction,
+    format!(
+      "This will shift **{affected}** meditation entries for {} by **{offset_text}**. Are you sure?",
+      user.mention()
+    ),
+    BloomBotEmbed::new()
+      .title("Meditation Entries Shifted")
+      .description(format!(
+        "**User**: <@{}>\n**Offset**: {offset_text}\n**Entries**: {affected}",
+        user.id
+      )),
+  )
+  .await
+}
+
+/// Remove a user's duplicate entries
+///
+/// Removes meditation entries with the same time and duration as another of the user's entries, keeping one of each. Useful when a user has imported the same data more than once. Removed entries are moved to the trash, so they can be restored.
+#[poise::command(slash_command, rename = "dedupe")]
+pub async fn dedupe_entries(
+  ctx: Context<'_>,
+  #[description = "The user whose duplicate entries to remove"] user: serenity::User,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let batch_id = Ulid::new().to_string();
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let affected = DatabaseHandler::remove_duplicate_meditation_entries(
+    &mut transaction,
+    &guild_id,
+    &user.id,
+    &batch_id,
+    &ctx.author().id,
+    "dedupe",
+  )
+  .await?;
+
+  if affected == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: {} has no duplicate meditation entries.",
+            user.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  confirm_adjustment(
+    ctx,
+    transaction,
+    format!(
+      "This will remove **{affected}** duplicate meditation entries for {}. They can be restored within {TRASH_RETENTION_DAYS} days with `/manage trash restore {batch_id}`. Are you sure?",
+      user.mention()
+    ),
+    BloomBotEmbed::new()
+      .title("Duplicate Meditation Entries Removed")
+      .description(format!(
+        "**User**: <@{}>\n**Entries**: {affected}\n**Trash batch**: `{batch_id}`",
+        user.id
+      )),
+  )
+  .await
+}
+
+/// Asks for confirmation of a bulk adjustment that has already been applied in the transaction.
+///
+/// Commits and posts the log embed if confirmed. Otherwise the transaction is rolled back.
+async fn confirm_adjustment(
+  ctx: Context<'_>,
+  transaction: sqlx::Transaction<'_, sqlx::Postgres>,
+  prompt: String,
+  log_embed: serenity::CreateEmbed,
+) -> Result<()> {
+  let ctx_id = ctx.id();
+
+  let confirm_id = format!("{ctx_id}confirm");
+  let cancel_id = format!("{ctx_id}cancel");
+
+  ctx
+    .send(
+      CreateReply::default()
+        .content(prompt)
+        .ephemeral(true)
+        .components(vec![CreateActionRow::Buttons(vec![
+          CreateButton::new(confirm_id.clone())
+            .label("Yes")
+            .style(serenity::ButtonStyle::Success),
+          CreateButton::new(cancel_id.clone())
+            .label("No")
+            .style(serenity::ButtonStyle::Danger),
+        ])]),
+    )
+    .await?;
+
+  let press = serenity::ComponentInteractionCollector::new(ctx)
+    .filter(move |press| press.data.custom_id == confirm_id || press.data.custom_id == cancel_id)
+    .timeout(std::time::Duration::from_secs(60))
+    .await;
+
+  // This happens when the user didn't press any button for 60 seconds
+  let Some(press) = press else {
+    DatabaseHandler::rollback_transaction(transaction).await?;
+    return Ok(());
+  };
+
+  if !press.data.custom_id.ends_with("confirm") {
+    DatabaseHandler::rollback_transaction(transaction).await?;
+    press
+      .create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+          CreateInteractionResponseMessage::new()
+            .content("Cancelled.")
+            .components(Vec::new()),
+        ),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  press
+    .create_response(
+      ctx,
+      CreateInteractionResponse::UpdateMessage(
+        CreateInteractionResponseMessage::new()
+          .content(":white_check_mark: Entries have been adjusted.")
+          .components(Vec::new()),
+      ),
+    )
+    .await?;
+
+  let log_embed = log_embed.footer(
+    CreateEmbedFooter::new(format!(
+      "Adjusted by {} ({})",
+      ctx.author().name,
+      ctx.author().id
+    ))
+    .icon_url(ctx.author().avatar_url().unwrap_or_default()),
+  );
+
+  let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+
+  log_channel
+    .send_message(ctx, CreateMessage::new().embed(log_embed))
+    .await?;
+
+  Ok(())
+}
+
+/// Manage data retention policies
+///
+/// Commands to choose how long logged data is kept before a nightly job purges it. Policies start disabled, so the number of records affected can be reviewed before anything is deleted. Data without a policy is kept indefinitely.
+#[poise::command(
+  slash_command,
+  subcommands(
+    "set_retention",
+    "enable_retention",
+    "disable_retention",
+    "review_retention"
+  ),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn retention(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Set how long a category of data is kept
+///
+/// Sets how many days a category of logged data is kept. The policy is disabled until enabled with `/manage retention enable`, including when changing an existing policy.
+#[poise::command(slash_command, rename = "set")]
+pub async fn set_retention(
+  ctx: Context<'_>,
+  #[description = "The category of data"] category: RetentionCategory,
+  #[description = "The number of days to keep records for"]
+  #[min = 7]
+  #[max = 3650]
+  days: i32,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let cutoff = chrono::Utc::now() - chrono::Duration::days(i64::from(days));
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::set_retention_policy(&mut transaction, &guild_id, category, days).await?;
+  let expired =
+    DatabaseHandler::count_expired_records(&mut transaction, &guild_id, category, cutoff).await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: {} will be kept for {days} days once enabled. {expired} records are currently older than that. Use `/manage retention enable` to start purging them.",
+      category.name()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Enable a retention policy
+///
+/// Enables the retention policy for a category, so expired records are purged every night.
+#[poise::command(slash_command, rename = "enable")]
+pub async fn enable_retention(
+  ctx: Context<'_>,
+  #[description = "The category of data"] category: RetentionCategory,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let updated =
+    DatabaseHandler::set_retention_policy_enabled(&mut transaction, &guild_id, category, true)
+      .await?;
+
+  if updated == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: No retention period has been set for {}. Please use `/manage retention set` first.",
+            category.name()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Retention policy for {} has been enabled. Expired records will be purged tonight.",
+      category.name()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Disable a retention policy
+///
+/// Disables the retention policy for a category, so its records are kept indefinitely.
+#[poise::command(slash_command, rename = "disable")]
+pub async fn disable_retention(
+  ctx: Context<'_>,
+  #[description = "The category of data"] category: RetentionCategory,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let updated =
+    DatabaseHandler::set_retention_policy_enabled(&mut transaction, &guild_id, category, false)
+      .await?;
+
+  if updated == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: No retention period has been set for {}.",
+            category.name()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Retention policy for {} has been disabled.",
+      category.name()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Review retention policies
+///
+/// Shows the retention policy for each category of data, with a dry run of how many records would be purged tonight.
+#[poise::command(slash_command, rename = "review")]
+pub async fn review_retention(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let now = chrono::Utc::now();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let policies = DatabaseHandler::get_retention_policies(&mut transaction, &guild_id).await?;
+
+  let mut embed = BloomBotEmbed::new().title("Data Retention");
+  for category in RetentionCategory::ALL {
+    let policy = policies
+      .iter()
+      .find(|policy| policy.category.key() == category.key());
+
+    let value = match policy {
+      Some(policy) => {
+        let cutoff = now - chrono::Duration::days(i64::from(policy.retention_days));
+        let expired =
+          DatabaseHandler::count_expired_records(&mut transaction, &guild_id, category, cutoff)
+            .await?;
+        format!(
+          "Kept for {} days ({})\n{expired} records {} purged tonight",
+          policy.retention_days,
+          if policy.enabled {
+            "enabled"
+          } else {
+            "disabled"
+          },
+          if policy.enabled {
+            "will be"
+          } else {
+            "would be"
+          }
+        )
+      }
+      None => "Kept indefinitely".to_string(),
+    };
+
+    embed = embed.field(category.name(), value, false);
+  }
+  drop(transaction);
+
+  ctx
+    .send(CreateReply::default().embed(embed).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Set the prefix for text commands
+///
+/// Sets a prefix so members can use `add`, `streak`, and `stats` as text commands (e.g. `!add 20`) in the server's tracking channels. Text commands can't reply privately, so members with private tracking are asked to use the slash commands instead. Leave the prefix empty to turn text commands off.
+#[poise::command(slash_command)]
+pub async fn prefix(
+  ctx: Context<'_>,
+  #[description = "The prefix to use (Leave empty to turn text commands off)"]
+  #[max_length = 5]
+  prefix: Option<String>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let prefix = prefix.map(|prefix| prefix.trim().to_string());
+  if prefix
+    .as_ref()
+    .is_some_and(|prefix| prefix.is_empty() || prefix.contains(char::is_whitespace))
+  {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Prefixes cannot contain spaces.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::set_command_prefix(&mut transaction, &guild_id, prefix.as_deref()).await?;
+
+  let message = match &prefix {
+    Some(prefix) => format!(
+      ":white_check_mark: Text commands are now enabled in tracking channels with the prefix `{prefix}`. Try `{prefix}add 20`."
+    ),
+    None => ":white_check_mark: Text commands have been turned off.".to_string(),
+  };
+
+  commit_and_say(ctx, transaction, MessageType::TextOnly(message), true).await?;
+
+  data.prefixes.invalidate(&guild_id).await?;
+
+  Ok(())
+}
+
+/// Manage voice channel tracking
+///
+/// Commands to choose voice channels where time spent is automatically added as meditation time.
+#[poise::command(
+  slash_command,
+  subcommands("voice_track", "voice_untrack", "voice_tracked"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn voice(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Track time in a voice channel
+///
+/// Starts adding time spent in a voice channel as meditation time. Visits shorter than a few minutes are not added. Time in a stage channel is recorded as talk attendance instead, shown separately in `/stats user`.
+///
+/// Optionally require several members in the channel for time to count, to encourage group sits. Only the time spent together is added. Using this on a tracked channel updates the number of members required.
+#[poise::command(slash_command, rename = "track")]
+pub async fn voice_track(
+  ctx: Context<'_>,
+  #[description = "The voice channel to track"]
+  #[channel_types("Voice", "Stage")]
+  channel: serenity::GuildChannel,
+  #[description = "Members needed in the channel for time to count (Defaults to 1)"]
+  #[min = 1]
+  #[max = 25]
+  min_participants: Option<i16>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+  let min_participants = min_participants.unwrap_or(1);
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let existing = DatabaseHandler::get_tracking_voice_channels(&mut transaction, &guild_id)
+    .await?
+    .into_iter()
+    .find(|tracked| tracked.channel_id == channel.id);
+
+  if existing.is_some_and(|existing| existing.min_participants == min_participants) {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Time in {} is already tracked.",
+            channel.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  DatabaseHandler::add_tracking_voice_channel(
+    &mut transaction,
+    &guild_id,
+    &channel.id,
+    min_participants,
+  )
+  .await?;
+
+  let requirement = if min_participants > 1 {
+    format!(" Time counts while at least **{min_participants}** members are in the channel.")
+  } else {
+    String::new()
+  };
+  let message = if existing.is_some() {
+    format!(
+      ":white_check_mark: Updated tracking for {}.{requirement} The new requirement applies from the next time someone joins or leaves the channel.",
+      channel.mention()
+    )
+  } else {
+    format!(
+      ":white_check_mark: Time in {} will now be tracked.{requirement} Members already in the channel will be tracked from the next time they join.",
+      channel.mention()
+    )
+  };
+
+  commit_and_say(ctx, transaction, MessageType::TextOnly(message), true).await?;
+
+  Ok(())
+}
+
+/// Stop tracking time in a voice channel
+///
+/// Stops adding time spent in a voice channel as meditation time. Members in the channel are still credited for their time so far when they leave.
+#[poise::command(slash_command, rename = "untrack")]
+pub async fn voice_untrack(
+  ctx: Context<'_>,
+  #[description = "The voice channel to stop tracking"]
+  #[channel_types("Voice", "Stage")]
+  channel: serenity::GuildChannel,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed =
+    DatabaseHandler::remove_tracking_voice_channel(&mut transaction, &guild_id, &channel.id)
+      .await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(":x: Time in {} is not tracked.", channel.mention()))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Time in {} will no longer be tracked.",
+      channel.mention()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// List tracked voice channels
+///
+/// Lists the voice channels where time is added as meditation time.
+#[poise::command(slash_command, rename = "list")]
+pub async fn voice_tracked(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let channels = DatabaseHandler::get_tracking_voice_channels(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let content = if channels.is_empty() {
+    "Time is not tracked in any voice channels.".to_string()
+  } else {
+    format!(
+      "Time is tracked in: {}",
+      channels
+        .iter()
+        .map(|tracked| {
+          if tracked.min_participants > 1 {
+            format!(
+              "{} (with {}+ members)",
+              tracked.channel_id.mention(),
+              tracked.min_participants
+            )
+          } else {
+            tracked.channel_id.mention().to_string()
+          }
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+    )
+  };
+
+  ctx
+    .send(CreateReply::default().content(content).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Manage the starboard forum
+///
+/// Commands to post starred messages as threads in a forum channel instead of the starboard channel, to choose the forum tags applied to messages starred in each channel, and to choose what happens to starboard posts when the starred message is deleted.
+#[poise::command(
+  slash_command,
+  subcommands(
+    "starboard_forum",
+    "starboard_tag",
+    "starboard_untag",
+    "starboard_deleted",
+    "starboard_settings"
+  ),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn starboard(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Gets the forum channel that starred messages are posted in, if one is set and still exists.
+async fn get_starboard_forum(
+  ctx: Context<'_>,
+  guild_id: serenity::GuildId,
+) -> Result<Option<serenity::GuildChannel>> {
+  let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+  let forum_id = DatabaseHandler::get_starboard_forum(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let Some(forum_id) = forum_id else {
+    return Ok(None);
+  };
+
+  Ok(
+    forum_id
+      .to_channel(ctx)
+      .await
+      .ok()
+      .and_then(serenity::Channel::guild),
+  )
+}
+
+async fn autocomplete_forum_tag(
+  ctx: Context<'_>,
+  partial: &str,
+) -> Vec<serenity::AutocompleteChoice> {
+  let Some(guild_id) = ctx.guild_id() else {
+    return Vec::new();
+  };
+
+  let Ok(Some(forum)) = get_starboard_forum(ctx, guild_id).await else {
+    return Vec::new();
+  };
+
+  let partial = partial.to_lowercase();
+
+  forum
+    .available_tags
+    .into_iter()
+    .filter(|tag| tag.name.to_lowercase().contains(&partial))
+    .take(25)
+    .map(|tag| serenity::AutocompleteChoice::new(tag.name, tag.id.to_string()))
+    .collect()
+}
+
+/// Finds a forum tag chosen from autocomplete, or typed by name.
+fn find_forum_tag<'a>(
+  forum: &'a serenity::GuildChannel,
+  tag: &str,
+) -> Option<&'a serenity::ForumTag> {
+  forum
+    .available_tags
+    .iter()
+    .find(|available| available.id.to_string() == tag || available.name.eq_ignore_ascii_case(tag))
+}
+
+/// Post starred messages in a forum
+///
+/// Posts newly starred messages as threads in a forum channel, one thread per message, instead of in the starboard channel. Star counts are kept up to date in the first message of each thread.
+///
+/// Leave the forum empty to go back to the starboard channel. Choosing a different forum clears the tags set for the previous one.
+#[poise::command(slash_command, rename = "forum")]
+pub async fn starboard_forum(
+  ctx: Context<'_>,
+  #[description = "The forum to post starred messages in (Defaults to the starboard channel)"]
+  #[channel_types("Forum")]
+  forum: Option<serenity::GuildChannel>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let existing = DatabaseHandler::get_starboard_forum(&mut transaction, &guild_id).await?;
+
+  let Some(forum) = forum else {
+    if DatabaseHandler::remove_starboard_forum(&mut transaction, &guild_id).await? == 0 {
+      ctx
+        .send(
+          CreateReply::default()
+            .content(":x: Starred messages are already posted in the starboard channel.")
+            .ephemeral(true),
+        )
+        .await?;
+      return Ok(());
+    }
+
+    commit_and_say(
+      ctx,
+      transaction,
+      MessageType::TextOnly(format!(
+        ":white_check_mark: Starred messages will be posted in <#{}> again. Messages already in the forum are still updated there.",
+        CHANNELS.starchannel
+      )),
+      true,
+    )
+    .await?;
+    return Ok(());
+  };
+
+  if existing == Some(forum.id) {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Starred messages are already posted in {}.",
+            forum.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  // Tags belong to a single forum, so they don't carry over
+  DatabaseHandler::remove_starboard_forum(&mut transaction, &guild_id).await?;
+  DatabaseHandler::set_starboard_forum(&mut transaction, &guild_id, &forum.id).await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Starred messages will be posted in {}. Use `/manage starboard tag` to tag them by the channel they were starred in.",
+      forum.mention()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Tag starred messages from a channel
+///
+/// Applies a forum tag to threads for messages starred in a channel. Messages starred in a thread also get the tags set for the thread's channel. A thread can have up to 5 tags.
+#[poise::command(slash_command, rename = "tag")]
+pub async fn starboard_tag(
+  ctx: Context<'_>,
+  #[description = "The channel messages are starred in"]
+  #[channel_types("Text", "News", "Forum", "Voice", "Stage")]
+  channel: serenity::GuildChannel,
+  #[description = "The forum tag to apply"]
+  #[autocomplete = "autocomplete_forum_tag"]
+  tag: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let Some(forum) = get_starboard_forum(ctx, guild_id).await? else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Starred messages are not posted in a forum. Use `/manage starboard forum` to choose one first.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let Some(tag) = find_forum_tag(&forum, &tag) else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Tag not found in {}. Please choose a tag from the list.",
+            forum.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let added =
+    DatabaseHandler::add_starboard_forum_tag(&mut transaction, &guild_id, &channel.id, &tag.id)
+      .await?;
+
+  if added == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Messages starred in {} are already tagged **{}**.",
+            channel.mention(),
+            tag.name
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Messages starred in {} will be tagged **{}**.",
+      channel.mention(),
+      tag.name
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Stop tagging starred messages from a channel
+///
+/// Stops applying a forum tag to threads for messages starred in a channel. Leave the tag empty to remove all of the channel's tags. Threads that were already posted keep their tags.
+#[poise::command(slash_command, rename = "untag")]
+pub async fn starboard_untag(
+  ctx: Context<'_>,
+  #[description = "The channel messages are starred in"]
+  #[channel_types("Text", "News", "Forum", "Voice", "Stage")]
+  channel: serenity::GuildChannel,
+  #[description = "The forum tag to stop applying (Defaults to all)"]
+  #[autocomplete = "autocomplete_forum_tag"]
+  tag: Option<String>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  // Tags that were removed from the forum can still be untagged by ID
+  let tag_id = match &tag {
+    Some(tag) => {
+      let forum = get_starboard_forum(ctx, guild_id).await?;
+      let tag_id = forum
+        .as_ref()
+        .and_then(|forum| find_forum_tag(forum, tag))
+        .map(|tag| tag.id)
+        .or_else(|| {
+          tag
+            .parse::<u64>()
+            .ok()
+            .filter(|id| *id != 0)
+            .map(serenity::ForumTagId::new)
+        });
+
+      let Some(tag_id) = tag_id else {
+        ctx
+          .send(
+            CreateReply::default()
+              .content(":x: Tag not found. Please choose a tag from the list.")
+              .ephemeral(true),
+          )
+          .await?;
+        return Ok(());
+      };
+
+      Some(tag_id)
+    }
+    None => None,
+  };
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed = DatabaseHandler::remove_starboard_forum_tags(
+    &mut transaction,
+    &guild_id,
+    &channel.id,
+    tag_id.as_ref(),
+  )
+  .await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Messages starred in {} don't have that tag.",
+            channel.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Removed {removed} tag(s) from messages starred in {}.",
+      channel.mention()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Choose what happens when a starred message is deleted
+///
+/// Sets whether the starboard post for a deleted message is removed, marked as deleted, or kept as it is. Defaults to removing the post.
+///
+/// Deletions the bot missed, such as while it was offline, are found within a few days and handled the same way.
+#[poise::command(slash_command, rename = "deleted")]
+pub async fn starboard_deleted(
+  ctx: Context<'_>,
+  #[description = "What happens to the starboard post"] action: StarboardCleanup,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::set_starboard_deleted_action(&mut transaction, &guild_id, action.key()).await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: When a starred message is deleted, Bloom will **{}**.",
+      action.name()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Show the starboard settings
+///
+/// Shows where starred messages are posted, the forum tags applied for each channel, and what happens when a starred message is deleted.
+#[poise::command(slash_command, rename = "show")]
+pub async fn starboard_settings(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let forum_id = DatabaseHandler::get_starboard_forum(&mut transaction, &guild_id).await?;
+  let tags = DatabaseHandler::get_starboard_forum_tags(&mut transaction, &guild_id).await?;
+  let cleanup = starboard::cleanup_for(&mut transaction, Some(&guild_id)).await?;
+  drop(transaction);
+
+  let mut embed = BloomBotEmbed::new()
+    .title("Starboard Settings")
+    .field(
+      "Posted In",
+      forum_id.map_or(format!("<#{}>", CHANNELS.starchannel), |forum_id| {
+        forum_id.mention().to_string()
+      }),
+      false,
+    )
+    .field("When Starred Messages Are Deleted", cleanup.name(), false);
+
+  if forum_id.is_some() {
+    let forum = get_starboard_forum(ctx, guild_id).await?;
+
+    let mut tags_by_channel: BTreeMap<serenity::ChannelId, Vec<String>> = BTreeMap::new();
+    for tag in tags {
+      let name = forum
+        .as_ref()
+        .and_then(|forum| {
+          forum
+            .available_tags
+            .iter()
+            .find(|available| available.id == tag.tag_id)
+        })
+        .map_or("Removed tag".to_string(), |available| {
+          available.name.clone()
+        });
+
+      tags_by_channel
+        .entry(tag.source_channel_id)
+        .or_default()
+        .push(name);
+    }
+
+    let tags = if tags_by_channel.is_empty() {
+      "None".to_string()
+    } else {
+      tags_by_channel
+        .iter()
+        .map(|(channel_id, names)| format!("{}: {}", channel_id.mention(), names.join(", ")))
+        .collect::<Vec<String>>()
+        .join("\n")
+    };
+
+    embed = embed.field("Forum Tags", tags, false);
+  }
+
+  ctx
+    .send(CreateReply::default().embed(embed).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Most announcements a server can have scheduled at once.
+const MAX_ANNOUNCEMENTS: usize = 25;
+
+#[derive(Debug, Modal)]
+#[name = "Schedule an announcement"]
+struct AnnouncementModal {
+  #[name = "Title"]
+  #[placeholder = "Only shown when posted as an embed"]
+  #[max_length = 256]
+  title: Option<String>,
+  #[name = "Message"]
+  #[paragraph]
+  #[max_length = 2000]
+  content: String,
+}
+
+/// Manage scheduled announcements
+///
+/// Commands to schedule messages to be posted in a channel later, either once or repeatedly, and to list or cancel scheduled announcements.
+#[poise::command(
+  slash_command,
+  subcommands("announce_schedule", "announce_list", "announce_cancel"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn announce(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+async fn autocomplete_announcement(
+  ctx: Context<'_>,
+  partial: &str,
+) -> Vec<serenity::AutocompleteChoice> {
+  let Some(guild_id) = ctx.guild_id() else {
+    return Vec::new();
+  };
+
+  let Ok(mut transaction) = ctx.data().db.start_transaction_with_retry(5).await else {
+    return Vec::new();
+  };
+  let Ok(announcements) =
+    DatabaseHandler::get_announcements(&mut transaction, Some(&guild_id), None).await
+  else {
+    return Vec::new();
+  };
+  drop(transaction);
+
+  let partial = partial.to_lowercase();
+
+  announcements
+    .into_iter()
+    .filter(|announcement| announcement.content.to_lowercase().contains(&partial))
+    .take(25)
+    .map(|announcement| {
+      let name = format!(
+        "{} UTC: {}",
+        announcement.next_run_at.format("%Y-%m-%d %H:%M"),
+        announcement.content.replace('\n', " ")
+      );
+      serenity::AutocompleteChoice::new(name.chars().take(100).collect::<String>(), announcement.id)
+    })
+    .collect()
+}
+
+/// Schedule an announcement
+///
+/// Schedules a message to be posted in a channel. Choose a time to post it once, a repeat schedule to post it regularly, or both to start repeating from a set time. The message is entered in a form after running the command.
+///
+/// Times are in UTC. Repeat schedules use the standard cron format of minute, hour, day of month, month, and day of week. For example, `0 9 * * MON` posts every Monday at 09:00 UTC.
+#[poise::command(slash_command, rename = "schedule")]
+pub async fn announce_schedule(
+  ctx: poise::ApplicationContext<'_, AppData, AppError>,
+  #[description = "The channel to post in"]
+  #[channel_types("Text", "News")]
+  channel: serenity::GuildChannel,
+  #[description = "When to post, as YYYY-MM-DD HH:MM in UTC (Defaults to the repeat schedule)"]
+  time: Option<String>,
+  #[description = "Repeat on a cron schedule in UTC, such as \"0 9 * * MON\" (Defaults to posting once)"]
+  repeat: Option<String>,
+  #[description = "Post as an embed (Defaults to false)"] embed: Option<bool>,
+) -> Result<()> {
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+  let now = chrono::Utc::now();
+
+  let schedule = match &repeat {
+    Some(repeat) => {
+      let Some(schedule) = CronSchedule::parse(repeat) else {
+        ctx
+          .send(
+            CreateReply::default()
+              .content(":x: Invalid repeat schedule. Please use the cron format of minute, hour, day of month, month, and day of week, such as `0 9 * * MON`.")
+              .ephemeral(true),
+          )
+          .await?;
+        return Ok(());
+      };
+      Some(schedule)
+    }
+    None => None,
+  };
+
+  let next_run_at = match (&time, schedule) {
+    (Some(time), _) => {
+      let Some(time) = chrono::NaiveDateTime::parse_from_str(time.trim(), "%Y-%m-%d %H:%M")
+        .ok()
+        .map(|time| time.and_utc())
+        .filter(|time| *time > now)
+      else {
+        ctx
+          .send(
+            CreateReply::default()
+              .content(":x: Please enter a time in the future, as `YYYY-MM-DD HH:MM` in UTC.")
+              .ephemeral(true),
+          )
+          .await?;
+        return Ok(());
+      };
+      time
+    }
+    (None, Some(schedule)) => {
+      let Some(time) = schedule.next_after(now) else {
+        ctx
+          .send(
+            CreateReply::default()
+              .content(":x: That repeat schedule never matches a real date.")
+              .ephemeral(true),
+          )
+          .await?;
+        return Ok(());
+      };
+      time
+    }
+    (None, None) => {
+      ctx
+        .send(
+          CreateReply::default()
+            .content(
+              ":x: Please choose a time to post the announcement, a repeat schedule, or both.",
+            )
+            .ephemeral(true),
+        )
+        .await?;
+      return Ok(());
+    }
+  };
+
+  let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+  let existing =
+    DatabaseHandler::get_announcements(&mut transaction, Some(&guild_id), None).await?;
+  drop(transaction);
+
+  if existing.len() >= MAX_ANNOUNCEMENTS {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Servers can have at most {MAX_ANNOUNCEMENTS} scheduled announcements. Please cancel one first."
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let Some(announcement) = AnnouncementModal::execute(ctx).await? else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: No data was provided.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::add_announcement(
+    &mut transaction,
+    &guild_id,
+    &channel.id,
+    &ctx.author().id,
+    announcement.title.as_deref(),
+    &announcement.content,
+    embed.unwrap_or(false),
+    repeat.as_deref().map(str::trim),
+    next_run_at,
+  )
+  .await?;
+
+  let repeat_text = match &repeat {
+    Some(repeat) => format!(", then on the schedule `{}`", repeat.trim()),
+    None => String::new(),
+  };
+
+  commit_and_say(
+    poise::Context::Application(ctx),
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: The announcement will be posted in {} on <t:{}:F>{repeat_text}.",
+      channel.mention(),
+      next_run_at.timestamp()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// List scheduled announcements
+///
+/// Lists the announcements scheduled in this server, in the order they will be posted.
+#[poise::command(slash_command, rename = "list")]
+pub async fn announce_list(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let announcements =
+    DatabaseHandler::get_announcements(&mut transaction, Some(&guild_id), None).await?;
+  drop(transaction);
+
+  let content = if announcements.is_empty() {
+    "No announcements are scheduled.".to_string()
+  } else {
+    announcements
+      .iter()
+      .map(|announcement| {
+        let repeat = match &announcement.repeat_schedule {
+          Some(repeat) => format!(" (repeats `{repeat}`)"),
+          None => String::new(),
+        };
+        let snippet = announcement.content.replace('\n', " ");
+        let snippet = if snippet.chars().count() > 80 {
+          format!("{}...", snippet.chars().take(77).collect::<String>())
+        } else {
+          snippet
+        };
+
+        format!(
+          "- <t:{}:f> in {}{repeat}: {snippet}",
+          announcement.next_run_at.timestamp(),
+          announcement.channel_id.mention()
+        )
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  };
+
+  ctx
+    .send(CreateReply::default().content(content).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Cancel a scheduled announcement
+///
+/// Cancels a scheduled announcement, including all future posts of a repeating announcement.
+#[poise::command(slash_command, rename = "cancel")]
+pub async fn announce_cancel(
+  ctx: Context<'_>,
+  #[description = "The announcement to cancel"]
+  #[autocomplete = "autocomplete_announcement"]
+  announcement: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed =
+    DatabaseHandler::remove_announcement(&mut transaction, &guild_id, &announcement).await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Announcement not found. Please choose an announcement from the list.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(":white_check_mark: The announcement has been cancelled.".to_string()),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Most message templates a server can have.
+const MAX_TEMPLATES: usize = 25;
+
+#[derive(Debug, Modal)]
+#[name = "Message template"]
+struct TemplateModal {
+  #[name = "Title"]
+  #[placeholder = "Only shown when posted as an embed"]
+  #[max_length = 256]
+  title: Option<String>,
+  #[name = "Message"]
+  #[placeholder = "Use {variables} such as {date}, or your own such as {theme}"]
+  #[paragraph]
+  #[max_length = 2000]
+  content: String,
+}
+
+/// Manage message templates
+///
+/// Commands to store reusable messages for recurring posts, such as weekly check-ins or challenge kick-offs, and post them with `/manage post`.
+///
+/// Templates can include variables in braces, such as `{theme}`, which are filled in when posting. The variables `{date}`, `{weekday}`, `{week}`, `{month}`, `{year}`, `{server}`, and `{channel}` are filled in automatically.
+#[poise::command(
+  slash_command,
+  subcommands("add_template", "edit_template", "remove_template", "list_templates"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn templates(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+async fn autocomplete_template(
+  ctx: Context<'_>,
+  partial: &str,
+) -> Vec<serenity::AutocompleteChoice> {
+  let Some(guild_id) = ctx.guild_id() else {
+    return Vec::new();
+  };
+
+  let Ok(mut transaction) = ctx.data().db.start_transaction_with_retry(5).await else {
+    return Vec::new();
+  };
+  let Ok(templates) = DatabaseHandler::get_message_templates(&mut transaction, &guild_id).await
+  else {
+    return Vec::new();
+  };
+  drop(transaction);
+
+  let partial = partial.to_lowercase();
+
+  templates
+    .into_iter()
+    .filter(|template| template.name.to_lowercase().contains(&partial))
+    .take(25)
+    .map(|template| serenity::AutocompleteChoice::new(template.name.clone(), template.name))
+    .collect()
+}
+
+/// Add a message template
+///
+/// Adds a reusable message template. The message is entered in a form after running the command.
+#[poise::command(slash_command, rename = "add")]
+pub async fn add_template(
+  ctx: poise::ApplicationContext<'_, AppData, AppError>,
+  #[description = "A short name for the template"]
+  #[max_length = 50]
+  name: String,
+  #[description = "Post as an embed (Defaults to false)"] embed: Option<bool>,
+) -> Result<()> {
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+  let name = name.trim().to_string();
+
+  let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+  let existing = DatabaseHandler::get_message_templates(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  if existing.iter().any(|template| template.name == name) {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: A template named **{name}** already exists. Use `/manage templates edit` to change it."
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  if existing.len() >= MAX_TEMPLATES {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Servers can have at most {MAX_TEMPLATES} templates. Please remove one first."
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let Some(template) = TemplateModal::execute(ctx).await? else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: No data was provided.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::add_message_template(
+    &mut transaction,
+    &guild_id,
+    &name,
+    template.title.as_deref(),
+    &template.content,
+    embed.unwrap_or(false),
+  )
+  .await?;
+
+  commit_and_say(
+    poise::Context::Application(ctx),
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Template **{name}** has been added. Use `/manage post` to post it."
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Edit a message template
+///
+/// Edits the message of a template, and optionally whether it is posted as an embed.
+#[poise::command(slash_command, rename = "edit")]
+pub async fn edit_template(
+  ctx: poise::ApplicationContext<'_, AppData, AppError>,
+  #[description = "The template to edit"]
+  #[autocomplete = "autocomplete_template"]
+  name: String,
+  #[description = "Post as an embed (Defaults to unchanged)"] embed: Option<bool>,
+) -> Result<()> {
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+  let existing = DatabaseHandler::get_message_template(&mut transaction, &guild_id, &name).await?;
+  drop(transaction);
+
+  let Some(existing) = existing else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Template not found. Please choose a template from the list.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let defaults = TemplateModal {
+    title: existing.title,
+    content: existing.content,
+  };
+
+  let Some(template) = TemplateModal::execute_with_defaults(ctx, defaults).await? else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: No data was provided.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::update_message_template(
+    &mut transaction,
+    &guild_id,
+    &name,
+    template.title.as_deref(),
+    &template.content,
+    embed,
+  )
+  .await?;
+
+  commit_and_say(
+    poise::Context::Application(ctx),
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Template **{name}** has been updated."
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Remove a message template
+///
+/// Removes a message template.
+#[poise::command(slash_command, rename = "remove")]
+pub async fn remove_template(
+  ctx: Context<'_>,
+  #[description = "The template to remove"]
+  #[autocomplete = "autocomplete_template"]
+  name: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed =
+    DatabaseHandler::remove_message_template(&mut transaction, &guild_id, &name).await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Template not found. Please choose a template from the list.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Template **{name}** has been removed."
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// List message templates
+///
+/// Lists the message templates in this server, with the variables each one uses.
+#[poise::command(slash_command, rename = "list")]
+pub async fn list_templates(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let templates = DatabaseHandler::get_message_templates(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let content = if templates.is_empty() {
+    "No templates have been added.".to_string()
+  } else {
+    templates
+      .iter()
+      .map(|template| {
+        let format = if template.as_embed { "embed" } else { "text" };
+        let text = format!(
+          "{} {}",
+          template.title.as_deref().unwrap_or_default(),
+          template.content
+        );
+        // Rendering without values reports every variable as missing
+        let variables = match render_template(&text, &HashMap::new()) {
+          Ok(_) => "no variables".to_string(),
+          Err(variables) => variables
+            .iter()
+            .map(|variable| format!("`{{{variable}}}`"))
+            .collect::<Vec<String>>()
+            .join(", "),
+        };
+
+        format!("- **{}** ({format}): {variables}", template.name)
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  };
+
+  ctx
+    .send(CreateReply::default().content(content).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Post a message template
+///
+/// Fills in a message template and shows a preview, which can then be posted in the chosen channel.
+///
+/// Give values for the template's own variables as `name=value` pairs separated by semicolons, such as `theme=Gratitude; host=Sam`. Values given for automatic variables such as `{date}` replace them.
+#[poise::command(slash_command)]
+pub async fn post(
+  ctx: Context<'_>,
+  #[description = "The template to post"]
+  #[autocomplete = "autocomplete_template"]
+  template: String,
+  #[description = "The channel to post in"]
+  #[channel_types("Text", "News")]
+  channel: serenity::GuildChannel,
+  #[description = "Values for variables, such as \"theme=Gratitude; host=Sam\""] values: Option<
+    String,
+  >,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let found = DatabaseHandler::get_message_template(&mut transaction, &guild_id, &template).await?;
+  drop(transaction);
+
+  let Some(template) = found else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Template not found. Please choose a template from the list.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let Some(given) = parse_template_values(values.as_deref().unwrap_or_default()) else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Please give values as `name=value` pairs separated by semicolons, such as `theme=Gratitude; host=Sam`.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let today = chrono::Utc::now().date_naive();
+  let mut variables: HashMap<String, String> = HashMap::from([
+    ("date".to_string(), today.format("%Y-%m-%d").to_string()),
+    ("weekday".to_string(), today.format("%A").to_string()),
+    ("week".to_string(), today.iso_week().week().to_string()),
+    ("month".to_string(), today.format("%B").to_string()),
+    ("year".to_string(), today.year().to_string()),
+    ("server".to_string(), guild_id.name(ctx).unwrap_or_default()),
+    ("channel".to_string(), channel.mention().to_string()),
+  ]);
+  variables.extend(given);
+
+  let rendered = render_template(&template.content, &variables).and_then(|content| {
+    let title = template
+      .title
+      .as_deref()
+      .map(|title| render_template(title, &variables))
+      .transpose()?;
+    Ok((title, content))
+  });
+
+  let (title, content) = match rendered {
+    Ok(rendered) => rendered,
+    Err(missing) => {
+      ctx
+        .send(
+          CreateReply::default()
+            .content(format!(
+              ":x: Please give values for: {}",
+              missing
+                .iter()
+                .map(|variable| format!("`{variable}`"))
+                .collect::<Vec<String>>()
+                .join(", ")
+            ))
+            .ephemeral(true),
+        )
+        .await?;
+      return Ok(());
+    }
+  };
+
+  if !template.as_embed && content.chars().count() > 2000 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(
+            ":x: The filled in message is longer than 2000 characters. Please use shorter values.",
+          )
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let ctx_id = ctx.id();
+
+  let confirm_id = format!("{ctx_id}confirm");
+  let cancel_id = format!("{ctx_id}cancel");
+
+  let mut preview =
+    CreateReply::default()
+      .ephemeral(true)
+      .components(vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(confirm_id.clone())
+          .label(
+            format!("Post in #{}", channel.name)
+              .chars()
+              .take(80)
+              .collect::<String>(),
+          )
+          .style(serenity::ButtonStyle::Success),
+        CreateButton::new(cancel_id.clone())
+          .label("Cancel")
+          .style(serenity::ButtonStyle::Danger),
+      ])]);
+  preview = if template.as_embed {
+    let mut embed = BloomBotEmbed::new().description(&content);
+    if let Some(title) = &title {
+      embed = embed.title(title);
+    }
+    preview.embed(embed)
+  } else {
+    preview.content(&content)
+  };
+
+  let check = ctx.send(preview).await?;
+
+  // Loop through incoming interactions with the buttons
+  while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+    // We defined our button IDs to start with `ctx_id`. If they don't, some other command's
+    // button was pressed
+    .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+    // Timeout when no button has been pressed in one minute
+    .timeout(std::time::Duration::from_secs(60))
+    .await
+  {
+    if press.data.custom_id != confirm_id && press.data.custom_id != cancel_id {
+      // This is an unrelated button interaction
+      continue;
+    }
+
+    let confirmed = press.data.custom_id == confirm_id;
+
+    let outcome = if confirmed {
+      channel
+        .send_message(
+          ctx,
+          announcements::announcement_message(title.as_deref(), &content, template.as_embed),
+        )
+        .await?;
+      format!(
+        ":white_check_mark: Template **{}** has been posted in {}.",
+        template.name,
+        channel.mention()
+      )
+    } else {
+      "Cancelled.".to_string()
+    };
+
+    press
+      .create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+          CreateInteractionResponseMessage::new()
+            .content(outcome)
+            .embeds(Vec::new())
+            .components(Vec::new()),
+        ),
+      )
+      .await?;
+
+    return Ok(());
+  }
+
+  // This happens when the user didn't press any button for 60 seconds
+  check
+    .edit(
+      ctx,
+      CreateReply::default()
+        .content(":x: Timed out. The template was not posted.")
+        .components(Vec::new()),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Manage automatic discussion threads
+///
+/// Commands to choose channels where Bloom starts a discussion thread on each new message, such as check-in channels.
+#[poise::command(
+  slash_command,
+  subcommands("threads_set", "threads_remove", "threads_list"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn threads(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Start threads on new messages in a channel
+///
+/// Starts a discussion thread on each new message in a channel. Messages from bots are skipped.
+///
+/// The thread name can include `{author}`, `{channel}`, `{content}` (the first line of the message), `{date}`, and `{weekday}`. Using this on a channel that already has threads started updates the name.
+#[poise::command(slash_command, rename = "set")]
+pub async fn threads_set(
+  ctx: Context<'_>,
+  #[description = "The channel to start threads in"]
+  #[channel_types("Text", "News")]
+  channel: serenity::GuildChannel,
+  #[description = "Thread name, such as \"{author}'s check-in\" (Defaults to \"{author}'s post on {date}\")"]
+  #[max_length = 100]
+  name: Option<String>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let name = name.map_or(DEFAULT_AUTO_THREAD_NAME.to_string(), |name| {
+    name.trim().to_string()
+  });
+
+  let known = AUTO_THREAD_VARIABLES
+    .iter()
+    .map(|variable| ((*variable).to_string(), String::new()))
+    .collect::<HashMap<String, String>>();
+  if let Err(unknown) = render_template(&name, &known) {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Unknown variables: {}. Thread names can use {}.",
+            unknown
+              .iter()
+              .map(|variable| format!("`{{{variable}}}`"))
+              .collect::<Vec<String>>()
+              .join(", "),
+            AUTO_THREAD_VARIABLES
+              .iter()
+              .map(|variable| format!("`{{{variable}}}`"))
+              .collect::<Vec<String>>()
+              .join(", ")
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::set_auto_thread_channel(&mut transaction, &guild_id, &channel.id, &name).await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: New messages in {} will get a thread named `{name}`.",
+      channel.mention()
+    )),
+    true,
+  )
+  .await?;
+
+  data.auto_threads.invalidate(&guild_id).await;
+
+  Ok(())
+}
+
+/// Stop starting threads in a channel
+///
+/// Stops starting discussion threads on new messages in a channel. Existing threads are kept.
+#[poise::command(slash_command, rename = "remove")]
+pub async fn threads_remove(
+  ctx: Context<'_>,
+  #[description = "The channel to stop starting threads in"]
+  #[channel_types("Text", "News")]
+  channel: serenity::GuildChannel,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed =
+    DatabaseHandler::remove_auto_thread_channel(&mut transaction, &guild_id, &channel.id).await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Threads are not started in {}.",
+            channel.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Threads will no longer be started in {}.",
+      channel.mention()
+    )),
+    true,
+  )
+  .await?;
+
+  data.auto_threads.invalidate(&guild_id).await;
+
+  Ok(())
+}
+
+/// List channels with automatic threads
+///
+/// Lists the channels where discussion threads are started on new messages, and how the threads are named.
+#[poise::command(slash_command, rename = "list")]
+pub async fn threads_list(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let channels = DatabaseHandler::get_auto_thread_channels(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let content = if channels.is_empty() {
+    "Threads are not started in any channels.".to_string()
+  } else {
+    channels
+      .iter()
+      .map(|channel| {
+        format!(
+          "- {}: `{}`",
+          channel.channel_id.mention(),
+          channel.name_pattern
+        )
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  };
+
+  ctx
+    .send(CreateReply::default().content(content).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Manage the weekly check-in prompt
+///
+/// Commands to post a weekly check-in prompt with a button members press to check in. Consecutive weeks of check-ins are shown as a check-in streak in `/stats user`.
+#[poise::command(
+  slash_command,
+  subcommands("checkin_set", "checkin_remove", "checkin_show"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn checkin(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Post a weekly check-in prompt
+///
+/// Posts a check-in prompt in a channel every week at the chosen day and time (UTC). Members press the button on the prompt to check in, once per week. Using this again replaces the current prompt.
+#[poise::command(slash_command, rename = "set")]
+pub async fn checkin_set(
+  ctx: Context<'_>,
+  #[description = "The channel to post the prompt in"]
+  #[channel_types("Text", "News")]
+  channel: serenity::GuildChannel,
+  #[description = "The day of the week to post the prompt"] weekday: Weekday,
+  #[description = "The time to post the prompt, in UTC (e.g. 18:00 or 6pm)"] time: String,
+  #[description = "The message to post with the button"]
+  #[max_length = 2000]
+  message: Option<String>,
+) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let Some(send_minute) = parse_time_of_day(&time) else {
+    ctx
+      .say(":x: Please specify a time such as `18:00` or `6pm`.")
+      .await?;
+    return Ok(());
+  };
+
+  let message = message.unwrap_or_else(|| DEFAULT_CHECKIN_MESSAGE.to_string());
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::set_checkin_prompt(
+    &mut transaction,
+    &guild_id,
+    &channel.id,
+    weekday.days_from_monday(),
+    send_minute,
+    &message,
+  )
+  .await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: The check-in prompt will be posted in {} every {} at **{:02}:{:02}** UTC.",
+      channel.mention(),
+      weekday.name(),
+      send_minute / 60,
+      send_minute % 60,
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Stop posting the weekly check-in prompt
+///
+/// Stops posting the weekly check-in prompt. Check-ins already recorded are kept, and buttons on earlier prompts keep working.
+#[poise::command(slash_command, rename = "remove")]
+pub async fn checkin_remove(ctx: Context<'_>) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed = DatabaseHandler::remove_checkin_prompt(&mut transaction, &guild_id).await?;
+
+  if removed == 0 {
+    ctx.say(":x: There is no weekly check-in prompt.").await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(
+      ":white_check_mark: The weekly check-in prompt will no longer be posted.".to_string(),
+    ),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Show the weekly check-in prompt
+///
+/// Shows where and when the weekly check-in prompt is posted, and its message.
+#[poise::command(slash_command, rename = "show")]
+pub async fn checkin_show(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let prompts = DatabaseHandler::get_checkin_prompts(&mut transaction, Some(&guild_id)).await?;
+  drop(transaction);
+
+  let content = match prompts.first() {
+    Some(prompt) => format!(
+      "Posted in {} every {} at {:02}:{:02} UTC:\n>>> {}",
+      prompt.channel_id.mention(),
+      Weekday::from_days_from_monday(prompt.weekday).map_or("week", |weekday| weekday.name()),
+      prompt.send_minute / 60,
+      prompt.send_minute % 60,
+      prompt.message,
+    ),
+    None => "There is no weekly check-in prompt.".to_string(),
+  };
+
+  ctx
+    .send(CreateReply::default().content(content).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Manage the server's collective goal
+///
+/// Commands to set a shared meditation target for the whole server, such as 10,000 hours in a quarter. Progress is shown as a progress bar that is updated daily, and milestones are announced at 25%, 50%, 75%, and 100%.
+#[poise::command(
+  slash_command,
+  subcommands("collective_start", "collective_end", "collective_show"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn collective(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Start a collective goal
+///
+/// Starts a shared meditation target for the whole server. All minutes added from the start date through the end date count toward it. Starting a new goal replaces the current one.
+#[poise::command(slash_command, rename = "start")]
+pub async fn collective_start(
+  ctx: Context<'_>,
+  #[description = "Name of the goal, such as \"Spring Quarter\""]
+  #[max_length = 80]
+  name: String,
+  #[description = "Target in hours, such as 10000"]
+  #[min = 1]
+  target_hours: i64,
+  #[description = "Last day that counts toward the goal (YYYY-MM-DD)"] end_date: String,
+  #[description = "The channel for the progress bar and milestone announcements"]
+  #[channel_types("Text", "News")]
+  channel: serenity::GuildChannel,
+  #[description = "First day that counts toward the goal (YYYY-MM-DD, defaults to today)"]
+  start_date: Option<String>,
+) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let today = chrono::Utc::now().date_naive();
+
+  let starts_on = match start_date {
+    Some(start_date) => {
+      let Ok(starts_on) = chrono::NaiveDate::parse_from_str(start_date.trim(), "%Y-%m-%d") else {
+        ctx
+          .say(":x: Please specify the start date as `YYYY-MM-DD`.")
+          .await?;
+        return Ok(());
+      };
+      starts_on
+    }
+    None => today,
+  };
+
+  let Ok(ends_on) = chrono::NaiveDate::parse_from_str(end_date.trim(), "%Y-%m-%d") else {
+    ctx
+      .say(":x: Please specify the end date as `YYYY-MM-DD`.")
+      .await?;
+    return Ok(());
+  };
+
+  if ends_on < today || ends_on < starts_on {
+    ctx
+      .say(":x: The end date must not be in the past or before the start date.")
+      .await?;
+    return Ok(());
+  }
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::set_collective_goal(
+    &mut transaction,
+    &guild_id,
+    name.trim(),
+    target_hours.saturating_mul(60),
+    starts_on,
+    ends_on,
+    &channel.id,
+  )
+  .await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: The collective goal **{}** of {target_hours} hours has been set, from {} to {}. Progress will be posted in {}.",
+      name.trim(),
+      starts_on.format("%Y-%m-%d"),
+      ends_on.format("%Y-%m-%d"),
+      channel.mention(),
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// End the collective goal
+///
+/// Ends the current collective goal early. The progress message is kept, but is no longer updated.
+#[poise::command(slash_command, rename = "end")]
+pub async fn collective_end(ctx: Context<'_>) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed = DatabaseHandler::remove_collective_goal(&mut transaction, &guild_id).await?;
+
+  if removed == 0 {
+    ctx.say(":x: There is no collective goal.").await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(":white_check_mark: The collective goal has been ended.".to_string()),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Show progress toward the collective goal
+///
+/// Shows the current collective goal and its progress bar.
+#[poise::command(slash_command, rename = "show")]
+pub async fn collective_show(ctx: Context<'_>) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let goals = DatabaseHandler::get_collective_goals(&mut transaction, Some(&guild_id)).await?;
+
+  let Some(goal) = goals.first() else {
+    ctx.say("There is no collective goal.").await?;
+    return Ok(());
+  };
+
+  let minutes = DatabaseHandler::get_guild_minutes_between(
+    &mut transaction,
+    &guild_id,
+    goal.starts_on,
+    goal.ends_on,
+  )
+  .await?;
+  let palette = DatabaseHandler::get_chart_palette(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let (embed, chart) = collective_goals::progress_embed(goal, minutes, palette).await?;
+
+  ctx
+    .send(
+      CreateReply::default()
+        .embed(embed.field("Channel", goal.channel_id.mention().to_string(), true))
+        .attachment(CreateAttachment::path(chart.get_file_path()).await?)
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Manage announcements of the server's total hours
+///
+/// Commands to choose how often the server's total meditation hours are announced after `/add`, and the message that is posted.
+#[poise::command(
+  slash_command,
+  subcommands("milestones_set", "milestones_reset", "milestones_show"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn milestones(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Change how the server's total hours are announced
+///
+/// Changes how many sessions are added between announcements of the server's total hours, and the message that is posted. An interval of 0 turns announcements off. Options that are left out keep their current setting.
+///
+/// The message can include `{hours}`, `{minutes}`, and `{sessions}`, which are the server's totals.
+#[poise::command(slash_command, rename = "set")]
+pub async fn milestones_set(
+  ctx: Context<'_>,
+  #[description = "Sessions added between announcements (0 turns announcements off)"]
+  #[min = 0]
+  #[max = 1000]
+  interval: Option<i32>,
+  #[description = "Message to post, such as \"We've meditated for {hours} hours together!\""]
+  #[max_length = 500]
+  message: Option<String>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  if interval.is_none() && message.is_none() {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Choose an interval, a message, or both.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let message = message.map(|message| message.trim().to_string());
+
+  if let Some(message) = &message {
+    let known = HOURS_MESSAGE_VARIABLES
+      .iter()
+      .map(|variable| ((*variable).to_string(), String::new()))
+      .collect::<HashMap<String, String>>();
+    if let Err(unknown) = render_template(message, &known) {
+      ctx
+        .send(
+          CreateReply::default()
+            .content(format!(
+              ":x: Unknown variables: {}. The message can use {}.",
+              unknown
+                .iter()
+                .map(|variable| format!("`{{{variable}}}`"))
+                .collect::<Vec<String>>()
+                .join(", "),
+              HOURS_MESSAGE_VARIABLES
+                .iter()
+                .map(|variable| format!("`{{{variable}}}`"))
+                .collect::<Vec<String>>()
+                .join(", ")
+            ))
+            .ephemeral(true),
+        )
+        .await?;
+      return Ok(());
+    }
+  }
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let current = DatabaseHandler::get_hours_announcement(&mut transaction, &guild_id).await?;
+  let announcement = HoursAnnouncement {
+    session_interval: interval.unwrap_or(current.session_interval),
+    message: message.or(current.message),
+  };
+  DatabaseHandler::set_hours_announcement(&mut transaction, &guild_id, &announcement).await?;
+
+  let content = if announcement.session_interval == 0 {
+    ":white_check_mark: The server's total hours will no longer be announced.".to_string()
+  } else {
+    format!(
+      ":white_check_mark: The server's total hours will be announced every {} sessions.",
+      announcement.session_interval
+    )
+  };
+
+  commit_and_say(ctx, transaction, MessageType::TextOnly(content), true).await?;
+
+  Ok(())
+}
+
+/// Reset announcements of the server's total hours
+///
+/// Goes back to announcing the server's total hours every 10 sessions with the default message.
+#[poise::command(slash_command, rename = "reset")]
+pub async fn milestones_reset(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed = DatabaseHandler::remove_hours_announcement(&mut transaction, &guild_id).await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Announcements of the server's total hours already use the defaults.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: The server's total hours will be announced every {DEFAULT_HOURS_INTERVAL} sessions with the default message."
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Show how the server's total hours are announced
+///
+/// Shows how often the server's total hours are announced, and the message that is posted.
+#[poise::command(slash_command, rename = "show")]
+pub async fn milestones_show(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let announcement = DatabaseHandler::get_hours_announcement(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let content = if announcement.session_interval == 0 {
+    "The server's total hours are not announced.".to_string()
+  } else {
+    format!(
+      "Announced every {} sessions:\n>>> {}",
+      announcement.session_interval,
+      announcement
+        .message
+        .as_deref()
+        .unwrap_or(DEFAULT_HOURS_MESSAGE),
+    )
+  };
+
+  ctx
+    .send(CreateReply::default().content(content).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Turn streak restores on or off
+///
+/// Turns streak restores on or off. While they are on, members earn a streak credit for every 7 days of their meditation streak, up to 3 at a time, and can spend a credit with `/streak restore` to restore a single missed day in the past 48 hours.
+///
+/// Restored streaks are marked as restored on leaderboards. Credits already earned are kept when restores are turned off.
+#[poise::command(slash_command)]
+pub async fn restores(
+  ctx: Context<'_>,
+  #[description = "Turn streak restores on or off"] mode: OnOff,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let enabled = matches!(mode, OnOff::On);
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  if enabled == DatabaseHandler::streak_restores_enabled(&mut transaction, &guild_id).await? {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!("Streak restores are already {}.", mode.name()))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  DatabaseHandler::set_streak_restores_enabled(&mut transaction, &guild_id, enabled).await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Streak restores are now {}.",
+      mode.name()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Manage leaderboard rollovers
+///
+/// Commands to post the final leaderboard in a channel at the end of each week or month, congratulating the top members. Each new leaderboard is pinned, and the previous one is unpinned.
+#[poise::command(
+  slash_command,
+  subcommands("rollover_set", "rollover_remove", "rollover_show"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn rollover(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Post the final leaderboard when a period ends
+///
+/// Posts and pins the final leaderboard in a channel at the end of each week or month, starting with the period in progress. Weeks run from Monday to Sunday, and periods end at midnight UTC. Using this again for the same period changes the channel.
+#[poise::command(slash_command, rename = "set")]
+pub async fn rollover_set(
+  ctx: Context<'_>,
+  #[description = "Whether to post weekly or monthly leaderboards"] period: LeaderboardPeriod,
+  #[description = "The channel to post the leaderboard in"]
+  #[channel_types("Text", "News")]
+  channel: serenity::GuildChannel,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  // Periods that already ended aren't posted, so the first leaderboard is for the period in progress
+  let (last_period_start, _) = leaderboard_rollovers::completed_period(period, chrono::Utc::now());
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::set_leaderboard_rollover(
+    &mut transaction,
+    &guild_id,
+    period,
+    &channel.id,
+    last_period_start,
+  )
+  .await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: The final {} leaderboard will be posted and pinned in {}.",
+      period.name(),
+      channel.mention()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Stop posting the final leaderboard
+///
+/// Stops posting the final weekly or monthly leaderboard. Leaderboards that were already posted stay pinned.
+#[poise::command(slash_command, rename = "remove")]
+pub async fn rollover_remove(
+  ctx: Context<'_>,
+  #[description = "Whether to stop weekly or monthly leaderboards"] period: LeaderboardPeriod,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let removed =
+    DatabaseHandler::remove_leaderboard_rollover(&mut transaction, &guild_id, period).await?;
+
+  if removed == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: The final {} leaderboard is not posted.",
+            period.name()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: The final {} leaderboard will no longer be posted.",
+      period.name()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Show where final leaderboards are posted
+///
+/// Shows the channels where the final weekly and monthly leaderboards are posted.
+#[poise::command(slash_command, rename = "show")]
+pub async fn rollover_show(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let rollovers =
+    DatabaseHandler::get_leaderboard_rollovers(&mut transaction, Some(&guild_id)).await?;
+  drop(transaction);
+
+  let content = if rollovers.is_empty() {
+    "Final leaderboards are not posted.".to_string()
+  } else {
+    rollovers
+      .iter()
+      .map(|rollover| {
+        format!(
+          "**{}**: {}",
+          rollover.period.name(),
+          rollover.channel_id.mention()
+        )
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  };
+
+  ctx
+    .send(CreateReply::default().content(content).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Choose the colors used in charts
+///
+/// Sets the colors used in the server's stats charts, leaderboards, and progress bars. High-contrast and colorblind-safe palettes adjust to light and dark mode. While a palette other than the default is chosen, donators' role colors are not used in their stats charts.
+#[poise::command(slash_command)]
+pub async fn charts(
+  ctx: Context<'_>,
+  #[description = "The palette to use"] palette: ChartPalette,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::set_chart_palette(&mut transaction, &guild_id, palette).await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Charts now use the {} palette.",
+      palette.name()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Restore or purge deleted meditation entries
+///
+/// Commands to list, restore, or permanently purge meditation entries that were deleted, reset, removed after an anomaly review, or replaced by an import. Deleted entries stay in the trash for 30 days before they are purged automatically.
+#[poise::command(
+  slash_command,
+  subcommands("trash_list", "trash_restore", "trash_purge"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn trash(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// List deleted meditation entries
+///
+/// Lists the 25 most recent batches of deleted meditation entries, optionally only those for one user. Entries deleted together, such as by a reset, are restored or purged together.
+#[poise::command(slash_command, rename = "list")]
+pub async fn trash_list(
+  ctx: Context<'_>,
+  #[description = "Only show entries deleted for this user"] user: Option<serenity::User>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let batches = DatabaseHandler::get_trash_batches(
+    &mut transaction,
+    &guild_id,
+    user.as_ref().map(|user| &user.id),
+  )
+  .await?;
+  drop(transaction);
+
+  let content = if batches.is_empty() {
+    "The trash is empty.".to_string()
+  } else {
+    batches
+      .iter()
+      .map(|batch| {
+        format!(
+          "`{}` <@{}>: {} entries ({} minutes), {} by <@{}> <t:{}:R>",
+          batch.batch_id,
+          batch.user_id,
+          batch.entries,
+          batch.minutes,
+          batch.reason,
+          batch.deleted_by,
+          batch.deleted_at.timestamp()
+        )
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  };
+
+  ctx
+    .send(
+      CreateReply::default()
+        .content(content)
+        .allowed_mentions(serenity::CreateAllowedMentions::new())
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Restore deleted meditation entries
+///
+/// Restores a batch of deleted meditation entries. Use `/manage trash list` to find the batch ID.
+#[poise::command(slash_command, rename = "restore")]
+pub async fn trash_restore(
+  ctx: Context<'_>,
+  #[description = "The batch to restore"] batch_id: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let restored =
+    DatabaseHandler::restore_trash_batch(&mut transaction, &guild_id, &batch_id).await?;
+
+  if restored == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: No deleted entries found in batch `{batch_id}`."
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Restored {restored} meditation entries."
+    )),
+    true,
+  )
+  .await?;
+
+  log_trash_action(ctx, "Restored", &batch_id, restored).await?;
+
+  Ok(())
+}
+
+/// Permanently delete meditation entries from the trash
+///
+/// Permanently deletes a batch of meditation entries from the trash, so they can no longer be restored. Use `/manage trash list` to find the batch ID.
+#[poise::command(slash_command, rename = "purge")]
+pub async fn trash_purge(
+  ctx: Context<'_>,
+  #[description = "The batch to purge"] batch_id: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let purged = DatabaseHandler::purge_trash_batch(&mut transaction, &guild_id, &batch_id).await?;
+
+  if purged == 0 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: No deleted entries found in batch `{batch_id}`."
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Permanently deleted {purged} meditation entries."
+    )),
+    true,
+  )
+  .await?;
+
+  log_trash_action(ctx, "Purged", &batch_id, purged).await?;
+
+  Ok(())
+}
+
+async fn log_trash_action(
+  ctx: Context<'_>,
+  outcome: &str,
+  batch_id: &str,
+  entries: u64,
+) -> Result<()> {
+  let log_embed = BloomBotEmbed::new()
+    .title(format!("Meditation Entries {outcome}"))
+    .description(format!(
+      "**Trash batch**: `{batch_id}`\n**Entries**: {entries}"
+    ))
+    .footer(
+      CreateEmbedFooter::new(format!(
+        "{outcome} by {} ({})",
+        ctx.author().name,
+        ctx.author().id
+      ))
+      .icon_url(ctx.author().avatar_url().unwrap_or_default()),
+    );
+
+  let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+
+  log_channel
+    .send_message(ctx, CreateMessage::new().embed(log_embed))
+    .await?;
+
+  Ok(())
+}
+
+/// View a member's private stats with their consent
+///
+/// Commands to ask a member for access to their private stats, and to review the access log. Each request, answer, and view is recorded in the access log.
+#[poise::command(
+  slash_command,
+  subcommands("stats_request", "stats_log"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn stats(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Ask a member for access to their private stats
+///
+/// Sends the member a DM asking them to let you view their private stats, for example to help with a support request. If they approve, you can view their stats with `/stats user` for 24 hours.
+#[poise::command(slash_command, rename = "request")]
+pub async fn stats_request(
+  ctx: Context<'_>,
+  #[description = "The member whose stats you need to see"] user: serenity::User,
+  #[description = "Why you need to see their stats, shown to the member"]
+  #[max_length = 512]
+  reason: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+
+  let stats_private = DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user.id)
+    .await?
+    .is_some_and(|tracking_profile| tracking_profile.stats_private);
+  if !stats_private {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: {}'s stats are not private. You can view them with `/stats user`.",
+            user.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let request_id = DatabaseHandler::create_stats_access_request(
+    &mut transaction,
+    &guild_id,
+    &user.id,
+    &ctx.author().id,
+    &reason,
+  )
+  .await?;
+  DatabaseHandler::add_stats_access_log(
+    &mut transaction,
+    &guild_id,
+    &user.id,
+    &ctx.author().id,
+    StatsAccessAction::Requested,
+  )
+  .await?;
+
+  let guild_name = guild_id
+    .name(ctx)
+    .unwrap_or_else(|| "this server".to_string());
+  let message = stats_access::request_message(&guild_name, ctx.author(), &reason, &request_id);
+
+  // Only keep the request if the member can actually be asked
+  if user.direct_message(ctx, message).await.is_err() {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Could not send {} a DM. They may have DMs from server members turned off.",
+            user.mention()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Asked {} for access to their stats. You'll be able to view them with `/stats user` if they approve.",
+      user.mention()
+    )),
+    true,
+  )
+  .await?;
+
+  let log_embed = BloomBotEmbed::new()
+    .title("Stats Access Requested")
+    .description(format!("**User**: <@{}>\n**Reason**: {reason}", user.id))
+    .footer(
+      CreateEmbedFooter::new(format!(
+        "Requested by {} ({})",
+        ctx.author().name,
+        ctx.author().id
+      ))
+      .icon_url(ctx.author().avatar_url().unwrap_or_default()),
+    );
+
+  let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+
+  log_channel
+    .send_message(ctx, CreateMessage::new().embed(log_embed))
+    .await?;
+
+  Ok(())
+}
+
+/// Show the access log for a member's private stats
+///
+/// Shows the 25 most recent requests, answers, and views of a member's private stats by staff.
+#[poise::command(slash_command, rename = "log")]
+pub async fn stats_log(
+  ctx: Context<'_>,
+  #[description = "The member to show the access log for"] user: serenity::User,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let entries =
+    DatabaseHandler::get_stats_access_log(&mut transaction, &guild_id, &user.id).await?;
+  drop(transaction);
+
+  let content = if entries.is_empty() {
+    format!("No staff have asked to view {}'s stats.", user.mention())
+  } else {
+    entries
+      .iter()
+      .map(|entry| {
+        format!(
+          "<t:{}:f> <@{}> {}",
+          entry.occurred_at.timestamp(),
+          entry.staff_id,
+          entry.action.name()
+        )
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  };
+
+  ctx
+    .send(
+      CreateReply::default()
+        .content(content)
+        .allowed_mentions(serenity::CreateAllowedMentions::new())
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Simulate commands without saving anything
+///
+/// Commands to see what an action would do for a member without saving it, for checking role and milestone settings.
+#[poise::command(slash_command, subcommands("simulate_add"), subcommand_required)]
+#[allow(clippy::unused_async)]
+pub async fn simulate(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Simulate adding a session for a member
+///
+/// Runs an add for a member the same way `/add` does, then discards it. Reports what would happen: the member's new total and streak, the roles they would be given, where they would be congratulated, any streak credit, and server announcements and milestones. Nothing is saved and no roles are changed.
+#[poise::command(slash_command, rename = "add")]
+pub async fn simulate_add(
+  ctx: Context<'_>,
+  #[description = "The member to simulate the add for"] user: serenity::User,
+  #[description = "Minutes to add"]
+  #[min = 1]
+  minutes: i32,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  ctx.defer_ephemeral().await?;
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+
+  let tracking_profile =
+    match DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user.id).await? {
+      Some(tracking_profile) => tracking_profile,
+      None => TrackingProfile {
+        ..Default::default()
+      },
+    };
+  let limits = DatabaseHandler::get_session_limits(&mut transaction, &guild_id).await?;
+
+  let mut session = Vec::new();
+  if limits
+    .min_minutes
+    .is_some_and(|min_minutes| minutes < min_minutes)
+    || limits
+      .max_minutes
+      .is_some_and(|max_minutes| minutes > max_minutes)
+  {
+    session.push(":x: Would be rejected by the server's session limits. The rest of this report assumes it was added.".to_string());
+  } else if minutes > limits.confirm_minutes {
+    session.push(format!(
+      ":warning: Longer than {} minutes, so the member would be asked to confirm it.",
+      limits.confirm_minutes
+    ));
+  }
+
+  let sum_before =
+    DatabaseHandler::get_user_meditation_sum(&mut transaction, &guild_id, &user.id).await?;
+  let streak_before = DatabaseHandler::get_streak(&mut transaction, &guild_id, &user.id).await?;
+
+  // Stored in local time, like sessions added with /add
+  let occurred_at =
+    chrono::Utc::now() + chrono::Duration::minutes(i64::from(tracking_profile.utc_offset));
+  DatabaseHandler::create_meditation_entry(
+    &mut transaction,
+    &guild_id,
+    &user.id,
+    minutes,
+    occurred_at,
+    EntrySource::Slash,
+  )
+  .await?;
+
+  let user_sum =
+    DatabaseHandler::get_user_meditation_sum(&mut transaction, &guild_id, &user.id).await?;
+  let user_streak = DatabaseHandler::get_streak(&mut transaction, &guild_id, &user.id).await?;
+  let guild_count =
+    DatabaseHandler::get_guild_meditation_count(&mut transaction, &guild_id).await?;
+  let guild_sum = DatabaseHandler::get_guild_meditation_sum(&mut transaction, &guild_id).await?;
+  let hours_announcement =
+    DatabaseHandler::get_hours_announcement(&mut transaction, &guild_id).await?;
+  let record_streak = DatabaseHandler::get_record_streak(&mut transaction, &guild_id).await?;
+
+  // Earned inside the transaction like a real add, and rolled back with everything else
+  let earned_streak_credit = if tracking_profile.streaks_active
+    && user_streak > 0
+    && user_streak % STREAK_CREDIT_DAYS == 0
+    && DatabaseHandler::streak_restores_enabled(&mut transaction, &guild_id).await?
+  {
+    DatabaseHandler::earn_streak_credit(&mut transaction, &guild_id, &user.id, MAX_STREAK_CREDITS)
+      .await?
+  } else {
+    false
+  };
+
+  DatabaseHandler::rollback_transaction(transaction).await?;
+
+  session.push(format!(
+    "**Total**: {sum_before} → {user_sum} minutes\n**Streak**: {streak_before} → {user_streak} days{}",
+    if tracking_profile.streaks_active {
+      ""
+    } else {
+      " (streak reporting is off)"
+    }
+  ));
+  if earned_streak_credit {
+    session.push(format!(
+      ":coin: Would earn a streak credit for a {user_streak}-day streak."
+    ));
+  }
+
+  let guild = ctx.guild().unwrap().clone();
+  let roles = match guild.member(ctx, user.id).await {
+    Ok(member) => {
+      let streak = tracking_profile.streaks_active.then_some(user_streak);
+      let changes = milestones::role_changes(&guild, &member, user_sum, streak);
+
+      if changes.is_empty() {
+        "No role changes.".to_string()
+      } else {
+        changes
+          .iter()
+          .map(|change| {
+            let delivery = milestones::congrats_delivery(
+              &tracking_profile,
+              tracking_profile.anonymous_tracking,
+              change.milestone,
+            );
+            let replaced = if change.replaced.is_empty() {
+              String::new()
+            } else {
+              format!(
+                ", replacing {}",
+                change
+                  .replaced
+                  .iter()
+                  .map(|role_id| role_id.mention().to_string())
+                  .collect::<Vec<String>>()
+                  .join(", ")
+              )
+            };
+            format!(
+              "Would be given {}{replaced}. Congratulations: {}{}.",
+              change.role_id.mention(),
+              delivery.name(),
+              if tracking_profile.congrats_cards && delivery != CongratsDelivery::Off {
+                ", with a card"
+              } else {
+                ""
+              }
+            )
+          })
+          .collect::<Vec<String>>()
+          .join("\n")
+      }
+    }
+    Err(_) => "Not a member of this server, so roles can't be checked.".to_string(),
+  };
+
+  let mut server = Vec::new();
+  if milestones::is_hours_announcement_due(&hours_announcement, guild_count) {
+    server.push(format!(
+      "Would announce: {}",
+      milestones::hours_announcement_message(&hours_announcement, guild_sum, guild_count)
+    ));
+  }
+  if let Some(hours) = milestones::crossed_hours_milestone(guild_sum, minutes) {
+    server.push(format!(
+      "Would notify webhooks of the {hours}-hour milestone."
+    ));
+  }
+  if record_streak > 0 && i32::try_from(user_streak).is_ok_and(|streak| streak > record_streak) {
+    server.push(format!(
+      "Would set a new record streak, beating {record_streak} days, and notify webhooks."
+    ));
+  }
+  if server.is_empty() {
+    server.push("No announcements or milestones.".to_string());
+  }
+
+  ctx
+    .send(
+      CreateReply::default()
+        .embed(
+          BloomBotEmbed::new()
+            .title("Simulated Add")
+            .description(format!(
+              "**User**: {}\n**Time**: {minutes} minutes\n\nNothing was saved and no roles were changed.",
+              user.mention()
+            ))
+            .field("Session", session.join("\n"), false)
+            .field("Roles", roles, false)
+            .field("Server", server.join("\n"), false),
+        )
+        .allowed_mentions(serenity::CreateAllowedMentions::new())
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Register commands with Discord
+///
+/// Commands for the bot owner to re-register slash commands without restarting the bot, and to leave commands out when registering them in this server.
+#[poise::command(
+  slash_command,
+  subcommands(
+    "synccommands_global",
+    "synccommands_server",
+    "synccommands_exclude",
+    "synccommands_include",
+    "synccommands_excluded"
+  ),
+  subcommand_required,
+  owners_only
+)]
+#[allow(clippy::unused_async)]
+pub async fn synccommands(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Lists what registering commands added, removed, or changed.
+fn command_diff_summary(diff: &CommandDiff) -> String {
+  if diff.is_empty() {
+    return "Commands were already up to date.".to_string();
+  }
+
+  [
+    ("Added", &diff.added),
+    ("Removed", &diff.removed),
+    ("Changed", &diff.changed),
+  ]
+  .into_iter()
+  .filter(|(_, names)| !names.is_empty())
+  .map(|(label, names)| {
+    format!(
+      "**{label}**: {}",
+      names
+        .iter()
+        .map(|name| format!("`/{name}`"))
+        .collect::<Vec<String>>()
+        .join(", ")
+    )
+  })
+  .collect::<Vec<String>>()
+  .join("\n")
+}
+
+/// Re-register global commands
+///
+/// Registers every command globally, replacing the commands currently registered, and shows which commands were added, removed, or changed. Global commands can take up to an hour to update in every server.
+#[poise::command(slash_command, rename = "global")]
+pub async fn synccommands_global(ctx: Context<'_>) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let commands = command_sync::application_commands(&ctx.framework().options().commands, &[]);
+  let registered = serenity::Command::get_global_commands(ctx).await?;
+  let diff = command_sync::diff(&registered, &commands)?;
+
+  serenity::Command::set_global_commands(ctx, commands).await?;
+
+  ctx
+    .send(
+      CreateReply::default()
+        .content(format!(
+          ":white_check_mark: Registered commands globally.\n\n{}",
+          command_diff_summary(&diff)
+        ))
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Re-register commands in this server
+///
+/// Registers every command in this server, leaving out commands excluded with `/manage synccommands exclude`, and shows which commands were added, removed, or changed. Server commands update right away, and are shown alongside any global commands.
+#[poise::command(slash_command, rename = "server")]
+pub async fn synccommands_server(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  ctx.defer_ephemeral().await?;
+
+  let commands =
+    command_sync::guild_commands(&data.db, &ctx.framework().options().commands, &guild_id).await?;
+  let registered = guild_id.get_commands(ctx).await?;
+  let diff = command_sync::diff(&registered, &commands)?;
+
+  guild_id.set_commands(ctx, commands).await?;
+
+  ctx
+    .send(
+      CreateReply::default()
+        .content(format!(
+          ":white_check_mark: Registered commands in this server.\n\n{}",
+          command_diff_summary(&diff)
+        ))
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Exclude a command from this server
+///
+/// Leaves a command out the next time commands are registered in this server with `/manage synccommands server`. Doesn't affect global commands.
+#[poise::command(slash_command, rename = "exclude")]
+pub async fn synccommands_exclude(
+  ctx: Context<'_>,
+  #[description = "Name of the top-level command to exclude"] command: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let command = command.trim().trim_start_matches('/').to_lowercase();
+
+  if !ctx
+    .framework()
+    .options()
+    .commands
+    .iter()
+    .any(|known| known.name == command)
+  {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(":x: There is no `/{command}` command."))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  if UNEXCLUDABLE_COMMANDS.contains(&command.as_str()) {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: `/{command}` can't be excluded, since it's needed to undo exclusions."
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+
+  let message = if DatabaseHandler::add_command_exclusion(&mut transaction, &guild_id, &command)
+    .await?
+    > 0
+  {
+    format!(":white_check_mark: `/{command}` will be left out next time commands are registered with `/manage synccommands server`.")
+  } else {
+    format!(":x: `/{command}` is already excluded.")
+  };
+
+  commit_and_say(ctx, transaction, MessageType::TextOnly(message), true).await?;
+
+  Ok(())
+}
+
+/// Stop excluding a command from this server
+///
+/// Includes a previously excluded command the next time commands are registered in this server with `/manage synccommands server`.
+#[poise::command(slash_command, rename = "include")]
+pub async fn synccommands_include(
+  ctx: Context<'_>,
+  #[description = "Name of the excluded command"] command: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let command = command.trim().trim_start_matches('/').to_lowercase();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+
+  let message = if DatabaseHandler::remove_command_exclusion(&mut transaction, &guild_id, &command)
+    .await?
+    > 0
+  {
+    format!(":white_check_mark: `/{command}` will be included next time commands are registered with `/manage synccommands server`.")
+  } else {
+    format!(":x: `/{command}` isn't excluded.")
+  };
+
+  commit_and_say(ctx, transaction, MessageType::TextOnly(message), true).await?;
+
+  Ok(())
+}
+
+/// List excluded commands
+///
+/// Lists the commands left out when commands are registered in this server.
+#[poise::command(slash_command, rename = "excluded")]
+pub async fn synccommands_excluded(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let excluded = DatabaseHandler::get_command_exclusions(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let content = if excluded.is_empty() {
+    "No commands are excluded from this server.".to_string()
+  } else {
+    format!(
+      "**Excluded commands**: {}",
+      excluded
+        .iter()
+        .map(|name| format!("`/{name}`"))
+        .collect::<Vec<String>>()
+        .join(", ")
+    )
+  };
+
+  ctx
+    .send(CreateReply::default().content(content).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
+/// Manage feature flags
+///
+/// Commands to turn features that are being rolled out on or off for the server, or go back to each feature's default.
+#[poise::command(
+  slash_command,
+  subcommands("flags_set", "flags_reset", "flags_list"),
+  subcommand_required
+)]
+#[allow(clippy::unused_async)]
+pub async fn flags(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+#[allow(clippy::unused_async)]
+async fn autocomplete_feature_flag(_ctx: Context<'_>, partial: &str) -> Vec<String> {
+  let partial = partial.to_lowercase();
+
+  FEATURE_FLAGS
+    .iter()
+    .filter(|flag| flag.name.contains(&partial))
+    .map(|flag| flag.name.to_string())
+    .collect()
+}
+
+/// Turn a feature on or off
+///
+/// Turns a feature on or off for the server, overriding its default.
+#[poise::command(slash_command, rename = "set")]
+pub async fn flags_set(
+  ctx: Context<'_>,
+  #[description = "The feature to turn on or off"]
+  #[autocomplete = "autocomplete_feature_flag"]
+  flag: String,
+  #[description = "Whether the feature is on"] enabled: bool,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let Some(feature) = feature_flags::find(&flag) else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(":x: There is no `{flag}` feature flag."))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::set_feature_flag(&mut transaction, &guild_id, feature.name, enabled).await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: `{}` is now **{}** in this server.",
+      feature.name,
+      if enabled { "on" } else { "off" }
+    )),
+    true,
+  )
+  .await?;
+
+  data.flags.invalidate(guild_id, feature.name).await?;
+
+  Ok(())
+}
+
+/// Reset a feature to its default
+///
+/// Stops overriding a feature, so it follows its default again.
+#[poise::command(slash_command, rename = "reset")]
+pub async fn flags_reset(
+  ctx: Context<'_>,
+  #[description = "The feature to reset"]
+  #[autocomplete = "autocomplete_feature_flag"]
+  flag: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let Some(feature) = feature_flags::find(&flag) else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(":x: There is no `{flag}` feature flag."))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+
+  let message =
+    if DatabaseHandler::remove_feature_flag(&mut transaction, &guild_id, feature.name).await? > 0 {
+      format!(
+        ":white_check_mark: `{}` is back to its default, which is **{}**.",
+        feature.name,
+        if feature.default { "on" } else { "off" }
+      )
+    } else {
+      format!(":x: `{}` is already using its default.", feature.name)
+    };
+
+  commit_and_say(ctx, transaction, MessageType::TextOnly(message), true).await?;
+
+  data.flags.invalidate(guild_id, feature.name).await?;
+
+  Ok(())
+}
+
+/// List feature flags
+///
+/// Lists every feature flag, whether it's on in this server, and whether that's the default.
+#[poise::command(slash_command, rename = "list")]
+pub async fn flags_list(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let overrides = DatabaseHandler::get_feature_flags(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let flags = FEATURE_FLAGS
+    .iter()
+    .map(|flag| {
+      let overridden = overrides
+        .iter()
+        .find(|(name, _)| name == flag.name)
+        .map(|(_, enabled)| *enabled);
+      let enabled = overridden.unwrap_or(flag.default);
+
+      format!(
+        "`{}`: **{}**{}\n{}",
+        flag.name,
+        if enabled { "on" } else { "off" },
+        if overridden.is_some() {
+          ""
+        } else {
+          " (default)"
+        },
+        flag.description
+      )
+    })
+    .collect::<Vec<String>>()
+    .join("\n");
+
+  ctx
+    .send(
+      CreateReply::default()
+        .embed(
+          BloomBotEmbed::new()
+            .title("Feature Flags")
+            .description(flags),
+        )
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
@@ -1,4 +1,4 @@
-use crate::config::BloomBotEmbed;
+use crate::config::{BloomBotEmbed, EmojiKind};
 use crate::database::DatabaseHandler;
 use crate::Context;
 use anyhow::Result;
@@ -7,17 +7,25 @@ use poise::{serenity_prelude as serenity, CreateReply};
 use std::sync::atomic::Ordering;
 
 pub mod add;
+pub mod add_quote;
+pub mod calendar;
 pub mod challenge;
+pub mod checkin;
 pub mod coffee;
+pub mod community_sit;
 pub mod complete;
 pub mod courses;
 pub mod customize;
 pub mod erase;
 pub mod glossary;
 pub mod hello;
+pub mod journal;
+pub mod helpers;
 pub mod help;
+pub mod import;
 pub mod keys;
 pub mod manage;
+pub mod owner;
 pub mod pick_winner;
 pub mod ping;
 pub mod quote;
@@ -25,12 +33,62 @@ pub mod quotes;
 pub mod recent;
 pub mod remove_entry;
 pub mod report_message;
+pub mod sit;
 pub mod stats;
+pub mod status;
 pub mod streak;
 pub mod suggest;
 pub mod terms;
 pub mod whatis;
 
+/// Returns the emoji configured for the current server, falling back to a unicode emoji.
+pub async fn emoji(ctx: Context<'_>, kind: EmojiKind) -> String {
+  let data = ctx.data();
+  data.emojis.emoji(&data.db, ctx.guild_id(), kind).await
+}
+
+/// Refuses to show private output in reply to a text command, since text command replies can't be ephemeral and would be seen by everyone in the channel.
+///
+/// Returns `true` if the command was refused, in which case the member has already been asked to use the slash command instead.
+pub async fn refuse_private_text_command(ctx: Context<'_>, private: bool) -> Result<bool> {
+  if !private || matches!(ctx, poise::Context::Application(_)) {
+    return Ok(false);
+  }
+
+  ctx
+    .say(format!(
+      ":x: This would show private information, so please use `/{}` instead.",
+      ctx.command().qualified_name
+    ))
+    .await?;
+
+  Ok(true)
+}
+
+/// Sends a reply only the author can see. Slash command replies are ephemeral, and text command replies, which can't be, are sent by direct message instead.
+pub async fn send_private(ctx: Context<'_>, reply: CreateReply) -> Result<()> {
+  match ctx {
+    poise::Context::Application(_) => {
+      ctx.send(reply.ephemeral(true)).await?;
+    }
+    poise::Context::Prefix(_) => {
+      let mut message = serenity::CreateMessage::new()
+        .embeds(reply.embeds)
+        .add_files(reply.attachments);
+      if let Some(content) = reply.content {
+        message = message.content(content);
+      }
+      if let Some(allowed_mentions) = reply.allowed_mentions {
+        message = message.allowed_mentions(allowed_mentions);
+      }
+
+      ctx.author().direct_message(ctx, message).await?;
+    }
+  }
+
+  Ok(())
+}
+
 #[allow(clippy::large_enum_variant)]
 enum MessageType {
   TextOnly(String),
@@ -41,6 +99,7 @@ enum MessageType {
 /// This is useful because we don't always know whether the interaction has timed out or not,
 /// and we don't want to commit any changes if we can't inform the user of the result.
 /// If we could not commit the transaction but were able to send a message, we will edit the message to inform the user.
+/// Returns the sent message, so callers can follow up on it, such as by reacting.
 ///
 /// # Arguments
 /// ctx - The context of the interaction
@@ -53,12 +112,12 @@ enum MessageType {
 ///
 /// # Errors
 ///
-async fn commit_and_say(
-  ctx: Context<'_>,
+async fn commit_and_say<'a>(
+  ctx: Context<'a>,
   transaction: sqlx::Transaction<'_, sqlx::Postgres>,
   message: MessageType,
   ephemeral: bool,
-) -> Result<()> {
+) -> Result<poise::ReplyHandle<'a>> {
   let response = match message {
     MessageType::TextOnly(message) => {
       ctx
@@ -72,20 +131,20 @@ async fn commit_and_say(
     }
   };
 
-  match response {
-    Ok(sent_message) => {
-      match DatabaseHandler::commit_transaction(transaction).await {
-        Ok(()) => {}
-        Err(e) => {
-          let _ = sent_message.edit(ctx, CreateReply::default()
-            .content("<:mminfo:1194141918133768234> A fatal error occurred while trying to save your changes. Please contact staff for assistance.")
-            .ephemeral(true)).await;
-          return Err(anyhow::anyhow!("Could not send message: {e}"));
-        }
-      };
-    }
+  let sent_message = match response {
+    Ok(sent_message) => match DatabaseHandler::commit_transaction(transaction).await {
+      Ok(()) => sent_message,
+      Err(e) => {
+        let info = emoji(ctx, EmojiKind::Info).await;
+        let _ = sent_message.edit(ctx, CreateReply::default()
+          .content(format!("{info} A fatal error occurred while trying to save your changes. Please contact staff for assistance."))
+          .ephemeral(true)).await;
+        return Err(anyhow::anyhow!("Could not send message: {e}"));
+      }
+    },
     Err(e) => {
       DatabaseHandler::rollback_transaction(transaction).await?;
+      let info = emoji(ctx, EmojiKind::Info).await;
       // As it's very likely that when this happens the interaction has timed out,
       // we don't want to send a response to the interaction, but rather to the channel.
       // The alternative is that there is a second instance of the bot running, which we can detect by checking if the interaction has already been responded to.
@@ -96,7 +155,7 @@ async fn commit_and_say(
           if !has_sent_initial_response {
             let _ = ctx
               .channel_id()
-              .say(&ctx, "<:mminfo:1194141918133768234> An error may have occurred. If your command failed, please contact staff for assistance.")
+              .say(&ctx, format!("{info} An error may have occurred. If your command failed, please contact staff for assistance."))
               .await;
             info!("Issued rollback transaction error for slash command with no initial response.");
           }
@@ -104,7 +163,7 @@ async fn commit_and_say(
         poise::Context::Prefix(_) => {
           let _ = ctx
             .channel_id()
-            .say(&ctx, "<:mminfo:1194141918133768234> An error may have occurred. If your command failed, please contact staff for assistance.")
+            .say(&ctx, format!("{info} An error may have occurred. If your command failed, please contact staff for assistance."))
             .await;
           info!("Issued rollback transaction error for prefix command.");
         }
@@ -114,7 +173,7 @@ async fn commit_and_say(
     }
   };
 
-  Ok(())
+  Ok(sent_message)
 }
 
 pub async fn course_not_found(
@@ -0,0 +1,556 @@
+use crate::database::{DatabaseHandler, DiagnosticsQueryResult};
+use crate::Context;
+use anyhow::Result;
+use log::info;
+use poise::serenity_prelude::CreateAttachment;
+use poise::CreateReply;
+use std::time::Instant;
+
+/// Most rows included in a query result.
+const MAX_QUERY_ROWS: i64 = 1000;
+/// Queries taking longer than this are cancelled, so a bad query can't hold up the database.
+const QUERY_TIMEOUT_MS: u64 = 5000;
+/// Longer values are cut short, so one large column doesn't make the table unreadable.
+const MAX_CELL_LENGTH: usize = 80;
+/// Statements a query may start with. Each of these can be run as a subquery, which keeps queries to a single statement.
+const ALLOWED_STATEMENTS: [&str; 4] = ["select", "with", "values", "table"];
+/// Functions a query may call. Anything else is rejected, since functions such as `pg_terminate_backend`, `set_config`, `pg_sleep`, and `dblink` can act outside a read-only transaction.
+const ALLOWED_FUNCTIONS: &[&str] = &[
+  // Aggregate and window functions
+  "count",
+  "sum",
+  "avg",
+  "min",
+  "max",
+  "array_agg",
+  "string_agg",
+  "json_agg",
+  "jsonb_agg",
+  "bool_and",
+  "bool_or",
+  "every",
+  "stddev",
+  "variance",
+  "percentile_cont",
+  "percentile_disc",
+  "mode",
+  "row_number",
+  "rank",
+  "dense_rank",
+  "percent_rank",
+  "cume_dist",
+  "ntile",
+  "lag",
+  "lead",
+  "first_value",
+  "last_value",
+  // Conditional functions
+  "coalesce",
+  "nullif",
+  "greatest",
+  "least",
+  // Text functions
+  "lower",
+  "upper",
+  "length",
+  "char_length",
+  "substring",
+  "substr",
+  "trim",
+  "btrim",
+  "ltrim",
+  "rtrim",
+  "replace",
+  "concat",
+  "concat_ws",
+  "left",
+  "right",
+  "split_part",
+  "position",
+  "strpos",
+  "starts_with",
+  // Math functions
+  "round",
+  "floor",
+  "ceil",
+  "ceiling",
+  "abs",
+  "trunc",
+  "mod",
+  "power",
+  "sqrt",
+  // Date and time functions
+  "now",
+  "date_trunc",
+  "date_part",
+  "extract",
+  "age",
+  "to_char",
+  "to_date",
+  "to_timestamp",
+  "make_date",
+  "make_interval",
+  // Array and JSON functions
+  "unnest",
+  "generate_series",
+  "array_length",
+  "cardinality",
+  "json_build_object",
+  "jsonb_build_object",
+  "json_array_length",
+  "jsonb_array_length",
+  // Size functions
+  "pg_size_pretty",
+  "pg_relation_size",
+  "pg_total_relation_size",
+  "pg_database_size",
+];
+/// Keywords and type names that can be followed by an opening parenthesis without calling a function.
+const PARENTHESIZED_KEYWORDS: &[&str] = &[
+  "all",
+  "and",
+  "any",
+  "array",
+  "as",
+  "between",
+  "by",
+  "case",
+  "cast",
+  "distinct",
+  "else",
+  "except",
+  "exists",
+  "filter",
+  "from",
+  "group",
+  "having",
+  "ilike",
+  "in",
+  "intersect",
+  "is",
+  "join",
+  "lateral",
+  "like",
+  "limit",
+  "not",
+  "offset",
+  "on",
+  "or",
+  "over",
+  "row",
+  "select",
+  "some",
+  "then",
+  "union",
+  "using",
+  "values",
+  "when",
+  "where",
+  "with",
+  "within",
+  "char",
+  "character",
+  "decimal",
+  "interval",
+  "numeric",
+  "time",
+  "timestamp",
+  "timestamptz",
+  "varchar",
+];
+
+/// Commands for the bot owner
+///
+/// Diagnostics commands for debugging the bot in production. Only available to the bot owner.
+#[poise::command(
+  slash_command,
+  subcommands("query"),
+  subcommand_required,
+  owners_only,
+  category = "Secret",
+  hide_in_help,
+  default_member_permissions = "ADMINISTRATOR"
+)]
+#[allow(clippy::unused_async)]
+pub async fn owner(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Checks that a query is a single statement of an allowed kind, returning the reason it was rejected if not.
+fn check_query(query: &str) -> Option<String> {
+  let first_word = query
+    .split_whitespace()
+    .next()
+    .unwrap_or_default()
+    .to_lowercase();
+
+  if !ALLOWED_STATEMENTS.contains(&first_word.as_str()) {
+    return Some(format!(
+      "Queries must start with one of: {}.",
+      ALLOWED_STATEMENTS.join(", ").to_uppercase()
+    ));
+  }
+
+  if query.contains(';') {
+    return Some("Queries must be a single statement, without semicolons.".to_string());
+  }
+
+  let Some(called) = called_functions(query) else {
+    return Some("Queries can't contain comments or unterminated strings.".to_string());
+  };
+
+  if let Some(function) = called.iter().find(|name| {
+    !ALLOWED_FUNCTIONS.contains(&name.as_str()) && !PARENTHESIZED_KEYWORDS.contains(&name.as_str())
+  }) {
+    return Some(format!(
+      "Queries can't call `{function}`. Only common aggregate, text, math, date, array, and JSON functions are allowed."
+    ));
+  }
+
+  None
+}
+
+/// Names followed by an opening parenthesis in a query, outside of strings, which includes every function it calls.
+///
+/// Names are lowercased unless quoted, as Postgres does. Aliases with column lists, such as `AS q (x)`, aren't calls and are left out.
+/// Returns `None` if the query has a comment or an unterminated string, since either could hide a call.
+fn called_functions(query: &str) -> Option<Vec<String>> {
+  let chars: Vec<char> = query.chars().collect();
+  let mut called = Vec::new();
+  let mut before_last_name: Option<String> = None;
+  let mut last_name = None;
+  let mut i = 0;
+
+  // Skips a string starting at the quote at `start`, returning the index after it
+  let skip_string = |start: usize, backslash_escapes: bool| -> Option<usize> {
+    let mut i = start + 1;
+    loop {
+      match chars.get(i)? {
+        '\\' if backslash_escapes => i += 2,
+        '\'' if chars.get(i + 1) == Some(&'\'') => i += 2,
+        '\'' => return Some(i + 1),
+        _ => i += 1,
+      }
+    }
+  };
+
+  while let Some(&c) = chars.get(i) {
+    let mut name = None;
+
+    match c {
+      '-' if chars.get(i + 1) == Some(&'-') => return None,
+      '/' if chars.get(i + 1) == Some(&'*') => return None,
+      '\'' => i = skip_string(i, false)?,
+      '"' => {
+        let mut quoted = String::new();
+        i += 1;
+        loop {
+          match chars.get(i)? {
+            '"' if chars.get(i + 1) == Some(&'"') => {
+              quoted.push('"');
+              i += 2;
+            }
+            '"' => break,
+            c => {
+              quoted.push(*c);
+              i += 1;
+            }
+          }
+        }
+        i += 1;
+        name = Some(quoted);
+      }
+      '$' => {
+        // Dollar-quoted strings, such as $$text$$ or $tag$text$tag$
+        let tag_end = (i + 1..chars.len())
+          .take_while(|&j| chars[j] == '$' || chars[j].is_alphanumeric() || chars[j] == '_')
+          .find(|&j| chars[j] == '$');
+        match tag_end {
+          Some(tag_end) if !chars[i + 1].is_ascii_digit() => {
+            let tag = &chars[i..=tag_end];
+            let body_start = tag_end + 1;
+            let close = (body_start..=chars.len().saturating_sub(tag.len()))
+              .find(|&j| chars[j..j + tag.len()] == *tag)?;
+            i = close + tag.len();
+          }
+          _ => i += 1,
+        }
+      }
+      '(' => {
+        let is_alias = before_last_name
+          .as_deref()
+          .is_some_and(|word| matches!(word, "as" | "with" | "recursive"));
+        if let Some(name) = last_name.take().filter(|_| !is_alias) {
+          called.push(name);
+        }
+        i += 1;
+      }
+      c if c.is_alphabetic() || c == '_' => {
+        let start = i;
+        while chars
+          .get(i)
+          .is_some_and(|&c| c.is_alphanumeric() || c == '_' || c == '$')
+        {
+          i += 1;
+        }
+        let word = chars[start..i].iter().collect::<String>().to_lowercase();
+
+        // Escape strings, such as E'it\'s', allow quotes escaped with a backslash
+        if word == "e" && chars.get(i) == Some(&'\'') {
+          i = skip_string(i, true)?;
+        } else {
+          name = Some(word);
+        }
+      }
+      c if c.is_whitespace() => {
+        i += 1;
+        continue;
+      }
+      _ => i += 1,
+    }
+
+    before_last_name = last_name;
+    last_name = name;
+  }
+
+  Some(called)
+}
+
+/// Cuts a value to [`MAX_CELL_LENGTH`] characters and keeps it on one line.
+fn format_cell(value: Option<&str>) -> String {
+  let value = value.unwrap_or("NULL").replace(['\n', '\r', '\t'], " ");
+
+  if value.chars().count() > MAX_CELL_LENGTH {
+    let truncated: String = value.chars().take(MAX_CELL_LENGTH - 1).collect();
+    format!("{truncated}…")
+  } else {
+    value
+  }
+}
+
+/// Lays out query results as a plain text table.
+fn format_table(result: &DiagnosticsQueryResult) -> String {
+  let header: Vec<String> = result
+    .columns
+    .iter()
+    .map(|column| format_cell(Some(column)))
+    .collect();
+  let rows: Vec<Vec<String>> = result
+    .rows
+    .iter()
+    .map(|row| {
+      row
+        .iter()
+        .map(|value| format_cell(value.as_deref()))
+        .collect()
+    })
+    .collect();
+
+  let widths: Vec<usize> = (0..header.len())
+    .map(|i| {
+      std::iter::once(&header)
+        .chain(&rows)
+        .filter_map(|row| row.get(i))
+        .map(|cell| cell.chars().count())
+        .max()
+        .unwrap_or_default()
+    })
+    .collect();
+
+  let format_row = |row: &[String]| {
+    row
+      .iter()
+      .zip(&widths)
+      .map(|(cell, width)| format!("{cell:<width$}"))
+      .collect::<Vec<String>>()
+      .join(" | ")
+      .trim_end()
+      .to_string()
+  };
+
+  let separator = widths
+    .iter()
+    .map(|width| "-".repeat(*width))
+    .collect::<Vec<String>>()
+    .join("-+-");
+
+  std::iter::once(format_row(&header))
+    .chain(std::iter::once(separator))
+    .chain(rows.iter().map(|row| format_row(row)))
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+/// Run a read-only SQL query
+///
+/// Runs a single read-only query against the database and attaches the results as a table. Queries run in a read-only transaction, time out after 5 seconds, and return at most 1,000 rows. Only common aggregate, text, math, date, array, and JSON functions can be called.
+#[poise::command(slash_command)]
+pub async fn query(
+  ctx: Context<'_>,
+  #[description = "A single SELECT, WITH, VALUES, or TABLE statement"] sql: String,
+) -> Result<()> {
+  let data = ctx.data();
+
+  let sql = sql.trim().trim_end_matches(';').trim().to_string();
+
+  if let Some(reason) = check_query(&sql) {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(":x: {reason}"))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  ctx.defer_ephemeral().await?;
+
+  info!(
+    "Diagnostics query run by {} ({}): {sql}",
+    ctx.author().name,
+    ctx.author().id
+  );
+
+  let started_at = Instant::now();
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let result = DatabaseHandler::run_diagnostics_query(
+    &mut transaction,
+    &sql,
+    MAX_QUERY_ROWS,
+    QUERY_TIMEOUT_MS,
+  )
+  .await;
+  DatabaseHandler::rollback_transaction(transaction).await?;
+  let elapsed = started_at.elapsed();
+
+  let result = match result {
+    Ok(result) => result,
+    Err(e) => {
+      ctx
+        .send(
+          CreateReply::default()
+            .content(format!(":x: The query failed: {e}"))
+            .ephemeral(true),
+        )
+        .await?;
+      return Ok(());
+    }
+  };
+
+  if result.rows.is_empty() {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            "The query returned no rows in {}ms.",
+            elapsed.as_millis()
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let summary = if result.truncated {
+    format!(
+      "Showing the first {} rows. The query took {}ms.",
+      result.rows.len(),
+      elapsed.as_millis()
+    )
+  } else {
+    format!(
+      "The query returned {} rows in {}ms.",
+      result.rows.len(),
+      elapsed.as_millis()
+    )
+  };
+
+  ctx
+    .send(
+      CreateReply::default()
+        .content(summary)
+        .attachment(CreateAttachment::bytes(
+          format_table(&result).into_bytes(),
+          "query.txt",
+        ))
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn allows_common_functions() {
+    assert_eq!(
+      check_query(
+        "SELECT user_id, COUNT(*), SUM(meditation_minutes) FROM meditation GROUP BY user_id"
+      ),
+      None
+    );
+    assert_eq!(
+      check_query("SELECT date_trunc('day', occurred_at) AS day, round(avg(meditation_minutes)) FROM meditation GROUP BY 1"),
+      None
+    );
+    assert_eq!(
+      check_query("WITH recent AS (SELECT * FROM meditation WHERE occurred_at > now() - interval '1 day') SELECT count(*) FROM recent"),
+      None
+    );
+    assert_eq!(
+      check_query("SELECT x::numeric(10, 2) FROM (VALUES (1), (2)) AS q (x) WHERE x IN (1, 2)"),
+      None
+    );
+  }
+
+  #[test]
+  fn rejects_other_functions() {
+    for query in [
+      "SELECT pg_terminate_backend(123)",
+      "SELECT set_config('default_transaction_read_only', 'off', false)",
+      "SELECT pg_sleep (100)",
+      "SELECT * FROM dblink('host=example', 'SELECT 1') AS t (x int)",
+      "SELECT lo_import('/etc/passwd')",
+      "SELECT pg_catalog.pg_sleep(1)",
+      "SELECT \"pg_sleep\"(1)",
+      "SELECT 1 AS x, pg_sleep(1)",
+      "SELECT count(*) FROM meditation WHERE pg_sleep(1) IS NOT NULL",
+    ] {
+      assert!(check_query(query).is_some(), "{query}");
+    }
+  }
+
+  #[test]
+  fn ignores_names_in_strings() {
+    assert_eq!(check_query("SELECT 'pg_sleep(1)' AS text"), None);
+    assert_eq!(check_query("SELECT 'it''s pg_sleep(1)' AS text"), None);
+    assert_eq!(check_query("SELECT E'it\\'s pg_sleep(1)' AS text"), None);
+    assert_eq!(check_query("SELECT $$pg_sleep(1)$$ AS text"), None);
+    assert_eq!(check_query("SELECT $tag$pg_sleep(1)$tag$ AS text"), None);
+  }
+
+  #[test]
+  fn finds_calls_after_strings() {
+    assert!(check_query("SELECT 'a\\', pg_sleep(1), 'b'").is_some());
+    assert!(check_query("SELECT E'a\\'', pg_sleep(1), 'b'").is_some());
+    assert!(check_query("SELECT $$a$$, pg_sleep(1)").is_some());
+  }
+
+  #[test]
+  fn rejects_comments_and_unterminated_strings() {
+    assert!(check_query("SELECT pg_sleep/**/(1)").is_some());
+    assert!(check_query("SELECT 1 -- comment").is_some());
+    assert!(check_query("SELECT 'unterminated").is_some());
+    assert!(check_query("SELECT $$unterminated").is_some());
+  }
+
+  #[test]
+  fn rejects_other_statements() {
+    assert!(check_query("DELETE FROM meditation").is_some());
+    assert!(check_query("SELECT 1; DELETE FROM meditation").is_some());
+  }
+}
@@ -1,5 +1,8 @@
-use crate::config::{BloomBotEmbed, CHANNELS, ROLES};
+use crate::config::{
+  BloomBotEmbed, CHALLENGE_GOAL_MINUTES, CHALLENGE_GOAL_SESSIONS, CHANNELS, ROLES,
+};
 use crate::database::DatabaseHandler;
+use crate::webhooks::{self, WebhookEvent};
 use crate::Context;
 use anyhow::Result;
 use chrono::Datelike;
@@ -23,12 +26,25 @@ pub enum Months {
   December,
 }
 
+#[derive(poise::ChoiceParameter)]
+pub enum ResumeAction {
+  #[name = "continue"]
+  Continue,
+  #[name = "roll back"]
+  RollBack,
+}
+
+/// Announces the winner and offers them the reserved key, recording each step on the draw.
+///
+/// If the draw was interrupted after the announcement was posted, pass `announced` to skip straight to the offer.
 async fn finalize_winner(
   reserved_key: String,
   ctx: Context<'_>,
   winner: serenity::Member,
   minutes: i64,
   selected_date: chrono::DateTime<chrono::Utc>,
+  draw_id: &str,
+  announced: bool,
 ) -> Result<()> {
   let now = chrono::Utc::now();
   let guild_name = ctx
@@ -70,9 +86,24 @@ async fn finalize_winner(
   let announcement_channel = serenity::ChannelId::new(CHANNELS.announcement);
   let dm_channel = winner.user.create_dm_channel(ctx).await?;
 
-  announcement_channel
-    .send_message(ctx, CreateMessage::new().embed(announcement_embed))
-    .await?;
+  if !announced {
+    announcement_channel
+      .send_message(ctx, CreateMessage::new().embed(announcement_embed))
+      .await?;
+
+    webhooks::notify(
+      &ctx.data().db,
+      ctx.guild_id().unwrap(),
+      WebhookEvent::ChallengeCompleted {
+        challenge: selected_date.format("%B %Y").to_string(),
+        winner: winner.user.id,
+      },
+    );
+
+    let mut conn = ctx.data().db.get_connection_with_retry(5).await?;
+    DatabaseHandler::update_prize_draw(&mut conn, draw_id, "announced", None).await?;
+    drop(conn);
+  }
 
   let ctx_id = ctx.id();
   let redeem_id = format!("{ctx_id}redeem");
@@ -95,11 +126,26 @@ async fn finalize_winner(
     .await
   else {
     ctx
-      .send(CreateReply::default().content(":x: Could not send DM to member. Please run `/usekey` and copy a key manually if they want one.\n\n**No key has been used.**"))
+      .send(CreateReply::default().content(":x: Could not send DM to member. Please use `/pickwinner resume` to try again once they can receive DMs, or to roll back the draw and return the key to the pool.\n\n**No key has been used.**"))
       .await?;
     return Ok(());
   };
 
+  // Track the award so unclaimed prizes get a reminder and expire if the bot restarts
+  let mut conn = ctx.data().db.get_connection_with_retry(5).await?;
+  let award_id = DatabaseHandler::add_prize_award(
+    &mut conn,
+    &ctx.guild_id().unwrap(),
+    &winner.user.id,
+    &reserved_key,
+    &selected_date.format("%B %Y").to_string(),
+    &dm_channel.id,
+    &dm_message.id,
+  )
+  .await?;
+  DatabaseHandler::update_prize_draw(&mut conn, draw_id, "dm_sent", Some(&award_id)).await?;
+  drop(conn);
+
   ctx
     .send(CreateReply::default().content(format!(
       ":white_check_mark: Sent DM to {} and sent announcement!",
@@ -120,6 +166,8 @@ async fn finalize_winner(
     if press.data.custom_id == redeem_id {
       let mut conn = ctx.data().db.get_connection_with_retry(5).await?;
       DatabaseHandler::mark_key_used(&mut conn, &reserved_key).await?;
+      DatabaseHandler::resolve_prize_award(&mut conn, &award_id, "accepted").await?;
+      DatabaseHandler::update_prize_draw(&mut conn, draw_id, "confirmed", None).await?;
       let hyperlink = format!(
         "[Redeem your key](https://store.steampowered.com/account/registerkey?key={reserved_key})"
       );
@@ -165,6 +213,8 @@ async fn finalize_winner(
     } else if press.data.custom_id == cancel_id {
       let mut conn = ctx.data().db.get_connection_with_retry(5).await?;
       DatabaseHandler::unreserve_key(&mut conn, &reserved_key).await?;
+      DatabaseHandler::resolve_prize_award(&mut conn, &award_id, "declined").await?;
+      DatabaseHandler::update_prize_draw(&mut conn, draw_id, "confirmed", None).await?;
 
       dm_message
         .edit(ctx, EditMessage::new().components(Vec::new()))
@@ -202,6 +252,12 @@ async fn finalize_winner(
     continue;
   }
 
+  let mut conn = ctx.data().db.get_connection_with_retry(5).await?;
+  DatabaseHandler::unreserve_key(&mut conn, &reserved_key).await?;
+  DatabaseHandler::resolve_prize_award(&mut conn, &award_id, "expired").await?;
+  DatabaseHandler::update_prize_draw(&mut conn, draw_id, "confirmed", None).await?;
+  drop(conn);
+
   let timeout_embed = BloomBotEmbed::new()
     .title("**Congratulations on winning the giveaway!** 🥳")
     .description("You've won a key for [Playne: The Meditation Game](<https://store.steampowered.com/app/865540/PLAYNE__The_Meditation_Game/>) on Steam!\n\n**Would you like to redeem your key? Please contact server staff and we'll get one to you!**")
@@ -235,26 +291,37 @@ async fn finalize_winner(
   Ok(())
 }
 
-/// Pick a winner for the monthly challenge
+/// Commands for the monthly challenge draw
 ///
-/// Picks the winner for the monthly meditation challenge and allows them to claim an unused Playne key.
-///
-/// Finds a user who meets the following criteria (defaults):
-/// - Has the `@meditation challengers` role
-/// - Has tracked at least 30 minutes during the specified month
-/// - Has at least 8 sessions during the specified month
-/// - Has not received a Playne key previously
-/// If multiple users meet this criteria, one is chosen at random.
+/// Commands to pick the winner for the monthly meditation challenge, or to resume a draw that was interrupted before the winner received their key offer.
 #[poise::command(
   slash_command,
   required_permissions = "ADMINISTRATOR",
   default_member_permissions = "ADMINISTRATOR",
   category = "Admin Commands",
   rename = "pickwinner",
+  subcommands("draw", "resume"),
+  subcommand_required,
   //hide_in_help,
   guild_only
 )]
-pub async fn pick_winner(
+#[allow(clippy::unused_async)]
+pub async fn pick_winner(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Pick a winner for the monthly challenge
+///
+/// Picks the winner for the monthly meditation challenge and allows them to claim an unused Playne key.
+///
+/// Finds a user who meets the following criteria (defaults):
+/// - Has the `@meditation challengers` role
+/// - Has tracked at least 30 minutes during the specified month
+/// - Has at least 8 sessions during the specified month
+/// - Has not received a Playne key previously
+/// If multiple users meet this criteria, one is chosen at random.
+#[poise::command(slash_command)]
+pub async fn draw(
   ctx: Context<'_>,
   #[description = "The year to pick a winner for (defaults to this year in UTC)"] year: Option<i32>,
   #[description = "The month to pick a winner for (defaults to this month in UTC)"] month: Option<
@@ -275,6 +342,20 @@ pub async fn pick_winner(
 
   let mut transaction = data.db.start_transaction_with_retry(5).await?;
 
+  if DatabaseHandler::get_interrupted_prize_draw(&mut transaction, &guild_id)
+    .await?
+    .is_some()
+  {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: A previous draw was interrupted. Please use `/pickwinner resume` to continue or roll it back first.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
   if !DatabaseHandler::unused_key_exists(&mut transaction, &guild_id).await? {
     ctx
       .send(
@@ -329,6 +410,8 @@ pub async fn pick_winner(
   let start_datetime = chrono::NaiveDateTime::new(start_date, time).and_utc();
   let end_datetime = chrono::NaiveDateTime::new(end_date, time).and_utc();
 
+  let limits = DatabaseHandler::get_session_limits(&mut transaction, &guild_id).await?;
+
   let mut conn = data.db.get_connection_with_retry(5).await?;
   // Since the stream is async, we can't use the same connection for the transaction
   let mut database_winner_candidates =
@@ -363,6 +446,7 @@ pub async fn pick_winner(
       &member.user.id,
       start_datetime,
       end_datetime,
+      limits.daily_cap_minutes,
     )
     .await?;
 
@@ -376,8 +460,8 @@ pub async fn pick_winner(
     .await?;
 
     // Make sure user has at least 30 minutes and 8 sessions during the challenge period
-    if challenge_minutes < minimum_minutes.unwrap_or(30)
-      || challenge_count < minimum_count.unwrap_or(8)
+    if challenge_minutes < minimum_minutes.unwrap_or(CHALLENGE_GOAL_MINUTES)
+      || challenge_count < minimum_count.unwrap_or(CHALLENGE_GOAL_SESSIONS.unsigned_abs())
     {
       continue;
     }
@@ -391,9 +475,30 @@ pub async fn pick_winner(
       return Ok(());
     };
 
+    // The draw is recorded with the key reservation, so an interrupted draw can't leave a key reserved without a record
+    let draw_id = DatabaseHandler::add_prize_draw(
+      &mut transaction,
+      &guild_id,
+      &member.user.id,
+      &start_datetime.format("%B %Y").to_string(),
+      start_datetime,
+      challenge_minutes,
+      &reserved_key,
+    )
+    .await?;
+
     DatabaseHandler::commit_transaction(transaction).await?;
 
-    finalize_winner(reserved_key, ctx, member, challenge_minutes, start_datetime).await?;
+    finalize_winner(
+      reserved_key,
+      ctx,
+      member,
+      challenge_minutes,
+      start_datetime,
+      &draw_id,
+      false,
+    )
+    .await?;
 
     return Ok(());
   }
@@ -408,3 +513,87 @@ pub async fn pick_winner(
 
   Ok(())
 }
+
+/// Resume an interrupted draw
+///
+/// Continues a draw that was interrupted before the winner received their key offer, such as when they could not be sent a DM. Alternatively, rolls the draw back and returns the reserved key to the pool.
+///
+/// Draws where the offer was already sent are tracked with `/keys awards` instead.
+#[poise::command(slash_command)]
+pub async fn resume(
+  ctx: Context<'_>,
+  #[description = "Whether to continue or roll back the draw (defaults to continue)"]
+  action: Option<ResumeAction>,
+) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let data = ctx.data();
+
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let draw = DatabaseHandler::get_interrupted_prize_draw(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let Some(draw) = draw else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: There is no interrupted draw to resume.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let announced = draw.state == "announced";
+
+  if let Some(ResumeAction::RollBack) = action {
+    let mut conn = data.db.get_connection_with_retry(5).await?;
+    DatabaseHandler::unreserve_key(&mut conn, &draw.steam_key).await?;
+    DatabaseHandler::update_prize_draw(&mut conn, &draw.id, "rolled_back", None).await?;
+    drop(conn);
+
+    let note = if announced {
+      " The winner announcement was already posted, so please remove it if needed."
+    } else {
+      ""
+    };
+
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":white_check_mark: The draw for <@{}> has been rolled back and the key returned to the pool.{note}",
+            draw.user_id
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let Ok(member) = guild_id.member(ctx, draw.user_id).await else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: The winner is no longer in the server. Please roll back the draw instead.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  finalize_winner(
+    draw.steam_key,
+    ctx,
+    member,
+    draw.minutes,
+    draw.challenge_start,
+    &draw.id,
+    announced,
+  )
+  .await?;
+
+  Ok(())
+}
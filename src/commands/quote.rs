@@ -1,18 +1,30 @@
+use crate::commands::helpers::cooldown::member_cooldown;
+use crate::commands::helpers::rate_limit::throttle_images;
 use crate::config::BloomBotEmbed;
 use crate::database::DatabaseHandler;
+use crate::images::{QuoteCardBackground, QuoteCardDrawer};
 use crate::Context;
 use anyhow::Result;
+use poise::serenity_prelude::CreateAttachment;
+use poise::ChoiceParameter;
+use std::time::Duration;
 
 /// Get a meditation/mindfulness quote
 ///
-/// Get a random meditation/mindfulness quote.
-#[poise::command(
-  slash_command,
-  category = "Informational",
-  member_cooldown = 300,
-  guild_only
-)]
-pub async fn quote(ctx: Context<'_>) -> Result<()> {
+/// Get a random meditation/mindfulness quote. Optionally render the quote as an image card, using the background preset configured for the server.
+#[poise::command(slash_command, category = "Informational", guild_only)]
+pub async fn quote(
+  ctx: Context<'_>,
+  #[description = "Render the quote as an image card (Defaults to false)"] image: Option<bool>,
+) -> Result<()> {
+  if !member_cooldown(ctx, "quote", Duration::from_secs(300)).await? {
+    return Ok(());
+  }
+
+  if image.unwrap_or(false) && !throttle_images(ctx).await? {
+    return Ok(());
+  }
+
   let data = ctx.data();
 
   // We unwrap here, because we know that the command is guild-only.
@@ -24,12 +36,30 @@ pub async fn quote(ctx: Context<'_>) -> Result<()> {
       ctx.say("No quotes found.").await?;
     }
     Some(quote) => {
+      DatabaseHandler::record_quote_shown(&mut transaction, &quote.id).await?;
+      let author = quote.author.unwrap_or("Anonymous".to_string());
+
+      if image.unwrap_or(false) {
+        let background = DatabaseHandler::get_quote_card_background(&mut transaction, &guild_id)
+          .await?
+          .and_then(|name| QuoteCardBackground::from_name(&name))
+          .unwrap_or_default();
+        DatabaseHandler::commit_transaction(transaction).await?;
+
+        let card = QuoteCardDrawer::new()?.draw(quote.quote.as_str(), &author, background)?;
+        let file_path = card.get_file_path();
+
+        ctx
+          .send(poise::CreateReply::default().attachment(CreateAttachment::path(&file_path).await?))
+          .await?;
+
+        return Ok(());
+      }
+
+      DatabaseHandler::commit_transaction(transaction).await?;
+
       let embed = BloomBotEmbed::new()
-        .description(format!(
-          "{}\n\n\\― {}",
-          quote.quote.as_str(),
-          quote.author.unwrap_or("Anonymous".to_string())
-        ))
+        .description(format!("{}\n\n\\― {}", quote.quote.as_str(), author))
         .clone();
 
       ctx
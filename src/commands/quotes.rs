@@ -1,22 +1,25 @@
+use crate::commands::customize::OnOff;
 use crate::commands::{commit_and_say, MessageType};
-use crate::database::DatabaseHandler;
+use crate::config::BloomBotEmbed;
+use crate::database::{DatabaseHandler, QuoteUsage};
+use crate::images::QuoteCardBackground;
 use crate::pagination::{PageRowRef, Pagination};
 use crate::{Context, Data as AppData, Error as AppError};
 use anyhow::Result;
 use poise::serenity_prelude::{self as serenity, builder::*};
-use poise::{CreateReply, Modal};
+use poise::{ChoiceParameter, CreateReply, Modal};
 
 #[derive(Debug, Modal)]
 #[name = "Add a new quote"]
-struct AddQuoteModal {
+pub(crate) struct AddQuoteModal {
   #[name = "Quote text"]
   #[placeholder = "Input quote text here"]
   #[paragraph]
   #[max_length = 300]
-  quote: String,
+  pub(crate) quote: String,
   #[name = "Author's name"]
   #[placeholder = "Defaults to \"Anonymous\""]
-  author: Option<String>,
+  pub(crate) author: Option<String>,
 }
 
 #[derive(Debug, Modal)]
@@ -32,7 +35,7 @@ struct EditQuoteModal {
 
 /// Commands for managing quotes
 ///
-/// Commands to list, add, edit, or remove quotes.
+/// Commands to list, add, edit, or remove quotes, to see how often quotes are shown, to set the background used for quote image cards, or to choose whether quotes are added to `/add` confirmations by default.
 ///
 /// These quotes are used both for the `/quote` command and for motivational messages when a user runs `/add`.
 ///
@@ -42,7 +45,7 @@ struct EditQuoteModal {
   required_permissions = "MANAGE_ROLES",
   default_member_permissions = "MANAGE_ROLES",
   category = "Moderator Commands",
-  subcommands("list", "add", "edit", "remove"),
+  subcommands("list", "add", "edit", "remove", "background", "confirmations", "stats"),
   subcommand_required,
   //hide_in_help,
   guild_only
@@ -62,19 +65,40 @@ pub async fn add(ctx: poise::ApplicationContext<'_, AppData, AppError>) -> Resul
   let quote_data = AddQuoteModal::execute(ctx).await?;
 
   if let Some(quote_data) = quote_data {
-    let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+    save_quote(ctx, quote_data.quote.as_str(), quote_data.author.as_deref()).await?;
+  } else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: No data was provided.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
 
-    // We unwrap here, because we know that the command is guild-only.
-    let guild_id = ctx.guild_id().unwrap();
+  Ok(())
+}
 
-    DatabaseHandler::add_quote(
-      &mut transaction,
-      &guild_id,
-      quote_data.quote.as_str(),
-      quote_data.author.as_deref(),
-    )
-    .await?;
+/// Adds a quote, first checking for a similar existing quote.
+///
+/// When a likely duplicate is found, it is shown to the user, who can choose to add the quote anyway.
+pub(crate) async fn save_quote(
+  ctx: poise::ApplicationContext<'_, AppData, AppError>,
+  quote: &str,
+  author: Option<&str>,
+) -> Result<()> {
+  let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
 
+  let existing =
+    DatabaseHandler::get_similar_quote(&mut transaction, &guild_id, quote, 0.6).await?;
+
+  DatabaseHandler::add_quote(&mut transaction, &guild_id, quote, author).await?;
+
+  let Some(existing) = existing else {
     commit_and_say(
       poise::Context::Application(ctx),
       transaction,
@@ -82,17 +106,88 @@ pub async fn add(ctx: poise::ApplicationContext<'_, AppData, AppError>) -> Resul
       true,
     )
     .await?;
-  } else {
-    ctx
-      .send(
-        CreateReply::default()
-          .content(":x: No data was provided.")
-          .ephemeral(true),
+
+    return Ok(());
+  };
+
+  let ctx_id = ctx.id();
+
+  let confirm_id = format!("{ctx_id}confirm");
+  let cancel_id = format!("{ctx_id}cancel");
+
+  let check = ctx
+    .send(
+      CreateReply::default()
+        .content(format!(
+          "This looks like a quote that already exists (ID `{}`):\n> {}\n— {}\n\nDo you want to add it anyway?",
+          existing.id,
+          existing.quote.replace('\n', "\n> "),
+          existing.author.as_deref().unwrap_or("Anonymous"),
+        ))
+        .ephemeral(true)
+        .components(vec![CreateActionRow::Buttons(vec![
+          CreateButton::new(confirm_id.clone())
+            .label("Add Anyway")
+            .style(serenity::ButtonStyle::Success),
+          CreateButton::new(cancel_id.clone())
+            .label("Cancel")
+            .style(serenity::ButtonStyle::Danger),
+        ])]),
+    )
+    .await?;
+
+  // Loop through incoming interactions with the buttons
+  while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+    // We defined our button IDs to start with `ctx_id`. If they don't, some other command's
+    // button was pressed
+    .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+    // Timeout when no button has been pressed in one minute
+    .timeout(std::time::Duration::from_secs(60))
+    .await
+  {
+    if press.data.custom_id != confirm_id && press.data.custom_id != cancel_id {
+      // This is an unrelated button interaction
+      continue;
+    }
+
+    let confirmed = press.data.custom_id == confirm_id;
+
+    press
+      .create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+          CreateInteractionResponseMessage::new()
+            .content(if confirmed {
+              ":white_check_mark: Quote has been added."
+            } else {
+              "Cancelled."
+            })
+            .components(Vec::new()),
+        ),
       )
       .await?;
+
+    if confirmed {
+      DatabaseHandler::commit_transaction(transaction).await?;
+    } else {
+      DatabaseHandler::rollback_transaction(transaction).await?;
+    }
+
     return Ok(());
   }
 
+  // This happens when the user didn't press any button for 60 seconds
+  check
+    .edit(
+      poise::Context::Application(ctx),
+      CreateReply::default()
+        .content(":x: Timed out. The quote was not added.")
+        .components(Vec::new()),
+    )
+    .await?;
+
+  DatabaseHandler::rollback_transaction(transaction).await?;
+
   Ok(())
 }
 
@@ -202,6 +297,139 @@ pub async fn remove(
   Ok(())
 }
 
+/// Set the background for quote image cards
+///
+/// Sets the background preset used when `/quote` renders a quote as an image card.
+#[poise::command(slash_command)]
+pub async fn background(
+  ctx: Context<'_>,
+  #[description = "The background preset to use"] preset: QuoteCardBackground,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::set_quote_card_background(&mut transaction, &guild_id, preset.name()).await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Quote card background has been set to **{}**.",
+      preset.name()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+/// Choose whether add confirmations include a quote
+///
+/// Chooses whether `/add` confirmations include a random quote for members who haven't chosen for themselves with `/customize quotes`. Quotes are on by default.
+#[poise::command(slash_command)]
+pub async fn confirmations(
+  ctx: Context<'_>,
+  #[description = "Include quotes in add confirmations by default"] quotes: OnOff,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let add_quotes = matches!(quotes, OnOff::On);
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::set_add_quotes_default(&mut transaction, &guild_id, add_quotes).await?;
+
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Quotes in add confirmations turned **{}** by default. Members can still choose for themselves with `/customize quotes`.",
+      quotes.name()
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
+
+fn format_quote_usage<'a>(quotes: impl Iterator<Item = &'a QuoteUsage>) -> String {
+  quotes
+    .map(|quote| {
+      let snippet: String = quote.quote.chars().take(60).collect();
+      let ellipsis = if quote.quote.chars().count() > 60 {
+        "…"
+      } else {
+        ""
+      };
+      format!(
+        "`{}` **{}×** {snippet}{ellipsis}",
+        quote.id, quote.times_shown
+      )
+    })
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+/// Show how often quotes are shown
+///
+/// Lists the most and least shown quotes, counting quotes shown by `/quote` and after `/add`. Useful for deciding which quotes to prune or rotate.
+#[poise::command(slash_command)]
+pub async fn stats(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let usage = DatabaseHandler::get_quote_usage(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  if usage.is_empty() {
+    ctx
+      .send(
+        CreateReply::default()
+          .content("No quotes found.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let total_shown: i64 = usage.iter().map(|quote| i64::from(quote.times_shown)).sum();
+  let never_shown = usage.iter().filter(|quote| quote.times_shown == 0).count();
+  let shown = usage.len().min(5);
+
+  let embed = BloomBotEmbed::new()
+    .title("Quote Usage")
+    .description(format!(
+      "**{}** quotes have been shown **{total_shown}** times in total. **{never_shown}** have never been shown.",
+      usage.len()
+    ))
+    .field(
+      "Most Shown",
+      format_quote_usage(usage.iter().take(shown)),
+      false,
+    )
+    .field(
+      "Least Shown",
+      format_quote_usage(usage.iter().rev().take(shown)),
+      false,
+    );
+
+  ctx
+    .send(CreateReply::default().embed(embed).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
+
 /// List all quotes in the database
 ///
 /// Lists all quotes in the database.
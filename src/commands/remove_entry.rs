@@ -1,6 +1,7 @@
 use crate::commands::{commit_and_say, MessageType};
 use crate::config::{BloomBotEmbed, CHANNELS};
 use crate::database::DatabaseHandler;
+use crate::retention::TRASH_RETENTION_DAYS;
 use crate::Context;
 use anyhow::Result;
 use poise::serenity_prelude::{self as serenity, CreateEmbedFooter, CreateMessage};
@@ -37,12 +38,20 @@ pub async fn remove_entry(
     return Ok(());
   }
 
-  DatabaseHandler::delete_meditation_entry(&mut transaction, id.as_str()).await?;
+  let batch_id = DatabaseHandler::delete_meditation_entry(
+    &mut transaction,
+    id.as_str(),
+    &ctx.author().id,
+    "removed",
+  )
+  .await?;
 
   commit_and_say(
     ctx,
     transaction,
-    MessageType::TextOnly(":white_check_mark: Entry has been removed.".to_string()),
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Entry has been removed. If this was a mistake, a moderator can restore it within {TRASH_RETENTION_DAYS} days."
+    )),
     true,
   )
   .await?;
@@ -50,7 +59,7 @@ pub async fn remove_entry(
   let log_embed = BloomBotEmbed::new()
     .title("Meditation Entry Removed")
     .description(format!(
-      "**User**: {}\n**ID**: {}\n**Date**: {}\n**Time**: {} minute(s)",
+      "**User**: {}\n**ID**: {}\n**Date**: {}\n**Time**: {} minute(s)\n**Trash batch**: `{batch_id}`",
       ctx.author(),
       entry.id,
       entry.occurred_at.format("%B %d, %Y"),
@@ -0,0 +1,126 @@
+use crate::commands::add::{add_session, Privacy};
+use crate::config::{EntrySource, SIT_MAX_MINUTES};
+use crate::database::DatabaseHandler;
+use crate::Context;
+use anyhow::Result;
+use poise::CreateReply;
+
+/// Time a session yourself
+///
+/// Start a session when you sit down to meditate, and stop it when you're done. The time in between is added to your meditation time, just like with `/add`.
+///
+/// Sessions in progress are saved, so they keep running even if the bot restarts.
+#[poise::command(
+  slash_command,
+  category = "Meditation Tracking",
+  subcommands("start", "stop"),
+  subcommand_required,
+  guild_only
+)]
+#[allow(clippy::unused_async)]
+pub async fn sit(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Start a session
+///
+/// Starts timing a meditation session. Use `/sit stop` when you're done.
+#[poise::command(slash_command)]
+pub async fn start(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+  let user_id = ctx.author().id;
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let started = DatabaseHandler::start_open_session(&mut transaction, &guild_id, &user_id).await?;
+
+  if !started {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: You already have a session in progress. Use `/sit stop` to end it.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  ctx
+    .send(
+      CreateReply::default()
+        .content(":white_check_mark: Your session has started. Use `/sit stop` when you're done. Enjoy your practice!")
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Stop a session
+///
+/// Stops your session in progress and adds the time to your meditation time.
+#[poise::command(slash_command)]
+pub async fn stop(
+  ctx: Context<'_>,
+  #[description = "Set visibility of response (Defaults to public)"] privacy: Option<Privacy>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+  let user_id = ctx.author().id;
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let started_at = DatabaseHandler::end_open_session(&mut transaction, &guild_id, &user_id).await?;
+
+  let Some(started_at) = started_at else {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: You don't have a session in progress. Use `/sit start` to begin one.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  // The session is closed whether or not it can be logged, so a forgotten session doesn't linger
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  let elapsed = (chrono::Utc::now() - started_at).num_minutes();
+
+  if elapsed > SIT_MAX_MINUTES {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(format!(
+            ":x: Your session ran for more than {} hours, so it may not have been stopped. It has been ended without adding any time. Please use `/add` to add the time you actually meditated.",
+            SIT_MAX_MINUTES / 60
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let Ok(minutes) = i32::try_from(elapsed) else {
+    return Ok(());
+  };
+
+  if minutes < 1 {
+    ctx
+      .send(
+        CreateReply::default()
+          .content(":x: Your session was shorter than a minute, so no time was added.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  add_session(ctx, minutes, None, None, privacy, EntrySource::Timer, false).await
+}
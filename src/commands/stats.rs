@@ -1,15 +1,25 @@
 #![allow(clippy::unused_async)]
 
-use crate::config::{BloomBotEmbed, ROLES};
-use crate::database::Timeframe;
-use crate::database::{DatabaseHandler, TrackingProfile};
+use crate::commands::helpers::rate_limit::throttle_images;
+use crate::commands::refuse_private_text_command;
+use crate::config::{
+  BloomBotEmbed, ChartPalette, EntrySource, LeaderboardPeriod, StatsAccessAction, ROLES,
+};
+use crate::database::{
+  ArchivedTotals, DatabaseHandler, LeaderboardUser, RollingAverage, TrackingProfile, UserTotals,
+};
+use crate::database::{Timeframe, TimeframeStats};
+use crate::leaderboard_rollovers;
+use crate::pagination::{PageRowRef, Pagination};
+use crate::streaks::Activity;
 use crate::Context;
 use crate::{charts, config};
 use anyhow::Result;
+use futures::StreamExt;
 use poise::serenity_prelude::{self as serenity, builder::*};
 use poise::ChoiceParameter;
 
-#[derive(poise::ChoiceParameter)]
+#[derive(Clone, Hash, poise::ChoiceParameter)]
 pub enum StatsType {
   #[name = "Minutes"]
   MeditationMinutes,
@@ -25,6 +35,52 @@ pub enum Privacy {
   Public,
 }
 
+#[derive(Clone, Copy, poise::ChoiceParameter)]
+pub enum Trend {
+  #[name = "7-day average"]
+  Week,
+  #[name = "30-day average"]
+  Month,
+}
+
+impl Trend {
+  fn window_days(self) -> i32 {
+    match self {
+      Trend::Week => 7,
+      Trend::Month => 30,
+    }
+  }
+}
+
+#[derive(Clone, Copy, poise::ChoiceParameter)]
+pub enum LeaderboardType {
+  #[name = "Minutes"]
+  Minutes,
+  #[name = "Sessions"]
+  Sessions,
+  #[name = "Most Improved"]
+  MostImproved,
+  #[name = "Days Active"]
+  DaysActive,
+}
+
+#[derive(Clone, Copy, poise::ChoiceParameter)]
+pub enum LeaderboardScope {
+  #[name = "This Server"]
+  Server,
+  #[name = "Combined with Partners"]
+  Combined,
+}
+
+/// Number of users shown on the leaderboard.
+const LEADERBOARD_SIZE: usize = 10;
+
+/// Complete months shown in the growth stats.
+const GROWTH_MONTHS: i32 = 12;
+
+/// Number of users shown for each past leaderboard, so a full page fits in one embed.
+const HISTORY_LEADERBOARD_SIZE: i32 = 5;
+
 #[derive(poise::ChoiceParameter)]
 pub enum Theme {
   #[name = "Light Mode"]
@@ -33,13 +89,77 @@ pub enum Theme {
   DarkMode,
 }
 
+/// Returns whether to draw charts in light mode: the theme chosen for the command, or otherwise the chart theme set in `/customize settings`.
+async fn chart_light_mode(ctx: Context<'_>, theme: Option<Theme>) -> Result<bool> {
+  if let Some(theme) = theme {
+    return Ok(matches!(theme, Theme::LightMode));
+  }
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+  let tracking_profile =
+    DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &ctx.author().id).await?;
+
+  Ok(tracking_profile.is_some_and(|tracking_profile| tracking_profile.light_mode))
+}
+
+/// Picks the rolling average values matching the stats type, for use as a chart trend line.
+fn trend_values(averages: &[RollingAverage], stats_type: &StatsType) -> Vec<f64> {
+  averages
+    .iter()
+    .map(|average| match stats_type {
+      StatsType::MeditationMinutes => average.minutes,
+      StatsType::MeditationCount => average.count,
+    })
+    .collect()
+}
+
+/// Renders the chart data as a text table, for members who can't see the chart image.
+fn chart_table(
+  chart_stats: &[TimeframeStats],
+  timeframe: &Timeframe,
+  stats_type: &StatsType,
+  trend: Option<(Trend, &[f64])>,
+) -> String {
+  let value_header = match stats_type {
+    StatsType::MeditationMinutes => "Minutes",
+    StatsType::MeditationCount => "Sessions",
+  };
+
+  let mut headers = vec!["Date", value_header];
+  if let Some((trend, _)) = trend {
+    headers.push(trend.name());
+  }
+
+  let rows = charts::chart_rows(
+    chart_stats,
+    timeframe,
+    stats_type,
+    trend.map(|(_, values)| values),
+  );
+
+  charts::render_table(&headers, &rows)
+}
+
 /// Show stats for a user or the server
 ///
-/// Shows stats for yourself, a specified user, or the whole server.
+/// Shows stats for yourself, a specified user, or the whole server, the server leaderboard, the longest current streaks, the members whose messages were starred most, or the final leaderboards of past weeks and months. Staff can also compare activity between roles, and see how many new members start tracking and keep coming back.
 #[poise::command(
   slash_command,
+  prefix_command,
   category = "Meditation Tracking",
-  subcommands("user", "server"),
+  subcommands(
+    "user",
+    "server",
+    "leaderboard",
+    "streaks",
+    "stars",
+    "history",
+    "roles",
+    "growth"
+  ),
   subcommand_required,
   guild_only
 )]
@@ -51,8 +171,8 @@ pub async fn stats(_: Context<'_>) -> Result<()> {
 ///
 /// Shows stats for yourself or a specified user.
 ///
-/// Defaults to daily minutes for yourself. Optionally specify the user, type (minutes or session count), and/or timeframe (daily, weekly, monthly, or yearly).
-#[poise::command(slash_command)]
+/// Defaults to daily minutes for yourself. Optionally specify the user, type (minutes or session count), and/or timeframe (daily, weekly, monthly, or yearly). Daily charts can also show a 7-day or 30-day rolling average trend line. The chart data can also be shown as a text table for screen readers. Stats can be limited to entries logged one way, such as from voice channels or imports.
+#[poise::command(slash_command, prefix_command)]
 pub async fn user(
   ctx: Context<'_>,
   #[description = "The user to get the stats of (Defaults to you)"] user: Option<serenity::User>,
@@ -63,10 +183,27 @@ pub async fn user(
     Timeframe,
   >,
   #[description = "Set visibility of response (Defaults to public)"] privacy: Option<Privacy>,
-  #[description = "Toggle between light mode and dark mode (Defaults to dark mode)"] theme: Option<
-    Theme,
+  #[description = "Toggle between light mode and dark mode (Defaults to your chart theme setting)"]
+  theme: Option<Theme>,
+  #[description = "Show a rolling average trend line (Daily timeframe only)"] trend: Option<Trend>,
+  #[description = "Also show the chart data as a text table (Defaults to false)"] table: Option<
+    bool,
+  >,
+  #[description = "Only count entries logged this way (Defaults to all entries)"] source: Option<
+    EntrySource,
   >,
 ) -> Result<()> {
+  if trend.is_some() && !matches!(timeframe, None | Some(Timeframe::Daily)) {
+    ctx
+      .send(
+        poise::CreateReply::default()
+          .content(":x: Trend lines are only available for the daily timeframe.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
   let data = ctx.data();
   let mut transaction = data.db.start_transaction_with_retry(5).await?;
 
@@ -85,31 +222,24 @@ pub async fn user(
         ..Default::default()
       },
     };
+  let palette = DatabaseHandler::get_chart_palette(&mut transaction, &guild_id).await?;
 
-  let privacy = match privacy {
-    Some(privacy) => match privacy {
-      Privacy::Private => true,
-      Privacy::Public => false,
-    },
-    None => tracking_profile.stats_private,
-  };
-
-  if privacy {
-    ctx.defer_ephemeral().await?;
-  } else {
-    ctx.defer().await?;
-  }
-
-  if ctx.author().id != user.id
-    && tracking_profile.stats_private
-    && !ctx.author().has_role(&ctx, guild_id, ROLES.staff).await?
+  // Private stats are only shown to others with the member's consent, through `/manage stats request`
+  let viewing_private = ctx.author().id != user.id && tracking_profile.stats_private;
+  if viewing_private
+    && !DatabaseHandler::has_stats_access(&mut transaction, &guild_id, &user.id, &ctx.author().id)
+      .await?
   {
+    let content = if ctx.author().has_role(&ctx, guild_id, ROLES.staff).await? {
+      format!("Sorry, {user_nick_or_name}'s stats are set to private. Use `/manage stats request` to ask them for access.")
+    } else {
+      format!("Sorry, {user_nick_or_name}'s stats are set to private.")
+    };
+
     ctx
       .send(
         poise::CreateReply::default()
-          .content(format!(
-            "Sorry, {user_nick_or_name}'s stats are set to private."
-          ))
+          .content(content)
           .ephemeral(true)
           .allowed_mentions(serenity::CreateAllowedMentions::new()),
       )
@@ -118,6 +248,44 @@ pub async fn user(
     return Ok(());
   }
 
+  // Stats viewed with the member's consent are never shown publicly
+  let privacy = viewing_private
+    || match privacy {
+      Some(privacy) => match privacy {
+        Privacy::Private => true,
+        Privacy::Public => false,
+      },
+      None => tracking_profile.stats_private,
+    };
+
+  if refuse_private_text_command(ctx, privacy).await? {
+    return Ok(());
+  }
+
+  if !throttle_images(ctx).await? {
+    return Ok(());
+  }
+
+  if privacy {
+    ctx.defer_ephemeral().await?;
+  } else {
+    ctx.defer().await?;
+  }
+
+  if viewing_private {
+    DatabaseHandler::add_stats_access_log(
+      &mut transaction,
+      &guild_id,
+      &user.id,
+      &ctx.author().id,
+      StatsAccessAction::Viewed,
+    )
+    .await?;
+    DatabaseHandler::commit_transaction(transaction).await?;
+  } else {
+    drop(transaction);
+  }
+
   let stats_type = stats_type.unwrap_or(StatsType::MeditationMinutes);
   let timeframe = timeframe.unwrap_or(Timeframe::Daily);
 
@@ -128,25 +296,51 @@ pub async fn user(
     Timeframe::Daily => "Days",
   };
 
-  let stats =
-    DatabaseHandler::get_user_stats(&mut transaction, &guild_id, &user.id, &timeframe).await?;
+  let user_id = user.id;
+  let (stats, checkin_streak) = DatabaseHandler::with_retry(3, || async move {
+    let mut transaction = data.db.start_transaction_with_retry(5).await?;
+    let stats =
+      DatabaseHandler::get_user_stats(&mut transaction, &guild_id, &user_id, &timeframe, source)
+        .await?;
+    let checkin_streak = DatabaseHandler::get_activity_streak(
+      &mut transaction,
+      &guild_id,
+      &user_id,
+      Activity::CheckIn,
+    )
+    .await?;
+    Ok((stats, checkin_streak))
+  })
+  .await?;
+
+  // Hide streaks if disabled, or if set to private, unless own stats in ephemeral
+  let show_streaks = tracking_profile.streaks_active
+    && (!tracking_profile.streaks_private || (ctx.author().id == user.id && privacy));
 
   let mut embed = BloomBotEmbed::new();
   embed = embed
     .title(format!("Stats for {user_nick_or_name}"))
     .author(CreateEmbedAuthor::new(format!("{user_nick_or_name}'s Stats")).icon_url(user.face()));
+  if let Some(source) = source {
+    embed = embed.description(format!("Showing only entries from **{}**.", source.name()));
+  }
+
+  let locale = ctx.locale();
 
   match stats_type {
     StatsType::MeditationMinutes => {
       embed = embed
         .field(
           "All-Time Meditation Minutes",
-          format!("```{}```", stats.all_minutes),
+          format!("```{}```", charts::format_number(stats.all_minutes, locale)),
           true,
         )
         .field(
           format!("Minutes The Past 12 {timeframe_header}"),
-          format!("```{}```", stats.timeframe_stats.sum.unwrap_or(0)),
+          format!(
+            "```{}```",
+            charts::format_number(stats.timeframe_stats.sum.unwrap_or(0), locale)
+          ),
           true,
         );
     }
@@ -154,27 +348,57 @@ pub async fn user(
       embed = embed
         .field(
           "All-Time Session Count",
-          format!("```{}```", stats.all_count),
+          format!("```{}```", charts::format_number(stats.all_count, locale)),
           true,
         )
         .field(
           format!("Sessions The Past 12 {timeframe_header}"),
-          format!("```{}```", stats.timeframe_stats.count.unwrap_or(0)),
+          format!(
+            "```{}```",
+            charts::format_number(stats.timeframe_stats.count.unwrap_or(0), locale)
+          ),
           true,
         );
     }
   }
 
-  // Role-based bar color for donators; default otherwise
-  let bar_color = if user.has_role(&ctx, guild_id, config::ROLES.patreon).await?
-    || user.has_role(&ctx, guild_id, config::ROLES.kofi).await?
+  if stats.talks_attended > 0 {
+    embed = embed.field(
+      "Talks Attended",
+      format!(
+        "```{} ({} minutes)```",
+        stats.talks_attended, stats.talk_minutes
+      ),
+      true,
+    );
+  }
+
+  // Only shown for members who check in, so other members' stats stay the same
+  if show_streaks && checkin_streak > 0 {
+    embed = embed.field(
+      "Check-In Streak",
+      format!(
+        "```{checkin_streak} {}```",
+        if checkin_streak == 1 { "week" } else { "weeks" }
+      ),
+      true,
+    );
+  }
+
+  let light_mode = chart_light_mode(ctx, theme).await?;
+
+  // Role-based bar color for donators, unless the server chose another palette; palette color otherwise
+  let palette_color = charts::bar_color(palette, light_mode);
+  let bar_color = if matches!(palette, ChartPalette::Default)
+    && (user.has_role(&ctx, guild_id, config::ROLES.patreon).await?
+      || user.has_role(&ctx, guild_id, config::ROLES.kofi).await?)
   {
     match guild_id.member(&ctx, user.id).await?.colour(ctx) {
       Some(color) => (color.r(), color.g(), color.b(), 1.0),
-      None => (253, 172, 46, 1.0),
+      None => palette_color,
     }
   } else {
-    (253, 172, 46, 1.0)
+    palette_color
   };
 
   // Role-based bar color for all users
@@ -183,25 +407,63 @@ pub async fn user(
   //  None => (253, 172, 46, 1.0)
   //};
 
-  let light_mode = match theme {
-    Some(theme) => match theme {
-      Theme::LightMode => true,
-      Theme::DarkMode => false,
-    },
-    None => false,
-  };
-
-  let chart_stats =
-    DatabaseHandler::get_user_chart_stats(&mut transaction, &guild_id, &user.id, &timeframe)
-      .await?;
-  let chart_drawer = charts::ChartDrawer::new()?;
+  let (chart_stats, averages) = DatabaseHandler::with_retry(3, || async move {
+    let mut transaction = data.db.start_transaction_with_retry(5).await?;
+    let chart_stats = DatabaseHandler::get_user_chart_stats(
+      &mut transaction,
+      &guild_id,
+      &user_id,
+      &timeframe,
+      source,
+    )
+    .await?;
+    let averages = match trend {
+      Some(trend) => Some(
+        DatabaseHandler::get_rolling_averages(
+          &mut transaction,
+          &guild_id,
+          Some(&user_id),
+          trend.window_days(),
+          source,
+        )
+        .await?,
+      ),
+      None => None,
+    };
+    Ok((chart_stats, averages))
+  })
+  .await?;
+  let trend_line = averages.map(|averages| trend_values(&averages, &stats_type));
+  let chart_drawer = charts::ChartDrawer::new()?.locale(ctx.locale());
   let chart = chart_drawer
-    .draw(&chart_stats, &timeframe, &stats_type, bar_color, light_mode)
+    .draw(
+      &chart_stats,
+      &timeframe,
+      &stats_type,
+      bar_color,
+      light_mode,
+      trend
+        .zip(trend_line.as_deref())
+        .map(|(trend, values)| (trend.name(), values)),
+    )
     .await?;
   let file_path = chart.get_file_path();
 
   embed = embed.image(chart.get_attachment_url());
 
+  if table.unwrap_or(false) {
+    embed = embed.field(
+      "Chart Data",
+      chart_table(
+        &chart_stats,
+        &timeframe,
+        &stats_type,
+        trend.zip(trend_line.as_deref()),
+      ),
+      false,
+    );
+  }
+
   let average = match stats_type {
     StatsType::MeditationMinutes => stats.timeframe_stats.sum.unwrap_or(0) / 12,
     StatsType::MeditationCount => stats.timeframe_stats.count.unwrap_or(0) / 12,
@@ -212,11 +474,7 @@ pub async fn user(
     StatsType::MeditationCount => "sessions",
   };
 
-  // Hide streak in footer if streaks disabled
-  if tracking_profile.streaks_active
-    // Hide streak in footer if streak set to private, unless own stats in ephemeral
-    && (!tracking_profile.streaks_private || (ctx.author().id == user.id && privacy))
-  {
+  if show_streaks {
     embed = embed.footer(CreateEmbedFooter::new(format!(
       "Avg. {} {}: {}・Current streak: {}",
       timeframe.name().to_lowercase(),
@@ -250,18 +508,39 @@ pub async fn user(
 ///
 /// Shows stats for the whole server.
 ///
-/// Defaults to daily minutes. Optionally specify the type (minutes or session count) and/or timeframe (daily, weekly, monthly, or yearly).
-#[poise::command(slash_command)]
+/// Defaults to daily minutes. Optionally specify the type (minutes or session count) and/or timeframe (daily, weekly, monthly, or yearly). Daily charts can also show a 7-day or 30-day rolling average trend line. The chart data can also be shown as a text table for screen readers. Stats can be limited to entries logged one way, such as from voice channels or imports.
+#[poise::command(slash_command, prefix_command)]
 pub async fn server(
   ctx: Context<'_>,
   #[description = "The type of stats to get (Defaults to minutes)"] stats_type: Option<StatsType>,
   #[description = "The timeframe to get the stats for (Defaults to daily)"] timeframe: Option<
     Timeframe,
   >,
-  #[description = "Toggle between light mode and dark mode (Defaults to dark mode)"] theme: Option<
-    Theme,
+  #[description = "Toggle between light mode and dark mode (Defaults to your chart theme setting)"]
+  theme: Option<Theme>,
+  #[description = "Show a rolling average trend line (Daily timeframe only)"] trend: Option<Trend>,
+  #[description = "Also show the chart data as a text table (Defaults to false)"] table: Option<
+    bool,
+  >,
+  #[description = "Only count entries logged this way (Defaults to all entries)"] source: Option<
+    EntrySource,
   >,
 ) -> Result<()> {
+  if trend.is_some() && !matches!(timeframe, None | Some(Timeframe::Daily)) {
+    ctx
+      .send(
+        poise::CreateReply::default()
+          .content(":x: Trend lines are only available for the daily timeframe.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  if !throttle_images(ctx).await? {
+    return Ok(());
+  }
+
   ctx.defer().await?;
 
   let data = ctx.data();
@@ -279,27 +558,40 @@ pub async fn server(
     Timeframe::Daily => "Days",
   };
 
-  let mut transaction = data.db.start_transaction_with_retry(5).await?;
-
-  let stats = DatabaseHandler::get_guild_stats(&mut transaction, &guild_id, &timeframe).await?;
+  let (stats, palette) = DatabaseHandler::with_retry(3, || async move {
+    let mut transaction = data.db.start_transaction_with_retry(5).await?;
+    let stats =
+      DatabaseHandler::get_guild_stats(&mut transaction, &guild_id, &timeframe, source).await?;
+    let palette = DatabaseHandler::get_chart_palette(&mut transaction, &guild_id).await?;
+    Ok((stats, palette))
+  })
+  .await?;
 
   let mut embed = BloomBotEmbed::new();
   embed = embed.title(format!("Stats for {guild_name}")).author(
     CreateEmbedAuthor::new(format!("{guild_name}'s Stats"))
       .icon_url(ctx.guild().unwrap().icon_url().unwrap_or_default()),
   );
+  if let Some(source) = source {
+    embed = embed.description(format!("Showing only entries from **{}**.", source.name()));
+  }
+
+  let locale = ctx.locale();
 
   match stats_type {
     StatsType::MeditationMinutes => {
       embed = embed
         .field(
           "All-Time Meditation Minutes",
-          format!("```{}```", stats.all_minutes),
+          format!("```{}```", charts::format_number(stats.all_minutes, locale)),
           true,
         )
         .field(
           format!("Minutes The Past 12 {timeframe_header}"),
-          format!("```{}```", stats.timeframe_stats.sum.unwrap_or(0)),
+          format!(
+            "```{}```",
+            charts::format_number(stats.timeframe_stats.sum.unwrap_or(0), locale)
+          ),
           true,
         );
     }
@@ -307,36 +599,75 @@ pub async fn server(
       embed = embed
         .field(
           "All-Time Session Count",
-          format!("```{}```", stats.all_count),
+          format!("```{}```", charts::format_number(stats.all_count, locale)),
           true,
         )
         .field(
           format!("Sessions The Past 12 {timeframe_header}"),
-          format!("```{}```", stats.timeframe_stats.count.unwrap_or(0)),
+          format!(
+            "```{}```",
+            charts::format_number(stats.timeframe_stats.count.unwrap_or(0), locale)
+          ),
           true,
         );
     }
   }
 
-  let bar_color = (253, 172, 46, 1.0);
-  let light_mode = match theme {
-    Some(theme) => match theme {
-      Theme::LightMode => true,
-      Theme::DarkMode => false,
-    },
-    None => false,
-  };
+  let light_mode = chart_light_mode(ctx, theme).await?;
+  let bar_color = charts::bar_color(palette, light_mode);
 
-  let chart_stats =
-    DatabaseHandler::get_guild_chart_stats(&mut transaction, &guild_id, &timeframe).await?;
-  let chart_drawer = charts::ChartDrawer::new()?;
+  let (chart_stats, averages) = DatabaseHandler::with_retry(3, || async move {
+    let mut transaction = data.db.start_transaction_with_retry(5).await?;
+    let chart_stats =
+      DatabaseHandler::get_guild_chart_stats(&mut transaction, &guild_id, &timeframe, source)
+        .await?;
+    let averages = match trend {
+      Some(trend) => Some(
+        DatabaseHandler::get_rolling_averages(
+          &mut transaction,
+          &guild_id,
+          None,
+          trend.window_days(),
+          source,
+        )
+        .await?,
+      ),
+      None => None,
+    };
+    Ok((chart_stats, averages))
+  })
+  .await?;
+  let trend_line = averages.map(|averages| trend_values(&averages, &stats_type));
+  let chart_drawer = charts::ChartDrawer::new()?.locale(ctx.locale());
   let chart = chart_drawer
-    .draw(&chart_stats, &timeframe, &stats_type, bar_color, light_mode)
+    .draw(
+      &chart_stats,
+      &timeframe,
+      &stats_type,
+      bar_color,
+      light_mode,
+      trend
+        .zip(trend_line.as_deref())
+        .map(|(trend, values)| (trend.name(), values)),
+    )
     .await?;
   let file_path = chart.get_file_path();
 
   embed = embed.image(chart.get_attachment_url());
 
+  if table.unwrap_or(false) {
+    embed = embed.field(
+      "Chart Data",
+      chart_table(
+        &chart_stats,
+        &timeframe,
+        &stats_type,
+        trend.zip(trend_line.as_deref()),
+      ),
+      false,
+    );
+  }
+
   ctx
     .send({
       let mut f =
@@ -349,3 +680,616 @@ pub async fn server(
 
   Ok(())
 }
+
+/// Formats a leaderboard entry, showing the value the leaderboard is ranked by.
+fn leaderboard_line(
+  rank: usize,
+  user: &LeaderboardUser,
+  leaderboard_type: LeaderboardType,
+) -> String {
+  let value = match leaderboard_type {
+    LeaderboardType::Minutes => format!("{} minutes", user.minutes),
+    LeaderboardType::Sessions => format!("{} sessions", user.sessions),
+    LeaderboardType::DaysActive => format!("{} days", user.days_active),
+    LeaderboardType::MostImproved => match user.improvement_percent() {
+      Some(percent) => format!("+{} minutes (+{percent:.0}%)", user.improvement()),
+      None => format!("+{} minutes (new)", user.improvement()),
+    },
+  };
+
+  format!("**{rank}.** <@{}> — {value}", user.user_id)
+}
+
+/// Show the server leaderboard
+///
+/// Shows the top meditators in the server for the past day, week, month, or year. Users with private stats are not shown. Servers linked as partners with `/manage partners` can show a combined leaderboard.
+///
+/// Defaults to weekly minutes. Most improved ranks users by how many more minutes they meditated than in the period before. Days active ranks users by the number of days they meditated, rewarding consistency over volume.
+#[poise::command(slash_command, prefix_command)]
+pub async fn leaderboard(
+  ctx: Context<'_>,
+  #[description = "What to rank users by (Defaults to minutes)"] leaderboard_type: Option<
+    LeaderboardType,
+  >,
+  #[description = "The timeframe to rank users over (Defaults to weekly)"] timeframe: Option<
+    Timeframe,
+  >,
+  #[description = "Include linked partner servers (Defaults to this server)"] scope: Option<
+    LeaderboardScope,
+  >,
+) -> Result<()> {
+  let data = ctx.data();
+
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut guild_ids = vec![guild_id];
+  if let Some(LeaderboardScope::Combined) = scope {
+    let mut transaction = data.db.start_transaction_with_retry(5).await?;
+    let links = DatabaseHandler::get_guild_links(&mut transaction, &guild_id).await?;
+    drop(transaction);
+
+    guild_ids.extend(
+      links
+        .into_iter()
+        .filter(|link| link.mutual)
+        .map(|link| link.partner_guild_id),
+    );
+
+    if guild_ids.len() == 1 {
+      ctx
+        .send(
+          poise::CreateReply::default()
+            .content(":x: This server is not linked with any partner servers.")
+            .ephemeral(true),
+        )
+        .await?;
+      return Ok(());
+    }
+  }
+
+  ctx.defer().await?;
+
+  let guild_name = guild_ids
+    .iter()
+    .map(|id| id.name(ctx).unwrap_or(id.to_string()))
+    .collect::<Vec<String>>()
+    .join(" + ");
+
+  let leaderboard_type = leaderboard_type.unwrap_or(LeaderboardType::Minutes);
+  let timeframe = timeframe.unwrap_or(Timeframe::Weekly);
+
+  let guild_ids = &guild_ids;
+  let (mut users, combined_minutes) = DatabaseHandler::with_retry(3, || async move {
+    let mut transaction = data.db.start_transaction_with_retry(5).await?;
+    // The cap of the server viewing the leaderboard applies, including to partner servers
+    let limits = DatabaseHandler::get_session_limits(&mut transaction, &guild_id).await?;
+    let users = DatabaseHandler::get_leaderboard(
+      &mut transaction,
+      guild_ids,
+      &timeframe,
+      limits.daily_cap_minutes,
+    )
+    .await?;
+    let combined_minutes = if guild_ids.len() > 1 {
+      Some(DatabaseHandler::get_guilds_minutes(&mut transaction, guild_ids, &timeframe).await?)
+    } else {
+      None
+    };
+    Ok((users, combined_minutes))
+  })
+  .await?;
+
+  match leaderboard_type {
+    LeaderboardType::Minutes => {
+      users.retain(|user| user.minutes > 0);
+      users.sort_by_key(|user| std::cmp::Reverse(user.minutes));
+    }
+    LeaderboardType::Sessions => {
+      users.retain(|user| user.sessions > 0);
+      users.sort_by_key(|user| std::cmp::Reverse(user.sessions));
+    }
+    LeaderboardType::MostImproved => {
+      users.retain(|user| user.improvement() > 0);
+      users.sort_by_key(|user| std::cmp::Reverse(user.improvement()));
+    }
+    LeaderboardType::DaysActive => {
+      // Ties are broken by minutes, so the most consistent meditators are ranked first
+      users.retain(|user| user.days_active > 0);
+      users.sort_by_key(|user| std::cmp::Reverse((user.days_active, user.minutes)));
+    }
+  }
+
+  let period = match timeframe {
+    Timeframe::Yearly => "year",
+    Timeframe::Monthly => "month",
+    Timeframe::Weekly => "week",
+    Timeframe::Daily => "day",
+  };
+
+  let description = if users.is_empty() {
+    format!("No one has meditated in the past {period} yet.")
+  } else {
+    users
+      .iter()
+      .take(LEADERBOARD_SIZE)
+      .zip(1..)
+      .map(|(user, rank)| leaderboard_line(rank, user, leaderboard_type))
+      .collect::<Vec<String>>()
+      .join("\n")
+  };
+
+  let mut embed = BloomBotEmbed::new()
+    .title(format!("{} Leaderboard", leaderboard_type.name()))
+    .author(
+      CreateEmbedAuthor::new(guild_name)
+        .icon_url(ctx.guild().unwrap().icon_url().unwrap_or_default()),
+    )
+    .description(description)
+    .footer(CreateEmbedFooter::new(format!("Past {period}")));
+
+  if let Some(combined_minutes) = combined_minutes {
+    embed = embed.field(
+      "Combined Total",
+      format!("```{combined_minutes} minutes```"),
+      false,
+    );
+  }
+
+  ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+  Ok(())
+}
+
+/// Show the streak leaderboard
+///
+/// Shows the members with the longest current meditation streaks. Members who turned streak reporting off, or made their streak or stats private, are not shown.
+///
+/// Streaks that include a missed day restored with `/streak restore` are marked as restored.
+#[poise::command(slash_command, prefix_command)]
+pub async fn streaks(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  let guild_id = ctx.guild_id().unwrap();
+
+  ctx.defer().await?;
+
+  let mut users = DatabaseHandler::with_retry(3, || async move {
+    let mut transaction = data.db.start_transaction_with_retry(5).await?;
+    let users = DatabaseHandler::get_streak_leaderboard(&mut transaction, &guild_id).await?;
+    Ok(users)
+  })
+  .await?;
+
+  users.sort_by_key(|user| std::cmp::Reverse(user.streak));
+
+  let description = if users.is_empty() {
+    "No one has a meditation streak right now.".to_string()
+  } else {
+    users
+      .iter()
+      .take(LEADERBOARD_SIZE)
+      .zip(1..)
+      .map(|(user, rank)| {
+        let restored = if user.restored { " (restored)" } else { "" };
+        format!(
+          "**{rank}.** <@{}> — {} days{restored}",
+          user.user_id, user.streak
+        )
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  };
+
+  let embed = BloomBotEmbed::new()
+    .title("Streak Leaderboard")
+    .author(
+      CreateEmbedAuthor::new(guild_id.name(ctx).unwrap_or(guild_id.to_string()))
+        .icon_url(ctx.guild().unwrap().icon_url().unwrap_or_default()),
+    )
+    .description(description);
+
+  ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+  Ok(())
+}
+
+/// Show the star leaderboard
+///
+/// Shows the members whose messages received the most stars on the starboard, along with the total stars received by yourself or a specified user.
+///
+/// Only messages currently on the starboard are counted, using their latest star count.
+#[poise::command(slash_command, prefix_command)]
+pub async fn stars(
+  ctx: Context<'_>,
+  #[description = "The user to show stars received for (Defaults to you)"] user: Option<
+    serenity::User,
+  >,
+) -> Result<()> {
+  let data = ctx.data();
+
+  let guild_id = ctx.guild_id().unwrap();
+
+  let user = user.unwrap_or_else(|| ctx.author().clone());
+  let user_nick_or_name = match user.nick_in(&ctx, guild_id).await {
+    Some(nick) => nick,
+    None => user.name.clone(),
+  };
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let top_authors = DatabaseHandler::get_star_leaderboard(
+    &mut transaction,
+    &guild_id,
+    i64::try_from(LEADERBOARD_SIZE)?,
+  )
+  .await?;
+  let user_stars = DatabaseHandler::get_author_stars(&mut transaction, &guild_id, &user.id).await?;
+  drop(transaction);
+
+  let description = if top_authors.is_empty() {
+    "No messages have been starred yet.".to_string()
+  } else {
+    top_authors
+      .iter()
+      .zip(1..)
+      .map(|(author, rank)| {
+        format!(
+          "**{rank}.** <@{}> — ⭐ {} stars ({} messages)",
+          author.user_id, author.stars, author.messages
+        )
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
+  };
+
+  let embed = BloomBotEmbed::new()
+    .title("Star Leaderboard")
+    .author(
+      CreateEmbedAuthor::new(guild_id.name(ctx).unwrap_or(guild_id.to_string()))
+        .icon_url(ctx.guild().unwrap().icon_url().unwrap_or_default()),
+    )
+    .description(description)
+    .field(
+      format!("{user_nick_or_name}'s Stars"),
+      format!(
+        "⭐ {} stars across {} starred messages",
+        user_stars.stars, user_stars.messages
+      ),
+      false,
+    );
+
+  ctx.send(poise::CreateReply::default().embed(embed)).await?;
+
+  Ok(())
+}
+
+/// Browse past leaderboards
+///
+/// Commands to browse the final leaderboards saved at the end of each week or month.
+#[poise::command(slash_command, subcommands("history_leaderboard"), subcommand_required)]
+pub async fn history(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
+/// Browse past final leaderboards
+///
+/// Shows the final leaderboards saved at the end of past weeks or months, newest first, with the totals for the same week or month a year earlier where available.
+///
+/// Members who have since made their stats private are not shown.
+#[poise::command(slash_command, rename = "leaderboard")]
+pub async fn history_leaderboard(
+  ctx: Context<'_>,
+  #[description = "The leaderboards to browse"] period: LeaderboardPeriod,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  ctx.defer().await?;
+
+  let mut leaderboards = DatabaseHandler::with_retry(3, || async move {
+    let mut transaction = data.db.start_transaction_with_retry(5).await?;
+    let leaderboards = DatabaseHandler::get_leaderboard_archive(
+      &mut transaction,
+      &guild_id,
+      period,
+      HISTORY_LEADERBOARD_SIZE,
+    )
+    .await?;
+    Ok(leaderboards)
+  })
+  .await?;
+
+  let totals: std::collections::HashMap<chrono::DateTime<chrono::Utc>, ArchivedTotals> =
+    leaderboards
+      .iter()
+      .map(|leaderboard| (leaderboard.period_start, leaderboard.totals))
+      .collect();
+  for leaderboard in &mut leaderboards {
+    let last_year = leaderboard_rollovers::same_period_last_year(period, leaderboard.period_start);
+    leaderboard.last_year = totals.get(&last_year).copied();
+  }
+
+  // Define some unique identifiers for the navigation buttons
+  let ctx_id = ctx.id();
+  let prev_button_id = format!("{ctx_id}prev");
+  let next_button_id = format!("{ctx_id}next");
+
+  let mut current_page = 0;
+
+  let entries: Vec<PageRowRef> = leaderboards
+    .iter()
+    .map(|leaderboard| leaderboard as _)
+    .collect();
+  let title = match period {
+    LeaderboardPeriod::Weekly => "Past Weekly Leaderboards",
+    LeaderboardPeriod::Monthly => "Past Monthly Leaderboards",
+  };
+  let pagination = Pagination::new(title, entries).await?;
+
+  let first_page = pagination.create_page_embed(current_page);
+
+  ctx
+    .send({
+      let mut f = poise::CreateReply::default();
+      if pagination.get_page_count() > 1 {
+        f = f.components(vec![CreateActionRow::Buttons(vec![
+          CreateButton::new(&prev_button_id).label("Previous"),
+          CreateButton::new(&next_button_id).label("Next"),
+        ])]);
+      }
+      f.embeds = vec![first_page];
+      f
+    })
+    .await?;
+
+  // Loop through incoming interactions with the navigation buttons
+  while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+    // We defined our button IDs to start with `ctx_id`. If they don't, some other command's
+    // button was pressed
+    .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+    // Timeout when no navigation button has been pressed for 24 hours
+    .timeout(std::time::Duration::from_secs(3600 * 24))
+    .await
+  {
+    // Depending on which button was pressed, go to next or previous page
+    if press.data.custom_id == next_button_id {
+      current_page = pagination.update_page_number(current_page, 1);
+    } else if press.data.custom_id == prev_button_id {
+      current_page = pagination.update_page_number(current_page, -1);
+    } else {
+      // This is an unrelated button interaction
+      continue;
+    }
+
+    // Update the message with the new page contents
+    press
+      .create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+          CreateInteractionResponseMessage::new().embed(pagination.create_page_embed(current_page)),
+        ),
+      )
+      .await?;
+  }
+
+  Ok(())
+}
+
+/// Compare activity between roles
+///
+/// Compares meditation activity between members of different roles, such as course participants and graduates, over the chosen timeframe (defaults to monthly). Shows member counts, totals, and average minutes per member, with a comparison chart.
+///
+/// Members with more than one of the roles are counted in each. Requires `Ban Members` permissions.
+#[poise::command(slash_command, required_permissions = "BAN_MEMBERS")]
+pub async fn roles(
+  ctx: Context<'_>,
+  #[description = "The first role to compare"] role: serenity::Role,
+  #[description = "The second role to compare"] role_2: Option<serenity::Role>,
+  #[description = "The third role to compare"] role_3: Option<serenity::Role>,
+  #[description = "The fourth role to compare"] role_4: Option<serenity::Role>,
+  #[description = "The timeframe to compare activity over (Defaults to monthly)"] timeframe: Option<
+    Timeframe,
+  >,
+  #[description = "Toggle between light mode and dark mode (Defaults to your chart theme setting)"]
+  theme: Option<Theme>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  if !throttle_images(ctx).await? {
+    return Ok(());
+  }
+
+  ctx.defer_ephemeral().await?;
+
+  let mut roles = vec![role];
+  for role in [role_2, role_3, role_4].into_iter().flatten() {
+    if !roles.iter().any(|existing| existing.id == role.id) {
+      roles.push(role);
+    }
+  }
+
+  let timeframe = timeframe.unwrap_or(Timeframe::Monthly);
+  let start_time = chrono::Utc::now() - timeframe.period();
+
+  // Role membership isn't stored, so members are fetched when the command is used
+  let mut members_by_role: Vec<Vec<serenity::UserId>> = vec![Vec::new(); roles.len()];
+  let mut members = guild_id.members_iter(ctx).boxed();
+  while let Some(member) = members.next().await {
+    let member = member?;
+    for (role, role_members) in roles.iter().zip(members_by_role.iter_mut()) {
+      if member.roles.contains(&role.id) {
+        role_members.push(member.user.id);
+      }
+    }
+  }
+
+  let user_ids: Vec<serenity::UserId> = members_by_role.iter().flatten().copied().collect();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let totals =
+    DatabaseHandler::get_users_totals(&mut transaction, &guild_id, &user_ids, start_time).await?;
+  let palette = DatabaseHandler::get_chart_palette(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  let totals: std::collections::HashMap<serenity::UserId, UserTotals> = totals
+    .into_iter()
+    .map(|totals| (totals.user_id, totals))
+    .collect();
+
+  let mut embed = BloomBotEmbed::new()
+    .title("Activity by Role")
+    .footer(CreateEmbedFooter::new(format!(
+      "Past {}",
+      match timeframe {
+        Timeframe::Yearly => "year",
+        Timeframe::Monthly => "month",
+        Timeframe::Weekly => "week",
+        Timeframe::Daily => "day",
+      }
+    )));
+  let mut bars = Vec::new();
+
+  for (role, role_members) in roles.iter().zip(&members_by_role) {
+    let role_totals: Vec<&UserTotals> = role_members
+      .iter()
+      .filter_map(|user_id| totals.get(user_id))
+      .collect();
+    let minutes: i64 = role_totals.iter().map(|totals| totals.minutes).sum();
+    let sessions: i64 = role_totals.iter().map(|totals| totals.sessions).sum();
+
+    #[allow(clippy::cast_precision_loss)]
+    let average = if role_members.is_empty() {
+      0.0
+    } else {
+      minutes as f64 / role_members.len() as f64
+    };
+
+    embed = embed.field(
+      role.name.clone(),
+      format!(
+        "**Members**: {} ({} active)\n**Minutes**: {minutes}\n**Sessions**: {sessions}\n**Avg. per Member**: {average:.1} minutes",
+        role_members.len(),
+        role_totals.len()
+      ),
+      true,
+    );
+    bars.push((role.name.clone(), average));
+  }
+
+  let light_mode = chart_light_mode(ctx, theme).await?;
+
+  let chart = charts::ChartDrawer::new()?
+    .locale(ctx.locale())
+    .draw_comparison(
+      "Avg. Minutes per Member",
+      &bars,
+      charts::bar_color(palette, light_mode),
+      light_mode,
+    )
+    .await?;
+  let file_path = chart.get_file_path();
+
+  embed = embed.image(chart.get_attachment_url());
+
+  ctx
+    .send(
+      poise::CreateReply::default()
+        .attachment(CreateAttachment::path(&file_path).await?)
+        .embed(embed)
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Show new member growth and retention
+///
+/// Shows how many members started tracking in each of the past 12 months, how many of them meditated again within 30 days of their first entry, and how many members active the month before didn't return. Members count as new in the month of their first entry in this server.
+///
+/// Retention is only shown once a month's new members have had 30 days to return. Requires `Ban Members` permissions.
+#[poise::command(slash_command, required_permissions = "BAN_MEMBERS")]
+pub async fn growth(
+  ctx: Context<'_>,
+  #[description = "Toggle between light mode and dark mode (Defaults to your chart theme setting)"]
+  theme: Option<Theme>,
+) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  if !data.flags.enabled(guild_id, "growth_stats").await? {
+    ctx
+      .send(
+        poise::CreateReply::default()
+          .content(":x: Growth stats aren't enabled in this server.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  if !throttle_images(ctx).await? {
+    return Ok(());
+  }
+
+  ctx.defer_ephemeral().await?;
+
+  let (months, palette) = DatabaseHandler::with_retry(3, || async move {
+    let mut transaction = data.db.start_transaction_with_retry(5).await?;
+    let months =
+      DatabaseHandler::get_guild_growth(&mut transaction, &guild_id, GROWTH_MONTHS).await?;
+    let palette = DatabaseHandler::get_chart_palette(&mut transaction, &guild_id).await?;
+    Ok((months, palette))
+  })
+  .await?;
+
+  let light_mode = chart_light_mode(ctx, theme).await?;
+
+  let chart = charts::ChartDrawer::new()?
+    .locale(ctx.locale())
+    .draw_growth(&months, charts::bar_color(palette, light_mode), light_mode)
+    .await?;
+
+  let rows: Vec<Vec<String>> = months
+    .iter()
+    .map(|month| {
+      vec![
+        month.month_start.format("%Y-%m").to_string(),
+        month.new_members.to_string(),
+        month
+          .retention_percent()
+          .map_or("-".to_string(), |percent| format!("{percent:.0}%")),
+        month.active_members.to_string(),
+        month.churned.to_string(),
+      ]
+    })
+    .collect();
+
+  let embed = BloomBotEmbed::new()
+    .title("Member Growth")
+    .description(charts::render_table(
+      &["Month", "New", "Retained", "Active", "Churned"],
+      &rows,
+    ))
+    .footer(CreateEmbedFooter::new(
+      "Retained: new members who meditated again within 30 days. Churned: active the month before, but not this month.",
+    ))
+    .image(chart.get_attachment_url());
+
+  ctx
+    .send(
+      poise::CreateReply::default()
+        .attachment(CreateAttachment::path(chart.get_file_path()).await?)
+        .embed(embed)
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(())
+}
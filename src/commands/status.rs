@@ -0,0 +1,131 @@
+use crate::add_timings;
+use crate::charts;
+use crate::config::BloomBotEmbed;
+use crate::Context;
+use anyhow::Result;
+use poise::serenity_prelude::CreateEmbedFooter;
+use poise::CreateReply;
+use std::time::Duration;
+
+fn format_duration(duration: Duration) -> String {
+  let total_minutes = duration.as_secs() / 60;
+  let days = total_minutes / (60 * 24);
+  let hours = (total_minutes / 60) % 24;
+  let minutes = total_minutes % 60;
+
+  if days > 0 {
+    format!("{days}d {hours}h {minutes}m")
+  } else if hours > 0 {
+    format!("{hours}h {minutes}m")
+  } else {
+    format!("{minutes}m")
+  }
+}
+
+/// Reads the resident memory of the bot process. Only available on Linux.
+#[allow(clippy::cast_precision_loss)]
+fn get_memory_usage() -> Option<String> {
+  let status = std::fs::read_to_string("/proc/self/status").ok()?;
+  let rss_kb = status
+    .lines()
+    .find(|line| line.starts_with("VmRSS:"))?
+    .split_whitespace()
+    .nth(1)?
+    .parse::<u64>()
+    .ok()?;
+
+  Some(format!("{:.1} MB", rss_kb as f64 / 1024.0))
+}
+
+/// Show bot status and diagnostics
+///
+/// Shows uptime, shard latency, database pool state, cache hit rates, the chart rendering queue, how long each step of adding a session takes, and memory usage.
+///
+/// Requires `Manage Roles` permissions.
+#[poise::command(
+  slash_command,
+  required_permissions = "MANAGE_ROLES",
+  default_member_permissions = "MANAGE_ROLES",
+  category = "Moderator Commands"
+)]
+#[allow(clippy::cast_precision_loss)]
+pub async fn status(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  let uptime = format_duration(data.started_at.elapsed());
+
+  let shards = {
+    let runners = ctx.framework().shard_manager().runners.lock().await;
+    let mut shards: Vec<String> = runners
+      .iter()
+      .map(|(shard_id, runner)| match runner.latency {
+        Some(latency) => format!("Shard {shard_id}: {}ms", latency.as_millis()),
+        None => format!("Shard {shard_id}: unknown"),
+      })
+      .collect();
+    shards.sort();
+    shards.join("\n")
+  };
+
+  let pool = data.db.get_pool_stats();
+  let active = pool.active();
+
+  let (hits, misses) = data.term_names.hit_stats();
+  let hit_rate = if hits + misses == 0 {
+    "n/a".to_string()
+  } else {
+    format!("{:.1}%", hits as f64 / (hits + misses) as f64 * 100.0)
+  };
+
+  let (queued_charts, rendering_charts) = charts::render_queue_stats();
+
+  let add_stages = add_timings::stage_stats()
+    .into_iter()
+    .map(|(stage, count, average, max)| {
+      format!(
+        "{}: avg {}ms, max {}ms ({count})",
+        stage.name(),
+        average.as_millis(),
+        max.as_millis()
+      )
+    })
+    .collect::<Vec<String>>()
+    .join("\n");
+
+  let memory = get_memory_usage().unwrap_or("unavailable".to_string());
+
+  let embed = BloomBotEmbed::new()
+    .title("Bot Status")
+    .field("Uptime", uptime, true)
+    .field("Memory", memory, true)
+    .field("Shard Latency", shards, false)
+    .field(
+      "Database Pool",
+      format!(
+        "Active: {active}\nIdle: {}\nMax: {}",
+        pool.idle, pool.max_connections
+      ),
+      true,
+    )
+    .field(
+      "Term Name Cache",
+      format!("Hit rate: {hit_rate}\nHits: {hits}\nMisses: {misses}"),
+      true,
+    )
+    .field(
+      "Chart Rendering",
+      format!("Queued: {queued_charts}\nRendering: {rendering_charts}"),
+      true,
+    )
+    .field("Add Timings", add_stages, false)
+    .footer(CreateEmbedFooter::new(format!(
+      "Bloom v{}",
+      env!("CARGO_PKG_VERSION")
+    )));
+
+  ctx
+    .send(CreateReply::default().embed(embed).ephemeral(true))
+    .await?;
+
+  Ok(())
+}
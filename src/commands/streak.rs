@@ -1,4 +1,7 @@
+use crate::commands::{commit_and_say, refuse_private_text_command, MessageType};
+use crate::config::{MAX_STREAK_CREDITS, STREAK_CREDIT_DAYS, STREAK_RESTORE_DAYS};
 use crate::database::{DatabaseHandler, TrackingProfile};
+use crate::streaks::Activity;
 use crate::{config, Context};
 use anyhow::Result;
 use poise::serenity_prelude as serenity;
@@ -11,13 +14,29 @@ pub enum Privacy {
   Public,
 }
 
+/// See or restore your meditation streak
+///
+/// Commands to see your current meditation streak, or another member's, and to restore a broken streak with a streak credit in servers that have turned streak restores on.
+#[poise::command(
+  slash_command,
+  prefix_command,
+  category = "Meditation Tracking",
+  subcommands("show", "restore"),
+  subcommand_required,
+  guild_only
+)]
+#[allow(clippy::unused_async)]
+pub async fn streak(_: Context<'_>) -> Result<()> {
+  Ok(())
+}
+
 /// See your current meditation streak
 ///
-/// Shows your current meditation streak. Setting the visibility here will override your custom streak privacy settings.
+/// Shows your current meditation streak, along with your practice diary streak if you keep one with `/journal`. Setting the visibility here will override your custom streak privacy settings.
 ///
-/// Can also be used to check another member's streak, unless set to private.
-#[poise::command(slash_command, category = "Meditation Tracking", guild_only)]
-pub async fn streak(
+/// Can also be used to check another member's streak, unless set to private. Streaks that include a day restored with `/streak restore` are marked as restored.
+#[poise::command(slash_command, prefix_command)]
+pub async fn show(
   ctx: Context<'_>,
   #[description = "The user to check the streak of"] user: Option<serenity::User>,
   #[description = "Set visibility of response (Default is public)"] privacy: Option<Privacy>,
@@ -33,6 +52,22 @@ pub async fn streak(
 
   let mut transaction = data.db.start_transaction_with_retry(5).await?;
   let streak = DatabaseHandler::get_streak(&mut transaction, &guild_id, &user_id).await?;
+  let restored =
+    if DatabaseHandler::is_streak_restored(&mut transaction, &guild_id, &user_id).await? {
+      " (restored)"
+    } else {
+      ""
+    };
+  let diary_streak =
+    DatabaseHandler::get_activity_streak(&mut transaction, &guild_id, &user_id, Activity::Journal)
+      .await?;
+
+  // Only mentioned for members who keep a diary, so the reply stays the same for everyone else
+  let diary = if diary_streak > 0 {
+    format!(" Practice diary streak: {diary_streak} days.")
+  } else {
+    String::new()
+  };
 
   let tracking_profile =
     match DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user_id).await? {
@@ -50,6 +85,12 @@ pub async fn streak(
     None => tracking_profile.streaks_private,
   };
 
+  // Staff can see private streaks, which must never be shown publicly
+  let shows_private = privacy || (user_id != ctx.author().id && tracking_profile.streaks_private);
+  if refuse_private_text_command(ctx, shows_private).await? {
+    return Ok(());
+  }
+
   if user.is_some() && (user_id != ctx.author().id) {
     let user = user.unwrap();
     let user_nick_or_name = match user.nick_in(&ctx, guild_id).await {
@@ -68,7 +109,7 @@ pub async fn streak(
           .send(
             poise::CreateReply::default()
               .content(format!(
-                "{user_nick_or_name}'s current **private** meditation streak is {streak} days."
+                "{user_nick_or_name}'s current **private** meditation streak is {streak} days{restored}.{diary}"
               ))
               .ephemeral(true)
               .allowed_mentions(serenity::CreateAllowedMentions::new()),
@@ -96,7 +137,7 @@ pub async fn streak(
       .send(
         poise::CreateReply::default()
           .content(format!(
-            "{user_nick_or_name}'s current meditation streak is {streak} days."
+            "{user_nick_or_name}'s current meditation streak is {streak} days{restored}.{diary}"
           ))
           .ephemeral(privacy)
           .allowed_mentions(serenity::CreateAllowedMentions::new()),
@@ -109,10 +150,88 @@ pub async fn streak(
   ctx
     .send(
       poise::CreateReply::default()
-        .content(format!("Your current meditation streak is {streak} days."))
+        .content(format!(
+          "Your current meditation streak is {streak} days{restored}.{diary}"
+        ))
         .ephemeral(privacy),
     )
     .await?;
 
   Ok(())
 }
+
+/// Restore a broken streak with a streak credit
+///
+/// Spends a streak credit to count a missed day toward your meditation streak, joining your recent sessions to your earlier streak. Only a single missed day in the past 48 hours can be restored.
+///
+/// In servers that have turned streak restores on, you earn a streak credit for every 7 days of your streak, up to 3 at a time. Restored streaks are marked as restored on leaderboards.
+#[poise::command(slash_command)]
+pub async fn restore(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+  let user_id = ctx.author().id;
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+
+  if !DatabaseHandler::streak_restores_enabled(&mut transaction, &guild_id).await? {
+    ctx
+      .send(
+        poise::CreateReply::default()
+          .content(":x: Streak restores are not turned on in this server.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let credits = DatabaseHandler::get_streak_credits(&mut transaction, &guild_id, &user_id).await?;
+  if credits < 1 {
+    ctx
+      .send(
+        poise::CreateReply::default()
+          .content(format!(
+            ":x: You don't have any streak credits. You earn one for every {STREAK_CREDIT_DAYS} days of your streak, up to {MAX_STREAK_CREDITS} at a time."
+          ))
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let Some(days_ago) = DatabaseHandler::get_restorable_streak_day(
+    &mut transaction,
+    &guild_id,
+    &user_id,
+    STREAK_RESTORE_DAYS,
+  )
+  .await?
+  else {
+    ctx
+      .send(
+        poise::CreateReply::default()
+          .content(":x: There's no missed day to restore. Only a single missed day in the past 48 hours can be restored, and only if it joins your recent sessions to an earlier streak.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  DatabaseHandler::restore_streak_day(&mut transaction, &guild_id, &user_id, days_ago).await?;
+  let streak = DatabaseHandler::get_streak(&mut transaction, &guild_id, &user_id).await?;
+
+  let remaining = credits - 1;
+  commit_and_say(
+    ctx,
+    transaction,
+    MessageType::TextOnly(format!(
+      ":white_check_mark: Your streak has been restored to **{streak} days**. You have {remaining} streak {} left.",
+      if remaining == 1 { "credit" } else { "credits" }
+    )),
+    true,
+  )
+  .await?;
+
+  Ok(())
+}
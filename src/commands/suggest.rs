@@ -1,7 +1,9 @@
+use crate::commands::helpers::cooldown::member_cooldown;
 use crate::config::{BloomBotEmbed, CHANNELS};
 use crate::Context;
 use anyhow::Result;
 use poise::serenity_prelude::{self as serenity, builder::*};
+use std::time::Duration;
 
 /// Submit an anonymous server suggestion
 ///
@@ -11,13 +13,16 @@ use poise::serenity_prelude::{self as serenity, builder::*};
 #[poise::command(
   slash_command,
   category = "Utilities",
-  member_cooldown = 3600,
   guild_only
 )]
 pub async fn suggest(
   ctx: Context<'_>,
   #[description = "The suggestion to add"] suggestion: String,
 ) -> Result<()> {
+  if !member_cooldown(ctx, "suggest", Duration::from_secs(3600)).await? {
+    return Ok(());
+  }
+
   // Log suggestion in staff channel
   let log_embed = BloomBotEmbed::new()
     .title("New Suggestion")
@@ -1,12 +1,20 @@
 use crate::commands::{commit_and_say, MessageType};
-use crate::database::DatabaseHandler;
+use crate::config::{BloomBotEmbed, CHANNELS};
+use crate::database::{DatabaseHandler, TermSuggestion};
 use crate::{Context, Data as AppData, Error as AppError};
 use anyhow::Result;
+use log::error;
 use pgvector;
-use poise::serenity_prelude as serenity;
+use poise::serenity_prelude::{self as serenity, builder::*};
 use poise::Modal;
 use std::cmp::Ordering;
 
+/// Number of terms sent to the embeddings API in a single request when reindexing.
+const REINDEX_BATCH_SIZE: i64 = 20;
+
+/// Largest pronunciation audio file accepted, in bytes.
+const MAX_PRONUNCIATION_AUDIO_SIZE: u32 = 1024 * 1024;
+
 #[derive(Debug, Modal)]
 #[name = "Add a new term"]
 struct AddTermModal {
@@ -45,6 +53,29 @@ struct UpdateTermModal {
   aliases: Option<String>,
 }
 
+pub async fn autocomplete_term(ctx: Context<'_>, partial: &str) -> Vec<String> {
+  let Some(guild_id) = ctx.guild_id() else {
+    return Vec::new();
+  };
+
+  let data = ctx.data();
+  let term_names = match data.term_names.get(&data.db, &guild_id).await {
+    Ok(term_names) => term_names,
+    Err(e) => {
+      error!("Error loading term names for autocomplete: {e}");
+      return Vec::new();
+    }
+  };
+
+  let partial = partial.to_lowercase();
+
+  term_names
+    .into_iter()
+    .filter(|name| name.to_lowercase().starts_with(&partial))
+    .take(25)
+    .collect()
+}
+
 pub async fn term_not_found(
   ctx: Context<'_>,
   transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
@@ -101,7 +132,7 @@ pub async fn term_not_found(
 
 /// Commands for managing glossary entries
 ///
-/// Commands to add, remove, or edit glossary entries.
+/// Commands to add, remove, or edit glossary entries, review terms suggested by members, or regenerate search embeddings.
 ///
 /// Requires `Manage Roles` permissions.
 #[poise::command(
@@ -109,7 +140,7 @@ pub async fn term_not_found(
   required_permissions = "MANAGE_ROLES",
   default_member_permissions = "MANAGE_ROLES",
   category = "Moderator Commands",
-  subcommands("add", "remove", "edit"),
+  subcommands("add", "remove", "edit", "review", "reindex"),
   subcommand_required,
   //hide_in_help,
   guild_only
@@ -175,6 +206,8 @@ pub async fn add(
       true,
     )
     .await?;
+
+    ctx.data().term_names.invalidate(&guild_id).await?;
   } else {
     ctx
       .send(
@@ -192,11 +225,38 @@ pub async fn add(
 /// Update an existing term in the glossary
 ///
 /// Updates an existing term in the glossary.
+///
+/// Optionally set a phonetic pronunciation or upload an audio file (up to 1 MB) demonstrating how the term is pronounced. These are shown when the term is looked up.
 #[poise::command(slash_command)]
 pub async fn edit(
   ctx: poise::ApplicationContext<'_, AppData, AppError>,
-  #[description = "The term to edit"] term_name: String,
+  #[description = "The term to edit"]
+  #[autocomplete = "autocomplete_term"]
+  term_name: String,
+  #[description = "Phonetic pronunciation of the term"] pronunciation: Option<String>,
+  #[description = "Audio file demonstrating the pronunciation of the term"]
+  pronunciation_audio: Option<serenity::Attachment>,
+  #[description = "Remove the existing pronunciation and audio (Defaults to false)"]
+  clear_pronunciation: Option<bool>,
 ) -> Result<()> {
+  if let Some(audio) = &pronunciation_audio {
+    let is_audio = audio
+      .content_type
+      .as_ref()
+      .is_some_and(|content_type| content_type.starts_with("audio"));
+
+    if !is_audio || audio.size > MAX_PRONUNCIATION_AUDIO_SIZE {
+      ctx
+        .send(
+          poise::CreateReply::default()
+            .content(":x: Pronunciation audio must be an audio file no larger than 1 MB.")
+            .ephemeral(true),
+        )
+        .await?;
+      return Ok(());
+    }
+  }
+
   let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
 
   // We unwrap here, because we know that the command is guild-only.
@@ -269,6 +329,38 @@ pub async fn edit(
     )
     .await?;
 
+    if clear_pronunciation.unwrap_or(false) {
+      DatabaseHandler::update_term_pronunciation(&mut transaction, &existing_term.id, None).await?;
+      DatabaseHandler::update_term_pronunciation_audio(
+        &mut transaction,
+        &existing_term.id,
+        None,
+        None,
+      )
+      .await?;
+    }
+
+    if let Some(pronunciation) = &pronunciation {
+      DatabaseHandler::update_term_pronunciation(
+        &mut transaction,
+        &existing_term.id,
+        Some(pronunciation.as_str()),
+      )
+      .await?;
+    }
+
+    if let Some(audio) = &pronunciation_audio {
+      let audio_data = audio.download().await?;
+
+      DatabaseHandler::update_term_pronunciation_audio(
+        &mut transaction,
+        &existing_term.id,
+        Some(audio_data.as_slice()),
+        Some(audio.filename.as_str()),
+      )
+      .await?;
+    }
+
     commit_and_say(
       poise::Context::Application(ctx),
       transaction,
@@ -276,6 +368,8 @@ pub async fn edit(
       true,
     )
     .await?;
+
+    ctx.data().term_names.invalidate(&guild_id).await?;
   } else {
     ctx
       .send(
@@ -296,7 +390,9 @@ pub async fn edit(
 #[poise::command(slash_command)]
 pub async fn remove(
   ctx: Context<'_>,
-  #[description = "The term to remove"] term: String,
+  #[description = "The term to remove"]
+  #[autocomplete = "autocomplete_term"]
+  term: String,
 ) -> Result<()> {
   let data = ctx.data();
 
@@ -325,5 +421,426 @@ pub async fn remove(
   )
   .await?;
 
+  data.term_names.invalidate(&guild_id).await?;
+
+  Ok(())
+}
+
+fn suggestion_embed(suggestion: &TermSuggestion, remaining: usize) -> CreateEmbed {
+  let mut embed = BloomBotEmbed::new()
+    .title(format!("Term Suggestion: {}", suggestion.term_name))
+    .description(suggestion.meaning.clone())
+    .field("Suggested by", format!("<@{}>", suggestion.user_id), true)
+    .field(
+      "Date",
+      suggestion.created_at.format("%B %d, %Y").to_string(),
+      true,
+    )
+    .footer(CreateEmbedFooter::new(format!(
+      "{remaining} pending suggestion(s)"
+    )));
+
+  if let Some(usage) = &suggestion.usage {
+    embed = embed.field("Example of Usage:", usage, false);
+  }
+
+  if let Some(links) = &suggestion.links {
+    if !links.is_empty() {
+      embed = embed.field("Related Resources:", links.join("\n"), false);
+    }
+  }
+
+  embed
+}
+
+async fn approve_suggestion(
+  ctx: Context<'_>,
+  guild_id: &serenity::GuildId,
+  suggestion: &TermSuggestion,
+  term_data: &UpdateTermModal,
+) -> Result<bool> {
+  let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+
+  if DatabaseHandler::term_exists(&mut transaction, guild_id, suggestion.term_name.as_str()).await?
+  {
+    return Ok(false);
+  }
+
+  let links: Vec<String> = match &term_data.links {
+    Some(links) => links.split(',').map(|s| s.trim().to_string()).collect(),
+    None => Vec::new(),
+  };
+
+  let aliases: Vec<String> = match &term_data.aliases {
+    Some(aliases) => aliases.split(',').map(|s| s.trim().to_string()).collect(),
+    None => Vec::new(),
+  };
+
+  let vector = pgvector::Vector::from(
+    ctx
+      .data()
+      .embeddings
+      .create_embedding(suggestion.term_name.clone(), ctx.author().id)
+      .await?,
+  );
+
+  DatabaseHandler::add_term(
+    &mut transaction,
+    suggestion.term_name.as_str(),
+    term_data.definition.as_str(),
+    term_data.example.as_deref(),
+    links.as_slice(),
+    term_data.category.as_deref(),
+    aliases.as_slice(),
+    guild_id,
+    vector,
+  )
+  .await?;
+
+  DatabaseHandler::review_term_suggestion(
+    &mut transaction,
+    &suggestion.id,
+    "approved",
+    &ctx.author().id,
+  )
+  .await?;
+
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  ctx.data().term_names.invalidate(guild_id).await?;
+
+  Ok(true)
+}
+
+async fn log_review(ctx: Context<'_>, suggestion: &TermSuggestion, outcome: &str) -> Result<()> {
+  let log_embed = BloomBotEmbed::new()
+    .title(format!("Term Suggestion {outcome}"))
+    .description(format!(
+      "**Term**: {}\n**Suggested by**: <@{}>",
+      suggestion.term_name, suggestion.user_id
+    ))
+    .footer(
+      CreateEmbedFooter::new(format!(
+        "{} by {} ({})",
+        outcome,
+        ctx.author().name,
+        ctx.author().id
+      ))
+      .icon_url(ctx.author().avatar_url().unwrap_or_default()),
+    );
+
+  let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+
+  log_channel
+    .send_message(ctx, CreateMessage::new().embed(log_embed))
+    .await?;
+
+  Ok(())
+}
+
+/// Review terms suggested by members
+///
+/// Shows pending glossary term suggestions one at a time, with the option to approve, edit, reject, or skip each suggestion.
+///
+/// Approved suggestions are added to the glossary. Editing a suggestion allows you to revise it before it is added.
+#[poise::command(slash_command)]
+pub async fn review(ctx: Context<'_>) -> Result<()> {
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let suggestions =
+    DatabaseHandler::get_pending_term_suggestions(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  if suggestions.is_empty() {
+    ctx
+      .send(
+        poise::CreateReply::default()
+          .content("There are no pending term suggestions.")
+          .ephemeral(true),
+      )
+      .await?;
+    return Ok(());
+  }
+
+  let ctx_id = ctx.id();
+  let approve_id = format!("{ctx_id}approve");
+  let edit_id = format!("{ctx_id}edit");
+  let reject_id = format!("{ctx_id}reject");
+  let skip_id = format!("{ctx_id}skip");
+
+  let review_buttons = vec![CreateActionRow::Buttons(vec![
+    CreateButton::new(approve_id.clone())
+      .label("Approve")
+      .style(serenity::ButtonStyle::Success),
+    CreateButton::new(edit_id.clone())
+      .label("Edit")
+      .style(serenity::ButtonStyle::Primary),
+    CreateButton::new(reject_id.clone())
+      .label("Reject")
+      .style(serenity::ButtonStyle::Danger),
+    CreateButton::new(skip_id.clone())
+      .label("Skip")
+      .style(serenity::ButtonStyle::Secondary),
+  ])];
+
+  let mut current = 0;
+
+  let reply = ctx
+    .send(
+      poise::CreateReply::default()
+        .embed(suggestion_embed(&suggestions[current], suggestions.len()))
+        .components(review_buttons.clone())
+        .ephemeral(true),
+    )
+    .await?;
+
+  // Loop through incoming interactions with the review buttons
+  while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+    // We defined our button IDs to start with `ctx_id`. If they don't, some other command's
+    // button was pressed
+    .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+    // Timeout when no button has been pressed for 10 minutes
+    .timeout(std::time::Duration::from_secs(600))
+    .await
+  {
+    let suggestion = &suggestions[current];
+
+    let status = if press.data.custom_id == approve_id {
+      press
+        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+        .await?;
+
+      let term_data = UpdateTermModal {
+        definition: suggestion.meaning.clone(),
+        example: suggestion.usage.clone(),
+        category: None,
+        links: suggestion.links.as_ref().map(|links| links.join(", ")),
+        aliases: None,
+      };
+
+      if approve_suggestion(ctx, &guild_id, suggestion, &term_data).await? {
+        log_review(ctx, suggestion, "Approved").await?;
+        format!(
+          ":white_check_mark: **{}** has been added to the glossary.",
+          suggestion.term_name
+        )
+      } else {
+        format!(
+          ":x: The glossary already has an entry for **{}**.",
+          suggestion.term_name
+        )
+      }
+    } else if press.data.custom_id == edit_id {
+      let defaults = UpdateTermModal {
+        definition: suggestion.meaning.clone(),
+        example: suggestion.usage.clone(),
+        category: None,
+        links: suggestion.links.as_ref().map(|links| links.join(", ")),
+        aliases: None,
+      };
+
+      let Some(term_data) = poise::execute_modal_on_component_interaction::<UpdateTermModal>(
+        ctx,
+        press.clone(),
+        Some(defaults),
+        None,
+      )
+      .await?
+      else {
+        // Leave the current suggestion on display if the modal was dismissed
+        continue;
+      };
+
+      if approve_suggestion(ctx, &guild_id, suggestion, &term_data).await? {
+        log_review(ctx, suggestion, "Approved").await?;
+        format!(
+          ":white_check_mark: **{}** has been edited and added to the glossary.",
+          suggestion.term_name
+        )
+      } else {
+        format!(
+          ":x: The glossary already has an entry for **{}**.",
+          suggestion.term_name
+        )
+      }
+    } else if press.data.custom_id == reject_id {
+      press
+        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+        .await?;
+
+      let mut transaction = data.db.start_transaction_with_retry(5).await?;
+      DatabaseHandler::review_term_suggestion(
+        &mut transaction,
+        &suggestion.id,
+        "rejected",
+        &ctx.author().id,
+      )
+      .await?;
+      DatabaseHandler::commit_transaction(transaction).await?;
+
+      log_review(ctx, suggestion, "Rejected").await?;
+      format!(
+        ":white_check_mark: Suggestion for **{}** has been rejected.",
+        suggestion.term_name
+      )
+    } else if press.data.custom_id == skip_id {
+      press
+        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+        .await?;
+
+      format!("Skipped **{}**.", suggestion.term_name)
+    } else {
+      // This is an unrelated button interaction
+      continue;
+    };
+
+    current += 1;
+
+    if current >= suggestions.len() {
+      reply
+        .edit(
+          ctx,
+          poise::CreateReply::default()
+            .content(format!(
+              "{status}\nThere are no more pending term suggestions."
+            ))
+            .components(Vec::new()),
+        )
+        .await?;
+      return Ok(());
+    }
+
+    reply
+      .edit(
+        ctx,
+        poise::CreateReply::default()
+          .content(status)
+          .embed(suggestion_embed(
+            &suggestions[current],
+            suggestions.len() - current,
+          ))
+          .components(review_buttons.clone()),
+      )
+      .await?;
+  }
+
+  // This happens when the user didn't press any button for 10 minutes
+  reply
+    .edit(
+      ctx,
+      poise::CreateReply::default()
+        .embed(suggestion_embed(
+          &suggestions[current],
+          suggestions.len() - current,
+        ))
+        .components(Vec::new()),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Regenerate search embeddings for all terms
+///
+/// Regenerates the search embeddings for all terms in the glossary. Useful after changing embedding models or if search results seem inaccurate.
+///
+/// Terms are processed in batches. If reindexing is interrupted, run the command again with `resume` set to true to continue where it left off.
+#[poise::command(slash_command)]
+pub async fn reindex(
+  ctx: Context<'_>,
+  #[description = "Continue an interrupted reindex instead of starting over (Defaults to false)"]
+  resume: Option<bool>,
+) -> Result<()> {
+  ctx.defer_ephemeral().await?;
+
+  let data = ctx.data();
+
+  // We unwrap here, because we know that the command is guild-only.
+  let guild_id = ctx.guild_id().unwrap();
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  if !resume.unwrap_or(false) {
+    DatabaseHandler::reset_term_reindex_progress(&mut transaction, &guild_id).await?;
+  }
+  let pending = DatabaseHandler::get_pending_reindex_count(&mut transaction, &guild_id).await?;
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  if pending == 0 {
+    ctx
+      .say(":white_check_mark: There are no terms left to reindex.")
+      .await?;
+    return Ok(());
+  }
+
+  let reply = ctx.say(format!("Reindexing {pending} term(s)...")).await?;
+
+  let mut processed = 0;
+
+  loop {
+    let mut transaction = data.db.start_transaction_with_retry(5).await?;
+    let terms =
+      DatabaseHandler::get_terms_pending_reindex(&mut transaction, &guild_id, REINDEX_BATCH_SIZE)
+        .await?;
+
+    if terms.is_empty() {
+      break;
+    }
+
+    let names = terms.iter().map(|term| term.name.clone()).collect();
+    let embeddings = match data
+      .embeddings
+      .create_embeddings(names, ctx.author().id)
+      .await
+    {
+      Ok(embeddings) => embeddings,
+      Err(e) => {
+        reply
+          .edit(
+            ctx,
+            poise::CreateReply::default().content(format!(
+              ":x: Reindexing was interrupted after {processed} of {pending} term(s). Use `/terms reindex` with `resume` set to true to continue."
+            )),
+          )
+          .await?;
+        return Err(e);
+      }
+    };
+
+    for (term, embedding) in terms.iter().zip(embeddings) {
+      DatabaseHandler::update_term_embedding(
+        &mut transaction,
+        &term.id,
+        pgvector::Vector::from(embedding),
+      )
+      .await?;
+    }
+
+    DatabaseHandler::commit_transaction(transaction).await?;
+
+    processed += terms.len();
+
+    reply
+      .edit(
+        ctx,
+        poise::CreateReply::default().content(format!(
+          "Reindexing terms... ({processed} of {pending} complete)"
+        )),
+      )
+      .await?;
+  }
+
+  reply
+    .edit(
+      ctx,
+      poise::CreateReply::default().content(format!(
+        ":white_check_mark: Successfully reindexed {processed} term(s)."
+      )),
+    )
+    .await?;
+
   Ok(())
 }
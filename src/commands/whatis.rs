@@ -1,8 +1,9 @@
+use crate::commands::terms::autocomplete_term;
 use crate::commands::BloomBotEmbed;
 use crate::database::DatabaseHandler;
 use crate::Context;
 use anyhow::Result;
-use poise::serenity_prelude::CreateEmbedFooter;
+use poise::serenity_prelude::{CreateAttachment, CreateEmbedFooter};
 
 /// See information about a term
 ///
@@ -10,7 +11,9 @@ use poise::serenity_prelude::CreateEmbedFooter;
 #[poise::command(slash_command, category = "Informational", guild_only)]
 pub async fn whatis(
   ctx: Context<'_>,
-  #[description = "The term to show information about"] term: String,
+  #[description = "The term to show information about"]
+  #[autocomplete = "autocomplete_term"]
+  term: String,
 ) -> Result<()> {
   let guild_id = ctx.guild_id().unwrap();
 
@@ -19,8 +22,20 @@ pub async fn whatis(
   let term_info = DatabaseHandler::get_term(&mut transaction, &guild_id, term.as_str()).await?;
   let mut embed = BloomBotEmbed::new();
 
+  let mut audio_attachment = None;
+
   if let Some(term_info) = term_info {
-    embed = embed.title(term_info.name);
+    let pronunciation =
+      DatabaseHandler::get_term_pronunciation(&mut transaction, &term_info.id).await?;
+
+    if let (Some(audio), Some(audio_name)) = (pronunciation.audio, pronunciation.audio_name) {
+      audio_attachment = Some(CreateAttachment::bytes(audio, audio_name));
+    }
+
+    embed = match pronunciation.phonetic {
+      Some(phonetic) => embed.title(format!("{} ({phonetic})", term_info.name)),
+      None => embed.title(term_info.name),
+    };
     match term_info.meaning.split_once('\n') {
       Some(one_liner) => {
         embed = embed.description(format!(
@@ -109,12 +124,16 @@ pub async fn whatis(
     }
   }
 
-  ctx
-    .send(poise::CreateReply {
-      embeds: vec![embed],
-      ..Default::default()
-    })
-    .await?;
+  let mut reply = poise::CreateReply {
+    embeds: vec![embed],
+    ..Default::default()
+  };
+
+  if let Some(audio_attachment) = audio_attachment {
+    reply = reply.attachment(audio_attachment);
+  }
+
+  ctx.send(reply).await?;
 
   Ok(())
 }
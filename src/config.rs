@@ -3,6 +3,199 @@ use poise::serenity_prelude::{self as serenity, Embed, Guild, Member, RoleId};
 pub const EMBED_COLOR: u32 = 0xFDAC2E;
 pub const TERMS_PER_PAGE: usize = 10;
 pub const MIN_STARS: u64 = 5;
+/// How long logged message edits are kept, in days.
+pub const EDIT_LOG_RETENTION_DAYS: i64 = 30;
+/// Minimum number of changed characters for an edit to be logged.
+pub const MIN_EDIT_LOG_CHANGE: usize = 5;
+/// Days without activity before a private notification thread is archived.
+pub const NOTIFICATION_THREAD_ARCHIVE_DAYS: i64 = 7;
+/// Days without activity before a private notification thread is deleted.
+pub const NOTIFICATION_THREAD_DELETE_DAYS: i64 = 30;
+/// Minimum time between sessions logged with the same quick-log link, in minutes.
+pub const QUICKLOG_COOLDOWN_MINUTES: i64 = 10;
+/// Longest session that can be logged with `/sit stop`, in minutes. Longer sessions were most likely never stopped.
+pub const SIT_MAX_MINUTES: i64 = 360;
+/// Shortest time in a tracked voice channel that is added as a session, in minutes. Shorter visits are usually just passing through.
+pub const VC_MIN_MINUTES: i64 = 5;
+/// Variables that can be used in the names of automatically created discussion threads.
+pub const AUTO_THREAD_VARIABLES: &[&str] = &["author", "channel", "content", "date", "weekday"];
+/// Name used for automatically created discussion threads when no pattern is chosen.
+pub const DEFAULT_AUTO_THREAD_NAME: &str = "{author}'s post on {date}";
+/// Message posted with the weekly check-in button when no message is chosen.
+pub const DEFAULT_CHECKIN_MESSAGE: &str =
+  "How has your practice been this week? Press the button below to check in.";
+/// Sessions added in a guild between announcements of its total hours, when no interval is chosen.
+pub const DEFAULT_HOURS_INTERVAL: i32 = 10;
+/// Message announcing a guild's total hours, when no message is chosen.
+pub const DEFAULT_HOURS_MESSAGE: &str =
+  "Awesome sauce! This server has collectively generated {hours} hours of realmbreaking meditation!";
+/// Variables that can be used in the message announcing a guild's total hours.
+pub const HOURS_MESSAGE_VARIABLES: &[&str] = &["hours", "minutes", "sessions"];
+/// Streak days needed to earn a streak credit, in servers that turned on streak restores.
+pub const STREAK_CREDIT_DAYS: u64 = 7;
+/// Most streak credits a member can hold at once.
+pub const MAX_STREAK_CREDITS: i64 = 3;
+/// Days ago a missed day can be, and still be restored with a streak credit.
+pub const STREAK_RESTORE_DAYS: i32 = 2;
+/// Minutes of meditation needed during the month to complete the monthly challenge.
+pub const CHALLENGE_GOAL_MINUTES: i64 = 30;
+/// Sessions needed during the month to complete the monthly challenge.
+pub const CHALLENGE_GOAL_SESSIONS: i64 = 8;
+/// Day of the month when monthly challenge participants who opted in get a progress reminder.
+pub const CHALLENGE_REMINDER_DAY: u32 = 15;
+/// How often the bot's activity status is rotated, in seconds.
+pub const STATUS_ROTATION_INTERVAL: u64 = 300;
+/// Messages the bot's activity status rotates between, in order.
+pub const STATUS_ROTATION: &[StatusMessage] = &[
+  StatusMessage::HoursMeditated,
+  StatusMessage::MeditatorCount,
+  StatusMessage::QuoteSnippet,
+];
+/// Features that can be turned on or off per server with `/manage flags`.
+pub const FEATURE_FLAGS: &[FeatureFlag] = &[
+  FeatureFlag {
+    name: "growth_stats",
+    description: "Staff can see member growth and retention with /stats growth",
+    default: true,
+  },
+  FeatureFlag {
+    name: "leaderboard_avatars",
+    description: "Member avatars are shown on posted final leaderboards",
+    default: true,
+  },
+];
+
+/// Commands that write to the database, which are blocked while maintenance mode is on.
+/// Listing a command also blocks its subcommands.
+pub const WRITE_COMMANDS: &[&str] = &[
+  "add",
+  "remove",
+  "erase message",
+  "erase populate",
+  "customize settings",
+  "customize reaction",
+  "customize quotes",
+  "customize quiet",
+  "customize thread reset",
+  "customize quicklog",
+  "customize healthsync",
+  "streak restore",
+  "challenge join",
+  "challenge leave",
+  "checkin mood",
+  "sit",
+  "journal",
+  "import start",
+  "import resume",
+  "manage create",
+  "manage update",
+  "manage delete",
+  "manage reset",
+  "manage migrate",
+  "manage permissions restrict",
+  "manage permissions unrestrict",
+  "manage restore",
+  "manage reasons add",
+  "manage reasons edit",
+  "manage reasons remove",
+  "manage editlog watch",
+  "manage editlog unwatch",
+  "manage logging filters",
+  "manage logging ignore",
+  "manage logging unignore",
+  "manage limits set",
+  "manage emojis set",
+  "manage emojis reset",
+  "manage anomalies",
+  "manage setup",
+  "manage partners link",
+  "manage partners unlink",
+  "manage webhooks add",
+  "manage webhooks remove",
+  "manage adjust shift",
+  "manage adjust dedupe",
+  "manage retention set",
+  "manage retention enable",
+  "manage retention disable",
+  "manage prefix",
+  "manage voice track",
+  "manage voice untrack",
+  "manage starboard forum",
+  "manage starboard tag",
+  "manage starboard untag",
+  "manage starboard deleted",
+  "manage announce schedule",
+  "manage announce cancel",
+  "manage templates add",
+  "manage templates edit",
+  "manage templates remove",
+  "manage post",
+  "manage threads set",
+  "manage threads remove",
+  "manage checkin set",
+  "manage checkin remove",
+  "manage collective start",
+  "manage collective end",
+  "manage milestones set",
+  "manage milestones reset",
+  "manage restores",
+  "manage charts",
+  "manage rollover set",
+  "manage rollover remove",
+  "manage trash restore",
+  "manage trash purge",
+  "manage stats request",
+  "manage tracking add",
+  "manage tracking remove",
+  "manage tracking default",
+  "manage synccommands exclude",
+  "manage flags set",
+  "manage flags reset",
+  "manage synccommands include",
+  "quotes add",
+  "quotes edit",
+  "quotes remove",
+  "quotes background",
+  "quotes confirmations",
+  "add_quote",
+  "terms add",
+  "terms edit",
+  "terms remove",
+  "terms review",
+  "terms reindex",
+  "glossary suggest",
+  "course add",
+  "course edit",
+  "course remove",
+  "course reminders set",
+  "course reminders remove",
+  "keys add",
+  "keys remove",
+  "keys use",
+  "keys recipients update",
+  "community_sit bells set",
+  "community_sit bells remove",
+  "pickwinner",
+];
+
+/// Kinds of messages that can be shown in the bot's activity status.
+#[derive(Debug, Clone, Copy)]
+pub enum StatusMessage {
+  /// Total hours meditated across all servers.
+  HoursMeditated,
+  /// Number of members who have tracked at least one meditation.
+  MeditatorCount,
+  /// A snippet of a random quote.
+  QuoteSnippet,
+}
+
+/// A feature that can be rolled out one server at a time, such as a new or experimental subsystem.
+pub struct FeatureFlag {
+  pub name: &'static str,
+  pub description: &'static str,
+  /// Whether the feature is on in servers that haven't turned it on or off.
+  pub default: bool,
+}
 
 /// Sensible defaults for use within our application.
 pub struct BloomBotEmbed {}
@@ -45,6 +238,7 @@ pub struct Channels {
   pub reportchannel: u64,
   pub donators: u64,
   pub suggestion: u64,
+  pub lounge: u64,
 }
 
 pub const CHANNELS: Channels = Channels {
@@ -56,6 +250,7 @@ pub const CHANNELS: Channels = Channels {
   reportchannel: 855894610001395743,
   donators: 551895169532952578,
   suggestion: 553676378621476887,
+  lounge: 501464482996944909,
 };
 
 pub struct Emotes<'a> {
@@ -68,6 +263,311 @@ pub const EMOTES: Emotes = Emotes {
   report: 852463521894629376,
 };
 
+/// Emoji used in bot messages. Servers can replace these with their own custom emoji using `/manage emojis`.
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum EmojiKind {
+  #[name = "info"]
+  Info,
+  #[name = "love"]
+  Love,
+  #[name = "welcome"]
+  Welcome,
+  #[name = "celebrate"]
+  Celebrate,
+}
+
+impl EmojiKind {
+  /// Name used to store the emoji in the database.
+  pub fn key(self) -> &'static str {
+    match self {
+      Self::Info => "info",
+      Self::Love => "love",
+      Self::Welcome => "welcome",
+      Self::Celebrate => "celebrate",
+    }
+  }
+
+  /// Unicode emoji used when a server has not configured its own.
+  pub fn fallback(self) -> &'static str {
+    match self {
+      Self::Info => "ℹ️",
+      Self::Love => "❤️",
+      Self::Welcome => "🤗",
+      Self::Celebrate => "✨",
+    }
+  }
+}
+
+/// Kinds of logged data that servers can purge after a retention period using `/manage retention`.
+///
+/// Data without a category, such as meditation entries, is kept until it is removed by hand.
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum RetentionCategory {
+  #[name = "erase logs"]
+  Erases,
+  #[name = "message edit logs"]
+  MessageEdits,
+  #[name = "command usage"]
+  CommandUsage,
+  #[name = "reviewed anomaly flags"]
+  AnomalyFlags,
+}
+
+impl RetentionCategory {
+  pub const ALL: [Self; 4] = [
+    Self::Erases,
+    Self::MessageEdits,
+    Self::CommandUsage,
+    Self::AnomalyFlags,
+  ];
+
+  /// Name used to store the policy in the database.
+  pub fn key(self) -> &'static str {
+    match self {
+      Self::Erases => "erases",
+      Self::MessageEdits => "message_edits",
+      Self::CommandUsage => "command_usage",
+      Self::AnomalyFlags => "anomaly_flags",
+    }
+  }
+
+  pub fn from_key(key: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|category| category.key() == key)
+  }
+}
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum BellSound {
+  #[name = "singing bowl"]
+  Bowl,
+  #[name = "chime"]
+  Chime,
+}
+
+impl BellSound {
+  pub const ALL: [Self; 2] = [Self::Bowl, Self::Chime];
+
+  /// Name used to store the sound in the database.
+  pub fn key(self) -> &'static str {
+    match self {
+      Self::Bowl => "bowl",
+      Self::Chime => "chime",
+    }
+  }
+
+  pub fn from_key(key: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|sound| sound.key() == key)
+  }
+}
+
+/// Colors used for a guild's charts, chosen with `/manage charts`.
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum ChartPalette {
+  #[name = "default"]
+  Default,
+  #[name = "high contrast"]
+  HighContrast,
+  #[name = "colorblind-safe"]
+  ColorblindSafe,
+}
+
+impl ChartPalette {
+  pub const ALL: [Self; 3] = [Self::Default, Self::HighContrast, Self::ColorblindSafe];
+
+  /// Name used to store the palette in the database.
+  pub fn key(self) -> &'static str {
+    match self {
+      Self::Default => "default",
+      Self::HighContrast => "high_contrast",
+      Self::ColorblindSafe => "colorblind_safe",
+    }
+  }
+
+  pub fn from_key(key: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|palette| palette.key() == key)
+  }
+}
+
+/// Where a meditation entry was logged from, shown in `/recent` and used to filter `/stats`.
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum EntrySource {
+  #[name = "slash command"]
+  Slash,
+  #[name = "voice channel"]
+  Voice,
+  #[name = "import"]
+  Import,
+  #[name = "API"]
+  Api,
+  #[name = "timer"]
+  Timer,
+}
+
+impl EntrySource {
+  pub const ALL: [Self; 5] = [
+    Self::Slash,
+    Self::Voice,
+    Self::Import,
+    Self::Api,
+    Self::Timer,
+  ];
+
+  /// Name used to store the source in the database.
+  pub fn key(self) -> &'static str {
+    match self {
+      Self::Slash => "slash",
+      Self::Voice => "vc",
+      Self::Import => "import",
+      Self::Api => "api",
+      Self::Timer => "timer",
+    }
+  }
+
+  pub fn from_key(key: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|source| source.key() == key)
+  }
+}
+
+/// How `/import` handles rows that overlap entries the member already has.
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum ImportConflict {
+  #[name = "skip duplicates"]
+  Skip,
+  #[name = "replace overlapping days"]
+  Replace,
+  #[name = "merge duplicates"]
+  Merge,
+}
+
+impl ImportConflict {
+  pub const ALL: [Self; 3] = [Self::Skip, Self::Replace, Self::Merge];
+
+  /// Name used to store the strategy in the database.
+  pub fn key(self) -> &'static str {
+    match self {
+      Self::Skip => "skip",
+      Self::Replace => "replace",
+      Self::Merge => "merge",
+    }
+  }
+
+  pub fn from_key(key: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|strategy| strategy.key() == key)
+  }
+}
+
+/// Where a member is congratulated on a new time or streak role, chosen in `/customize settings`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum CongratsDelivery {
+  #[name = "public"]
+  Public,
+  #[name = "private"]
+  Private,
+  #[name = "off"]
+  Off,
+}
+
+impl CongratsDelivery {
+  pub const ALL: [Self; 3] = [Self::Public, Self::Private, Self::Off];
+
+  /// Name used to store the setting in the database.
+  pub fn key(self) -> &'static str {
+    match self {
+      Self::Public => "public",
+      Self::Private => "private",
+      Self::Off => "off",
+    }
+  }
+
+  pub fn from_key(key: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|delivery| delivery.key() == key)
+  }
+}
+
+/// Events recorded in the access log for staff views of a member's private stats.
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum StatsAccessAction {
+  #[name = "requested"]
+  Requested,
+  #[name = "approved"]
+  Approved,
+  #[name = "denied"]
+  Denied,
+  #[name = "viewed"]
+  Viewed,
+}
+
+impl StatsAccessAction {
+  pub const ALL: [Self; 4] = [Self::Requested, Self::Approved, Self::Denied, Self::Viewed];
+
+  /// Name used to store the action in the database.
+  pub fn key(self) -> &'static str {
+    match self {
+      Self::Requested => "requested",
+      Self::Approved => "approved",
+      Self::Denied => "denied",
+      Self::Viewed => "viewed",
+    }
+  }
+
+  pub fn from_key(key: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|action| action.key() == key)
+  }
+}
+
+/// Calendar periods whose final leaderboard can be posted when they end, using `/manage rollover`.
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum LeaderboardPeriod {
+  #[name = "weekly"]
+  Weekly,
+  #[name = "monthly"]
+  Monthly,
+}
+
+impl LeaderboardPeriod {
+  pub const ALL: [Self; 2] = [Self::Weekly, Self::Monthly];
+
+  /// Name used to store the period in the database.
+  pub fn key(self) -> &'static str {
+    match self {
+      Self::Weekly => "weekly",
+      Self::Monthly => "monthly",
+    }
+  }
+
+  pub fn from_key(key: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|period| period.key() == key)
+  }
+}
+
+/// What happens to a starboard post when the starred message is deleted.
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum StarboardCleanup {
+  #[name = "remove the post"]
+  Remove,
+  #[name = "mark the post as deleted"]
+  Mark,
+  #[name = "keep the post"]
+  Keep,
+}
+
+impl StarboardCleanup {
+  pub const ALL: [Self; 3] = [Self::Remove, Self::Mark, Self::Keep];
+
+  /// Name used to store the setting in the database.
+  pub fn key(self) -> &'static str {
+    match self {
+      Self::Remove => "remove",
+      Self::Mark => "mark",
+      Self::Keep => "keep",
+    }
+  }
+
+  pub fn from_key(key: &str) -> Option<Self> {
+    Self::ALL.into_iter().find(|cleanup| cleanup.key() == key)
+  }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum TimeSumRoles {
   One,
@@ -0,0 +1,86 @@
+use crate::commands::helpers::time::last_weekly_time;
+use crate::config::BloomBotEmbed;
+use crate::database::DatabaseHandler;
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, Utc};
+use log::{error, info};
+use poise::serenity_prelude::{self as serenity, CreateMessage, Mentionable};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often course reminders are checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 5);
+/// Hours after its scheduled time that a reminder can still be sent, such as after a restart. Later than this, the week is skipped.
+const MAX_DELAY_HOURS: i64 = 6;
+
+/// Sends weekly course announcements to participants, and removes reminders once their course has ended.
+///
+/// Runs until the bot shuts down.
+pub async fn send_course_reminders(http: Arc<serenity::Http>, db: DatabaseHandler) {
+  let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(e) = send_due_reminders(&http, &db).await {
+      error!("Error while sending course reminders: {e:?}");
+    }
+  }
+}
+
+async fn send_due_reminders(http: &serenity::Http, db: &DatabaseHandler) -> Result<()> {
+  let now = Utc::now();
+
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let ended = DatabaseHandler::remove_ended_course_reminders(&mut transaction).await?;
+  let reminders = DatabaseHandler::get_course_reminders(&mut transaction, None).await?;
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  if ended > 0 {
+    info!("Removed {ended} course reminders for courses that have ended");
+  }
+
+  for reminder in reminders {
+    let due_at = last_weekly_time(reminder.weekday, reminder.send_minute, now);
+
+    if reminder
+      .last_sent_at
+      .is_some_and(|sent_at| sent_at >= due_at)
+      || now - due_at > ChronoDuration::hours(MAX_DELAY_HOURS)
+      || due_at.date_naive() > reminder.ends_on
+    {
+      continue;
+    }
+
+    // Marked as sent first, so a failing channel doesn't get retried every few minutes
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    DatabaseHandler::mark_course_reminder_sent(&mut transaction, &reminder.id, now).await?;
+    DatabaseHandler::commit_transaction(transaction).await?;
+
+    let result = reminder
+      .channel_id
+      .send_message(
+        http,
+        CreateMessage::new()
+          .content(reminder.participant_role.mention().to_string())
+          .embed(
+            BloomBotEmbed::new()
+              .title(&reminder.course_name)
+              .description(&reminder.message),
+          )
+          .allowed_mentions(
+            serenity::CreateAllowedMentions::new().roles(vec![reminder.participant_role]),
+          ),
+      )
+      .await;
+
+    if let Err(e) = result {
+      error!(
+        "Could not send reminder for course {} in {}: {e:?}",
+        reminder.course_name, reminder.guild_id
+      );
+    }
+  }
+
+  Ok(())
+}
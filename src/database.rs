@@ -5,12 +5,22 @@
   clippy::too_many_arguments
 )]
 
+use crate::config::{
+  ChartPalette, CongratsDelivery, EntrySource, ImportConflict, LeaderboardPeriod,
+  RetentionCategory, StatsAccessAction, DEFAULT_HOURS_INTERVAL,
+};
 use crate::pagination::PageRow;
+use crate::streaks::{self, Activity};
 use anyhow::{Context, Result};
 use chrono::Utc;
-use futures::{stream::Stream, StreamExt, TryStreamExt};
+use futures::{stream::Stream, StreamExt};
 use log::{info, warn};
 use poise::serenity_prelude::{self as serenity, Mentionable};
+use poise::ChoiceParameter;
+use rand::Rng;
+use sqlx::ConnectOptions;
+use std::collections::BTreeMap;
+use std::str::FromStr;
 use ulid::Ulid;
 
 #[derive(Debug)]
@@ -20,11 +30,7 @@ struct Res {
   meditation_count: Option<i64>,
 }
 
-#[derive(Debug)]
-struct MeditationCountByDay {
-  days_ago: Option<f64>,
-}
-
+#[derive(Clone)]
 pub struct DatabaseHandler {
   pool: sqlx::PgPool,
 }
@@ -38,6 +44,15 @@ pub struct TrackingProfile {
   pub streaks_active: bool,
   pub streaks_private: bool,
   pub stats_private: bool,
+  pub congrats_cards: bool,
+  pub congrats: CongratsDelivery,
+  pub add_reaction: Option<String>,
+  /// Whether a random quote is added to `/add` confirmations. Follows the guild's default when not set.
+  pub add_quotes: Option<bool>,
+  /// Whether time spent in voice channels is added automatically.
+  pub vc_tracking: bool,
+  /// Whether stats charts default to light mode.
+  pub light_mode: bool,
 }
 
 //Default values for tracking customization
@@ -51,6 +66,12 @@ impl Default for TrackingProfile {
       streaks_active: true,
       streaks_private: false,
       stats_private: false,
+      congrats_cards: false,
+      congrats: CongratsDelivery::Public,
+      add_reaction: None,
+      add_quotes: None,
+      vc_tracking: true,
+      light_mode: false,
     }
   }
 }
@@ -60,6 +81,9 @@ pub struct UserStats {
   pub all_count: u64,
   pub timeframe_stats: TimeframeStats,
   pub streak: u64,
+  /// Talks attended in stage channels, which don't count as meditation.
+  pub talks_attended: i64,
+  pub talk_minutes: i64,
 }
 
 pub struct GuildStats {
@@ -68,7 +92,7 @@ pub struct GuildStats {
   pub timeframe_stats: TimeframeStats,
 }
 
-#[derive(poise::ChoiceParameter)]
+#[derive(Clone, Copy, Hash, poise::ChoiceParameter)]
 pub enum Timeframe {
   Yearly,
   Monthly,
@@ -76,12 +100,78 @@ pub enum Timeframe {
   Daily,
 }
 
-#[derive(Debug)]
+impl Timeframe {
+  /// Length of a single leaderboard period.
+  pub fn period(self) -> chrono::Duration {
+    match self {
+      Timeframe::Daily => chrono::Duration::days(1),
+      Timeframe::Weekly => chrono::Duration::weeks(1),
+      Timeframe::Monthly => chrono::Duration::days(30),
+      Timeframe::Yearly => chrono::Duration::days(365),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Hash)]
 pub struct TimeframeStats {
   pub sum: Option<i64>,
   pub count: Option<i64>,
 }
 
+/// A user's totals since a point in time.
+pub struct UserTotals {
+  pub user_id: serenity::UserId,
+  pub minutes: i64,
+  pub sessions: i64,
+}
+
+/// A user's totals for a leaderboard period, along with their minutes for the period before it.
+pub struct LeaderboardUser {
+  pub user_id: serenity::UserId,
+  pub minutes: i64,
+  pub sessions: i64,
+  /// Number of distinct days with at least one session.
+  pub days_active: i64,
+  pub previous_minutes: i64,
+}
+
+/// A user's current meditation streak, for the streak leaderboard.
+pub struct StreakLeaderboardUser {
+  pub user_id: serenity::UserId,
+  pub streak: u64,
+  /// Whether the streak includes a missed day restored with a streak credit.
+  pub restored: bool,
+}
+
+impl LeaderboardUser {
+  /// Increase in minutes compared to the previous period.
+  pub fn improvement(&self) -> i64 {
+    self.minutes - self.previous_minutes
+  }
+
+  /// Increase in minutes as a percentage of the previous period, or `None` if there were no minutes to compare against.
+  #[allow(clippy::cast_precision_loss)]
+  pub fn improvement_percent(&self) -> Option<f64> {
+    if self.previous_minutes == 0 {
+      None
+    } else {
+      Some(self.improvement() as f64 / self.previous_minutes as f64 * 100.0)
+    }
+  }
+}
+
+/// A partner guild linked for combined stats. Links only take effect once both guilds have linked each other.
+pub struct GuildLink {
+  pub partner_guild_id: serenity::GuildId,
+  pub mutual: bool,
+}
+
+/// Trailing average of daily minutes and session count.
+pub struct RollingAverage {
+  pub minutes: f64,
+  pub count: f64,
+}
+
 pub struct EraseData {
   pub id: String,
   pub user_id: serenity::UserId,
@@ -120,6 +210,8 @@ pub struct MeditationData {
   pub user_id: serenity::UserId,
   pub meditation_minutes: i32,
   pub occurred_at: chrono::DateTime<Utc>,
+  /// How the entry was logged, or `None` if it was logged before sources were recorded.
+  pub source: Option<EntrySource>,
 }
 
 impl PageRow for MeditationData {
@@ -134,15 +226,20 @@ impl PageRow for MeditationData {
   fn body(&self) -> String {
     let now = chrono::Utc::now();
 
+    let source = self
+      .source
+      .map(|source| format!("\nSource: {}", source.name()))
+      .unwrap_or_default();
+
     if now - self.occurred_at < chrono::Duration::days(1) {
       format!(
-        "Date: {}\nID: `{}`",
+        "Date: {}{source}\nID: `{}`",
         chrono_humanize::HumanTime::from(self.occurred_at),
         self.id
       )
     } else {
       format!(
-        "Date: `{}`\nID: `{}`",
+        "Date: `{}`{source}\nID: `{}`",
         self.occurred_at.format("%Y-%m-%d %H:%M"),
         self.id
       )
@@ -174,6 +271,13 @@ impl PageRow for QuoteData {
   }
 }
 
+/// How often a quote has been shown by `/quote` or after `/add`.
+pub struct QuoteUsage {
+  pub id: String,
+  pub quote: String,
+  pub times_shown: i32,
+}
+
 pub struct SteamKeyData {
   pub steam_key: String,
   pub used: bool,
@@ -246,6 +350,54 @@ impl PageRow for SteamKeyRecipientData {
   }
 }
 
+pub struct PrizeAward {
+  pub id: String,
+  pub user_id: serenity::UserId,
+  pub steam_key: String,
+  pub challenge: String,
+  pub status: String,
+  pub dm_channel_id: Option<serenity::ChannelId>,
+  pub dm_message_id: Option<serenity::MessageId>,
+  pub awarded_at: chrono::DateTime<Utc>,
+}
+
+/// A monthly challenge draw, recorded as it progresses so an interrupted draw can be resumed or rolled back.
+///
+/// States move from `key_reserved` to `announced`, `dm_sent`, and finally `confirmed` once the winner responds or the offer times out. Draws abandoned with `/pickwinner resume` are `rolled_back`.
+pub struct PrizeDraw {
+  pub id: String,
+  pub user_id: serenity::UserId,
+  pub challenge_start: chrono::DateTime<Utc>,
+  pub minutes: i64,
+  pub steam_key: String,
+  pub state: String,
+}
+
+impl PageRow for PrizeAward {
+  fn title(&self) -> String {
+    format!("__{}__", self.challenge)
+  }
+
+  fn alternate_title(&self) -> String {
+    self.title()
+  }
+
+  fn body(&self) -> String {
+    format!(
+      "Winner: {}\nStatus: {}\nAwarded: {}",
+      self.user_id.mention(),
+      match self.status.as_str() {
+        "sent" => "Key sent, awaiting response",
+        "accepted" => "Accepted",
+        "declined" => "Declined",
+        "expired" => "Expired",
+        status => status,
+      },
+      self.awarded_at.format("%B %d, %Y"),
+    )
+  }
+}
+
 pub struct CourseData {
   pub course_name: String,
   pub participant_role: serenity::RoleId,
@@ -319,6 +471,22 @@ impl PageRow for Term {
   }
 }
 
+pub struct TermPronunciation {
+  pub phonetic: Option<String>,
+  pub audio: Option<Vec<u8>>,
+  pub audio_name: Option<String>,
+}
+
+pub struct TermSuggestion {
+  pub id: String,
+  pub term_name: String,
+  pub meaning: String,
+  pub usage: Option<String>,
+  pub links: Option<Vec<String>>,
+  pub user_id: serenity::UserId,
+  pub created_at: chrono::DateTime<Utc>,
+}
+
 #[derive(Debug, sqlx::FromRow)]
 pub struct TermSearchResult {
   pub term_name: String,
@@ -331,24 +499,603 @@ pub struct TermNames {
   pub aliases: Option<Vec<String>>,
 }
 
+pub struct CommandUsageStats {
+  pub command_name: String,
+  pub uses: i64,
+  pub errors: i64,
+  pub median_latency: f64,
+  pub p95_latency: f64,
+}
+
+pub struct CommandRestriction {
+  pub command_name: String,
+  pub role_id: Option<serenity::RoleId>,
+  pub channel_id: Option<serenity::ChannelId>,
+}
+
+#[derive(Clone)]
+pub struct ModerationReason {
+  pub label: String,
+  pub reason: String,
+}
+
+/// A custom emoji configured for a server, replacing the default for [`crate::config::EmojiKind`].
+#[derive(Clone)]
+pub struct GuildEmoji {
+  pub name: String,
+  pub emoji: String,
+}
+
+/// A user whose time over the past day is far above their usual daily average.
+pub struct VolumeSpike {
+  pub guild_id: serenity::GuildId,
+  pub user_id: serenity::UserId,
+  pub recent_minutes: i64,
+  pub daily_average: f64,
+  pub entry_ids: Vec<String>,
+}
+
+/// A user who repeatedly added the same duration at near-constant intervals.
+pub struct RepeatedDuration {
+  pub guild_id: serenity::GuildId,
+  pub user_id: serenity::UserId,
+  pub meditation_minutes: i32,
+  pub entry_count: i64,
+  pub average_gap: f64,
+  pub entry_ids: Vec<String>,
+}
+
+pub struct AnomalyFlag {
+  pub id: String,
+  pub user_id: serenity::UserId,
+  pub kind: String,
+  pub details: String,
+  pub entry_ids: Vec<String>,
+  pub flagged_at: chrono::DateTime<Utc>,
+}
+
+/// Per-guild limits on the length of a single meditation entry.
+pub struct SessionLimits {
+  pub min_minutes: Option<i32>,
+  pub max_minutes: Option<i32>,
+  /// Entries longer than this require confirmation.
+  pub confirm_minutes: i32,
+  /// Most minutes per day counted toward leaderboards and challenges. Personal stats are never capped.
+  pub daily_cap_minutes: Option<i32>,
+}
+
+/// Rows returned by an owner diagnostics query, with every value as text.
+pub struct DiagnosticsQueryResult {
+  pub columns: Vec<String>,
+  pub rows: Vec<Vec<Option<String>>>,
+  /// Whether the query returned more rows than were kept.
+  pub truncated: bool,
+}
+
+impl Default for SessionLimits {
+  fn default() -> Self {
+    Self {
+      min_minutes: None,
+      max_minutes: None,
+      confirm_minutes: 300,
+      daily_cap_minutes: None,
+    }
+  }
+}
+
+/// How often a guild's total meditation hours are announced after `/add`, and what is announced.
+pub struct HoursAnnouncement {
+  /// Announced after every this many sessions added in the guild. Zero turns announcements off.
+  pub session_interval: i32,
+  /// Message with variables such as `{hours}`. The default message is used when not set.
+  pub message: Option<String>,
+}
+
+impl Default for HoursAnnouncement {
+  fn default() -> Self {
+    Self {
+      session_interval: DEFAULT_HOURS_INTERVAL,
+      message: None,
+    }
+  }
+}
+
+/// Channels set up for a guild with `/manage setup`.
+#[derive(Default)]
+pub struct GuildSettings {
+  pub tracking_channel_id: Option<serenity::ChannelId>,
+  pub log_channel_id: Option<serenity::ChannelId>,
+}
+
+/// An outbound webhook that receives guild milestone events.
+pub struct GuildWebhook {
+  pub id: String,
+  pub url: String,
+  pub created_at: chrono::DateTime<Utc>,
+}
+
+/// A link that logs a preset session when opened, for phone automations.
+pub struct QuickLogToken {
+  pub user_id: serenity::UserId,
+  pub guild_id: serenity::GuildId,
+  pub minutes: i32,
+  pub last_used_at: Option<chrono::DateTime<Utc>>,
+}
+
+/// A token that lets a phone automation push mindful minutes from Apple Health or Google Fit.
+pub struct HealthSyncToken {
+  pub user_id: serenity::UserId,
+  pub guild_id: serenity::GuildId,
+  pub last_synced_at: Option<chrono::DateTime<Utc>>,
+}
+
+/// How long a guild keeps one category of logged data before the nightly purge removes it.
+pub struct RetentionPolicy {
+  pub guild_id: serenity::GuildId,
+  pub category: RetentionCategory,
+  pub retention_days: i32,
+  pub enabled: bool,
+}
+
+/// A staff member's request to view a member's private stats, answered by the member.
+pub struct StatsAccessRequest {
+  pub guild_id: serenity::GuildId,
+  pub user_id: serenity::UserId,
+  pub staff_id: serenity::UserId,
+  pub reason: String,
+}
+
+pub struct StatsAccessLogEntry {
+  pub staff_id: serenity::UserId,
+  pub action: StatsAccessAction,
+  pub occurred_at: chrono::DateTime<Utc>,
+}
+
+/// Entries removed together by one delete, reset, anomaly cleanup or import, which can be restored until the trash is emptied.
+pub struct TrashBatch {
+  pub batch_id: String,
+  pub user_id: serenity::UserId,
+  pub reason: String,
+  pub deleted_by: serenity::UserId,
+  pub deleted_at: chrono::DateTime<Utc>,
+  pub entries: i64,
+  pub minutes: i64,
+}
+
+pub struct NotificationThread {
+  pub user_id: serenity::UserId,
+  pub thread_id: serenity::ChannelId,
+  pub created_at: chrono::DateTime<Utc>,
+}
+
+/// Times of day, in minutes after local midnight, during which a user's notifications are held back.
+///
+/// The end may be earlier than the start, for quiet hours that run past midnight.
+pub struct QuietHours {
+  pub start_minute: i16,
+  pub end_minute: i16,
+  pub utc_offset: i16,
+}
+
+pub struct QueuedNotification {
+  pub id: String,
+  pub user_id: serenity::UserId,
+  pub title: String,
+  pub description: String,
+  pub footer: Option<String>,
+  pub fields: Vec<(String, String)>,
+}
+
+/// A voice session saved so it survives a restart.
+///
+/// `last_seen_at` is refreshed while the bot is running, so a member who left while the bot was down is credited up to about when it stopped.
+pub struct SavedVoiceSession {
+  pub user_id: serenity::UserId,
+  pub channel_id: serenity::ChannelId,
+  pub joined_at: chrono::DateTime<Utc>,
+  pub counted_seconds: i32,
+  pub counting_since: Option<chrono::DateTime<Utc>>,
+  pub last_seen_at: chrono::DateTime<Utc>,
+}
+
+pub struct TrackedVoiceChannel {
+  pub channel_id: serenity::ChannelId,
+  /// Members needed in the channel before time counts, to encourage group sits.
+  pub min_participants: i16,
+}
+
+/// Bells rung in a voice channel during a scheduled community sit.
+pub struct SitBells {
+  pub event_id: serenity::ScheduledEventId,
+  /// Minutes between interval bells, if any are rung between the start and end bells.
+  pub interval_minutes: Option<i32>,
+  /// Minutes after the start when the ending bells are rung. Without one, they are rung when the event ends.
+  pub duration_minutes: Option<i32>,
+  pub sound: String,
+}
+
+/// A weekly announcement for a course's participants, such as a lesson release.
+pub struct CourseReminder {
+  pub id: String,
+  pub guild_id: serenity::GuildId,
+  pub course_name: String,
+  pub participant_role: serenity::RoleId,
+  pub channel_id: serenity::ChannelId,
+  /// Days from Monday.
+  pub weekday: i16,
+  /// Minutes after midnight UTC.
+  pub send_minute: i16,
+  pub message: String,
+  /// Last day announcements are sent. The reminder is removed after this day.
+  pub ends_on: chrono::NaiveDate,
+  pub last_sent_at: Option<chrono::DateTime<Utc>>,
+}
+
+/// A message staff scheduled to be posted once, or repeatedly on a cron schedule.
+pub struct Announcement {
+  pub id: String,
+  pub guild_id: serenity::GuildId,
+  pub channel_id: serenity::ChannelId,
+  pub created_by: serenity::UserId,
+  /// Embed title. Only used for announcements posted as an embed.
+  pub title: Option<String>,
+  pub content: String,
+  pub as_embed: bool,
+  /// Cron schedule for repeating announcements. `None` for one-off announcements.
+  pub repeat_schedule: Option<String>,
+  pub next_run_at: chrono::DateTime<Utc>,
+}
+
+/// A reusable message that staff can fill in and post with `/manage post`.
+pub struct MessageTemplate {
+  pub name: String,
+  /// Embed title. Only used for templates posted as an embed.
+  pub title: Option<String>,
+  pub content: String,
+  pub as_embed: bool,
+}
+
+/// A channel where the bot starts a discussion thread on each new message.
+#[derive(Clone)]
+pub struct AutoThreadChannel {
+  pub channel_id: serenity::ChannelId,
+  /// Thread name, with variables such as `{author}` filled in for each message.
+  pub name_pattern: String,
+}
+
+/// A guild's weekly check-in prompt, posted with a button members press to check in.
+pub struct CheckInPrompt {
+  pub id: String,
+  pub guild_id: serenity::GuildId,
+  pub channel_id: serenity::ChannelId,
+  /// Days from Monday.
+  pub weekday: i16,
+  /// Minutes after midnight UTC.
+  pub send_minute: i16,
+  pub message: String,
+  pub last_sent_at: Option<chrono::DateTime<Utc>>,
+}
+
+/// A member's place on a final leaderboard saved when its week or month ended.
+pub struct ArchivedLeaderboardUser {
+  pub user_id: serenity::UserId,
+  pub rank: i32,
+  pub minutes: i64,
+  pub sessions: i64,
+}
+
+/// Totals for everyone on a final leaderboard.
+#[derive(Clone, Copy)]
+pub struct ArchivedTotals {
+  pub members: i64,
+  pub minutes: i64,
+}
+
+/// A final leaderboard saved when its week or month ended, along with the totals for the same period a year earlier.
+pub struct ArchivedLeaderboard {
+  pub period: LeaderboardPeriod,
+  pub period_start: chrono::DateTime<Utc>,
+  pub totals: ArchivedTotals,
+  pub last_year: Option<ArchivedTotals>,
+  pub users: Vec<ArchivedLeaderboardUser>,
+}
+
+impl PageRow for ArchivedLeaderboard {
+  fn title(&self) -> String {
+    match self.period {
+      LeaderboardPeriod::Weekly => {
+        format!("__Week of {}__", self.period_start.format("%B %-d, %Y"))
+      }
+      LeaderboardPeriod::Monthly => format!("__{}__", self.period_start.format("%B %Y")),
+    }
+  }
+
+  fn alternate_title(&self) -> String {
+    self.title()
+  }
+
+  fn body(&self) -> String {
+    let mut lines = self
+      .users
+      .iter()
+      .map(|user| {
+        format!(
+          "**{}.** {} — {} minutes, {} sessions",
+          user.rank,
+          user.user_id.mention(),
+          user.minutes,
+          user.sessions
+        )
+      })
+      .collect::<Vec<String>>();
+
+    lines.push(format!(
+      "{} members meditated for {} minutes.",
+      self.totals.members, self.totals.minutes
+    ));
+
+    if let Some(last_year) = self.last_year {
+      lines.push(format!(
+        "This time last year: {} members, {} minutes.",
+        last_year.members, last_year.minutes
+      ));
+    }
+
+    lines.join("\n")
+  }
+}
+
+/// A channel where the final leaderboard is posted and pinned at the end of each week or month.
+pub struct LeaderboardRollover {
+  pub id: String,
+  pub guild_id: serenity::GuildId,
+  pub period: LeaderboardPeriod,
+  pub channel_id: serenity::ChannelId,
+  /// Start of the most recent period that was posted, or that had already ended when the rollover was set up.
+  pub last_period_start: chrono::DateTime<Utc>,
+  /// The last posted leaderboard, which is unpinned when the next one is pinned.
+  pub pinned_message_id: Option<serenity::MessageId>,
+}
+
+/// A member who joined a challenge with `/challenge join`.
+pub struct ChallengeParticipant {
+  pub guild_id: serenity::GuildId,
+  pub user_id: serenity::UserId,
+}
+
+/// A challenge participant's meditation during the challenge period.
+pub struct ChallengeProgress {
+  pub user_id: serenity::UserId,
+  pub minutes: i64,
+  pub sessions: i64,
+  pub stats_private: bool,
+}
+
+/// New and returning meditators for one month in a guild.
+#[derive(Clone)]
+pub struct GrowthMonth {
+  /// The first day of the month.
+  pub month_start: chrono::NaiveDate,
+  /// Members whose first entry in the guild was this month.
+  pub new_members: i64,
+  /// New members whose first entry was at least 30 days ago, so their retention is known.
+  pub retention_eligible: i64,
+  /// Eligible new members who meditated again on a later day within 30 days of their first entry.
+  pub retained: i64,
+  /// Members with an entry this month.
+  pub active_members: i64,
+  /// Members with an entry the month before, but none this month.
+  pub churned: i64,
+}
+
+impl GrowthMonth {
+  /// Share of new members who meditated again within 30 days, from 0 to 100, if any are eligible yet.
+  #[allow(clippy::cast_precision_loss)]
+  pub fn retention_percent(&self) -> Option<f64> {
+    (self.retention_eligible > 0)
+      .then(|| self.retained as f64 * 100.0 / self.retention_eligible as f64)
+  }
+}
+
+/// A member's import of past meditation entries with `/import`, saved after each chunk so it can be resumed.
+pub struct ImportJob {
+  pub id: String,
+  pub file_name: String,
+  pub file_url: String,
+  pub file_size: i32,
+  /// Data rows read so far, not counting the header. Resuming skips this many rows.
+  pub rows_processed: i32,
+  pub chunks_completed: i32,
+  pub entries_added: i32,
+  pub rows_failed: i32,
+  pub on_conflict: ImportConflict,
+  /// Rows left out because the member already had an entry at about the same time.
+  pub duplicates_skipped: i32,
+  /// Existing entries removed because the file had entries on the same day.
+  pub entries_replaced: i32,
+  /// Existing entries lengthened to match a duplicate row.
+  pub entries_merged: i32,
+  /// When the import last saved a chunk, or `None` if it was stopped by an error.
+  pub heartbeat_at: Option<chrono::DateTime<Utc>>,
+}
+
+/// What happened to the rows of one imported chunk.
+#[derive(Default)]
+pub struct ImportedChunk {
+  pub added: i32,
+  pub duplicates_skipped: i32,
+  pub entries_replaced: i32,
+  pub entries_merged: i32,
+}
+
+/// A row of an imported file that couldn't be added.
+pub struct ImportError {
+  pub chunk: i32,
+  /// Line in the file, starting at 1 for the header.
+  pub line: i32,
+  pub message: String,
+}
+
+/// Anonymous mood check-ins and meditation for one week in a guild.
+#[derive(Clone)]
+pub struct MoodWeek {
+  /// The Monday the week starts on.
+  pub week_start: chrono::NaiveDate,
+  pub responses: i64,
+  /// Average mood score from 1 to 5, if anyone responded.
+  pub average_score: Option<f64>,
+  pub minutes: i64,
+}
+
+/// A shared meditation target the whole guild works toward over a period, such as 10,000 hours in a quarter.
+pub struct CollectiveGoal {
+  pub id: String,
+  pub guild_id: serenity::GuildId,
+  pub name: String,
+  pub target_minutes: i64,
+  pub starts_on: chrono::NaiveDate,
+  /// Last day that counts toward the goal.
+  pub ends_on: chrono::NaiveDate,
+  /// Channel for milestone announcements and the progress message.
+  pub channel_id: serenity::ChannelId,
+  /// Message showing the progress bar, which is updated daily.
+  pub progress_message_id: Option<serenity::MessageId>,
+  /// Highest milestone announced so far, as a percentage of the target.
+  pub last_milestone: i16,
+  pub last_rendered_on: Option<chrono::NaiveDate>,
+}
+
+pub struct DeleteLogSettings {
+  pub ignore_bots: bool,
+  pub min_length: i32,
+  pub ignored_channels: Vec<serenity::ChannelId>,
+}
+
+impl Default for DeleteLogSettings {
+  fn default() -> Self {
+    Self {
+      ignore_bots: true,
+      min_length: 0,
+      ignored_channels: Vec::new(),
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct RestoreCounts {
+  pub terms: u64,
+  pub quotes: u64,
+  pub courses: u64,
+  pub steam_key_recipients: u64,
+  pub tracking_profiles: u64,
+  pub command_permissions: u64,
+  pub quote_settings: u64,
+}
+
+pub struct PoolStats {
+  pub size: u32,
+  pub idle: usize,
+  pub max_connections: u32,
+}
+
+impl PoolStats {
+  /// Connections currently checked out of the pool.
+  pub fn active(&self) -> u32 {
+    self
+      .size
+      .saturating_sub(u32::try_from(self.idle).unwrap_or(u32::MAX))
+  }
+}
+
+/// Postgres error codes for failures that can succeed when the transaction is run again:
+/// `serialization_failure`, `deadlock_detected`, and `lock_not_available`.
+const TRANSIENT_ERROR_CODES: [&str; 3] = ["40001", "40P01", "55P03"];
+/// Base delay before retrying a transient failure. Doubles with each attempt.
+const RETRY_BASE_DELAY_MS: u64 = 50;
+
+/// Reads an environment variable, falling back to a default when it is not set.
+fn env_or<T: FromStr>(key: &str, default: T) -> Result<T>
+where
+  T::Err: std::error::Error + Send + Sync + 'static,
+{
+  match std::env::var(key) {
+    Ok(value) => value
+      .parse()
+      .with_context(|| format!("Invalid value for {key} environment variable")),
+    Err(_) => Ok(default),
+  }
+}
+
 #[allow(clippy::struct_field_names)]
 pub struct StarMessage {
   pub record_id: String,
   pub starred_message_id: serenity::MessageId,
   pub board_message_id: serenity::MessageId,
   pub starred_channel_id: serenity::ChannelId,
+  /// Channel the board message was posted in. `None` for messages posted before starboard forums were added, which are in the starboard channel.
+  pub board_channel_id: Option<serenity::ChannelId>,
+  /// `None` for messages starred before guilds were recorded.
+  pub guild_id: Option<serenity::GuildId>,
+}
+
+impl StarMessage {
+  /// Whether the board message starts a thread in a starboard forum, rather than being a message in the starboard channel.
+  pub fn is_forum_post(&self) -> bool {
+    self
+      .board_channel_id
+      .is_some_and(|channel_id| channel_id.get() == self.board_message_id.get())
+  }
+}
+
+/// Stars received by a member, across their messages on the starboard.
+pub struct StarredAuthor {
+  pub user_id: serenity::UserId,
+  pub messages: i64,
+  pub stars: i64,
+}
+
+pub struct StarboardForumTag {
+  pub source_channel_id: serenity::ChannelId,
+  pub tag_id: serenity::ForumTagId,
 }
 
 impl DatabaseHandler {
   pub async fn new() -> Result<Self> {
     let database_url =
       std::env::var("DATABASE_URL").with_context(|| "Missing DATABASE_URL environment variable")?;
+    let max_connections = env_or("DATABASE_MAX_CONNECTIONS", 10)?;
+    let min_connections = env_or("DATABASE_MIN_CONNECTIONS", 0)?;
+    let acquire_timeout = env_or("DATABASE_ACQUIRE_TIMEOUT_SECS", 30)?;
+    let statement_timeout: u64 = env_or("DATABASE_STATEMENT_TIMEOUT_MS", 0)?;
+    let slow_query_threshold = env_or("DATABASE_SLOW_QUERY_MS", 1000)?;
+
+    let mut connect_options = sqlx::postgres::PgConnectOptions::from_str(&database_url)?
+      .log_slow_statements(
+        log::LevelFilter::Warn,
+        std::time::Duration::from_millis(slow_query_threshold),
+      );
+    // A timeout of 0 leaves statements unlimited, which is also the Postgres default
+    if statement_timeout > 0 {
+      connect_options =
+        connect_options.options([("statement_timeout", statement_timeout.to_string())]);
+    }
+
+    info!(
+      "Database pool: {min_connections}-{max_connections} connections, {acquire_timeout}s acquire timeout, {statement_timeout}ms statement timeout"
+    );
+
     // let pool = sqlx::PgPool::connect(&database_url).await?;
     let max_retries = 5;
     let mut attempts = 0;
 
     loop {
-      let pool = match sqlx::PgPool::connect(&database_url).await {
+      let pool = match sqlx::postgres::PgPoolOptions::new()
+        .max_connections(max_connections)
+        .min_connections(min_connections)
+        .acquire_timeout(std::time::Duration::from_secs(acquire_timeout))
+        .connect_with(connect_options.clone())
+        .await
+      {
         Ok(pool) => pool,
         Err(e) => {
           if attempts >= max_retries {
@@ -383,7 +1130,15 @@ impl DatabaseHandler {
     }
   }
 
-  pub async fn get_connection(&self) -> Result<sqlx::pool::PoolConnection<sqlx::Postgres>> {
+  pub fn get_pool_stats(&self) -> PoolStats {
+    PoolStats {
+      size: self.pool.size(),
+      idle: self.pool.num_idle(),
+      max_connections: self.pool.options().get_max_connections(),
+    }
+  }
+
+  pub async fn get_connection(&self) -> Result<sqlx::pool::PoolConnection<sqlx::Postgres>> {
     Ok(self.pool.acquire().await?)
   }
 
@@ -455,6 +1210,52 @@ impl DatabaseHandler {
     }
   }
 
+  /// Checks whether an error is a transient Postgres failure, such as a serialization failure or a connection reset mid-query.
+  pub fn is_transient_error(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<sqlx::Error>() {
+      Some(sqlx::Error::Database(db_error)) => db_error
+        .code()
+        .is_some_and(|code| TRANSIENT_ERROR_CODES.contains(&code.as_ref())),
+      Some(sqlx::Error::Io(io_error)) => matches!(
+        io_error.kind(),
+        std::io::ErrorKind::ConnectionReset
+          | std::io::ErrorKind::ConnectionAborted
+          | std::io::ErrorKind::BrokenPipe
+          | std::io::ErrorKind::UnexpectedEof
+      ),
+      _ => false,
+    }
+  }
+
+  /// Runs a database operation, retrying with jittered exponential backoff when it fails with a transient error.
+  ///
+  /// A failed transaction can't be reused, so the operation should start its own transaction and return it (or its results) on success. Any other error is returned immediately.
+  pub async fn with_retry<T, F, Fut>(max_attempts: usize, mut operation: F) -> Result<T>
+  where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+  {
+    let mut attempts = 0;
+
+    loop {
+      match operation().await {
+        Ok(result) => return Ok(result),
+        Err(e) => {
+          attempts += 1;
+          if attempts >= max_attempts || !Self::is_transient_error(&e) {
+            return Err(e);
+          }
+
+          // Jitter keeps concurrent retries from colliding again
+          let delay = RETRY_BASE_DELAY_MS << (attempts - 1).min(6);
+          let jitter = rand::thread_rng().gen_range(0..=delay / 2);
+          warn!("Transient database error, retry attempt {attempts} of {max_attempts}: {e}");
+          tokio::time::sleep(std::time::Duration::from_millis(delay + jitter)).await;
+        }
+      }
+    }
+  }
+
   pub async fn commit_transaction(
     transaction: sqlx::Transaction<'_, sqlx::Postgres>,
   ) -> Result<()> {
@@ -566,6 +1367,126 @@ impl DatabaseHandler {
     Ok(())
   }
 
+  pub async fn update_congrats_cards(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    congrats_cards: bool,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE tracking_profile SET congrats_cards = $1 WHERE user_id = $2 AND guild_id = $3
+      "#,
+      congrats_cards,
+      user_id.to_string(),
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn update_congrats_delivery(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    congrats: CongratsDelivery,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE tracking_profile SET congrats = $1 WHERE user_id = $2 AND guild_id = $3
+      "#,
+      congrats.key(),
+      user_id.to_string(),
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn update_add_reaction(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    add_reaction: Option<&str>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE tracking_profile SET add_reaction = $1 WHERE user_id = $2 AND guild_id = $3
+      "#,
+      add_reaction,
+      user_id.to_string(),
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn update_add_quotes(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    add_quotes: Option<bool>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE tracking_profile SET add_quotes = $1 WHERE user_id = $2 AND guild_id = $3
+      "#,
+      add_quotes,
+      user_id.to_string(),
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn update_vc_tracking(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    vc_tracking: bool,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE tracking_profile SET vc_tracking = $1 WHERE user_id = $2 AND guild_id = $3
+      "#,
+      vc_tracking,
+      user_id.to_string(),
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn update_light_mode(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    light_mode: bool,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE tracking_profile SET light_mode = $1 WHERE user_id = $2 AND guild_id = $3
+      "#,
+      light_mode,
+      user_id.to_string(),
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
   pub async fn get_tracking_profile(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     guild_id: &serenity::GuildId,
@@ -573,7 +1494,7 @@ impl DatabaseHandler {
   ) -> Result<Option<TrackingProfile>> {
     let row = sqlx::query!(
       r#"
-        SELECT user_id, guild_id, utc_offset, anonymous_tracking, streaks_active, streaks_private, stats_private FROM tracking_profile WHERE user_id = $1 AND guild_id = $2
+        SELECT user_id, guild_id, utc_offset, anonymous_tracking, streaks_active, streaks_private, stats_private, congrats_cards, congrats, add_reaction, add_quotes, vc_tracking, light_mode FROM tracking_profile WHERE user_id = $1 AND guild_id = $2
       "#,
       user_id.to_string(),
       guild_id.to_string(),
@@ -590,6 +1511,12 @@ impl DatabaseHandler {
         streaks_active: row.streaks_active,
         streaks_private: row.streaks_private,
         stats_private: row.stats_private,
+        congrats_cards: row.congrats_cards,
+        congrats: CongratsDelivery::from_key(&row.congrats).unwrap_or(CongratsDelivery::Public),
+        add_reaction: row.add_reaction,
+        add_quotes: row.add_quotes,
+        vc_tracking: row.vc_tracking,
+        light_mode: row.light_mode,
       }),
       None => None,
     };
@@ -840,15 +1767,17 @@ impl DatabaseHandler {
     guild_id: &serenity::GuildId,
     user_id: &serenity::UserId,
     minutes: i32,
+    source: EntrySource,
   ) -> Result<()> {
     sqlx::query!(
       r#"
-        INSERT INTO meditation (record_id, user_id, meditation_minutes, guild_id) VALUES ($1, $2, $3, $4)
+        INSERT INTO meditation (record_id, user_id, meditation_minutes, guild_id, source) VALUES ($1, $2, $3, $4, $5)
       "#,
       Ulid::new().to_string(),
       user_id.to_string(),
       minutes,
       guild_id.to_string(),
+      source.key(),
     )
     .execute(&mut **transaction)
     .await?;
@@ -862,16 +1791,18 @@ impl DatabaseHandler {
     user_id: &serenity::UserId,
     minutes: i32,
     occurred_at: chrono::DateTime<Utc>,
+    source: EntrySource,
   ) -> Result<()> {
     sqlx::query!(
       r#"
-        INSERT INTO meditation (record_id, user_id, meditation_minutes, guild_id, occurred_at) VALUES ($1, $2, $3, $4, $5)
+        INSERT INTO meditation (record_id, user_id, meditation_minutes, guild_id, occurred_at, source) VALUES ($1, $2, $3, $4, $5, $6)
       "#,
       Ulid::new().to_string(),
       user_id.to_string(),
       minutes,
       guild_id.to_string(),
       occurred_at,
+      source.key(),
     )
     .execute(&mut **transaction)
     .await?;
@@ -886,7 +1817,7 @@ impl DatabaseHandler {
   ) -> Result<Vec<MeditationData>> {
     let rows = sqlx::query!(
       r#"
-        SELECT record_id, user_id, meditation_minutes, occurred_at FROM meditation WHERE user_id = $1 AND guild_id = $2 ORDER BY occurred_at DESC
+        SELECT record_id, user_id, meditation_minutes, occurred_at, source FROM meditation WHERE user_id = $1 AND guild_id = $2 ORDER BY occurred_at DESC
       "#,
       user_id.to_string(),
       guild_id.to_string(),
@@ -901,6 +1832,7 @@ impl DatabaseHandler {
         user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
         meditation_minutes: row.meditation_minutes,
         occurred_at: row.occurred_at,
+        source: row.source.as_deref().and_then(EntrySource::from_key),
       })
       .collect();
 
@@ -914,7 +1846,7 @@ impl DatabaseHandler {
   ) -> Result<Option<MeditationData>> {
     let row = sqlx::query!(
       r#"
-        SELECT record_id, user_id, meditation_minutes, occurred_at FROM meditation WHERE record_id = $1 AND guild_id = $2
+        SELECT record_id, user_id, meditation_minutes, occurred_at, source FROM meditation WHERE record_id = $1 AND guild_id = $2
       "#,
       meditation_id,
       guild_id.to_string(),
@@ -928,6 +1860,7 @@ impl DatabaseHandler {
         user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
         meditation_minutes: row.meditation_minutes,
         occurred_at: row.occurred_at,
+        source: row.source.as_deref().and_then(EntrySource::from_key),
       }),
       None => None,
     };
@@ -955,38 +1888,124 @@ impl DatabaseHandler {
     Ok(())
   }
 
+  /// Moves the entry to the trash and returns the ID of the trash batch it can be restored from.
   pub async fn delete_meditation_entry(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     meditation_id: &str,
-  ) -> Result<()> {
+    deleted_by: &serenity::UserId,
+    reason: &str,
+  ) -> Result<String> {
+    let batch_id = Ulid::new().to_string();
+
     sqlx::query!(
       r#"
-        DELETE FROM meditation WHERE record_id = $1
+        WITH removed AS (
+          DELETE FROM meditation WHERE record_id = $1
+          RETURNING record_id, user_id, guild_id, occurred_at, meditation_minutes, source
+        )
+        INSERT INTO meditation_trash (record_id, user_id, guild_id, occurred_at, meditation_minutes, source, batch_id, reason, deleted_by)
+        SELECT record_id, user_id, guild_id, occurred_at, meditation_minutes, source, $2, $3, $4 FROM removed
       "#,
       meditation_id,
+      batch_id,
+      reason,
+      deleted_by.to_string(),
     )
     .execute(&mut **transaction)
     .await?;
 
-    Ok(())
+    Ok(batch_id)
   }
 
+  /// Moves all of the user's entries to the trash and returns the ID of the trash batch they can be restored from.
   pub async fn reset_user_meditation_entries(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     guild_id: &serenity::GuildId,
     user_id: &serenity::UserId,
-  ) -> Result<()> {
+    deleted_by: &serenity::UserId,
+    reason: &str,
+  ) -> Result<String> {
+    let batch_id = Ulid::new().to_string();
+
     sqlx::query!(
       r#"
-        DELETE FROM meditation WHERE user_id = $1 AND guild_id = $2
+        WITH removed AS (
+          DELETE FROM meditation WHERE user_id = $1 AND guild_id = $2
+          RETURNING record_id, user_id, guild_id, occurred_at, meditation_minutes, source
+        )
+        INSERT INTO meditation_trash (record_id, user_id, guild_id, occurred_at, meditation_minutes, source, batch_id, reason, deleted_by)
+        SELECT record_id, user_id, guild_id, occurred_at, meditation_minutes, source, $3, $4, $5 FROM removed
       "#,
       user_id.to_string(),
       guild_id.to_string(),
+      batch_id,
+      reason,
+      deleted_by.to_string(),
     )
     .execute(&mut **transaction)
     .await?;
 
-    Ok(())
+    Ok(batch_id)
+  }
+
+  pub async fn shift_user_meditation_entries(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    offset_minutes: i32,
+  ) -> Result<u64> {
+    let updated = sqlx::query!(
+      r#"
+        UPDATE meditation SET occurred_at = occurred_at + make_interval(mins => $3)
+        WHERE user_id = $1 AND guild_id = $2
+      "#,
+      user_id.to_string(),
+      guild_id.to_string(),
+      offset_minutes,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(updated.rows_affected())
+  }
+
+  /// Moves entries with the same time and duration as an earlier entry to the trash batch `batch_id`, keeping the first of each, and returns how many were moved.
+  pub async fn remove_duplicate_meditation_entries(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    batch_id: &str,
+    deleted_by: &serenity::UserId,
+    reason: &str,
+  ) -> Result<u64> {
+    let removed = sqlx::query!(
+      r#"
+        WITH removed AS (
+          DELETE FROM meditation WHERE record_id IN (
+            SELECT record_id FROM (
+              SELECT record_id, ROW_NUMBER() OVER (
+                PARTITION BY occurred_at, meditation_minutes ORDER BY record_id
+              ) AS position
+              FROM meditation
+              WHERE user_id = $1 AND guild_id = $2
+            ) AS entries
+            WHERE position > 1
+          )
+          RETURNING record_id, user_id, guild_id, occurred_at, meditation_minutes, source
+        )
+        INSERT INTO meditation_trash (record_id, user_id, guild_id, occurred_at, meditation_minutes, source, batch_id, reason, deleted_by)
+        SELECT record_id, user_id, guild_id, occurred_at, meditation_minutes, source, $3, $4, $5 FROM removed
+      "#,
+      user_id.to_string(),
+      guild_id.to_string(),
+      batch_id,
+      reason,
+      deleted_by.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(removed.rows_affected())
   }
 
   pub async fn migrate_meditation_entries(
@@ -994,8 +2013,8 @@ impl DatabaseHandler {
     guild_id: &serenity::GuildId,
     old_user_id: &serenity::UserId,
     new_user_id: &serenity::UserId,
-  ) -> Result<()> {
-    sqlx::query!(
+  ) -> Result<u64> {
+    let updated = sqlx::query!(
       r#"
         UPDATE meditation SET user_id = $3 WHERE user_id = $1 AND guild_id = $2
       "#,
@@ -1006,7 +2025,7 @@ impl DatabaseHandler {
     .execute(&mut **transaction)
     .await?;
 
-    Ok(())
+    Ok(updated.rows_affected())
   }
 
   pub fn get_winner_candidates<'a>(
@@ -1035,21 +2054,28 @@ impl DatabaseHandler {
     })
   }
 
+  /// Gets the minutes a user meditated during a challenge, counting at most `daily_cap` minutes per day.
   pub async fn get_winner_candidate_meditation_sum(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     guild_id: &serenity::GuildId,
     user_id: &serenity::UserId,
     start_date: chrono::DateTime<Utc>,
     end_date: chrono::DateTime<Utc>,
+    daily_cap: Option<i32>,
   ) -> Result<i64> {
     let row = sqlx::query!(
       r#"
-        SELECT SUM(meditation_minutes) AS winner_candidate_total FROM meditation WHERE user_id = $1 AND guild_id = $2 AND occurred_at >= $3 AND occurred_at <= $4
+        SELECT SUM(daily_minutes)::BIGINT AS winner_candidate_total FROM (
+          SELECT LEAST(SUM(meditation_minutes), COALESCE($5::INTEGER, 2147483647)) AS daily_minutes
+          FROM meditation WHERE user_id = $1 AND guild_id = $2 AND occurred_at >= $3 AND occurred_at <= $4
+          GROUP BY (occurred_at AT TIME ZONE 'UTC')::date
+        ) AS daily
       "#,
       user_id.to_string(),
       guild_id.to_string(),
       start_date,
       end_date,
+      daily_cap,
     )
     .fetch_one(&mut **transaction)
     .await?;
@@ -1159,6 +2185,52 @@ impl DatabaseHandler {
     Ok(guild_total.try_into().unwrap())
   }
 
+  pub async fn get_total_meditation_sum(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  ) -> Result<i64> {
+    let row = sqlx::query!(
+      r#"
+        SELECT COALESCE(SUM(meditation_minutes), 0) AS total FROM meditation
+      "#,
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.total.unwrap_or(0))
+  }
+
+  pub async fn get_meditator_count(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  ) -> Result<u64> {
+    let row = sqlx::query!(
+      r#"
+        SELECT COUNT(DISTINCT user_id) AS total FROM meditation
+      "#,
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.total.unwrap_or(0).try_into().unwrap())
+  }
+
+  pub async fn get_random_quote_any_guild(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  ) -> Result<Option<QuoteData>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT record_id, quote, author FROM quote ORDER BY RANDOM() LIMIT 1
+      "#,
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| QuoteData {
+      id: row.record_id,
+      quote: row.quote,
+      author: row.author,
+    }))
+  }
+
   pub async fn get_all_quotes(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     guild_id: &serenity::GuildId,
@@ -1247,111 +2319,224 @@ impl DatabaseHandler {
     Ok(row.map(|row| row.quote))
   }
 
-  pub async fn get_streak(
+  /// Gets the user's local date, using the UTC offset in their tracking profile.
+  async fn get_local_date(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     guild_id: &serenity::GuildId,
     user_id: &serenity::UserId,
-  ) -> Result<u64> {
-    let mut row = sqlx::query_as!(
-      MeditationCountByDay,
+  ) -> Result<chrono::NaiveDate> {
+    let row = sqlx::query!(
       r#"
-      WITH cte AS (
-        SELECT date_part('day', NOW() - DATE_TRUNC('day', "occurred_at")) AS "days_ago"
-        FROM meditation 
-        WHERE user_id = $1 AND guild_id = $2
-        AND "occurred_at"::date <= NOW()::date
-      )
-      SELECT "days_ago"
-      FROM cte
-      GROUP BY "days_ago"
-      ORDER BY "days_ago" ASC;
+        SELECT utc_offset FROM tracking_profile WHERE user_id = $1 AND guild_id = $2
       "#,
       user_id.to_string(),
       guild_id.to_string(),
     )
-    .fetch(&mut **transaction);
-
-    let mut last = 0;
-    let mut streak = 0;
-
-    if let Some(first) = row.try_next().await? {
-      // date_part 'day' can only be 1-31
-      #[allow(clippy::cast_possible_truncation)]
-      let days_ago = first.days_ago.unwrap() as i32;
-
-      if days_ago > 2 {
-        return Ok(0);
-      }
-
-      last = days_ago;
-      streak = 1;
-    }
-
-    while let Some(row) = row.try_next().await? {
-      // date_part 'day' can only be 1-31
-      #[allow(clippy::cast_possible_truncation)]
-      let days_ago = row.days_ago.unwrap() as i32;
-
-      if days_ago != last + 1 {
-        break;
-      }
-
-      last = days_ago;
-      streak += 1;
-    }
+    .fetch_optional(&mut **transaction)
+    .await?;
 
-    Ok(streak)
+    Ok(streaks::local_date(
+      Utc::now(),
+      row.map_or(0, |row| row.utc_offset),
+    ))
   }
 
-  pub async fn course_exists(
+  /// Gets how many days ago the user meditated, in ascending order without duplicates.
+  ///
+  /// Entries are stored in the user's local time, so days are counted back from the user's local date rather than the server's.
+  async fn get_meditation_days_ago(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     guild_id: &serenity::GuildId,
-    course_name: &str,
-  ) -> Result<bool> {
-    let row = sqlx::query!(
+    user_id: &serenity::UserId,
+  ) -> Result<Vec<i32>> {
+    let today = Self::get_local_date(transaction, guild_id, user_id).await?;
+
+    let rows = sqlx::query!(
       r#"
-        SELECT EXISTS(SELECT 1 FROM course WHERE course_name = $1 AND guild_id = $2)
+        SELECT (occurred_at AT TIME ZONE 'UTC')::date AS "day!"
+        FROM meditation
+        WHERE user_id = $1 AND guild_id = $2
+        UNION
+        SELECT streak_day
+        FROM streak_credits
+        WHERE user_id = $1 AND guild_id = $2 AND kind = 'restored'
+        ORDER BY "day!" DESC
       "#,
-      course_name,
+      user_id.to_string(),
       guild_id.to_string(),
     )
-    .fetch_one(&mut **transaction)
+    .fetch_all(&mut **transaction)
     .await?;
 
-    Ok(row.exists.unwrap())
+    Ok(
+      rows
+        .into_iter()
+        .filter_map(|row| streaks::days_ago(today, row.day))
+        .collect(),
+    )
   }
 
-  pub async fn add_course(
+  /// Gets how many days ago the user logged an activity other than meditation, in ascending order without duplicates.
+  ///
+  /// Like meditation entries, activities are stored in the user's local time and counted back from their local date.
+  async fn get_activity_days_ago(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     guild_id: &serenity::GuildId,
-    course_name: &str,
-    participant_role: &serenity::Role,
-    graduate_role: &serenity::Role,
-  ) -> Result<()> {
-    sqlx::query!(
+    user_id: &serenity::UserId,
+    activity: Activity,
+  ) -> Result<Vec<i32>> {
+    let today = Self::get_local_date(transaction, guild_id, user_id).await?;
+
+    let rows = sqlx::query!(
       r#"
-        INSERT INTO course (record_id, course_name, participant_role, graduate_role, guild_id) VALUES ($1, $2, $3, $4, $5)
+        SELECT DISTINCT (occurred_at AT TIME ZONE 'UTC')::date AS "day!"
+        FROM activity_log
+        WHERE user_id = $1 AND guild_id = $2 AND activity = $3
+        ORDER BY "day!" DESC
       "#,
-      Ulid::new().to_string(),
-      course_name,
-      participant_role.id.to_string(),
-      graduate_role.id.to_string(),
+      user_id.to_string(),
       guild_id.to_string(),
+      activity.key(),
     )
-    .execute(&mut **transaction)
+    .fetch_all(&mut **transaction)
     .await?;
 
-    Ok(())
+    Ok(
+      rows
+        .into_iter()
+        .filter_map(|row| streaks::days_ago(today, row.day))
+        .collect(),
+    )
   }
 
-  pub async fn update_course(
+  async fn get_activity_weeks_ago(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
-    course_name: &str,
-    participant_role: String,
-    graduate_role: String,
-  ) -> Result<()> {
-    sqlx::query!(
-      r#"
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    activity: Activity,
+  ) -> Result<Vec<i32>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT DISTINCT (DATE_TRUNC('week', NOW())::date - DATE_TRUNC('week', occurred_at)::date) / 7 AS "weeks_ago!"
+        FROM activity_log
+        WHERE user_id = $1 AND guild_id = $2 AND activity = $3 AND occurred_at <= NOW()
+        ORDER BY "weeks_ago!" ASC
+      "#,
+      user_id.to_string(),
+      guild_id.to_string(),
+      activity.key(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.weeks_ago).collect())
+  }
+
+  pub async fn get_streak(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+  ) -> Result<u64> {
+    Self::get_activity_streak(transaction, guild_id, user_id, Activity::Meditation).await
+  }
+
+  pub async fn get_activity_streak(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    activity: Activity,
+  ) -> Result<u64> {
+    let days_ago = match activity {
+      Activity::Meditation => Self::get_meditation_days_ago(transaction, guild_id, user_id).await?,
+      Activity::Journal | Activity::Talk => {
+        Self::get_activity_days_ago(transaction, guild_id, user_id, activity).await?
+      }
+      Activity::CheckIn => {
+        let weeks_ago =
+          Self::get_activity_weeks_ago(transaction, guild_id, user_id, activity).await?;
+        return Ok(streaks::consecutive_weeks(&weeks_ago));
+      }
+    };
+
+    Ok(streaks::consecutive_days(&days_ago))
+  }
+
+  pub async fn add_activity_entry(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    activity: Activity,
+    note: Option<&str>,
+    minutes: Option<i32>,
+    occurred_at: chrono::DateTime<Utc>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO activity_log (record_id, guild_id, user_id, activity, note, minutes, occurred_at) VALUES ($1, $2, $3, $4, $5, $6, $7)
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      user_id.to_string(),
+      activity.key(),
+      note,
+      minutes,
+      occurred_at,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn course_exists(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    course_name: &str,
+  ) -> Result<bool> {
+    let row = sqlx::query!(
+      r#"
+        SELECT EXISTS(SELECT 1 FROM course WHERE course_name = $1 AND guild_id = $2)
+      "#,
+      course_name,
+      guild_id.to_string(),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.exists.unwrap())
+  }
+
+  pub async fn add_course(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    course_name: &str,
+    participant_role: &serenity::Role,
+    graduate_role: &serenity::Role,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO course (record_id, course_name, participant_role, graduate_role, guild_id) VALUES ($1, $2, $3, $4, $5)
+      "#,
+      Ulid::new().to_string(),
+      course_name,
+      participant_role.id.to_string(),
+      graduate_role.id.to_string(),
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn update_course(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    course_name: &str,
+    participant_role: String,
+    graduate_role: String,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
         UPDATE course SET participant_role = $1, graduate_role = $2 WHERE LOWER(course_name) = LOWER($3)
       "#,
       participant_role,
@@ -1430,6 +2615,35 @@ impl DatabaseHandler {
     Ok(steam_keys)
   }
 
+  /// Finds the existing quote most similar to the given text, if any is similar enough to likely be a duplicate.
+  pub async fn get_similar_quote(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    quote: &str,
+    similarity: f32,
+  ) -> Result<Option<QuoteData>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT record_id, quote, author, SET_LIMIT($2), SIMILARITY(LOWER(quote), LOWER($1)) AS similarity_score
+        FROM quote
+        WHERE LOWER(quote) % LOWER($1) AND guild_id = $3
+        ORDER BY similarity_score DESC
+        LIMIT 1
+      "#,
+      quote,
+      similarity,
+      guild_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| QuoteData {
+      id: row.record_id,
+      quote: row.quote,
+      author: row.author,
+    }))
+  }
+
   pub async fn add_quote(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     guild_id: &serenity::GuildId,
@@ -1608,6 +2822,160 @@ impl DatabaseHandler {
     Ok(())
   }
 
+  pub async fn reset_term_reindex_progress(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE term SET reindexed_at = NULL WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn get_terms_pending_reindex(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    limit: i64,
+  ) -> Result<Vec<Term>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT record_id, term_name, meaning, usage, links, category, aliases
+        FROM term
+        WHERE guild_id = $1 AND reindexed_at IS NULL
+        ORDER BY term_name ASC
+        LIMIT $2
+      "#,
+      guild_id.to_string(),
+      limit,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let terms = rows
+      .into_iter()
+      .map(|row| Term {
+        id: row.record_id,
+        name: row.term_name,
+        meaning: row.meaning,
+        usage: row.usage,
+        links: row.links,
+        category: row.category,
+        aliases: row.aliases,
+      })
+      .collect();
+
+    Ok(terms)
+  }
+
+  pub async fn get_pending_reindex_count(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<u64> {
+    let row = sqlx::query!(
+      r#"
+        SELECT COUNT(record_id) AS pending_count FROM term WHERE guild_id = $1 AND reindexed_at IS NULL
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.pending_count.unwrap_or(0).try_into()?)
+  }
+
+  pub async fn update_term_embedding(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    term_id: &str,
+    vector: pgvector::Vector,
+  ) -> Result<()> {
+    sqlx::query(
+      r#"
+        UPDATE term SET embedding = $1, reindexed_at = NOW() WHERE record_id = $2
+      "#,
+    )
+    .bind(vector)
+    .bind(term_id)
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn get_term_pronunciation(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    term_id: &str,
+  ) -> Result<TermPronunciation> {
+    let row = sqlx::query!(
+      r#"
+        SELECT pronunciation, pronunciation_audio, pronunciation_audio_name
+        FROM term
+        WHERE record_id = $1
+      "#,
+      term_id,
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    let pronunciation = match row {
+      Some(row) => TermPronunciation {
+        phonetic: row.pronunciation,
+        audio: row.pronunciation_audio,
+        audio_name: row.pronunciation_audio_name,
+      },
+      None => TermPronunciation {
+        phonetic: None,
+        audio: None,
+        audio_name: None,
+      },
+    };
+
+    Ok(pronunciation)
+  }
+
+  pub async fn update_term_pronunciation(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    term_id: &str,
+    phonetic: Option<&str>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE term SET pronunciation = $1 WHERE record_id = $2
+      "#,
+      phonetic,
+      term_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn update_term_pronunciation_audio(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    term_id: &str,
+    audio: Option<&[u8]>,
+    audio_name: Option<&str>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE term SET pronunciation_audio = $1, pronunciation_audio_name = $2 WHERE record_id = $3
+      "#,
+      audio,
+      audio_name,
+      term_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
   pub async fn get_all_courses(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     guild_id: &serenity::GuildId,
@@ -1665,6 +3033,24 @@ impl DatabaseHandler {
     Ok(course_data)
   }
 
+  pub async fn get_course_created_at(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    course_name: &str,
+  ) -> Result<Option<chrono::DateTime<Utc>>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT created_at FROM course WHERE LOWER(course_name) = LOWER($1) AND guild_id = $2
+      "#,
+      course_name,
+      guild_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.and_then(|row| row.created_at))
+  }
+
   pub async fn get_course_in_dm(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     course_name: &str,
@@ -1954,17 +3340,15 @@ impl DatabaseHandler {
     Ok(quote)
   }
 
-  pub async fn remove_course(
+  pub async fn record_quote_shown(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
-    guild_id: &serenity::GuildId,
-    course_name: &str,
+    quote_id: &str,
   ) -> Result<()> {
     sqlx::query!(
       r#"
-        DELETE FROM course WHERE course_name = $1 AND guild_id = $2
+        UPDATE quote SET times_shown = times_shown + 1 WHERE record_id = $1
       "#,
-      course_name,
-      guild_id.to_string(),
+      quote_id,
     )
     .execute(&mut **transaction)
     .await?;
@@ -1972,16 +3356,143 @@ impl DatabaseHandler {
     Ok(())
   }
 
-  pub async fn remove_steam_key(
+  /// Gets how often each quote has been shown, most shown first.
+  pub async fn get_quote_usage(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     guild_id: &serenity::GuildId,
-    key: &str,
-  ) -> Result<()> {
-    sqlx::query!(
+  ) -> Result<Vec<QuoteUsage>> {
+    let rows = sqlx::query!(
       r#"
-        DELETE FROM steamkey WHERE steam_key = $1 AND guild_id = $2
+        SELECT record_id, quote, times_shown FROM quote WHERE guild_id = $1 ORDER BY times_shown DESC, record_id ASC
       "#,
-      key,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| QuoteUsage {
+          id: row.record_id,
+          quote: row.quote,
+          times_shown: row.times_shown,
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn get_quote_card_background(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Option<String>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT card_background FROM quote_settings WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.and_then(|row| row.card_background))
+  }
+
+  pub async fn set_quote_card_background(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    card_background: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO quote_settings (record_id, guild_id, card_background) VALUES ($1, $2, $3)
+        ON CONFLICT (guild_id) DO UPDATE SET card_background = EXCLUDED.card_background
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      card_background,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Whether `/add` confirmations in the guild include a random quote, for members who haven't chosen.
+  pub async fn get_add_quotes_default(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<bool> {
+    let row = sqlx::query!(
+      r#"
+        SELECT add_quotes FROM quote_settings WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map_or(true, |row| row.add_quotes))
+  }
+
+  pub async fn set_add_quotes_default(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    add_quotes: bool,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO quote_settings (record_id, guild_id, add_quotes) VALUES ($1, $2, $3)
+        ON CONFLICT (guild_id) DO UPDATE SET add_quotes = EXCLUDED.add_quotes
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      add_quotes,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_course(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    course_name: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        DELETE FROM course WHERE course_name = $1 AND guild_id = $2
+      "#,
+      course_name,
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    sqlx::query!(
+      r#"
+        DELETE FROM course_reminders WHERE course_name = $1 AND guild_id = $2
+      "#,
+      course_name,
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_steam_key(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    key: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        DELETE FROM steamkey WHERE steam_key = $1 AND guild_id = $2
+      "#,
+      key,
       guild_id.to_string(),
     )
     .execute(&mut **transaction)
@@ -2026,6 +3537,85 @@ impl DatabaseHandler {
     Ok(row.exists.unwrap())
   }
 
+  pub async fn add_term_suggestion(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    term_name: &str,
+    meaning: &str,
+    usage: Option<&str>,
+    links: &[String],
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO term_suggestion (record_id, term_name, meaning, usage, links, user_id, guild_id) VALUES ($1, $2, $3, $4, $5, $6, $7)
+      "#,
+      Ulid::new().to_string(),
+      term_name,
+      meaning,
+      usage,
+      links,
+      user_id.to_string(),
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn get_pending_term_suggestions(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<TermSuggestion>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT record_id, term_name, meaning, usage, links, user_id, created_at
+        FROM term_suggestion
+        WHERE guild_id = $1 AND status = 'pending'
+        ORDER BY created_at ASC
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let suggestions = rows
+      .into_iter()
+      .map(|row| TermSuggestion {
+        id: row.record_id,
+        term_name: row.term_name,
+        meaning: row.meaning,
+        usage: row.usage,
+        links: row.links,
+        user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+        created_at: row.created_at,
+      })
+      .collect();
+
+    Ok(suggestions)
+  }
+
+  pub async fn review_term_suggestion(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    suggestion_id: &str,
+    status: &str,
+    reviewer_id: &serenity::UserId,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE term_suggestion SET status = $1, reviewed_by = $2 WHERE record_id = $3
+      "#,
+      status,
+      reviewer_id.to_string(),
+      suggestion_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
   pub async fn remove_term(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     term_name: &str,
@@ -2049,6 +3639,7 @@ impl DatabaseHandler {
     guild_id: &serenity::GuildId,
     user_id: &serenity::UserId,
     timeframe: &Timeframe,
+    source: Option<EntrySource>,
   ) -> Result<UserStats> {
     // Get total count, total sum, and count/sum for timeframe
     let end_time = chrono::Utc::now();
@@ -2063,10 +3654,11 @@ impl DatabaseHandler {
       r#"
         SELECT COUNT(record_id) AS total_count, SUM(meditation_minutes) AS total_sum
         FROM meditation
-        WHERE guild_id = $1 AND user_id = $2
+        WHERE guild_id = $1 AND user_id = $2 AND ($3::text IS NULL OR source = $3)
       "#,
       guild_id.to_string(),
       user_id.to_string(),
+      source.map(EntrySource::key),
     )
     .fetch_one(&mut **transaction)
     .await?;
@@ -2076,12 +3668,26 @@ impl DatabaseHandler {
       r#"
         SELECT COUNT(record_id) AS count, SUM(meditation_minutes) AS sum
         FROM meditation
-        WHERE guild_id = $1 AND user_id = $2 AND occurred_at >= $3 AND occurred_at <= $4
+        WHERE guild_id = $1 AND user_id = $2 AND occurred_at >= $3 AND occurred_at <= $4 AND ($5::text IS NULL OR source = $5)
       "#,
       guild_id.to_string(),
       user_id.to_string(),
       start_time,
       end_time,
+      source.map(EntrySource::key),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    let talk_data = sqlx::query!(
+      r#"
+        SELECT COUNT(record_id) AS talk_count, SUM(minutes) AS talk_sum
+        FROM activity_log
+        WHERE guild_id = $1 AND user_id = $2 AND activity = $3
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+      Activity::Talk.key(),
     )
     .fetch_one(&mut **transaction)
     .await?;
@@ -2091,6 +3697,8 @@ impl DatabaseHandler {
       all_count: total_data.total_count.unwrap_or(0).try_into()?,
       timeframe_stats: timeframe_data,
       streak: DatabaseHandler::get_streak(transaction, guild_id, user_id).await?,
+      talks_attended: talk_data.talk_count.unwrap_or(0),
+      talk_minutes: talk_data.talk_sum.unwrap_or(0),
     };
 
     Ok(user_stats)
@@ -2100,6 +3708,7 @@ impl DatabaseHandler {
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     guild_id: &serenity::GuildId,
     timeframe: &Timeframe,
+    source: Option<EntrySource>,
   ) -> Result<GuildStats> {
     // Get total count, total sum, and count/sum for timeframe
     let end_time = chrono::Utc::now();
@@ -2114,9 +3723,10 @@ impl DatabaseHandler {
       r#"
         SELECT COUNT(record_id) AS total_count, SUM(meditation_minutes) AS total_sum
         FROM meditation
-        WHERE guild_id = $1
+        WHERE guild_id = $1 AND ($2::text IS NULL OR source = $2)
       "#,
       guild_id.to_string(),
+      source.map(EntrySource::key),
     )
     .fetch_one(&mut **transaction)
     .await?;
@@ -2126,11 +3736,12 @@ impl DatabaseHandler {
       r#"
         SELECT COUNT(record_id) AS count, SUM(meditation_minutes) AS sum
         FROM meditation
-        WHERE guild_id = $1 AND occurred_at >= $2 AND occurred_at <= $3
+        WHERE guild_id = $1 AND occurred_at >= $2 AND occurred_at <= $3 AND ($4::text IS NULL OR source = $4)
       "#,
       guild_id.to_string(),
       start_time,
       end_time,
+      source.map(EntrySource::key),
     )
     .fetch_one(&mut **transaction)
     .await?;
@@ -2144,75 +3755,259 @@ impl DatabaseHandler {
     Ok(guild_stats)
   }
 
-  pub async fn quote_exists(
+  /// Gets totals for every user who meditated in any of the guilds during the current or previous period of the timeframe, counting at most `daily_cap` minutes per user per day.
+  ///
+  /// Users are combined across guilds, so partner servers can share a leaderboard. Periods are rolling windows ending now. Entry times are stored in the user's local time, so active days follow their time zone. Users with private stats are left out.
+  pub async fn get_leaderboard(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_ids: &[serenity::GuildId],
+    timeframe: &Timeframe,
+    daily_cap: Option<i32>,
+  ) -> Result<Vec<LeaderboardUser>> {
+    let period = timeframe.period();
+    let end_time = chrono::Utc::now();
+    let start_time = end_time - period;
+    let previous_start_time = start_time - period;
+
+    let rows = sqlx::query!(
+      r#"
+        WITH daily AS (
+          SELECT meditation.user_id, occurred_at >= $2 AS current,
+            LEAST(SUM(meditation_minutes), COALESCE($5::INTEGER, 2147483647)) AS minutes,
+            COUNT(*) AS sessions
+          FROM meditation
+          LEFT JOIN tracking_profile ON tracking_profile.user_id = meditation.user_id AND tracking_profile.guild_id = meditation.guild_id
+          WHERE meditation.guild_id = ANY($1) AND occurred_at >= $3 AND occurred_at <= $4
+            AND NOT COALESCE(tracking_profile.stats_private, FALSE)
+          GROUP BY meditation.user_id, (occurred_at AT TIME ZONE 'UTC')::date, occurred_at >= $2
+        )
+        SELECT user_id,
+          COALESCE(SUM(minutes) FILTER (WHERE current), 0)::BIGINT AS "minutes!",
+          COALESCE(SUM(sessions) FILTER (WHERE current), 0)::BIGINT AS "sessions!",
+          COUNT(*) FILTER (WHERE current) AS "days_active!",
+          COALESCE(SUM(minutes) FILTER (WHERE NOT current), 0)::BIGINT AS "previous_minutes!"
+        FROM daily
+        GROUP BY user_id
+      "#,
+      &guild_ids.iter().map(ToString::to_string).collect::<Vec<String>>(),
+      start_time,
+      previous_start_time,
+      end_time,
+      daily_cap,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let users = rows
+      .into_iter()
+      .map(|row| LeaderboardUser {
+        user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+        minutes: row.minutes,
+        sessions: row.sessions,
+        days_active: row.days_active,
+        previous_minutes: row.previous_minutes,
+      })
+      .collect();
+
+    Ok(users)
+  }
+
+  /// Gets the totals of each of the given users since the start time. Users without entries are left out.
+  pub async fn get_users_totals(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     guild_id: &serenity::GuildId,
-    quote_id: &str,
-  ) -> Result<bool> {
-    let row = sqlx::query!(
+    user_ids: &[serenity::UserId],
+    start_time: chrono::DateTime<Utc>,
+  ) -> Result<Vec<UserTotals>> {
+    let rows = sqlx::query!(
       r#"
-        SELECT EXISTS(SELECT 1 FROM quote WHERE record_id = $1 AND guild_id = $2)
+        SELECT user_id, SUM(meditation_minutes) AS "minutes!", COUNT(record_id) AS "sessions!"
+        FROM meditation
+        WHERE guild_id = $1 AND user_id = ANY($2) AND occurred_at >= $3
+        GROUP BY user_id
       "#,
-      quote_id,
       guild_id.to_string(),
+      &user_ids
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>(),
+      start_time,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let totals = rows
+      .into_iter()
+      .map(|row| UserTotals {
+        user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+        minutes: row.minutes,
+        sessions: row.sessions,
+      })
+      .collect();
+
+    Ok(totals)
+  }
+
+  /// Gets the total minutes meditated across the guilds during the current period of the timeframe.
+  pub async fn get_guilds_minutes(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_ids: &[serenity::GuildId],
+    timeframe: &Timeframe,
+  ) -> Result<i64> {
+    let start_time = chrono::Utc::now() - timeframe.period();
+
+    let row = sqlx::query!(
+      r#"
+        SELECT COALESCE(SUM(meditation_minutes), 0) AS "minutes!" FROM meditation WHERE guild_id = ANY($1) AND occurred_at >= $2
+      "#,
+      &guild_ids.iter().map(ToString::to_string).collect::<Vec<String>>(),
+      start_time,
     )
     .fetch_one(&mut **transaction)
     .await?;
 
-    Ok(row.exists.unwrap())
+    Ok(row.minutes)
   }
 
-  pub async fn get_user_chart_stats(
+  pub async fn add_guild_link(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     guild_id: &serenity::GuildId,
-    user_id: &serenity::UserId,
-    timeframe: &Timeframe,
-  ) -> Result<Vec<TimeframeStats>> {
-    // Get the last 12 days, weeks, months, or years
-    let rows: Vec<Res> = match timeframe {
-      Timeframe::Daily => {
-        sqlx::query_as!(
-          Res,
-          r#"WITH "daily_data" AS (
-            SELECT date_part('day', NOW() - DATE_TRUNC('day', "occurred_at")) AS times_ago, meditation_minutes
-            FROM meditation
-            WHERE guild_id = $1 AND user_id = $2
-          ) SELECT "times_ago", SUM(meditation_minutes) AS meditation_minutes, COUNT(*) AS meditation_count
-          FROM "daily_data"
-          WHERE "times_ago" <= 12
-          GROUP BY "times_ago";"#,
-          guild_id.to_string(),
-          user_id.to_string(),
-        ).fetch_all(&mut **transaction).await?
-      },
-      Timeframe::Weekly => {
-        sqlx::query_as!(
-          Res,
-          r#"WITH "weekly_data" AS (
-            SELECT floor(extract(epoch from NOW() - "occurred_at")/(60*60*24*7))::float AS "times_ago", meditation_minutes
-            FROM meditation
-            WHERE "guild_id" = $1 AND "user_id" = $2
-        ) SELECT "times_ago", SUM(meditation_minutes) AS meditation_minutes, COUNT(*) AS meditation_count
-            FROM "weekly_data"
-            WHERE "times_ago" <= 12
-        GROUP BY "times_ago";"#,
-          guild_id.to_string(),
-          user_id.to_string(),
-        ).fetch_all(&mut **transaction).await?
-      },
+    partner_guild_id: &serenity::GuildId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        INSERT INTO guild_links (record_id, guild_id, partner_guild_id) VALUES ($1, $2, $3)
+        ON CONFLICT (guild_id, partner_guild_id) DO NOTHING
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      partner_guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn remove_guild_link(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    partner_guild_id: &serenity::GuildId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM guild_links WHERE guild_id = $1 AND partner_guild_id = $2
+      "#,
+      guild_id.to_string(),
+      partner_guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Gets the guilds this guild has linked with, and whether each has linked back.
+  pub async fn get_guild_links(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<GuildLink>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT partner_guild_id, EXISTS (
+          SELECT 1 FROM guild_links AS partner WHERE partner.guild_id = guild_links.partner_guild_id AND partner.partner_guild_id = guild_links.guild_id
+        ) AS "mutual!"
+        FROM guild_links WHERE guild_id = $1 ORDER BY created_at ASC
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let links = rows
+      .into_iter()
+      .map(|row| GuildLink {
+        partner_guild_id: serenity::GuildId::new(row.partner_guild_id.parse::<u64>().unwrap()),
+        mutual: row.mutual,
+      })
+      .collect();
+
+    Ok(links)
+  }
+
+  pub async fn quote_exists(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    quote_id: &str,
+  ) -> Result<bool> {
+    let row = sqlx::query!(
+      r#"
+        SELECT EXISTS(SELECT 1 FROM quote WHERE record_id = $1 AND guild_id = $2)
+      "#,
+      quote_id,
+      guild_id.to_string(),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.exists.unwrap())
+  }
+
+  pub async fn get_user_chart_stats(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    timeframe: &Timeframe,
+    source: Option<EntrySource>,
+  ) -> Result<Vec<TimeframeStats>> {
+    // Get the last 12 days, weeks, months, or years
+    let rows: Vec<Res> = match timeframe {
+      Timeframe::Daily => {
+        sqlx::query_as!(
+          Res,
+          r#"WITH "daily_data" AS (
+            SELECT date_part('day', NOW() - DATE_TRUNC('day', "occurred_at")) AS times_ago, meditation_minutes
+            FROM meditation
+            WHERE guild_id = $1 AND user_id = $2 AND ($3::text IS NULL OR source = $3)
+          ) SELECT "times_ago", SUM(meditation_minutes) AS meditation_minutes, COUNT(*) AS meditation_count
+          FROM "daily_data"
+          WHERE "times_ago" <= 12
+          GROUP BY "times_ago";"#,
+          guild_id.to_string(),
+          user_id.to_string(),
+          source.map(EntrySource::key),
+        ).fetch_all(&mut **transaction).await?
+      },
+      Timeframe::Weekly => {
+        sqlx::query_as!(
+          Res,
+          r#"WITH "weekly_data" AS (
+            SELECT floor(extract(epoch from NOW() - "occurred_at")/(60*60*24*7))::float AS "times_ago", meditation_minutes
+            FROM meditation
+            WHERE "guild_id" = $1 AND "user_id" = $2 AND ($3::text IS NULL OR source = $3)
+        ) SELECT "times_ago", SUM(meditation_minutes) AS meditation_minutes, COUNT(*) AS meditation_count
+            FROM "weekly_data"
+            WHERE "times_ago" <= 12
+        GROUP BY "times_ago";"#,
+          guild_id.to_string(),
+          user_id.to_string(),
+          source.map(EntrySource::key),
+        ).fetch_all(&mut **transaction).await?
+      },
       Timeframe::Monthly => {
         sqlx::query_as!(
           Res,
           r#"WITH "monthly_data" AS (
             SELECT floor(extract(epoch from NOW() - "occurred_at")/(60*60*24*30))::float AS "times_ago", meditation_minutes
             FROM meditation
-            WHERE "guild_id" = $1 AND "user_id" = $2
+            WHERE "guild_id" = $1 AND "user_id" = $2 AND ($3::text IS NULL OR source = $3)
         ) SELECT "times_ago", SUM(meditation_minutes) AS meditation_minutes, COUNT(*) AS meditation_count
             FROM "monthly_data"
             WHERE "times_ago" <= 12
         GROUP BY "times_ago";"#,
           guild_id.to_string(),
           user_id.to_string(),
+          source.map(EntrySource::key),
         ).fetch_all(&mut **transaction).await?
       },
       Timeframe::Yearly => {
@@ -2221,13 +4016,14 @@ impl DatabaseHandler {
           r#"WITH "yearly_data" AS (
             SELECT floor(extract(epoch from NOW() - "occurred_at")/(60*60*24*365))::float AS "times_ago", meditation_minutes
             FROM meditation
-            WHERE "guild_id" = $1 AND "user_id" = $2
+            WHERE "guild_id" = $1 AND "user_id" = $2 AND ($3::text IS NULL OR source = $3)
         ) SELECT "times_ago", SUM(meditation_minutes) AS meditation_minutes, COUNT(*) AS meditation_count
             FROM "yearly_data"
             WHERE "times_ago" <= 12
         GROUP BY "times_ago";"#,
           guild_id.to_string(),
           user_id.to_string(),
+          source.map(EntrySource::key),
         ).fetch_all(&mut **transaction).await?
       },
     };
@@ -2261,10 +4057,55 @@ impl DatabaseHandler {
     Ok(stats)
   }
 
+  /// Gets the trailing `window_days` average for each of the last 12 days, oldest first, for a user or, when no user is given, the whole guild. Only entries from `source` are counted, if given.
+  pub async fn get_rolling_averages(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: Option<&serenity::UserId>,
+    window_days: i32,
+    source: Option<EntrySource>,
+  ) -> Result<Vec<RollingAverage>> {
+    let rows = sqlx::query!(
+      r#"
+        WITH days AS (
+          SELECT generate_series(DATE_TRUNC('day', NOW()) - ($3 + 10) * INTERVAL '1 day', DATE_TRUNC('day', NOW()), INTERVAL '1 day') AS day
+        ), daily AS (
+          SELECT days.day, COALESCE(SUM(meditation.meditation_minutes), 0) AS minutes, COUNT(meditation.record_id) AS sessions
+          FROM days
+          LEFT JOIN meditation ON DATE_TRUNC('day', meditation.occurred_at) = days.day AND meditation.guild_id = $1 AND ($2::text IS NULL OR meditation.user_id = $2) AND ($4::text IS NULL OR meditation.source = $4)
+          GROUP BY days.day
+        ), averages AS (
+          SELECT day, (AVG(minutes) OVER w)::float8 AS average_minutes, (AVG(sessions) OVER w)::float8 AS average_count
+          FROM daily
+          WINDOW w AS (ORDER BY day ROWS BETWEEN $3 - 1 PRECEDING AND CURRENT ROW)
+        )
+        SELECT average_minutes, average_count FROM averages ORDER BY day DESC LIMIT 12
+      "#,
+      guild_id.to_string(),
+      user_id.map(ToString::to_string),
+      window_days,
+      source.map(EntrySource::key),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let averages = rows
+      .into_iter()
+      .map(|row| RollingAverage {
+        minutes: row.average_minutes.unwrap_or(0.0),
+        count: row.average_count.unwrap_or(0.0),
+      })
+      .rev()
+      .collect();
+
+    Ok(averages)
+  }
+
   pub async fn get_guild_chart_stats(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     guild_id: &serenity::GuildId,
     timeframe: &Timeframe,
+    source: Option<EntrySource>,
   ) -> Result<Vec<TimeframeStats>> {
     // Get the last 12 days, weeks, months, or years
     let rows: Vec<Res> = match timeframe {
@@ -2274,12 +4115,13 @@ impl DatabaseHandler {
           r#"WITH "daily_data" AS (
             SELECT date_part('day', NOW() - DATE_TRUNC('day', "occurred_at")) AS times_ago, meditation_minutes
             FROM meditation
-            WHERE guild_id = $1
+            WHERE guild_id = $1 AND ($2::text IS NULL OR source = $2)
           ) SELECT "times_ago", SUM(meditation_minutes) AS meditation_minutes, COUNT(*) AS meditation_count
           FROM "daily_data"
           WHERE "times_ago" <= 12
           GROUP BY "times_ago";"#,
           guild_id.to_string(),
+          source.map(EntrySource::key),
         ).fetch_all(&mut **transaction).await?
       },
       Timeframe::Weekly => {
@@ -2288,12 +4130,13 @@ impl DatabaseHandler {
           r#"WITH "weekly_data" AS (
             SELECT floor(extract(epoch from NOW() - "occurred_at")/(60*60*24*7))::float AS "times_ago", meditation_minutes
             FROM meditation
-            WHERE "guild_id" = $1
+            WHERE "guild_id" = $1 AND ($2::text IS NULL OR source = $2)
         ) SELECT "times_ago", SUM(meditation_minutes) AS meditation_minutes, COUNT(*) AS meditation_count
             FROM "weekly_data"
             WHERE "times_ago" <= 12
         GROUP BY "times_ago";"#,
           guild_id.to_string(),
+          source.map(EntrySource::key),
         ).fetch_all(&mut **transaction).await?
       },
       Timeframe::Monthly => {
@@ -2302,12 +4145,13 @@ impl DatabaseHandler {
           r#"WITH "monthly_data" AS (
             SELECT floor(extract(epoch from NOW() - "occurred_at")/(60*60*24*30))::float AS "times_ago", meditation_minutes
             FROM meditation
-            WHERE "guild_id" = $1
+            WHERE "guild_id" = $1 AND ($2::text IS NULL OR source = $2)
         ) SELECT "times_ago", SUM(meditation_minutes) AS meditation_minutes, COUNT(*) AS meditation_count
             FROM "monthly_data"
             WHERE "times_ago" <= 12
         GROUP BY "times_ago";"#,
           guild_id.to_string(),
+          source.map(EntrySource::key),
         ).fetch_all(&mut **transaction).await?
       },
       Timeframe::Yearly => {
@@ -2316,12 +4160,13 @@ impl DatabaseHandler {
           r#"WITH "yearly_data" AS (
             SELECT floor(extract(epoch from NOW() - "occurred_at")/(60*60*24*365))::float AS "times_ago", meditation_minutes
             FROM meditation
-            WHERE "guild_id" = $1
+            WHERE "guild_id" = $1 AND ($2::text IS NULL OR source = $2)
         ) SELECT "times_ago", SUM(meditation_minutes) AS meditation_minutes, COUNT(*) AS meditation_count
             FROM "yearly_data"
             WHERE "times_ago" <= 12
         GROUP BY "times_ago";"#,
           guild_id.to_string(),
+          source.map(EntrySource::key),
         ).fetch_all(&mut **transaction).await?
       },
     };
@@ -2361,7 +4206,7 @@ impl DatabaseHandler {
   ) -> Result<Option<StarMessage>> {
     let row = sqlx::query!(
       r#"
-        SELECT record_id, starred_message_id, board_message_id, starred_channel_id
+        SELECT record_id, starred_message_id, board_message_id, starred_channel_id, board_channel_id, guild_id
         FROM "star"
         WHERE starred_message_id = $1
       "#,
@@ -2380,6 +4225,12 @@ impl DatabaseHandler {
         starred_channel_id: serenity::ChannelId::new(
           row.starred_channel_id.parse::<u64>().unwrap(),
         ),
+        board_channel_id: row
+          .board_channel_id
+          .map(|channel_id| serenity::ChannelId::new(channel_id.parse::<u64>().unwrap())),
+        guild_id: row
+          .guild_id
+          .map(|guild_id| serenity::GuildId::new(guild_id.parse::<u64>().unwrap())),
       }),
       None => None,
     };
@@ -2387,6 +4238,119 @@ impl DatabaseHandler {
     Ok(star_message)
   }
 
+  /// Gets starred messages that haven't been checked for deletion since `checked_before`, least recently checked first. Messages already marked as deleted are skipped.
+  pub async fn get_unchecked_star_messages(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    checked_before: chrono::DateTime<Utc>,
+    limit: i64,
+  ) -> Result<Vec<StarMessage>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT record_id, starred_message_id, board_message_id, starred_channel_id, board_channel_id, guild_id
+        FROM "star"
+        WHERE NOT source_deleted AND (checked_at IS NULL OR checked_at < $1)
+        ORDER BY checked_at NULLS FIRST
+        LIMIT $2
+      "#,
+      checked_before,
+      limit,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| StarMessage {
+          record_id: row.record_id,
+          starred_message_id: serenity::MessageId::new(
+            row.starred_message_id.parse::<u64>().unwrap(),
+          ),
+          board_message_id: serenity::MessageId::new(row.board_message_id.parse::<u64>().unwrap()),
+          starred_channel_id: serenity::ChannelId::new(
+            row.starred_channel_id.parse::<u64>().unwrap(),
+          ),
+          board_channel_id: row
+            .board_channel_id
+            .map(|channel_id| serenity::ChannelId::new(channel_id.parse::<u64>().unwrap())),
+          guild_id: row
+            .guild_id
+            .map(|guild_id| serenity::GuildId::new(guild_id.parse::<u64>().unwrap())),
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn mark_star_message_checked(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    record_id: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE "star" SET checked_at = NOW() WHERE record_id = $1
+      "#,
+      record_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Marks a starred message as deleted, keeping its starboard post and star count.
+  pub async fn mark_star_source_deleted(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    record_id: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE "star" SET source_deleted = TRUE WHERE record_id = $1
+      "#,
+      record_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Gets what happens to the starboard post when a starred message is deleted, if the guild has chosen.
+  pub async fn get_starboard_deleted_action(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Option<String>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT deleted_action FROM starboard_settings WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| row.deleted_action))
+  }
+
+  pub async fn set_starboard_deleted_action(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    deleted_action: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO starboard_settings (record_id, guild_id, deleted_action) VALUES ($1, $2, $3)
+        ON CONFLICT (guild_id) DO UPDATE SET deleted_action = $3
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      deleted_action,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
   pub async fn delete_star_message(
     transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     record_id: &str,
@@ -2400,6 +4364,15 @@ impl DatabaseHandler {
     .execute(&mut **transaction)
     .await?;
 
+    sqlx::query!(
+      r#"
+        DELETE FROM star_count_history WHERE star_id = $1
+      "#,
+      record_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
     Ok(())
   }
 
@@ -2408,19 +4381,5120 @@ impl DatabaseHandler {
     starred_message_id: &serenity::MessageId,
     board_message_id: &serenity::MessageId,
     starred_channel_id: &serenity::ChannelId,
+    board_channel_id: &serenity::ChannelId,
+    guild_id: Option<&serenity::GuildId>,
+    author_id: &serenity::UserId,
+    star_count: i32,
   ) -> Result<()> {
+    let record_id = Ulid::new().to_string();
+
     sqlx::query!(
       r#"
-        INSERT INTO "star" (record_id, starred_message_id, board_message_id, starred_channel_id) VALUES ($1, $2, $3, $4)
+        INSERT INTO "star" (record_id, starred_message_id, board_message_id, starred_channel_id, board_channel_id, guild_id, author_id, star_count) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
       "#,
-      Ulid::new().to_string(),
+      record_id,
       starred_message_id.to_string(),
       board_message_id.to_string(),
       starred_channel_id.to_string(),
+      board_channel_id.to_string(),
+      guild_id.map(ToString::to_string),
+      author_id.to_string(),
+      star_count,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    sqlx::query!(
+      r#"
+        INSERT INTO star_count_history (record_id, star_id, star_count) VALUES ($1, $2, $3)
+      "#,
+      Ulid::new().to_string(),
+      record_id,
+      star_count,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Updates the star count of a starred message, recording the change in its history.
+  pub async fn update_star_count(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    record_id: &str,
+    star_count: i32,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE "star" SET star_count = $2 WHERE record_id = $1
+      "#,
+      record_id,
+      star_count,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    sqlx::query!(
+      r#"
+        INSERT INTO star_count_history (record_id, star_id, star_count) VALUES ($1, $2, $3)
+      "#,
+      Ulid::new().to_string(),
+      record_id,
+      star_count,
     )
     .execute(&mut **transaction)
     .await?;
 
     Ok(())
   }
+
+  /// Gets the members whose messages received the most stars, ordered by total stars. Messages starred before authors were recorded are not counted.
+  pub async fn get_star_leaderboard(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    limit: i64,
+  ) -> Result<Vec<StarredAuthor>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT author_id AS "author_id!", COUNT(*) AS "messages!", COALESCE(SUM(star_count), 0)::BIGINT AS "stars!"
+        FROM "star"
+        WHERE guild_id = $1 AND author_id IS NOT NULL
+        GROUP BY author_id
+        ORDER BY 3 DESC, 2 DESC
+        LIMIT $2
+      "#,
+      guild_id.to_string(),
+      limit,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| StarredAuthor {
+          user_id: serenity::UserId::new(row.author_id.parse::<u64>().unwrap()),
+          messages: row.messages,
+          stars: row.stars,
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn get_author_stars(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+  ) -> Result<StarredAuthor> {
+    let row = sqlx::query!(
+      r#"
+        SELECT COUNT(*) AS "messages!", COALESCE(SUM(star_count), 0)::BIGINT AS "stars!"
+        FROM "star"
+        WHERE guild_id = $1 AND author_id = $2
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(StarredAuthor {
+      user_id: *user_id,
+      messages: row.messages,
+      stars: row.stars,
+    })
+  }
+
+  /// Whether a channel is a thread created for a starred message in a starboard forum.
+  pub async fn is_starboard_thread(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    channel_id: &serenity::ChannelId,
+  ) -> Result<bool> {
+    let row = sqlx::query!(
+      r#"
+        SELECT EXISTS (SELECT 1 FROM "star" WHERE board_channel_id = $1)
+      "#,
+      channel_id.to_string(),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.exists.unwrap())
+  }
+
+  pub async fn get_starboard_forum(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Option<serenity::ChannelId>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT channel_id FROM starboard_forums WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| serenity::ChannelId::new(row.channel_id.parse::<u64>().unwrap())))
+  }
+
+  pub async fn set_starboard_forum(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO starboard_forums (record_id, guild_id, channel_id) VALUES ($1, $2, $3)
+        ON CONFLICT (guild_id) DO UPDATE SET channel_id = $3
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      channel_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Removes the starboard forum for a guild, along with its tags, since tags belong to the forum.
+  pub async fn remove_starboard_forum(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM starboard_forums WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    sqlx::query!(
+      r#"
+        DELETE FROM starboard_forum_tags WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn get_starboard_forum_tags(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<StarboardForumTag>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT source_channel_id, tag_id FROM starboard_forum_tags WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| StarboardForumTag {
+          source_channel_id: serenity::ChannelId::new(
+            row.source_channel_id.parse::<u64>().unwrap(),
+          ),
+          tag_id: serenity::ForumTagId::new(row.tag_id.parse::<u64>().unwrap()),
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn add_starboard_forum_tag(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    source_channel_id: &serenity::ChannelId,
+    tag_id: &serenity::ForumTagId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        INSERT INTO starboard_forum_tags (record_id, guild_id, source_channel_id, tag_id) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (source_channel_id, tag_id) DO NOTHING
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      source_channel_id.to_string(),
+      tag_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Removes a tag from a source channel, or all of the channel's tags when `tag_id` is `None`.
+  pub async fn remove_starboard_forum_tags(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    source_channel_id: &serenity::ChannelId,
+    tag_id: Option<&serenity::ForumTagId>,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM starboard_forum_tags WHERE guild_id = $1 AND source_channel_id = $2 AND ($3::TEXT IS NULL OR tag_id = $3)
+      "#,
+      guild_id.to_string(),
+      source_channel_id.to_string(),
+      tag_id.map(ToString::to_string),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn add_command_usage(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    command_name: &str,
+    user_id: &serenity::UserId,
+    guild_id: Option<&serenity::GuildId>,
+    latency_ms: i32,
+    success: bool,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO command_usage (record_id, command_name, user_id, guild_id, latency_ms, success) VALUES ($1, $2, $3, $4, $5, $6)
+      "#,
+      Ulid::new().to_string(),
+      command_name,
+      user_id.to_string(),
+      guild_id.map(ToString::to_string),
+      latency_ms,
+      success,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn get_command_usage_stats(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    start_time: chrono::DateTime<Utc>,
+  ) -> Result<Vec<CommandUsageStats>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT command_name, COUNT(*) AS uses, COUNT(*) FILTER (WHERE NOT success) AS errors,
+          PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY latency_ms) AS median_latency,
+          PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY latency_ms) AS p95_latency
+        FROM command_usage
+        WHERE guild_id = $1 AND occurred_at >= $2
+        GROUP BY command_name
+        ORDER BY uses DESC, command_name ASC
+      "#,
+      guild_id.to_string(),
+      start_time,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let stats = rows
+      .into_iter()
+      .map(|row| CommandUsageStats {
+        command_name: row.command_name,
+        uses: row.uses.unwrap_or(0),
+        errors: row.errors.unwrap_or(0),
+        median_latency: row.median_latency.unwrap_or(0.0),
+        p95_latency: row.p95_latency.unwrap_or(0.0),
+      })
+      .collect();
+
+    Ok(stats)
+  }
+
+  pub async fn add_command_restriction(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    command_name: &str,
+    role_id: Option<&serenity::RoleId>,
+    channel_id: Option<&serenity::ChannelId>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO command_permissions (record_id, guild_id, command_name, role_id, channel_id) VALUES ($1, $2, $3, $4, $5)
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      command_name,
+      role_id.map(ToString::to_string),
+      channel_id.map(ToString::to_string),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_command_restriction(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    command_name: &str,
+    role_id: Option<&serenity::RoleId>,
+    channel_id: Option<&serenity::ChannelId>,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM command_permissions
+        WHERE guild_id = $1 AND command_name = $2
+          AND role_id IS NOT DISTINCT FROM $3 AND channel_id IS NOT DISTINCT FROM $4
+      "#,
+      guild_id.to_string(),
+      command_name,
+      role_id.map(ToString::to_string),
+      channel_id.map(ToString::to_string),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn command_restriction_exists(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    command_name: &str,
+    role_id: Option<&serenity::RoleId>,
+    channel_id: Option<&serenity::ChannelId>,
+  ) -> Result<bool> {
+    let row = sqlx::query!(
+      r#"
+        SELECT EXISTS (
+          SELECT 1 FROM command_permissions
+          WHERE guild_id = $1 AND command_name = $2
+            AND role_id IS NOT DISTINCT FROM $3 AND channel_id IS NOT DISTINCT FROM $4
+        )
+      "#,
+      guild_id.to_string(),
+      command_name,
+      role_id.map(ToString::to_string),
+      channel_id.map(ToString::to_string),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.exists.unwrap())
+  }
+
+  /// Gets restrictions for the given command names, or for all commands if `command_names` is empty.
+  pub async fn get_command_restrictions(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    command_names: &[String],
+  ) -> Result<Vec<CommandRestriction>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT command_name, role_id, channel_id FROM command_permissions
+        WHERE guild_id = $1 AND (CARDINALITY($2::text[]) = 0 OR command_name = ANY($2))
+        ORDER BY command_name ASC, created_at ASC
+      "#,
+      guild_id.to_string(),
+      command_names,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let restrictions = rows
+      .into_iter()
+      .map(|row| CommandRestriction {
+        command_name: row.command_name,
+        role_id: row
+          .role_id
+          .map(|id| serenity::RoleId::new(id.parse::<u64>().unwrap())),
+        channel_id: row
+          .channel_id
+          .map(|id| serenity::ChannelId::new(id.parse::<u64>().unwrap())),
+      })
+      .collect();
+
+    Ok(restrictions)
+  }
+
+  /// Builds a JSON backup of all data owned by a guild, excluding meditation entries, steam keys, and term embeddings.
+  pub async fn get_guild_backup(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<String> {
+    let row = sqlx::query!(
+      r#"
+        SELECT jsonb_pretty(jsonb_build_object(
+          'format', 'bloombot-backup',
+          'version', 1,
+          'guild_id', $1::text,
+          'created_at', NOW(),
+          'terms', COALESCE((
+            SELECT jsonb_agg(t ORDER BY t.term_name) FROM (
+              SELECT record_id, term_name, meaning, usage, links, category, aliases, pronunciation
+              FROM term WHERE guild_id = $1
+            ) t
+          ), '[]'::jsonb),
+          'quotes', COALESCE((
+            SELECT jsonb_agg(q ORDER BY q.created_at) FROM (
+              SELECT record_id, quote, author, created_at FROM quote WHERE guild_id = $1
+            ) q
+          ), '[]'::jsonb),
+          'courses', COALESCE((
+            SELECT jsonb_agg(c ORDER BY c.course_name) FROM (
+              SELECT record_id, course_name, participant_role, graduate_role, created_at
+              FROM course WHERE guild_id = $1
+            ) c
+          ), '[]'::jsonb),
+          'steam_keys', (
+            SELECT jsonb_build_object(
+              'total', COUNT(*),
+              'used', COUNT(*) FILTER (WHERE used),
+              'reserved', COUNT(*) FILTER (WHERE reserved IS NOT NULL AND NOT used)
+            )
+            FROM steamkey WHERE guild_id = $1
+          ),
+          'steam_key_recipients', COALESCE((
+            SELECT jsonb_agg(r) FROM (
+              SELECT record_id, user_id, challenge_prize, donator_perk, total_keys
+              FROM steamkey_recipients WHERE guild_id = $1
+            ) r
+          ), '[]'::jsonb),
+          'tracking_profiles', COALESCE((
+            SELECT jsonb_agg(p) FROM (
+              SELECT record_id, user_id, utc_offset, anonymous_tracking, streaks_active, streaks_private, stats_private
+              FROM tracking_profile WHERE guild_id = $1
+            ) p
+          ), '[]'::jsonb),
+          'quote_settings', (
+            SELECT to_jsonb(s) FROM (
+              SELECT record_id, card_background, add_quotes FROM quote_settings WHERE guild_id = $1
+            ) s
+          ),
+          'command_permissions', COALESCE((
+            SELECT jsonb_agg(cp ORDER BY cp.command_name) FROM (
+              SELECT record_id, command_name, role_id, channel_id FROM command_permissions WHERE guild_id = $1
+            ) cp
+          ), '[]'::jsonb)
+        )) AS archive
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.archive.unwrap_or_default())
+  }
+
+  /// Reads the format and version of a backup archive. Fails if the archive is not valid JSON.
+  pub async fn get_backup_format(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    archive: &str,
+  ) -> Result<(Option<String>, Option<String>)> {
+    let row = sqlx::query!(
+      r#"
+        SELECT ($1::text)::jsonb ->> 'format' AS format, ($1::text)::jsonb ->> 'version' AS version
+      "#,
+      archive,
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok((row.format, row.version))
+  }
+
+  /// Restores a backup made with [`DatabaseHandler::get_guild_backup`] into a guild.
+  ///
+  /// Only missing records are added, so existing data is never overwritten. Restored terms have no embeddings until they are reindexed.
+  pub async fn restore_guild_backup(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    archive: &str,
+  ) -> Result<RestoreCounts> {
+    let mut counts = RestoreCounts::default();
+
+    counts.terms = sqlx::query!(
+      r#"
+        INSERT INTO term (record_id, term_name, meaning, usage, links, category, aliases, pronunciation, guild_id)
+        SELECT record_id, term_name, meaning, usage, links, category, aliases, pronunciation, $1
+        FROM jsonb_to_recordset(($2::text)::jsonb -> 'terms') AS t(
+          record_id TEXT, term_name TEXT, meaning TEXT, usage TEXT, links TEXT[], category TEXT, aliases TEXT[], pronunciation TEXT
+        )
+        ON CONFLICT DO NOTHING
+      "#,
+      guild_id.to_string(),
+      archive,
+    )
+    .execute(&mut **transaction)
+    .await?
+    .rows_affected();
+
+    counts.quotes = sqlx::query!(
+      r#"
+        INSERT INTO quote (record_id, quote, author, created_at, guild_id)
+        SELECT record_id, quote, author, created_at, $1
+        FROM jsonb_to_recordset(($2::text)::jsonb -> 'quotes') AS q(
+          record_id TEXT, quote TEXT, author TEXT, created_at TIMESTAMPTZ
+        )
+        ON CONFLICT DO NOTHING
+      "#,
+      guild_id.to_string(),
+      archive,
+    )
+    .execute(&mut **transaction)
+    .await?
+    .rows_affected();
+
+    counts.courses = sqlx::query!(
+      r#"
+        INSERT INTO course (record_id, course_name, participant_role, graduate_role, created_at, guild_id)
+        SELECT record_id, course_name, participant_role, graduate_role, created_at, $1
+        FROM jsonb_to_recordset(($2::text)::jsonb -> 'courses') AS c(
+          record_id TEXT, course_name TEXT, participant_role TEXT, graduate_role TEXT, created_at TIMESTAMPTZ
+        )
+        ON CONFLICT DO NOTHING
+      "#,
+      guild_id.to_string(),
+      archive,
+    )
+    .execute(&mut **transaction)
+    .await?
+    .rows_affected();
+
+    counts.steam_key_recipients = sqlx::query!(
+      r#"
+        INSERT INTO steamkey_recipients (record_id, user_id, challenge_prize, donator_perk, total_keys, guild_id)
+        SELECT record_id, user_id, challenge_prize, donator_perk, total_keys, $1
+        FROM jsonb_to_recordset(($2::text)::jsonb -> 'steam_key_recipients') AS r(
+          record_id TEXT, user_id TEXT, challenge_prize BOOLEAN, donator_perk BOOLEAN, total_keys SMALLINT
+        )
+        WHERE NOT EXISTS (
+          SELECT 1 FROM steamkey_recipients existing WHERE existing.user_id = r.user_id AND existing.guild_id = $1
+        )
+        ON CONFLICT DO NOTHING
+      "#,
+      guild_id.to_string(),
+      archive,
+    )
+    .execute(&mut **transaction)
+    .await?
+    .rows_affected();
+
+    counts.tracking_profiles = sqlx::query!(
+      r#"
+        INSERT INTO tracking_profile (record_id, user_id, utc_offset, anonymous_tracking, streaks_active, streaks_private, stats_private, guild_id)
+        SELECT record_id, user_id, utc_offset, anonymous_tracking, streaks_active, streaks_private, stats_private, $1
+        FROM jsonb_to_recordset(($2::text)::jsonb -> 'tracking_profiles') AS p(
+          record_id TEXT, user_id TEXT, utc_offset SMALLINT, anonymous_tracking BOOLEAN, streaks_active BOOLEAN, streaks_private BOOLEAN, stats_private BOOLEAN
+        )
+        WHERE NOT EXISTS (
+          SELECT 1 FROM tracking_profile existing WHERE existing.user_id = p.user_id AND existing.guild_id = $1
+        )
+        ON CONFLICT DO NOTHING
+      "#,
+      guild_id.to_string(),
+      archive,
+    )
+    .execute(&mut **transaction)
+    .await?
+    .rows_affected();
+
+    counts.command_permissions = sqlx::query!(
+      r#"
+        INSERT INTO command_permissions (record_id, command_name, role_id, channel_id, guild_id)
+        SELECT record_id, command_name, role_id, channel_id, $1
+        FROM jsonb_to_recordset(($2::text)::jsonb -> 'command_permissions') AS cp(
+          record_id TEXT, command_name TEXT, role_id TEXT, channel_id TEXT
+        )
+        WHERE NOT EXISTS (
+          SELECT 1 FROM command_permissions existing
+          WHERE existing.guild_id = $1 AND existing.command_name = cp.command_name
+            AND existing.role_id IS NOT DISTINCT FROM cp.role_id
+            AND existing.channel_id IS NOT DISTINCT FROM cp.channel_id
+        )
+        ON CONFLICT DO NOTHING
+      "#,
+      guild_id.to_string(),
+      archive,
+    )
+    .execute(&mut **transaction)
+    .await?
+    .rows_affected();
+
+    counts.quote_settings = sqlx::query!(
+      r#"
+        INSERT INTO quote_settings (record_id, card_background, add_quotes, guild_id)
+        SELECT record_id, card_background, COALESCE(add_quotes, TRUE), $1
+        FROM jsonb_to_record(($2::text)::jsonb -> 'quote_settings') AS s(record_id TEXT, card_background TEXT, add_quotes BOOLEAN)
+        WHERE ($2::text)::jsonb -> 'quote_settings' != 'null'::jsonb
+        ON CONFLICT DO NOTHING
+      "#,
+      guild_id.to_string(),
+      archive,
+    )
+    .execute(&mut **transaction)
+    .await?
+    .rows_affected();
+
+    Ok(counts)
+  }
+
+  pub async fn get_moderation_reasons(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<ModerationReason>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT label, reason FROM moderation_reasons WHERE guild_id = $1 ORDER BY label ASC
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let reasons = rows
+      .into_iter()
+      .map(|row| ModerationReason {
+        label: row.label,
+        reason: row.reason,
+      })
+      .collect();
+
+    Ok(reasons)
+  }
+
+  pub async fn moderation_reason_exists(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    label: &str,
+  ) -> Result<bool> {
+    let row = sqlx::query!(
+      r#"
+        SELECT EXISTS (SELECT 1 FROM moderation_reasons WHERE guild_id = $1 AND LOWER(label) = LOWER($2))
+      "#,
+      guild_id.to_string(),
+      label,
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.exists.unwrap())
+  }
+
+  pub async fn add_moderation_reason(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    label: &str,
+    reason: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO moderation_reasons (record_id, guild_id, label, reason) VALUES ($1, $2, $3, $4)
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      label,
+      reason,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn update_moderation_reason(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    label: &str,
+    reason: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        UPDATE moderation_reasons SET reason = $3 WHERE guild_id = $1 AND LOWER(label) = LOWER($2)
+      "#,
+      guild_id.to_string(),
+      label,
+      reason,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn remove_moderation_reason(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    label: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM moderation_reasons WHERE guild_id = $1 AND LOWER(label) = LOWER($2)
+      "#,
+      guild_id.to_string(),
+      label,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn get_guild_emojis(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<GuildEmoji>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT name, emoji FROM guild_emojis WHERE guild_id = $1 ORDER BY name ASC
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let emojis = rows
+      .into_iter()
+      .map(|row| GuildEmoji {
+        name: row.name,
+        emoji: row.emoji,
+      })
+      .collect();
+
+    Ok(emojis)
+  }
+
+  pub async fn set_guild_emoji(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    name: &str,
+    emoji: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO guild_emojis (record_id, guild_id, name, emoji) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (guild_id, name) DO UPDATE SET emoji = $4
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      name,
+      emoji,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_guild_emoji(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    name: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM guild_emojis WHERE guild_id = $1 AND name = $2
+      "#,
+      guild_id.to_string(),
+      name,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn add_edit_log_channel(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        INSERT INTO edit_log_channels (record_id, guild_id, channel_id) VALUES ($1, $2, $3)
+        ON CONFLICT (channel_id) DO NOTHING
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      channel_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn remove_edit_log_channel(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM edit_log_channels WHERE guild_id = $1 AND channel_id = $2
+      "#,
+      guild_id.to_string(),
+      channel_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn get_edit_log_channels(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<serenity::ChannelId>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT channel_id FROM edit_log_channels WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let channels = rows
+      .into_iter()
+      .map(|row| serenity::ChannelId::new(row.channel_id.parse::<u64>().unwrap()))
+      .collect();
+
+    Ok(channels)
+  }
+
+  pub async fn is_edit_log_channel(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    channel_id: &serenity::ChannelId,
+  ) -> Result<bool> {
+    let row = sqlx::query!(
+      r#"
+        SELECT EXISTS (SELECT 1 FROM edit_log_channels WHERE channel_id = $1)
+      "#,
+      channel_id.to_string(),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.exists.unwrap())
+  }
+
+  pub async fn add_message_edit(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+    message_id: &serenity::MessageId,
+    user_id: &serenity::UserId,
+    old_content: Option<&str>,
+    new_content: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO message_edits (record_id, guild_id, channel_id, message_id, user_id, old_content, new_content) VALUES ($1, $2, $3, $4, $5, $6, $7)
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      channel_id.to_string(),
+      message_id.to_string(),
+      user_id.to_string(),
+      old_content,
+      new_content,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Gets the content of a message as of its most recent logged edit, for when the previous version is no longer cached.
+  pub async fn get_last_message_edit_content(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    message_id: &serenity::MessageId,
+  ) -> Result<Option<String>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT new_content FROM message_edits WHERE message_id = $1 ORDER BY edited_at DESC LIMIT 1
+      "#,
+      message_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| row.new_content))
+  }
+
+  pub async fn prune_message_edits(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    older_than: chrono::DateTime<Utc>,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM message_edits WHERE edited_at < $1
+      "#,
+      older_than,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn get_delete_log_settings(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<DeleteLogSettings> {
+    let row = sqlx::query!(
+      r#"
+        SELECT ignore_bots, min_length FROM delete_log_settings WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    let channels = sqlx::query!(
+      r#"
+        SELECT channel_id FROM delete_log_ignored_channels WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let mut settings = match row {
+      Some(row) => DeleteLogSettings {
+        ignore_bots: row.ignore_bots,
+        min_length: row.min_length,
+        ignored_channels: Vec::new(),
+      },
+      None => DeleteLogSettings::default(),
+    };
+
+    settings.ignored_channels = channels
+      .into_iter()
+      .map(|row| serenity::ChannelId::new(row.channel_id.parse::<u64>().unwrap()))
+      .collect();
+
+    Ok(settings)
+  }
+
+  /// Updates the delete log filters for a guild. Filters that are `None` keep their current value.
+  pub async fn set_delete_log_filters(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    ignore_bots: Option<bool>,
+    min_length: Option<i32>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO delete_log_settings (record_id, guild_id, ignore_bots, min_length) VALUES ($1, $2, COALESCE($3, TRUE), COALESCE($4, 0))
+        ON CONFLICT (guild_id) DO UPDATE SET ignore_bots = COALESCE($3, delete_log_settings.ignore_bots), min_length = COALESCE($4, delete_log_settings.min_length)
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      ignore_bots,
+      min_length,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn add_delete_log_ignored_channel(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        INSERT INTO delete_log_ignored_channels (record_id, guild_id, channel_id) VALUES ($1, $2, $3)
+        ON CONFLICT (channel_id) DO NOTHING
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      channel_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn remove_delete_log_ignored_channel(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM delete_log_ignored_channels WHERE guild_id = $1 AND channel_id = $2
+      "#,
+      guild_id.to_string(),
+      channel_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn add_notification_thread(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    thread_id: &serenity::ChannelId,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO notification_threads (record_id, guild_id, user_id, thread_id) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (thread_id) DO NOTHING
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      user_id.to_string(),
+      thread_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn get_user_notification_threads(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+  ) -> Result<Vec<serenity::ChannelId>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT thread_id FROM notification_threads WHERE guild_id = $1 AND user_id = $2
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let threads = rows
+      .into_iter()
+      .map(|row| serenity::ChannelId::new(row.thread_id.parse::<u64>().unwrap()))
+      .collect();
+
+    Ok(threads)
+  }
+
+  pub async fn get_notification_threads(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  ) -> Result<Vec<NotificationThread>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT user_id, thread_id, created_at FROM notification_threads
+      "#,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let threads = rows
+      .into_iter()
+      .map(|row| NotificationThread {
+        user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+        thread_id: serenity::ChannelId::new(row.thread_id.parse::<u64>().unwrap()),
+        created_at: row.created_at,
+      })
+      .collect();
+
+    Ok(threads)
+  }
+
+  pub async fn remove_notification_thread(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    thread_id: &serenity::ChannelId,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        DELETE FROM notification_threads WHERE thread_id = $1
+      "#,
+      thread_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Creates default settings for a guild. Returns `false` if the guild already has settings.
+  pub async fn add_guild_settings(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<bool> {
+    let result = sqlx::query!(
+      r#"
+        INSERT INTO guild_settings (record_id, guild_id) VALUES ($1, $2)
+        ON CONFLICT (guild_id) DO NOTHING
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+  }
+
+  pub async fn get_guild_settings(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<GuildSettings> {
+    let row = sqlx::query!(
+      r#"
+        SELECT tracking_channel_id, log_channel_id FROM guild_settings WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    let settings = match row {
+      Some(row) => GuildSettings {
+        tracking_channel_id: row
+          .tracking_channel_id
+          .map(|id| serenity::ChannelId::new(id.parse::<u64>().unwrap())),
+        log_channel_id: row
+          .log_channel_id
+          .map(|id| serenity::ChannelId::new(id.parse::<u64>().unwrap())),
+      },
+      None => GuildSettings::default(),
+    };
+
+    Ok(settings)
+  }
+
+  pub async fn set_guild_settings(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    settings: &GuildSettings,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO guild_settings (record_id, guild_id, tracking_channel_id, log_channel_id) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (guild_id) DO UPDATE SET tracking_channel_id = EXCLUDED.tracking_channel_id, log_channel_id = EXCLUDED.log_channel_id
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      settings.tracking_channel_id.map(|id| id.to_string()),
+      settings.log_channel_id.map(|id| id.to_string()),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn get_command_prefix(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Option<String>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT command_prefix FROM guild_settings WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.and_then(|row| row.command_prefix))
+  }
+
+  pub async fn set_command_prefix(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    prefix: Option<&str>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO guild_settings (record_id, guild_id, command_prefix) VALUES ($1, $2, $3)
+        ON CONFLICT (guild_id) DO UPDATE SET command_prefix = EXCLUDED.command_prefix
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      prefix,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Gets the guild's chart palette, or the default palette if none has been chosen.
+  pub async fn get_chart_palette(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<ChartPalette> {
+    let row = sqlx::query!(
+      r#"
+        SELECT chart_palette FROM guild_settings WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(
+      row
+        .and_then(|row| row.chart_palette)
+        .and_then(|key| ChartPalette::from_key(&key))
+        .unwrap_or(ChartPalette::Default),
+    )
+  }
+
+  pub async fn set_chart_palette(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    palette: ChartPalette,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO guild_settings (record_id, guild_id, chart_palette) VALUES ($1, $2, $3)
+        ON CONFLICT (guild_id) DO UPDATE SET chart_palette = EXCLUDED.chart_palette
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      palette.key(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn add_guild_webhook(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    url: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        INSERT INTO guild_webhooks (record_id, guild_id, url) VALUES ($1, $2, $3)
+        ON CONFLICT (guild_id, url) DO NOTHING
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      url,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn remove_guild_webhook(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    webhook_id: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM guild_webhooks WHERE guild_id = $1 AND record_id = $2
+      "#,
+      guild_id.to_string(),
+      webhook_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn get_guild_webhooks(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<GuildWebhook>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT record_id, url, created_at FROM guild_webhooks WHERE guild_id = $1 ORDER BY created_at ASC
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let webhooks = rows
+      .into_iter()
+      .map(|row| GuildWebhook {
+        id: row.record_id,
+        url: row.url,
+        created_at: row.created_at,
+      })
+      .collect();
+
+    Ok(webhooks)
+  }
+
+  /// Gets the longest streak reached in the guild since record tracking began, or 0 if none has been recorded.
+  pub async fn get_record_streak(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<i32> {
+    let row = sqlx::query!(
+      r#"
+        SELECT record_streak FROM guild_settings WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map_or(0, |row| row.record_streak))
+  }
+
+  pub async fn set_record_streak(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    streak: i32,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO guild_settings (record_id, guild_id, record_streak) VALUES ($1, $2, $3)
+        ON CONFLICT (guild_id) DO UPDATE SET record_streak = EXCLUDED.record_streak
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      streak,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Creates a quick-log token for the user, replacing any token they already had.
+  ///
+  /// Only a hash of the token is stored, so links can't be recovered from the database.
+  pub async fn set_quicklog_token(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    token: &str,
+    minutes: i32,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO quicklog_tokens (record_id, token_hash, user_id, guild_id, minutes) VALUES ($1, ENCODE(SHA256(CONVERT_TO($2, 'UTF8')), 'hex'), $3, $4, $5)
+        ON CONFLICT (user_id, guild_id) DO UPDATE SET token_hash = EXCLUDED.token_hash, minutes = EXCLUDED.minutes, created_at = CURRENT_TIMESTAMP, last_used_at = NULL
+      "#,
+      Ulid::new().to_string(),
+      token,
+      user_id.to_string(),
+      guild_id.to_string(),
+      minutes,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Locks the token's row until the transaction ends, so concurrent requests with the same token wait for each other's cooldown.
+  pub async fn get_quicklog_token(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    token: &str,
+  ) -> Result<Option<QuickLogToken>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT user_id, guild_id, minutes, last_used_at FROM quicklog_tokens WHERE token_hash = ENCODE(SHA256(CONVERT_TO($1, 'UTF8')), 'hex')
+        FOR UPDATE
+      "#,
+      token,
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| QuickLogToken {
+      user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+      guild_id: serenity::GuildId::new(row.guild_id.parse::<u64>().unwrap()),
+      minutes: row.minutes,
+      last_used_at: row.last_used_at,
+    }))
+  }
+
+  pub async fn mark_quicklog_token_used(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    token: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE quicklog_tokens SET last_used_at = CURRENT_TIMESTAMP WHERE token_hash = ENCODE(SHA256(CONVERT_TO($1, 'UTF8')), 'hex')
+      "#,
+      token,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_quicklog_token(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM quicklog_tokens WHERE user_id = $1 AND guild_id = $2
+      "#,
+      user_id.to_string(),
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Creates a health sync token for the user, replacing any token they already had.
+  ///
+  /// Only a hash of the token is stored, so tokens can't be recovered from the database.
+  pub async fn set_health_sync_token(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    token: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO health_sync_tokens (record_id, token_hash, user_id, guild_id) VALUES ($1, ENCODE(SHA256(CONVERT_TO($2, 'UTF8')), 'hex'), $3, $4)
+        ON CONFLICT (user_id, guild_id) DO UPDATE SET token_hash = EXCLUDED.token_hash, created_at = CURRENT_TIMESTAMP, last_synced_at = NULL
+      "#,
+      Ulid::new().to_string(),
+      token,
+      user_id.to_string(),
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn get_health_sync_token(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    token: &str,
+  ) -> Result<Option<HealthSyncToken>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT user_id, guild_id, last_synced_at FROM health_sync_tokens WHERE token_hash = ENCODE(SHA256(CONVERT_TO($1, 'UTF8')), 'hex')
+      "#,
+      token,
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| HealthSyncToken {
+      user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+      guild_id: serenity::GuildId::new(row.guild_id.parse::<u64>().unwrap()),
+      last_synced_at: row.last_synced_at,
+    }))
+  }
+
+  pub async fn mark_health_sync_token_used(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    token: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE health_sync_tokens SET last_synced_at = CURRENT_TIMESTAMP WHERE token_hash = ENCODE(SHA256(CONVERT_TO($1, 'UTF8')), 'hex')
+      "#,
+      token,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_health_sync_token(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM health_sync_tokens WHERE user_id = $1 AND guild_id = $2
+      "#,
+      user_id.to_string(),
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Adds synced sessions, leaving out any that overlap an entry the member already has, however it was logged.
+  ///
+  /// Entries are timed at the end of the session, like those logged with `/add`, so a session overlaps an entry timed between its start and `tolerance_minutes` after its end. Returns how many were added.
+  pub async fn add_synced_entries(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    sessions: &[(chrono::DateTime<Utc>, i32)],
+    tolerance_minutes: i32,
+  ) -> Result<u64> {
+    let record_ids = sessions
+      .iter()
+      .map(|_| Ulid::new().to_string())
+      .collect::<Vec<String>>();
+    let occurred_at = sessions
+      .iter()
+      .map(|(occurred_at, _)| *occurred_at)
+      .collect::<Vec<chrono::DateTime<Utc>>>();
+    let minutes = sessions
+      .iter()
+      .map(|(_, minutes)| *minutes)
+      .collect::<Vec<i32>>();
+
+    // Sessions repeated within the same push are only added once
+    let result = sqlx::query!(
+      r#"
+        INSERT INTO meditation (record_id, user_id, meditation_minutes, guild_id, occurred_at, source)
+        SELECT DISTINCT ON (sessions.occurred_at) sessions.record_id, $1, sessions.meditation_minutes, $2, sessions.occurred_at, $7
+        FROM UNNEST($3::text[], $4::integer[], $5::timestamptz[]) AS sessions (record_id, meditation_minutes, occurred_at)
+        WHERE NOT EXISTS (
+          SELECT 1 FROM meditation
+          WHERE guild_id = $2 AND user_id = $1
+            AND occurred_at BETWEEN sessions.occurred_at - make_interval(mins => sessions.meditation_minutes + $6)
+              AND sessions.occurred_at + make_interval(mins => $6)
+        )
+        ORDER BY sessions.occurred_at, sessions.meditation_minutes DESC
+      "#,
+      user_id.to_string(),
+      guild_id.to_string(),
+      &record_ids,
+      &minutes,
+      &occurred_at,
+      tolerance_minutes,
+      EntrySource::Api.key(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn get_session_limits(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<SessionLimits> {
+    let row = sqlx::query!(
+      r#"
+        SELECT min_minutes, max_minutes, confirm_minutes, daily_cap_minutes FROM session_limits WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    let limits = match row {
+      Some(row) => SessionLimits {
+        min_minutes: row.min_minutes,
+        max_minutes: row.max_minutes,
+        confirm_minutes: row.confirm_minutes,
+        daily_cap_minutes: row.daily_cap_minutes,
+      },
+      None => SessionLimits::default(),
+    };
+
+    Ok(limits)
+  }
+
+  pub async fn set_session_limits(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    limits: &SessionLimits,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO session_limits (record_id, guild_id, min_minutes, max_minutes, confirm_minutes, daily_cap_minutes) VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (guild_id) DO UPDATE SET min_minutes = EXCLUDED.min_minutes, max_minutes = EXCLUDED.max_minutes, confirm_minutes = EXCLUDED.confirm_minutes, daily_cap_minutes = EXCLUDED.daily_cap_minutes
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      limits.min_minutes,
+      limits.max_minutes,
+      limits.confirm_minutes,
+      limits.daily_cap_minutes,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Finds users whose time over the past day is at least `ratio` times their daily average over the preceding 30 days.
+  pub async fn get_volume_spikes(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ratio: f64,
+    min_minutes: i64,
+  ) -> Result<Vec<VolumeSpike>> {
+    let rows = sqlx::query!(
+      r#"
+        WITH recent AS (
+          SELECT guild_id, user_id, SUM(meditation_minutes) AS recent_minutes, ARRAY_AGG(record_id) AS entry_ids
+          FROM meditation
+          WHERE occurred_at >= NOW() - INTERVAL '1 day'
+          GROUP BY guild_id, user_id
+        ), baseline AS (
+          SELECT guild_id, user_id, SUM(meditation_minutes)::float8 / 30 AS daily_average
+          FROM meditation
+          WHERE occurred_at >= NOW() - INTERVAL '31 days' AND occurred_at < NOW() - INTERVAL '1 day'
+          GROUP BY guild_id, user_id
+        )
+        SELECT recent.guild_id, recent.user_id, recent.recent_minutes, baseline.daily_average, recent.entry_ids
+        FROM recent
+        INNER JOIN baseline ON recent.guild_id = baseline.guild_id AND recent.user_id = baseline.user_id
+        WHERE recent.recent_minutes >= $1 * baseline.daily_average AND recent.recent_minutes >= $2
+      "#,
+      ratio,
+      min_minutes,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let spikes = rows
+      .into_iter()
+      .map(|row| VolumeSpike {
+        guild_id: serenity::GuildId::new(row.guild_id.parse::<u64>().unwrap()),
+        user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+        recent_minutes: row.recent_minutes.unwrap_or_default(),
+        daily_average: row.daily_average.unwrap_or_default(),
+        entry_ids: row.entry_ids.unwrap_or_default(),
+      })
+      .collect();
+
+    Ok(spikes)
+  }
+
+  /// Finds users who added the same duration at least `min_repeats` times in the past week, with the time between entries varying by less than `max_gap_deviation` seconds.
+  pub async fn get_repeated_durations(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    min_repeats: i64,
+    max_gap_deviation: f64,
+  ) -> Result<Vec<RepeatedDuration>> {
+    let rows = sqlx::query!(
+      r#"
+        WITH entries AS (
+          SELECT guild_id, user_id, record_id, meditation_minutes,
+            EXTRACT(EPOCH FROM occurred_at - LAG(occurred_at) OVER (PARTITION BY guild_id, user_id, meditation_minutes ORDER BY occurred_at))::float8 AS gap
+          FROM meditation
+          WHERE occurred_at >= NOW() - INTERVAL '7 days'
+        )
+        SELECT guild_id, user_id, meditation_minutes, COUNT(*) AS entry_count, AVG(gap) AS average_gap, ARRAY_AGG(record_id) AS entry_ids
+        FROM entries
+        GROUP BY guild_id, user_id, meditation_minutes
+        HAVING COUNT(*) >= $1 AND STDDEV_SAMP(gap) < $2
+      "#,
+      min_repeats,
+      max_gap_deviation,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let repeats = rows
+      .into_iter()
+      .map(|row| RepeatedDuration {
+        guild_id: serenity::GuildId::new(row.guild_id.parse::<u64>().unwrap()),
+        user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+        meditation_minutes: row.meditation_minutes,
+        entry_count: row.entry_count.unwrap_or_default(),
+        average_gap: row.average_gap.unwrap_or_default(),
+        entry_ids: row.entry_ids.unwrap_or_default(),
+      })
+      .collect();
+
+    Ok(repeats)
+  }
+
+  /// Adds an anomaly flag to the review queue, unless the same kind of anomaly was already flagged for the user in the past week. Returns whether the flag was added.
+  pub async fn add_anomaly_flag(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    kind: &str,
+    details: &str,
+    entry_ids: &[String],
+  ) -> Result<bool> {
+    let result = sqlx::query!(
+      r#"
+        INSERT INTO anomaly_flags (record_id, guild_id, user_id, kind, details, entry_ids)
+        SELECT $1, $2, $3, $4, $5, $6
+        WHERE NOT EXISTS (
+          SELECT 1 FROM anomaly_flags WHERE guild_id = $2 AND user_id = $3 AND kind = $4 AND flagged_at > NOW() - INTERVAL '7 days'
+        )
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      user_id.to_string(),
+      kind,
+      details,
+      entry_ids,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+  }
+
+  pub async fn get_pending_anomaly_flags(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<AnomalyFlag>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT record_id, user_id, kind, details, entry_ids, flagged_at FROM anomaly_flags WHERE guild_id = $1 AND status = 'pending' ORDER BY flagged_at ASC
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let flags = rows
+      .into_iter()
+      .map(|row| AnomalyFlag {
+        id: row.record_id,
+        user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+        kind: row.kind,
+        details: row.details,
+        entry_ids: row.entry_ids,
+        flagged_at: row.flagged_at,
+      })
+      .collect();
+
+    Ok(flags)
+  }
+
+  pub async fn review_anomaly_flag(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    flag_id: &str,
+    status: &str,
+    reviewed_by: &serenity::UserId,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE anomaly_flags SET status = $2, reviewed_by = $3 WHERE record_id = $1
+      "#,
+      flag_id,
+      status,
+      reviewed_by.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn delete_meditation_entries(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    meditation_ids: &[String],
+    deleted_by: &serenity::UserId,
+    reason: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        WITH removed AS (
+          DELETE FROM meditation WHERE record_id = ANY($1) AND guild_id = $2 AND user_id = $3
+          RETURNING record_id, user_id, guild_id, occurred_at, meditation_minutes, source
+        )
+        INSERT INTO meditation_trash (record_id, user_id, guild_id, occurred_at, meditation_minutes, source, batch_id, reason, deleted_by)
+        SELECT record_id, user_id, guild_id, occurred_at, meditation_minutes, source, $4, $5, $6 FROM removed
+      "#,
+      meditation_ids,
+      guild_id.to_string(),
+      user_id.to_string(),
+      Ulid::new().to_string(),
+      reason,
+      deleted_by.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn add_prize_award(
+    connection: &mut sqlx::pool::PoolConnection<sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    key: &str,
+    challenge: &str,
+    dm_channel_id: &serenity::ChannelId,
+    dm_message_id: &serenity::MessageId,
+  ) -> Result<String> {
+    let record_id = Ulid::new().to_string();
+
+    sqlx::query!(
+      r#"
+        INSERT INTO prize_awards (record_id, guild_id, user_id, steam_key, challenge, dm_channel_id, dm_message_id) VALUES ($1, $2, $3, $4, $5, $6, $7)
+      "#,
+      record_id,
+      guild_id.to_string(),
+      user_id.to_string(),
+      key,
+      challenge,
+      dm_channel_id.to_string(),
+      dm_message_id.to_string(),
+    )
+    .execute(&mut **connection)
+    .await?;
+
+    Ok(record_id)
+  }
+
+  /// Resolves a prize award that is still awaiting a response. Returns false if the award was already resolved.
+  pub async fn resolve_prize_award(
+    connection: &mut sqlx::pool::PoolConnection<sqlx::Postgres>,
+    award_id: &str,
+    status: &str,
+  ) -> Result<bool> {
+    let result = sqlx::query!(
+      r#"
+        UPDATE prize_awards SET status = $2, resolved_at = NOW() WHERE record_id = $1 AND status = 'sent'
+      "#,
+      award_id,
+      status,
+    )
+    .execute(&mut **connection)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+  }
+
+  pub async fn mark_prize_award_reminded(
+    connection: &mut sqlx::pool::PoolConnection<sqlx::Postgres>,
+    award_id: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE prize_awards SET reminded_at = NOW() WHERE record_id = $1
+      "#,
+      award_id,
+    )
+    .execute(&mut **connection)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Gets prize awards still awaiting a response that were sent before `awarded_before`. When `unreminded_only` is true, awards that have already had a reminder sent are skipped.
+  pub async fn get_pending_prize_awards(
+    connection: &mut sqlx::pool::PoolConnection<sqlx::Postgres>,
+    awarded_before: chrono::DateTime<Utc>,
+    unreminded_only: bool,
+  ) -> Result<Vec<PrizeAward>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT record_id, user_id, steam_key, challenge, status, dm_channel_id, dm_message_id, awarded_at FROM prize_awards
+        WHERE status = 'sent' AND awarded_at < $1 AND (NOT $2 OR reminded_at IS NULL)
+      "#,
+      awarded_before,
+      unreminded_only,
+    )
+    .fetch_all(&mut **connection)
+    .await?;
+
+    let awards = rows
+      .into_iter()
+      .map(|row| PrizeAward {
+        id: row.record_id,
+        user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+        steam_key: row.steam_key,
+        challenge: row.challenge,
+        status: row.status,
+        dm_channel_id: row
+          .dm_channel_id
+          .map(|id| serenity::ChannelId::new(id.parse::<u64>().unwrap())),
+        dm_message_id: row
+          .dm_message_id
+          .map(|id| serenity::MessageId::new(id.parse::<u64>().unwrap())),
+        awarded_at: row.awarded_at,
+      })
+      .collect();
+
+    Ok(awards)
+  }
+
+  pub async fn get_prize_awards(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<PrizeAward>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT record_id, user_id, steam_key, challenge, status, dm_channel_id, dm_message_id, awarded_at FROM prize_awards
+        WHERE guild_id = $1 ORDER BY awarded_at DESC
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let awards = rows
+      .into_iter()
+      .map(|row| PrizeAward {
+        id: row.record_id,
+        user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+        steam_key: row.steam_key,
+        challenge: row.challenge,
+        status: row.status,
+        dm_channel_id: row
+          .dm_channel_id
+          .map(|id| serenity::ChannelId::new(id.parse::<u64>().unwrap())),
+        dm_message_id: row
+          .dm_message_id
+          .map(|id| serenity::MessageId::new(id.parse::<u64>().unwrap())),
+        awarded_at: row.awarded_at,
+      })
+      .collect();
+
+    Ok(awards)
+  }
+
+  pub async fn get_retention_policies(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<RetentionPolicy>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT guild_id, category, retention_days, enabled FROM retention_policies WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let policies = rows
+      .into_iter()
+      .filter_map(|row| {
+        Some(RetentionPolicy {
+          guild_id: serenity::GuildId::new(row.guild_id.parse::<u64>().unwrap()),
+          category: RetentionCategory::from_key(&row.category)?,
+          retention_days: row.retention_days,
+          enabled: row.enabled,
+        })
+      })
+      .collect();
+
+    Ok(policies)
+  }
+
+  pub async fn get_enabled_retention_policies(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  ) -> Result<Vec<RetentionPolicy>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT guild_id, category, retention_days, enabled FROM retention_policies WHERE enabled = TRUE
+      "#,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let policies = rows
+      .into_iter()
+      .filter_map(|row| {
+        Some(RetentionPolicy {
+          guild_id: serenity::GuildId::new(row.guild_id.parse::<u64>().unwrap()),
+          category: RetentionCategory::from_key(&row.category)?,
+          retention_days: row.retention_days,
+          enabled: row.enabled,
+        })
+      })
+      .collect();
+
+    Ok(policies)
+  }
+
+  /// Sets the retention period for a category. Policies start disabled, so counts can be reviewed before anything is purged.
+  pub async fn set_retention_policy(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    category: RetentionCategory,
+    retention_days: i32,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO retention_policies (record_id, guild_id, category, retention_days) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (guild_id, category) DO UPDATE SET retention_days = EXCLUDED.retention_days, enabled = FALSE
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      category.key(),
+      retention_days,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn set_retention_policy_enabled(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    category: RetentionCategory,
+    enabled: bool,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        UPDATE retention_policies SET enabled = $3 WHERE guild_id = $1 AND category = $2
+      "#,
+      guild_id.to_string(),
+      category.key(),
+      enabled,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Counts the records a retention policy would purge, for dry runs.
+  pub async fn count_expired_records(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    category: RetentionCategory,
+    cutoff: chrono::DateTime<Utc>,
+  ) -> Result<u64> {
+    let count = match category {
+      RetentionCategory::Erases => {
+        sqlx::query!(
+          r#"
+            SELECT COUNT(record_id) AS "count!" FROM erases WHERE guild_id = $1 AND occurred_at < $2
+          "#,
+          guild_id.to_string(),
+          cutoff,
+        )
+        .fetch_one(&mut **transaction)
+        .await?
+        .count
+      }
+      RetentionCategory::MessageEdits => {
+        sqlx::query!(
+          r#"
+            SELECT COUNT(record_id) AS "count!" FROM message_edits WHERE guild_id = $1 AND edited_at < $2
+          "#,
+          guild_id.to_string(),
+          cutoff,
+        )
+        .fetch_one(&mut **transaction)
+        .await?
+        .count
+      }
+      RetentionCategory::CommandUsage => {
+        sqlx::query!(
+          r#"
+            SELECT COUNT(record_id) AS "count!" FROM command_usage WHERE guild_id = $1 AND occurred_at < $2
+          "#,
+          guild_id.to_string(),
+          cutoff,
+        )
+        .fetch_one(&mut **transaction)
+        .await?
+        .count
+      }
+      RetentionCategory::AnomalyFlags => {
+        sqlx::query!(
+          r#"
+            SELECT COUNT(record_id) AS "count!" FROM anomaly_flags WHERE guild_id = $1 AND status != 'pending' AND flagged_at < $2
+          "#,
+          guild_id.to_string(),
+          cutoff,
+        )
+        .fetch_one(&mut **transaction)
+        .await?
+        .count
+      }
+    };
+
+    Ok(count.try_into().unwrap())
+  }
+
+  /// Deletes the records a retention policy has expired. Anomaly flags still awaiting review are never purged.
+  pub async fn purge_expired_records(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    category: RetentionCategory,
+    cutoff: chrono::DateTime<Utc>,
+  ) -> Result<u64> {
+    let result = match category {
+      RetentionCategory::Erases => {
+        sqlx::query!(
+          r#"
+            DELETE FROM erases WHERE guild_id = $1 AND occurred_at < $2
+          "#,
+          guild_id.to_string(),
+          cutoff,
+        )
+        .execute(&mut **transaction)
+        .await?
+      }
+      RetentionCategory::MessageEdits => {
+        sqlx::query!(
+          r#"
+            DELETE FROM message_edits WHERE guild_id = $1 AND edited_at < $2
+          "#,
+          guild_id.to_string(),
+          cutoff,
+        )
+        .execute(&mut **transaction)
+        .await?
+      }
+      RetentionCategory::CommandUsage => {
+        sqlx::query!(
+          r#"
+            DELETE FROM command_usage WHERE guild_id = $1 AND occurred_at < $2
+          "#,
+          guild_id.to_string(),
+          cutoff,
+        )
+        .execute(&mut **transaction)
+        .await?
+      }
+      RetentionCategory::AnomalyFlags => {
+        sqlx::query!(
+          r#"
+            DELETE FROM anomaly_flags WHERE guild_id = $1 AND status != 'pending' AND flagged_at < $2
+          "#,
+          guild_id.to_string(),
+          cutoff,
+        )
+        .execute(&mut **transaction)
+        .await?
+      }
+    };
+
+    Ok(result.rows_affected())
+  }
+
+  /// Lists trash batches, newest first, optionally only those for one user.
+  pub async fn get_trash_batches(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: Option<&serenity::UserId>,
+  ) -> Result<Vec<TrashBatch>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT batch_id, user_id, reason, deleted_by, MAX(deleted_at) AS "deleted_at!",
+          COUNT(record_id) AS "entries!", COALESCE(SUM(meditation_minutes), 0) AS "minutes!"
+        FROM meditation_trash
+        WHERE guild_id = $1 AND ($2::text IS NULL OR user_id = $2)
+        GROUP BY batch_id, user_id, reason, deleted_by
+        ORDER BY MAX(deleted_at) DESC
+        LIMIT 25
+      "#,
+      guild_id.to_string(),
+      user_id.map(ToString::to_string),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let batches = rows
+      .into_iter()
+      .map(|row| TrashBatch {
+        batch_id: row.batch_id,
+        user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+        reason: row.reason,
+        deleted_by: serenity::UserId::new(row.deleted_by.parse::<u64>().unwrap()),
+        deleted_at: row.deleted_at,
+        entries: row.entries,
+        minutes: row.minutes,
+      })
+      .collect();
+
+    Ok(batches)
+  }
+
+  /// Moves a trash batch back into the meditation table and returns how many entries were restored.
+  ///
+  /// Entries whose ID is already in the meditation table can't be restored, so they are left in the trash rather than lost.
+  pub async fn restore_trash_batch(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    batch_id: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        WITH restored AS (
+          INSERT INTO meditation (record_id, user_id, guild_id, occurred_at, meditation_minutes, source)
+          SELECT record_id, user_id, guild_id, occurred_at, meditation_minutes, source
+          FROM meditation_trash WHERE guild_id = $1 AND batch_id = $2
+          ON CONFLICT (record_id) DO NOTHING
+          RETURNING record_id
+        )
+        DELETE FROM meditation_trash
+        WHERE guild_id = $1 AND batch_id = $2 AND record_id IN (SELECT record_id FROM restored)
+      "#,
+      guild_id.to_string(),
+      batch_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Permanently deletes a trash batch and returns how many entries were purged.
+  pub async fn purge_trash_batch(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    batch_id: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM meditation_trash WHERE guild_id = $1 AND batch_id = $2
+      "#,
+      guild_id.to_string(),
+      batch_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn purge_expired_trash(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    cutoff: chrono::DateTime<Utc>,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM meditation_trash WHERE deleted_at < $1
+      "#,
+      cutoff,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn add_prize_draw(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    challenge: &str,
+    challenge_start: chrono::DateTime<Utc>,
+    minutes: i64,
+    key: &str,
+  ) -> Result<String> {
+    let record_id = Ulid::new().to_string();
+
+    sqlx::query!(
+      r#"
+        INSERT INTO prize_draws (record_id, guild_id, user_id, challenge, challenge_start, minutes, steam_key) VALUES ($1, $2, $3, $4, $5, $6, $7)
+      "#,
+      record_id,
+      guild_id.to_string(),
+      user_id.to_string(),
+      challenge,
+      challenge_start,
+      minutes,
+      key,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(record_id)
+  }
+
+  /// Gets the most recent draw that was interrupted before the winner was sent their offer.
+  pub async fn get_interrupted_prize_draw(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Option<PrizeDraw>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT record_id, user_id, challenge_start, minutes, steam_key, state FROM prize_draws
+        WHERE guild_id = $1 AND state IN ('key_reserved', 'announced')
+        ORDER BY created_at DESC LIMIT 1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| PrizeDraw {
+      id: row.record_id,
+      user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+      challenge_start: row.challenge_start,
+      minutes: row.minutes,
+      steam_key: row.steam_key,
+      state: row.state,
+    }))
+  }
+
+  pub async fn update_prize_draw(
+    connection: &mut sqlx::pool::PoolConnection<sqlx::Postgres>,
+    draw_id: &str,
+    state: &str,
+    award_id: Option<&str>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE prize_draws SET state = $2, award_id = COALESCE($3, award_id), updated_at = NOW() WHERE record_id = $1
+      "#,
+      draw_id,
+      state,
+      award_id,
+    )
+    .execute(&mut **connection)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Starts a self-timed session. Returns false if the user already has a session in progress.
+  pub async fn start_open_session(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+  ) -> Result<bool> {
+    let result = sqlx::query!(
+      r#"
+        INSERT INTO open_sessions (record_id, guild_id, user_id) VALUES ($1, $2, $3)
+        ON CONFLICT (guild_id, user_id) DO NOTHING
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      user_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+  }
+
+  /// Ends a self-timed session, returning when it was started.
+  pub async fn end_open_session(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+  ) -> Result<Option<chrono::DateTime<Utc>>> {
+    let row = sqlx::query!(
+      r#"
+        DELETE FROM open_sessions WHERE guild_id = $1 AND user_id = $2 RETURNING started_at
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| row.started_at))
+  }
+
+  pub async fn get_quiet_hours(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: &serenity::UserId,
+  ) -> Result<Option<QuietHours>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT start_minute, end_minute, utc_offset FROM quiet_hours WHERE user_id = $1
+      "#,
+      user_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| QuietHours {
+      start_minute: row.start_minute,
+      end_minute: row.end_minute,
+      utc_offset: row.utc_offset,
+    }))
+  }
+
+  pub async fn set_quiet_hours(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: &serenity::UserId,
+    quiet_hours: &QuietHours,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO quiet_hours (user_id, start_minute, end_minute, utc_offset) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (user_id) DO UPDATE SET start_minute = $2, end_minute = $3, utc_offset = $4
+      "#,
+      user_id.to_string(),
+      quiet_hours.start_minute,
+      quiet_hours.end_minute,
+      quiet_hours.utc_offset,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Keeps quiet hours in step with the user's UTC offset, when they have quiet hours set.
+  pub async fn update_quiet_hours_offset(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: &serenity::UserId,
+    utc_offset: i16,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE quiet_hours SET utc_offset = $1 WHERE user_id = $2
+      "#,
+      utc_offset,
+      user_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_quiet_hours(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: &serenity::UserId,
+  ) -> Result<bool> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM quiet_hours WHERE user_id = $1
+      "#,
+      user_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+  }
+
+  pub async fn queue_notification(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    user_id: &serenity::UserId,
+    title: &str,
+    description: &str,
+    footer: Option<&str>,
+    fields: &[(String, String)],
+    deliver_after: chrono::DateTime<Utc>,
+  ) -> Result<()> {
+    let (field_names, field_values): (Vec<String>, Vec<String>) = fields.iter().cloned().unzip();
+
+    sqlx::query!(
+      r#"
+        INSERT INTO queued_notifications (record_id, user_id, title, description, footer, field_names, field_values, deliver_after) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+      "#,
+      Ulid::new().to_string(),
+      user_id.to_string(),
+      title,
+      description,
+      footer,
+      &field_names,
+      &field_values,
+      deliver_after,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn get_due_notifications(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  ) -> Result<Vec<QueuedNotification>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT record_id, user_id, title, description, footer, field_names, field_values FROM queued_notifications
+        WHERE deliver_after <= NOW()
+        ORDER BY created_at ASC
+      "#,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| QueuedNotification {
+          id: row.record_id,
+          user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+          title: row.title,
+          description: row.description,
+          footer: row.footer,
+          fields: row.field_names.into_iter().zip(row.field_values).collect(),
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn remove_queued_notification(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    record_id: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        DELETE FROM queued_notifications WHERE record_id = $1
+      "#,
+      record_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Tracks a voice channel, or updates the participants it needs if it is already tracked.
+  pub async fn add_tracking_voice_channel(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+    min_participants: i16,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO tracking_voice_channels (record_id, guild_id, channel_id, min_participants) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (channel_id) DO UPDATE SET min_participants = $4
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      channel_id.to_string(),
+      min_participants,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_tracking_voice_channel(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM tracking_voice_channels WHERE guild_id = $1 AND channel_id = $2
+      "#,
+      guild_id.to_string(),
+      channel_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn get_tracking_voice_channels(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<TrackedVoiceChannel>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT channel_id, min_participants FROM tracking_voice_channels WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| TrackedVoiceChannel {
+          channel_id: serenity::ChannelId::new(row.channel_id.parse::<u64>().unwrap()),
+          min_participants: row.min_participants,
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn save_voice_session(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    channel_id: &serenity::ChannelId,
+    joined_at: chrono::DateTime<Utc>,
+    counted_seconds: i32,
+    counting_since: Option<chrono::DateTime<Utc>>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO voice_sessions (guild_id, user_id, channel_id, joined_at, counted_seconds, counting_since) VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (guild_id, user_id) DO UPDATE SET channel_id = $3, joined_at = $4, counted_seconds = $5, counting_since = $6, last_seen_at = NOW()
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+      channel_id.to_string(),
+      joined_at,
+      counted_seconds,
+      counting_since,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_voice_session(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        DELETE FROM voice_sessions WHERE guild_id = $1 AND user_id = $2
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn get_voice_sessions(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<SavedVoiceSession>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT user_id, channel_id, joined_at, counted_seconds, counting_since, last_seen_at FROM voice_sessions WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| SavedVoiceSession {
+          user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+          channel_id: serenity::ChannelId::new(row.channel_id.parse::<u64>().unwrap()),
+          joined_at: row.joined_at,
+          counted_seconds: row.counted_seconds,
+          counting_since: row.counting_since,
+          last_seen_at: row.last_seen_at,
+        })
+        .collect(),
+    )
+  }
+
+  /// Marks all saved voice sessions as still in progress.
+  pub async fn touch_voice_sessions(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE voice_sessions SET last_seen_at = NOW()
+      "#,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn set_sit_bells(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    event_id: &serenity::ScheduledEventId,
+    interval_minutes: Option<i32>,
+    duration_minutes: Option<i32>,
+    sound: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO sit_bells (event_id, guild_id, interval_minutes, duration_minutes, sound) VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (event_id) DO UPDATE SET interval_minutes = $3, duration_minutes = $4, sound = $5
+      "#,
+      event_id.to_string(),
+      guild_id.to_string(),
+      interval_minutes,
+      duration_minutes,
+      sound,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_sit_bells(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    event_id: &serenity::ScheduledEventId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM sit_bells WHERE guild_id = $1 AND event_id = $2
+      "#,
+      guild_id.to_string(),
+      event_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn get_sit_bells(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<SitBells>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT event_id, interval_minutes, duration_minutes, sound FROM sit_bells WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| SitBells {
+          event_id: serenity::ScheduledEventId::new(row.event_id.parse::<u64>().unwrap()),
+          interval_minutes: row.interval_minutes,
+          duration_minutes: row.duration_minutes,
+          sound: row.sound,
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn get_event_sit_bells(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    event_id: &serenity::ScheduledEventId,
+  ) -> Result<Option<SitBells>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT event_id, interval_minutes, duration_minutes, sound FROM sit_bells WHERE event_id = $1
+      "#,
+      event_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| SitBells {
+      event_id: serenity::ScheduledEventId::new(row.event_id.parse::<u64>().unwrap()),
+      interval_minutes: row.interval_minutes,
+      duration_minutes: row.duration_minutes,
+      sound: row.sound,
+    }))
+  }
+
+  pub async fn set_course_reminder(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    course_name: &str,
+    channel_id: &serenity::ChannelId,
+    weekday: i16,
+    send_minute: i16,
+    message: &str,
+    ends_on: chrono::NaiveDate,
+  ) -> Result<()> {
+    // Marked as sent now, so a reminder due earlier today isn't sent as soon as it's set
+    sqlx::query!(
+      r#"
+        INSERT INTO course_reminders (record_id, guild_id, course_name, channel_id, weekday, send_minute, message, ends_on, last_sent_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, NOW())
+        ON CONFLICT (course_name) DO UPDATE SET channel_id = $4, weekday = $5, send_minute = $6, message = $7, ends_on = $8, last_sent_at = NOW()
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      course_name,
+      channel_id.to_string(),
+      weekday,
+      send_minute,
+      message,
+      ends_on,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_course_reminder(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    course_name: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM course_reminders WHERE LOWER(course_name) = LOWER($1) AND guild_id = $2
+      "#,
+      course_name,
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Removes reminders for courses that have ended.
+  pub async fn remove_ended_course_reminders(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM course_reminders WHERE ends_on < (NOW() AT TIME ZONE 'UTC')::date
+      "#,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Gets course reminders for a guild, or for all guilds if none is given.
+  pub async fn get_course_reminders(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: Option<&serenity::GuildId>,
+  ) -> Result<Vec<CourseReminder>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT course_reminders.record_id, course_reminders.guild_id, course_reminders.course_name, course.participant_role, course_reminders.channel_id, course_reminders.weekday, course_reminders.send_minute, course_reminders.message, course_reminders.ends_on, course_reminders.last_sent_at
+        FROM course_reminders
+        INNER JOIN course ON course.course_name = course_reminders.course_name
+        WHERE $1::text IS NULL OR course_reminders.guild_id = $1
+        ORDER BY course_reminders.course_name ASC
+      "#,
+      guild_id.map(ToString::to_string),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| CourseReminder {
+          id: row.record_id,
+          guild_id: serenity::GuildId::new(row.guild_id.parse::<u64>().unwrap()),
+          course_name: row.course_name,
+          participant_role: serenity::RoleId::new(row.participant_role.parse::<u64>().unwrap()),
+          channel_id: serenity::ChannelId::new(row.channel_id.parse::<u64>().unwrap()),
+          weekday: row.weekday,
+          send_minute: row.send_minute,
+          message: row.message,
+          ends_on: row.ends_on,
+          last_sent_at: row.last_sent_at,
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn mark_course_reminder_sent(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    reminder_id: &str,
+    sent_at: chrono::DateTime<Utc>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE course_reminders SET last_sent_at = $2 WHERE record_id = $1
+      "#,
+      reminder_id,
+      sent_at,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn add_announcement(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+    created_by: &serenity::UserId,
+    title: Option<&str>,
+    content: &str,
+    as_embed: bool,
+    repeat_schedule: Option<&str>,
+    next_run_at: chrono::DateTime<Utc>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO announcements (record_id, guild_id, channel_id, created_by, title, content, as_embed, repeat_schedule, next_run_at) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      channel_id.to_string(),
+      created_by.to_string(),
+      title,
+      content,
+      as_embed,
+      repeat_schedule,
+      next_run_at,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_announcement(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    announcement_id: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM announcements WHERE guild_id = $1 AND record_id = $2
+      "#,
+      guild_id.to_string(),
+      announcement_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Gets scheduled announcements, ordered by when they are next posted. Gets announcements for all guilds when `guild_id` is `None`, or only those due by `due_by` when set.
+  pub async fn get_announcements(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: Option<&serenity::GuildId>,
+    due_by: Option<chrono::DateTime<Utc>>,
+  ) -> Result<Vec<Announcement>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT record_id, guild_id, channel_id, created_by, title, content, as_embed, repeat_schedule, next_run_at
+        FROM announcements
+        WHERE ($1::TEXT IS NULL OR guild_id = $1) AND ($2::TIMESTAMPTZ IS NULL OR next_run_at <= $2)
+        ORDER BY next_run_at
+      "#,
+      guild_id.map(ToString::to_string),
+      due_by,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| Announcement {
+          id: row.record_id,
+          guild_id: serenity::GuildId::new(row.guild_id.parse::<u64>().unwrap()),
+          channel_id: serenity::ChannelId::new(row.channel_id.parse::<u64>().unwrap()),
+          created_by: serenity::UserId::new(row.created_by.parse::<u64>().unwrap()),
+          title: row.title,
+          content: row.content,
+          as_embed: row.as_embed,
+          repeat_schedule: row.repeat_schedule,
+          next_run_at: row.next_run_at,
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn reschedule_announcement(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    announcement_id: &str,
+    next_run_at: chrono::DateTime<Utc>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE announcements SET next_run_at = $2 WHERE record_id = $1
+      "#,
+      announcement_id,
+      next_run_at,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn add_message_template(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    name: &str,
+    title: Option<&str>,
+    content: &str,
+    as_embed: bool,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        INSERT INTO message_templates (record_id, guild_id, name, title, content, as_embed) VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (guild_id, name) DO NOTHING
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      name,
+      title,
+      content,
+      as_embed,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Updates a message template. When `as_embed` is `None`, the template keeps its current format.
+  pub async fn update_message_template(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    name: &str,
+    title: Option<&str>,
+    content: &str,
+    as_embed: Option<bool>,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        UPDATE message_templates SET title = $3, content = $4, as_embed = COALESCE($5, as_embed) WHERE guild_id = $1 AND name = $2
+      "#,
+      guild_id.to_string(),
+      name,
+      title,
+      content,
+      as_embed,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn remove_message_template(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    name: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM message_templates WHERE guild_id = $1 AND name = $2
+      "#,
+      guild_id.to_string(),
+      name,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn get_message_templates(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<MessageTemplate>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT name, title, content, as_embed FROM message_templates WHERE guild_id = $1 ORDER BY name ASC
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| MessageTemplate {
+          name: row.name,
+          title: row.title,
+          content: row.content,
+          as_embed: row.as_embed,
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn get_message_template(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    name: &str,
+  ) -> Result<Option<MessageTemplate>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT name, title, content, as_embed FROM message_templates WHERE guild_id = $1 AND name = $2
+      "#,
+      guild_id.to_string(),
+      name,
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| MessageTemplate {
+      name: row.name,
+      title: row.title,
+      content: row.content,
+      as_embed: row.as_embed,
+    }))
+  }
+
+  pub async fn set_auto_thread_channel(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+    name_pattern: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO auto_thread_channels (record_id, guild_id, channel_id, name_pattern) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (channel_id) DO UPDATE SET name_pattern = $4
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      channel_id.to_string(),
+      name_pattern,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_auto_thread_channel(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM auto_thread_channels WHERE guild_id = $1 AND channel_id = $2
+      "#,
+      guild_id.to_string(),
+      channel_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn get_auto_thread_channels(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<AutoThreadChannel>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT channel_id, name_pattern FROM auto_thread_channels WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| AutoThreadChannel {
+          channel_id: serenity::ChannelId::new(row.channel_id.parse::<u64>().unwrap()),
+          name_pattern: row.name_pattern,
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn set_checkin_prompt(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+    weekday: i16,
+    send_minute: i16,
+    message: &str,
+  ) -> Result<()> {
+    // Marked as sent now, so a prompt due earlier today isn't posted as soon as it's set
+    sqlx::query!(
+      r#"
+        INSERT INTO checkin_prompts (record_id, guild_id, channel_id, weekday, send_minute, message, last_sent_at) VALUES ($1, $2, $3, $4, $5, $6, NOW())
+        ON CONFLICT (guild_id) DO UPDATE SET channel_id = $3, weekday = $4, send_minute = $5, message = $6, last_sent_at = NOW()
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      channel_id.to_string(),
+      weekday,
+      send_minute,
+      message,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_checkin_prompt(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM checkin_prompts WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Gets the check-in prompt for a guild, or for all guilds if none is given.
+  pub async fn get_checkin_prompts(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: Option<&serenity::GuildId>,
+  ) -> Result<Vec<CheckInPrompt>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT record_id, guild_id, channel_id, weekday, send_minute, message, last_sent_at
+        FROM checkin_prompts
+        WHERE $1::text IS NULL OR guild_id = $1
+      "#,
+      guild_id.map(ToString::to_string),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| CheckInPrompt {
+          id: row.record_id,
+          guild_id: serenity::GuildId::new(row.guild_id.parse::<u64>().unwrap()),
+          channel_id: serenity::ChannelId::new(row.channel_id.parse::<u64>().unwrap()),
+          weekday: row.weekday,
+          send_minute: row.send_minute,
+          message: row.message,
+          last_sent_at: row.last_sent_at,
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn mark_checkin_prompt_sent(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    prompt_id: &str,
+    sent_at: chrono::DateTime<Utc>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE checkin_prompts SET last_sent_at = $2 WHERE record_id = $1
+      "#,
+      prompt_id,
+      sent_at,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Whether the user has checked in since the start of the current week, which begins on Monday.
+  pub async fn has_checked_in_this_week(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+  ) -> Result<bool> {
+    let row = sqlx::query!(
+      r#"
+        SELECT EXISTS(
+          SELECT 1 FROM activity_log
+          WHERE guild_id = $1 AND user_id = $2 AND activity = $3 AND occurred_at >= DATE_TRUNC('week', NOW())
+        ) AS "exists!"
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+      Activity::CheckIn.key(),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.exists)
+  }
+
+  /// Records that a member joined a challenge, and whether they get progress reminders. Joining again only changes reminders if given.
+  pub async fn join_challenge(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    challenge: &str,
+    reminders: Option<bool>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO challenge_participants (record_id, guild_id, user_id, challenge, reminders) VALUES ($1, $2, $3, $4, COALESCE($5, FALSE))
+        ON CONFLICT (guild_id, user_id, challenge) DO UPDATE SET reminders = COALESCE($5, challenge_participants.reminders)
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      user_id.to_string(),
+      challenge,
+      reminders,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn leave_challenge(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    challenge: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM challenge_participants WHERE guild_id = $1 AND user_id = $2 AND challenge = $3
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+      challenge,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Gets participants in all guilds who opted in to reminders for a challenge and haven't been reminded since `reminded_before`.
+  pub async fn get_challenge_reminders_due(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    challenge: &str,
+    reminded_before: chrono::DateTime<Utc>,
+  ) -> Result<Vec<ChallengeParticipant>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT guild_id, user_id FROM challenge_participants
+        WHERE challenge = $1 AND reminders AND (last_reminded_at IS NULL OR last_reminded_at < $2)
+      "#,
+      challenge,
+      reminded_before,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| ChallengeParticipant {
+          guild_id: serenity::GuildId::new(row.guild_id.parse::<u64>().unwrap()),
+          user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn mark_challenge_reminded(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    challenge: &str,
+    reminded_at: chrono::DateTime<Utc>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE challenge_participants SET last_reminded_at = $4 WHERE guild_id = $1 AND user_id = $2 AND challenge = $3
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+      challenge,
+      reminded_at,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Gets the minutes and sessions of a challenge's participants during the challenge period, for all participants or only the given user. Minutes are counted up to `daily_cap` per day, as for the challenge draw.
+  pub async fn get_challenge_progress(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: Option<&serenity::UserId>,
+    challenge: &str,
+    start_date: chrono::DateTime<Utc>,
+    end_date: chrono::DateTime<Utc>,
+    daily_cap: Option<i32>,
+  ) -> Result<Vec<ChallengeProgress>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT challenge_participants.user_id, COALESCE(SUM(daily.minutes), 0)::BIGINT AS "minutes!", COALESCE(SUM(daily.sessions), 0)::BIGINT AS "sessions!",
+          COALESCE(BOOL_OR(tracking_profile.stats_private), FALSE) AS "stats_private!"
+        FROM challenge_participants
+        LEFT JOIN tracking_profile ON tracking_profile.user_id = challenge_participants.user_id AND tracking_profile.guild_id = challenge_participants.guild_id
+        LEFT JOIN (
+          SELECT user_id, LEAST(SUM(meditation_minutes), COALESCE($6::INTEGER, 2147483647)) AS minutes, COUNT(*) AS sessions
+          FROM meditation WHERE guild_id = $1 AND occurred_at >= $4 AND occurred_at <= $5
+          GROUP BY user_id, DATE(occurred_at)
+        ) AS daily ON daily.user_id = challenge_participants.user_id
+        WHERE challenge_participants.guild_id = $1 AND challenge_participants.challenge = $3
+        AND ($2::TEXT IS NULL OR challenge_participants.user_id = $2)
+        GROUP BY challenge_participants.user_id
+      "#,
+      guild_id.to_string(),
+      user_id.map(ToString::to_string),
+      challenge,
+      start_date,
+      end_date,
+      daily_cap,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| ChallengeProgress {
+          user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+          minutes: row.minutes,
+          sessions: row.sessions,
+          stats_private: row.stats_private,
+        })
+        .collect(),
+    )
+  }
+
+  /// Records an anonymous mood score for the current week, replacing the member's earlier score for the week.
+  ///
+  /// Only a hash of the member, the week, and `secret` is stored, so scores can't be linked to the member or to their scores from other weeks. Returns whether the member had already responded this week.
+  pub async fn set_mood_checkin(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    secret: &str,
+    score: i16,
+  ) -> Result<bool> {
+    // xmax is only set on the returned row when an existing score was replaced
+    let row = sqlx::query!(
+      r#"
+        WITH week AS (SELECT DATE_TRUNC('week', NOW())::date AS week_start)
+        INSERT INTO mood_checkins (guild_id, user_hash, week_start, score)
+        SELECT $1, ENCODE(SHA256(CONVERT_TO($3 || ':' || $1 || ':' || $2 || ':' || week.week_start::TEXT, 'UTF8')), 'hex'), week.week_start, $4
+        FROM week
+        ON CONFLICT (guild_id, week_start, user_hash) DO UPDATE SET score = $4
+        RETURNING NOT (xmax = 0) AS "replaced!"
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+      secret,
+      score,
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.replaced)
+  }
+
+  /// Gets mood check-ins and meditation minutes for each of the past `weeks` weeks, including the current one, oldest first.
+  pub async fn get_mood_trend(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    weeks: i32,
+  ) -> Result<Vec<MoodWeek>> {
+    let rows = sqlx::query!(
+      r#"
+        WITH weeks AS (
+          SELECT GENERATE_SERIES(DATE_TRUNC('week', NOW()) - ($2::INTEGER - 1) * INTERVAL '1 week', DATE_TRUNC('week', NOW()), INTERVAL '1 week')::date AS week_start
+        )
+        SELECT weeks.week_start AS "week_start!",
+          (SELECT COUNT(*) FROM mood_checkins WHERE guild_id = $1 AND mood_checkins.week_start = weeks.week_start) AS "responses!",
+          (SELECT AVG(score)::FLOAT8 FROM mood_checkins WHERE guild_id = $1 AND mood_checkins.week_start = weeks.week_start) AS average_score,
+          (SELECT COALESCE(SUM(meditation_minutes), 0)::BIGINT FROM meditation WHERE guild_id = $1 AND occurred_at >= weeks.week_start AND occurred_at < weeks.week_start + 7) AS "minutes!"
+        FROM weeks
+        ORDER BY weeks.week_start ASC
+      "#,
+      guild_id.to_string(),
+      weeks,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| MoodWeek {
+          week_start: row.week_start,
+          responses: row.responses,
+          average_score: row.average_score,
+          minutes: row.minutes,
+        })
+        .collect(),
+    )
+  }
+
+  /// Sets the guild's collective goal, replacing any current goal and its progress message.
+  pub async fn set_collective_goal(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    name: &str,
+    target_minutes: i64,
+    starts_on: chrono::NaiveDate,
+    ends_on: chrono::NaiveDate,
+    channel_id: &serenity::ChannelId,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO collective_goals (record_id, guild_id, name, target_minutes, starts_on, ends_on, channel_id) VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (guild_id) DO UPDATE SET record_id = $1, name = $3, target_minutes = $4, starts_on = $5, ends_on = $6, channel_id = $7, progress_message_id = NULL, last_milestone = 0, last_rendered_on = NULL
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      name,
+      target_minutes,
+      starts_on,
+      ends_on,
+      channel_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_collective_goal(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM collective_goals WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Gets the collective goal for a guild, or for all guilds if none is given.
+  pub async fn get_collective_goals(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: Option<&serenity::GuildId>,
+  ) -> Result<Vec<CollectiveGoal>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT record_id, guild_id, name, target_minutes, starts_on, ends_on, channel_id, progress_message_id, last_milestone, last_rendered_on
+        FROM collective_goals
+        WHERE $1::text IS NULL OR guild_id = $1
+      "#,
+      guild_id.map(ToString::to_string),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| CollectiveGoal {
+          id: row.record_id,
+          guild_id: serenity::GuildId::new(row.guild_id.parse::<u64>().unwrap()),
+          name: row.name,
+          target_minutes: row.target_minutes,
+          starts_on: row.starts_on,
+          ends_on: row.ends_on,
+          channel_id: serenity::ChannelId::new(row.channel_id.parse::<u64>().unwrap()),
+          progress_message_id: row
+            .progress_message_id
+            .map(|id| serenity::MessageId::new(id.parse::<u64>().unwrap())),
+          last_milestone: row.last_milestone,
+          last_rendered_on: row.last_rendered_on,
+        })
+        .collect(),
+    )
+  }
+
+  pub async fn update_collective_goal_progress(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    goal_id: &str,
+    progress_message_id: Option<&serenity::MessageId>,
+    last_milestone: i16,
+    last_rendered_on: Option<chrono::NaiveDate>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE collective_goals SET progress_message_id = $2, last_milestone = $3, last_rendered_on = $4 WHERE record_id = $1
+      "#,
+      goal_id,
+      progress_message_id.map(ToString::to_string),
+      last_milestone,
+      last_rendered_on,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Gets the minutes meditated in a guild from the start of one day through the end of another.
+  pub async fn get_guild_minutes_between(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    starts_on: chrono::NaiveDate,
+    ends_on: chrono::NaiveDate,
+  ) -> Result<i64> {
+    let row = sqlx::query!(
+      r#"
+        SELECT COALESCE(SUM(meditation_minutes), 0)::BIGINT AS "minutes!" FROM meditation
+        WHERE guild_id = $1 AND occurred_at::date >= $2 AND occurred_at::date <= $3
+      "#,
+      guild_id.to_string(),
+      starts_on,
+      ends_on,
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.minutes)
+  }
+
+  pub async fn get_hours_announcement(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<HoursAnnouncement> {
+    let row = sqlx::query!(
+      r#"
+        SELECT session_interval, message FROM hours_announcements WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    let announcement = match row {
+      Some(row) => HoursAnnouncement {
+        session_interval: row.session_interval,
+        message: row.message,
+      },
+      None => HoursAnnouncement::default(),
+    };
+
+    Ok(announcement)
+  }
+
+  pub async fn set_hours_announcement(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    announcement: &HoursAnnouncement,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO hours_announcements (record_id, guild_id, session_interval, message) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (guild_id) DO UPDATE SET session_interval = $3, message = $4
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      announcement.session_interval,
+      announcement.message,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_hours_announcement(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM hours_announcements WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Gets how many days ago each day the user restored with a streak credit was, in ascending order.
+  pub async fn get_restored_days_ago(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+  ) -> Result<Vec<i32>> {
+    let today = Self::get_local_date(transaction, guild_id, user_id).await?;
+
+    let rows = sqlx::query!(
+      r#"
+        SELECT streak_day
+        FROM streak_credits
+        WHERE guild_id = $1 AND user_id = $2 AND kind = 'restored'
+        ORDER BY streak_day DESC
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .filter_map(|row| streaks::days_ago(today, row.streak_day))
+        .collect(),
+    )
+  }
+
+  pub async fn get_streak_credits(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+  ) -> Result<i64> {
+    let row = sqlx::query!(
+      r#"
+        SELECT COALESCE(SUM(amount), 0)::BIGINT AS "credits!" FROM streak_credits WHERE guild_id = $1 AND user_id = $2
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.credits)
+  }
+
+  /// Gives the user a streak credit for today, unless they already earned one today or hold `max_credits`. Returns whether a credit was given.
+  pub async fn earn_streak_credit(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    max_credits: i64,
+  ) -> Result<bool> {
+    let result = sqlx::query!(
+      r#"
+        INSERT INTO streak_credits (record_id, guild_id, user_id, kind, amount, streak_day)
+        SELECT $1, $2, $3, 'earned', 1, NOW()::date
+        WHERE (SELECT COALESCE(SUM(amount), 0) FROM streak_credits WHERE guild_id = $2 AND user_id = $3) < $4
+        ON CONFLICT (guild_id, user_id, kind, streak_day) DO NOTHING
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      user_id.to_string(),
+      max_credits,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+  }
+
+  /// Spends a streak credit to count the day `days_ago`, counted back from the user's local date, toward their meditation streak.
+  pub async fn restore_streak_day(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    days_ago: i32,
+  ) -> Result<()> {
+    let streak_day = Self::get_local_date(transaction, guild_id, user_id).await?
+      - chrono::Duration::days(i64::from(days_ago));
+
+    sqlx::query!(
+      r#"
+        INSERT INTO streak_credits (record_id, guild_id, user_id, kind, amount, streak_day) VALUES ($1, $2, $3, 'restored', -1, $4)
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      user_id.to_string(),
+      streak_day,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn streak_restores_enabled(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<bool> {
+    let row = sqlx::query!(
+      r#"
+        SELECT EXISTS(SELECT 1 FROM streak_credit_settings WHERE guild_id = $1) AS "enabled!"
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.enabled)
+  }
+
+  /// Turns streak restores on or off for the guild. Credits already earned are kept, so they can be spent if restores are turned back on.
+  pub async fn set_streak_restores_enabled(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    enabled: bool,
+  ) -> Result<()> {
+    if enabled {
+      sqlx::query!(
+        r#"
+          INSERT INTO streak_credit_settings (record_id, guild_id) VALUES ($1, $2)
+          ON CONFLICT (guild_id) DO NOTHING
+        "#,
+        Ulid::new().to_string(),
+        guild_id.to_string(),
+      )
+      .execute(&mut **transaction)
+      .await?;
+    } else {
+      sqlx::query!(
+        r#"
+          DELETE FROM streak_credit_settings WHERE guild_id = $1
+        "#,
+        guild_id.to_string(),
+      )
+      .execute(&mut **transaction)
+      .await?;
+    }
+
+    Ok(())
+  }
+
+  /// Gets the current meditation streak of every user in the guild with a streak. Users who turned streaks off, or made their streak or stats private, are left out.
+  pub async fn get_streak_leaderboard(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<StreakLeaderboardUser>> {
+    // A streak ends once a full day is missed, so only users active in the last two days are counted.
+    // Days are counted back from each user's local date, since entries are stored in local time.
+    // Local dates are at most a day either side of the UTC date, so entries from the last three UTC days cover everyone who could have a streak.
+    let rows = sqlx::query!(
+      r#"
+        WITH days AS (
+          SELECT user_id, (occurred_at AT TIME ZONE 'UTC')::date AS day, FALSE AS restored
+          FROM meditation
+          WHERE guild_id = $1
+          UNION ALL
+          SELECT user_id, streak_day AS day, TRUE AS restored
+          FROM streak_credits
+          WHERE guild_id = $1 AND kind = 'restored'
+        )
+        SELECT DISTINCT days.user_id AS "user_id!", days.day AS "day!", days.restored AS "restored!", COALESCE(tracking_profile.utc_offset, 0::SMALLINT) AS "utc_offset!"
+        FROM days
+        LEFT JOIN tracking_profile ON tracking_profile.user_id = days.user_id AND tracking_profile.guild_id = $1
+        WHERE COALESCE(tracking_profile.streaks_active, TRUE)
+          AND NOT COALESCE(tracking_profile.streaks_private, FALSE)
+          AND NOT COALESCE(tracking_profile.stats_private, FALSE)
+          AND days.user_id IN (
+            SELECT user_id FROM days WHERE day >= (NOW() AT TIME ZONE 'UTC')::date - 3
+          )
+        ORDER BY "user_id!", "day!" DESC
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let now = Utc::now();
+    let mut days_by_user: BTreeMap<String, (Vec<i32>, Vec<i32>)> = BTreeMap::new();
+    for row in rows {
+      let Some(day_ago) = streaks::days_ago(streaks::local_date(now, row.utc_offset), row.day)
+      else {
+        continue;
+      };
+
+      let (days_ago, restored_days_ago) = days_by_user.entry(row.user_id).or_default();
+      if days_ago.last() != Some(&day_ago) {
+        days_ago.push(day_ago);
+      }
+      if row.restored {
+        restored_days_ago.push(day_ago);
+      }
+    }
+
+    let users = days_by_user
+      .into_iter()
+      .filter_map(|(user_id, (days_ago, restored_days_ago))| {
+        let streak = streaks::consecutive_days(&days_ago);
+        (streak > 0).then(|| StreakLeaderboardUser {
+          user_id: serenity::UserId::new(user_id.parse::<u64>().unwrap()),
+          streak,
+          restored: streaks::includes_restored_day(&days_ago, &restored_days_ago),
+        })
+      })
+      .collect();
+
+    Ok(users)
+  }
+
+  /// Finds a missed day, up to `window` days ago, that the user could restore with a streak credit to keep their meditation streak going.
+  pub async fn get_restorable_streak_day(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    window: i32,
+  ) -> Result<Option<i32>> {
+    let days_ago = Self::get_meditation_days_ago(transaction, guild_id, user_id).await?;
+
+    Ok(streaks::restorable_day(&days_ago, window))
+  }
+
+  /// Whether the user's current meditation streak includes a day restored with a streak credit.
+  pub async fn is_streak_restored(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+  ) -> Result<bool> {
+    let restored_days_ago = Self::get_restored_days_ago(transaction, guild_id, user_id).await?;
+    if restored_days_ago.is_empty() {
+      return Ok(false);
+    }
+
+    let days_ago = Self::get_meditation_days_ago(transaction, guild_id, user_id).await?;
+
+    Ok(streaks::includes_restored_day(
+      &days_ago,
+      &restored_days_ago,
+    ))
+  }
+
+  /// Posts the final leaderboard for each `period` in the channel. Periods that ended before `last_period_start` are not posted.
+  pub async fn set_leaderboard_rollover(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    period: LeaderboardPeriod,
+    channel_id: &serenity::ChannelId,
+    last_period_start: chrono::DateTime<Utc>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO leaderboard_rollovers (record_id, guild_id, period, channel_id, last_period_start) VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (guild_id, period) DO UPDATE SET channel_id = $4
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      period.key(),
+      channel_id.to_string(),
+      last_period_start,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_leaderboard_rollover(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    period: LeaderboardPeriod,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM leaderboard_rollovers WHERE guild_id = $1 AND period = $2
+      "#,
+      guild_id.to_string(),
+      period.key(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Gets the leaderboard rollovers for the guild, or for every guild if none is given.
+  pub async fn get_leaderboard_rollovers(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: Option<&serenity::GuildId>,
+  ) -> Result<Vec<LeaderboardRollover>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT record_id, guild_id, period, channel_id, last_period_start, pinned_message_id
+        FROM leaderboard_rollovers
+        WHERE $1::text IS NULL OR guild_id = $1
+        ORDER BY period
+      "#,
+      guild_id.map(ToString::to_string),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let rollovers = rows
+      .into_iter()
+      .filter_map(|row| {
+        Some(LeaderboardRollover {
+          id: row.record_id,
+          guild_id: serenity::GuildId::new(row.guild_id.parse::<u64>().unwrap()),
+          period: LeaderboardPeriod::from_key(&row.period)?,
+          channel_id: serenity::ChannelId::new(row.channel_id.parse::<u64>().unwrap()),
+          last_period_start: row.last_period_start,
+          pinned_message_id: row
+            .pinned_message_id
+            .map(|id| serenity::MessageId::new(id.parse::<u64>().unwrap())),
+        })
+      })
+      .collect();
+
+    Ok(rollovers)
+  }
+
+  pub async fn mark_leaderboard_rollover_posted(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    rollover_id: &str,
+    period_start: chrono::DateTime<Utc>,
+    pinned_message_id: Option<&serenity::MessageId>,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE leaderboard_rollovers SET last_period_start = $2, pinned_message_id = COALESCE($3, pinned_message_id) WHERE record_id = $1
+      "#,
+      rollover_id,
+      period_start,
+      pinned_message_id.map(ToString::to_string),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Saves the final leaderboard for the period in every guild, unless it was already saved. Returns the number of members saved.
+  ///
+  /// Minutes are capped per day by each guild's daily cap, and members with private stats are left out.
+  pub async fn archive_leaderboards(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    period: LeaderboardPeriod,
+    start_time: chrono::DateTime<Utc>,
+    end_time: chrono::DateTime<Utc>,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        INSERT INTO leaderboard_archive (guild_id, period, period_start, rank, user_id, minutes, sessions)
+        WITH daily AS (
+          SELECT meditation.guild_id, meditation.user_id,
+            LEAST(SUM(meditation_minutes), COALESCE(MAX(session_limits.daily_cap_minutes), 2147483647)) AS minutes,
+            COUNT(*) AS sessions
+          FROM meditation
+          LEFT JOIN tracking_profile ON tracking_profile.user_id = meditation.user_id AND tracking_profile.guild_id = meditation.guild_id
+          LEFT JOIN session_limits ON session_limits.guild_id = meditation.guild_id
+          WHERE occurred_at >= $2 AND occurred_at < $3
+            AND NOT COALESCE(tracking_profile.stats_private, FALSE)
+            AND NOT EXISTS (
+              SELECT 1 FROM leaderboard_archive
+              WHERE leaderboard_archive.guild_id = meditation.guild_id AND period = $1 AND period_start = $2
+            )
+          GROUP BY meditation.guild_id, meditation.user_id, DATE(occurred_at)
+        ),
+        totals AS (
+          SELECT guild_id, user_id, SUM(minutes)::BIGINT AS minutes, SUM(sessions)::BIGINT AS sessions
+          FROM daily
+          GROUP BY guild_id, user_id
+          HAVING SUM(minutes) > 0
+        )
+        SELECT guild_id, $1, $2, (RANK() OVER (PARTITION BY guild_id ORDER BY minutes DESC))::INTEGER, user_id, minutes, sessions
+        FROM totals
+        ON CONFLICT DO NOTHING
+      "#,
+      period.key(),
+      start_time,
+      end_time,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn get_archived_leaderboard(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    period: LeaderboardPeriod,
+    period_start: chrono::DateTime<Utc>,
+    limit: i64,
+  ) -> Result<Vec<ArchivedLeaderboardUser>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT user_id, rank, minutes, sessions
+        FROM leaderboard_archive
+        WHERE guild_id = $1 AND period = $2 AND period_start = $3
+        ORDER BY rank, user_id
+        LIMIT $4
+      "#,
+      guild_id.to_string(),
+      period.key(),
+      period_start,
+      limit,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let users = rows
+      .into_iter()
+      .map(|row| ArchivedLeaderboardUser {
+        user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+        rank: row.rank,
+        minutes: row.minutes,
+        sessions: row.sessions,
+      })
+      .collect();
+
+    Ok(users)
+  }
+
+  /// Gets the guild's saved final leaderboards for the period, newest first, with the top `limit` members of each.
+  ///
+  /// Members who have since made their stats private are left out, but still count toward the totals. Every leaderboard has a member ranked first, so no period is dropped when they are.
+  pub async fn get_leaderboard_archive(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    period: LeaderboardPeriod,
+    limit: i32,
+  ) -> Result<Vec<ArchivedLeaderboard>> {
+    let rows = sqlx::query!(
+      r#"
+        WITH archive AS (
+          SELECT leaderboard_archive.*,
+            COUNT(*) OVER (PARTITION BY period_start) AS members,
+            SUM(minutes) OVER (PARTITION BY period_start)::BIGINT AS total_minutes
+          FROM leaderboard_archive
+          WHERE guild_id = $1 AND period = $2
+        )
+        SELECT archive.period_start, archive.rank, archive.user_id, archive.minutes, archive.sessions,
+          archive.members AS "members!", archive.total_minutes AS "total_minutes!",
+          NOT COALESCE(tracking_profile.stats_private, FALSE) AS "shown!"
+        FROM archive
+        LEFT JOIN tracking_profile ON tracking_profile.user_id = archive.user_id AND tracking_profile.guild_id = archive.guild_id
+        WHERE archive.rank <= $3
+        ORDER BY archive.period_start DESC, archive.rank, archive.user_id
+      "#,
+      guild_id.to_string(),
+      period.key(),
+      limit,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let mut leaderboards: Vec<ArchivedLeaderboard> = Vec::new();
+    for row in rows {
+      if leaderboards.last().map_or(true, |leaderboard| {
+        leaderboard.period_start != row.period_start
+      }) {
+        leaderboards.push(ArchivedLeaderboard {
+          period,
+          period_start: row.period_start,
+          totals: ArchivedTotals {
+            members: row.members,
+            minutes: row.total_minutes,
+          },
+          last_year: None,
+          users: Vec::new(),
+        });
+      }
+
+      if row.shown {
+        if let Some(leaderboard) = leaderboards.last_mut() {
+          leaderboard.users.push(ArchivedLeaderboardUser {
+            user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+            rank: row.rank,
+            minutes: row.minutes,
+            sessions: row.sessions,
+          });
+        }
+      }
+    }
+
+    Ok(leaderboards)
+  }
+
+  /// Gets new members, 30-day retention, and churn for each of the last `months` complete months, oldest first. Members count as new in the month of their first entry in the guild.
+  pub async fn get_guild_growth(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    months: i32,
+  ) -> Result<Vec<GrowthMonth>> {
+    let rows = sqlx::query!(
+      r#"
+        WITH months AS (
+          SELECT GENERATE_SERIES(DATE_TRUNC('month', NOW()) - $2::INTEGER * INTERVAL '1 month', DATE_TRUNC('month', NOW()) - INTERVAL '1 month', INTERVAL '1 month')::date AS month_start
+        ),
+        cohorts AS (
+          SELECT user_id, first_at, DATE_TRUNC('month', first_at)::date AS month_start,
+            EXISTS (
+              SELECT 1 FROM meditation
+              WHERE guild_id = $1 AND meditation.user_id = firsts.user_id
+                AND DATE(occurred_at) > DATE(first_at) AND occurred_at < first_at + INTERVAL '30 days'
+            ) AS retained
+          FROM (
+            SELECT user_id, MIN(occurred_at) AS first_at FROM meditation WHERE guild_id = $1 GROUP BY user_id
+          ) firsts
+          WHERE first_at >= (SELECT MIN(month_start) FROM months)
+        ),
+        active AS (
+          SELECT DISTINCT user_id, DATE_TRUNC('month', occurred_at)::date AS month_start
+          FROM meditation
+          WHERE guild_id = $1 AND occurred_at >= DATE_TRUNC('month', NOW()) - ($2::INTEGER + 1) * INTERVAL '1 month'
+        )
+        SELECT months.month_start AS "month_start!",
+          (SELECT COUNT(*) FROM cohorts WHERE cohorts.month_start = months.month_start) AS "new_members!",
+          (SELECT COUNT(*) FROM cohorts WHERE cohorts.month_start = months.month_start AND first_at <= NOW() - INTERVAL '30 days') AS "retention_eligible!",
+          (SELECT COUNT(*) FROM cohorts WHERE cohorts.month_start = months.month_start AND first_at <= NOW() - INTERVAL '30 days' AND retained) AS "retained!",
+          (SELECT COUNT(*) FROM active WHERE active.month_start = months.month_start) AS "active_members!",
+          (
+            SELECT COUNT(*) FROM active previous
+            WHERE previous.month_start = (months.month_start - INTERVAL '1 month')::date
+              AND NOT EXISTS (SELECT 1 FROM active WHERE active.user_id = previous.user_id AND active.month_start = months.month_start)
+          ) AS "churned!"
+        FROM months
+        ORDER BY months.month_start ASC
+      "#,
+      guild_id.to_string(),
+      months,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| GrowthMonth {
+          month_start: row.month_start,
+          new_members: row.new_members,
+          retention_eligible: row.retention_eligible,
+          retained: row.retained,
+          active_members: row.active_members,
+          churned: row.churned,
+        })
+        .collect(),
+    )
+  }
+
+  /// Starts an import job for the file and returns its ID.
+  pub async fn create_import_job(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    file_name: &str,
+    file_url: &str,
+    file_size: i32,
+    on_conflict: ImportConflict,
+  ) -> Result<String> {
+    let id = Ulid::new().to_string();
+
+    sqlx::query!(
+      r#"
+        INSERT INTO import_jobs (record_id, guild_id, user_id, file_name, file_url, file_size, on_conflict, heartbeat_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+      "#,
+      id,
+      guild_id.to_string(),
+      user_id.to_string(),
+      file_name,
+      file_url,
+      file_size,
+      on_conflict.key(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(id)
+  }
+
+  /// The member's most recent import that hasn't finished, if any.
+  pub async fn get_unfinished_import_job(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+  ) -> Result<Option<ImportJob>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT record_id, file_name, file_url, file_size, rows_processed, chunks_completed, entries_added, rows_failed,
+          on_conflict, duplicates_skipped, entries_replaced, entries_merged, heartbeat_at
+        FROM import_jobs
+        WHERE guild_id = $1 AND user_id = $2 AND finished_at IS NULL
+        ORDER BY started_at DESC
+        LIMIT 1
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| ImportJob {
+      id: row.record_id,
+      file_name: row.file_name,
+      file_url: row.file_url,
+      file_size: row.file_size,
+      rows_processed: row.rows_processed,
+      chunks_completed: row.chunks_completed,
+      entries_added: row.entries_added,
+      rows_failed: row.rows_failed,
+      on_conflict: ImportConflict::from_key(&row.on_conflict).unwrap_or(ImportConflict::Skip),
+      duplicates_skipped: row.duplicates_skipped,
+      entries_replaced: row.entries_replaced,
+      entries_merged: row.entries_merged,
+      heartbeat_at: row.heartbeat_at,
+    }))
+  }
+
+  /// Claims an unfinished import so it can be resumed, optionally pointing it at a newly uploaded copy of the file.
+  ///
+  /// Returns `false` if the import is still running, which is when it saved a chunk after `stale_before`.
+  pub async fn claim_import_job(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    job_id: &str,
+    stale_before: chrono::DateTime<Utc>,
+    file_url: Option<&str>,
+  ) -> Result<bool> {
+    let result = sqlx::query!(
+      r#"
+        UPDATE import_jobs
+        SET heartbeat_at = NOW(), file_url = COALESCE($3, file_url)
+        WHERE record_id = $1 AND finished_at IS NULL AND (heartbeat_at IS NULL OR heartbeat_at < $2)
+      "#,
+      job_id,
+      stale_before,
+      file_url,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+  }
+
+  /// Adds a chunk of imported entries, handling rows that overlap the member's existing entries with the chosen strategy.
+  ///
+  /// Rows are staged first, so they can be compared against existing entries in bulk. Rows within `tolerance_minutes` of an existing entry are duplicates.
+  pub async fn import_entries(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    job_id: &str,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    on_conflict: ImportConflict,
+    tolerance_minutes: i32,
+    entries: &[(chrono::DateTime<Utc>, i32)],
+  ) -> Result<ImportedChunk> {
+    let record_ids = entries
+      .iter()
+      .map(|_| Ulid::new().to_string())
+      .collect::<Vec<String>>();
+    let occurred_at = entries
+      .iter()
+      .map(|(occurred_at, _)| *occurred_at)
+      .collect::<Vec<chrono::DateTime<Utc>>>();
+    let minutes = entries
+      .iter()
+      .map(|(_, minutes)| *minutes)
+      .collect::<Vec<i32>>();
+
+    sqlx::query!(
+      r#"
+        INSERT INTO import_staging (job_id, record_id, meditation_minutes, occurred_at)
+        SELECT $1, record_id, meditation_minutes, occurred_at
+        FROM UNNEST($2::text[], $3::integer[], $4::timestamptz[]) AS entries (record_id, meditation_minutes, occurred_at)
+      "#,
+      job_id,
+      &record_ids,
+      &minutes,
+      &occurred_at,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    let mut chunk = ImportedChunk::default();
+
+    match on_conflict {
+      ImportConflict::Skip => {
+        let skipped = sqlx::query!(
+          r#"
+            DELETE FROM import_staging staged
+            WHERE staged.job_id = $1 AND EXISTS (
+              SELECT 1 FROM meditation
+              WHERE guild_id = $2 AND user_id = $3
+                AND occurred_at BETWEEN staged.occurred_at - make_interval(mins => $4) AND staged.occurred_at + make_interval(mins => $4)
+            )
+          "#,
+          job_id,
+          guild_id.to_string(),
+          user_id.to_string(),
+          tolerance_minutes,
+        )
+        .execute(&mut **transaction)
+        .await?;
+        chunk.duplicates_skipped = i32::try_from(skipped.rows_affected())?;
+      }
+      ImportConflict::Replace => {
+        // Each day is only replaced the first time the import reaches it, so a day split across chunks keeps the entries added for it earlier
+        let replaced = sqlx::query!(
+          r#"
+            WITH new_days AS (
+              SELECT DISTINCT (occurred_at AT TIME ZONE 'UTC')::date AS day FROM import_staging WHERE job_id = $1
+              EXCEPT
+              SELECT day FROM import_job_days WHERE job_id = $1
+            ),
+            recorded AS (
+              INSERT INTO import_job_days (job_id, day) SELECT $1, day FROM new_days
+            ),
+            removed AS (
+              DELETE FROM meditation
+              WHERE guild_id = $2 AND user_id = $3
+                AND (occurred_at AT TIME ZONE 'UTC')::date IN (SELECT day FROM new_days)
+              RETURNING record_id, user_id, guild_id, occurred_at, meditation_minutes, source
+            )
+            INSERT INTO meditation_trash (record_id, user_id, guild_id, occurred_at, meditation_minutes, source, batch_id, reason, deleted_by)
+            SELECT record_id, user_id, guild_id, occurred_at, meditation_minutes, source, $1, 'import', $3 FROM removed
+          "#,
+          job_id,
+          guild_id.to_string(),
+          user_id.to_string(),
+        )
+        .execute(&mut **transaction)
+        .await?;
+        chunk.entries_replaced = i32::try_from(replaced.rows_affected())?;
+      }
+      ImportConflict::Merge => {
+        let merged = sqlx::query!(
+          r#"
+            WITH matched AS (
+              SELECT meditation.record_id, MAX(staged.meditation_minutes) AS meditation_minutes
+              FROM import_staging staged
+              JOIN meditation ON meditation.guild_id = $2 AND meditation.user_id = $3
+                AND meditation.occurred_at BETWEEN staged.occurred_at - make_interval(mins => $4) AND staged.occurred_at + make_interval(mins => $4)
+              WHERE staged.job_id = $1
+              GROUP BY meditation.record_id
+            )
+            UPDATE meditation
+            SET meditation_minutes = GREATEST(meditation.meditation_minutes, matched.meditation_minutes)
+            FROM matched
+            WHERE meditation.record_id = matched.record_id
+          "#,
+          job_id,
+          guild_id.to_string(),
+          user_id.to_string(),
+          tolerance_minutes,
+        )
+        .execute(&mut **transaction)
+        .await?;
+        chunk.entries_merged = i32::try_from(merged.rows_affected())?;
+
+        let duplicates = sqlx::query!(
+          r#"
+            DELETE FROM import_staging staged
+            WHERE staged.job_id = $1 AND EXISTS (
+              SELECT 1 FROM meditation
+              WHERE guild_id = $2 AND user_id = $3
+                AND occurred_at BETWEEN staged.occurred_at - make_interval(mins => $4) AND staged.occurred_at + make_interval(mins => $4)
+            )
+          "#,
+          job_id,
+          guild_id.to_string(),
+          user_id.to_string(),
+          tolerance_minutes,
+        )
+        .execute(&mut **transaction)
+        .await?;
+        chunk.duplicates_skipped = i32::try_from(duplicates.rows_affected())?;
+      }
+    }
+
+    let added = sqlx::query!(
+      r#"
+        INSERT INTO meditation (record_id, user_id, meditation_minutes, guild_id, occurred_at, source)
+        SELECT record_id, $2, meditation_minutes, $3, occurred_at, $4 FROM import_staging WHERE job_id = $1
+      "#,
+      job_id,
+      user_id.to_string(),
+      guild_id.to_string(),
+      EntrySource::Import.key(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+    chunk.added = i32::try_from(added.rows_affected())?;
+
+    sqlx::query!(
+      r#"
+        DELETE FROM import_staging WHERE job_id = $1
+      "#,
+      job_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(chunk)
+  }
+
+  /// Records rows of a chunk that couldn't be imported, for the final error report.
+  pub async fn add_import_errors(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    job_id: &str,
+    errors: &[ImportError],
+  ) -> Result<()> {
+    if errors.is_empty() {
+      return Ok(());
+    }
+
+    let chunks = errors.iter().map(|error| error.chunk).collect::<Vec<i32>>();
+    let lines = errors.iter().map(|error| error.line).collect::<Vec<i32>>();
+    let messages = errors
+      .iter()
+      .map(|error| error.message.clone())
+      .collect::<Vec<String>>();
+
+    sqlx::query!(
+      r#"
+        INSERT INTO import_job_errors (job_id, chunk, line, message)
+        SELECT $1, chunk, line, message
+        FROM UNNEST($2::integer[], $3::integer[], $4::text[]) AS errors (chunk, line, message)
+        ON CONFLICT (job_id, line) DO NOTHING
+      "#,
+      job_id,
+      &chunks,
+      &lines,
+      &messages,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Saves progress after a chunk. Committed with the chunk's entries, so a resumed import never adds them twice.
+  pub async fn save_import_checkpoint(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    job_id: &str,
+    rows_processed: i32,
+    rows_failed: i32,
+    imported: &ImportedChunk,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE import_jobs
+        SET rows_processed = rows_processed + $2,
+          rows_failed = rows_failed + $3,
+          entries_added = entries_added + $4,
+          duplicates_skipped = duplicates_skipped + $5,
+          entries_replaced = entries_replaced + $6,
+          entries_merged = entries_merged + $7,
+          chunks_completed = chunks_completed + 1,
+          heartbeat_at = NOW()
+        WHERE record_id = $1
+      "#,
+      job_id,
+      rows_processed,
+      rows_failed,
+      imported.added,
+      imported.duplicates_skipped,
+      imported.entries_replaced,
+      imported.entries_merged,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Marks an import as stopped by an error, so it can be resumed right away instead of waiting to go stale.
+  pub async fn pause_import_job(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    job_id: &str,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        UPDATE import_jobs SET heartbeat_at = NULL WHERE record_id = $1 AND finished_at IS NULL
+      "#,
+      job_id,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Marks an import as finished and returns its final totals.
+  pub async fn finish_import_job(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    job_id: &str,
+  ) -> Result<ImportJob> {
+    let row = sqlx::query!(
+      r#"
+        UPDATE import_jobs
+        SET finished_at = NOW(), heartbeat_at = NULL
+        WHERE record_id = $1
+        RETURNING record_id, file_name, file_url, file_size, rows_processed, chunks_completed, entries_added, rows_failed,
+          on_conflict, duplicates_skipped, entries_replaced, entries_merged, heartbeat_at
+      "#,
+      job_id,
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(ImportJob {
+      id: row.record_id,
+      file_name: row.file_name,
+      file_url: row.file_url,
+      file_size: row.file_size,
+      rows_processed: row.rows_processed,
+      chunks_completed: row.chunks_completed,
+      entries_added: row.entries_added,
+      rows_failed: row.rows_failed,
+      on_conflict: ImportConflict::from_key(&row.on_conflict).unwrap_or(ImportConflict::Skip),
+      duplicates_skipped: row.duplicates_skipped,
+      entries_replaced: row.entries_replaced,
+      entries_merged: row.entries_merged,
+      heartbeat_at: row.heartbeat_at,
+    })
+  }
+
+  pub async fn get_import_errors(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    job_id: &str,
+  ) -> Result<Vec<ImportError>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT chunk, line, message FROM import_job_errors WHERE job_id = $1 ORDER BY line
+      "#,
+      job_id,
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| ImportError {
+          chunk: row.chunk,
+          line: row.line,
+          message: row.message,
+        })
+        .collect(),
+    )
+  }
+
+  /// Records a pending request and returns its ID, which the member's approve and deny buttons refer to.
+  pub async fn create_stats_access_request(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    staff_id: &serenity::UserId,
+    reason: &str,
+  ) -> Result<String> {
+    let request_id = Ulid::new().to_string();
+
+    sqlx::query!(
+      r#"
+        INSERT INTO stats_access_requests (record_id, guild_id, user_id, staff_id, reason) VALUES ($1, $2, $3, $4, $5)
+      "#,
+      request_id,
+      guild_id.to_string(),
+      user_id.to_string(),
+      staff_id.to_string(),
+      reason,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(request_id)
+  }
+
+  /// Approves or denies a pending request made after `requested_after`. Only the member the request is for can answer it, and only once.
+  ///
+  /// Returns `None` if there was no such request.
+  pub async fn answer_stats_access_request(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    request_id: &str,
+    user_id: &serenity::UserId,
+    requested_after: chrono::DateTime<Utc>,
+    expires_at: Option<chrono::DateTime<Utc>>,
+  ) -> Result<Option<StatsAccessRequest>> {
+    let row = sqlx::query!(
+      r#"
+        UPDATE stats_access_requests
+        SET status = CASE WHEN $4::timestamptz IS NULL THEN 'denied' ELSE 'approved' END,
+          responded_at = NOW(), expires_at = $4
+        WHERE record_id = $1 AND user_id = $2 AND status = 'pending' AND requested_at > $3
+        RETURNING guild_id, user_id, staff_id, reason
+      "#,
+      request_id,
+      user_id.to_string(),
+      requested_after,
+      expires_at,
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| StatsAccessRequest {
+      guild_id: serenity::GuildId::new(row.guild_id.parse::<u64>().unwrap()),
+      user_id: serenity::UserId::new(row.user_id.parse::<u64>().unwrap()),
+      staff_id: serenity::UserId::new(row.staff_id.parse::<u64>().unwrap()),
+      reason: row.reason,
+    }))
+  }
+
+  pub async fn has_stats_access(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    staff_id: &serenity::UserId,
+  ) -> Result<bool> {
+    let row = sqlx::query!(
+      r#"
+        SELECT EXISTS (
+          SELECT 1 FROM stats_access_requests
+          WHERE guild_id = $1 AND user_id = $2 AND staff_id = $3 AND status = 'approved' AND expires_at > NOW()
+        ) AS "exists!"
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+      staff_id.to_string(),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.exists)
+  }
+
+  pub async fn add_stats_access_log(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+    staff_id: &serenity::UserId,
+    action: StatsAccessAction,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO stats_access_log (record_id, guild_id, user_id, staff_id, action) VALUES ($1, $2, $3, $4, $5)
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      user_id.to_string(),
+      staff_id.to_string(),
+      action.key(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Gets the most recent access log entries for a member, newest first.
+  pub async fn get_stats_access_log(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    user_id: &serenity::UserId,
+  ) -> Result<Vec<StatsAccessLogEntry>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT staff_id, action, occurred_at FROM stats_access_log
+        WHERE guild_id = $1 AND user_id = $2
+        ORDER BY occurred_at DESC
+        LIMIT 25
+      "#,
+      guild_id.to_string(),
+      user_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let entries = rows
+      .into_iter()
+      .filter_map(|row| {
+        Some(StatsAccessLogEntry {
+          staff_id: serenity::UserId::new(row.staff_id.parse::<u64>().unwrap()),
+          action: StatsAccessAction::from_key(&row.action)?,
+          occurred_at: row.occurred_at,
+        })
+      })
+      .collect();
+
+    Ok(entries)
+  }
+
+  pub async fn add_tracking_channel(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        INSERT INTO tracking_channels (record_id, guild_id, channel_id) VALUES ($1, $2, $3)
+        ON CONFLICT (channel_id) DO NOTHING
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      channel_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn remove_tracking_channel(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM tracking_channels WHERE guild_id = $1 AND channel_id = $2
+      "#,
+      guild_id.to_string(),
+      channel_id.to_string(),
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn get_tracking_channels(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<serenity::ChannelId>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT channel_id FROM tracking_channels WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let channels = rows
+      .into_iter()
+      .map(|row| serenity::ChannelId::new(row.channel_id.parse::<u64>().unwrap()))
+      .collect();
+
+    Ok(channels)
+  }
+
+  /// Picks the channel for public add confirmations and server announcements.
+  ///
+  /// That's the channel the command was used in when it's the default tracking channel or one of the additional tracking channels, and the default tracking channel otherwise. Guilds without a default tracking channel keep everything in the channel the command was used in.
+  pub async fn get_confirmation_channel(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: serenity::ChannelId,
+  ) -> Result<serenity::ChannelId> {
+    let row = sqlx::query!(
+      r#"
+        SELECT
+          (SELECT tracking_channel_id FROM guild_settings WHERE guild_id = $1) AS default_channel_id,
+          EXISTS (SELECT 1 FROM tracking_channels WHERE guild_id = $1 AND channel_id = $2) AS "is_tracking_channel!"
+      "#,
+      guild_id.to_string(),
+      channel_id.to_string(),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    let default_channel_id = row
+      .default_channel_id
+      .map(|id| serenity::ChannelId::new(id.parse::<u64>().unwrap()));
+
+    Ok(match default_channel_id {
+      Some(default_channel_id) if !row.is_tracking_channel => default_channel_id,
+      _ => channel_id,
+    })
+  }
+
+  /// Whether the channel is the guild's default tracking channel or one of its additional tracking channels.
+  pub async fn is_tracking_channel(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    channel_id: &serenity::ChannelId,
+  ) -> Result<bool> {
+    let row = sqlx::query!(
+      r#"
+        SELECT
+          EXISTS (SELECT 1 FROM guild_settings WHERE guild_id = $1 AND tracking_channel_id = $2)
+          OR EXISTS (SELECT 1 FROM tracking_channels WHERE guild_id = $1 AND channel_id = $2) AS "is_tracking_channel!"
+      "#,
+      guild_id.to_string(),
+      channel_id.to_string(),
+    )
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    Ok(row.is_tracking_channel)
+  }
+
+  pub async fn add_command_exclusion(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    command_name: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        INSERT INTO command_exclusions (record_id, guild_id, command_name) VALUES ($1, $2, $3)
+        ON CONFLICT (guild_id, command_name) DO NOTHING
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      command_name,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  pub async fn remove_command_exclusion(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    command_name: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM command_exclusions WHERE guild_id = $1 AND command_name = $2
+      "#,
+      guild_id.to_string(),
+      command_name,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Names of the top-level commands left out when commands are registered in the guild.
+  pub async fn get_command_exclusions(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<String>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT command_name FROM command_exclusions WHERE guild_id = $1 ORDER BY command_name
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.command_name).collect())
+  }
+
+  /// Runs a query for `/owner query` read-only, keeping up to `max_rows` rows.
+  ///
+  /// The query is run as a subquery, so it must be a single statement that returns rows. Columns are read as text through `json_each_text`, which keeps them in order.
+  /// The transaction is set to read-only with a statement timeout, so it should be rolled back afterwards.
+  /// Read-only transactions can still call functions that act outside them, such as `pg_terminate_backend`, so `/owner query` only allows queries calling common functions.
+  pub async fn run_diagnostics_query(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    query: &str,
+    max_rows: i64,
+    timeout_ms: u64,
+  ) -> Result<DiagnosticsQueryResult> {
+    use sqlx::Row;
+
+    sqlx::query("SET TRANSACTION READ ONLY")
+      .execute(&mut **transaction)
+      .await?;
+    sqlx::query(&format!("SET LOCAL statement_timeout = {timeout_ms}"))
+      .execute(&mut **transaction)
+      .await?;
+
+    let wrapped = format!(
+      r#"
+        SELECT
+          ARRAY(SELECT key FROM json_each_text(row_to_json(q))) AS columns,
+          ARRAY(SELECT value FROM json_each_text(row_to_json(q))) AS cells
+        FROM ({query}) AS q
+        LIMIT $1
+      "#
+    );
+    let rows = sqlx::query(&wrapped)
+      .bind(max_rows + 1)
+      .fetch_all(&mut **transaction)
+      .await?;
+
+    let columns = match rows.first() {
+      Some(row) => row.try_get::<Vec<String>, _>("columns")?,
+      None => Vec::new(),
+    };
+    let truncated = rows.len() > usize::try_from(max_rows)?;
+    let rows = rows
+      .iter()
+      .take(usize::try_from(max_rows)?)
+      .map(|row| row.try_get::<Vec<Option<String>>, _>("cells"))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(DiagnosticsQueryResult {
+      columns,
+      rows,
+      truncated,
+    })
+  }
+
+  pub async fn set_feature_flag(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    flag_name: &str,
+    enabled: bool,
+  ) -> Result<()> {
+    sqlx::query!(
+      r#"
+        INSERT INTO feature_flags (record_id, guild_id, flag_name, enabled) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (guild_id, flag_name) DO UPDATE SET enabled = $4
+      "#,
+      Ulid::new().to_string(),
+      guild_id.to_string(),
+      flag_name,
+      enabled,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+  }
+
+  pub async fn remove_feature_flag(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    flag_name: &str,
+  ) -> Result<u64> {
+    let result = sqlx::query!(
+      r#"
+        DELETE FROM feature_flags WHERE guild_id = $1 AND flag_name = $2
+      "#,
+      guild_id.to_string(),
+      flag_name,
+    )
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(result.rows_affected())
+  }
+
+  /// Whether the guild turned a feature on or off, or `None` if it uses the flag's default.
+  pub async fn get_feature_flag(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+    flag_name: &str,
+  ) -> Result<Option<bool>> {
+    let row = sqlx::query!(
+      r#"
+        SELECT enabled FROM feature_flags WHERE guild_id = $1 AND flag_name = $2
+      "#,
+      guild_id.to_string(),
+      flag_name,
+    )
+    .fetch_optional(&mut **transaction)
+    .await?;
+
+    Ok(row.map(|row| row.enabled))
+  }
+
+  /// Features the guild turned on or off, by flag name.
+  pub async fn get_feature_flags(
+    transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<(String, bool)>> {
+    let rows = sqlx::query!(
+      r#"
+        SELECT flag_name, enabled FROM feature_flags WHERE guild_id = $1
+      "#,
+      guild_id.to_string(),
+    )
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| (row.flag_name, row.enabled))
+        .collect(),
+    )
+  }
 }
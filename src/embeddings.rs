@@ -44,4 +44,38 @@ impl OpenAIHandler {
 
     Ok(embedding)
   }
+
+  /// Creates embeddings for multiple inputs in a single request, returned in the same order as the inputs.
+  pub async fn create_embeddings(
+    &self,
+    inputs: Vec<String>,
+    user: serenity::UserId,
+  ) -> Result<Vec<Vec<f32>>> {
+    let input_count = inputs.len();
+    let request = CreateEmbeddingRequest {
+      model: "text-embedding-ada-002".to_string(),
+      input: EmbeddingInput::StringArray(inputs),
+      user: Some(user.to_string()),
+      ..Default::default()
+    };
+
+    let mut embeddings = self.client.embeddings().create(request).await?.data;
+
+    if embeddings.len() != input_count {
+      return Err(anyhow::anyhow!(
+        "Expected {} embeddings, got {}",
+        input_count,
+        embeddings.len()
+      ));
+    }
+
+    embeddings.sort_by_key(|embedding| embedding.index);
+
+    Ok(
+      embeddings
+        .into_iter()
+        .map(|embedding| embedding.embedding)
+        .collect(),
+    )
+  }
 }
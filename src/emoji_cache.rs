@@ -0,0 +1,85 @@
+use crate::config::EmojiKind;
+use crate::database::{DatabaseHandler, GuildEmoji};
+use anyhow::Result;
+use log::error;
+use poise::serenity_prelude as serenity;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// How long custom emoji are kept before they are reloaded from the database.
+const EMOJIS_TTL: Duration = Duration::from_secs(60 * 60);
+
+struct CachedEmojis {
+  loaded_at: Instant,
+  emojis: Vec<GuildEmoji>,
+}
+
+/// Per-guild cache of custom emoji used in bot messages.
+///
+/// Entries expire after [`EMOJIS_TTL`], and should be invalidated whenever emoji are set or reset.
+#[derive(Default)]
+pub struct EmojiCache {
+  entries: RwLock<HashMap<serenity::GuildId, CachedEmojis>>,
+}
+
+impl EmojiCache {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub async fn get(
+    &self,
+    db: &DatabaseHandler,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<GuildEmoji>> {
+    if let Some(cached) = self.entries.read().await.get(guild_id) {
+      if cached.loaded_at.elapsed() < EMOJIS_TTL {
+        return Ok(cached.emojis.clone());
+      }
+    }
+
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    let emojis = DatabaseHandler::get_guild_emojis(&mut transaction, guild_id).await?;
+    drop(transaction);
+
+    self.entries.write().await.insert(
+      *guild_id,
+      CachedEmojis {
+        loaded_at: Instant::now(),
+        emojis: emojis.clone(),
+      },
+    );
+
+    Ok(emojis)
+  }
+
+  /// Returns the emoji configured for the guild, or the unicode fallback if none is configured.
+  ///
+  /// Never fails, since emoji are only decoration; lookup errors are logged and the fallback is used.
+  pub async fn emoji(
+    &self,
+    db: &DatabaseHandler,
+    guild_id: Option<serenity::GuildId>,
+    kind: EmojiKind,
+  ) -> String {
+    let Some(guild_id) = guild_id else {
+      return kind.fallback().to_string();
+    };
+
+    match self.get(db, &guild_id).await {
+      Ok(emojis) => emojis
+        .into_iter()
+        .find(|emoji| emoji.name == kind.key())
+        .map_or(kind.fallback().to_string(), |emoji| emoji.emoji),
+      Err(e) => {
+        error!("Could not load custom emoji for guild {guild_id}: {e}");
+        kind.fallback().to_string()
+      }
+    }
+  }
+
+  pub async fn invalidate(&self, guild_id: &serenity::GuildId) {
+    self.entries.write().await.remove(guild_id);
+  }
+}
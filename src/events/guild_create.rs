@@ -0,0 +1,75 @@
+use crate::config::BloomBotEmbed;
+use crate::database::DatabaseHandler;
+use anyhow::Result;
+use log::info;
+use poise::serenity_prelude::{Context, CreateEmbed, CreateMessage, Guild};
+
+fn setup_guide() -> CreateEmbed {
+  BloomBotEmbed::new()
+    .title("Thanks for adding Bloom!")
+    .description(
+      "Bloom helps your community track meditation time, build streaks, and share quotes and resources. Here's how to get started:",
+    )
+    .field(
+      "1. Create channels",
+      "Use `/manage setup` to create a channel for tracking meditation and a private channel for logs.",
+      false,
+    )
+    .field(
+      "2. Set session limits",
+      "Use `/manage limits set` to choose the shortest and longest entries members can add.",
+      false,
+    )
+    .field(
+      "3. Customize emoji",
+      "Use `/manage emojis set` to use your server's own emoji in bot messages.",
+      false,
+    )
+    .field(
+      "4. Restrict commands",
+      "Use `/manage permissions restrict` to limit commands to specific roles or channels.",
+      false,
+    )
+}
+
+/// Provisions default settings when the bot joins a new guild, and posts a setup guide for administrators.
+///
+/// The guide is posted to the system channel, or sent to the server owner if there is none.
+pub async fn guild_create(
+  ctx: &Context,
+  database: &DatabaseHandler,
+  guild: &Guild,
+  is_new: Option<bool>,
+  trace_id: &str,
+) -> Result<()> {
+  // Guilds are also created on every startup, so only provision guilds the bot just joined
+  if is_new != Some(true) {
+    return Ok(());
+  }
+
+  let mut transaction = database.start_transaction_with_retry(5).await?;
+  let provisioned = DatabaseHandler::add_guild_settings(&mut transaction, &guild.id).await?;
+  transaction.commit().await?;
+
+  // Guilds that have had the bot before keep their existing settings
+  if !provisioned {
+    return Ok(());
+  }
+
+  info!(
+    "[{trace_id}] Joined new guild {} ({})",
+    guild.name, guild.id
+  );
+
+  let message = CreateMessage::new().embed(setup_guide());
+  match guild.system_channel_id {
+    Some(channel_id) => {
+      channel_id.send_message(ctx, message).await?;
+    }
+    None => {
+      guild.owner_id.direct_message(ctx, message).await?;
+    }
+  }
+
+  Ok(())
+}
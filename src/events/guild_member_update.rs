@@ -1,4 +1,5 @@
-use crate::config;
+use crate::config::{self, EmojiKind};
+use crate::Data;
 use anyhow::Result;
 use poise::serenity_prelude::{self as serenity, Context, CreateMessage, Member};
 
@@ -27,6 +28,7 @@ impl UpdateType {
 
 pub async fn guild_member_update(
   ctx: &Context,
+  data: &Data,
   old_if_available: &Option<Member>,
   new: &Option<Member>,
 ) -> Result<()> {
@@ -43,8 +45,9 @@ pub async fn guild_member_update(
             .embed(config::BloomBotEmbed::new()
               .title(":tada: New Donator :tada:")
               .description(format!(
-                "Please welcome <@{}> as a new donator on Patreon.\n\nThank you for your generosity! It helps keep this community alive <:loveit:579017125809881089>",
-                new.user.id
+                "Please welcome <@{}> as a new donator on Patreon.\n\nThank you for your generosity! It helps keep this community alive {}",
+                new.user.id,
+                data.emojis.emoji(&data.db, Some(new.guild_id), EmojiKind::Love).await,
               ))
             )
           )
@@ -58,8 +61,9 @@ pub async fn guild_member_update(
             .embed(config::BloomBotEmbed::new()
               .title(":tada: New Donator :tada:")
               .description(format!(
-                "Please welcome <@{}> as a new donator on Ko-fi.\n\nThank you for your generosity! It helps keep this community alive <:loveit:579017125809881089>",
-                new.user.id
+                "Please welcome <@{}> as a new donator on Ko-fi.\n\nThank you for your generosity! It helps keep this community alive {}",
+                new.user.id,
+                data.emojis.emoji(&data.db, Some(new.guild_id), EmojiKind::Love).await,
               ))
             )
           )
@@ -74,8 +78,9 @@ pub async fn guild_member_update(
               .embed(config::BloomBotEmbed::new()
                   .title(":tada: A new member has arrived! :tada:")
                   .description(format!(
-                    "Welcome to the Meditation Mind community, <@{}>!\n\nCheck out <id:customize> to grab some roles and customize your community experience.\n\nWe're glad you've joined us! <:aww:578864572979478558>",
-                    new.user.id
+                    "Welcome to the Meditation Mind community, <@{}>!\n\nCheck out <id:customize> to grab some roles and customize your community experience.\n\nWe're glad you've joined us! {}",
+                    new.user.id,
+                    data.emojis.emoji(&data.db, Some(new.guild_id), EmojiKind::Welcome).await,
                   ))
                   .thumbnail("https://meditationmind.org/wp-content/uploads/2020/04/Webp.net-resizeimage-1.png")
             )
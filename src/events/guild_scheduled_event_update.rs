@@ -0,0 +1,44 @@
+use crate::database::DatabaseHandler;
+use crate::Data;
+use anyhow::Result;
+use poise::serenity_prelude::{Context, ScheduledEvent, ScheduledEventStatus};
+
+/// Starts ringing bells when a community sit with bells configured begins, and stops them when it ends.
+///
+/// A sit that is completed gets its ending bells if they haven't been rung yet. A canceled sit doesn't.
+pub async fn guild_scheduled_event_update(
+  ctx: &Context,
+  data: &Data,
+  event: &ScheduledEvent,
+) -> Result<()> {
+  match event.status {
+    ScheduledEventStatus::Active => {
+      let Some(channel_id) = event.channel_id else {
+        return Ok(());
+      };
+
+      let mut transaction = data.db.start_transaction_with_retry(5).await?;
+      let bells = DatabaseHandler::get_event_sit_bells(&mut transaction, &event.id).await?;
+      drop(transaction);
+
+      if let Some(bells) = bells {
+        data
+          .bells
+          .start(ctx, event.guild_id, channel_id, bells)
+          .await;
+      }
+    }
+    ScheduledEventStatus::Completed => {
+      data.bells.stop(ctx, event.guild_id, event.id, true).await?;
+    }
+    ScheduledEventStatus::Canceled => {
+      data
+        .bells
+        .stop(ctx, event.guild_id, event.id, false)
+        .await?;
+    }
+    _ => {}
+  }
+
+  Ok(())
+}
@@ -0,0 +1,179 @@
+use crate::checkins::CHECKIN_BUTTON_ID;
+use crate::config::{BloomBotEmbed, StatsAccessAction, CHANNELS};
+use crate::database::DatabaseHandler;
+use crate::stats_access::{
+  APPROVE_BUTTON_PREFIX, DENY_BUTTON_PREFIX, GRANT_HOURS, REQUEST_EXPIRY_DAYS,
+};
+use crate::streaks::Activity;
+use anyhow::Result;
+use poise::serenity_prelude::{
+  ChannelId, ComponentInteraction, Context, CreateEmbedFooter, CreateInteractionResponse,
+  CreateInteractionResponseMessage, CreateMessage, Interaction,
+};
+
+/// Handles buttons on messages the bot posts outside of commands, which need to keep working after a restart.
+///
+/// Buttons on command replies are handled by collectors in the commands themselves.
+pub async fn interaction_create(
+  ctx: &Context,
+  database: &DatabaseHandler,
+  interaction: &Interaction,
+) -> Result<()> {
+  let Interaction::Component(component) = interaction else {
+    return Ok(());
+  };
+
+  if component.data.custom_id == CHECKIN_BUTTON_ID {
+    check_in(ctx, database, component).await?;
+  } else if let Some(request_id) = component.data.custom_id.strip_prefix(APPROVE_BUTTON_PREFIX) {
+    answer_stats_access(ctx, database, component, request_id, true).await?;
+  } else if let Some(request_id) = component.data.custom_id.strip_prefix(DENY_BUTTON_PREFIX) {
+    answer_stats_access(ctx, database, component, request_id, false).await?;
+  }
+
+  Ok(())
+}
+
+/// Records a weekly check-in, at most once per week, and shows the member their check-in streak.
+async fn check_in(
+  ctx: &Context,
+  database: &DatabaseHandler,
+  component: &ComponentInteraction,
+) -> Result<()> {
+  let Some(guild_id) = component.guild_id else {
+    return Ok(());
+  };
+  let user_id = component.user.id;
+
+  let mut transaction = database.start_transaction_with_retry(5).await?;
+  let already_checked_in =
+    DatabaseHandler::has_checked_in_this_week(&mut transaction, &guild_id, &user_id).await?;
+
+  if !already_checked_in {
+    DatabaseHandler::add_activity_entry(
+      &mut transaction,
+      &guild_id,
+      &user_id,
+      Activity::CheckIn,
+      None,
+      None,
+      chrono::Utc::now(),
+    )
+    .await?;
+  }
+
+  let streak =
+    DatabaseHandler::get_activity_streak(&mut transaction, &guild_id, &user_id, Activity::CheckIn)
+      .await?;
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  let weeks = if streak == 1 { "week" } else { "weeks" };
+  let content = if already_checked_in {
+    format!("You've already checked in this week. Check-in streak: **{streak} {weeks}**.")
+  } else {
+    format!(":white_check_mark: Thanks for checking in! Check-in streak: **{streak} {weeks}**.")
+  };
+
+  component
+    .create_response(
+      ctx,
+      CreateInteractionResponse::Message(
+        CreateInteractionResponseMessage::new()
+          .content(content)
+          .ephemeral(true),
+      ),
+    )
+    .await?;
+
+  Ok(())
+}
+
+/// Approves or denies a staff member's request to view the member's private stats, from the buttons on the request DM.
+async fn answer_stats_access(
+  ctx: &Context,
+  database: &DatabaseHandler,
+  component: &ComponentInteraction,
+  request_id: &str,
+  approved: bool,
+) -> Result<()> {
+  let now = chrono::Utc::now();
+  let expires_at = approved.then(|| now + chrono::Duration::hours(GRANT_HOURS));
+
+  let mut transaction = database.start_transaction_with_retry(5).await?;
+  let request = DatabaseHandler::answer_stats_access_request(
+    &mut transaction,
+    request_id,
+    &component.user.id,
+    now - chrono::Duration::days(REQUEST_EXPIRY_DAYS),
+    expires_at,
+  )
+  .await?;
+
+  let Some(request) = request else {
+    drop(transaction);
+    component
+      .create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+          CreateInteractionResponseMessage::new()
+            .content("This request has already been answered or has expired.")
+            .components(Vec::new()),
+        ),
+      )
+      .await?;
+    return Ok(());
+  };
+
+  let action = if approved {
+    StatsAccessAction::Approved
+  } else {
+    StatsAccessAction::Denied
+  };
+  DatabaseHandler::add_stats_access_log(
+    &mut transaction,
+    &request.guild_id,
+    &request.user_id,
+    &request.staff_id,
+    action,
+  )
+  .await?;
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  let content = match expires_at {
+    Some(expires_at) => format!(
+      ":white_check_mark: Approved. <@{}> can view your stats until <t:{}:f>.",
+      request.staff_id,
+      expires_at.timestamp()
+    ),
+    None => ":x: Denied. Your stats stay private.".to_string(),
+  };
+
+  component
+    .create_response(
+      ctx,
+      CreateInteractionResponse::UpdateMessage(
+        CreateInteractionResponseMessage::new()
+          .content(content)
+          .components(Vec::new()),
+      ),
+    )
+    .await?;
+
+  let outcome = if approved { "Approved" } else { "Denied" };
+  let log_embed = BloomBotEmbed::new()
+    .title(format!("Stats Access {outcome}"))
+    .description(format!(
+      "**User**: <@{}>\n**Staff**: <@{}>\n**Reason**: {}",
+      request.user_id, request.staff_id, request.reason
+    ))
+    .footer(CreateEmbedFooter::new(format!(
+      "{outcome} by {} ({})",
+      component.user.name, component.user.id
+    )));
+
+  ChannelId::new(CHANNELS.bloomlogs)
+    .send_message(ctx, CreateMessage::new().embed(log_embed))
+    .await?;
+
+  Ok(())
+}
@@ -0,0 +1,86 @@
+use crate::commands::helpers::template::render_template;
+use crate::Data;
+use anyhow::Result;
+use poise::serenity_prelude::{builder::*, Context, Message, MessageType};
+use std::collections::HashMap;
+
+/// Longest name Discord allows for a thread.
+const MAX_THREAD_NAME_LENGTH: usize = 100;
+
+/// Fills in an auto-thread name pattern for a message.
+///
+/// Falls back to "Discussion" when the pattern fills in to nothing, such as `{content}` for a message with only an image.
+fn thread_name(ctx: &Context, pattern: &str, message: &Message) -> String {
+  let author = message
+    .member
+    .as_ref()
+    .and_then(|member| member.nick.clone())
+    .or_else(|| message.author.global_name.clone())
+    .unwrap_or_else(|| message.author.name.clone());
+  let channel = ctx
+    .cache
+    .channel(message.channel_id)
+    .map(|channel| channel.name.clone())
+    .unwrap_or_default();
+  let content = message.content_safe(ctx);
+  let date = message.timestamp.date_naive();
+
+  let values = HashMap::from([
+    ("author".to_string(), author),
+    ("channel".to_string(), channel),
+    (
+      "content".to_string(),
+      content
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string(),
+    ),
+    ("date".to_string(), date.format("%Y-%m-%d").to_string()),
+    ("weekday".to_string(), date.format("%A").to_string()),
+  ]);
+
+  // Patterns are checked when set, so missing variables are left out rather than failing
+  let name = render_template(pattern, &values).unwrap_or_else(|_| pattern.to_string());
+  let name = name.trim();
+
+  if name.is_empty() {
+    "Discussion".to_string()
+  } else {
+    name.chars().take(MAX_THREAD_NAME_LENGTH).collect()
+  }
+}
+
+/// Starts a discussion thread on new messages in channels where auto-threads are set up.
+pub async fn message(ctx: &Context, data: &Data, message: &Message) -> Result<()> {
+  let Some(guild_id) = message.guild_id else {
+    return Ok(());
+  };
+
+  if message.author.bot
+    || !matches!(
+      message.kind,
+      MessageType::Regular | MessageType::InlineReply
+    )
+  {
+    return Ok(());
+  }
+
+  let Some(settings) = data
+    .auto_threads
+    .get(&data.db, &guild_id, &message.channel_id)
+    .await?
+  else {
+    return Ok(());
+  };
+
+  let name = thread_name(ctx, &settings.name_pattern, message);
+
+  message
+    .channel_id
+    .create_thread_from_message(ctx, message.id, CreateThread::new(name))
+    .await?;
+
+  Ok(())
+}
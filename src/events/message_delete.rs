@@ -1,10 +1,17 @@
+use crate::config::{BloomBotEmbed, CHANNELS};
 use crate::database::DatabaseHandler;
+use crate::starboard;
 use anyhow::Result;
-use poise::serenity_prelude::{self as serenity};
+use log::error;
+use poise::serenity_prelude::{self as serenity, builder::*, ChannelId, Context};
 
 pub async fn message_delete(
+  ctx: &Context,
   database: &DatabaseHandler,
+  channel_id: &serenity::ChannelId,
   deleted_message_id: &serenity::MessageId,
+  guild_id: &Option<serenity::GuildId>,
+  trace_id: &str,
 ) -> Result<()> {
   let mut transaction = database.start_transaction().await?;
 
@@ -12,11 +19,85 @@ pub async fn message_delete(
     DatabaseHandler::get_star_message_by_message_id(&mut transaction, deleted_message_id).await?;
 
   if let Some(star_message) = star_message {
-    let star_message_id = star_message.record_id;
-    DatabaseHandler::delete_star_message(&mut transaction, &star_message_id).await?;
+    let cleanup = starboard::cleanup_for(&mut transaction, guild_id.as_ref()).await?;
+    // Deleted messages are still logged if the starboard post can't be cleaned up
+    if let Err(e) =
+      starboard::clean_up_deleted(&ctx.http, &mut transaction, &star_message, cleanup).await
+    {
+      error!("[{trace_id}] Could not clean up starboard post for deleted message {deleted_message_id}: {e:?}");
+    }
   }
 
+  // Only messages still in the cache can be logged, since their content is otherwise unknown
+  let Some(guild_id) = guild_id else {
+    transaction.commit().await?;
+    return Ok(());
+  };
+  let Some(deleted_message) = ctx
+    .cache
+    .message(channel_id, deleted_message_id)
+    .map(|message| serenity::Message::clone(&message))
+  else {
+    transaction.commit().await?;
+    return Ok(());
+  };
+
+  let settings = DatabaseHandler::get_delete_log_settings(&mut transaction, guild_id).await?;
+
   transaction.commit().await?;
 
+  let too_short = deleted_message.content.chars().count()
+    < usize::try_from(settings.min_length).unwrap_or_default();
+
+  if (settings.ignore_bots && deleted_message.author.bot)
+    || settings.ignored_channels.contains(channel_id)
+    || too_short
+  {
+    return Ok(());
+  }
+
+  // If longer than 4096 - 6 characters for the embed, truncate to 4096 - 3 for "..."
+  let content = if deleted_message.content.chars().count() > 4090 {
+    format!(
+      "{}...",
+      deleted_message
+        .content
+        .chars()
+        .take(4087)
+        .collect::<String>()
+    )
+  } else {
+    deleted_message.content.clone()
+  };
+
+  let mut log_embed = BloomBotEmbed::new()
+    .title("Message Deleted")
+    .description(format!("```{content}```"))
+    .field("Channel", format!("<#{channel_id}>"), true)
+    .field(
+      "Author",
+      format!("{} ({})", deleted_message.author, deleted_message.author.id),
+      true,
+    );
+
+  if !deleted_message.attachments.is_empty() {
+    log_embed = log_embed.field(
+      "Attachments",
+      deleted_message
+        .attachments
+        .iter()
+        .map(|attachment| attachment.filename.clone())
+        .collect::<Vec<String>>()
+        .join("\n"),
+      false,
+    );
+  }
+
+  let log_channel = ChannelId::new(CHANNELS.logs);
+
+  log_channel
+    .send_message(ctx, CreateMessage::new().embed(log_embed))
+    .await?;
+
   Ok(())
 }
@@ -0,0 +1,111 @@
+use crate::config::{BloomBotEmbed, CHANNELS, EDIT_LOG_RETENTION_DAYS, MIN_EDIT_LOG_CHANGE};
+use crate::database::DatabaseHandler;
+use anyhow::Result;
+use poise::serenity_prelude::{builder::*, ChannelId, Context, Message, MessageUpdateEvent};
+
+/// Counts how many characters changed between two versions of a message, ignoring the unchanged
+/// start and end.
+fn changed_length(old: &str, new: &str) -> usize {
+  let old: Vec<char> = old.chars().collect();
+  let new: Vec<char> = new.chars().collect();
+
+  let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+  let suffix = old[prefix..]
+    .iter()
+    .rev()
+    .zip(new[prefix..].iter().rev())
+    .take_while(|(a, b)| a == b)
+    .count();
+
+  (old.len() - prefix - suffix).max(new.len() - prefix - suffix)
+}
+
+/// Wraps message content in a code block, truncated to fit in an embed field.
+fn content_field(content: &str) -> String {
+  // If longer than 1024 - 6 characters for the embed, truncate to 1024 - 3 for "..."
+  let content = if content.chars().count() > 1018 {
+    format!("{}...", content.chars().take(1015).collect::<String>())
+  } else {
+    content.to_string()
+  };
+
+  format!("```{content}```")
+}
+
+pub async fn message_update(
+  ctx: &Context,
+  database: &DatabaseHandler,
+  old_if_available: &Option<Message>,
+  event: &MessageUpdateEvent,
+) -> Result<()> {
+  // Content is only included when it has changed
+  let (Some(guild_id), Some(author), Some(new_content)) =
+    (event.guild_id, &event.author, &event.content)
+  else {
+    return Ok(());
+  };
+
+  if author.bot {
+    return Ok(());
+  }
+
+  let mut transaction = database.start_transaction().await?;
+
+  if !DatabaseHandler::is_edit_log_channel(&mut transaction, &event.channel_id).await? {
+    return Ok(());
+  }
+
+  // Fall back to the last logged version when the previous version is no longer cached
+  let old_content = match old_if_available {
+    Some(old_message) => Some(old_message.content.clone()),
+    None => DatabaseHandler::get_last_message_edit_content(&mut transaction, &event.id).await?,
+  };
+
+  if let Some(old_content) = &old_content {
+    if changed_length(old_content, new_content) < MIN_EDIT_LOG_CHANGE {
+      return Ok(());
+    }
+  }
+
+  DatabaseHandler::add_message_edit(
+    &mut transaction,
+    &guild_id,
+    &event.channel_id,
+    &event.id,
+    &author.id,
+    old_content.as_deref(),
+    new_content,
+  )
+  .await?;
+
+  let retention_cutoff = chrono::Utc::now() - chrono::Duration::days(EDIT_LOG_RETENTION_DAYS);
+  DatabaseHandler::prune_message_edits(&mut transaction, retention_cutoff).await?;
+
+  transaction.commit().await?;
+
+  let message_link = event.id.link(event.channel_id, Some(guild_id));
+
+  let log_embed = BloomBotEmbed::new()
+    .title("Message Edited")
+    .description(format!(
+      "**Channel**: <#{}>\n**Author**: {} ({})\n**Message**: {message_link}",
+      event.channel_id, author, author.id
+    ))
+    .field(
+      "Before",
+      match &old_content {
+        Some(old_content) => content_field(old_content),
+        None => "*Previous version unavailable*".to_string(),
+      },
+      false,
+    )
+    .field("After", content_field(new_content), false);
+
+  let log_channel = ChannelId::new(CHANNELS.logs);
+
+  log_channel
+    .send_message(ctx, CreateMessage::new().embed(log_embed))
+    .await?;
+
+  Ok(())
+}
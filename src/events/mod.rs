@@ -1,13 +1,25 @@
+mod guild_create;
 // mod guild_member_addition;
 mod guild_member_removal;
 mod guild_member_update;
+mod guild_scheduled_event_update;
+mod interaction_create;
+mod message;
 mod message_delete;
+mod message_update;
 mod reaction_add;
 mod reaction_remove;
+mod voice_state_update;
 
+pub use guild_create::guild_create;
 // pub use guild_member_addition::guild_member_addition;
 pub use guild_member_removal::guild_member_removal;
 pub use guild_member_update::guild_member_update;
+pub use guild_scheduled_event_update::guild_scheduled_event_update;
+pub use interaction_create::interaction_create;
+pub use message::message;
 pub use message_delete::message_delete;
+pub use message_update::message_update;
 pub use reaction_add::reaction_add;
 pub use reaction_remove::reaction_remove;
+pub use voice_state_update::voice_state_update;
@@ -2,9 +2,16 @@ use crate::config::{self, CHANNELS, EMOTES, ROLES};
 use crate::database::DatabaseHandler;
 use anyhow::{Context as AnyhowContext, Result};
 use poise::serenity_prelude::{
-  builder::*, ChannelId, Context, MessageFlags, Reaction, ReactionType, UserId,
+  builder::*, ChannelId, Context, ForumTagId, GuildChannel, GuildId, Message, MessageFlags,
+  MessageId, Reaction, ReactionType, UserId,
 };
 
+/// Most tags Discord allows on a forum thread.
+const MAX_APPLIED_TAGS: usize = 5;
+
+/// Longest name Discord allows for a thread.
+const MAX_THREAD_NAME_LENGTH: usize = 100;
+
 pub async fn reaction_add(
   ctx: &Context,
   database: &DatabaseHandler,
@@ -101,13 +108,21 @@ async fn add_star(ctx: &Context, database: &DatabaseHandler, reaction: &Reaction
         .map_or(0, |r| r.count);
 
       let mut transaction = database.start_transaction().await?;
+
+      // Stars on starboard forum threads are for the thread, not the original message
+      if DatabaseHandler::is_starboard_thread(&mut transaction, &reaction.channel_id).await? {
+        return Ok(());
+      }
+
       let star_message =
         DatabaseHandler::get_star_message_by_message_id(&mut transaction, &reaction.message_id)
           .await?;
 
       if let Some(star_message) = star_message {
-        // Already exists, find the starboard channel
-        let starboard_channel = ChannelId::new(config::CHANNELS.starchannel);
+        // Already exists, find the starboard channel or forum thread
+        let starboard_channel = star_message
+          .board_channel_id
+          .unwrap_or(ChannelId::new(config::CHANNELS.starchannel));
 
         // Get the starboard message
         let mut starboard_message = starboard_channel
@@ -130,6 +145,13 @@ async fn add_star(ctx: &Context, database: &DatabaseHandler, reaction: &Reaction
           starboard_message
             .edit(ctx, EditMessage::new().embed(updated_embed))
             .await?;
+          DatabaseHandler::update_star_count(
+            &mut transaction,
+            &star_message.record_id,
+            i32::try_from(star_count).unwrap_or(i32::MAX),
+          )
+          .await?;
+          transaction.commit().await?;
         } else {
           let _ = starboard_channel
             .delete_message(&ctx, starboard_message.id)
@@ -212,52 +234,126 @@ async fn create_star_message(
       }
     }
 
-    let starboard_channel = ChannelId::new(CHANNELS.starchannel);
-
-    let starboard_message = match &starred_message.attachments.first() {
-      Some(attachment) => match &attachment.content_type {
-        Some(content_type) => {
-          if content_type.starts_with("image") {
-            starboard_channel
-              .send_message(ctx, CreateMessage::new().embed(embed))
-              .await?
-          } else {
-            starboard_channel
-              .send_message(
-                ctx,
-                CreateMessage::new()
-                  .embed(embed)
-                  .add_file(CreateAttachment::url(ctx, attachment.url.as_str()).await?),
-              )
-              .await?
-          }
-        }
-        None => {
-          starboard_channel
-            .send_message(
-              ctx,
-              CreateMessage::new()
-                .embed(embed)
-                .add_file(CreateAttachment::url(ctx, attachment.url.as_str()).await?),
-            )
-            .await?
-        }
-      },
-      None => {
-        starboard_channel
-          .send_message(ctx, CreateMessage::new().embed(embed))
-          .await?
+    let mut starboard_message = CreateMessage::new().embed(embed);
+
+    // Images are shown in the embed, so only other attachments need to be reuploaded
+    if let Some(attachment) = &starred_message.attachments.first() {
+      if !attachment
+        .content_type
+        .as_ref()
+        .is_some_and(|content_type| content_type.starts_with("image"))
+      {
+        starboard_message =
+          starboard_message.add_file(CreateAttachment::url(ctx, attachment.url.as_str()).await?);
       }
+    }
+
+    let forum = match reaction.guild_id {
+      Some(guild_id) => DatabaseHandler::get_starboard_forum(transaction, &guild_id)
+        .await?
+        .map(|forum_id| (guild_id, forum_id)),
+      None => None,
+    };
+
+    let (board_channel_id, board_message_id) = if let Some((guild_id, forum_id)) = forum {
+      // The starter message of a forum thread shares its ID with the thread
+      let thread_name = forum_post_name(ctx, &starred_message, &author_nick_or_name, message_type);
+      let thread = create_forum_post(
+        ctx,
+        transaction,
+        guild_id,
+        &starred_message,
+        forum_id,
+        CreateForumPost::new(thread_name, starboard_message),
+      )
+      .await?;
+      (thread.id, MessageId::new(thread.id.get()))
+    } else {
+      let starboard_channel = ChannelId::new(CHANNELS.starchannel);
+      let board_message = starboard_channel
+        .send_message(ctx, starboard_message)
+        .await?;
+      (starboard_channel, board_message.id)
     };
 
     DatabaseHandler::insert_star_message(
       transaction,
       &reaction.message_id,
-      &starboard_message.id,
+      &board_message_id,
       &reaction.channel_id,
+      &board_channel_id,
+      reaction.guild_id.as_ref(),
+      &starred_message.author.id,
+      i32::try_from(star_count).unwrap_or(i32::MAX),
     )
     .await?;
   }
 
   Ok(())
 }
+
+/// Names a starboard forum thread after the start of the starred message.
+fn forum_post_name(
+  ctx: &Context,
+  starred_message: &Message,
+  author_nick_or_name: &str,
+  message_type: &str,
+) -> String {
+  let content = starred_message.content_safe(ctx);
+  let snippet = content.lines().next().unwrap_or_default().trim();
+
+  let name = if snippet.is_empty() {
+    format!("{author_nick_or_name}'s {message_type}")
+  } else {
+    format!("{author_nick_or_name}: {snippet}")
+  };
+
+  name.chars().take(MAX_THREAD_NAME_LENGTH).collect()
+}
+
+/// Creates a thread in the starboard forum, applying the tags set for the channel the message was starred in.
+///
+/// Messages in a thread also get the tags set for the thread's parent channel. Tags that have since been removed from the forum are skipped.
+async fn create_forum_post(
+  ctx: &Context,
+  transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  guild_id: GuildId,
+  starred_message: &Message,
+  forum_id: ChannelId,
+  post: CreateForumPost,
+) -> Result<GuildChannel> {
+  let forum = forum_id
+    .to_channel(ctx)
+    .await?
+    .guild()
+    .with_context(|| format!("Starboard forum {forum_id} is not a server channel"))?;
+
+  let mut source_channels = vec![starred_message.channel_id];
+  if let Some(channel) = starred_message.channel_id.to_channel(ctx).await?.guild() {
+    if channel.thread_metadata.is_some() {
+      source_channels.extend(channel.parent_id);
+    }
+  }
+
+  let mut tags: Vec<ForumTagId> = DatabaseHandler::get_starboard_forum_tags(transaction, &guild_id)
+    .await?
+    .into_iter()
+    .filter(|tag| {
+      source_channels.contains(&tag.source_channel_id)
+        && forum
+          .available_tags
+          .iter()
+          .any(|available| available.id == tag.tag_id)
+    })
+    .map(|tag| tag.tag_id)
+    .collect();
+  tags.sort_unstable();
+  tags.dedup();
+  tags.truncate(MAX_APPLIED_TAGS);
+
+  let thread = forum_id
+    .create_forum_post(ctx, post.set_applied_tags(tags))
+    .await?;
+
+  Ok(thread)
+}
@@ -30,7 +30,9 @@ async fn remove_star(ctx: &Context, database: &DatabaseHandler, reaction: &React
           .find(|r| r.reaction_type == ReactionType::Unicode(EMOTES.star.to_string()))
           .map_or(0, |r| r.count);
 
-        let starboard_channel = ChannelId::new(config::CHANNELS.starchannel);
+        let starboard_channel = star_message
+          .board_channel_id
+          .unwrap_or(ChannelId::new(config::CHANNELS.starchannel));
 
         if star_count >= config::MIN_STARS {
           // Get the starboard message
@@ -46,10 +48,22 @@ async fn remove_star(ctx: &Context, database: &DatabaseHandler, reaction: &React
           starboard_message
             .edit(ctx, EditMessage::new().embed(updated_embed))
             .await?;
+          DatabaseHandler::update_star_count(
+            &mut transaction,
+            &star_message.record_id,
+            i32::try_from(star_count).unwrap_or(i32::MAX),
+          )
+          .await?;
+          transaction.commit().await?;
         } else {
-          starboard_channel
-            .delete_message(&ctx, star_message.board_message_id)
-            .await?;
+          if star_message.is_forum_post() {
+            // Deleting the thread also deletes its starter message
+            starboard_channel.delete(&ctx).await?;
+          } else {
+            starboard_channel
+              .delete_message(&ctx, star_message.board_message_id)
+              .await?;
+          }
           DatabaseHandler::delete_star_message(&mut transaction, &star_message.record_id).await?;
           transaction.commit().await?;
         }
@@ -0,0 +1,71 @@
+use crate::database::DatabaseHandler;
+use crate::voice_tracker;
+use crate::Data;
+use anyhow::Result;
+use poise::serenity_prelude::{Context, VoiceState};
+
+/// Starts timing members when they join a tracked voice channel, and adds the time as a session when they leave.
+///
+/// Moving between channels ends the session in the old channel and, if the new channel is tracked, starts a new one.
+pub async fn voice_state_update(
+  ctx: &Context,
+  data: &Data,
+  new: &VoiceState,
+  trace_id: &str,
+) -> Result<()> {
+  let Some(guild_id) = new.guild_id else {
+    return Ok(());
+  };
+  let user_id = new.user_id;
+
+  if new.member.as_ref().is_some_and(|member| member.user.bot) {
+    return Ok(());
+  }
+
+  let current = data.voice_sessions.get(guild_id, user_id).await?;
+
+  // Muting, deafening, or streaming doesn't change the channel
+  if current.map(|session| session.channel_id) == new.channel_id {
+    return Ok(());
+  }
+
+  let now = chrono::Utc::now();
+
+  if let Some(session) = data
+    .voice_sessions
+    .end(&data.db, guild_id, user_id, now)
+    .await?
+  {
+    voice_tracker::credit_session(
+      ctx,
+      &data.db,
+      guild_id,
+      user_id,
+      session.channel_id,
+      session.counted_minutes(now),
+      now,
+      trace_id,
+    )
+    .await?;
+  }
+
+  let Some(channel_id) = new.channel_id else {
+    return Ok(());
+  };
+
+  let mut transaction = data.db.start_transaction_with_retry(5).await?;
+  let tracked = DatabaseHandler::get_tracking_voice_channels(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  if tracked
+    .iter()
+    .any(|tracked| tracked.channel_id == channel_id)
+  {
+    data
+      .voice_sessions
+      .start(&data.db, guild_id, user_id, channel_id, now)
+      .await?;
+  }
+
+  Ok(())
+}
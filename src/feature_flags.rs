@@ -0,0 +1,68 @@
+use crate::config::{FeatureFlag, FEATURE_FLAGS};
+use crate::database::DatabaseHandler;
+use crate::shared_state::SharedState;
+use anyhow::Result;
+use log::warn;
+use poise::serenity_prelude as serenity;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long a guild's flag is kept before it is reloaded from the database.
+const FLAG_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Looks up a flag in [`FEATURE_FLAGS`] by name.
+pub fn find(name: &str) -> Option<&'static FeatureFlag> {
+  FEATURE_FLAGS.iter().find(|flag| flag.name == name)
+}
+
+/// Checks whether features listed in [`FEATURE_FLAGS`] are on in a guild, so new subsystems can be rolled out one server at a time.
+///
+/// Flags are cached in [`SharedState`] for [`FLAG_TTL`], and should be invalidated whenever a guild turns one on or off.
+pub struct FeatureFlags {
+  db: DatabaseHandler,
+  state: Arc<dyn SharedState>,
+}
+
+impl FeatureFlags {
+  pub fn new(db: DatabaseHandler, state: Arc<dyn SharedState>) -> Self {
+    Self { db, state }
+  }
+
+  fn key(guild_id: serenity::GuildId, name: &str) -> String {
+    format!("flag:{guild_id}:{name}")
+  }
+
+  /// Whether the feature is on in the guild, falling back to the flag's default. Flags that aren't in [`FEATURE_FLAGS`] are always off.
+  pub async fn enabled(&self, guild_id: serenity::GuildId, name: &str) -> Result<bool> {
+    let Some(flag) = find(name) else {
+      warn!("Checked unknown feature flag {name}");
+      return Ok(false);
+    };
+
+    if let Some(cached) = self.state.get(&Self::key(guild_id, name)).await? {
+      return Ok(cached == "on");
+    }
+
+    let mut transaction = self.db.start_transaction_with_retry(5).await?;
+    let enabled = DatabaseHandler::get_feature_flag(&mut transaction, &guild_id, name)
+      .await?
+      .unwrap_or(flag.default);
+    drop(transaction);
+
+    self
+      .state
+      .set(
+        &Self::key(guild_id, name),
+        if enabled { "on" } else { "off" }.to_string(),
+        Some(FLAG_TTL),
+      )
+      .await?;
+
+    Ok(enabled)
+  }
+
+  pub async fn invalidate(&self, guild_id: serenity::GuildId, name: &str) -> Result<()> {
+    self.state.remove(&Self::key(guild_id, name)).await?;
+    Ok(())
+  }
+}
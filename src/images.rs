@@ -0,0 +1,351 @@
+#![allow(
+  clippy::cast_possible_truncation,
+  clippy::cast_possible_wrap,
+  clippy::cast_sign_loss
+)]
+
+use anyhow::{Context, Result};
+use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use std::path::PathBuf;
+use tempfile::NamedTempFile;
+
+const CARD_WIDTH: u32 = 800;
+const CARD_HEIGHT: u32 = 450;
+const CARD_MARGIN: u32 = 60;
+const AVATAR_SIZE: u32 = 160;
+
+#[derive(Debug, Clone, Copy, Default, poise::ChoiceParameter)]
+pub enum QuoteCardBackground {
+  #[default]
+  #[name = "sunrise"]
+  Sunrise,
+  #[name = "forest"]
+  Forest,
+  #[name = "ocean"]
+  Ocean,
+  #[name = "dusk"]
+  Dusk,
+  #[name = "stone"]
+  Stone,
+}
+
+impl QuoteCardBackground {
+  /// Top and bottom colors of the background gradient.
+  fn gradient(self) -> (RGBColor, RGBColor) {
+    match self {
+      Self::Sunrise => (RGBColor(253, 172, 46), RGBColor(214, 80, 60)),
+      Self::Forest => (RGBColor(76, 130, 88), RGBColor(28, 58, 40)),
+      Self::Ocean => (RGBColor(64, 146, 196), RGBColor(22, 52, 98)),
+      Self::Dusk => (RGBColor(124, 88, 170), RGBColor(38, 30, 72)),
+      Self::Stone => (RGBColor(110, 110, 118), RGBColor(42, 42, 48)),
+    }
+  }
+}
+
+pub struct QuoteCard {
+  file: NamedTempFile,
+}
+
+pub struct CongratsCard {
+  file: NamedTempFile,
+}
+
+pub struct CongratsCardDrawer {
+  file: NamedTempFile,
+}
+
+pub struct QuoteCardDrawer {
+  file: NamedTempFile,
+}
+
+/// Splits text into lines of at most `max_chars` characters, breaking on whitespace where possible.
+fn wrap_text(text: &str, max_chars: usize) -> Vec<String> {
+  let mut lines = Vec::new();
+  let mut current = String::new();
+
+  for word in text.split_whitespace() {
+    if !current.is_empty() && current.chars().count() + word.chars().count() + 1 > max_chars {
+      lines.push(std::mem::take(&mut current));
+    }
+    if !current.is_empty() {
+      current.push(' ');
+    }
+    current.push_str(word);
+  }
+
+  if !current.is_empty() {
+    lines.push(current);
+  }
+
+  lines
+}
+
+fn blend(top: RGBColor, bottom: RGBColor, ratio: f64) -> RGBColor {
+  let channel = |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * ratio) as u8;
+
+  RGBColor(
+    channel(top.0, bottom.0),
+    channel(top.1, bottom.1),
+    channel(top.2, bottom.2),
+  )
+}
+
+fn draw_gradient(
+  root: &DrawingArea<BitMapBackend<'_>, plotters::coord::Shift>,
+  top: RGBColor,
+  bottom: RGBColor,
+) -> Result<()> {
+  for y in 0..CARD_HEIGHT {
+    let color = blend(top, bottom, f64::from(y) / f64::from(CARD_HEIGHT));
+    root.draw(&PathElement::new(
+      vec![(0, y as i32), (CARD_WIDTH as i32, y as i32)],
+      color,
+    ))?;
+  }
+
+  Ok(())
+}
+
+impl QuoteCardDrawer {
+  pub fn new() -> Result<Self> {
+    let file = tempfile::Builder::new()
+      .prefix("quote")
+      .suffix(".png")
+      .tempfile()?;
+
+    Ok(Self { file })
+  }
+
+  pub fn draw(
+    self,
+    quote: &str,
+    author: &str,
+    background: QuoteCardBackground,
+  ) -> Result<QuoteCard> {
+    let path = self.file.path().to_path_buf();
+
+    let root = BitMapBackend::new(&path, (CARD_WIDTH, CARD_HEIGHT)).into_drawing_area();
+
+    let (top, bottom) = background.gradient();
+    draw_gradient(&root, top, bottom)?;
+
+    // Longer quotes get a smaller font so that they still fit on the card
+    let font_size = match quote.chars().count() {
+      0..=100 => 36,
+      101..=200 => 30,
+      _ => 24,
+    };
+    let max_chars = ((CARD_WIDTH - CARD_MARGIN * 2) / (font_size / 2)) as usize;
+    let lines = wrap_text(quote, max_chars);
+    let line_height = (font_size * 3 / 2) as i32;
+
+    let quote_style = ("serif", font_size, FontStyle::Italic)
+      .into_font()
+      .color(&WHITE)
+      .pos(Pos::new(HPos::Center, VPos::Center));
+
+    let center_x = (CARD_WIDTH / 2) as i32;
+    let text_height = line_height * lines.len() as i32;
+    let first_line_y = (CARD_HEIGHT as i32 - text_height) / 2 + line_height / 2 - 20;
+
+    for (i, line) in lines.iter().enumerate() {
+      let open_quote = if i == 0 { "“" } else { "" };
+      let close_quote = if i == lines.len() - 1 { "”" } else { "" };
+
+      root.draw(&Text::new(
+        format!("{open_quote}{line}{close_quote}"),
+        (center_x, first_line_y + line_height * i as i32),
+        quote_style.clone(),
+      ))?;
+    }
+
+    let author_style = ("sans-serif", 24)
+      .into_font()
+      .color(&WHITE.mix(0.85))
+      .pos(Pos::new(HPos::Right, VPos::Bottom));
+
+    root.draw(&Text::new(
+      format!("― {author}"),
+      (
+        (CARD_WIDTH - CARD_MARGIN) as i32,
+        (CARD_HEIGHT - CARD_MARGIN / 2) as i32,
+      ),
+      author_style,
+    ))?;
+
+    root
+      .present()
+      .with_context(|| "Could not present quote card")?;
+
+    Ok(QuoteCard { file: self.file })
+  }
+}
+
+impl CongratsCardDrawer {
+  pub fn new() -> Result<Self> {
+    let file = tempfile::Builder::new()
+      .prefix("congrats")
+      .suffix(".png")
+      .tempfile()?;
+
+    Ok(Self { file })
+  }
+
+  /// Draws a card celebrating a new role, with the member's avatar, a badge in the role's color, and the milestone reached.
+  ///
+  /// If the avatar can't be decoded, the member's initial is drawn in its place.
+  pub fn draw(
+    self,
+    avatar: Option<&[u8]>,
+    name: &str,
+    role_name: &str,
+    role_color: (u8, u8, u8),
+    milestone: &str,
+  ) -> Result<CongratsCard> {
+    let path = self.file.path().to_path_buf();
+
+    let root = BitMapBackend::new(&path, (CARD_WIDTH, CARD_HEIGHT)).into_drawing_area();
+
+    let (top, bottom) = QuoteCardBackground::Dusk.gradient();
+    draw_gradient(&root, top, bottom)?;
+
+    let center_x = (CARD_WIDTH / 2) as i32;
+    let avatar_top = CARD_MARGIN / 2;
+    let avatar_left = CARD_WIDTH / 2 - AVATAR_SIZE / 2;
+    let radius = (AVATAR_SIZE / 2) as i32;
+    let avatar_center = (center_x, (avatar_top + AVATAR_SIZE / 2) as i32);
+
+    root.draw(&Circle::new(avatar_center, radius + 6, WHITE.filled()))?;
+
+    match avatar.and_then(|bytes| image::load_from_memory(bytes).ok()) {
+      Some(avatar) => {
+        let mut avatar = avatar
+          .resize_exact(
+            AVATAR_SIZE,
+            AVATAR_SIZE,
+            image::imageops::FilterType::Triangle,
+          )
+          .to_rgb8();
+
+        // Bitmaps can't be clipped, so pixels outside the circle are painted with the background instead
+        for (x, y, pixel) in avatar.enumerate_pixels_mut() {
+          let dx = x as i32 - radius;
+          let dy = y as i32 - radius;
+          if dx * dx + dy * dy > (radius + 6) * (radius + 6) {
+            let color = blend(
+              top,
+              bottom,
+              f64::from(avatar_top + y) / f64::from(CARD_HEIGHT),
+            );
+            *pixel = image::Rgb([color.0, color.1, color.2]);
+          } else if dx * dx + dy * dy > radius * radius {
+            *pixel = image::Rgb([255, 255, 255]);
+          }
+        }
+
+        root.draw(&BitMapElement::from((
+          (avatar_left as i32, avatar_top as i32),
+          image::DynamicImage::ImageRgb8(avatar),
+        )))?;
+      }
+      None => {
+        let initial = name
+          .chars()
+          .next()
+          .map(|initial| initial.to_uppercase().to_string())
+          .unwrap_or_default();
+
+        root.draw(&Circle::new(avatar_center, radius, top.filled()))?;
+        root.draw(&Text::new(
+          initial,
+          avatar_center,
+          ("sans-serif", 72)
+            .into_font()
+            .color(&WHITE)
+            .pos(Pos::new(HPos::Center, VPos::Center)),
+        ))?;
+      }
+    }
+
+    let title_style = ("sans-serif", 40, FontStyle::Bold)
+      .into_font()
+      .color(&WHITE)
+      .pos(Pos::new(HPos::Center, VPos::Center));
+
+    root.draw(&Text::new(
+      format!("Congratulations, {name}!"),
+      (center_x, (avatar_top + AVATAR_SIZE + 50) as i32),
+      title_style,
+    ))?;
+
+    // The badge is sized to the role name, so short and long names both fit snugly
+    let badge_y = (avatar_top + AVATAR_SIZE + 115) as i32;
+    let badge_half_width =
+      (role_name.chars().count() as i32 * 15 + 40).min(CARD_WIDTH as i32 / 2 - 20);
+    let (r, g, b) = role_color;
+    let badge_color = if (r, g, b) == (0, 0, 0) {
+      WHITE.mix(0.25).to_rgba()
+    } else {
+      RGBColor(r, g, b).to_rgba()
+    };
+
+    root.draw(&Rectangle::new(
+      [
+        (center_x - badge_half_width, badge_y - 26),
+        (center_x + badge_half_width, badge_y + 26),
+      ],
+      badge_color.filled(),
+    ))?;
+    root.draw(&Text::new(
+      role_name.to_string(),
+      (center_x, badge_y),
+      ("sans-serif", 30, FontStyle::Bold)
+        .into_font()
+        .color(&WHITE)
+        .pos(Pos::new(HPos::Center, VPos::Center)),
+    ))?;
+
+    root.draw(&Text::new(
+      milestone.to_string(),
+      (center_x, (CARD_HEIGHT - CARD_MARGIN / 2 - 20) as i32),
+      ("sans-serif", 26)
+        .into_font()
+        .color(&WHITE.mix(0.85))
+        .pos(Pos::new(HPos::Center, VPos::Center)),
+    ))?;
+
+    root
+      .present()
+      .with_context(|| "Could not present congratulations card")?;
+
+    Ok(CongratsCard { file: self.file })
+  }
+}
+
+impl CongratsCard {
+  pub fn get_file_path(&self) -> PathBuf {
+    self.file.path().to_path_buf()
+  }
+}
+
+impl QuoteCard {
+  pub fn get_file_path(&self) -> PathBuf {
+    self.file.path().to_path_buf()
+  }
+
+  pub fn get_file_name(&self) -> String {
+    self
+      .file
+      .path()
+      .file_name()
+      .unwrap()
+      .to_str()
+      .unwrap()
+      .to_string()
+  }
+
+  pub fn get_attachment_url(&self) -> String {
+    format!("attachment://{}", self.get_file_name())
+  }
+}
@@ -0,0 +1,283 @@
+use crate::avatar_cache::AvatarCache;
+use crate::charts::{self, ChartDrawer, LeaderboardBar};
+use crate::config::{BloomBotEmbed, LeaderboardPeriod};
+use crate::database::{DatabaseHandler, LeaderboardRollover};
+use crate::feature_flags::FeatureFlags;
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Months, Utc};
+use log::{error, info};
+use poise::serenity_prelude::{self as serenity, CreateAttachment, CreateMessage};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often leaderboard rollovers are checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Number of members shown on a final leaderboard.
+const ROLLOVER_SIZE: i64 = 10;
+/// Number of top members congratulated by name.
+const CONGRATULATED: usize = 3;
+
+/// Start of the same period a year before the one starting at `period_start`. For weeks, this is the Monday 52 weeks earlier.
+pub fn same_period_last_year(
+  period: LeaderboardPeriod,
+  period_start: DateTime<Utc>,
+) -> DateTime<Utc> {
+  match period {
+    LeaderboardPeriod::Weekly => period_start - ChronoDuration::weeks(52),
+    LeaderboardPeriod::Monthly => period_start
+      .checked_sub_months(Months::new(12))
+      .unwrap_or(period_start),
+  }
+}
+
+/// Start and end of the most recent period that has fully ended, in UTC. Weeks start on Monday.
+pub fn completed_period(
+  period: LeaderboardPeriod,
+  now: DateTime<Utc>,
+) -> (DateTime<Utc>, DateTime<Utc>) {
+  let today = now.date_naive();
+
+  let (start, end) = match period {
+    LeaderboardPeriod::Weekly => {
+      let this_week =
+        today - ChronoDuration::days(i64::from(today.weekday().num_days_from_monday()));
+      (this_week - ChronoDuration::weeks(1), this_week)
+    }
+    LeaderboardPeriod::Monthly => {
+      let this_month = today.with_day(1).unwrap_or(today);
+      let last_month = this_month
+        .checked_sub_months(Months::new(1))
+        .unwrap_or(this_month);
+      (last_month, this_month)
+    }
+  };
+
+  (
+    start.and_time(chrono::NaiveTime::MIN).and_utc(),
+    end.and_time(chrono::NaiveTime::MIN).and_utc(),
+  )
+}
+
+/// Saves the final leaderboard of every guild once a week or month ends, then posts and pins it in each guild's chosen channel.
+///
+/// Runs until the bot shuts down.
+pub async fn post_leaderboard_rollovers(
+  http: Arc<serenity::Http>,
+  db: DatabaseHandler,
+  flags: Arc<FeatureFlags>,
+) {
+  let mut interval = tokio::time::interval(CHECK_INTERVAL);
+  let avatars = AvatarCache::new();
+  // Start of the last period saved for each of `LeaderboardPeriod::ALL`, so each period is only saved once
+  let mut archived: [Option<DateTime<Utc>>; 2] = [None; 2];
+
+  loop {
+    interval.tick().await;
+
+    // Rollovers are posted from the saved leaderboards, so they wait until saving succeeds
+    if let Err(e) = archive_completed_periods(&db, &mut archived).await {
+      error!("Error while archiving leaderboards: {e:?}");
+      continue;
+    }
+
+    if let Err(e) = post_due_rollovers(&http, &db, &flags, &avatars).await {
+      error!("Error while posting leaderboard rollovers: {e:?}");
+    }
+  }
+}
+
+async fn archive_completed_periods(
+  db: &DatabaseHandler,
+  archived: &mut [Option<DateTime<Utc>>; 2],
+) -> Result<()> {
+  let now = Utc::now();
+
+  for (period, archived_start) in LeaderboardPeriod::ALL.into_iter().zip(archived.iter_mut()) {
+    let (start, end) = completed_period(period, now);
+    if archived_start.is_some_and(|archived_start| archived_start >= start) {
+      continue;
+    }
+
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    let saved = DatabaseHandler::archive_leaderboards(&mut transaction, period, start, end).await?;
+    DatabaseHandler::commit_transaction(transaction).await?;
+
+    if saved > 0 {
+      info!(
+        "Archived {} leaderboard starting {start} with {saved} members",
+        period.key()
+      );
+    }
+    *archived_start = Some(start);
+  }
+
+  Ok(())
+}
+
+async fn post_due_rollovers(
+  http: &serenity::Http,
+  db: &DatabaseHandler,
+  flags: &FeatureFlags,
+  avatars: &AvatarCache,
+) -> Result<()> {
+  let now = Utc::now();
+
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let rollovers = DatabaseHandler::get_leaderboard_rollovers(&mut transaction, None).await?;
+  drop(transaction);
+
+  for rollover in rollovers {
+    let (start, end) = completed_period(rollover.period, now);
+    if rollover.last_period_start >= start {
+      continue;
+    }
+
+    if let Err(e) = post_rollover(http, db, flags, avatars, &rollover, start, end).await {
+      error!(
+        "Could not post {} leaderboard rollover in {}: {e:?}",
+        rollover.period.key(),
+        rollover.guild_id
+      );
+    }
+  }
+
+  Ok(())
+}
+
+async fn post_rollover(
+  http: &serenity::Http,
+  db: &DatabaseHandler,
+  flags: &FeatureFlags,
+  avatars: &AvatarCache,
+  rollover: &LeaderboardRollover,
+  start: DateTime<Utc>,
+  end: DateTime<Utc>,
+) -> Result<()> {
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let users = DatabaseHandler::get_archived_leaderboard(
+    &mut transaction,
+    &rollover.guild_id,
+    rollover.period,
+    start,
+    ROLLOVER_SIZE,
+  )
+  .await?;
+  let palette = DatabaseHandler::get_chart_palette(&mut transaction, &rollover.guild_id).await?;
+  drop(transaction);
+
+  // Nothing is posted for a period without sessions, but it is still marked so it isn't checked again
+  if users.is_empty() {
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    DatabaseHandler::mark_leaderboard_rollover_posted(&mut transaction, &rollover.id, start, None)
+      .await?;
+    DatabaseHandler::commit_transaction(transaction).await?;
+    return Ok(());
+  }
+
+  let (title, period_name) = match rollover.period {
+    LeaderboardPeriod::Weekly => (
+      format!(
+        "Weekly Leaderboard: {} – {}",
+        start.format("%B %-d"),
+        (end - ChronoDuration::days(1)).format("%B %-d")
+      ),
+      "last week",
+    ),
+    LeaderboardPeriod::Monthly => (
+      format!("Monthly Leaderboard: {}", start.format("%B %Y")),
+      "last month",
+    ),
+  };
+
+  let show_avatars = flags
+    .enabled(rollover.guild_id, "leaderboard_avatars")
+    .await?;
+
+  let mut bars = Vec::with_capacity(users.len());
+  for user in &users {
+    // Members who have left are still shown, just without their name and avatar
+    let (name, avatar) = match rollover.guild_id.member(http, user.user_id).await {
+      Ok(member) if show_avatars => (
+        member.display_name().to_string(),
+        avatars.get(&member.user).await,
+      ),
+      Ok(member) => (member.display_name().to_string(), None),
+      Err(_) => (user.user_id.to_string(), None),
+    };
+    #[allow(clippy::cast_precision_loss)]
+    bars.push(LeaderboardBar {
+      name,
+      value: user.minutes as f64,
+      avatar,
+    });
+  }
+
+  let chart = ChartDrawer::new()?
+    .draw_leaderboard("Minutes", &bars, charts::bar_color(palette, false), false)
+    .await?;
+
+  let ranking = users
+    .iter()
+    .map(|user| {
+      format!(
+        "**{}.** <@{}> — {} minutes, {} sessions",
+        user.rank, user.user_id, user.minutes, user.sessions
+      )
+    })
+    .collect::<Vec<String>>()
+    .join("\n");
+
+  let winners = users
+    .iter()
+    .take(CONGRATULATED)
+    .map(|user| format!("<@{}>", user.user_id))
+    .collect::<Vec<String>>();
+  let winners = match winners.split_last() {
+    Some((last, rest)) if !rest.is_empty() => format!("{} and {last}", rest.join(", ")),
+    _ => winners.join(""),
+  };
+
+  let message = rollover
+    .channel_id
+    .send_message(
+      http,
+      CreateMessage::new()
+        .content(format!(
+          ":trophy: Congratulations to {winners} for leading the server in meditation {period_name}!"
+        ))
+        .embed(
+          BloomBotEmbed::new()
+            .title(title)
+            .description(ranking)
+            .image(chart.get_attachment_url()),
+        )
+        .add_file(CreateAttachment::path(chart.get_file_path()).await?),
+    )
+    .await?;
+
+  // Saved before pinning, so the leaderboard isn't posted again if pinning fails
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::mark_leaderboard_rollover_posted(
+    &mut transaction,
+    &rollover.id,
+    start,
+    Some(&message.id),
+  )
+  .await?;
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  if let Some(previous) = rollover.pinned_message_id {
+    if let Err(e) = rollover.channel_id.unpin(http, previous).await {
+      info!(
+        "Could not unpin previous leaderboard in {}: {e}",
+        rollover.guild_id
+      );
+    }
+  }
+
+  if let Err(e) = message.pin(http).await {
+    error!("Could not pin leaderboard in {}: {e}", rollover.guild_id);
+  }
+
+  Ok(())
+}
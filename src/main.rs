@@ -7,48 +7,141 @@
 
 use anyhow::{Context as ErrorContext, Error, Result};
 use commands::{
-  add::add, challenge::challenge, coffee::coffee, complete::complete, courses::course,
-  customize::customize, erase::erase, glossary::glossary, hello::hello, help::help, keys::keys,
-  manage::manage, pick_winner::pick_winner, ping::ping, quote::quote, quotes::quotes,
-  recent::recent, remove_entry::remove_entry, report_message::report_message, stats::stats,
-  streak::streak, suggest::suggest, terms::terms, whatis::whatis,
+  add::add, add_quote::add_quote, calendar::calendar, challenge::challenge, checkin::checkin,
+  coffee::coffee, community_sit::community_sit, complete::complete, courses::course,
+  customize::customize, erase::erase, glossary::glossary, hello::hello, help::help, import::import,
+  journal::journal, keys::keys, manage::manage, owner::owner, pick_winner::pick_winner, ping::ping,
+  quote::quote, quotes::quotes, recent::recent, remove_entry::remove_entry,
+  report_message::report_message, sit::sit, stats::stats, status::status, streak::streak,
+  suggest::suggest, terms::terms, whatis::whatis,
 };
 use dotenvy::dotenv;
-use log::{error, info};
+use log::{debug, error, info};
 use poise::serenity_prelude::{self as serenity, model::channel};
 use rand::rngs::SmallRng;
 use rand::SeedableRng;
 use serenity::FullEvent as Event;
+use songbird::SerenityInit;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::Mutex;
+use ulid::Ulid;
 
+mod add_timings;
+mod announcements;
+mod anomaly_detector;
+mod api;
+mod auto_thread_cache;
+mod avatar_cache;
+mod bells;
+mod calendar;
+mod challenge_reminders;
+mod chart_cache;
 mod charts;
+mod checkins;
+mod collective_goals;
+mod command_sync;
 mod commands;
 mod config;
+mod course_reminders;
 mod database;
 mod embeddings;
+mod emoji_cache;
 mod events;
+mod feature_flags;
+mod images;
+mod leaderboard_rollovers;
+mod milestones;
+mod notifications;
 mod pagination;
+mod pool_monitor;
+mod prefix_cache;
+mod prize_tracker;
+mod reason_cache;
+mod retention;
+mod shared_state;
+mod starboard;
+mod stats_access;
+mod status;
+mod streaks;
+mod term_cache;
+mod thread_manager;
+mod voice_tracker;
+mod webhooks;
 
 pub struct Data {
   pub db: database::DatabaseHandler,
   pub rng: Arc<Mutex<SmallRng>>,
   pub embeddings: Arc<embeddings::OpenAIHandler>,
+  pub term_names: Arc<term_cache::TermNameCache>,
+  pub moderation_reasons: Arc<reason_cache::ReasonCache>,
+  pub emojis: Arc<emoji_cache::EmojiCache>,
+  pub prefixes: Arc<prefix_cache::PrefixCache>,
+  pub auto_threads: Arc<auto_thread_cache::AutoThreadCache>,
+  pub voice_sessions: Arc<voice_tracker::VoiceSessions>,
+  pub bells: Arc<bells::BellRinger>,
+  pub started_at: Instant,
+  /// Shared with the HTTP API, so it turns away writes too.
+  pub maintenance: Arc<AtomicBool>,
+  pub state: Arc<dyn shared_state::SharedState>,
+  pub flags: Arc<feature_flags::FeatureFlags>,
 }
 pub type Context<'a> = poise::Context<'a, Data, Error>;
 
+/// Per-invocation data, set when a command starts.
+struct Invocation {
+  started_at: Instant,
+  /// Identifies the invocation in logs and in error messages shown to users.
+  trace_id: String,
+}
+
+/// Environment variables for tuning log levels of a group of targets independently of `RUST_LOG`.
+const LOG_TARGETS: [(&str, &[&str]); 4] = [
+  (
+    "LOG_LEVEL_DB",
+    &["sqlx", "bloombot::database", "bloombot::pool_monitor"],
+  ),
+  ("LOG_LEVEL_DISCORD", &["serenity", "poise"]),
+  (
+    "LOG_LEVEL_CHARTS",
+    &["bloombot::charts", "bloombot::images"],
+  ),
+  ("LOG_LEVEL_ADD", &["bloombot::add_timings"]),
+];
+
+fn init_logger() {
+  let mut logger = pretty_env_logger::formatted_builder();
+  logger.parse_filters(&std::env::var("RUST_LOG").unwrap_or("info".to_string()));
+
+  for (variable, targets) in LOG_TARGETS {
+    if let Ok(level) = std::env::var(variable) {
+      for target in targets {
+        logger.parse_filters(&format!("{target}={level}"));
+      }
+    }
+  }
+
+  logger.init();
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
   dotenv().ok();
 
-  pretty_env_logger::init();
+  init_logger();
 
   let token =
     std::env::var("DISCORD_TOKEN").with_context(|| "Missing DISCORD_TOKEN environment variable")?;
   let test_guild = std::env::var("TEST_GUILD_ID");
 
-  let intents =
-    serenity::GatewayIntents::non_privileged() | serenity::GatewayIntents::GUILD_MEMBERS;
+  let intents = serenity::GatewayIntents::non_privileged()
+    | serenity::GatewayIntents::GUILD_MEMBERS
+    | serenity::GatewayIntents::MESSAGE_CONTENT;
+
+  // Keep recent messages cached so edits can be compared against the previous version
+  let mut cache_settings = serenity::cache::Settings::default();
+  cache_settings.max_messages = 100;
 
   let framework = poise::Framework::builder()
     .options(poise::FrameworkOptions {
@@ -58,11 +151,16 @@ async fn main() -> Result<()> {
         pick_winner(),
         erase(),
         manage(),
+        owner(),
+        community_sit(),
         quotes(),
         terms(),
         challenge(),
+        checkin(),
+        calendar(),
         customize(),
         add(),
+        sit(),
         recent(),
         remove_entry(),
         stats(),
@@ -72,14 +170,57 @@ async fn main() -> Result<()> {
         quote(),
         coffee(),
         hello(),
+        journal(),
+        import(),
         help(),
         ping(),
+        status(),
         suggest(),
         complete(),
         report_message(),
+        add_quote(),
       ],
+      // Text commands are opt-in, so only guilds that set a prefix with `/manage prefix` get them
+      prefix_options: poise::PrefixFrameworkOptions {
+        dynamic_prefix: Some(|ctx| Box::pin(dynamic_prefix(ctx))),
+        mention_as_prefix: false,
+        ..Default::default()
+      },
+      command_check: Some(|ctx| Box::pin(command_check(ctx))),
+      pre_command: |ctx| {
+        Box::pin(async move {
+          let trace_id = Ulid::new().to_string();
+          debug!(
+            "[{trace_id}] /{} invoked by {} ({})",
+            ctx.command().qualified_name,
+            ctx.author().name,
+            ctx.author().id
+          );
+
+          ctx
+            .set_invocation_data(Invocation {
+              started_at: Instant::now(),
+              trace_id,
+            })
+            .await;
+        })
+      },
+      post_command: |ctx| {
+        Box::pin(async move {
+          record_command_usage(ctx, true).await;
+        })
+      },
       event_handler: |ctx, event, _framework, data| {
-        Box::pin(event_handler(ctx, event, data))
+        Box::pin(async move {
+          let trace_id = Ulid::new().to_string();
+          if let Err(e) = event_handler(ctx, event, data, &trace_id).await {
+            error!(
+              "[{trace_id}] Error while handling {} event: {e:?}",
+              event.snake_case_name()
+            );
+          }
+          Ok(())
+        })
       },
       on_error: |error| {
         Box::pin(async move {
@@ -90,11 +231,17 @@ async fn main() -> Result<()> {
     })
     .setup(|ctx, _ready, framework| {
       Box::pin(async move {
+        let db = database::DatabaseHandler::new().await?;
+        let state = shared_state::connect().await?;
+        let flags = Arc::new(feature_flags::FeatureFlags::new(db.clone(), state.clone()));
+
         if let Ok(test_guild) = test_guild {
           info!("Registering commands in test guild {test_guild}");
 
           let guild_id = serenity::GuildId::new(test_guild.parse::<u64>()?);
-          poise::builtins::register_in_guild(ctx, &framework.options().commands, guild_id).await?;
+          let commands =
+            command_sync::guild_commands(&db, &framework.options().commands, &guild_id).await?;
+          guild_id.set_commands(ctx, commands).await?;
 
           info!("Setting default activity text");
           ctx.set_activity(Some(serenity::ActivityData::custom(
@@ -109,10 +256,114 @@ async fn main() -> Result<()> {
             "Tracking your meditations",
           )));
         }
+
+        info!("Starting database pool monitor");
+        tokio::spawn(pool_monitor::monitor_pool(ctx.http.clone(), db.clone()));
+
+        info!("Starting notification thread manager");
+        tokio::spawn(thread_manager::manage_notification_threads(
+          ctx.http.clone(),
+          db.clone(),
+        ));
+
+        info!("Starting queued notification delivery");
+        tokio::spawn(notifications::deliver_queued_notifications(
+          ctx.http.clone(),
+          db.clone(),
+        ));
+
+        info!("Starting voice session heartbeats");
+        tokio::spawn(voice_tracker::record_heartbeats(db.clone()));
+
+        info!("Starting tracking anomaly detection");
+        tokio::spawn(anomaly_detector::detect_anomalies(
+          ctx.http.clone(),
+          db.clone(),
+        ));
+
+        info!("Starting prize award tracking");
+        tokio::spawn(prize_tracker::track_prize_awards(
+          ctx.http.clone(),
+          db.clone(),
+        ));
+
+        info!("Starting course reminders");
+        tokio::spawn(course_reminders::send_course_reminders(
+          ctx.http.clone(),
+          db.clone(),
+        ));
+
+        info!("Starting challenge reminders");
+        tokio::spawn(challenge_reminders::send_challenge_reminders(
+          ctx.http.clone(),
+          db.clone(),
+        ));
+
+        info!("Starting collective goal tracking");
+        tokio::spawn(collective_goals::track_collective_goals(
+          ctx.http.clone(),
+          db.clone(),
+        ));
+
+        info!("Starting weekly check-in prompts");
+        tokio::spawn(checkins::post_checkin_prompts(ctx.http.clone(), db.clone()));
+
+        info!("Starting leaderboard rollovers");
+        tokio::spawn(leaderboard_rollovers::post_leaderboard_rollovers(
+          ctx.http.clone(),
+          db.clone(),
+          flags.clone(),
+        ));
+
+        info!("Starting scheduled announcements");
+        tokio::spawn(announcements::post_announcements(
+          ctx.http.clone(),
+          db.clone(),
+        ));
+
+        info!("Starting starboard reconciliation");
+        tokio::spawn(starboard::reconcile_starboard(ctx.http.clone(), db.clone()));
+
+        info!("Starting data retention purge");
+        tokio::spawn(retention::purge_expired_data(db.clone()));
+
+        info!("Starting chart cache cleanup");
+        tokio::spawn(chart_cache::clean_chart_cache());
+
+        let maintenance = Arc::new(AtomicBool::new(false));
+
+        // The HTTP API is optional, since it needs a publicly reachable address
+        if let Ok(address) = std::env::var("API_BIND_ADDRESS") {
+          info!("Starting HTTP API");
+          tokio::spawn(api::serve(
+            address,
+            ctx.http.clone(),
+            db.clone(),
+            maintenance.clone(),
+          ));
+        }
+
+        info!("Starting activity status rotation");
+        tokio::spawn(status::rotate_status(
+          framework.shard_manager().clone(),
+          db.clone(),
+        ));
+
         Ok(Data {
-          db: database::DatabaseHandler::new().await?,
+          db,
           rng: Arc::new(Mutex::new(SmallRng::from_entropy())),
           embeddings: Arc::new(embeddings::OpenAIHandler::new()?),
+          term_names: Arc::new(term_cache::TermNameCache::new(state.clone())),
+          moderation_reasons: Arc::new(reason_cache::ReasonCache::new()),
+          emojis: Arc::new(emoji_cache::EmojiCache::new()),
+          prefixes: Arc::new(prefix_cache::PrefixCache::new(state.clone())),
+          auto_threads: Arc::new(auto_thread_cache::AutoThreadCache::new()),
+          voice_sessions: Arc::new(voice_tracker::VoiceSessions::new(state.clone())),
+          bells: Arc::new(bells::BellRinger::new()),
+          started_at: Instant::now(),
+          maintenance,
+          state,
+          flags,
         })
       })
     })
@@ -120,6 +371,8 @@ async fn main() -> Result<()> {
 
   let mut client = serenity::Client::builder(&token, intents)
     .framework(framework)
+    .cache_settings(cache_settings)
+    .register_songbird()
     .await
     .map_err(|e| anyhow::anyhow!(e))?;
 
@@ -127,13 +380,175 @@ async fn main() -> Result<()> {
     .map_err(|e| anyhow::anyhow!("Error starting client: {e}"))
 }
 
+/// Looks up the text command prefix for the guild a message was sent in.
+///
+/// Only commands marked as prefix commands can be used this way. Direct messages never have a prefix.
+async fn dynamic_prefix(ctx: poise::PartialContext<'_, Data, Error>) -> Result<Option<String>> {
+  let Some(guild_id) = ctx.guild_id else {
+    return Ok(None);
+  };
+
+  ctx.data.prefixes.get(&ctx.data.db, &guild_id).await
+}
+
+/// Blocks write commands while maintenance mode is on, keeps text commands to tracking channels, and enforces role and channel restrictions configured with `/manage permissions`.
+///
+/// Restrictions on a parent command also apply to its subcommands. Administrators are never restricted, so they can always undo a restriction.
+async fn command_check(ctx: Context<'_>) -> Result<bool> {
+  if ctx.data().maintenance.load(Ordering::Relaxed) {
+    let command_name = &ctx.command().qualified_name;
+    let is_write_command = config::WRITE_COMMANDS.iter().any(|write_command| {
+      command_name == write_command || command_name.starts_with(&format!("{write_command} "))
+    });
+
+    if is_write_command {
+      let info = commands::emoji(ctx, config::EmojiKind::Info).await;
+      ctx
+        .send(
+          poise::CreateReply::default()
+            .content(format!("{info} Bloom is in maintenance mode, so changes can't be saved right now. Please try again later."))
+            .ephemeral(true),
+        )
+        .await?;
+      return Ok(false);
+    }
+  }
+
+  let Some(guild_id) = ctx.guild_id() else {
+    return Ok(true);
+  };
+
+  // Text commands can't reply privately, so they're kept to tracking channels, where sessions are posted anyway
+  if let poise::Context::Prefix(_) = ctx {
+    let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+    let is_tracking_channel = database::DatabaseHandler::is_tracking_channel(
+      &mut transaction,
+      &guild_id,
+      &ctx.channel_id(),
+    )
+    .await?;
+    drop(transaction);
+
+    if !is_tracking_channel {
+      ctx
+        .say(":x: Text commands can only be used in tracking channels.")
+        .await?;
+      return Ok(false);
+    }
+  }
+
+  let Some(member) = ctx.author_member().await else {
+    return Ok(true);
+  };
+
+  if member
+    .permissions
+    .is_some_and(serenity::Permissions::administrator)
+  {
+    return Ok(true);
+  }
+
+  // "terms add" can be restricted through either "terms" or "terms add"
+  let words: Vec<&str> = ctx.command().qualified_name.split(' ').collect();
+  let command_names: Vec<String> = (1..=words.len()).map(|i| words[..i].join(" ")).collect();
+
+  let mut transaction = ctx.data().db.start_transaction_with_retry(5).await?;
+  let restrictions = database::DatabaseHandler::get_command_restrictions(
+    &mut transaction,
+    &guild_id,
+    &command_names,
+  )
+  .await?;
+  drop(transaction);
+
+  let mut allowed_roles = restrictions.iter().filter_map(|r| r.role_id).peekable();
+  let role_allowed =
+    allowed_roles.peek().is_none() || allowed_roles.any(|role_id| member.roles.contains(&role_id));
+
+  let mut allowed_channels = restrictions.iter().filter_map(|r| r.channel_id).peekable();
+  let channel_allowed = allowed_channels.peek().is_none()
+    || allowed_channels.any(|channel_id| channel_id == ctx.channel_id());
+
+  if role_allowed && channel_allowed {
+    return Ok(true);
+  }
+
+  let content = if role_allowed {
+    ":x: This command can't be used in this channel."
+  } else {
+    ":x: You don't have a role that is allowed to use this command."
+  };
+  ctx
+    .send(
+      poise::CreateReply::default()
+        .content(content)
+        .ephemeral(true),
+    )
+    .await?;
+
+  Ok(false)
+}
+
+/// Gets the trace ID of the current invocation, if the command got far enough to be assigned one.
+async fn get_trace_id(ctx: Context<'_>) -> String {
+  match ctx.invocation_data::<Invocation>().await {
+    Some(invocation) => invocation.trace_id.clone(),
+    None => "unknown".to_string(),
+  }
+}
+
+async fn record_command_usage(ctx: Context<'_>, success: bool) {
+  let command_name = &ctx.command().qualified_name;
+  let (latency_ms, trace_id) = match ctx.invocation_data::<Invocation>().await {
+    Some(invocation) => (
+      i32::try_from(invocation.started_at.elapsed().as_millis()).unwrap_or(i32::MAX),
+      invocation.trace_id.clone(),
+    ),
+    None => (0, "unknown".to_string()),
+  };
+
+  let mut transaction = match ctx.data().db.start_transaction_with_retry(5).await {
+    Ok(transaction) => transaction,
+    Err(e) => {
+      error!("[{trace_id}] Could not record usage of /{command_name}: {e}");
+      return;
+    }
+  };
+
+  if let Err(e) = database::DatabaseHandler::add_command_usage(
+    &mut transaction,
+    command_name,
+    &ctx.author().id,
+    ctx.guild_id().as_ref(),
+    latency_ms,
+    success,
+  )
+  .await
+  {
+    error!("[{trace_id}] Could not record usage of /{command_name}: {e}");
+    return;
+  }
+
+  if let Err(e) = database::DatabaseHandler::commit_transaction(transaction).await {
+    error!("[{trace_id}] Could not record usage of /{command_name}: {e}");
+  }
+}
+
 async fn error_handler(error: poise::FrameworkError<'_, Data, Error>) {
   match error {
     poise::FrameworkError::Command { ctx, error, .. } => {
-      match ctx.say("An error occurred while running the command").await {
+      record_command_usage(ctx, false).await;
+      let trace_id = get_trace_id(ctx).await;
+
+      match ctx
+        .say(format!(
+          "An error occurred while running the command. If this keeps happening, please contact staff and mention trace ID `{trace_id}`."
+        ))
+        .await
+      {
         Ok(_) => {}
         Err(e) => {
-          error!("While handling error, could not send message: {e}");
+          error!("[{trace_id}] While handling error, could not send message: {e}");
         }
       };
 
@@ -142,7 +557,7 @@ async fn error_handler(error: poise::FrameworkError<'_, Data, Error>) {
       let channel = if let Ok(channel) = channel_id.to_channel(ctx).await {
         Some(channel)
       } else {
-        error!("While handling error, could not get channel {channel_id}");
+        error!("[{trace_id}] While handling error, could not get channel {channel_id}");
         None
       };
 
@@ -165,7 +580,7 @@ async fn error_handler(error: poise::FrameworkError<'_, Data, Error>) {
       let user = ctx.author();
 
       error!(
-        "\x1B[1m/{}\x1B[0m failed with error: {:?}",
+        "[{trace_id}] \x1B[1m/{}\x1B[0m failed with error: {:?}",
         command.name, error
       );
       error!("\tSource: {source}");
@@ -212,10 +627,18 @@ async fn event_handler(
   event: &Event,
   // _framework: poise::FrameworkContext<'_, Data, Error>,
   data: &Data,
+  trace_id: &str,
 ) -> Result<(), Error> {
   let database = &data.db;
 
   match event {
+    Event::GuildCreate { guild, is_new } => {
+      events::guild_create(ctx, database, guild, *is_new, trace_id).await?;
+      data
+        .voice_sessions
+        .reconcile(ctx, database, guild, trace_id)
+        .await?;
+    }
     // Event::GuildMemberAddition { new_member } => {
     //   events::guild_member_addition(ctx, new_member).await?;
     // }
@@ -227,14 +650,38 @@ async fn event_handler(
       new,
       ..
     } => {
-      events::guild_member_update(ctx, old_if_available, new).await?;
+      events::guild_member_update(ctx, data, old_if_available, new).await?;
+    }
+    Event::GuildScheduledEventUpdate { event } => {
+      events::guild_scheduled_event_update(ctx, data, event).await?;
+    }
+    Event::InteractionCreate { interaction } => {
+      events::interaction_create(ctx, database, interaction).await?;
+    }
+    Event::Message { new_message } => {
+      events::message(ctx, data, new_message).await?;
     }
     Event::MessageDelete {
-      channel_id: _,
+      channel_id,
       deleted_message_id,
-      guild_id: _,
+      guild_id,
+    } => {
+      events::message_delete(
+        ctx,
+        database,
+        channel_id,
+        deleted_message_id,
+        guild_id,
+        trace_id,
+      )
+      .await?;
+    }
+    Event::MessageUpdate {
+      old_if_available,
+      event,
+      ..
     } => {
-      events::message_delete(database, deleted_message_id).await?;
+      events::message_update(ctx, database, old_if_available, event).await?;
     }
     Event::ReactionAdd { add_reaction } => {
       events::reaction_add(ctx, database, add_reaction).await?;
@@ -242,6 +689,9 @@ async fn event_handler(
     Event::ReactionRemove { removed_reaction } => {
       events::reaction_remove(ctx, database, removed_reaction).await?;
     }
+    Event::VoiceStateUpdate { new, .. } => {
+      events::voice_state_update(ctx, data, new, trace_id).await?;
+    }
     Event::Ready { .. } => {
       info!("Connected!");
     }
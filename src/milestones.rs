@@ -0,0 +1,254 @@
+use crate::commands::helpers::template::render_template;
+use crate::config::{CongratsDelivery, StreakRoles, TimeSumRoles, DEFAULT_HOURS_MESSAGE};
+use crate::database::{HoursAnnouncement, TrackingProfile};
+use crate::images::{CongratsCard, CongratsCardDrawer};
+use anyhow::Result;
+use log::error;
+use poise::serenity_prelude::{self as serenity, Mentionable};
+use std::collections::HashMap;
+
+/// Minutes between the guild hour milestones sent to webhooks, which is every 10,000 hours.
+const WEBHOOK_MILESTONE_MINUTES: i64 = 600_000;
+
+/// Whether the guild's total hours are announced after the session that brought its session count to `session_count`.
+pub fn is_hours_announcement_due(announcement: &HoursAnnouncement, session_count: u64) -> bool {
+  u64::try_from(announcement.session_interval)
+    .is_ok_and(|interval| interval > 0 && session_count % interval == 0)
+}
+
+/// Fills in the guild's hours announcement with its totals.
+pub fn hours_announcement_message(
+  announcement: &HoursAnnouncement,
+  guild_minutes: i64,
+  session_count: u64,
+) -> String {
+  let values = HashMap::from([
+    ("hours".to_string(), (guild_minutes / 60).to_string()),
+    ("minutes".to_string(), guild_minutes.to_string()),
+    ("sessions".to_string(), session_count.to_string()),
+  ]);
+
+  // Messages are checked when set, so this only falls back if the variables have since changed
+  announcement
+    .message
+    .as_deref()
+    .and_then(|message| render_template(message, &values).ok())
+    .or_else(|| render_template(DEFAULT_HOURS_MESSAGE, &values).ok())
+    .unwrap_or_default()
+}
+
+/// The hours milestone the guild passed by adding `minutes`, if any, such as 10,000 or 20,000 hours.
+pub fn crossed_hours_milestone(guild_minutes: i64, minutes: i32) -> Option<i64> {
+  let milestone = guild_minutes / WEBHOOK_MILESTONE_MINUTES;
+  let previous = (guild_minutes - i64::from(minutes)) / WEBHOOK_MILESTONE_MINUTES;
+
+  (previous < milestone).then(|| milestone * WEBHOOK_MILESTONE_MINUTES / 60)
+}
+
+/// What a member reached to be given a new role.
+#[derive(Debug, Clone, Copy)]
+pub enum RoleMilestone {
+  /// Total meditation minutes.
+  Time(i64),
+  /// Current meditation streak, in days.
+  Streak(u64),
+}
+
+/// A time or streak role newly given to a member after an add.
+pub struct Promotion {
+  pub role_id: serenity::RoleId,
+  pub milestone: RoleMilestone,
+}
+
+impl Promotion {
+  /// Congratulations on the new role, mentioning the member.
+  pub fn message(&self, member: &serenity::Member) -> String {
+    match self.milestone {
+      RoleMilestone::Time(_) => format!(":tada: Congrats to {}, your hard work is paying off! Your total meditation minutes have given you the <@&{}> role!", member.mention(), self.role_id),
+      RoleMilestone::Streak(streak) => format!(":tada: Congrats to {}, your hard work is paying off! Your current streak is {streak}, giving you the <@&{}> role!", member.mention(), self.role_id),
+    }
+  }
+
+  /// Draws a congratulations card with the member's avatar, the new role, and the milestone they reached.
+  pub async fn draw_card(
+    &self,
+    guild: &serenity::Guild,
+    member: &serenity::Member,
+  ) -> Result<CongratsCard> {
+    // A missing avatar shouldn't cost the member their card, so the initial is drawn instead
+    let avatar = match reqwest::get(member.user.static_face()).await {
+      Ok(response) => response.bytes().await.ok(),
+      Err(err) => {
+        error!("Error fetching avatar for congratulations card: {err}");
+        None
+      }
+    };
+
+    let (role_name, role_color) = match guild.roles.get(&self.role_id) {
+      Some(role) => (
+        role.name.clone(),
+        (role.colour.r(), role.colour.g(), role.colour.b()),
+      ),
+      None => (String::from("New Role"), (0, 0, 0)),
+    };
+
+    let milestone = match self.milestone {
+      RoleMilestone::Time(minutes) => format!("{minutes} minutes of meditation"),
+      RoleMilestone::Streak(streak) => format!("{streak}-day meditation streak"),
+    };
+
+    CongratsCardDrawer::new()?.draw(
+      avatar.as_deref(),
+      member.display_name(),
+      &role_name,
+      role_color,
+      &milestone,
+    )
+  }
+}
+
+/// Decides where a member is congratulated on a new role, so slash and voice channel adds follow the same rules.
+///
+/// Congratulations the member wants public are sent privately instead when the add itself was private, or when the role would reveal a streak they keep private.
+pub fn congrats_delivery(
+  tracking_profile: &TrackingProfile,
+  private_add: bool,
+  milestone: RoleMilestone,
+) -> CongratsDelivery {
+  let reveals_private_streak =
+    matches!(milestone, RoleMilestone::Streak(_)) && tracking_profile.streaks_private;
+
+  match tracking_profile.congrats {
+    CongratsDelivery::Public if private_add || reveals_private_streak => CongratsDelivery::Private,
+    delivery => delivery,
+  }
+}
+
+/// A role a member has earned but doesn't have yet, and the lower roles it replaces.
+pub struct RoleChange {
+  pub role_id: serenity::RoleId,
+  pub replaced: Vec<serenity::RoleId>,
+  pub milestone: RoleMilestone,
+}
+
+/// Works out which time and streak roles the member should be given for `user_sum` and, when given, `user_streak`, without changing anything.
+pub fn role_changes(
+  guild: &serenity::Guild,
+  member: &serenity::Member,
+  user_sum: i64,
+  user_streak: Option<u64>,
+) -> Vec<RoleChange> {
+  let time_role = TimeSumRoles::from_sum(user_sum).map(|role| RoleChange {
+    role_id: role.to_role_id(),
+    replaced: TimeSumRoles::get_users_current_roles(guild, member),
+    milestone: RoleMilestone::Time(user_sum),
+  });
+  let streak_role = user_streak.and_then(|user_streak| {
+    StreakRoles::from_streak(user_streak).map(|role| RoleChange {
+      role_id: role.to_role_id(),
+      replaced: StreakRoles::get_users_current_roles(guild, member),
+      milestone: RoleMilestone::Streak(user_streak),
+    })
+  });
+
+  [time_role, streak_role]
+    .into_iter()
+    .flatten()
+    .filter(|change| !change.replaced.contains(&change.role_id))
+    .collect()
+}
+
+/// Gives the member the time role for `user_sum` and, when given, the streak role for `user_streak`, replacing the lower roles they had.
+///
+/// Returns the roles newly given. Updating stops at the first role that can't be changed, and the milestone it was for is returned too,
+/// so the member can be told their roles are out of date.
+pub async fn promote(
+  cache_http: impl serenity::CacheHttp + Copy,
+  guild: &serenity::Guild,
+  member: &serenity::Member,
+  user_sum: i64,
+  user_streak: Option<u64>,
+) -> (Vec<Promotion>, Option<RoleMilestone>) {
+  let mut promotions = Vec::new();
+
+  for change in role_changes(guild, member, user_sum, user_streak) {
+    for role in change.replaced {
+      if let Err(err) = member.remove_role(cache_http, role).await {
+        error!("Error removing role: {err}");
+        return (promotions, Some(change.milestone));
+      }
+    }
+
+    if let Err(err) = member.add_role(cache_http, change.role_id).await {
+      error!("Error adding role: {err}");
+      return (promotions, Some(change.milestone));
+    }
+
+    promotions.push(Promotion {
+      role_id: change.role_id,
+      milestone: change.milestone,
+    });
+  }
+
+  (promotions, None)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn every(session_interval: i32) -> HoursAnnouncement {
+    HoursAnnouncement {
+      session_interval,
+      message: None,
+    }
+  }
+
+  #[test]
+  fn hours_announced_on_exact_interval() {
+    assert!(is_hours_announcement_due(&every(10), 10));
+    assert!(is_hours_announcement_due(&every(10), 20));
+    assert!(is_hours_announcement_due(&every(1), 7));
+  }
+
+  #[test]
+  fn hours_not_announced_between_intervals() {
+    assert!(!is_hours_announcement_due(&every(10), 9));
+    assert!(!is_hours_announcement_due(&every(10), 11));
+    assert!(!is_hours_announcement_due(&every(10), 19));
+  }
+
+  #[test]
+  fn hours_not_announced_when_turned_off() {
+    assert!(!is_hours_announcement_due(&every(0), 10));
+    assert!(!is_hours_announcement_due(&every(-10), 10));
+  }
+
+  #[test]
+  fn milestone_crossed() {
+    assert_eq!(crossed_hours_milestone(600_030, 60), Some(10_000));
+    assert_eq!(crossed_hours_milestone(1_200_001, 2), Some(20_000));
+  }
+
+  #[test]
+  fn milestone_hit_exactly() {
+    assert_eq!(crossed_hours_milestone(600_000, 1), Some(10_000));
+    assert_eq!(crossed_hours_milestone(600_000, 600_000), Some(10_000));
+  }
+
+  #[test]
+  fn milestone_not_crossed() {
+    // Reaching a milestone counts once, so starting on one doesn't count again
+    assert_eq!(crossed_hours_milestone(600_030, 30), None);
+    assert_eq!(crossed_hours_milestone(599_999, 60), None);
+    assert_eq!(crossed_hours_milestone(1_000, 1_000), None);
+    assert_eq!(crossed_hours_milestone(600_000, 0), None);
+  }
+
+  #[test]
+  fn multiple_milestones_crossed_at_once() {
+    // Only the highest milestone passed is announced
+    assert_eq!(crossed_hours_milestone(1_250_000, 700_000), Some(20_000));
+    assert_eq!(crossed_hours_milestone(1_800_000, 1_800_000), Some(30_000));
+  }
+}
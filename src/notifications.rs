@@ -0,0 +1,400 @@
+use crate::config::{BloomBotEmbed, CHANNELS};
+use crate::database::{DatabaseHandler, QuietHours};
+use anyhow::Result;
+use log::{error, info};
+use poise::serenity_prelude::{
+  self as serenity, AutoArchiveDuration, CreateAllowedMentions, CreateEmbedFooter, CreateMessage,
+  CreateThread, EditThread,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often queued notifications are checked for delivery.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 5);
+const MINUTES_PER_DAY: i64 = 24 * 60;
+/// Name given to private notification threads created for members with closed DMs.
+pub const NOTIFICATION_THREAD_NAME: &str = "Private Notification Thread";
+
+/// A notification for a single user.
+#[derive(Default)]
+pub struct Notification<'a> {
+  pub title: &'a str,
+  pub description: &'a str,
+  pub footer: Option<&'a str>,
+  /// Embed fields, as name and value pairs.
+  pub fields: Vec<(String, String)>,
+  /// Footer used instead of `footer` when the notification is posted in a notification thread.
+  pub thread_footer: Option<&'a str>,
+}
+
+impl Notification<'_> {
+  fn to_embed(&self, footer: Option<&str>) -> serenity::CreateEmbed {
+    let mut embed = BloomBotEmbed::new()
+      .title(self.title)
+      .description(self.description);
+
+    for (name, value) in &self.fields {
+      embed = embed.field(name, value, false);
+    }
+
+    if let Some(footer) = footer {
+      embed = embed.footer(CreateEmbedFooter::new(footer));
+    }
+
+    embed
+  }
+
+  fn to_message(&self) -> CreateMessage {
+    CreateMessage::new().embed(self.to_embed(self.footer))
+  }
+
+  fn to_thread_message(&self, user_id: serenity::UserId) -> CreateMessage {
+    CreateMessage::new()
+      .content(format!("Private notification for <@{user_id}>:"))
+      .embed(self.to_embed(self.thread_footer.or(self.footer)))
+      .allowed_mentions(CreateAllowedMentions::new().users([user_id]))
+  }
+}
+
+/// How a notification should reach the user.
+#[derive(Debug, Clone, Copy)]
+pub enum Delivery {
+  /// By DM, held back during the user's quiet hours. Dropped if the user has closed their DMs.
+  Dm,
+  /// By DM right away, falling back to the user's private notification thread in the guild.
+  ///
+  /// An existing thread is reused when there is one. Otherwise a new one is created in `channel_id`, or in the lounge if that isn't a text channel.
+  DmOrThread {
+    guild_id: serenity::GuildId,
+    channel_id: serenity::ChannelId,
+  },
+}
+
+/// Where a notification ended up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivered {
+  Dm,
+  /// Held back until the user's quiet hours are over.
+  Queued,
+  Thread(serenity::ChannelId),
+  /// The user couldn't be reached.
+  Nowhere,
+}
+
+impl QuietHours {
+  /// Returns how many minutes remain until quiet hours end, or `None` if they aren't in effect right now.
+  fn minutes_remaining(&self, now: chrono::DateTime<chrono::Utc>) -> Option<i64> {
+    let local = now + chrono::Duration::minutes(i64::from(self.utc_offset));
+    let minute = i64::from(chrono::Timelike::num_seconds_from_midnight(&local) / 60);
+    let start = i64::from(self.start_minute);
+    let end = i64::from(self.end_minute);
+
+    let quiet = if start <= end {
+      minute >= start && minute < end
+    } else {
+      minute >= start || minute < end
+    };
+
+    quiet.then(|| (end - minute).rem_euclid(MINUTES_PER_DAY))
+  }
+}
+
+/// Sends a notification to a user according to the delivery policy.
+///
+/// Delivery is best effort: [`Delivered::Nowhere`] means the user couldn't be reached, while errors are reserved for database and thread failures.
+pub async fn deliver(
+  http: &serenity::Http,
+  db: &DatabaseHandler,
+  user_id: serenity::UserId,
+  delivery: Delivery,
+  notification: &Notification<'_>,
+) -> Result<Delivered> {
+  match delivery {
+    Delivery::Dm => {
+      if queue_for_quiet_hours(db, user_id, notification).await? {
+        return Ok(Delivered::Queued);
+      }
+
+      if send(http, user_id, notification).await {
+        Ok(Delivered::Dm)
+      } else {
+        Ok(Delivered::Nowhere)
+      }
+    }
+    Delivery::DmOrThread {
+      guild_id,
+      channel_id,
+    } => {
+      if send(http, user_id, notification).await {
+        return Ok(Delivered::Dm);
+      }
+
+      let thread = notification_thread(http, db, guild_id, channel_id, user_id).await?;
+      thread
+        .send_message(http, notification.to_thread_message(user_id))
+        .await?;
+
+      Ok(Delivered::Thread(thread.id))
+    }
+  }
+}
+
+/// Queues the notification if the user is in their quiet hours, returning whether it was queued.
+async fn queue_for_quiet_hours(
+  db: &DatabaseHandler,
+  user_id: serenity::UserId,
+  notification: &Notification<'_>,
+) -> Result<bool> {
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let now = chrono::Utc::now();
+
+  let Some(remaining) = DatabaseHandler::get_quiet_hours(&mut transaction, &user_id)
+    .await?
+    .and_then(|quiet_hours| quiet_hours.minutes_remaining(now))
+  else {
+    return Ok(false);
+  };
+
+  DatabaseHandler::queue_notification(
+    &mut transaction,
+    &user_id,
+    notification.title,
+    notification.description,
+    notification.footer,
+    &notification.fields,
+    now + chrono::Duration::minutes(remaining),
+  )
+  .await?;
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  Ok(true)
+}
+
+async fn send(
+  http: &serenity::Http,
+  user_id: serenity::UserId,
+  notification: &Notification<'_>,
+) -> bool {
+  match user_id.create_dm_channel(http).await {
+    Ok(dm_channel) => dm_channel
+      .send_message(http, notification.to_message())
+      .await
+      .is_ok(),
+    Err(_) => false,
+  }
+}
+
+/// Returns the user's notification thread in the guild, reopening it if it was archived, or creates one if they have none.
+async fn notification_thread(
+  http: &serenity::Http,
+  db: &DatabaseHandler,
+  guild_id: serenity::GuildId,
+  channel_id: serenity::ChannelId,
+  user_id: serenity::UserId,
+) -> Result<serenity::GuildChannel> {
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let threads =
+    DatabaseHandler::get_user_notification_threads(&mut transaction, &guild_id, &user_id).await?;
+  drop(transaction);
+
+  for thread_id in threads {
+    // Threads deleted by hand are left for the thread manager to clean up
+    let Some(thread) = thread_id
+      .to_channel(http)
+      .await
+      .ok()
+      .and_then(serenity::Channel::guild)
+    else {
+      continue;
+    };
+
+    if thread
+      .thread_metadata
+      .is_some_and(|metadata| metadata.archived)
+    {
+      return Ok(
+        thread_id
+          .edit_thread(
+            http,
+            EditThread::new()
+              .archived(false)
+              .auto_archive_duration(AutoArchiveDuration::OneWeek),
+          )
+          .await?,
+      );
+    }
+
+    return Ok(thread);
+  }
+
+  create_thread(http, db, guild_id, channel_id, user_id).await
+}
+
+/// Creates a private notification thread for the user and records it, so the thread manager can archive and delete it later.
+///
+/// The thread is created in `channel_id` if it's a text channel, and in the lounge otherwise. The user is added to the thread when they are mentioned in it. It auto-archives after a week, so it stays open until the thread manager's own inactivity check.
+pub async fn create_thread(
+  http: &serenity::Http,
+  db: &DatabaseHandler,
+  guild_id: serenity::GuildId,
+  channel_id: serenity::ChannelId,
+  user_id: serenity::UserId,
+) -> Result<serenity::GuildChannel> {
+  let is_text_channel = channel_id
+    .to_channel(http)
+    .await?
+    .guild()
+    .is_some_and(|channel| channel.kind == serenity::ChannelType::Text);
+
+  let thread_channel = if is_text_channel {
+    channel_id
+  } else {
+    // If not a text channel, then create private thread in lounge to avoid failure
+    serenity::ChannelId::new(CHANNELS.lounge)
+  };
+
+  let mut notification_thread = thread_channel
+    .create_thread(
+      http,
+      CreateThread::new(NOTIFICATION_THREAD_NAME)
+        .auto_archive_duration(AutoArchiveDuration::OneWeek),
+    )
+    .await?;
+
+  notification_thread
+    .edit_thread(http, EditThread::new().invitable(false).locked(true))
+    .await?;
+
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  DatabaseHandler::add_notification_thread(
+    &mut transaction,
+    &guild_id,
+    &user_id,
+    &notification_thread.id,
+  )
+  .await?;
+  transaction.commit().await?;
+
+  Ok(notification_thread)
+}
+
+/// Delivers notifications that were held back during quiet hours, once those hours are over.
+///
+/// Runs until the bot shuts down.
+pub async fn deliver_queued_notifications(http: Arc<serenity::Http>, db: DatabaseHandler) {
+  let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(e) = deliver_due(&http, &db).await {
+      error!("Error while delivering queued notifications: {e:?}");
+    }
+  }
+}
+
+async fn deliver_due(http: &serenity::Http, db: &DatabaseHandler) -> Result<()> {
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let queued = DatabaseHandler::get_due_notifications(&mut transaction).await?;
+
+  for queued in queued {
+    let notification = Notification {
+      title: &queued.title,
+      description: &queued.description,
+      footer: queued.footer.as_deref(),
+      fields: queued.fields,
+      thread_footer: None,
+    };
+
+    if !send(http, queued.user_id, &notification).await {
+      info!(
+        "Could not deliver queued notification to {}",
+        queued.user_id
+      );
+    }
+
+    // Removed either way, so a user with closed DMs isn't retried forever
+    DatabaseHandler::remove_queued_notification(&mut transaction, &queued.id).await?;
+  }
+
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn quiet_hours(start: (i16, i16), end: (i16, i16), utc_offset: i16) -> QuietHours {
+    QuietHours {
+      start_minute: start.0 * 60 + start.1,
+      end_minute: end.0 * 60 + end.1,
+      utc_offset,
+    }
+  }
+
+  fn utc(hour: u32, minute: u32) -> chrono::DateTime<chrono::Utc> {
+    chrono::NaiveDate::from_ymd_opt(2024, 1, 1)
+      .unwrap()
+      .and_hms_opt(hour, minute, 0)
+      .unwrap()
+      .and_utc()
+  }
+
+  #[test]
+  fn within_same_day_window() {
+    let quiet = quiet_hours((13, 0), (17, 0), 0);
+    assert_eq!(quiet.minutes_remaining(utc(13, 0)), Some(240));
+    assert_eq!(quiet.minutes_remaining(utc(14, 30)), Some(150));
+    assert_eq!(quiet.minutes_remaining(utc(16, 59)), Some(1));
+  }
+
+  #[test]
+  fn outside_same_day_window() {
+    let quiet = quiet_hours((13, 0), (17, 0), 0);
+    assert_eq!(quiet.minutes_remaining(utc(12, 59)), None);
+    // Quiet hours are over once the end is reached
+    assert_eq!(quiet.minutes_remaining(utc(17, 0)), None);
+    assert_eq!(quiet.minutes_remaining(utc(23, 0)), None);
+  }
+
+  #[test]
+  fn within_window_past_midnight() {
+    let quiet = quiet_hours((22, 0), (7, 0), 0);
+    assert_eq!(quiet.minutes_remaining(utc(22, 0)), Some(540));
+    assert_eq!(quiet.minutes_remaining(utc(23, 0)), Some(480));
+    assert_eq!(quiet.minutes_remaining(utc(0, 0)), Some(420));
+    assert_eq!(quiet.minutes_remaining(utc(3, 0)), Some(240));
+  }
+
+  #[test]
+  fn outside_window_past_midnight() {
+    let quiet = quiet_hours((22, 0), (7, 0), 0);
+    assert_eq!(quiet.minutes_remaining(utc(7, 0)), None);
+    assert_eq!(quiet.minutes_remaining(utc(12, 0)), None);
+    assert_eq!(quiet.minutes_remaining(utc(21, 59)), None);
+  }
+
+  #[test]
+  fn uses_local_time() {
+    let behind = quiet_hours((22, 0), (7, 0), -300);
+    assert_eq!(behind.minutes_remaining(utc(4, 0)), Some(480));
+    assert_eq!(behind.minutes_remaining(utc(13, 0)), None);
+
+    let ahead = quiet_hours((22, 0), (7, 0), 540);
+    assert_eq!(ahead.minutes_remaining(utc(14, 0)), Some(480));
+    assert_eq!(ahead.minutes_remaining(utc(23, 0)), None);
+
+    // Local time is already the next day
+    let next_day = quiet_hours((22, 0), (7, 0), 60);
+    assert_eq!(next_day.minutes_remaining(utc(23, 30)), Some(390));
+  }
+
+  #[test]
+  fn empty_window_is_never_quiet() {
+    let quiet = quiet_hours((10, 0), (10, 0), 0);
+    assert_eq!(quiet.minutes_remaining(utc(10, 0)), None);
+    assert_eq!(quiet.minutes_remaining(utc(3, 0)), None);
+  }
+}
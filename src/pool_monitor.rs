@@ -0,0 +1,57 @@
+use crate::config::{BloomBotEmbed, CHANNELS};
+use crate::database::DatabaseHandler;
+use log::{error, warn};
+use poise::serenity_prelude::{self as serenity, CreateMessage};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often pool usage is sampled.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// Share of the pool that must be in use before a warning is sent.
+const SATURATION_THRESHOLD: f64 = 0.9;
+/// Minimum time between saturation warnings, so a busy period doesn't flood the logs channel.
+const WARNING_COOLDOWN: Duration = Duration::from_secs(60 * 10);
+
+/// Watches database pool usage and warns the logs channel when the pool is close to exhausted.
+///
+/// Commands start failing with acquire timeouts once every connection is checked out, so the warning is sent while there is still headroom. Runs until the bot shuts down.
+pub async fn monitor_pool(http: Arc<serenity::Http>, db: DatabaseHandler) {
+  let mut interval = tokio::time::interval(CHECK_INTERVAL);
+  let mut last_warning: Option<Instant> = None;
+
+  loop {
+    interval.tick().await;
+
+    let stats = db.get_pool_stats();
+    let active = stats.active();
+    let utilization = f64::from(active) / f64::from(stats.max_connections.max(1));
+
+    if utilization < SATURATION_THRESHOLD {
+      continue;
+    }
+    if last_warning.is_some_and(|warned_at| warned_at.elapsed() < WARNING_COOLDOWN) {
+      continue;
+    }
+    last_warning = Some(Instant::now());
+
+    warn!(
+      "Database pool is nearly saturated: {active} of {} connections in use",
+      stats.max_connections
+    );
+
+    let embed = BloomBotEmbed::new()
+      .title("Database Pool Saturation")
+      .description(format!(
+        "**Active**: {active}\n**Idle**: {}\n**Max**: {}\n\nCommands may start timing out while waiting for a connection. Consider raising `DATABASE_MAX_CONNECTIONS` or looking for slow queries in the logs.",
+        stats.idle, stats.max_connections
+      ));
+
+    let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+    if let Err(e) = log_channel
+      .send_message(&http, CreateMessage::new().embed(embed))
+      .await
+    {
+      error!("Could not send pool saturation warning: {e}");
+    }
+  }
+}
@@ -0,0 +1,56 @@
+use crate::database::DatabaseHandler;
+use crate::shared_state::SharedState;
+use anyhow::Result;
+use poise::serenity_prelude as serenity;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long command prefixes are kept before they are reloaded from the database.
+const PREFIX_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Per-guild cache of text command prefixes, since the prefix is looked up for every message.
+///
+/// Entries are kept in [`SharedState`], with an empty string for guilds without a prefix. They expire after [`PREFIX_TTL`], and should be invalidated whenever the prefix is changed.
+pub struct PrefixCache {
+  state: Arc<dyn SharedState>,
+}
+
+impl PrefixCache {
+  pub fn new(state: Arc<dyn SharedState>) -> Self {
+    Self { state }
+  }
+
+  fn key(guild_id: &serenity::GuildId) -> String {
+    format!("prefix:{guild_id}")
+  }
+
+  pub async fn get(
+    &self,
+    db: &DatabaseHandler,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Option<String>> {
+    if let Some(cached) = self.state.get(&Self::key(guild_id)).await? {
+      return Ok((!cached.is_empty()).then_some(cached));
+    }
+
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    let prefix = DatabaseHandler::get_command_prefix(&mut transaction, guild_id).await?;
+    drop(transaction);
+
+    self
+      .state
+      .set(
+        &Self::key(guild_id),
+        prefix.clone().unwrap_or_default(),
+        Some(PREFIX_TTL),
+      )
+      .await?;
+
+    Ok(prefix)
+  }
+
+  pub async fn invalidate(&self, guild_id: &serenity::GuildId) -> Result<()> {
+    self.state.remove(&Self::key(guild_id)).await?;
+    Ok(())
+  }
+}
@@ -0,0 +1,103 @@
+use crate::config::{BloomBotEmbed, CHANNELS};
+use crate::database::DatabaseHandler;
+use crate::notifications::{self, Delivered, Delivery, Notification};
+use anyhow::Result;
+use log::{error, info};
+use poise::serenity_prelude::{self as serenity, CreateMessage};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often prize awards are checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 30);
+/// Hours after a prize is sent before the winner is reminded to claim it.
+const REMINDER_AFTER_HOURS: i64 = 12;
+/// Hours after a prize is sent before it expires. Slightly longer than the 24 hours the offer
+/// buttons listen for, so only offers orphaned by a restart are expired here.
+const EXPIRE_AFTER_HOURS: i64 = 25;
+
+/// Sends reminders for unclaimed challenge prizes and expires offers that were never answered.
+///
+/// Runs until the bot shuts down.
+pub async fn track_prize_awards(http: Arc<serenity::Http>, db: DatabaseHandler) {
+  let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(e) = send_reminders(&http, &db).await {
+      error!("Error while sending prize reminders: {e:?}");
+    }
+    if let Err(e) = expire_awards(&http, &db).await {
+      error!("Error while expiring prize awards: {e:?}");
+    }
+  }
+}
+
+async fn send_reminders(http: &serenity::Http, db: &DatabaseHandler) -> Result<()> {
+  let mut conn = db.get_connection_with_retry(5).await?;
+  let reminder_cutoff = chrono::Utc::now() - chrono::Duration::hours(REMINDER_AFTER_HOURS);
+  let awards = DatabaseHandler::get_pending_prize_awards(&mut conn, reminder_cutoff, true).await?;
+
+  for award in awards {
+    let offer_link = match (award.dm_channel_id, award.dm_message_id) {
+      (Some(channel_id), Some(message_id)) => {
+        format!(" [Go to your prize]({})", message_id.link(channel_id, None))
+      }
+      _ => String::new(),
+    };
+
+    let description = format!(
+      "You won the {} challenge! Press 'Redeem' on your prize message to claim your key, or 'Cancel' to leave it for someone else.{offer_link}",
+      award.challenge
+    );
+    let footer = format!(
+      "This offer expires {} hours after it was sent.",
+      EXPIRE_AFTER_HOURS - 1
+    );
+    let notification = Notification {
+      title: "Reminder: You have an unclaimed prize!",
+      description: &description,
+      footer: Some(&footer),
+      ..Default::default()
+    };
+
+    // Reminders are best effort, since the winner may have closed their DMs
+    if notifications::deliver(http, db, award.user_id, Delivery::Dm, &notification).await?
+      == Delivered::Nowhere
+    {
+      info!("Could not send prize reminder to {}", award.user_id);
+    }
+
+    DatabaseHandler::mark_prize_award_reminded(&mut conn, &award.id).await?;
+  }
+
+  Ok(())
+}
+
+async fn expire_awards(http: &serenity::Http, db: &DatabaseHandler) -> Result<()> {
+  let mut conn = db.get_connection_with_retry(5).await?;
+  let expiry_cutoff = chrono::Utc::now() - chrono::Duration::hours(EXPIRE_AFTER_HOURS);
+  let awards = DatabaseHandler::get_pending_prize_awards(&mut conn, expiry_cutoff, false).await?;
+
+  let log_channel = serenity::ChannelId::new(CHANNELS.logs);
+
+  for award in awards {
+    if !DatabaseHandler::resolve_prize_award(&mut conn, &award.id, "expired").await? {
+      continue;
+    }
+    DatabaseHandler::unreserve_key(&mut conn, &award.steam_key).await?;
+
+    let log_embed = BloomBotEmbed::new()
+      .title("**Key Offer Expired**")
+      .description(format!(
+        "Playne key offer for the {} challenge sent to <@{}> was never answered. Key has been returned to the pool.",
+        award.challenge, award.user_id
+      ));
+
+    log_channel
+      .send_message(http, CreateMessage::new().embed(log_embed))
+      .await?;
+  }
+
+  Ok(())
+}
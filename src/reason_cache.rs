@@ -0,0 +1,58 @@
+use crate::database::{DatabaseHandler, ModerationReason};
+use anyhow::Result;
+use poise::serenity_prelude as serenity;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// How long moderation reasons are kept before they are reloaded from the database.
+const REASONS_TTL: Duration = Duration::from_secs(60 * 60);
+
+struct CachedReasons {
+  loaded_at: Instant,
+  reasons: Vec<ModerationReason>,
+}
+
+/// Per-guild cache of moderation reason templates, used for autocompletion and erase reasons.
+///
+/// Entries expire after [`REASONS_TTL`], and should be invalidated whenever reasons are added, edited, or removed.
+#[derive(Default)]
+pub struct ReasonCache {
+  entries: RwLock<HashMap<serenity::GuildId, CachedReasons>>,
+}
+
+impl ReasonCache {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub async fn get(
+    &self,
+    db: &DatabaseHandler,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<ModerationReason>> {
+    if let Some(cached) = self.entries.read().await.get(guild_id) {
+      if cached.loaded_at.elapsed() < REASONS_TTL {
+        return Ok(cached.reasons.clone());
+      }
+    }
+
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    let reasons = DatabaseHandler::get_moderation_reasons(&mut transaction, guild_id).await?;
+    drop(transaction);
+
+    self.entries.write().await.insert(
+      *guild_id,
+      CachedReasons {
+        loaded_at: Instant::now(),
+        reasons: reasons.clone(),
+      },
+    );
+
+    Ok(reasons)
+  }
+
+  pub async fn invalidate(&self, guild_id: &serenity::GuildId) {
+    self.entries.write().await.remove(guild_id);
+  }
+}
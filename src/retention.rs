@@ -0,0 +1,91 @@
+use crate::database::DatabaseHandler;
+use anyhow::Result;
+use log::{error, info};
+use poise::ChoiceParameter;
+use std::time::Duration;
+
+/// How often expired records are purged.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// How long deleted meditation entries stay in the trash, where `/manage trash restore` can bring them back.
+pub const TRASH_RETENTION_DAYS: i64 = 30;
+
+/// Nightly purge of logged data that has passed a guild's retention period.
+///
+/// Only policies enabled with `/manage retention enable` are applied. Deleted meditation entries are
+/// emptied from the trash after [`TRASH_RETENTION_DAYS`] regardless of policy. Runs until the bot shuts down.
+pub async fn purge_expired_data(db: DatabaseHandler) {
+  let mut interval = tokio::time::interval(PURGE_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(e) = apply_retention_policies(&db).await {
+      error!("Error while applying retention policies: {e:?}");
+    }
+
+    if let Err(e) = empty_trash(&db).await {
+      error!("Error while emptying the meditation trash: {e:?}");
+    }
+  }
+}
+
+async fn apply_retention_policies(db: &DatabaseHandler) -> Result<()> {
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let policies = DatabaseHandler::get_enabled_retention_policies(&mut transaction).await?;
+  drop(transaction);
+
+  let now = chrono::Utc::now();
+
+  // Each policy is purged in its own transaction, so one failure doesn't hold back the rest
+  for policy in policies {
+    let cutoff = now - chrono::Duration::days(i64::from(policy.retention_days));
+
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    match DatabaseHandler::purge_expired_records(
+      &mut transaction,
+      &policy.guild_id,
+      policy.category,
+      cutoff,
+    )
+    .await
+    {
+      Ok(purged) => {
+        DatabaseHandler::commit_transaction(transaction).await?;
+        if purged > 0 {
+          info!(
+            "Purged {purged} {} records older than {} days in guild {}",
+            policy.category.name(),
+            policy.retention_days,
+            policy.guild_id
+          );
+        }
+      }
+      Err(e) => {
+        error!(
+          "Error purging {} in guild {}: {e:?}",
+          policy.category.name(),
+          policy.guild_id
+        );
+      }
+    }
+  }
+
+  Ok(())
+}
+
+async fn empty_trash(db: &DatabaseHandler) -> Result<()> {
+  let cutoff = chrono::Utc::now() - chrono::Duration::days(TRASH_RETENTION_DAYS);
+
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let purged = DatabaseHandler::purge_expired_trash(&mut transaction, cutoff).await?;
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  if purged > 0 {
+    info!(
+      "Purged {purged} meditation entries older than {TRASH_RETENTION_DAYS} days from the trash"
+    );
+  }
+
+  Ok(())
+}
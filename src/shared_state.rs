@@ -0,0 +1,298 @@
+use anyhow::Result;
+use futures::future::BoxFuture;
+use log::info;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Short-lived state that every bot process needs to agree on, such as voice sessions, cooldowns, and caches.
+///
+/// Kept in memory by default. When the bot is built with the `redis` feature and `REDIS_URL` is set, it is kept in Redis instead,
+/// so the bot can be split across several processes. Keys are namespaced by their callers, such as `voice:{guild_id}:{user_id}`.
+pub trait SharedState: Send + Sync {
+  fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<String>>>;
+
+  /// Sets `key`, removing it after `ttl` if given.
+  fn set<'a>(
+    &'a self,
+    key: &'a str,
+    value: String,
+    ttl: Option<Duration>,
+  ) -> BoxFuture<'a, Result<()>>;
+
+  /// Sets `key` for `ttl` unless it is already set, returning whether it was set.
+  fn set_if_absent<'a>(
+    &'a self,
+    key: &'a str,
+    value: String,
+    ttl: Duration,
+  ) -> BoxFuture<'a, Result<bool>>;
+
+  /// Removes `key`, returning its value if it was set.
+  fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<String>>>;
+
+  /// Every key starting with `prefix`, with its value.
+  fn scan<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Result<Vec<(String, String)>>>;
+}
+
+/// Connects to Redis if `REDIS_URL` is set and the bot was built with the `redis` feature, and otherwise keeps state in memory.
+pub async fn connect() -> Result<Arc<dyn SharedState>> {
+  #[cfg(feature = "redis")]
+  if let Ok(url) = std::env::var("REDIS_URL") {
+    info!("Keeping shared state in Redis");
+    return Ok(Arc::new(RedisState::connect(&url).await?));
+  }
+
+  info!("Keeping shared state in memory");
+  Ok(Arc::new(MemoryState::default()))
+}
+
+struct MemoryEntry {
+  value: String,
+  expires_at: Option<Instant>,
+}
+
+impl MemoryEntry {
+  fn is_live(&self) -> bool {
+    self
+      .expires_at
+      .map_or(true, |expires_at| expires_at > Instant::now())
+  }
+}
+
+/// State kept in this process only, for running the bot as a single process.
+///
+/// Expired entries are dropped when they are next looked at.
+#[derive(Default)]
+pub struct MemoryState {
+  entries: Mutex<HashMap<String, MemoryEntry>>,
+}
+
+impl SharedState for MemoryState {
+  fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<String>>> {
+    Box::pin(async move {
+      let mut entries = self.entries.lock().await;
+
+      match entries.get(key) {
+        Some(entry) if entry.is_live() => Ok(Some(entry.value.clone())),
+        Some(_) => {
+          entries.remove(key);
+          Ok(None)
+        }
+        None => Ok(None),
+      }
+    })
+  }
+
+  fn set<'a>(
+    &'a self,
+    key: &'a str,
+    value: String,
+    ttl: Option<Duration>,
+  ) -> BoxFuture<'a, Result<()>> {
+    Box::pin(async move {
+      self.entries.lock().await.insert(
+        key.to_string(),
+        MemoryEntry {
+          value,
+          expires_at: ttl.map(|ttl| Instant::now() + ttl),
+        },
+      );
+
+      Ok(())
+    })
+  }
+
+  fn set_if_absent<'a>(
+    &'a self,
+    key: &'a str,
+    value: String,
+    ttl: Duration,
+  ) -> BoxFuture<'a, Result<bool>> {
+    Box::pin(async move {
+      let mut entries = self.entries.lock().await;
+
+      if entries.get(key).is_some_and(MemoryEntry::is_live) {
+        return Ok(false);
+      }
+
+      entries.insert(
+        key.to_string(),
+        MemoryEntry {
+          value,
+          expires_at: Some(Instant::now() + ttl),
+        },
+      );
+
+      Ok(true)
+    })
+  }
+
+  fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<String>>> {
+    Box::pin(async move {
+      Ok(
+        self
+          .entries
+          .lock()
+          .await
+          .remove(key)
+          .filter(MemoryEntry::is_live)
+          .map(|entry| entry.value),
+      )
+    })
+  }
+
+  fn scan<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Result<Vec<(String, String)>>> {
+    Box::pin(async move {
+      let mut entries = self.entries.lock().await;
+      entries.retain(|_, entry| entry.is_live());
+
+      Ok(
+        entries
+          .iter()
+          .filter(|(key, _)| key.starts_with(prefix))
+          .map(|(key, entry)| (key.clone(), entry.value.clone()))
+          .collect(),
+      )
+    })
+  }
+}
+
+/// Prefix for every key the bot stores in Redis, so the database can be shared with other applications.
+#[cfg(feature = "redis")]
+const REDIS_KEY_PREFIX: &str = "bloombot:";
+
+/// State kept in Redis, shared by every process connected to the same server.
+#[cfg(feature = "redis")]
+pub struct RedisState {
+  connection: redis::aio::ConnectionManager,
+}
+
+#[cfg(feature = "redis")]
+impl RedisState {
+  pub async fn connect(url: &str) -> Result<Self> {
+    let client = redis::Client::open(url)?;
+    let connection = redis::aio::ConnectionManager::new(client).await?;
+
+    Ok(Self { connection })
+  }
+
+  fn key(key: &str) -> String {
+    format!("{REDIS_KEY_PREFIX}{key}")
+  }
+}
+
+#[cfg(feature = "redis")]
+impl SharedState for RedisState {
+  fn get<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<String>>> {
+    Box::pin(async move {
+      let mut connection = self.connection.clone();
+
+      Ok(
+        redis::cmd("GET")
+          .arg(Self::key(key))
+          .query_async(&mut connection)
+          .await?,
+      )
+    })
+  }
+
+  fn set<'a>(
+    &'a self,
+    key: &'a str,
+    value: String,
+    ttl: Option<Duration>,
+  ) -> BoxFuture<'a, Result<()>> {
+    Box::pin(async move {
+      let mut connection = self.connection.clone();
+      let mut command = redis::cmd("SET");
+      command.arg(Self::key(key)).arg(value);
+      if let Some(ttl) = ttl {
+        command.arg("PX").arg(u64::try_from(ttl.as_millis())?);
+      }
+
+      command.query_async::<_, ()>(&mut connection).await?;
+
+      Ok(())
+    })
+  }
+
+  fn set_if_absent<'a>(
+    &'a self,
+    key: &'a str,
+    value: String,
+    ttl: Duration,
+  ) -> BoxFuture<'a, Result<bool>> {
+    Box::pin(async move {
+      let mut connection = self.connection.clone();
+
+      // Replies OK when the key was set, and nil when it already existed
+      let reply: Option<String> = redis::cmd("SET")
+        .arg(Self::key(key))
+        .arg(value)
+        .arg("NX")
+        .arg("PX")
+        .arg(u64::try_from(ttl.as_millis())?)
+        .query_async(&mut connection)
+        .await?;
+
+      Ok(reply.is_some())
+    })
+  }
+
+  fn remove<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<Option<String>>> {
+    Box::pin(async move {
+      let mut connection = self.connection.clone();
+
+      Ok(
+        redis::cmd("GETDEL")
+          .arg(Self::key(key))
+          .query_async(&mut connection)
+          .await?,
+      )
+    })
+  }
+
+  fn scan<'a>(&'a self, prefix: &'a str) -> BoxFuture<'a, Result<Vec<(String, String)>>> {
+    Box::pin(async move {
+      let mut connection = self.connection.clone();
+
+      let keys: Vec<String> = {
+        let mut iter = redis::cmd("SCAN")
+          .cursor_arg(0)
+          .arg("MATCH")
+          .arg(format!("{}*", Self::key(prefix)))
+          .clone()
+          .iter_async::<String>(&mut connection)
+          .await?;
+
+        let mut keys = Vec::new();
+        while let Some(key) = iter.next_item().await {
+          keys.push(key);
+        }
+        keys
+      };
+
+      if keys.is_empty() {
+        return Ok(Vec::new());
+      }
+
+      // Keys can expire between the scan and the lookup, so missing values are skipped
+      let values: Vec<Option<String>> = redis::cmd("MGET")
+        .arg(&keys)
+        .query_async(&mut connection)
+        .await?;
+
+      Ok(
+        keys
+          .into_iter()
+          .zip(values)
+          .filter_map(|(key, value)| {
+            Some((key.strip_prefix(REDIS_KEY_PREFIX)?.to_string(), value?))
+          })
+          .collect(),
+      )
+    })
+  }
+}
@@ -0,0 +1,204 @@
+use crate::config::{StarboardCleanup, CHANNELS};
+use crate::database::{DatabaseHandler, StarMessage};
+use anyhow::Result;
+use log::{error, info};
+use poise::serenity_prelude::{self as serenity, CreateEmbed, EditMessage};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often starred messages are checked for deletions the bot missed.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Days before a starred message is checked again.
+const RECHECK_DAYS: i64 = 7;
+
+/// Most starred messages checked each time, to stay well within rate limits.
+const CHECK_BATCH_SIZE: i64 = 50;
+
+/// Whether a request failed because the channel or message no longer exists.
+fn is_not_found(error: &serenity::Error) -> bool {
+  matches!(
+    error,
+    serenity::Error::Http(serenity::HttpError::UnsuccessfulRequest(response))
+      if response.status_code.as_u16() == 404
+  )
+}
+
+/// Gets what happens to starboard posts when a starred message in the guild is deleted. Defaults to removing the post.
+pub async fn cleanup_for(
+  transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  guild_id: Option<&serenity::GuildId>,
+) -> Result<StarboardCleanup> {
+  let deleted_action = match guild_id {
+    Some(guild_id) => DatabaseHandler::get_starboard_deleted_action(transaction, guild_id).await?,
+    None => None,
+  };
+
+  Ok(
+    deleted_action
+      .and_then(|key| StarboardCleanup::from_key(&key))
+      .unwrap_or(StarboardCleanup::Remove),
+  )
+}
+
+/// Removes or marks the starboard post for a starred message that was deleted, along with its record.
+///
+/// Marked posts keep their record, so their stars still count on the star leaderboard. When the post itself is already gone, only the record is removed.
+pub async fn clean_up_deleted(
+  http: &serenity::Http,
+  transaction: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+  star_message: &StarMessage,
+  cleanup: StarboardCleanup,
+) -> Result<()> {
+  let board_channel = star_message
+    .board_channel_id
+    .unwrap_or(serenity::ChannelId::new(CHANNELS.starchannel));
+
+  match cleanup {
+    StarboardCleanup::Remove => {
+      // Deleting a forum thread also deletes its starter message
+      let result = if star_message.is_forum_post() {
+        board_channel.delete(http).await.map(|_| ())
+      } else {
+        board_channel
+          .delete_message(http, star_message.board_message_id)
+          .await
+      };
+
+      if let Err(e) = result {
+        if !is_not_found(&e) {
+          return Err(e.into());
+        }
+      }
+    }
+    StarboardCleanup::Mark => {
+      match board_channel
+        .message(http, star_message.board_message_id)
+        .await
+      {
+        Ok(mut board_message) => {
+          if let Some(embed) = board_message.embeds.first() {
+            // The link to the original message no longer goes anywhere
+            let mut embed = embed.clone();
+            embed.fields.retain(|field| field.name != "Link");
+            let updated_embed =
+              CreateEmbed::from(embed).field("Link", "*The original message was deleted.*", false);
+
+            board_message
+              .edit(http, EditMessage::new().embed(updated_embed))
+              .await?;
+          }
+
+          DatabaseHandler::mark_star_source_deleted(transaction, &star_message.record_id).await?;
+          return Ok(());
+        }
+        Err(e) if is_not_found(&e) => {}
+        Err(e) => return Err(e.into()),
+      }
+    }
+    StarboardCleanup::Keep => {}
+  }
+
+  DatabaseHandler::delete_star_message(transaction, &star_message.record_id).await?;
+
+  Ok(())
+}
+
+/// Periodically checks that starred messages and their starboard posts still exist, cleaning up after deletions the bot missed, such as while it was offline.
+///
+/// Runs until the bot shuts down.
+pub async fn reconcile_starboard(http: Arc<serenity::Http>, db: DatabaseHandler) {
+  let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(e) = reconcile_star_messages(&http, &db).await {
+      error!("Error while reconciling the starboard: {e:?}");
+    }
+  }
+}
+
+async fn reconcile_star_messages(http: &serenity::Http, db: &DatabaseHandler) -> Result<()> {
+  let checked_before = chrono::Utc::now() - chrono::Duration::days(RECHECK_DAYS);
+
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let star_messages = DatabaseHandler::get_unchecked_star_messages(
+    &mut transaction,
+    checked_before,
+    CHECK_BATCH_SIZE,
+  )
+  .await?;
+  drop(transaction);
+
+  for star_message in star_messages {
+    let source = star_message
+      .starred_channel_id
+      .message(http, star_message.starred_message_id)
+      .await;
+
+    // Messages that can't be checked are still marked, so they don't hold up the rest
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+
+    match source {
+      Ok(_) => {
+        let board_channel = star_message
+          .board_channel_id
+          .unwrap_or(serenity::ChannelId::new(CHANNELS.starchannel));
+
+        match board_channel
+          .message(http, star_message.board_message_id)
+          .await
+        {
+          Ok(_) => {
+            DatabaseHandler::mark_star_message_checked(&mut transaction, &star_message.record_id)
+              .await?;
+          }
+          // Removed from the starboard by hand, so the message can be posted again if starred
+          Err(e) if is_not_found(&e) => {
+            info!(
+              "Removing record for starboard post {} that no longer exists",
+              star_message.board_message_id
+            );
+            DatabaseHandler::delete_star_message(&mut transaction, &star_message.record_id).await?;
+          }
+          Err(e) => {
+            error!(
+              "Could not check starboard post {}: {e:?}",
+              star_message.board_message_id
+            );
+            DatabaseHandler::mark_star_message_checked(&mut transaction, &star_message.record_id)
+              .await?;
+          }
+        }
+      }
+      Err(e) if is_not_found(&e) => {
+        info!(
+          "Cleaning up starboard post for deleted message {}",
+          star_message.starred_message_id
+        );
+        let cleanup = cleanup_for(&mut transaction, star_message.guild_id.as_ref()).await?;
+        if let Err(e) = clean_up_deleted(http, &mut transaction, &star_message, cleanup).await {
+          error!(
+            "Could not clean up starboard post {}: {e:?}",
+            star_message.board_message_id
+          );
+          DatabaseHandler::mark_star_message_checked(&mut transaction, &star_message.record_id)
+            .await?;
+        }
+      }
+      Err(e) => {
+        error!(
+          "Could not check starred message {}: {e:?}",
+          star_message.starred_message_id
+        );
+        DatabaseHandler::mark_star_message_checked(&mut transaction, &star_message.record_id)
+          .await?;
+      }
+    }
+
+    transaction.commit().await?;
+  }
+
+  Ok(())
+}
@@ -0,0 +1,40 @@
+use crate::config::BloomBotEmbed;
+use poise::serenity_prelude::{self as serenity, CreateActionRow, CreateButton, CreateMessage};
+
+/// Start of the custom ID of the approve button on access requests, followed by the request ID.
+pub const APPROVE_BUTTON_PREFIX: &str = "stats_access_approve:";
+/// Start of the custom ID of the deny button on access requests, followed by the request ID.
+pub const DENY_BUTTON_PREFIX: &str = "stats_access_deny:";
+
+/// Hours an approved request lets the staff member view the member's stats.
+pub const GRANT_HOURS: i64 = 24;
+/// Days a member has to answer a request before it can no longer be approved.
+pub const REQUEST_EXPIRY_DAYS: i64 = 7;
+
+/// Builds the DM asking a member to let a staff member view their private stats, with buttons to approve or deny.
+///
+/// The buttons carry the request ID, so they keep working after a restart.
+pub fn request_message(
+  guild_name: &str,
+  staff: &serenity::User,
+  reason: &str,
+  request_id: &str,
+) -> CreateMessage {
+  let approve = CreateButton::new(format!("{APPROVE_BUTTON_PREFIX}{request_id}"))
+    .label("Approve")
+    .style(serenity::ButtonStyle::Success);
+  let deny = CreateButton::new(format!("{DENY_BUTTON_PREFIX}{request_id}"))
+    .label("Deny")
+    .style(serenity::ButtonStyle::Danger);
+
+  CreateMessage::new()
+    .embed(
+      BloomBotEmbed::new()
+        .title("Request to View Your Stats")
+        .description(format!(
+          "{} ({}), a staff member in **{guild_name}**, has asked to see your private meditation stats.\n\n**Reason**: {reason}\n\nIf you approve, they can view your stats with `/stats user` for the next {GRANT_HOURS} hours. Each view is recorded in the server's access log. This request expires in {REQUEST_EXPIRY_DAYS} days.",
+          staff.name, staff.id
+        )),
+    )
+    .components(vec![CreateActionRow::Buttons(vec![approve, deny])])
+}
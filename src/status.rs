@@ -0,0 +1,83 @@
+use crate::config::{StatusMessage, STATUS_ROTATION, STATUS_ROTATION_INTERVAL};
+use crate::database::DatabaseHandler;
+use anyhow::Result;
+use log::error;
+use poise::serenity_prelude::{self as serenity, ActivityData};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Shown when a status message could not be built.
+const DEFAULT_STATUS: &str = "Tracking your meditations";
+/// Custom statuses are limited to 128 characters, so quotes are cut short well before that.
+const MAX_QUOTE_SNIPPET_LENGTH: usize = 100;
+
+/// Shortens a quote to fit in the activity status, cutting at a word boundary where possible.
+fn quote_snippet(quote: &str) -> String {
+  if quote.chars().count() <= MAX_QUOTE_SNIPPET_LENGTH {
+    return format!("“{quote}”");
+  }
+
+  let truncated: String = quote.chars().take(MAX_QUOTE_SNIPPET_LENGTH).collect();
+  let snippet = match truncated.rfind(' ') {
+    Some(index) => &truncated[..index],
+    None => truncated.as_str(),
+  };
+
+  format!(
+    "“{}…”",
+    snippet.trim_end_matches(|c: char| c.is_ascii_punctuation())
+  )
+}
+
+async fn build_status(db: &DatabaseHandler, message: StatusMessage) -> Result<Option<String>> {
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+
+  let status = match message {
+    StatusMessage::HoursMeditated => {
+      let minutes = DatabaseHandler::get_total_meditation_sum(&mut transaction).await?;
+      Some(format!("{} hours meditated together", minutes / 60))
+    }
+    StatusMessage::MeditatorCount => {
+      let meditators = DatabaseHandler::get_meditator_count(&mut transaction).await?;
+      Some(format!("Tracking {meditators} meditators"))
+    }
+    StatusMessage::QuoteSnippet => DatabaseHandler::get_random_quote_any_guild(&mut transaction)
+      .await?
+      .map(|quote| quote_snippet(&quote.quote)),
+  };
+
+  Ok(status)
+}
+
+async fn set_activity(shard_manager: &serenity::ShardManager, text: &str) {
+  for runner in shard_manager.runners.lock().await.values() {
+    runner
+      .runner_tx
+      .set_activity(Some(ActivityData::custom(text)));
+  }
+}
+
+/// Rotates the bot's activity status between the messages in [`STATUS_ROTATION`].
+///
+/// Each message is rebuilt from the database when it comes up, so the numbers stay current. Runs until the bot shuts down.
+pub async fn rotate_status(shard_manager: Arc<serenity::ShardManager>, db: DatabaseHandler) {
+  if STATUS_ROTATION.is_empty() {
+    return;
+  }
+
+  let mut interval = tokio::time::interval(Duration::from_secs(STATUS_ROTATION_INTERVAL));
+
+  for message in STATUS_ROTATION.iter().cycle() {
+    interval.tick().await;
+
+    let status = match build_status(&db, *message).await {
+      Ok(status) => status,
+      Err(e) => {
+        error!("Could not build {message:?} activity status: {e}");
+        None
+      }
+    };
+
+    set_activity(&shard_manager, status.as_deref().unwrap_or(DEFAULT_STATUS)).await;
+  }
+}
@@ -0,0 +1,190 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+/// Practices tracked separately from each other. Meditation entries are stored separately from other activities.
+#[derive(Debug, Clone, Copy)]
+pub enum Activity {
+  Meditation,
+  Journal,
+  /// Time spent listening to a talk in a stage channel.
+  Talk,
+  /// Answering the weekly check-in prompt. Streaks are counted in weeks rather than days.
+  CheckIn,
+}
+
+impl Activity {
+  /// Name used to store the activity in the database.
+  pub fn key(self) -> &'static str {
+    match self {
+      Self::Meditation => "meditation",
+      Self::Journal => "journal",
+      Self::Talk => "talk",
+      Self::CheckIn => "checkin",
+    }
+  }
+}
+
+/// A member's local date at `now`, given their UTC offset in minutes.
+///
+/// Entries are stored in the member's local time, so streak days are counted back from their local date rather than the server's.
+pub fn local_date(now: DateTime<Utc>, utc_offset: i16) -> NaiveDate {
+  (now + Duration::minutes(i64::from(utc_offset))).date_naive()
+}
+
+/// How many days before `today` an active day was, or `None` if it's after `today`, such as an entry logged by someone ahead of their saved time zone.
+pub fn days_ago(today: NaiveDate, day: NaiveDate) -> Option<i32> {
+  i32::try_from((today - day).num_days())
+    .ok()
+    .filter(|days_ago| *days_ago >= 0)
+}
+
+/// Counts consecutive days of activity, given how many days ago each active day was, in ascending order without duplicates.
+///
+/// A streak stays alive until a full day has been missed, so activity yesterday or the day before still counts.
+pub fn consecutive_days(days_ago: &[i32]) -> u64 {
+  consecutive_periods(days_ago, 2)
+}
+
+/// Counts consecutive weeks of activity, given how many weeks ago each active week was, in ascending order without duplicates.
+///
+/// A streak stays alive through the current week, so activity last week still counts until the week is over.
+pub fn consecutive_weeks(weeks_ago: &[i32]) -> u64 {
+  consecutive_periods(weeks_ago, 1)
+}
+
+/// Counts consecutive periods of activity, where the most recent activity can be up to `grace` periods ago.
+fn consecutive_periods(periods_ago: &[i32], grace: i32) -> u64 {
+  let Some((&first, rest)) = periods_ago.split_first() else {
+    return 0;
+  };
+
+  if first > grace {
+    return 0;
+  }
+
+  let mut last = first;
+  let mut streak = 1;
+
+  for &periods_ago in rest {
+    if periods_ago != last + 1 {
+      break;
+    }
+
+    last = periods_ago;
+    streak += 1;
+  }
+
+  streak
+}
+
+/// Finds a missed day that can be restored to keep a streak going, given how many days ago each active day was, in ascending order without duplicates.
+///
+/// Only a single missed day up to `window` days ago can be restored, and only when it joins recent activity to an earlier streak, making the streak longer.
+pub fn restorable_day(days_ago: &[i32], window: i32) -> Option<i32> {
+  let current = consecutive_days(days_ago);
+
+  (1..=window).find(|day| {
+    if days_ago.contains(day) || !days_ago.contains(&(day + 1)) {
+      return false;
+    }
+
+    let mut restored = days_ago.to_vec();
+    restored.push(*day);
+    restored.sort_unstable();
+
+    consecutive_days(&restored) > current
+  })
+}
+
+/// Whether the current streak, counted from the given active days, includes any of the restored days.
+///
+/// The active days should already include the restored days.
+pub fn includes_restored_day(days_ago: &[i32], restored_days_ago: &[i32]) -> bool {
+  let Some(&first) = days_ago.first() else {
+    return false;
+  };
+  let streak = consecutive_days(days_ago);
+
+  restored_days_ago
+    .iter()
+    .any(|day| u64::try_from(day - first).is_ok_and(|offset| offset < streak))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{days_ago, local_date};
+  use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+  fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc
+      .with_ymd_and_hms(year, month, day, hour, minute, 0)
+      .unwrap()
+  }
+
+  fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+  }
+
+  #[test]
+  fn utc_members_use_the_utc_date() {
+    assert_eq!(local_date(utc(2024, 3, 10, 0, 0), 0), date(2024, 3, 10));
+    assert_eq!(local_date(utc(2024, 3, 10, 23, 59), 0), date(2024, 3, 10));
+  }
+
+  #[test]
+  fn positive_offsets_around_midnight() {
+    // 23:30 UTC is already tomorrow half an hour or more east of UTC, but a minute earlier is still today half an hour east
+    assert_eq!(local_date(utc(2024, 3, 10, 23, 30), 60), date(2024, 3, 11));
+    assert_eq!(local_date(utc(2024, 3, 10, 23, 30), 30), date(2024, 3, 11));
+    assert_eq!(local_date(utc(2024, 3, 10, 23, 29), 30), date(2024, 3, 10));
+    assert_eq!(local_date(utc(2024, 3, 10, 0, 30), 60), date(2024, 3, 10));
+  }
+
+  #[test]
+  fn negative_offsets_around_midnight() {
+    // 00:30 UTC is still yesterday an hour west of UTC, but not half an hour west
+    assert_eq!(local_date(utc(2024, 3, 11, 0, 30), -60), date(2024, 3, 10));
+    assert_eq!(local_date(utc(2024, 3, 11, 0, 30), -30), date(2024, 3, 11));
+    assert_eq!(local_date(utc(2024, 3, 11, 0, 29), -30), date(2024, 3, 10));
+    assert_eq!(local_date(utc(2024, 3, 10, 23, 30), -60), date(2024, 3, 10));
+  }
+
+  #[test]
+  fn extreme_offsets() {
+    // UTC+14 (LINT) is a day ahead of UTC from 10:00 UTC, and UTC-12 a day behind until 12:00 UTC
+    assert_eq!(local_date(utc(2024, 3, 10, 9, 59), 840), date(2024, 3, 10));
+    assert_eq!(local_date(utc(2024, 3, 10, 10, 0), 840), date(2024, 3, 11));
+    assert_eq!(local_date(utc(2024, 3, 10, 11, 59), -720), date(2024, 3, 9));
+    assert_eq!(local_date(utc(2024, 3, 10, 12, 0), -720), date(2024, 3, 10));
+    assert_eq!(local_date(utc(2024, 3, 10, 13, 59), -840), date(2024, 3, 9));
+    assert_eq!(local_date(utc(2024, 3, 10, 14, 0), -840), date(2024, 3, 10));
+  }
+
+  #[test]
+  fn offsets_cross_month_and_year_boundaries() {
+    assert_eq!(local_date(utc(2023, 12, 31, 20, 0), 840), date(2024, 1, 1));
+    assert_eq!(local_date(utc(2024, 1, 1, 2, 0), -720), date(2023, 12, 31));
+    assert_eq!(local_date(utc(2024, 2, 29, 23, 0), 120), date(2024, 3, 1));
+  }
+
+  #[test]
+  fn members_on_either_side_of_utc_see_different_days() {
+    // At 11:00 UTC, a session on March 11 is today for a UTC+14 member and tomorrow for a UTC-12 member
+    let now = utc(2024, 3, 10, 11, 0);
+    let session = date(2024, 3, 11);
+
+    assert_eq!(days_ago(local_date(now, 840), session), Some(0));
+    assert_eq!(days_ago(local_date(now, -720), session), None);
+    assert_eq!(days_ago(local_date(now, -720), date(2024, 3, 9)), Some(0));
+    assert_eq!(days_ago(local_date(now, 840), date(2024, 3, 9)), Some(2));
+  }
+
+  #[test]
+  fn days_ago_counts_back_from_today() {
+    let today = date(2024, 3, 10);
+
+    assert_eq!(days_ago(today, date(2024, 3, 10)), Some(0));
+    assert_eq!(days_ago(today, date(2024, 3, 9)), Some(1));
+    assert_eq!(days_ago(today, date(2024, 2, 29)), Some(10));
+    assert_eq!(days_ago(today, date(2024, 3, 11)), None);
+  }
+}
@@ -0,0 +1,79 @@
+use crate::database::DatabaseHandler;
+use crate::shared_state::SharedState;
+use anyhow::Result;
+use poise::serenity_prelude as serenity;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long term names are kept before they are reloaded from the database.
+const TERM_NAMES_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Per-guild cache of glossary term names and aliases, used for autocompletion.
+///
+/// Entries are kept in [`SharedState`] one name per line, expire after [`TERM_NAMES_TTL`], and should be invalidated whenever terms are added, edited, or removed.
+/// Hits and misses are counted for this process only.
+pub struct TermNameCache {
+  state: Arc<dyn SharedState>,
+  hits: AtomicU64,
+  misses: AtomicU64,
+}
+
+impl TermNameCache {
+  pub fn new(state: Arc<dyn SharedState>) -> Self {
+    Self {
+      state,
+      hits: AtomicU64::new(0),
+      misses: AtomicU64::new(0),
+    }
+  }
+
+  fn key(guild_id: &serenity::GuildId) -> String {
+    format!("terms:{guild_id}")
+  }
+
+  pub async fn get(
+    &self,
+    db: &DatabaseHandler,
+    guild_id: &serenity::GuildId,
+  ) -> Result<Vec<String>> {
+    if let Some(cached) = self.state.get(&Self::key(guild_id)).await? {
+      self.hits.fetch_add(1, Ordering::Relaxed);
+      if cached.is_empty() {
+        return Ok(Vec::new());
+      }
+      return Ok(cached.lines().map(str::to_string).collect());
+    }
+
+    self.misses.fetch_add(1, Ordering::Relaxed);
+
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    let term_list = DatabaseHandler::get_term_list(&mut transaction, guild_id).await?;
+    drop(transaction);
+
+    let names: Vec<String> = term_list
+      .into_iter()
+      .flat_map(|term| std::iter::once(term.term_name).chain(term.aliases.unwrap_or_default()))
+      .collect();
+
+    self
+      .state
+      .set(&Self::key(guild_id), names.join("\n"), Some(TERM_NAMES_TTL))
+      .await?;
+
+    Ok(names)
+  }
+
+  /// Returns the number of cache hits and misses since startup.
+  pub fn hit_stats(&self) -> (u64, u64) {
+    (
+      self.hits.load(Ordering::Relaxed),
+      self.misses.load(Ordering::Relaxed),
+    )
+  }
+
+  pub async fn invalidate(&self, guild_id: &serenity::GuildId) -> Result<()> {
+    self.state.remove(&Self::key(guild_id)).await?;
+    Ok(())
+  }
+}
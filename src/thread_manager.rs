@@ -0,0 +1,83 @@
+use crate::config::{NOTIFICATION_THREAD_ARCHIVE_DAYS, NOTIFICATION_THREAD_DELETE_DAYS};
+use crate::database::DatabaseHandler;
+use anyhow::Result;
+use log::{error, info};
+use poise::serenity_prelude::{self as serenity, EditThread};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often notification threads are checked for inactivity.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Archives and deletes stale private notification threads created by the bot.
+///
+/// Threads are archived after [`NOTIFICATION_THREAD_ARCHIVE_DAYS`] without activity and deleted after [`NOTIFICATION_THREAD_DELETE_DAYS`]. Runs until the bot shuts down.
+pub async fn manage_notification_threads(http: Arc<serenity::Http>, db: DatabaseHandler) {
+  let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    if let Err(e) = clean_up_threads(&http, &db).await {
+      error!("Error while cleaning up notification threads: {e:?}");
+    }
+  }
+}
+
+async fn clean_up_threads(http: &serenity::Http, db: &DatabaseHandler) -> Result<()> {
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let threads = DatabaseHandler::get_notification_threads(&mut transaction).await?;
+  drop(transaction);
+
+  let now = chrono::Utc::now();
+  let archive_cutoff = now - chrono::Duration::days(NOTIFICATION_THREAD_ARCHIVE_DAYS);
+  let delete_cutoff = now - chrono::Duration::days(NOTIFICATION_THREAD_DELETE_DAYS);
+
+  for thread in threads {
+    // Threads that no longer exist only need their record removed
+    let Some(channel) = thread
+      .thread_id
+      .to_channel(http)
+      .await
+      .ok()
+      .and_then(serenity::Channel::guild)
+    else {
+      let mut transaction = db.start_transaction_with_retry(5).await?;
+      DatabaseHandler::remove_notification_thread(&mut transaction, &thread.thread_id).await?;
+      transaction.commit().await?;
+      continue;
+    };
+
+    let last_activity = channel
+      .last_message_id
+      .and_then(|message_id| {
+        chrono::DateTime::from_timestamp(message_id.created_at().unix_timestamp(), 0)
+      })
+      .unwrap_or(thread.created_at);
+
+    if last_activity < delete_cutoff {
+      info!(
+        "Deleting inactive notification thread {} for user {}",
+        thread.thread_id, thread.user_id
+      );
+      thread.thread_id.delete(http).await?;
+
+      let mut transaction = db.start_transaction_with_retry(5).await?;
+      DatabaseHandler::remove_notification_thread(&mut transaction, &thread.thread_id).await?;
+      transaction.commit().await?;
+    } else if last_activity < archive_cutoff {
+      let archived = channel
+        .thread_metadata
+        .is_some_and(|metadata| metadata.archived);
+
+      if !archived {
+        thread
+          .thread_id
+          .edit_thread(http, EditThread::new().archived(true))
+          .await?;
+      }
+    }
+  }
+
+  Ok(())
+}
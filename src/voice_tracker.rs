@@ -0,0 +1,808 @@
+use crate::config::{
+  BloomBotEmbed, CongratsDelivery, EntrySource, CHANNELS, SIT_MAX_MINUTES, VC_MIN_MINUTES,
+};
+use crate::database::{DatabaseHandler, TrackingProfile};
+use crate::milestones;
+use crate::notifications::{self, Delivery, Notification};
+use crate::shared_state::SharedState;
+use crate::streaks::Activity;
+use anyhow::Result;
+use log::{error, info};
+use poise::serenity_prelude::{self as serenity, CreateAttachment, CreateMessage, Mentionable};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// How often saved voice sessions are marked as still in progress.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Time a member has spent in a tracked voice channel, from when they joined.
+///
+/// Channels can require several members for time to count, so only the stretches with enough members in the channel are counted.
+#[derive(Debug, Clone, Copy)]
+pub struct VoiceSession {
+  pub channel_id: serenity::ChannelId,
+  pub joined_at: chrono::DateTime<chrono::Utc>,
+  /// Seconds already counted, from earlier stretches with enough members in the channel.
+  pub counted_seconds: i64,
+  /// When the current stretch with enough members started, if the session is counting right now.
+  pub counting_since: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl VoiceSession {
+  fn new(channel_id: serenity::ChannelId, joined_at: chrono::DateTime<chrono::Utc>) -> Self {
+    Self {
+      channel_id,
+      joined_at,
+      counted_seconds: 0,
+      counting_since: None,
+    }
+  }
+
+  /// Minutes counted toward the session up to the given time.
+  pub fn counted_minutes(&self, at: chrono::DateTime<chrono::Utc>) -> i64 {
+    let current = self
+      .counting_since
+      .map_or(0, |since| (at - since).num_seconds().max(0));
+
+    (self.counted_seconds + current) / 60
+  }
+
+  /// Encodes the session for [`SharedState`], as the channel, the join time, the seconds counted, and when counting started, or `-` if it isn't counting.
+  fn to_state(self) -> String {
+    format!(
+      "{} {} {} {}",
+      self.channel_id,
+      self.joined_at.timestamp_millis(),
+      self.counted_seconds,
+      self.counting_since.map_or("-".to_string(), |since| since
+        .timestamp_millis()
+        .to_string())
+    )
+  }
+
+  fn from_state(state: &str) -> Option<Self> {
+    let mut parts = state.split(' ');
+
+    let channel_id = serenity::ChannelId::new(parts.next()?.parse().ok()?);
+    let joined_at = chrono::DateTime::from_timestamp_millis(parts.next()?.parse().ok()?)?;
+    let counted_seconds = parts.next()?.parse().ok()?;
+    let counting_since = match parts.next()? {
+      "-" => None,
+      since => Some(chrono::DateTime::from_timestamp_millis(
+        since.parse().ok()?,
+      )?),
+    };
+
+    Some(Self {
+      channel_id,
+      joined_at,
+      counted_seconds,
+      counting_since,
+    })
+  }
+
+  /// Starts or stops counting time, returning whether anything changed.
+  fn set_counting(&mut self, counting: bool, at: chrono::DateTime<chrono::Utc>) -> bool {
+    match (counting, self.counting_since) {
+      (true, None) => {
+        self.counting_since = Some(at);
+        true
+      }
+      (false, Some(since)) => {
+        self.counted_seconds += (at - since).num_seconds().max(0);
+        self.counting_since = None;
+        true
+      }
+      _ => false,
+    }
+  }
+}
+
+/// Members currently sitting in tracked voice channels.
+///
+/// Sessions are keyed by guild and user, since a member can be in voice channels in more than one server at the same time. Sessions are kept in [`SharedState`],
+/// and are also saved to the database, so they can be picked up again after a restart.
+pub struct VoiceSessions {
+  state: Arc<dyn SharedState>,
+  /// Held while sessions are changed, so members joining and leaving a channel at the same time don't overwrite each other's sessions.
+  ///
+  /// Each guild is handled by a single shard, so this is enough even when sessions are shared between processes.
+  updating: Mutex<()>,
+}
+
+impl VoiceSessions {
+  pub fn new(state: Arc<dyn SharedState>) -> Self {
+    Self {
+      state,
+      updating: Mutex::new(()),
+    }
+  }
+
+  fn key(guild_id: serenity::GuildId, user_id: serenity::UserId) -> String {
+    format!("voice:{guild_id}:{user_id}")
+  }
+
+  pub async fn get(
+    &self,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+  ) -> Result<Option<VoiceSession>> {
+    Ok(
+      self
+        .state
+        .get(&Self::key(guild_id, user_id))
+        .await?
+        .and_then(|state| VoiceSession::from_state(&state)),
+    )
+  }
+
+  async fn insert(
+    &self,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+    session: VoiceSession,
+  ) -> Result<()> {
+    self
+      .state
+      .set(&Self::key(guild_id, user_id), session.to_state(), None)
+      .await
+  }
+
+  /// Starts timing a member in a channel. Time only counts once the channel is refreshed with enough members in it.
+  async fn begin(
+    &self,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+    channel_id: serenity::ChannelId,
+    joined_at: chrono::DateTime<chrono::Utc>,
+  ) -> Result<()> {
+    let _updating = self.updating.lock().await;
+    self
+      .insert(guild_id, user_id, VoiceSession::new(channel_id, joined_at))
+      .await
+  }
+
+  /// Removes a member's session, returning it with its counted time settled up to `at`, or `None` if they had no session in the guild.
+  async fn take(
+    &self,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+    at: chrono::DateTime<chrono::Utc>,
+  ) -> Result<Option<VoiceSession>> {
+    let session = {
+      let _updating = self.updating.lock().await;
+      self.state.remove(&Self::key(guild_id, user_id)).await?
+    };
+
+    Ok(
+      session
+        .and_then(|state| VoiceSession::from_state(&state))
+        .map(|mut session| {
+          session.set_counting(false, at);
+          session
+        }),
+    )
+  }
+
+  /// Starts or stops counting time for everyone in a channel, depending on whether there are at least `min_participants` members in it, returning their sessions.
+  async fn update_counting(
+    &self,
+    guild_id: serenity::GuildId,
+    channel_id: serenity::ChannelId,
+    min_participants: usize,
+    at: chrono::DateTime<chrono::Utc>,
+  ) -> Result<Vec<(serenity::UserId, VoiceSession)>> {
+    let _updating = self.updating.lock().await;
+    let prefix = format!("voice:{guild_id}:");
+    let mut sessions = self
+      .state
+      .scan(&prefix)
+      .await?
+      .into_iter()
+      .filter_map(|(key, state)| {
+        let user_id = serenity::UserId::new(key.strip_prefix(&prefix)?.parse().ok()?);
+        let session = VoiceSession::from_state(&state)?;
+
+        (session.channel_id == channel_id).then_some((user_id, session))
+      })
+      .collect::<Vec<_>>();
+    let participants = sessions.len();
+
+    for (user_id, session) in &mut sessions {
+      if session.set_counting(participants >= min_participants, at) {
+        self.insert(guild_id, *user_id, *session).await?;
+      }
+    }
+
+    Ok(sessions)
+  }
+
+  pub async fn start(
+    &self,
+    db: &DatabaseHandler,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+    channel_id: serenity::ChannelId,
+    joined_at: chrono::DateTime<chrono::Utc>,
+  ) -> Result<()> {
+    self.begin(guild_id, user_id, channel_id, joined_at).await?;
+
+    self.refresh(db, guild_id, channel_id, joined_at).await
+  }
+
+  /// Ends a session, returning it with its counted time settled up to `at`.
+  pub async fn end(
+    &self,
+    db: &DatabaseHandler,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+    at: chrono::DateTime<chrono::Utc>,
+  ) -> Result<Option<VoiceSession>> {
+    let Some(session) = self.take(guild_id, user_id, at).await? else {
+      return Ok(None);
+    };
+
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    DatabaseHandler::remove_voice_session(&mut transaction, &guild_id, &user_id).await?;
+    DatabaseHandler::commit_transaction(transaction).await?;
+
+    // The members left behind may no longer be enough for their time to count
+    self.refresh(db, guild_id, session.channel_id, at).await?;
+
+    Ok(Some(session))
+  }
+
+  /// Starts or stops counting time for everyone in a channel, depending on how many members are in it, and saves their sessions.
+  async fn refresh(
+    &self,
+    db: &DatabaseHandler,
+    guild_id: serenity::GuildId,
+    channel_id: serenity::ChannelId,
+    at: chrono::DateTime<chrono::Utc>,
+  ) -> Result<()> {
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    let min_participants =
+      DatabaseHandler::get_tracking_voice_channels(&mut transaction, &guild_id)
+        .await?
+        .into_iter()
+        .find(|tracked| tracked.channel_id == channel_id)
+        .map_or(1, |tracked| {
+          usize::try_from(tracked.min_participants).unwrap_or(1)
+        });
+
+    let in_channel = self
+      .update_counting(guild_id, channel_id, min_participants, at)
+      .await?;
+
+    for (user_id, session) in in_channel {
+      DatabaseHandler::save_voice_session(
+        &mut transaction,
+        &guild_id,
+        &user_id,
+        &session.channel_id,
+        session.joined_at,
+        i32::try_from(session.counted_seconds).unwrap_or(i32::MAX),
+        session.counting_since,
+      )
+      .await?;
+    }
+
+    DatabaseHandler::commit_transaction(transaction).await?;
+
+    Ok(())
+  }
+
+  /// Picks up voice sessions saved before a restart, once the guild's voice states are known.
+  ///
+  /// Members still in the same channel carry on where they left off. Members who left while the bot was down are credited up to when their session was last seen, and members who joined a tracked channel in the meantime start a new session.
+  pub async fn reconcile(
+    &self,
+    ctx: &serenity::Context,
+    db: &DatabaseHandler,
+    guild: &serenity::Guild,
+    trace_id: &str,
+  ) -> Result<()> {
+    let now = chrono::Utc::now();
+
+    let mut transaction = db.start_transaction_with_retry(5).await?;
+    let saved = DatabaseHandler::get_voice_sessions(&mut transaction, &guild.id).await?;
+    let tracked = DatabaseHandler::get_tracking_voice_channels(&mut transaction, &guild.id).await?;
+    drop(transaction);
+
+    for saved in saved {
+      let mut session = VoiceSession {
+        channel_id: saved.channel_id,
+        joined_at: saved.joined_at,
+        counted_seconds: i64::from(saved.counted_seconds),
+        counting_since: saved.counting_since,
+      };
+      let current_channel = guild
+        .voice_states
+        .get(&saved.user_id)
+        .and_then(|voice_state| voice_state.channel_id);
+
+      if current_channel == Some(saved.channel_id) {
+        self.insert(guild.id, saved.user_id, session).await?;
+        continue;
+      }
+
+      // Shared state outlives a restart when it is kept in Redis
+      self
+        .state
+        .remove(&Self::key(guild.id, saved.user_id))
+        .await?;
+
+      let mut transaction = db.start_transaction_with_retry(5).await?;
+      DatabaseHandler::remove_voice_session(&mut transaction, &guild.id, &saved.user_id).await?;
+      DatabaseHandler::commit_transaction(transaction).await?;
+
+      session.set_counting(false, saved.last_seen_at);
+      credit_session(
+        ctx,
+        db,
+        guild.id,
+        saved.user_id,
+        session.channel_id,
+        session.counted_minutes(saved.last_seen_at),
+        saved.last_seen_at,
+        trace_id,
+      )
+      .await?;
+    }
+
+    for (user_id, voice_state) in &guild.voice_states {
+      let Some(channel_id) = voice_state.channel_id else {
+        continue;
+      };
+      let is_bot = voice_state
+        .member
+        .as_ref()
+        .is_some_and(|member| member.user.bot);
+      let is_tracked = tracked
+        .iter()
+        .any(|tracked| tracked.channel_id == channel_id);
+
+      if !is_bot && is_tracked && self.get(guild.id, *user_id).await?.is_none() {
+        self
+          .insert(guild.id, *user_id, VoiceSession::new(channel_id, now))
+          .await?;
+      }
+    }
+
+    for tracked in tracked {
+      self.refresh(db, guild.id, tracked.channel_id, now).await?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Keeps saved voice sessions marked as in progress, so sessions interrupted by a restart end close to when the bot stopped.
+///
+/// Runs until the bot shuts down.
+pub async fn record_heartbeats(db: DatabaseHandler) {
+  let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+
+  loop {
+    interval.tick().await;
+
+    let result = async {
+      let mut transaction = db.start_transaction_with_retry(5).await?;
+      DatabaseHandler::touch_voice_sessions(&mut transaction).await?;
+      DatabaseHandler::commit_transaction(transaction).await
+    }
+    .await;
+
+    if let Err(e) = result {
+      error!("Error while recording voice session heartbeats: {e:?}");
+    }
+  }
+}
+
+/// Checks the cache for whether a channel is a stage channel, where talks are given rather than sits.
+fn is_stage_channel(
+  ctx: &serenity::Context,
+  guild_id: serenity::GuildId,
+  channel_id: serenity::ChannelId,
+) -> bool {
+  ctx.cache.guild(guild_id).is_some_and(|guild| {
+    guild
+      .channels
+      .get(&channel_id)
+      .is_some_and(|channel| channel.kind == serenity::ChannelType::Stage)
+  })
+}
+
+/// Records time spent listening to a talk in a stage channel, which is kept separate from meditation time.
+///
+/// Visits that are too short or too long to be counted as a session are skipped silently.
+async fn credit_talk(
+  db: &DatabaseHandler,
+  guild_id: serenity::GuildId,
+  user_id: serenity::UserId,
+  elapsed: i64,
+  ended_at: chrono::DateTime<chrono::Utc>,
+  trace_id: &str,
+) -> Result<()> {
+  if !(VC_MIN_MINUTES..=SIT_MAX_MINUTES).contains(&elapsed) {
+    return Ok(());
+  }
+  let Ok(minutes) = i32::try_from(elapsed) else {
+    return Ok(());
+  };
+
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let utc_offset = DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user_id)
+    .await?
+    .map_or(0, |tracking_profile| tracking_profile.utc_offset);
+
+  // Stored in local time, like meditation entries
+  let occurred_at = ended_at + chrono::Duration::minutes(i64::from(utc_offset));
+  DatabaseHandler::add_activity_entry(
+    &mut transaction,
+    &guild_id,
+    &user_id,
+    Activity::Talk,
+    None,
+    Some(minutes),
+    occurred_at,
+  )
+  .await?;
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  info!("[{trace_id}] Recorded {minutes} minutes of talk attendance for {user_id} in {guild_id}");
+
+  Ok(())
+}
+
+/// Adds the time counted in a voice session as a meditation entry, applying the server's session limits.
+///
+/// Time in a stage channel is recorded as talk attendance instead. Nothing is recorded for members who turned voice tracking off.
+/// Sessions over the limit aren't added, and are flagged to staff in the Bloom logs channel.
+#[allow(clippy::too_many_arguments)]
+pub async fn credit_session(
+  ctx: &serenity::Context,
+  db: &DatabaseHandler,
+  guild_id: serenity::GuildId,
+  user_id: serenity::UserId,
+  channel_id: serenity::ChannelId,
+  elapsed: i64,
+  ended_at: chrono::DateTime<chrono::Utc>,
+  trace_id: &str,
+) -> Result<()> {
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let tracking_profile =
+    match DatabaseHandler::get_tracking_profile(&mut transaction, &guild_id, &user_id).await? {
+      Some(tracking_profile) => tracking_profile,
+      None => TrackingProfile {
+        ..Default::default()
+      },
+    };
+
+  if !tracking_profile.vc_tracking {
+    return Ok(());
+  }
+
+  if is_stage_channel(ctx, guild_id, channel_id) {
+    drop(transaction);
+    return credit_talk(db, guild_id, user_id, elapsed, ended_at, trace_id).await;
+  }
+
+  let limits = DatabaseHandler::get_session_limits(&mut transaction, &guild_id).await?;
+
+  let min_minutes = limits.min_minutes.map_or(VC_MIN_MINUTES, |min_minutes| {
+    VC_MIN_MINUTES.max(i64::from(min_minutes))
+  });
+  let max_minutes = limits.max_minutes.map_or(SIT_MAX_MINUTES, |max_minutes| {
+    SIT_MAX_MINUTES.min(i64::from(max_minutes))
+  });
+
+  if elapsed < min_minutes {
+    return Ok(());
+  }
+
+  // Most likely left idle in the channel, so the time isn't added without the member confirming it
+  if elapsed > max_minutes {
+    info!("[{trace_id}] Skipped voice session of {elapsed} minutes for {user_id} in {guild_id}");
+
+    // Flag oversized session in Bloom logs channel
+    let log_embed = BloomBotEmbed::new()
+      .title("Oversized Voice Session Not Added")
+      .description(format!(
+        "**User**: <@{user_id}> ({user_id})\n**Channel**: {}\n**Time**: {elapsed} minutes\n**Limit**: {max_minutes} minutes",
+        channel_id.mention()
+      ));
+
+    let log_channel = serenity::ChannelId::new(CHANNELS.bloomlogs);
+
+    // The member should still be told why the time wasn't added if the log can't be sent
+    if let Err(e) = log_channel
+      .send_message(&ctx.http, CreateMessage::new().embed(log_embed))
+      .await
+    {
+      error!("[{trace_id}] Error flagging oversized voice session: {e}");
+    }
+
+    let description = format!(
+      "You were in {} for {elapsed} minutes, which is longer than can be added automatically. If you meditated for all or part of that time, please use `/add` to add it.",
+      channel_id.mention()
+    );
+    notifications::deliver(
+      &ctx.http,
+      db,
+      user_id,
+      Delivery::Dm,
+      &Notification {
+        title: "Voice Session Not Added",
+        description: &description,
+        ..Default::default()
+      },
+    )
+    .await?;
+
+    return Ok(());
+  }
+
+  let Ok(minutes) = i32::try_from(elapsed) else {
+    return Ok(());
+  };
+
+  // Stored in local time, like sessions added with /add
+  let occurred_at = ended_at + chrono::Duration::minutes(i64::from(tracking_profile.utc_offset));
+  DatabaseHandler::create_meditation_entry(
+    &mut transaction,
+    &guild_id,
+    &user_id,
+    minutes,
+    occurred_at,
+    EntrySource::Voice,
+  )
+  .await?;
+  let user_sum =
+    DatabaseHandler::get_user_meditation_sum(&mut transaction, &guild_id, &user_id).await?;
+  let user_streak = if tracking_profile.streaks_active {
+    Some(DatabaseHandler::get_streak(&mut transaction, &guild_id, &user_id).await?)
+  } else {
+    None
+  };
+  DatabaseHandler::commit_transaction(transaction).await?;
+
+  // Anonymous members are told privately, so the channel doesn't reveal who was sitting
+  if tracking_profile.anonymous_tracking {
+    let description = format!(
+      "Added **{minutes} minutes** from your sit in {}. Your total meditation time is now {user_sum} minutes :tada:",
+      channel_id.mention()
+    );
+    notifications::deliver(
+      &ctx.http,
+      db,
+      user_id,
+      Delivery::Dm,
+      &Notification {
+        title: "Voice Session Added",
+        description: &description,
+        ..Default::default()
+      },
+    )
+    .await?;
+  } else {
+    channel_id
+      .send_message(
+        ctx,
+        CreateMessage::new()
+          .content(format!(
+            "Added **{minutes} minutes** to {}'s meditation time from their sit here! Their total meditation time is now {user_sum} minutes :tada:",
+            user_id.mention()
+          ))
+          .allowed_mentions(serenity::CreateAllowedMentions::new()),
+      )
+      .await?;
+  }
+
+  congratulate(
+    ctx,
+    db,
+    guild_id,
+    user_id,
+    channel_id,
+    &tracking_profile,
+    user_sum,
+    user_streak,
+    trace_id,
+  )
+  .await
+}
+
+/// Gives the member any time or streak role their session earned, and congratulates them the same way `/add` does.
+///
+/// Public congratulations are posted in the voice channel. Private ones are sent as a notification, without a card.
+#[allow(clippy::too_many_arguments)]
+async fn congratulate(
+  ctx: &serenity::Context,
+  db: &DatabaseHandler,
+  guild_id: serenity::GuildId,
+  user_id: serenity::UserId,
+  channel_id: serenity::ChannelId,
+  tracking_profile: &TrackingProfile,
+  user_sum: i64,
+  user_streak: Option<u64>,
+  trace_id: &str,
+) -> Result<()> {
+  let Some(guild) = ctx.cache.guild(guild_id).map(|guild| guild.clone()) else {
+    return Ok(());
+  };
+  let member = guild.member(ctx, user_id).await?;
+
+  let (promotions, failed) = milestones::promote(ctx, &guild, &member, user_sum, user_streak).await;
+  if let Some(milestone) = failed {
+    error!(
+      "[{trace_id}] Could not update {milestone:?} roles for {user_id} in {guild_id} after a voice session"
+    );
+  }
+
+  for promotion in promotions {
+    // Voice sessions are only announced privately for anonymous members, like private adds
+    let delivery = milestones::congrats_delivery(
+      tracking_profile,
+      tracking_profile.anonymous_tracking,
+      promotion.milestone,
+    );
+
+    match delivery {
+      CongratsDelivery::Public => {
+        let mut message = CreateMessage::new()
+          .content(promotion.message(&member))
+          .allowed_mentions(serenity::CreateAllowedMentions::new());
+        if tracking_profile.congrats_cards {
+          match promotion.draw_card(&guild, &member).await {
+            Ok(card) => {
+              message = message.add_file(CreateAttachment::path(card.get_file_path()).await?);
+            }
+            Err(err) => error!("[{trace_id}] Error drawing congratulations card: {err}"),
+          }
+        }
+        channel_id.send_message(ctx, message).await?;
+      }
+      CongratsDelivery::Private => {
+        let description = promotion.message(&member);
+        notifications::deliver(
+          &ctx.http,
+          db,
+          user_id,
+          Delivery::Dm,
+          &Notification {
+            title: "New Role",
+            description: &description,
+            ..Default::default()
+          },
+        )
+        .await?;
+      }
+      CongratsDelivery::Off => {}
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::shared_state::MemoryState;
+
+  const GUILD: serenity::GuildId = serenity::GuildId::new(1);
+  const OTHER_GUILD: serenity::GuildId = serenity::GuildId::new(2);
+  const USER: serenity::UserId = serenity::UserId::new(10);
+  const OTHER_USER: serenity::UserId = serenity::UserId::new(11);
+  const CHANNEL: serenity::ChannelId = serenity::ChannelId::new(100);
+  const OTHER_CHANNEL: serenity::ChannelId = serenity::ChannelId::new(101);
+
+  fn sessions() -> VoiceSessions {
+    VoiceSessions::new(Arc::new(MemoryState::default()))
+  }
+
+  fn at(minutes: i64) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap() + chrono::Duration::minutes(minutes)
+  }
+
+  #[test]
+  fn state_round_trips() {
+    let mut session = VoiceSession::new(CHANNEL, at(0));
+    let decoded = VoiceSession::from_state(&session.to_state()).unwrap();
+    assert_eq!(decoded.channel_id, CHANNEL);
+    assert_eq!(decoded.joined_at, at(0));
+    assert_eq!(decoded.counting_since, None);
+
+    session.set_counting(true, at(5));
+    let decoded = VoiceSession::from_state(&session.to_state()).unwrap();
+    assert_eq!(decoded.counting_since, Some(at(5)));
+    assert_eq!(decoded.counted_minutes(at(25)), 20);
+  }
+
+  #[tokio::test]
+  async fn same_user_in_two_guilds() {
+    let sessions = sessions();
+    sessions.begin(GUILD, USER, CHANNEL, at(0)).await.unwrap();
+    sessions
+      .begin(OTHER_GUILD, USER, OTHER_CHANNEL, at(10))
+      .await
+      .unwrap();
+    sessions
+      .update_counting(GUILD, CHANNEL, 1, at(0))
+      .await
+      .unwrap();
+    sessions
+      .update_counting(OTHER_GUILD, OTHER_CHANNEL, 1, at(10))
+      .await
+      .unwrap();
+
+    // Leaving one server's channel leaves the session in the other server alone
+    let ended = sessions.take(GUILD, USER, at(30)).await.unwrap().unwrap();
+    assert_eq!(ended.channel_id, CHANNEL);
+    assert_eq!(ended.counted_minutes(at(30)), 30);
+    assert!(sessions.get(GUILD, USER).await.unwrap().is_none());
+
+    let other = sessions.get(OTHER_GUILD, USER).await.unwrap().unwrap();
+    assert_eq!(other.channel_id, OTHER_CHANNEL);
+    assert_eq!(other.joined_at, at(10));
+    assert_eq!(other.counted_minutes(at(30)), 20);
+  }
+
+  #[tokio::test]
+  async fn move_between_channels() {
+    let sessions = sessions();
+    sessions.begin(GUILD, USER, CHANNEL, at(0)).await.unwrap();
+    sessions
+      .begin(GUILD, OTHER_USER, CHANNEL, at(0))
+      .await
+      .unwrap();
+    sessions
+      .update_counting(GUILD, CHANNEL, 2, at(0))
+      .await
+      .unwrap();
+
+    // Moving ends the session in the old channel, with its time settled, and starts a new one
+    let ended = sessions.take(GUILD, USER, at(15)).await.unwrap().unwrap();
+    assert_eq!(ended.channel_id, CHANNEL);
+    assert_eq!(ended.counting_since, None);
+    assert_eq!(ended.counted_minutes(at(60)), 15);
+
+    sessions
+      .begin(GUILD, USER, OTHER_CHANNEL, at(15))
+      .await
+      .unwrap();
+    let moved = sessions
+      .update_counting(GUILD, OTHER_CHANNEL, 1, at(15))
+      .await
+      .unwrap();
+    assert_eq!(moved.len(), 1);
+    assert_eq!(moved[0].0, USER);
+    assert_eq!(moved[0].1.joined_at, at(15));
+    assert_eq!(moved[0].1.counted_minutes(at(25)), 10);
+
+    // The member left behind is now alone, so their time stops counting
+    let left_behind = sessions
+      .update_counting(GUILD, CHANNEL, 2, at(15))
+      .await
+      .unwrap();
+    assert_eq!(left_behind.len(), 1);
+    assert_eq!(left_behind[0].0, OTHER_USER);
+    assert_eq!(left_behind[0].1.counting_since, None);
+    assert_eq!(left_behind[0].1.counted_minutes(at(60)), 15);
+  }
+
+  #[tokio::test]
+  async fn leave_without_join() {
+    let sessions = sessions();
+    assert!(sessions.take(GUILD, USER, at(0)).await.unwrap().is_none());
+
+    // A session in another guild isn't ended by leaving this one
+    sessions
+      .begin(OTHER_GUILD, USER, CHANNEL, at(0))
+      .await
+      .unwrap();
+    assert!(sessions.take(GUILD, USER, at(5)).await.unwrap().is_none());
+    assert!(sessions.get(OTHER_GUILD, USER).await.unwrap().is_some());
+  }
+}
@@ -0,0 +1,116 @@
+use crate::database::DatabaseHandler;
+use anyhow::Result;
+use log::{error, warn};
+use poise::serenity_prelude as serenity;
+use serde_json::json;
+use std::time::Duration;
+
+/// How long a webhook endpoint has to respond before the delivery is abandoned.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Guild milestones delivered to webhooks registered with `/manage webhooks`.
+pub enum WebhookEvent {
+  /// The guild's total meditation time passed a multiple of 10,000 hours.
+  HoursMilestone { hours: i64 },
+  /// A winner was picked for a monthly challenge.
+  ChallengeCompleted {
+    challenge: String,
+    winner: serenity::UserId,
+  },
+  /// A member reached a longer streak than anyone in the guild before.
+  RecordStreak {
+    user_id: serenity::UserId,
+    streak: u64,
+  },
+}
+
+impl WebhookEvent {
+  fn name(&self) -> &'static str {
+    match self {
+      Self::HoursMilestone { .. } => "hours_milestone",
+      Self::ChallengeCompleted { .. } => "challenge_completed",
+      Self::RecordStreak { .. } => "record_streak",
+    }
+  }
+
+  fn payload(&self, guild_id: serenity::GuildId) -> serde_json::Value {
+    let data = match self {
+      Self::HoursMilestone { hours } => json!({ "hours": hours }),
+      Self::ChallengeCompleted { challenge, winner } => {
+        json!({ "challenge": challenge, "winner_id": winner.to_string() })
+      }
+      Self::RecordStreak { user_id, streak } => {
+        json!({ "user_id": user_id.to_string(), "streak": streak })
+      }
+    };
+
+    json!({
+      "event": self.name(),
+      "guild_id": guild_id.to_string(),
+      "timestamp": chrono::Utc::now().to_rfc3339(),
+      "data": data,
+    })
+  }
+}
+
+/// Delivers an event to every webhook registered for the guild.
+///
+/// Deliveries run in the background, so slow endpoints never hold up a command. Failed deliveries are logged and not retried.
+pub fn notify(db: &DatabaseHandler, guild_id: serenity::GuildId, event: WebhookEvent) {
+  let db = db.clone();
+
+  tokio::spawn(async move {
+    if let Err(e) = deliver(&db, guild_id, &event).await {
+      error!("Error delivering {} webhooks: {e}", event.name());
+    }
+  });
+}
+
+async fn deliver(
+  db: &DatabaseHandler,
+  guild_id: serenity::GuildId,
+  event: &WebhookEvent,
+) -> Result<()> {
+  let mut transaction = db.start_transaction_with_retry(5).await?;
+  let webhooks = DatabaseHandler::get_guild_webhooks(&mut transaction, &guild_id).await?;
+  drop(transaction);
+
+  if webhooks.is_empty() {
+    return Ok(());
+  }
+
+  let client = reqwest::Client::builder()
+    .timeout(WEBHOOK_TIMEOUT)
+    .build()?;
+  let payload = event.payload(guild_id);
+
+  for webhook in webhooks {
+    let response = client
+      .post(&webhook.url)
+      .header("X-Bloom-Event", event.name())
+      .json(&payload)
+      .send()
+      .await;
+
+    match response {
+      Ok(response) if !response.status().is_success() => {
+        warn!(
+          "Webhook {} responded to {} with {}",
+          webhook.id,
+          event.name(),
+          response.status()
+        );
+      }
+      Ok(_) => {}
+      Err(e) => {
+        warn!(
+          "Could not deliver {} to webhook {}: {e}",
+          event.name(),
+          webhook.id
+        );
+      }
+    }
+  }
+
+  Ok(())
+}